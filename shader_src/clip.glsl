@@ -0,0 +1,67 @@
+// Shared by every fragment shader, and inserted after their `#version` line when they are built.
+
+const int MAX_CLIPS = 8;
+
+// The rounded rects that everything is clipped to, in device pixels from the top-left corner of
+// the viewport.  Each rect is <x, y, width, height>, and each vector of radii holds the radius of
+// the top-left, top-right, bottom-right, then bottom-left corner.
+uniform int clipCount;
+uniform vec4 clipRects[MAX_CLIPS];
+uniform vec4 clipRadiiX[MAX_CLIPS];
+uniform vec4 clipRadiiY[MAX_CLIPS];
+// The height of the viewport, in device pixels, which window coordinates are flipped within,
+// since their y axis points up.
+uniform float viewportHeight;
+
+// The center of the fragment, in device pixels from the top-left corner of the viewport.
+vec2 fragmentPoint()
+{
+    return vec2(gl_FragCoord.x, viewportHeight - gl_FragCoord.y);
+}
+
+// Approximately how far p is outside of an ellipse with the given radii centered on the origin,
+// which is negative inside of it.
+float ellipseDistance(vec2 p, vec2 radii)
+{
+    float k0 = length(p / radii);
+    float k1 = length(p / (radii * radii));
+    return k1 > 0.0 ? k0 * (k0 - 1.0) / k1 : -min(radii.x, radii.y);
+}
+
+// How far p is outside of a rounded rect, which is negative inside of it.
+float roundedRectDistance(vec2 p, vec4 rect, vec4 radiiX, vec4 radiiY)
+{
+    vec2 halfSize = max(rect.zw, vec2(0.0)) * 0.5;
+    vec2 offset = p - (rect.xy + halfSize);
+    // The y axis points down, so the top corners are where the offset is negative.
+    vec2 radii;
+    if (offset.y < 0.0) {
+        radii = offset.x < 0.0 ? vec2(radiiX.x, radiiY.x) : vec2(radiiX.y, radiiY.y);
+    } else {
+        radii = offset.x < 0.0 ? vec2(radiiX.w, radiiY.w) : vec2(radiiX.z, radiiY.z);
+    }
+    vec2 fromCurveCenter = abs(offset) - (halfSize - radii);
+    if (radii.x > 0.0 && radii.y > 0.0 && fromCurveCenter.x > 0.0 && fromCurveCenter.y > 0.0) {
+        return ellipseDistance(fromCurveCenter, radii);
+    }
+    vec2 d = abs(offset) - halfSize;
+    return min(max(d.x, d.y), 0.0) + length(max(d, vec2(0.0)));
+}
+
+// How much of a pixel whose center is d pixels outside of an edge is covered, so that edges are
+// antialiased.
+float coverage(float d)
+{
+    return clamp(0.5 - d, 0.0, 1.0);
+}
+
+// How much of the fragment is inside all of the clips, from 0 to 1.
+float clipCoverage()
+{
+    vec2 p = fragmentPoint();
+    float result = 1.0;
+    for (int i = 0; i < clipCount; i++) {
+        result *= coverage(roundedRectDistance(p, clipRects[i], clipRadiiX[i], clipRadiiY[i]));
+    }
+    return result;
+}
\ No newline at end of file
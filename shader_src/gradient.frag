@@ -0,0 +1,66 @@
+#version 330 core
+in vec2 Point;
+out vec4 color;
+
+const int MAX_STOPS = 64;
+const float TAU = 6.28318530718;
+
+// 0 for linear gradients, 1 for radial gradients, and 2 for conic gradients.
+uniform int kind;
+// The start of the gradient line of linear gradients, or the center of radial and conic gradients.
+uniform vec2 origin;
+// The end of the gradient line of linear gradients, the radii of radial gradients, or the start
+// angle of conic gradients (in x).
+uniform vec2 extent;
+uniform bool repeating;
+uniform int stopCount;
+uniform vec4 stopColors[MAX_STOPS];
+uniform float stopPositions[MAX_STOPS];
+
+// How far along the gradient Point is, where 0 is 0% and 1 is 100%.
+float gradientPosition()
+{
+    vec2 offset = Point - origin;
+    if (kind == 0) {
+        vec2 line = extent - origin;
+        float lineLengthSquared = dot(line, line);
+        return lineLengthSquared > 0.0 ? dot(offset, line) / lineLengthSquared : 0.0;
+    }
+    if (kind == 1) {
+        return length(offset / max(extent, vec2(0.0001)));
+    }
+    // Conic gradients go clockwise from straight up, and y points down.
+    return fract((atan(offset.x, -offset.y) - extent.x) / TAU);
+}
+
+vec4 premultiplied(vec4 c)
+{
+    return vec4(c.rgb * c.a, c.a);
+}
+
+void main()
+{
+    float position = gradientPosition();
+    float first = stopPositions[0];
+    float last = stopPositions[stopCount - 1];
+    if (repeating && last > first) {
+        position = first + mod(position - first, last - first);
+    }
+
+    // Colors are interpolated premultiplied, so that transitions to transparent don't turn gray.
+    vec4 result = premultiplied(stopColors[stopCount - 1]);
+    if (position <= first) {
+        result = premultiplied(stopColors[0]);
+    } else {
+        for (int i = 1; i < stopCount; i++) {
+            if (position < stopPositions[i]) {
+                float span = stopPositions[i] - stopPositions[i - 1];
+                float weight = span > 0.0 ? (position - stopPositions[i - 1]) / span : 1.0;
+                result = mix(premultiplied(stopColors[i - 1]), premultiplied(stopColors[i]), weight);
+                break;
+            }
+        }
+    }
+    // Painted colors are blended as non-premultiplied.
+    color = result.a > 0.0 ? vec4(result.rgb / result.a, result.a * clipCoverage()) : vec4(0.0);
+}
\ No newline at end of file
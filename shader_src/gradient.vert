@@ -0,0 +1,10 @@
+#version 330 core
+layout (location = 0) in vec4 vertex; // <vec2 pos, vec2 point>
+out vec2 Point;
+
+void main()
+{
+    gl_Position = vec4(vertex.xy, 0.0, 1.0);
+    // The point within the gradient box, in CSS pixels from its top-left corner.
+    Point = vertex.zw;
+}
\ No newline at end of file
@@ -0,0 +1,23 @@
+#version 330 core
+out vec4 Color;
+
+// The rounded rect that is filled, and the one cut out of it if hasInner is set, in device
+// pixels like the clips.
+uniform vec4 color;
+uniform vec4 outerRect;
+uniform vec4 outerRadiiX;
+uniform vec4 outerRadiiY;
+uniform bool hasInner;
+uniform vec4 innerRect;
+uniform vec4 innerRadiiX;
+uniform vec4 innerRadiiY;
+
+void main()
+{
+    vec2 p = fragmentPoint();
+    float alpha = coverage(roundedRectDistance(p, outerRect, outerRadiiX, outerRadiiY));
+    if (hasInner) {
+        alpha *= 1.0 - coverage(roundedRectDistance(p, innerRect, innerRadiiX, innerRadiiY));
+    }
+    Color = vec4(color.rgb, color.a * alpha * clipCoverage());
+}
\ No newline at end of file
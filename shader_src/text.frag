@@ -8,5 +8,5 @@ uniform vec3 textColor;
 void main()
 {
     vec4 sampled = vec4(1.0, 1.0, 1.0, texture(text, TexCoords).r);
-    color = vec4(textColor, 1.0) * sampled;
+    color = vec4(textColor, clipCoverage()) * sampled;
 }
\ No newline at end of file
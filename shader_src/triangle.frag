@@ -8,5 +8,5 @@ out vec4 Color;
 
 void main()
 {
-    Color = IN.Color;
+    Color = vec4(IN.Color.rgb, IN.Color.a * clipCoverage());
 }
\ No newline at end of file
@@ -1,15 +1,23 @@
 use clap::{App, Arg, ArgMatches, SubCommand};
+use std::path::PathBuf;
 use std::str::FromStr;
 
 const DUMP_LAYOUT_CMD_NAME: &str = "dump-layout";
 const SIMILARITY_CMD_NAME: &str = "similarity";
+const HIT_TEST_CMD_NAME: &str = "hit-test";
 const RENDER_INPUT_ARG_NAME: &str = "FILES OR URLS";
 const DUMP_LAYOUT_INPUT_ARG_NAME: &str = "FILES";
 const SIMILARITY_INPUT_ARG_NAME: &str = "FILES";
+const HIT_TEST_INPUT_ARG_NAME: &str = "FILES";
+const POINT_ARG_NAME: &str = "point";
+const USER_STYLESHEET_ARG_NAME: &str = "user-stylesheet";
+/// The name of the default user stylesheet, found in the `kosmonaut` directory of the user's config
+/// directory.
+const DEFAULT_USER_STYLESHEET_FILE_NAME: &str = "user.css";
 
 pub fn setup_and_get_cli_args<'a>() -> ArgMatches<'a> {
     let headed_or_headless_applicable =
-        "Applicable in both headed and headless (e.g. the dump-layout and similarity commands) contexts.";
+        "Applicable in both headed and headless (e.g. the dump-layout, similarity and hit-test commands) contexts.";
     let scale_factor_help = format!(
         "Device/window scale factor.  {}",
         headed_or_headless_applicable
@@ -37,6 +45,21 @@ pub fn setup_and_get_cli_args<'a>() -> ArgMatches<'a> {
         .help(&height_help)
         .takes_value(true)
         .validator(is_num_validator);
+    let user_stylesheet_help = format!(
+        "A stylesheet to apply with the user origin, whose !important declarations override those of the document.  Can be passed multiple times.  {}",
+        headed_or_headless_applicable
+    );
+    let user_stylesheet_arg = Arg::with_name(USER_STYLESHEET_ARG_NAME)
+        .long(USER_STYLESHEET_ARG_NAME)
+        .value_name("FILE")
+        .help(&user_stylesheet_help)
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1);
+    let render_user_stylesheet_help = format!(
+        "{}  If none are passed, the `kosmonaut/{}` file in your config directory is used, if it exists.",
+        user_stylesheet_help, DEFAULT_USER_STYLESHEET_FILE_NAME
+    );
 
     App::new("Kosmonaut")
         .version("0.1")
@@ -45,6 +68,7 @@ pub fn setup_and_get_cli_args<'a>() -> ArgMatches<'a> {
         .arg(width_arg.clone())
         .arg(height_arg.clone())
         .arg(scale_factor_arg.clone())
+        .arg(user_stylesheet_arg.clone().help(&render_user_stylesheet_help))
         .arg(
             Arg::with_name(RENDER_INPUT_ARG_NAME)
                 .help("File(s) or URL(s) for Kosmonaut to render.")
@@ -73,6 +97,7 @@ pub fn setup_and_get_cli_args<'a>() -> ArgMatches<'a> {
                 .arg(scale_factor_arg.clone().required(true))
                 .arg(width_arg.clone().required(true))
                 .arg(height_arg.clone().required(true))
+                .arg(user_stylesheet_arg.clone())
         )
         .subcommand(
             SubCommand::with_name(SIMILARITY_CMD_NAME)
@@ -94,9 +119,37 @@ error.
                         .min_values(2)
                         .max_values(2)
                 )
+                .arg(scale_factor_arg.clone())
+                .arg(width_arg.clone())
+                .arg(height_arg.clone())
+                .arg(user_stylesheet_arg.clone())
+        )
+        .subcommand(
+            SubCommand::with_name(HIT_TEST_CMD_NAME)
+                .about("Prints the path of the element under each given point after first global layout, exiting afterwards.")
+                .arg(
+                    Arg::with_name(POINT_ARG_NAME)
+                        .short("p")
+                        .long(POINT_ARG_NAME)
+                        .value_name("X,Y")
+                        .help("A point to hit-test, in CSS pixels from the top-left corner of the viewport.  Can be passed multiple times.")
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(is_point_validator)
+                )
+                .arg(
+                    Arg::with_name(HIT_TEST_INPUT_ARG_NAME)
+                        .help("File(s) for Kosmonaut to hit-test.  Note only the first HTML file found is rendered, and beyond that only CSS files will be used.")
+                        .index(1)
+                        .required(true)
+                        .min_values(1)
+                )
                 .arg(scale_factor_arg)
                 .arg(width_arg)
                 .arg(height_arg)
+                .arg(user_stylesheet_arg)
         )
         .get_matches()
 }
@@ -108,6 +161,27 @@ fn is_num_validator(string: String) -> Result<(), String> {
     }
 }
 
+fn is_point_validator(string: String) -> Result<(), String> {
+    match parse_point(&string) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "given arg '{}' is not a point of the form X,Y",
+            string
+        )),
+    }
+}
+
+/// Parses a point of the form `X,Y`, e.g. `10,20.5`.
+fn parse_point(string: &str) -> Option<(f32, f32)> {
+    let mut coordinates = string.split(',');
+    let x = coordinates.next()?.trim().parse::<f32>().ok()?;
+    let y = coordinates.next()?.trim().parse::<f32>().ok()?;
+    match coordinates.next() {
+        Some(_) => None,
+        None => Some((x, y)),
+    }
+}
+
 fn is_bool_validator(string: String) -> Result<(), String> {
     match string.parse::<bool>() {
         Ok(_) => Ok(()),
@@ -126,6 +200,10 @@ pub fn has_similarity_subcommand(arg_matches: &ArgMatches) -> bool {
     arg_matches.subcommand_matches("similarity").is_some()
 }
 
+pub fn has_hit_test_subcommand(arg_matches: &ArgMatches) -> bool {
+    arg_matches.subcommand_matches("hit-test").is_some()
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum DumpLayoutVerbosity {
     /// Includes more information in layout-dump, such as margin, border, and padding values for
@@ -165,6 +243,7 @@ pub trait CliCommand {
 #[derive(Clone, Debug)]
 pub struct RenderCmd {
     pub files_or_urls: Option<Vec<String>>,
+    pub user_stylesheets: Vec<String>,
     pub window_width: Option<f32>,
     pub window_height: Option<f32>,
     pub scale_factor: Option<f32>,
@@ -173,6 +252,7 @@ pub struct RenderCmd {
 #[derive(Clone, Debug)]
 pub struct DumpLayoutCmd {
     pub file_paths: Vec<String>,
+    pub user_stylesheets: Vec<String>,
     pub window_width: f32,
     pub window_height: f32,
     pub scale_factor: f32,
@@ -182,16 +262,29 @@ pub struct DumpLayoutCmd {
 #[derive(Clone, Debug)]
 pub struct SimilarityCmd {
     pub file_paths: Vec<String>,
+    pub user_stylesheets: Vec<String>,
     pub window_width: Option<f32>,
     pub window_height: Option<f32>,
     pub scale_factor: Option<f32>,
     pub percent_only: bool,
 }
 
+#[derive(Clone, Debug)]
+pub struct HitTestCmd {
+    pub file_paths: Vec<String>,
+    pub user_stylesheets: Vec<String>,
+    pub window_width: Option<f32>,
+    pub window_height: Option<f32>,
+    pub scale_factor: Option<f32>,
+    /// The points to hit-test, in CSS pixels.
+    pub points: Vec<(f32, f32)>,
+}
+
 pub enum Command {
     Render(RenderCmd),
     DumpLayout(DumpLayoutCmd),
     Similarity(SimilarityCmd),
+    HitTest(HitTestCmd),
 }
 
 impl From<DumpLayoutCmd> for Command {
@@ -212,6 +305,12 @@ impl From<SimilarityCmd> for Command {
     }
 }
 
+impl From<HitTestCmd> for Command {
+    fn from(cmd: HitTestCmd) -> Self {
+        Command::HitTest(cmd)
+    }
+}
+
 pub fn get_command(global_matches: &ArgMatches) -> Command {
     if has_dump_layout_tree_subcommand(global_matches) {
         let matches = global_matches
@@ -221,6 +320,7 @@ pub fn get_command(global_matches: &ArgMatches) -> Command {
         let file_paths = files_or_urls(matches, DUMP_LAYOUT_INPUT_ARG_NAME).unwrap();
         DumpLayoutCmd {
             file_paths,
+            user_stylesheets: user_stylesheets(matches),
             window_width: window_width(matches).unwrap(),
             window_height: window_height(matches).unwrap(),
             scale_factor: scale_factor(matches).unwrap(),
@@ -234,20 +334,43 @@ pub fn get_command(global_matches: &ArgMatches) -> Command {
         let file_paths = files_or_urls(matches, SIMILARITY_INPUT_ARG_NAME).unwrap();
         SimilarityCmd {
             file_paths,
+            user_stylesheets: user_stylesheets(matches),
             window_width: window_width(matches),
             window_height: window_height(matches),
             scale_factor: scale_factor(matches),
             percent_only: similarity_percent_only(matches),
         }
         .into()
+    } else if has_hit_test_subcommand(global_matches) {
+        let matches = global_matches
+            .subcommand_matches(HIT_TEST_CMD_NAME)
+            .unwrap();
+        // unwrap safe here because this arg is marked as required for `hit-test`.
+        let file_paths = files_or_urls(matches, HIT_TEST_INPUT_ARG_NAME).unwrap();
+        HitTestCmd {
+            file_paths,
+            user_stylesheets: user_stylesheets(matches),
+            window_width: window_width(matches),
+            window_height: window_height(matches),
+            scale_factor: scale_factor(matches),
+            points: points(matches),
+        }
+        .into()
     } else {
         // If no sub-command was specified, assume the user wants to render the headed-representation
         // of the passed file / URL.
         let width = window_width(global_matches);
         let height = window_height(global_matches);
         let scale_factor = scale_factor(global_matches);
+        let mut user_stylesheets = user_stylesheets(global_matches);
+        if user_stylesheets.is_empty() {
+            user_stylesheets.extend(
+                default_user_stylesheet_path().map(|path| path.to_string_lossy().into_owned()),
+            );
+        }
         RenderCmd {
             files_or_urls: files_or_urls(global_matches, RENDER_INPUT_ARG_NAME),
+            user_stylesheets,
             window_width: width,
             window_height: height,
             scale_factor,
@@ -262,6 +385,34 @@ pub fn files_or_urls(arg_matches: &ArgMatches, arg_name: &str) -> Option<Vec<Str
         .map(|values| values.map(|value| value.to_owned()).collect::<Vec<_>>())
 }
 
+pub fn user_stylesheets(arg_matches: &ArgMatches) -> Vec<String> {
+    files_or_urls(arg_matches, USER_STYLESHEET_ARG_NAME).unwrap_or_default()
+}
+
+/// The path of the default user stylesheet, if one exists.  This is `kosmonaut/user.css` within
+/// `$XDG_CONFIG_HOME` (or `~/.config` if that isn't set), or within `%APPDATA%` on Windows.
+///
+/// This is only used when rendering — the `dump-layout` and `similarity` commands only apply the
+/// user stylesheets they're given, so their output doesn't depend on the machine they're run on.
+pub fn default_user_stylesheet_path() -> Option<PathBuf> {
+    let config_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else {
+        match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        }
+    };
+    let path = config_dir
+        .join("kosmonaut")
+        .join(DEFAULT_USER_STYLESHEET_FILE_NAME);
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
 pub fn window_width(arg_matches: &ArgMatches) -> Option<f32> {
     try_get_arg::<f32>(arg_matches, "width")
 }
@@ -274,6 +425,14 @@ pub fn scale_factor(arg_matches: &ArgMatches) -> Option<f32> {
     try_get_arg::<f32>(arg_matches, "scale-factor")
 }
 
+/// The points passed with `--point`, which have already been validated.
+pub fn points(arg_matches: &ArgMatches) -> Vec<(f32, f32)> {
+    arg_matches
+        .values_of(POINT_ARG_NAME)
+        .map(|values| values.filter_map(parse_point).collect())
+        .unwrap_or_default()
+}
+
 pub fn similarity_percent_only(arg_matches: &ArgMatches) -> bool {
     arg_matches.is_present("similarity-percent-only")
 }
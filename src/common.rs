@@ -5,3 +5,11 @@ pub enum Side {
     Right,
     Top,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
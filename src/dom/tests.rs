@@ -7,6 +7,7 @@ use tempdir::TempDir;
 
 use crate::dom::parser::parse_html;
 use crate::dom::traits::*;
+use crate::dom::tree::NodeRef;
 use crate::style::select::*;
 
 #[test]
@@ -174,3 +175,89 @@ fn specificity() {
     assert!(specificities[0] > specificities[2]);
     assert!(specificities[1] > specificities[2]);
 }
+
+#[test]
+fn url_attributes_resolve_against_the_first_base_href() {
+    let html = r#"
+<base target="_blank">
+<base href="/assets/">
+<base href="https://elsewhere.example/">
+<img src="cat.png"><a href="  https://other.example/page  ">"#;
+    let document = parse_html().one(html);
+    let img = document.select_first("img").unwrap().as_node().clone();
+    // Relative URLs can't be resolved against `about:blank`.
+    assert_eq!(img.url_attribute(local_name!("src")), None);
+
+    let document_url = url::Url::parse("https://example.com/dir/page.html").unwrap();
+    document.as_document().unwrap().set_url(document_url);
+    assert_eq!(
+        img.base_url().unwrap().as_str(),
+        "https://example.com/assets/"
+    );
+    assert_eq!(
+        img.url_attribute(local_name!("src")).unwrap().as_str(),
+        "https://example.com/assets/cat.png"
+    );
+    let a = document.select_first("a").unwrap().as_node().clone();
+    assert_eq!(
+        a.url_attribute(local_name!("href")).unwrap().as_str(),
+        "https://other.example/page"
+    );
+    assert_eq!(a.url_attribute(local_name!("title")), None);
+}
+
+/// Parses `html` into a document loaded from `https://example.com/dir/page.html`.
+fn document_at_example_com(html: &str) -> NodeRef {
+    let document = parse_html().one(html);
+    let document_url = url::Url::parse("https://example.com/dir/page.html").unwrap();
+    document.as_document().unwrap().set_url(document_url);
+    document
+}
+
+#[test]
+fn base_url_is_the_document_url_without_a_base_element() {
+    let document = document_at_example_com("<p>text</p>");
+    let p = document.select_first("p").unwrap().as_node().clone();
+    assert_eq!(
+        p.base_url().unwrap().as_str(),
+        "https://example.com/dir/page.html"
+    );
+}
+
+#[test]
+fn base_url_ignores_base_elements_without_href() {
+    let document = document_at_example_com(r#"<base target="_blank"><p>text</p>"#);
+    let p = document.select_first("p").unwrap().as_node().clone();
+    assert_eq!(
+        p.base_url().unwrap().as_str(),
+        "https://example.com/dir/page.html"
+    );
+
+    // Giving the `<base>` an `href` later changes the base URL.
+    let base = document.select_first("base").unwrap().as_node().clone();
+    base.set_attribute(local_name!("href"), "/other/");
+    assert_eq!(p.base_url().unwrap().as_str(), "https://example.com/other/");
+}
+
+#[test]
+fn base_url_resolves_a_relative_base_href_against_the_document_url() {
+    let document = document_at_example_com(r#"<base href="../assets/"><p>text</p>"#);
+    let p = document.select_first("p").unwrap().as_node().clone();
+    assert_eq!(
+        p.base_url().unwrap().as_str(),
+        "https://example.com/assets/"
+    );
+
+    // The cached base URL follows changes to the document URL and the `<base>` element.
+    let moved_url = url::Url::parse("https://example.org/a/b/page.html").unwrap();
+    document.as_document().unwrap().set_url(moved_url);
+    assert_eq!(
+        p.base_url().unwrap().as_str(),
+        "https://example.org/a/assets/"
+    );
+    document.select_first("base").unwrap().as_node().detach();
+    assert_eq!(
+        p.base_url().unwrap().as_str(),
+        "https://example.org/a/b/page.html"
+    );
+}
@@ -1,19 +1,23 @@
 use std::cell::Cell;
+use std::collections::HashMap;
 use std::fmt;
 use std::ops::Deref;
 use std::rc::{Rc, Weak};
+use std::sync::Arc;
 
 /// Much of this file is a direct copy-paste from [Kuchiki](https://github.com/kuchiki-rs/kuchiki/blob/master/src/tree.rs).
 /// Thanks to the authors of Kuchiki for their work.
 use html5ever::tree_builder::QuirksMode;
-use html5ever::QualName;
+use html5ever::{LocalName, QualName};
 
 use crate::dom::attributes::{Attribute, Attributes, ExpandedName};
 use crate::dom::cell_extras::*;
 use crate::dom::iter::NodeIterator;
+use crate::gfx::image::ImageState;
 use crate::style::properties::{ContextualPropertyDeclaration, ContextualPropertyDeclarations};
 use crate::style::values::computed::ComputedValues;
 use accountable_refcell::{Ref, RefCell, RefMut};
+use url::Url;
 
 /// The type of DOM node.
 /// https://html.spec.whatwg.org/#a-quick-introduction-to-html
@@ -69,6 +73,10 @@ pub struct ElementData {
     /// If the element is an HTML `<template>` element,
     /// the document fragment node that is the root of template contents.
     pub template_contents: Option<NodeRef>,
+
+    /// If the element is an HTML `<img>` element, the state of its image, or `None` if loading it
+    /// hasn't been attempted yet.
+    pub image: RefCell<Option<ImageState>>,
 }
 
 /// Data specific to document nodes.
@@ -76,6 +84,12 @@ pub struct ElementData {
 pub struct DocumentData {
     #[doc(hidden)]
     pub _quirks_mode: Cell<QuirksMode>,
+    #[doc(hidden)]
+    pub _url: RefCell<Url>,
+    #[doc(hidden)]
+    pub _base_url: RefCell<Option<Url>>,
+    #[doc(hidden)]
+    pub _images: RefCell<HashMap<Url, ImageState>>,
 }
 
 impl DocumentData {
@@ -84,6 +98,70 @@ impl DocumentData {
     pub fn quirks_mode(&self) -> QuirksMode {
         self._quirks_mode.get()
     }
+
+    /// The URL the document was loaded from, which is `about:blank` until it's set.
+    ///
+    /// https://dom.spec.whatwg.org/#concept-document-url
+    pub fn url(&self) -> Url {
+        self._url.borrow().clone()
+    }
+
+    /// Set the URL the document was loaded from.
+    pub fn set_url(&self, url: Url) {
+        *self._url.borrow_mut() = url;
+        self.forget_base_url();
+    }
+
+    /// Forget the cached base URL of the document, e.g. because a `<base>` element changed, so
+    /// that it's found again the next time it's needed.
+    fn forget_base_url(&self) {
+        *self._base_url.borrow_mut() = None;
+    }
+
+    /// The state of the image at `url` used by the document's styles, such as a background image,
+    /// or `None` if loading it hasn't been attempted yet.
+    pub fn image(&self, url: &Url) -> Option<ImageState> {
+        self._images.borrow().get(url).cloned()
+    }
+
+    /// Record the state of the image at `url` once loading it has been attempted.
+    pub fn set_image(&self, url: Url, state: ImageState) {
+        self._images.borrow_mut().insert(url, state);
+    }
+}
+
+bitflags! {
+    /// What must be recomputed for a node since the DOM last changed.  These are set by the methods
+    /// that change the DOM, such as `NodeRef::set_attribute`, and by restyling, and are cleared
+    /// once the styles and box tree of the document have been brought up to date.
+    pub struct DirtyFlags: u8 {
+        /// The style of the node and its descendants must be recomputed.
+        const STYLE = 1 << 0;
+        /// The style of some descendant of the node must be recomputed.
+        const DESCENDANT_STYLE = 1 << 1;
+        /// The boxes generated by the node must be rebuilt.
+        const BOX_TREE = 1 << 2;
+        /// The boxes generated by the node must be laid out again.
+        const LAYOUT = 1 << 3;
+        /// The boxes generated by some descendant of the node must be rebuilt or laid out again.
+        const DESCENDANT_BOXES = 1 << 4;
+    }
+}
+
+bitflags! {
+    /// The states of an element resulting from the user interacting with it, matched by the
+    /// `:hover`, `:active`, and `:focus` pseudo-classes.
+    ///
+    /// https://html.spec.whatwg.org/multipage/semantics-other.html#selector-hover
+    pub struct ElementState: u8 {
+        /// The pointer is over the element or one of its descendants.
+        const HOVER = 1 << 0;
+        /// The element or one of its descendants is being activated, e.g. by a mouse button
+        /// being held down on it.
+        const ACTIVE = 1 << 1;
+        /// The element has the keyboard focus, and the window has the system focus.
+        const FOCUS = 1 << 2;
+    }
 }
 
 /// A strong reference to a node.
@@ -142,9 +220,11 @@ pub struct Node {
     /// https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#value-stages
     contextual_decls: RefCell<ContextualPropertyDeclarations>,
     /// The result of step 3 and 4 of the CSS value processing stages.  Until the node goes through
-    /// the cascade and computed value calculation phases, this will be something like
-    /// `ComputedValues::default()`.
-    computed_values: RefCell<ComputedValues>,
+    /// the cascade and computed value calculation phases, this will be `ComputedValues::initial()`.
+    /// Nodes with the same style share the same computed values.
+    computed_values: RefCell<Arc<ComputedValues>>,
+    dirty_flags: Cell<DirtyFlags>,
+    element_state: Cell<ElementState>,
 }
 
 impl fmt::Debug for Node {
@@ -232,7 +312,9 @@ impl NodeRef {
             next_sibling: Cell::new(None),
             data,
             contextual_decls: RefCell::new(ContextualPropertyDeclarations::new()),
-            computed_values: RefCell::new(ComputedValues::default()),
+            computed_values: RefCell::new(ComputedValues::initial()),
+            dirty_flags: Cell::new(DirtyFlags::empty()),
+            element_state: Cell::new(ElementState::empty()),
         }))
     }
 
@@ -252,6 +334,7 @@ impl NodeRef {
             attributes: RefCell::new(Attributes {
                 map: attributes.into_iter().collect(),
             }),
+            image: RefCell::new(None),
         }))
     }
 
@@ -300,6 +383,9 @@ impl NodeRef {
     pub fn new_document() -> NodeRef {
         NodeRef::new(NodeData::Document(DocumentData {
             _quirks_mode: Cell::new(QuirksMode::NoQuirks),
+            _url: RefCell::new(Url::parse("about:blank").unwrap()),
+            _base_url: RefCell::new(None),
+            _images: RefCell::new(HashMap::new()),
         }))
     }
 
@@ -335,13 +421,20 @@ impl Node {
     /// Return a reference to this node’s computed values.
     #[inline]
     pub fn computed_values(&self) -> Ref<ComputedValues> {
-        self.computed_values.borrow()
+        Ref::map(self.computed_values.borrow(), |computed_values| {
+            &**computed_values
+        })
+    }
+
+    /// Return this node's computed values, for sharing with other nodes.
+    #[inline]
+    pub fn shared_computed_values(&self) -> Arc<ComputedValues> {
+        Arc::clone(&self.computed_values.borrow())
     }
 
-    /// Return a mutable reference to this node’s computed values.
     #[inline]
-    pub fn computed_values_mut(&self) -> RefMut<ComputedValues> {
-        self.computed_values.borrow_mut()
+    pub fn set_computed_values(&self, computed_values: Arc<ComputedValues>) {
+        *self.computed_values.borrow_mut() = computed_values;
     }
 
     #[inline]
@@ -349,6 +442,28 @@ impl Node {
         self.contextual_decls.borrow_mut().add(new_decl);
     }
 
+    /// Return the user interaction states of this node, e.g. whether it is hovered.
+    #[inline]
+    pub fn element_state(&self) -> ElementState {
+        self.element_state.get()
+    }
+
+    /// Return what must be recomputed for this node since the DOM last changed.
+    #[inline]
+    pub fn dirty_flags(&self) -> DirtyFlags {
+        self.dirty_flags.get()
+    }
+
+    #[inline]
+    pub fn insert_dirty_flags(&self, flags: DirtyFlags) {
+        self.dirty_flags.set(self.dirty_flags.get() | flags);
+    }
+
+    #[inline]
+    pub fn remove_dirty_flags(&self, flags: DirtyFlags) {
+        self.dirty_flags.set(self.dirty_flags.get() - flags);
+    }
+
     /// If this node is an element, return a reference to element-specific data.
     #[inline]
     pub fn as_element(&self) -> Option<&ElementData> {
@@ -423,11 +538,16 @@ impl Node {
     pub fn next_sibling(&self) -> Option<NodeRef> {
         self.next_sibling.clone_inner().map(NodeRef)
     }
+}
 
+impl NodeRef {
     /// Detach a node from its parent and siblings. Children are not affected.
     ///
     /// To remove a node and its descendants, detach it and drop any strong reference to it.
     pub fn detach(&self) {
+        if let Some(parent) = self.parent() {
+            parent.forget_document_base_url_if_base_moved(self);
+        }
         let parent_weak = self.parent.take();
         let previous_sibling_weak = self.previous_sibling.take();
         let next_sibling_strong = self.next_sibling.take();
@@ -456,14 +576,13 @@ impl Node {
             }
         }
     }
-}
 
-impl NodeRef {
     /// Append a new child to this node, after existing children.
     ///
     /// The new child is detached from its previous position.
     pub fn append(&self, new_child: NodeRef) {
         new_child.detach();
+        self.forget_document_base_url_if_base_moved(&new_child);
         new_child.parent.replace(Some(Rc::downgrade(&self.0)));
         if let Some(last_child_weak) = self.last_child.replace(Some(Rc::downgrade(&new_child.0))) {
             if let Some(last_child) = last_child_weak.upgrade() {
@@ -482,6 +601,7 @@ impl NodeRef {
     /// The new child is detached from its previous position.
     pub fn prepend(&self, new_child: NodeRef) {
         new_child.detach();
+        self.forget_document_base_url_if_base_moved(&new_child);
         new_child.parent.replace(Some(Rc::downgrade(&self.0)));
         if let Some(first_child) = self.first_child.take() {
             debug_assert!(first_child.previous_sibling.is_none());
@@ -501,6 +621,7 @@ impl NodeRef {
     /// The new sibling is detached from its previous position.
     pub fn insert_after(&self, new_sibling: NodeRef) {
         new_sibling.detach();
+        self.forget_document_base_url_if_base_moved(&new_sibling);
         new_sibling.parent.replace(self.parent.clone_inner());
         new_sibling
             .previous_sibling
@@ -525,6 +646,7 @@ impl NodeRef {
     /// The new sibling is detached from its previous position.
     pub fn insert_before(&self, new_sibling: NodeRef) {
         new_sibling.detach();
+        self.forget_document_base_url_if_base_moved(&new_sibling);
         new_sibling.parent.replace(self.parent.clone_inner());
         new_sibling.next_sibling.replace(Some(self.0.clone()));
         if let Some(previous_sibling_weak) = self
@@ -547,6 +669,170 @@ impl NodeRef {
     }
 }
 
+impl NodeRef {
+    /// Set the value of an attribute of this element, marking the styles that could depend on it
+    /// as dirty.
+    pub fn set_attribute<A: Into<LocalName>, V: Into<String>>(&self, local_name: A, value: V) {
+        if let Some(element) = self.as_element() {
+            element
+                .attributes
+                .borrow_mut()
+                .insert(local_name, value.into());
+            if element.name.local == local_name!("base") {
+                self.forget_document_base_url();
+            }
+            self.mark_style_dirty();
+        }
+    }
+
+    /// Remove an attribute of this element, marking the styles that could depend on it as dirty.
+    pub fn remove_attribute<A: Into<LocalName>>(&self, local_name: A) {
+        if let Some(element) = self.as_element() {
+            if element.attributes.borrow_mut().remove(local_name).is_some() {
+                if element.name.local == local_name!("base") {
+                    self.forget_document_base_url();
+                }
+                self.mark_style_dirty();
+            }
+        }
+    }
+
+    /// Return the document this node is in, unless it's in a tree without one.
+    pub fn owner_document(&self) -> Option<NodeRef> {
+        self.inclusive_ancestors()
+            .last()
+            .filter(|root| root.as_document().is_some())
+    }
+
+    /// Return the base URL of the document this node is in, which relative URLs in it are resolved
+    /// against.  This is the `href` of the document's first `<base>` element with one, resolved
+    /// against the document's URL, or failing that the document's URL itself.  It's cached on the
+    /// document until the document's URL or one of its `<base>` elements changes.
+    ///
+    /// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    pub fn base_url(&self) -> Option<Url> {
+        let document = self.owner_document()?;
+        let document_data = document.as_document()?;
+        if let Some(base_url) = &*document_data._base_url.borrow() {
+            return Some(base_url.clone());
+        }
+        let document_url = document_data.url();
+        let base_href = document.descendants().find_map(|node| {
+            let element = node.as_element()?;
+            if element.name.local != local_name!("base") {
+                return None;
+            }
+            let attributes = element.attributes.borrow();
+            attributes
+                .get(local_name!("href"))
+                .map(|href| href.trim().to_owned())
+        });
+        let base_url = base_href
+            .and_then(|href| document_url.join(&href).ok())
+            .unwrap_or(document_url);
+        *document_data._base_url.borrow_mut() = Some(base_url.clone());
+        Some(base_url)
+    }
+
+    /// Forget the cached base URL of the document this node is in, if any.
+    fn forget_document_base_url(&self) {
+        if let Some(document) = self.owner_document() {
+            if let Some(document_data) = document.as_document() {
+                document_data.forget_base_url();
+            }
+        }
+    }
+
+    /// Forget the cached base URL of the document this node is in if `moved`, a node being
+    /// inserted into or removed from it, is or contains a `<base>` element.  The subtree of `moved`
+    /// is only searched when there is a cached base URL to forget.
+    fn forget_document_base_url_if_base_moved(&self, moved: &NodeRef) {
+        if let Some(document) = self.owner_document() {
+            if let Some(document_data) = document.as_document() {
+                if document_data._base_url.borrow().is_some()
+                    && moved.inclusive_descendants().any(|node| {
+                        node.as_element()
+                            .map_or(false, |element| element.name.local == local_name!("base"))
+                    })
+                {
+                    document_data.forget_base_url();
+                }
+            }
+        }
+    }
+
+    /// Return the value of the URL attribute `local_name` of this element, such as the `href` of a
+    /// link or the `src` of an image, resolved against the base URL of its document.  Returns
+    /// `None` if the element doesn't have the attribute, isn't in a document, or the attribute
+    /// isn't a valid URL.
+    pub fn url_attribute<A: Into<LocalName>>(&self, local_name: A) -> Option<Url> {
+        let value = self
+            .as_element()?
+            .attributes
+            .borrow()
+            .get(local_name)?
+            .trim()
+            .to_owned();
+        self.base_url()?.join(&value).ok()
+    }
+
+    /// Add or remove `state` from the state of this element, marking the styles that could depend
+    /// on it as dirty if it changed.
+    pub fn set_element_state(&self, state: ElementState, value: bool) {
+        let old_state = self.element_state.get();
+        let mut new_state = old_state;
+        new_state.set(state, value);
+        if new_state != old_state {
+            self.element_state.set(new_state);
+            self.mark_style_dirty();
+        }
+    }
+
+    /// Set the contents of this text node, marking its boxes as dirty.
+    pub fn set_text<T: Into<String>>(&self, text: T) {
+        if let Some(contents) = self.as_text() {
+            *contents.borrow_mut() = text.into();
+            self.mark_boxes_dirty(DirtyFlags::BOX_TREE);
+        }
+    }
+
+    /// Mark the style of this node as dirty, along with that of its following siblings, since
+    /// selectors with sibling combinators can match them based on this node.
+    pub fn mark_style_dirty(&self) {
+        self.insert_dirty_flags(DirtyFlags::STYLE);
+        let mut next_sibling = self.next_sibling();
+        while let Some(sibling) = next_sibling {
+            sibling.insert_dirty_flags(DirtyFlags::STYLE);
+            next_sibling = sibling.next_sibling();
+        }
+        for ancestor in self.ancestors() {
+            if ancestor
+                .dirty_flags()
+                .contains(DirtyFlags::DESCENDANT_STYLE)
+            {
+                break;
+            }
+            ancestor.insert_dirty_flags(DirtyFlags::DESCENDANT_STYLE);
+        }
+    }
+
+    /// Mark the boxes of this node as dirty with `flags`, which are `BOX_TREE` and/or `LAYOUT`,
+    /// and flag its ancestors so that the box tree update can find this node without visiting
+    /// every other one.
+    pub fn mark_boxes_dirty(&self, flags: DirtyFlags) {
+        self.insert_dirty_flags(flags);
+        for ancestor in self.ancestors() {
+            if ancestor
+                .dirty_flags()
+                .contains(DirtyFlags::DESCENDANT_BOXES)
+            {
+                break;
+            }
+            ancestor.insert_dirty_flags(DirtyFlags::DESCENDANT_BOXES);
+        }
+    }
+}
+
 pub fn debug_recursive(node: &NodeRef) {
     node.inclusive_descendants().for_each(|node| {
         dbg!(node);
@@ -0,0 +1,374 @@
+//! Determining the character encoding of documents and stylesheets, and decoding them to text.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252, X_USER_DEFINED};
+
+/// How many bytes at the start of a document are prescanned for a `meta` element declaring its
+/// encoding.
+const PRESCAN_LENGTH: usize = 1024;
+
+/// Decodes the bytes of an HTML document, whose MIME type is `content_type` if it's known.
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let (text, _, _) = html_encoding(bytes, content_type).decode(bytes);
+    text.into_owned()
+}
+
+/// Returns the encoding of the bytes of an HTML document, which is the first of the encoding
+/// indicated by a byte order mark, the `charset` parameter of `content_type`, the encoding declared
+/// by a `meta` element near the start of the document, and windows-1252.
+///
+/// https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding
+pub fn html_encoding(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(charset_parameter))
+        .or_else(|| prescan(&bytes[..bytes.len().min(PRESCAN_LENGTH)]))
+        // This is the fallback encoding for most locales, and decodes any bytes, unlike UTF-8.
+        .unwrap_or(WINDOWS_1252)
+}
+
+/// Decodes the bytes of a stylesheet, whose MIME type is `content_type` if it's known.
+pub fn decode_css(bytes: &[u8], content_type: Option<&str>) -> String {
+    let (text, _, _) = css_encoding(bytes, content_type).decode(bytes);
+    text.into_owned()
+}
+
+/// Returns the encoding of the bytes of a stylesheet, which is the first of the encoding indicated
+/// by a byte order mark, the `charset` parameter of `content_type`, the encoding declared by an
+/// `@charset` rule at the very start of the stylesheet, and UTF-8.
+///
+/// The encoding of the document or stylesheet referring to the stylesheet should come before UTF-8,
+/// but isn't tracked yet.
+///
+/// https://drafts.csswg.org/css-syntax/#determine-the-fallback-encoding
+pub fn css_encoding(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    Encoding::for_bom(bytes)
+        .map(|(encoding, _)| encoding)
+        .or_else(|| content_type.and_then(charset_parameter))
+        .or_else(|| charset_rule_encoding(bytes))
+        .unwrap_or(UTF_8)
+}
+
+/// Returns the encoding named by the `charset` parameter of the MIME type `content_type`, e.g.
+/// `text/html; charset="Shift_JIS"`.
+fn charset_parameter(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let equals = parameter.find('=')?;
+        let (name, value) = (parameter[..equals].trim(), parameter[equals + 1..].trim());
+        if !name.eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim_matches('"').as_bytes())
+    })
+}
+
+/// Returns the encoding named by an `@charset "...";` rule that `bytes` start with.
+///
+/// https://drafts.csswg.org/css-syntax/#charset-rule
+fn charset_rule_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    const PREFIX: &[u8] = b"@charset \"";
+    let bytes = &bytes[..bytes.len().min(PRESCAN_LENGTH)];
+    if !bytes.starts_with(PREFIX) {
+        return None;
+    }
+    let label = &bytes[PREFIX.len()..];
+    let end = label.windows(2).position(|window| window == b"\";")?;
+    Encoding::for_label(&label[..end]).map(without_utf_16)
+}
+
+/// UTF-16 can't be declared by text that has already been read as ASCII, so is taken to mean
+/// UTF-8.
+fn without_utf_16(encoding: &'static Encoding) -> &'static Encoding {
+    if encoding == UTF_16BE || encoding == UTF_16LE {
+        UTF_8
+    } else {
+        encoding
+    }
+}
+
+/// Looks through `bytes`, the start of an HTML document, for a `meta` element declaring its
+/// encoding, skipping over comments and the attributes of other tags.
+///
+/// https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let mut scanner = Scanner { bytes, position: 0 };
+    while scanner.position < bytes.len() {
+        let rest = &bytes[scanner.position..];
+        if rest.starts_with(b"<!--") {
+            // The dashes of `<!--` can also end the comment, as in `<!-->`.
+            let end = rest[2..].windows(3).position(|window| window == b"-->")?;
+            scanner.position += 2 + end + 3;
+        } else if starts_with_ignoring_case(rest, b"<meta")
+            && rest
+                .get(5)
+                .map_or(false, |&byte| is_whitespace(byte) || byte == b'/')
+        {
+            scanner.position += 6;
+            if let Some(encoding) = scanner.meta_encoding()? {
+                return Some(encoding);
+            }
+        } else if rest.len() > 2
+            && rest[0] == b'<'
+            && (rest[1].is_ascii_alphabetic() || rest[1] == b'/' && rest[2].is_ascii_alphabetic())
+        {
+            let name_length = rest
+                .iter()
+                .position(|&byte| is_whitespace(byte) || byte == b'>')?;
+            scanner.position += name_length;
+            while scanner.attribute()?.is_some() {}
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            scanner.position += rest.iter().position(|&byte| byte == b'>')? + 1;
+        } else {
+            scanner.position += 1;
+        }
+    }
+    None
+}
+
+/// A position in the bytes being prescanned.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn current(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.current().map_or(false, is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Reads the attributes of a `meta` element, returning the encoding they declare, if any.
+    /// Returns `None` if the bytes run out first.
+    fn meta_encoding(&mut self) -> Option<Option<&'static Encoding>> {
+        let mut names = Vec::new();
+        let mut got_pragma = false;
+        let mut need_pragma = None;
+        let mut charset = None;
+        while let Some((name, value)) = self.attribute()? {
+            if names.contains(&name) {
+                continue;
+            }
+            match &*name {
+                b"http-equiv" if value == b"content-type" => got_pragma = true,
+                b"content" if charset.is_none() => {
+                    if let Some(encoding) = content_charset(&value) {
+                        charset = Some(encoding);
+                        need_pragma = Some(true);
+                    }
+                }
+                b"charset" => {
+                    charset = Encoding::for_label(&value);
+                    need_pragma = Some(false);
+                }
+                _ => {}
+            }
+            names.push(name);
+        }
+        let declared = match need_pragma {
+            Some(need_pragma) if !need_pragma || got_pragma => charset.map(without_utf_16),
+            _ => None,
+        };
+        Some(declared.map(|encoding| {
+            if encoding == X_USER_DEFINED {
+                WINDOWS_1252
+            } else {
+                encoding
+            }
+        }))
+    }
+
+    /// Reads the next attribute of a tag, with its name and value lowercased.  Returns `Some(None)`
+    /// at the end of the tag, and `None` if the bytes run out first.
+    ///
+    /// https://html.spec.whatwg.org/multipage/parsing.html#concept-get-attributes-when-sniffing
+    fn attribute(&mut self) -> Option<Option<(Vec<u8>, Vec<u8>)>> {
+        while self
+            .current()
+            .map_or(false, |byte| is_whitespace(byte) || byte == b'/')
+        {
+            self.position += 1;
+        }
+        if self.current()? == b'>' {
+            return Some(None);
+        }
+        let mut name = Vec::new();
+        loop {
+            match self.current()? {
+                b'=' if !name.is_empty() => {
+                    self.position += 1;
+                    break;
+                }
+                byte if is_whitespace(byte) => {
+                    self.skip_whitespace();
+                    if self.current()? != b'=' {
+                        return Some(Some((name, Vec::new())));
+                    }
+                    self.position += 1;
+                    break;
+                }
+                b'/' | b'>' => return Some(Some((name, Vec::new()))),
+                byte => name.push(byte.to_ascii_lowercase()),
+            }
+            self.position += 1;
+        }
+        self.skip_whitespace();
+        let mut value = Vec::new();
+        match self.current()? {
+            quote @ b'"' | quote @ b'\'' => loop {
+                self.position += 1;
+                match self.current()? {
+                    byte if byte == quote => {
+                        self.position += 1;
+                        return Some(Some((name, value)));
+                    }
+                    byte => value.push(byte.to_ascii_lowercase()),
+                }
+            },
+            b'>' => return Some(Some((name, value))),
+            _ => {}
+        }
+        while let Some(byte) = self.current() {
+            if is_whitespace(byte) || byte == b'>' {
+                return Some(Some((name, value)));
+            }
+            value.push(byte.to_ascii_lowercase());
+            self.position += 1;
+        }
+        None
+    }
+}
+
+/// Returns the encoding named in the `content` attribute of a `meta` element, e.g.
+/// `text/html; charset=utf-8`.
+///
+/// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element
+fn content_charset(content: &[u8]) -> Option<&'static Encoding> {
+    let mut position = 0;
+    loop {
+        let charset = content[position..]
+            .windows(7)
+            .position(|window| window.eq_ignore_ascii_case(b"charset"))?;
+        position += charset + 7;
+        while content
+            .get(position)
+            .map_or(false, |&byte| is_whitespace(byte))
+        {
+            position += 1;
+        }
+        if content.get(position) == Some(&b'=') {
+            break;
+        }
+    }
+    position += 1;
+    while content
+        .get(position)
+        .map_or(false, |&byte| is_whitespace(byte))
+    {
+        position += 1;
+    }
+    let rest = &content[position..];
+    let label = match *rest.first()? {
+        quote @ b'"' | quote @ b'\'' => {
+            let end = rest[1..].iter().position(|&byte| byte == quote)?;
+            &rest[1..end + 1]
+        }
+        _ => {
+            let end = rest
+                .iter()
+                .position(|&byte| is_whitespace(byte) || byte == b';')
+                .unwrap_or_else(|| rest.len());
+            &rest[..end]
+        }
+    };
+    Encoding::for_label(label)
+}
+
+fn starts_with_ignoring_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use encoding_rs::SHIFT_JIS;
+
+    #[test]
+    fn html_encoding_prefers_bom_then_content_type_then_meta() {
+        let html = b"<meta charset=shift_jis><p>hi</p>";
+        assert_eq!(html_encoding(html, None), SHIFT_JIS);
+        assert_eq!(
+            html_encoding(html, Some("text/html; charset=\"ISO-8859-1\"")),
+            WINDOWS_1252
+        );
+        assert_eq!(
+            html_encoding(b"\xFF\xFE<\0p\0>\0", Some("text/html; charset=utf-8")),
+            UTF_16LE
+        );
+        assert_eq!(html_encoding(b"<p>hi</p>", Some("text/html")), WINDOWS_1252);
+    }
+
+    #[test]
+    fn prescan_finds_meta_declarations() {
+        assert_eq!(
+            prescan(
+                br#"<!-- <meta charset="utf-8"> --><html lang='x>'><META CHARSET = 'Shift_JIS'>"#
+            ),
+            Some(SHIFT_JIS)
+        );
+        assert_eq!(
+            prescan(br#"<meta content="text/html; charset=utf-8" http-equiv="Content-Type">"#),
+            Some(UTF_8)
+        );
+        // `content` only counts alongside `http-equiv`.
+        assert_eq!(
+            prescan(br#"<meta content="text/html; charset=utf-8">"#),
+            None
+        );
+        assert_eq!(prescan(br#"<meta charset="utf-16le">"#), Some(UTF_8));
+        assert_eq!(
+            prescan(br#"<meta charset="x-user-defined">"#),
+            Some(WINDOWS_1252)
+        );
+        assert_eq!(prescan(br#"<meta charset="utf-8""#), None);
+    }
+
+    #[test]
+    fn html_is_decoded_with_its_encoding() {
+        assert_eq!(
+            decode_html(b"<meta charset=shift_jis>\x93\xfa\x96\x7b", None),
+            "<meta charset=shift_jis>日本"
+        );
+        assert_eq!(decode_html(b"caf\xe9", None), "café");
+    }
+
+    #[test]
+    fn css_encoding_is_declared_by_charset_rules() {
+        assert_eq!(
+            css_encoding(b"@charset \"Shift_JIS\"; p {}", None),
+            SHIFT_JIS
+        );
+        assert_eq!(
+            css_encoding(
+                b"@charset \"Shift_JIS\"; p {}",
+                Some("text/css;charset=utf-8")
+            ),
+            UTF_8
+        );
+        // Only the exact form at the very start of the stylesheet declares an encoding.
+        assert_eq!(css_encoding(b" @charset \"Shift_JIS\";", None), UTF_8);
+        assert_eq!(css_encoding(b"@charset 'Shift_JIS';", None), UTF_8);
+        assert_eq!(
+            decode_css(b"\xEF\xBB\xBFp::before { content: \"\xC3\xA9\" }", None),
+            "p::before { content: \"é\" }"
+        );
+    }
+}
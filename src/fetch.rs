@@ -0,0 +1,396 @@
+//! Loading of documents and their subresources, such as stylesheets, fonts and images, from local
+//! files, `data:` URLs or the web.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use isahc::http::header::{HeaderName, CONTENT_TYPE, LOCATION};
+use isahc::http::StatusCode;
+use url::{Position, Url};
+
+/// The most redirects followed when loading a resource, after which loading fails.
+///
+/// https://fetch.spec.whatwg.org/#http-redirect-fetch
+const MAX_REDIRECTS: usize = 20;
+
+/// Returns the URL of `file_or_url`, which is either an absolute URL or the path of a local file,
+/// relative to the current directory.
+pub fn url_from_file_or_url(file_or_url: &str) -> Result<Url, String> {
+    if let Ok(url) = Url::parse(file_or_url) {
+        return Ok(url);
+    }
+    let path = std::env::current_dir()
+        .map_err(|err| format!("couldn't get the current directory: {}", err))?
+        .join(file_or_url);
+    Url::from_file_path(&path)
+        .map_err(|_| format!("'{}' is neither a valid file path nor url", file_or_url))
+}
+
+/// Something documents and their subresources are loaded through.
+pub trait ResourceLoader {
+    /// Loads the resource at `url`, following any redirects.
+    fn load(&self, url: &Url) -> Result<Resource, String>;
+}
+
+/// A loaded resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resource {
+    /// The URL the resource was loaded from, which is the URL requested unless it redirected
+    /// elsewhere.
+    pub url: Url,
+    /// The URLs that redirected on the way to `url`, starting with the URL requested.
+    pub redirects: Vec<Url>,
+    /// The MIME type of `contents`, e.g. `text/css; charset=utf-8`, if it's known.
+    pub content_type: Option<String>,
+    pub contents: Vec<u8>,
+}
+
+/// The outcome of a single request, before any redirect is followed.
+enum Response {
+    Redirect(Url),
+    Contents {
+        content_type: Option<String>,
+        contents: Vec<u8>,
+    },
+}
+
+/// Loads the resource at `url` with `request`, following the redirects it responds with.  A URL
+/// redirected to without a fragment keeps the fragment of the URL redirected from.
+fn load_following_redirects<F>(url: &Url, request: F) -> Result<Resource, String>
+where
+    F: Fn(&Url) -> Result<Response, String>,
+{
+    let mut url = url.clone();
+    let mut redirects = Vec::new();
+    loop {
+        match request(&url)? {
+            Response::Redirect(mut location) => {
+                if redirects.len() == MAX_REDIRECTS {
+                    return Err(format!("too many redirects loading '{}'", redirects[0]));
+                }
+                if location.fragment().is_none() {
+                    location.set_fragment(url.fragment());
+                }
+                redirects.push(std::mem::replace(&mut url, location));
+            }
+            Response::Contents {
+                content_type,
+                contents,
+            } => {
+                return Ok(Resource {
+                    url,
+                    redirects,
+                    content_type,
+                    contents,
+                })
+            }
+        }
+    }
+}
+
+/// Loads `file:`, `data:`, `http:` and `https:` URLs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultResourceLoader;
+
+impl ResourceLoader for DefaultResourceLoader {
+    fn load(&self, url: &Url) -> Result<Resource, String> {
+        load_following_redirects(url, |url| match url.scheme() {
+            "file" => load_file(url),
+            "data" => load_data_url(url),
+            "http" | "https" => load_http(url),
+            scheme => Err(format!(
+                "can't load '{}', as '{}' urls aren't supported",
+                url, scheme
+            )),
+        })
+    }
+}
+
+fn load_file(url: &Url) -> Result<Response, String> {
+    let path = url
+        .to_file_path()
+        .map_err(|_| format!("'{}' is not a valid file url", url))?;
+    let contents = std::fs::read(&path)
+        .map_err(|err| format!("failed to read file '{}': {}", path.display(), err))?;
+    let content_type = path
+        .extension()
+        .and_then(|extension| content_type_of_extension(&extension.to_string_lossy()))
+        .map(|content_type| content_type.to_owned());
+    Ok(Response::Contents {
+        content_type,
+        contents,
+    })
+}
+
+/// The MIME type of files with the extension `extension`, for the types of files Kosmonaut can
+/// load.
+fn content_type_of_extension(extension: &str) -> Option<&'static str> {
+    Some(match &*extension.to_ascii_lowercase() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "txt" => "text/plain",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => return None,
+    })
+}
+
+fn load_http(url: &Url) -> Result<Response, String> {
+    // isahc doesn't follow redirects by default, so that each of them can be recorded.
+    let mut response =
+        isahc::get(url.as_str()).map_err(|err| format!("failed to fetch '{}': {}", url, err))?;
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_owned())
+    };
+    if response.status().is_redirection() {
+        if let Some(location) = header(LOCATION) {
+            let location = url
+                .join(&location)
+                .map_err(|err| format!("invalid redirect from '{}': {}", url, err))?;
+            return match location.scheme() {
+                "http" | "https" => Ok(Response::Redirect(location)),
+                _ => Err(format!(
+                    "refusing to redirect from '{}' to non-http url '{}'",
+                    url, location
+                )),
+            };
+        }
+    }
+    // Error pages, and redirects without a location, aren't the resource that was asked for.
+    if !response.status().is_success() {
+        return Err(status_error(url, response.status()));
+    }
+    let content_type = header(CONTENT_TYPE);
+    let mut contents = Vec::new();
+    response
+        .body_mut()
+        .read_to_end(&mut contents)
+        .map_err(|err| format!("failed to read response from '{}': {}", url, err))?;
+    Ok(Response::Contents {
+        content_type,
+        contents,
+    })
+}
+
+/// The error for an HTTP response from `url` with the unsuccessful `status`, e.g. a 404.
+fn status_error(url: &Url, status: StatusCode) -> String {
+    format!(
+        "failed to fetch '{}': the server responded with {}",
+        url, status
+    )
+}
+
+/// Decodes a `data:` URL, whose contents are either base64 or percent-encoded.  Its content type
+/// defaults to `text/plain;charset=US-ASCII`.
+///
+/// https://fetch.spec.whatwg.org/#data-urls
+fn load_data_url(url: &Url) -> Result<Response, String> {
+    let invalid = || format!("'{}' is not a valid data url", url);
+    // The fragment isn't part of the data.
+    let body = &url[Position::BeforePath..Position::AfterQuery];
+    let comma = body.find(',').ok_or_else(invalid)?;
+    let (mut media_type, data) = (body[..comma].trim(), &body[comma + 1..]);
+    let data = percent_encoding::percent_decode_str(data).collect::<Vec<u8>>();
+    let base64_parameter = media_type.rfind(';').filter(|&semicolon| {
+        media_type[semicolon + 1..]
+            .trim()
+            .eq_ignore_ascii_case("base64")
+    });
+    let contents = match base64_parameter {
+        Some(semicolon) => {
+            media_type = media_type[..semicolon].trim_end();
+            // ASCII whitespace is allowed among the base64 data.
+            let data = data
+                .into_iter()
+                .filter(|byte| !byte.is_ascii_whitespace())
+                .collect::<Vec<_>>();
+            base64::decode(&data).map_err(|_| invalid())?
+        }
+        None => data,
+    };
+    let media_type = percent_encoding::percent_decode_str(media_type).decode_utf8_lossy();
+    let content_type = if media_type.is_empty() {
+        "text/plain;charset=US-ASCII".to_owned()
+    } else if media_type.starts_with(';') {
+        format!("text/plain{}", media_type)
+    } else {
+        media_type.into_owned()
+    };
+    Ok(Response::Contents {
+        content_type: Some(content_type),
+        contents,
+    })
+}
+
+/// Loads resources from memory rather than from files or the network, e.g. so that tests don't
+/// depend on either.  URLs that haven't been added fail to load.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryResourceLoader {
+    responses: HashMap<Url, InMemoryResponse>,
+}
+
+#[derive(Clone, Debug)]
+enum InMemoryResponse {
+    Redirect(Url),
+    Status(StatusCode),
+    Contents {
+        content_type: Option<String>,
+        contents: Vec<u8>,
+    },
+}
+
+impl InMemoryResourceLoader {
+    pub fn new() -> InMemoryResourceLoader {
+        InMemoryResourceLoader::default()
+    }
+
+    /// Makes `contents` the resource at `url`.
+    pub fn add<C: Into<Vec<u8>>>(&mut self, url: Url, content_type: Option<&str>, contents: C) {
+        self.responses.insert(
+            url,
+            InMemoryResponse::Contents {
+                content_type: content_type.map(|content_type| content_type.to_owned()),
+                contents: contents.into(),
+            },
+        );
+    }
+
+    /// Makes `url` redirect to `location`.
+    pub fn add_redirect(&mut self, url: Url, location: Url) {
+        self.responses
+            .insert(url, InMemoryResponse::Redirect(location));
+    }
+
+    /// Makes `url` respond with the unsuccessful HTTP `status`, e.g. a 404.
+    pub fn add_status(&mut self, url: Url, status: StatusCode) {
+        self.responses.insert(url, InMemoryResponse::Status(status));
+    }
+}
+
+impl ResourceLoader for InMemoryResourceLoader {
+    fn load(&self, url: &Url) -> Result<Resource, String> {
+        load_following_redirects(url, |url| {
+            let mut url_without_fragment = url.clone();
+            url_without_fragment.set_fragment(None);
+            match self.responses.get(&url_without_fragment) {
+                Some(InMemoryResponse::Redirect(location)) => {
+                    Ok(Response::Redirect(location.clone()))
+                }
+                Some(InMemoryResponse::Status(status)) => Err(status_error(url, *status)),
+                Some(InMemoryResponse::Contents {
+                    content_type,
+                    contents,
+                }) => Ok(Response::Contents {
+                    content_type: content_type.clone(),
+                    contents: contents.clone(),
+                }),
+                None if url.scheme() == "data" => load_data_url(url),
+                None => Err(format!("no resource at '{}'", url)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn load_data(data_url: &str) -> (Option<String>, Vec<u8>) {
+        let resource = DefaultResourceLoader.load(&url(data_url)).unwrap();
+        (resource.content_type, resource.contents)
+    }
+
+    #[test]
+    fn data_urls_are_percent_or_base64_decoded() {
+        assert_eq!(
+            load_data("data:text/css,p%20%7B%20color:%20red%20%7D"),
+            (Some("text/css".to_owned()), b"p { color: red }".to_vec())
+        );
+        assert_eq!(
+            load_data("data:text/html;charset=utf-8;base64,PHA+aGk8L3A+#fragment"),
+            (
+                Some("text/html;charset=utf-8".to_owned()),
+                b"<p>hi</p>".to_vec()
+            )
+        );
+        assert_eq!(
+            load_data("data:,hello"),
+            (
+                Some("text/plain;charset=US-ASCII".to_owned()),
+                b"hello".to_vec()
+            )
+        );
+        assert_eq!(
+            load_data("data:;base64,aG V%20sbG8="),
+            (
+                Some("text/plain;charset=US-ASCII".to_owned()),
+                b"hello".to_vec()
+            )
+        );
+        assert!(DefaultResourceLoader.load(&url("data:text/plain")).is_err());
+        assert!(DefaultResourceLoader
+            .load(&url("data:;base64,!!!"))
+            .is_err());
+    }
+
+    #[test]
+    fn redirects_are_recorded() {
+        let mut loader = InMemoryResourceLoader::new();
+        let (first, second, last) = (
+            url("https://example.com/first"),
+            url("https://example.com/second#kept"),
+            url("https://example.com/last"),
+        );
+        loader.add_redirect(first.clone(), second.clone());
+        loader.add_redirect(second.clone(), last.clone());
+        loader.add(last, Some("text/html"), "<p>last</p>");
+
+        let resource = loader.load(&first).unwrap();
+        assert_eq!(resource.url, url("https://example.com/last#kept"));
+        assert_eq!(resource.redirects, vec![first, second]);
+        assert_eq!(resource.content_type, Some("text/html".to_owned()));
+        assert_eq!(resource.contents, b"<p>last</p>");
+    }
+
+    #[test]
+    fn redirect_loops_fail() {
+        let mut loader = InMemoryResourceLoader::new();
+        let (one, two) = (
+            url("https://example.com/one"),
+            url("https://example.com/two"),
+        );
+        loader.add_redirect(one.clone(), two.clone());
+        loader.add_redirect(two, one.clone());
+        assert!(loader.load(&one).is_err());
+        assert!(loader.load(&url("https://example.com/missing")).is_err());
+    }
+
+    #[test]
+    fn error_statuses_fail() {
+        let mut loader = InMemoryResourceLoader::new();
+        let (moved, missing) = (
+            url("https://example.com/moved"),
+            url("https://example.com/missing"),
+        );
+        loader.add_redirect(moved.clone(), missing.clone());
+        loader.add_status(missing.clone(), StatusCode::NOT_FOUND);
+        let err = loader.load(&moved).unwrap_err();
+        assert!(err.contains("404"), "{}", err);
+        assert!(err.contains(missing.as_str()), "{}", err);
+    }
+}
@@ -0,0 +1,534 @@
+//! The shapes each border style is painted with.  Every side of a border is a trapezoid, whose
+//! ends are mitered on the diagonals between the outer and inner corners of the border box, so
+//! that adjacent sides meet there without overlapping.
+//!
+//! https://www.w3.org/TR/css-backgrounds-3/#border-style
+
+use crate::layout::rect::{EdgeSizes, Rect, RoundedRect};
+use crate::style::values::computed::LineStyle;
+use crate::Side;
+use cssparser::RGBA;
+use pathfinder_geometry::vector::Vector2F;
+use std::f32::consts::PI;
+
+/// How many sides the polygons that approximate round dots have.
+const DOT_SEGMENTS: usize = 16;
+
+/// Dots are square, rather than round, in borders thinner than this, where the difference isn't
+/// visible.
+const MIN_ROUND_DOT_WIDTH: f32 = 3.;
+
+/// How long dashes are, relative to the border width.
+const DASH_LENGTH: f32 = 3.;
+
+/// The shortest gap between dashes, relative to the border width.
+const DASH_GAP: f32 = 2.;
+
+/// A convex polygon painted in a solid color, with points in CSS pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BorderPolygon {
+    pub color: RGBA,
+    pub points: Vec<Vector2F>,
+}
+
+/// The polygons that paint the border `side` of a box with the given `border_box` and border
+/// `widths`, in the given `style` and `color`.
+pub fn border_side_polygons(
+    border_box: Rect,
+    widths: EdgeSizes,
+    side: Side,
+    style: LineStyle,
+    color: RGBA,
+) -> Vec<BorderPolygon> {
+    let geometry = SideGeometry::new(border_box, widths, side);
+    if geometry.width <= 0. {
+        return Vec::new();
+    }
+    let (raised, sunken) = raised_and_sunken(color, side);
+    let polygon = |color: RGBA, points: Vec<Vector2F>| BorderPolygon { color, points };
+    match style {
+        LineStyle::None | LineStyle::Hidden => Vec::new(),
+        LineStyle::Solid => vec![polygon(color, geometry.trapezoid())],
+        // The two lines and the space between them are each a third of the border width, which
+        // leaves no space in borders too thin for that.
+        LineStyle::Double if geometry.width < 3. => vec![polygon(color, geometry.trapezoid())],
+        LineStyle::Double => vec![
+            polygon(color, geometry.band(0., 1. / 3.)),
+            polygon(color, geometry.band(2. / 3., 1.)),
+        ],
+        LineStyle::Inset => vec![polygon(sunken, geometry.trapezoid())],
+        LineStyle::Outset => vec![polygon(raised, geometry.trapezoid())],
+        LineStyle::Groove => vec![
+            polygon(sunken, geometry.band(0., 0.5)),
+            polygon(raised, geometry.band(0.5, 1.)),
+        ],
+        LineStyle::Ridge => vec![
+            polygon(raised, geometry.band(0., 0.5)),
+            polygon(sunken, geometry.band(0.5, 1.)),
+        ],
+        LineStyle::Dashed => geometry
+            .dashes()
+            .into_iter()
+            .map(|dash| polygon(color, dash))
+            .collect(),
+        LineStyle::Dotted => geometry
+            .dots()
+            .into_iter()
+            .map(|dot| polygon(color, dot))
+            .collect(),
+    }
+}
+
+/// A part of a border with rounded corners: the ring between `outer` and `inner`, painted in a
+/// solid color within the convex polygon `area`.  Everything is in CSS pixels.
+#[derive(Clone, Debug)]
+pub struct RoundedBorderShape {
+    pub color: RGBA,
+    pub outer: RoundedRect,
+    pub inner: RoundedRect,
+    pub area: Vec<Vector2F>,
+}
+
+/// The shapes that paint the border `side` of a box with the given rounded `border_box` and
+/// border `widths`, in the given `style` and `color`.  Each side is the part of the rings of the
+/// border that's between the lines through the outer and inner corners at either end of the
+/// side, so that adjacent sides meet along those lines, like they do when the corners are square.
+pub fn rounded_border_side_shapes(
+    border_box: RoundedRect,
+    widths: EdgeSizes,
+    side: Side,
+    style: LineStyle,
+    color: RGBA,
+) -> Vec<RoundedBorderShape> {
+    let geometry = SideGeometry::new(border_box.rect, widths, side);
+    if geometry.width <= 0. {
+        return Vec::new();
+    }
+    let (raised, sunken) = raised_and_sunken(color, side);
+    let wedge = geometry.wedge(border_box.rect);
+    // The ring between the fractions `from` and `to` of the border widths, measured from the
+    // outer edge.
+    let band = |color: RGBA, from: f32, to: f32| {
+        let inset = |fraction: f32| {
+            let mut edges = widths;
+            edges.scale_by(fraction);
+            border_box.shrunk_by_edges(edges)
+        };
+        RoundedBorderShape {
+            color,
+            outer: inset(from),
+            inner: inset(to),
+            area: wedge.clone(),
+        }
+    };
+    match style {
+        LineStyle::None | LineStyle::Hidden => Vec::new(),
+        LineStyle::Solid => vec![band(color, 0., 1.)],
+        LineStyle::Double if geometry.width < 3. => vec![band(color, 0., 1.)],
+        LineStyle::Double => vec![band(color, 0., 1. / 3.), band(color, 2. / 3., 1.)],
+        LineStyle::Inset => vec![band(sunken, 0., 1.)],
+        LineStyle::Outset => vec![band(raised, 0., 1.)],
+        LineStyle::Groove => vec![band(sunken, 0., 0.5), band(raised, 0.5, 1.)],
+        LineStyle::Ridge => vec![band(raised, 0., 0.5), band(sunken, 0.5, 1.)],
+        // TODO: Space dashes and dots along the curves of rounded corners.  For now, they're laid
+        // out like on a square border, and only the parts of them inside the ring are painted.
+        LineStyle::Dashed | LineStyle::Dotted => {
+            let ring = band(color, 0., 1.);
+            border_side_polygons(border_box.rect, widths, side, style, color)
+                .into_iter()
+                .map(|polygon| RoundedBorderShape {
+                    color: polygon.color,
+                    area: polygon.points,
+                    ..ring.clone()
+                })
+                .collect()
+        }
+    }
+}
+
+/// The shades of `color` that the border `side` is painted with when the border looks raised,
+/// and when it looks sunken.
+fn raised_and_sunken(color: RGBA, side: Side) -> (RGBA, RGBA) {
+    let (dark, light) = shades(color);
+    // Whether the side is lit, rather than in shadow, when the border looks raised.
+    let lit_when_raised = side == Side::Top || side == Side::Left;
+    if lit_when_raised {
+        (light, dark)
+    } else {
+        (dark, light)
+    }
+}
+
+/// The darker and lighter shades of `color` that the 3D border styles are painted with.  Black
+/// is lightened too, so that its shading is still visible.
+fn shades(color: RGBA) -> (RGBA, RGBA) {
+    let shade = |channel: f32, towards: f32| channel + (towards - channel) / 3.;
+    let alpha = color.alpha_f32();
+    let (red, green, blue) = (color.red_f32(), color.green_f32(), color.blue_f32());
+    (
+        RGBA::from_floats(shade(red, 0.), shade(green, 0.), shade(blue, 0.), alpha),
+        RGBA::from_floats(shade(red, 1.), shade(green, 1.), shade(blue, 1.), alpha),
+    )
+}
+
+/// The trapezoid of one side of a border.
+struct SideGeometry {
+    /// The corners of the side along the outer edge of the border, in the direction of `along`.
+    outer_start: Vector2F,
+    outer_end: Vector2F,
+    /// The corners of the side along the inner edge of the border.
+    inner_start: Vector2F,
+    inner_end: Vector2F,
+    /// A unit vector from `outer_start` towards `outer_end`.
+    along: Vector2F,
+    /// A unit vector from the outer edge towards the inner edge.
+    inward: Vector2F,
+    /// The width of the border on this side.
+    width: f32,
+}
+
+impl SideGeometry {
+    fn new(border_box: Rect, widths: EdgeSizes, side: Side) -> SideGeometry {
+        let (left, top) = (border_box.start_x, border_box.start_y);
+        let right = left + border_box.width.px();
+        let bottom = top + border_box.height.px();
+        let inner_left = left + widths.left.px();
+        let inner_top = top + widths.top.px();
+        let inner_right = right - widths.right.px();
+        let inner_bottom = bottom - widths.bottom.px();
+        let point = Vector2F::new;
+        match side {
+            Side::Top => SideGeometry {
+                outer_start: point(left, top),
+                outer_end: point(right, top),
+                inner_start: point(inner_left, inner_top),
+                inner_end: point(inner_right, inner_top),
+                along: point(1., 0.),
+                inward: point(0., 1.),
+                width: widths.top.px(),
+            },
+            Side::Right => SideGeometry {
+                outer_start: point(right, top),
+                outer_end: point(right, bottom),
+                inner_start: point(inner_right, inner_top),
+                inner_end: point(inner_right, inner_bottom),
+                along: point(0., 1.),
+                inward: point(-1., 0.),
+                width: widths.right.px(),
+            },
+            Side::Bottom => SideGeometry {
+                outer_start: point(left, bottom),
+                outer_end: point(right, bottom),
+                inner_start: point(inner_left, inner_bottom),
+                inner_end: point(inner_right, inner_bottom),
+                along: point(1., 0.),
+                inward: point(0., -1.),
+                width: widths.bottom.px(),
+            },
+            Side::Left => SideGeometry {
+                outer_start: point(left, top),
+                outer_end: point(left, bottom),
+                inner_start: point(inner_left, inner_top),
+                inner_end: point(inner_left, inner_bottom),
+                along: point(0., 1.),
+                inward: point(1., 0.),
+                width: widths.left.px(),
+            },
+        }
+    }
+
+    fn trapezoid(&self) -> Vec<Vector2F> {
+        self.band(0., 1.)
+    }
+
+    /// The part of the side between the fractions `from` and `to` of its width, measured from
+    /// its outer edge.
+    fn band(&self, from: f32, to: f32) -> Vec<Vector2F> {
+        let lerp = |a: Vector2F, b: Vector2F, weight: f32| a + (b - a) * weight;
+        vec![
+            lerp(self.outer_start, self.inner_start, from),
+            lerp(self.outer_end, self.inner_end, from),
+            lerp(self.outer_end, self.inner_end, to),
+            lerp(self.outer_start, self.inner_start, to),
+        ]
+    }
+
+    /// The part of `border_box` between the lines through the outer and inner corners at either
+    /// end of the side.  Unlike the trapezoid, this includes the curves of rounded inner corners,
+    /// which reach inside the inner edge of the border.
+    fn wedge(&self, border_box: Rect) -> Vec<Vector2F> {
+        // Keeps the part of `polygon` on the same side of the line from `from` to `to` as
+        // `towards`.
+        let keep_side = |polygon: &[Vector2F], from: Vector2F, to: Vector2F, towards: Vector2F| {
+            let direction = to - from;
+            let direction = if cross(direction, towards - from) < 0. {
+                direction * -1.
+            } else {
+                direction
+            };
+            clip_to_half_plane(polygon, from, direction)
+        };
+        let wedge = keep_side(
+            &border_box.corners(),
+            self.outer_start,
+            self.inner_start,
+            self.outer_end,
+        );
+        keep_side(&wedge, self.outer_end, self.inner_end, self.outer_start)
+    }
+
+    /// The length of the outer edge of the side.
+    fn length(&self) -> f32 {
+        (self.outer_end - self.outer_start).length()
+    }
+
+    /// Dashes that start and end at the corners, with the gaps between them stretched so that
+    /// they fit evenly.  Dashes at the corners are mitered like the side.
+    fn dashes(&self) -> Vec<Vec<Vector2F>> {
+        let length = self.length();
+        let dash = DASH_LENGTH * self.width;
+        let gap = DASH_GAP * self.width;
+        let count = ((length + gap) / (dash + gap)).floor();
+        if count < 2. {
+            return vec![self.trapezoid()];
+        }
+        let stretched_gap = (length - count * dash) / (count - 1.);
+        let trapezoid = self.trapezoid();
+        (0..count as usize)
+            .map(|index| {
+                let start = index as f32 * (dash + stretched_gap);
+                let across = self.inward * self.width;
+                let dash_start = self.outer_start + self.along * start;
+                let dash_end = self.outer_start + self.along * (start + dash);
+                clip_polygon(
+                    &[dash_start, dash_end, dash_end + across, dash_start + across],
+                    &trapezoid,
+                )
+            })
+            .filter(|dash| dash.len() >= 3)
+            .collect()
+    }
+
+    /// Dots as wide as the border, centered on its middle, with one centered in each corner and
+    /// the rest spread evenly with about a dot's width between them.  Dots in the corners are
+    /// shared with the adjacent side, along the diagonal.
+    fn dots(&self) -> Vec<Vec<Vector2F>> {
+        let radius = self.width / 2.;
+        let span = (self.length() - self.width).max(0.);
+        let count = ((span / (2. * self.width)).round() as usize).max(1) + 1;
+        let spacing = span / (count - 1) as f32;
+        let first_center = self.outer_start + self.along * radius + self.inward * radius;
+        let trapezoid = self.trapezoid();
+        (0..count)
+            .map(|index| {
+                let center = first_center + self.along * (index as f32 * spacing);
+                clip_polygon(&dot(center, radius), &trapezoid)
+            })
+            .filter(|dot| dot.len() >= 3)
+            .collect()
+    }
+}
+
+/// A dot of the given radius around `center`, which is round unless it's too small to tell.
+fn dot(center: Vector2F, radius: f32) -> Vec<Vector2F> {
+    if radius * 2. < MIN_ROUND_DOT_WIDTH {
+        return vec![
+            center + Vector2F::new(-radius, -radius),
+            center + Vector2F::new(radius, -radius),
+            center + Vector2F::new(radius, radius),
+            center + Vector2F::new(-radius, radius),
+        ];
+    }
+    (0..DOT_SEGMENTS)
+        .map(|index| {
+            let angle = 2. * PI * index as f32 / DOT_SEGMENTS as f32;
+            center + Vector2F::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// The part of the convex `polygon` that's inside the convex polygon `clip`, using the
+/// Sutherland–Hodgman algorithm.
+///
+/// https://en.wikipedia.org/wiki/Sutherland%E2%80%93Hodgman_algorithm
+pub fn clip_polygon(polygon: &[Vector2F], clip: &[Vector2F]) -> Vec<Vector2F> {
+    // Which side of each edge of `clip` is inside depends on its winding.
+    let winding = (0..clip.len())
+        .map(|index| cross(clip[index], clip[(index + 1) % clip.len()]))
+        .sum::<f32>()
+        .signum();
+    let mut output = polygon.to_vec();
+    for index in 0..clip.len() {
+        let (edge_start, edge_end) = (clip[index], clip[(index + 1) % clip.len()]);
+        output = clip_to_half_plane(&output, edge_start, (edge_end - edge_start) * winding);
+        if output.is_empty() {
+            break;
+        }
+    }
+    output
+}
+
+/// The part of `polygon` on the side of the line through `point` in the given `direction` where
+/// the cross product of `direction` with the offset from `point` isn't negative.
+fn clip_to_half_plane(polygon: &[Vector2F], point: Vector2F, direction: Vector2F) -> Vec<Vector2F> {
+    let inside = |vertex: Vector2F| cross(direction, vertex - point) >= 0.;
+    let crossing = |from: Vector2F, to: Vector2F| {
+        let t = cross(direction, point - from) / cross(direction, to - from);
+        from + (to - from) * t
+    };
+    let mut output = Vec::new();
+    for (index, &current) in polygon.iter().enumerate() {
+        let next = polygon[(index + 1) % polygon.len()];
+        match (inside(current), inside(next)) {
+            (true, true) => output.push(next),
+            (true, false) => output.push(crossing(current, next)),
+            (false, true) => {
+                output.push(crossing(current, next));
+                output.push(next);
+            }
+            (false, false) => {}
+        }
+    }
+    output
+}
+
+/// The z component of the cross product of `a` and `b`.
+fn cross(a: Vector2F, b: Vector2F) -> f32 {
+    a.x() * b.y() - a.y() * b.x()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::length::CSSPixelLength;
+
+    fn border_box() -> Rect {
+        Rect {
+            start_x: 0.,
+            start_y: 0.,
+            width: CSSPixelLength::new(100.),
+            height: CSSPixelLength::new(50.),
+        }
+    }
+
+    fn widths(left: f32, right: f32, top: f32, bottom: f32) -> EdgeSizes {
+        EdgeSizes {
+            left: CSSPixelLength::new(left),
+            right: CSSPixelLength::new(right),
+            top: CSSPixelLength::new(top),
+            bottom: CSSPixelLength::new(bottom),
+        }
+    }
+
+    fn points(polygon: &BorderPolygon) -> Vec<(f32, f32)> {
+        polygon
+            .points
+            .iter()
+            .map(|point| (point.x(), point.y()))
+            .collect()
+    }
+
+    #[test]
+    fn solid_sides_are_mitered_at_the_corners() {
+        let black = RGBA::new(0, 0, 0, 255);
+        let top = border_side_polygons(
+            border_box(),
+            widths(4., 8., 2., 2.),
+            Side::Top,
+            LineStyle::Solid,
+            black,
+        );
+        assert_eq!(top.len(), 1);
+        assert_eq!(
+            points(&top[0]),
+            vec![(0., 0.), (100., 0.), (92., 2.), (4., 2.)]
+        );
+    }
+
+    #[test]
+    fn double_borders_are_two_lines() {
+        let black = RGBA::new(0, 0, 0, 255);
+        let left = border_side_polygons(
+            border_box(),
+            widths(6., 6., 6., 6.),
+            Side::Left,
+            LineStyle::Double,
+            black,
+        );
+        assert_eq!(left.len(), 2);
+        assert_eq!(
+            points(&left[0]),
+            vec![(0., 0.), (0., 50.), (2., 48.), (2., 2.)]
+        );
+        assert_eq!(
+            points(&left[1]),
+            vec![(4., 4.), (4., 46.), (6., 44.), (6., 6.)]
+        );
+    }
+
+    #[test]
+    fn dashes_start_and_end_at_the_corners() {
+        let black = RGBA::new(0, 0, 0, 255);
+        // 100px fits 5 dashes of 12px with gaps of at least 8px, which are stretched to 10px.
+        let dashes = border_side_polygons(
+            border_box(),
+            widths(0., 0., 4., 0.),
+            Side::Top,
+            LineStyle::Dashed,
+            black,
+        );
+        assert_eq!(dashes.len(), 5);
+        assert_eq!(
+            points(&dashes[1]),
+            vec![(22., 0.), (34., 0.), (34., 4.), (22., 4.)]
+        );
+        assert_eq!(points(&dashes[4])[1], (100., 0.));
+    }
+
+    #[test]
+    fn rounded_sides_reach_past_the_inner_corners() {
+        let black = RGBA::new(0, 0, 0, 255);
+        let rounded = RoundedRect {
+            rect: border_box(),
+            radii: Default::default(),
+        };
+        let top = rounded_border_side_shapes(
+            rounded,
+            widths(10., 10., 10., 10.),
+            Side::Top,
+            LineStyle::Solid,
+            black,
+        );
+        assert_eq!(top.len(), 1);
+        // The diagonals through the corners of the top meet at the bottom of the box.
+        let mut area = top[0]
+            .area
+            .iter()
+            .map(|point| (point.x(), point.y()))
+            .collect::<Vec<_>>();
+        area.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        area.dedup();
+        assert_eq!(area, vec![(0., 0.), (50., 50.), (100., 0.)]);
+        assert_eq!(top[0].inner.rect.start_y, 10.);
+    }
+
+    #[test]
+    fn clipping_keeps_the_overlap() {
+        let square = |start: f32, size: f32| {
+            vec![
+                Vector2F::new(start, start),
+                Vector2F::new(start + size, start),
+                Vector2F::new(start + size, start + size),
+                Vector2F::new(start, start + size),
+            ]
+        };
+        let clipped = clip_polygon(&square(0., 10.), &square(5., 10.));
+        let mut corners = clipped
+            .iter()
+            .map(|point| (point.x(), point.y()))
+            .collect::<Vec<_>>();
+        corners.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        assert_eq!(corners, vec![(5., 5.), (5., 10.), (10., 5.), (10., 10.)]);
+        assert!(clip_polygon(&square(0., 4.), &square(5., 10.)).is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::gfx::display::{CharCommand, DisplayCommand, DisplayList};
 use crate::gfx::font::{FontError, PostscriptName};
-use crate::style::values::CSSFloat;
+use crate::gfx::shape::{GlyphOrientation, GlyphRun};
 use accountable_refcell::{Ref, RefCell};
 use app_units::Au;
 use cssparser::RGBA;
@@ -15,73 +15,67 @@ use gl::{
     TEXTURE_WRAP_S, TEXTURE_WRAP_T, UNPACK_ALIGNMENT, UNSIGNED_BYTE,
 };
 use pathfinder_geometry::transform2d::Transform2F;
-use pathfinder_geometry::vector::{Vector2F, Vector2I};
+use pathfinder_geometry::vector::Vector2F;
 use std::collections::HashMap;
 use std::os::raw::c_void;
 
+/// A glyph rasterized into an OpenGL texture.
 #[derive(Debug)]
-pub struct OpenglChar {
-    /// Offset to advance to next glyph.
-    advance: Vector2F,
-    /// Offset from baseline to left/top of glyph.
+pub struct OpenglGlyph {
+    /// Offset from the glyph origin (on the baseline) to the top-left corner of the rasterized
+    /// glyph, in device pixels.  Positive values go right and down.
     bearing: Vector2F,
-    /// The size of the character in pixels.
-    size_px: CSSFloat,
-    /// The OpenGL texture associated with this character.
+    /// The size of the rasterized glyph in device pixels.
+    size: Vector2F,
+    /// The OpenGL texture associated with this glyph.
     texture: Texture,
-    /// The actual character value.
-    val: char,
 }
 
-impl OpenglChar {
-    pub fn new(ch: char, size_px: i32, font: &Font, gl: &Gl) -> Result<OpenglChar, CharError> {
-        let glyph_id = match font.glyph_for_char(ch) {
-            Some(id) => id,
-            None => return Err(CharError::NoIdForChar),
-        };
-        let mut canvas = Canvas::new(Vector2I::splat(size_px), Format::A8);
+impl OpenglGlyph {
+    pub fn new(
+        glyph_id: u32,
+        size_px: f32,
+        font: &Font,
+        gl: &Gl,
+    ) -> Result<OpenglGlyph, CharError> {
+        let raster_bounds = font.raster_bounds(
+            glyph_id,
+            size_px,
+            Transform2F::default(),
+            HintingOptions::None,
+            RasterizationOptions::GrayscaleAa,
+        )?;
+        let mut canvas = Canvas::new(raster_bounds.size(), Format::A8);
+        // Rasterize so that the top-left corner of the glyph's bounds lands on the top-left corner
+        // of the canvas.
         font.rasterize_glyph(
             &mut canvas,
             glyph_id,
-            size_px as f32,
-            Transform2F::from_translation(Vector2F::new(0.0, size_px as f32)),
+            size_px,
+            Transform2F::from_translation(-raster_bounds.origin().to_f32()),
             HintingOptions::None,
             RasterizationOptions::GrayscaleAa,
         )?;
         // TODO: Implement texture packing.  Currently, for each font and size, a new OpenGL texture
-        // is generated along with each character and cached.  It would be better if we used one
-        // texture for each font and size, packing all rasterizations of each character into this
+        // is generated along with each glyph and cached.  It would be better if we used one
+        // texture for each font and size, packing all rasterizations of each glyph into this
         // singular texture.  This avoids rapid texture swapping when rendering lines of text,
         // which can cause serious perf issues.
-        let texture = OpenglChar::setup_texture(gl, &canvas);
-
-        // Divide by 64 because these are represented as FreeType font units, which are 1/64th
-        // of a pixel.  We want pixel units.
-        // TODO: Represent font units as a type to avoid confusion and ease conversion?
-        let advance_font_units = font.advance(glyph_id)?;
-        let advance = Vector2F::new(advance_font_units.x() / 64.0, advance_font_units.y() / 64.0);
-        let bearing_font_units = font.origin(glyph_id)?;
-        let bearing = Vector2F::new(bearing_font_units.x() / 64.0, bearing_font_units.y() / 64.0);
+        let texture = OpenglGlyph::setup_texture(gl, &canvas);
 
-        Ok(OpenglChar {
-            advance,
-            bearing,
-            size_px: size_px as f32,
+        Ok(OpenglGlyph {
+            bearing: raster_bounds.origin().to_f32(),
+            size: raster_bounds.size().to_f32(),
             texture,
-            val: ch,
         })
     }
 
-    pub fn advance(&self) -> Vector2F {
-        self.advance
-    }
-
     pub fn bearing(&self) -> Vector2F {
         self.bearing
     }
 
-    pub fn size_px(&self) -> CSSFloat {
-        self.size_px
+    pub fn size(&self) -> Vector2F {
+        self.size
     }
 
     fn setup_texture(gl: &Gl, rasterized_canvas: &Canvas) -> Texture {
@@ -116,7 +110,6 @@ impl OpenglChar {
 pub enum CharError {
     Font(FontError),
     Loading(GlyphLoadingError),
-    NoIdForChar,
 }
 
 impl From<GlyphLoadingError> for CharError {
@@ -132,24 +125,25 @@ impl From<FontError> for CharError {
 }
 
 pub struct CharHandle {
-    cached_chars: RefCell<HashMap<(PostscriptName, Au), HashMap<char, OpenglChar>>>,
+    cached_glyphs: RefCell<HashMap<(PostscriptName, Au), HashMap<u32, OpenglGlyph>>>,
     gl: Gl,
 }
 
 impl CharHandle {
     pub fn new(gl: &Gl) -> Self {
         CharHandle {
-            cached_chars: RefCell::new(HashMap::new()),
+            cached_glyphs: RefCell::new(HashMap::new()),
             gl: gl.clone(),
         }
     }
 
-    pub fn get_char(
+    /// Returns the glyph `glyph_id` of `font` rasterized at `font_size` (in device pixels).
+    pub fn get_glyph(
         &self,
         font: &Font,
         font_size: Au,
-        ch: char,
-    ) -> Result<Ref<OpenglChar>, CharError> {
+        glyph_id: u32,
+    ) -> Result<Ref<OpenglGlyph>, CharError> {
         let postscript_name = font.postscript_name().unwrap_or_else(|| {
             panic!(
                 "couldn't get font postscript name for font with `full_name`: {}",
@@ -158,64 +152,78 @@ impl CharHandle {
         });
         let font_and_size_key = (postscript_name, font_size);
         {
-            let mut font_to_chars = self.cached_chars.borrow_mut();
-            let chars = match font_to_chars.get_mut(&font_and_size_key) {
-                Some(chars) => chars,
-                None => {
-                    font_to_chars.insert(font_and_size_key.clone(), HashMap::new());
-                    font_to_chars.get_mut(&font_and_size_key).unwrap()
-                }
-            };
-            if !chars.contains_key(&ch) {
-                chars.insert(ch, OpenglChar::new(ch, font_size.to_px(), font, &self.gl)?);
+            let mut font_to_glyphs = self.cached_glyphs.borrow_mut();
+            let glyphs = font_to_glyphs
+                .entry(font_and_size_key.clone())
+                .or_insert_with(HashMap::new);
+            if !glyphs.contains_key(&glyph_id) {
+                let glyph = OpenglGlyph::new(glyph_id, font_size.to_f32_px(), font, &self.gl)?;
+                glyphs.insert(glyph_id, glyph);
             }
         }
-        let cached_chars = self.cached_chars.borrow();
-        let char_ref = Ref::map(cached_chars, |font_to_chars| {
-            let chars = font_to_chars.get(&font_and_size_key).unwrap();
-            chars.get(&ch).unwrap()
+        let cached_glyphs = self.cached_glyphs.borrow();
+        let glyph_ref = Ref::map(cached_glyphs, |font_to_glyphs| {
+            let glyphs = font_to_glyphs.get(&font_and_size_key).unwrap();
+            glyphs.get(&glyph_id).unwrap()
         });
-        Ok(char_ref)
+        Ok(glyph_ref)
     }
 
     // TODO: Not sure these `prepare` functions belong here in CharHandle...
-    pub fn prepare_char(
+    /// Prepares the glyphs of `glyph_run` for display, starting with the pen at `origin` (in CSS
+    /// pixels) and advancing it along the line after each glyph.  For upright glyphs in vertical
+    /// lines, the pen runs down the center of the line, at the top of each glyph's em box.  For all
+    /// other orientations, it runs along the baseline.
+    pub fn prepare_glyph_run(
         &self,
         display_list: &mut DisplayList,
-        ch: char,
+        glyph_run: &GlyphRun,
+        origin: Vector2F,
         color: RGBA,
         font: &Font,
-        size: Au,
         scale_factor: f32,
     ) -> Result<(), CharError> {
-        let opengl_char = self.get_char(font, size, ch)?;
-        display_list.push(DisplayCommand::Char(CharCommand::new(
-            opengl_char.advance(),
-            opengl_char.bearing(),
-            ch,
-            color,
-            Vector2F::new(size.to_f32_px(), size.to_f32_px()) * scale_factor,
-            // TODO: These starting x and y coordinates need to be determined by layout once inline
-            // layout is implemented.  For now, all characters will be painted over the top of  each
-            // other at 0,0.
-            Vector2F::new(300., 300.),
-            opengl_char.texture.id(),
-        )));
+        let raster_size = Au::from_f32_px(glyph_run.font_size().px() * scale_factor);
+        let orientation = glyph_run.orientation();
+        let mut pen = origin;
+        for glyph in glyph_run.glyphs() {
+            let opengl_glyph = self.get_glyph(font, raster_size, glyph.glyph_id)?;
+            // Glyphs without any outline, such as spaces, have nothing to paint.
+            if opengl_glyph.size().x() > 0. && opengl_glyph.size().y() > 0. {
+                display_list.push(DisplayCommand::Char(CharCommand::new(
+                    opengl_glyph.bearing(),
+                    glyph.glyph_id,
+                    color,
+                    opengl_glyph.size(),
+                    pen + offset_in_layout(glyph.offset, orientation),
+                    orientation,
+                    opengl_glyph.texture.id(),
+                )));
+            }
+            pen = pen + pen_advance(glyph.advance, orientation);
+        }
         Ok(())
     }
+}
 
-    pub fn prepare_str(
-        &self,
-        display_list: &mut DisplayList,
-        color: RGBA,
-        font: &Font,
-        str: &str,
-        size: Au,
-        scale_factor: f32,
-    ) -> Result<(), CharError> {
-        for char in str.chars() {
-            self.prepare_char(display_list, char, color, font, size, scale_factor)?;
+/// Converts a glyph offset, which is y-up and relative to the glyph, to y-down layout coordinates.
+fn offset_in_layout(offset: Vector2F, orientation: GlyphOrientation) -> Vector2F {
+    match orientation {
+        GlyphOrientation::Horizontal | GlyphOrientation::Upright => {
+            Vector2F::new(offset.x(), -offset.y())
         }
-        Ok(())
+        // Rotated a quarter turn clockwise, glyph-right is down and glyph-up is right.
+        GlyphOrientation::SidewaysRight => Vector2F::new(offset.y(), offset.x()),
+        // Rotated a quarter turn counter-clockwise, glyph-right is up and glyph-up is left.
+        GlyphOrientation::SidewaysLeft => Vector2F::new(-offset.y(), -offset.x()),
+    }
+}
+
+/// How far the pen moves after a glyph with the given advance, in layout coordinates.
+fn pen_advance(advance: f32, orientation: GlyphOrientation) -> Vector2F {
+    match orientation {
+        GlyphOrientation::Horizontal => Vector2F::new(advance, 0.),
+        GlyphOrientation::Upright | GlyphOrientation::SidewaysRight => Vector2F::new(0., advance),
+        GlyphOrientation::SidewaysLeft => Vector2F::new(0., -advance),
     }
 }
@@ -1,37 +1,132 @@
+use crate::dom::tree::NodeRef;
+use crate::gfx::border::{border_side_polygons, rounded_border_side_shapes};
 use crate::gfx::char::CharHandle;
+use crate::gfx::font::FontHandle;
+use crate::gfx::gradient::GradientCommand;
+use crate::gfx::image::{Image, ImageState};
+use crate::gfx::shape::GlyphOrientation;
 use crate::layout::behavior::BaseLayoutBoxBehavior;
+use crate::layout::dimensions::Dimensions;
+use crate::layout::flow::block::BlockLevelBox;
+use crate::layout::flow::inline::{InlineLevelContent, TextRun};
 use crate::layout::layout_box::LayoutBox;
-use crate::layout::rect::Rect;
+use crate::layout::rect::{Rect, RoundedRect};
+use crate::layout::replaced::ReplacedBox;
+use crate::layout::LayoutViewportDimensions;
+use crate::style::values::computed::background::{
+    layer_value, Attachment, BackgroundBox, BackgroundSizeLayer, RepeatStyle,
+};
+use crate::style::values::computed::image;
+use crate::style::values::computed::length::{CSSPixelLength, LengthPercentageOrAuto};
+use crate::style::values::computed::position::PositionComponent;
 use crate::style::values::computed::LineStyle;
+use crate::style::values::used::ToPx;
 use crate::Side;
 use cssparser::RGBA;
 use gl::texture::TextureId;
 use pathfinder_geometry::vector::Vector2F;
+use std::rc::Rc;
+use url::Url;
 
-/// Builds list of display commands that should be used to paint the output.
+/// Builds list of display commands that should be used to paint the output, which is a `viewport`
+/// in device pixels.
 pub fn build_display_list(
     layout_box: &LayoutBox,
-    _char_handle: &CharHandle,
-    _scale_factor: f32,
+    viewport: LayoutViewportDimensions,
+    char_handle: &CharHandle,
+    font_handle: &FontHandle,
+    scale_factor: f32,
 ) -> DisplayList {
     let mut display_list = Vec::new();
-    // TODO: Remove the three preceeding statements once text rendering is fixed.
-    // let font_handle = FontHandle::new();
-    // let font = font_handle.get_font("Helvetica").unwrap();
-    // char_handle
-    //     .prepare_char(
-    //         &mut display_list,
-    //         'A',
-    //         RGBA::new(0, 0, 0, 1),
-    //         &font,
-    //         Au::from_f32_px(12.),
-    //         scale_factor,
-    //     )
-    //     .unwrap();
-    prepare_layout_box(&mut display_list, &layout_box);
+    let text_context = TextPaintContext {
+        char_handle,
+        font_handle,
+        scale_factor,
+    };
+    let background_context = BackgroundPaintContext {
+        document: layout_box.node().owner_document(),
+        viewport: Rect {
+            start_x: 0.,
+            start_y: 0.,
+            width: viewport.width() / scale_factor,
+            height: viewport.height() / scale_factor,
+        },
+    };
+    prepare_layout_box(
+        &mut display_list,
+        &layout_box,
+        &text_context,
+        &background_context,
+    );
     display_list
 }
 
+/// Moves everything in `display_list` up by `scroll_y` CSS pixels, which is how far the document
+/// is scrolled down.
+pub fn scroll_display_list(display_list: &mut DisplayList, scroll_y: f32) {
+    for command in display_list {
+        match command {
+            DisplayCommand::Char(char_command) => {
+                let start_coords = char_command.start_coords;
+                char_command.start_coords =
+                    Vector2F::new(start_coords.x(), start_coords.y() - scroll_y);
+            }
+            DisplayCommand::RectSolidColor(_, rect) | DisplayCommand::Image(rect, _) => {
+                rect.start_y -= scroll_y
+            }
+            DisplayCommand::Polygon(_, points) => {
+                for point in points {
+                    *point = Vector2F::new(point.x(), point.y() - scroll_y);
+                }
+            }
+            DisplayCommand::RoundedRect(rounded_rect) => {
+                rounded_rect.outer.rect.start_y -= scroll_y;
+                if let Some(inner) = &mut rounded_rect.inner {
+                    inner.rect.start_y -= scroll_y;
+                }
+                for point in &mut rounded_rect.area {
+                    *point = Vector2F::new(point.x(), point.y() - scroll_y);
+                }
+            }
+            DisplayCommand::PushClip(clip) => clip.rect.start_y -= scroll_y,
+            DisplayCommand::BackgroundLayer(layer) => {
+                layer.clip.start_y -= scroll_y;
+                // Tiles fixed to the viewport stay put as the document scrolls beneath them, and
+                // only the area they're clipped to moves.
+                if !layer.fixed {
+                    layer.tile.start_y -= scroll_y;
+                }
+            }
+            DisplayCommand::ViewportBackground(_) | DisplayCommand::PopClip => {}
+        }
+    }
+}
+
+/// What's needed to turn glyphs into display commands.
+struct TextPaintContext<'a> {
+    char_handle: &'a CharHandle,
+    font_handle: &'a FontHandle,
+    scale_factor: f32,
+}
+
+/// What's needed to turn background images into display commands.
+struct BackgroundPaintContext {
+    /// The document being displayed, which holds the images that backgrounds refer to.
+    document: Option<NodeRef>,
+    /// The viewport, in CSS pixels, which is the positioning area of backgrounds fixed to it.
+    viewport: Rect,
+}
+
+impl BackgroundPaintContext {
+    /// The decoded image at `url`, if it was loaded successfully.
+    fn image(&self, url: &Url) -> Option<Rc<Image>> {
+        match self.document.as_ref()?.as_document()?.image(url)? {
+            ImageState::Available(image) => Some(image),
+            ImageState::Broken => None,
+        }
+    }
+}
+
 /// Represents a display list, which is a list of graphics operations Kosmonaut should perform to
 /// paint output to the screen.
 ///
@@ -58,59 +153,156 @@ pub enum DisplayCommand {
     /// special background painting capabilities that are more idiomatic, such as OpenGL's
     /// `Clear(COLOR_BUFFER_BIT)` and `ClearColor(r, g, b, a)` APIs.
     ViewportBackground(RGBA),
+    /// A convex polygon filled with a color, with points in CSS pixels, such as part of a border.
+    Polygon(RGBA, Vec<Vector2F>),
+    /// An image stretched to fill a rect, such as the content box of an `<img>`.
+    Image(Rect, Rc<Image>),
+    BackgroundLayer(BackgroundLayerCommand),
+    RoundedRect(RoundedRectCommand),
+    /// Clips everything painted until the matching [`PopClip`] to the inside of a rounded rect, in
+    /// CSS pixels, along with any clips pushed before it.
+    PushClip(RoundedRect),
+    PopClip,
+}
+
+/// A color filling the inside of `outer`, except for the inside of `inner` if there is one, within
+/// the convex polygon `area`.  Everything is in CSS pixels.
+///
+/// The polygon is what gets rasterized, and the rounded rects are what each of its pixels is
+/// tested against, so it should cover all of the shape that's meant to be painted.
+#[derive(Clone, Debug)]
+pub struct RoundedRectCommand {
+    pub color: RGBA,
+    pub outer: RoundedRect,
+    pub inner: Option<RoundedRect>,
+    pub area: Vec<Vector2F>,
+}
+
+/// A single layer of a box's background: an image tiled across the background painting area.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#backgrounds
+#[derive(Clone, Debug)]
+pub struct BackgroundLayerCommand {
+    pub content: LayerContent,
+    /// The background painting area, outside of which no part of the layer is painted.
+    pub clip: Rect,
+    /// The tile that all others are repeated from.
+    pub tile: Rect,
+    /// The distance between the starts of neighboring tiles horizontally, or `None` if the image
+    /// isn't repeated horizontally.
+    pub step_x: Option<f32>,
+    /// The distance between the starts of neighboring tiles vertically, or `None` if the image
+    /// isn't repeated vertically.
+    pub step_y: Option<f32>,
+    /// Whether the tiles are fixed to the viewport, rather than scrolling with the document.
+    pub fixed: bool,
+}
+
+/// What each tile of a background layer is painted with.
+#[derive(Clone, Debug)]
+pub enum LayerContent {
+    Image(Rc<Image>),
+    Gradient(GradientCommand),
+}
+
+impl BackgroundLayerCommand {
+    /// The tiles of this layer that are at least partially inside its clip.
+    pub fn tiles(&self) -> Vec<Rect> {
+        let clip = self.clip;
+        let starts_x = tile_starts(
+            self.tile.start_x,
+            self.step_x,
+            clip.start_x,
+            clip.start_x + clip.width.px(),
+        );
+        let starts_y = tile_starts(
+            self.tile.start_y,
+            self.step_y,
+            clip.start_y,
+            clip.start_y + clip.height.px(),
+        );
+        let mut tiles = Vec::new();
+        for &start_y in &starts_y {
+            for &start_x in &starts_x {
+                let tile = Rect {
+                    start_x,
+                    start_y,
+                    ..self.tile
+                };
+                if tile.intersection(&clip).is_some() {
+                    tiles.push(tile);
+                }
+            }
+        }
+        tiles
+    }
+}
+
+/// Where tiles start along one axis, when one of them starts at `start` and the others are each
+/// `step` apart, so that they cover everything from `clip_start` to `clip_end`.
+fn tile_starts(start: f32, step: Option<f32>, clip_start: f32, clip_end: f32) -> Vec<f32> {
+    let step = match step {
+        Some(step) if step > 0. => step,
+        _ => return vec![start],
+    };
+    let mut tile_start = start + ((clip_start - start) / step).floor() * step;
+    let mut starts = Vec::new();
+    while tile_start < clip_end {
+        starts.push(tile_start);
+        tile_start += step;
+    }
+    starts
 }
 
 #[derive(Clone, Debug)]
 pub struct CharCommand {
-    /// The horizontal and vertical distance to the next glyph.
-    advance: Vector2F,
-    /// Offset from baseline to left/top of glyph.
+    /// Offset from the glyph origin to the top-left corner of the rasterized glyph, in device
+    /// pixels.
     bearing: Vector2F,
-    /// The char to be rendered.
-    ch: char,
-    /// The color to render the char as.
+    /// The id of the glyph to be rendered.
+    glyph_id: u32,
+    /// The color to render the glyph as.
     color: RGBA,
-    /// The size to render the char as.
+    /// The size of the rasterized glyph, in device pixels.
     size: Vector2F,
-    /// The x and y coordinates of where the glyph origin should be placed on the layout viewport.
+    /// The x and y coordinates of where the glyph origin should be placed on the layout viewport,
+    /// in CSS pixels.
     start_coords: Vector2F,
+    /// How the glyph is rotated around its origin.
+    orientation: GlyphOrientation,
     // This is the only OpenGL-specific part of state associated with the command.  Kosmonaut is
     // pretty hardcoded to OpenGL, but I _really_ wanted to keep the DisplayCommands renderer-agnostic...
-    /// The OpenGL texture associated with this character.
+    /// The OpenGL texture associated with this glyph.
     texture_id: TextureId,
 }
 
 impl CharCommand {
     pub fn new(
-        advance: Vector2F,
         bearing: Vector2F,
-        ch: char,
+        glyph_id: u32,
         color: RGBA,
         size: Vector2F,
         start_coords: Vector2F,
+        orientation: GlyphOrientation,
         texture_id: TextureId,
     ) -> Self {
         CharCommand {
-            advance,
             bearing,
-            ch,
+            glyph_id,
             color,
             size,
             start_coords,
+            orientation,
             texture_id,
         }
     }
 
-    pub fn advance(&self) -> Vector2F {
-        self.advance
-    }
-
     pub fn bearing(&self) -> Vector2F {
         self.bearing
     }
 
-    pub fn ch(&self) -> char {
-        self.ch
+    pub fn glyph_id(&self) -> u32 {
+        self.glyph_id
     }
 
     pub fn color(&self) -> RGBA {
@@ -125,6 +317,10 @@ impl CharCommand {
         self.start_coords
     }
 
+    pub fn orientation(&self) -> GlyphOrientation {
+        self.orientation
+    }
+
     pub fn texture_id(&self) -> TextureId {
         self.texture_id
     }
@@ -132,60 +328,414 @@ impl CharCommand {
 
 /// Prepares a layout box for display in the correct order.  The order in which each part of a box
 /// is painted is defined here: https://www.w3.org/TR/CSS22/zindex.html
-fn prepare_layout_box(display_list: &mut DisplayList, layout_box: &LayoutBox) {
+fn prepare_layout_box(
+    display_list: &mut DisplayList,
+    layout_box: &LayoutBox,
+    text_context: &TextPaintContext,
+    background_context: &BackgroundPaintContext,
+) {
     // Step 1 of painting order
     if layout_box.is_root() {
         // Step 1.1
         let cvs = layout_box.computed_values();
         display_list.push(DisplayCommand::ViewportBackground(
-            cvs.background_color.rgba(),
+            cvs.get_background().background_color.rgba(),
         ));
-        // TODO: Step 1.2, painting background images
+        // Step 1.2.  The background images of the root element are painted over the whole
+        // canvas, which is at least as large as the viewport.
+        let margin_box = layout_box.dimensions().margin_box();
+        let viewport = background_context.viewport;
+        let canvas = Rect {
+            start_x: 0.,
+            start_y: 0.,
+            width: viewport.width.max(margin_box.start_x + margin_box.width),
+            height: viewport.height.max(margin_box.start_y + margin_box.height),
+        };
+        prepare_background_layers(display_list, layout_box, Some(canvas), background_context);
     }
 
     match layout_box {
-        LayoutBox::BlockLevel(_) => prepare_block_listitem_block_equiv(display_list, layout_box),
-        LayoutBox::InlineLevel(_) => {
+        // Block-level replaced elements are painted in step 4, and inline-level ones in step 7,
+        // but in both cases like a block.
+        LayoutBox::BlockLevel(BlockLevelBox::Replaced(replaced))
+        | LayoutBox::InlineLevel(InlineLevelContent::Replaced(replaced)) => {
+            prepare_block_listitem_block_equiv(display_list, layout_box, background_context);
+            prepare_replaced_content(display_list, replaced);
+        }
+        LayoutBox::BlockLevel(_) => {
+            prepare_block_listitem_block_equiv(display_list, layout_box, background_context)
+        }
+        LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => {
+            prepare_text_run(display_list, text_run, text_context)
+        }
+        LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(_)) => {
             // TODO: Implement other steps of painting order, 3 -> 10
-            // println!("skipping render of non-block box")
         }
     }
 
+    let clip = layout_box.overflow_clip();
+    if let Some(clip) = clip {
+        display_list.push(DisplayCommand::PushClip(clip));
+    }
     if let Some(children) = layout_box.children() {
         for child in children {
-            prepare_layout_box(display_list, child);
+            prepare_layout_box(display_list, child, text_context, background_context);
         }
     }
+    if clip.is_some() {
+        display_list.push(DisplayCommand::PopClip);
+    }
 }
 
 /// Preparation for step 2 from: https://www.w3.org/TR/CSS22/zindex.html
-fn prepare_block_listitem_block_equiv(display_list: &mut DisplayList, layout_box: &LayoutBox) {
-    prepare_background(display_list, layout_box);
+fn prepare_block_listitem_block_equiv(
+    display_list: &mut DisplayList,
+    layout_box: &LayoutBox,
+    background_context: &BackgroundPaintContext,
+) {
+    prepare_background(display_list, layout_box, background_context);
     prepare_borders(display_list, layout_box);
-    // TODO: Render text - https://learnopengl.com/In-Practice/Text-Rendering
+}
+
+/// Prepares the image of `replaced` for display, filling its content box.
+fn prepare_replaced_content(display_list: &mut DisplayList, replaced: &ReplacedBox) {
+    let content = replaced.dimensions().content;
+    if content.width == 0. || content.height == 0. {
+        return;
+    }
+    display_list.push(DisplayCommand::Image(content, replaced.image()));
+}
+
+/// Prepares the glyphs of each line fragment of `text_run` for display.  This is part of step 7
+/// from: https://www.w3.org/TR/CSS22/zindex.html
+fn prepare_text_run(
+    display_list: &mut DisplayList,
+    text_run: &TextRun,
+    text_context: &TextPaintContext,
+) {
+    let color = text_run.computed_values().get_inherited_text().color.rgba();
+    for fragment in text_run.fragments() {
+        let font = match fragment.glyphs.font() {
+            Some(font_id) => text_context.font_handle.font(font_id),
+            // Without a font, there are no glyphs to paint.
+            None => continue,
+        };
+        let rect = fragment.rect;
+        let origin = match fragment.glyphs.orientation() {
+            GlyphOrientation::Horizontal => Vector2F::new(rect.start_x, fragment.baseline),
+            // Upright glyphs are centered in the line.
+            GlyphOrientation::Upright => {
+                Vector2F::new(rect.start_x + rect.width.px() / 2., rect.start_y)
+            }
+            GlyphOrientation::SidewaysRight => Vector2F::new(fragment.baseline, rect.start_y),
+            GlyphOrientation::SidewaysLeft => {
+                Vector2F::new(fragment.baseline, rect.start_y + rect.height.px())
+            }
+        };
+        // Text containing a glyph that can't be rasterized is left partially unpainted, rather than
+        // failing the whole paint.
+        let _ = text_context.char_handle.prepare_glyph_run(
+            display_list,
+            &fragment.glyphs,
+            origin,
+            color,
+            &font,
+            text_context.scale_factor,
+        );
+    }
 }
 
 /// Prepares the background of `layout_box` for display by converting it to display command(s).
-fn prepare_background(display_list: &mut DisplayList, layout_box: &LayoutBox) {
-    let bg_color = layout_box.computed_values().background_color.rgba();
+fn prepare_background(
+    display_list: &mut DisplayList,
+    layout_box: &LayoutBox,
+    background_context: &BackgroundPaintContext,
+) {
+    let cvs = layout_box.computed_values();
+    let background = cvs.get_background();
+    let bg_color = background.background_color.rgba();
     if bg_color != RGBA::transparent() {
-        display_list.push(DisplayCommand::RectSolidColor(
-            bg_color,
-            layout_box.dimensions().border_box(),
-        ))
+        // The color is clipped like the bottom layer.
+        let bottom_layer = background.background_image.0.len() - 1;
+        let clip = *layer_value(&background.background_clip.0, bottom_layer);
+        let border_box = layout_box.rounded_border_box();
+        if border_box.radii.is_zero() {
+            display_list.push(DisplayCommand::RectSolidColor(
+                bg_color,
+                background_box(layout_box.dimensions(), clip),
+            ))
+        } else {
+            let outer = rounded_background_box(layout_box.dimensions(), border_box, clip);
+            display_list.push(DisplayCommand::RoundedRect(RoundedRectCommand {
+                color: bg_color,
+                outer,
+                inner: None,
+                area: outer.rect.corners().to_vec(),
+            }))
+        }
+    }
+    // The background images of the root element were painted over the whole canvas instead.
+    if !layout_box.is_root() {
+        prepare_background_layers(display_list, layout_box, None, background_context);
+    }
+}
+
+/// Prepares the background image layers of `layout_box` for display, from the bottom layer up.
+/// Each layer is painted within the box it's clipped to, unless a `canvas` to paint them all over
+/// is given.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#backgrounds
+fn prepare_background_layers(
+    display_list: &mut DisplayList,
+    layout_box: &LayoutBox,
+    canvas: Option<Rect>,
+    background_context: &BackgroundPaintContext,
+) {
+    let cvs = layout_box.computed_values();
+    let background = cvs.get_background();
+    let d = layout_box.dimensions();
+    // Layers painted over the canvas aren't clipped to the corners of the box.
+    let border_box = Some(layout_box.rounded_border_box())
+        .filter(|border_box| canvas.is_none() && !border_box.radii.is_zero());
+    for (index, layer_image) in background.background_image.0.iter().enumerate().rev() {
+        let loaded_image = match layer_image {
+            Some(image::Image::Url(url)) => match background_context.image(url) {
+                Some(loaded_image) => Some(loaded_image),
+                // Images that are missing or failed to load aren't painted.
+                None => continue,
+            },
+            Some(image::Image::Gradient(_)) => None,
+            None => continue,
+        };
+        let fixed = *layer_value(&background.background_attachment.0, index) == Attachment::Fixed;
+        let positioning_area = if fixed {
+            background_context.viewport
+        } else {
+            background_box(d, *layer_value(&background.background_origin.0, index))
+        };
+        let clip = canvas.unwrap_or_else(|| {
+            background_box(d, *layer_value(&background.background_clip.0, index))
+        });
+        let intrinsic_size = loaded_image
+            .as_ref()
+            .map(|loaded_image| (loaded_image.width() as f32, loaded_image.height() as f32));
+        let repeat = *layer_value(&background.background_repeat.0, index);
+        let area_width = positioning_area.width.px();
+        let area_height = positioning_area.height.px();
+        let size = *layer_value(&background.background_size.0, index);
+        let (mut width, mut height) =
+            background_image_size(size, area_width, area_height, intrinsic_size);
+
+        // `round` scales the tiles so that a whole number of them fit, and keeps the image's
+        // ratio if the other dimension's size is `auto`.
+        let (auto_width, auto_height) = match size {
+            BackgroundSizeLayer::Explicit {
+                width: size_width,
+                height: size_height,
+            } => (
+                size_width == LengthPercentageOrAuto::Auto,
+                size_height == LengthPercentageOrAuto::Auto,
+            ),
+            BackgroundSizeLayer::Cover | BackgroundSizeLayer::Contain => (false, false),
+        };
+        let round_x = repeat.x == RepeatStyle::Round && width > 0.;
+        let round_y = repeat.y == RepeatStyle::Round && height > 0.;
+        if round_x {
+            let rounded = area_width / (area_width / width).round().max(1.);
+            if !round_y && auto_height {
+                height *= rounded / width;
+            }
+            width = rounded;
+        }
+        if round_y {
+            let rounded = area_height / (area_height / height).round().max(1.);
+            if !round_x && auto_width {
+                width *= rounded / height;
+            }
+            height = rounded;
+        }
+        if width <= 0. || height <= 0. {
+            continue;
+        }
+
+        let (start_x, step_x) = tile_placement(
+            repeat.x,
+            positioning_area.start_x,
+            area_width,
+            width,
+            *layer_value(&background.background_position_x.0, index),
+        );
+        let (start_y, step_y) = tile_placement(
+            repeat.y,
+            positioning_area.start_y,
+            area_height,
+            height,
+            *layer_value(&background.background_position_y.0, index),
+        );
+        // Gradients are resolved against the size of their tiles.
+        let content = match (layer_image, loaded_image) {
+            (Some(image::Image::Gradient(gradient)), _) => {
+                LayerContent::Gradient(GradientCommand::new(gradient, width, height))
+            }
+            (_, Some(loaded_image)) => LayerContent::Image(loaded_image),
+            // Layers without an image were skipped above.
+            (_, None) => continue,
+        };
+        let layer = BackgroundLayerCommand {
+            content,
+            clip,
+            tile: Rect {
+                start_x,
+                start_y,
+                width: CSSPixelLength::new(width),
+                height: CSSPixelLength::new(height),
+            },
+            step_x,
+            step_y,
+            fixed,
+        };
+        if layer.tiles().is_empty() {
+            continue;
+        }
+        match border_box {
+            Some(border_box) => {
+                let clip = *layer_value(&background.background_clip.0, index);
+                display_list.push(DisplayCommand::PushClip(rounded_background_box(
+                    d, border_box, clip,
+                )));
+                display_list.push(DisplayCommand::BackgroundLayer(layer));
+                display_list.push(DisplayCommand::PopClip);
+            }
+            None => display_list.push(DisplayCommand::BackgroundLayer(layer)),
+        }
+    }
+}
+
+/// The rect of the given box of an element with dimensions `d`.
+fn background_box(d: Dimensions, background_box: BackgroundBox) -> Rect {
+    match background_box {
+        BackgroundBox::BorderBox => d.border_box(),
+        BackgroundBox::PaddingBox => d.padding_box(),
+        BackgroundBox::ContentBox => d.content,
+    }
+}
+
+/// The given box of an element with dimensions `d`, with its corners curved to match those of its
+/// rounded `border_box`.
+fn rounded_background_box(
+    d: Dimensions,
+    border_box: RoundedRect,
+    background_box: BackgroundBox,
+) -> RoundedRect {
+    match background_box {
+        BackgroundBox::BorderBox => border_box,
+        BackgroundBox::PaddingBox => border_box.shrunk_by_edges(d.border),
+        BackgroundBox::ContentBox => {
+            border_box.shrunk_by_edges(d.border.expanded_by_edges(d.padding))
+        }
+    }
+}
+
+/// The size of a background image before any `round` repetition, given its `background-size`, the
+/// size of its positioning area, and its intrinsic size, if it has one.  Images without an
+/// intrinsic size or ratio, such as gradients, fill the dimensions of the area that aren't given.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#the-background-size
+fn background_image_size(
+    size: BackgroundSizeLayer,
+    area_width: f32,
+    area_height: f32,
+    intrinsic_size: Option<(f32, f32)>,
+) -> (f32, f32) {
+    let intrinsic_size = intrinsic_size.filter(|&(width, height)| width > 0. && height > 0.);
+    match size {
+        BackgroundSizeLayer::Cover | BackgroundSizeLayer::Contain => match intrinsic_size {
+            Some((intrinsic_width, intrinsic_height)) => {
+                let scale_x = area_width / intrinsic_width;
+                let scale_y = area_height / intrinsic_height;
+                let scale = if size == BackgroundSizeLayer::Cover {
+                    scale_x.max(scale_y)
+                } else {
+                    scale_x.min(scale_y)
+                };
+                (intrinsic_width * scale, intrinsic_height * scale)
+            }
+            None => (area_width, area_height),
+        },
+        BackgroundSizeLayer::Explicit { width, height } => {
+            let resolve = |size: LengthPercentageOrAuto, available: f32| match size {
+                LengthPercentageOrAuto::LengthPercentage(length) => {
+                    Some(length.to_px(CSSPixelLength::new(available)).px())
+                }
+                LengthPercentageOrAuto::Auto => None,
+            };
+            match (
+                resolve(width, area_width),
+                resolve(height, area_height),
+                intrinsic_size,
+            ) {
+                (Some(width), Some(height), _) => (width, height),
+                (Some(width), None, Some((intrinsic_width, intrinsic_height))) => {
+                    (width, width * intrinsic_height / intrinsic_width)
+                }
+                (None, Some(height), Some((intrinsic_width, intrinsic_height))) => {
+                    (height * intrinsic_width / intrinsic_height, height)
+                }
+                (None, None, Some(intrinsic_size)) => intrinsic_size,
+                (Some(width), None, None) => (width, area_height),
+                (None, Some(height), None) => (area_width, height),
+                (None, None, None) => (area_width, area_height),
+            }
+        }
     }
 }
 
+/// Where the tile that all others are repeated from starts along one axis, and the distance
+/// between neighboring tiles if there are others, for an image of `size` in a positioning area
+/// that starts at `area_start` and is `area_size` long.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#the-background-repeat
+fn tile_placement(
+    repeat: RepeatStyle,
+    area_start: f32,
+    area_size: f32,
+    size: f32,
+    position: PositionComponent,
+) -> (f32, Option<f32>) {
+    if repeat == RepeatStyle::Space {
+        let count = (area_size / size).floor();
+        // The tiles touch both edges of the area, and the position is ignored, unless there's
+        // only room for one of them.
+        if count >= 2. {
+            let spacing = (area_size - count * size) / (count - 1.);
+            return (area_start, Some(size + spacing));
+        }
+    }
+    let start = area_start + position.to_px(CSSPixelLength::new(area_size - size)).px();
+    let step = match repeat {
+        RepeatStyle::Repeat | RepeatStyle::Round => Some(size),
+        RepeatStyle::Space | RepeatStyle::NoRepeat => None,
+    };
+    (start, step)
+}
+
 /// Prepares the borders of `layout_box` for display by converting them to display commands.
 fn prepare_borders(display_list: &mut DisplayList, layout_box: &LayoutBox) {
-    prepare_border(display_list, layout_box, Side::Bottom);
-    prepare_border(display_list, layout_box, Side::Left);
-    prepare_border(display_list, layout_box, Side::Right);
-    prepare_border(display_list, layout_box, Side::Top);
+    let border_box = layout_box.rounded_border_box();
+    prepare_border(display_list, layout_box, border_box, Side::Bottom);
+    prepare_border(display_list, layout_box, border_box, Side::Left);
+    prepare_border(display_list, layout_box, border_box, Side::Right);
+    prepare_border(display_list, layout_box, border_box, Side::Top);
 }
 
-/// Prepares the border `side` of `layout_box` for display by converting it to a display command.
-fn prepare_border(display_list: &mut DisplayList, layout_box: &LayoutBox, side: Side) {
+/// Prepares the border `side` of `layout_box`, whose border box is rounded like `border_box`, for
+/// display by converting it to display commands.
+fn prepare_border(
+    display_list: &mut DisplayList,
+    layout_box: &LayoutBox,
+    border_box: RoundedRect,
+    side: Side,
+) {
     let cvs = layout_box.computed_values();
     let d = layout_box.dimensions();
     let border_style = cvs.border_style(side);
@@ -201,32 +751,105 @@ fn prepare_border(display_list: &mut DisplayList, layout_box: &LayoutBox, side:
         return;
     }
 
-    let border_box = d.border_box();
-    let rect = match side {
-        Side::Bottom => Rect {
-            start_x: border_box.start_x,
-            start_y: (border_box.start_y + border_box.height - border_size_px).px(),
-            width: border_box.width,
-            height: border_size_px,
-        },
-        Side::Left => Rect {
-            start_x: border_box.start_x,
-            start_y: border_box.start_y,
-            width: border_size_px,
-            height: border_box.height,
-        },
-        Side::Right => Rect {
-            start_x: (border_box.start_x + border_box.width - border_size_px).px(),
-            start_y: border_box.start_y,
-            width: border_size_px,
-            height: border_box.height,
-        },
-        Side::Top => Rect {
-            start_x: border_box.start_x,
-            start_y: border_box.start_y,
-            width: border_box.width,
-            height: border_size_px,
-        },
-    };
-    display_list.push(DisplayCommand::RectSolidColor(border_color_rgba, rect));
+    if !border_box.radii.is_zero() {
+        for shape in
+            rounded_border_side_shapes(border_box, d.border, side, border_style, border_color_rgba)
+        {
+            display_list.push(DisplayCommand::RoundedRect(RoundedRectCommand {
+                color: shape.color,
+                outer: shape.outer,
+                inner: Some(shape.inner),
+                area: shape.area,
+            }));
+        }
+        return;
+    }
+
+    for polygon in border_side_polygons(
+        d.border_box(),
+        d.border,
+        side,
+        border_style,
+        border_color_rgba,
+    ) {
+        display_list.push(DisplayCommand::Polygon(polygon.color, polygon.points));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::length::LengthPercentage;
+    use crate::style::values::computed::Percentage;
+
+    fn percentage(value: f32) -> PositionComponent {
+        PositionComponent {
+            from_end: false,
+            offset: LengthPercentage::Percentage(Percentage(value)),
+        }
+    }
+
+    #[test]
+    fn auto_sizes_follow_the_intrinsic_ratio() {
+        let width_only = BackgroundSizeLayer::Explicit {
+            width: LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Percentage(
+                Percentage(0.5),
+            )),
+            height: LengthPercentageOrAuto::Auto,
+        };
+        assert_eq!(
+            background_image_size(width_only, 200., 100., Some((20., 10.))),
+            (100., 50.)
+        );
+        // Gradients have no ratio, so they fill the area in the dimension that's `auto`.
+        assert_eq!(
+            background_image_size(width_only, 200., 100., None),
+            (100., 100.)
+        );
+        assert_eq!(
+            background_image_size(BackgroundSizeLayer::Cover, 200., 100., Some((20., 20.))),
+            (200., 200.)
+        );
+        assert_eq!(
+            background_image_size(BackgroundSizeLayer::Contain, 200., 100., Some((20., 20.))),
+            (100., 100.)
+        );
+    }
+
+    #[test]
+    fn spaced_tiles_touch_both_edges() {
+        assert_eq!(
+            tile_placement(RepeatStyle::Space, 10., 100., 30., percentage(0.5)),
+            (10., Some(35.))
+        );
+        // With room for only one tile, it's positioned like it isn't repeated.
+        assert_eq!(
+            tile_placement(RepeatStyle::Space, 10., 100., 60., percentage(0.5)),
+            (30., None)
+        );
+    }
+
+    #[test]
+    fn tiles_cover_the_clip() {
+        let rect = |start_x, start_y, width, height| Rect {
+            start_x,
+            start_y,
+            width: CSSPixelLength::new(width),
+            height: CSSPixelLength::new(height),
+        };
+        let layer = BackgroundLayerCommand {
+            content: LayerContent::Image(Rc::new(Image::new(1, 1, vec![0; 4]))),
+            clip: rect(0., 0., 100., 20.),
+            tile: rect(45., 5., 30., 10.),
+            step_x: Some(30.),
+            step_y: None,
+            fixed: false,
+        };
+        let starts = layer
+            .tiles()
+            .iter()
+            .map(|tile| (tile.start_x, tile.start_y))
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![(-15., 5.), (15., 5.), (45., 5.), (75., 5.)]);
+    }
 }
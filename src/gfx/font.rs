@@ -1,37 +1,438 @@
-use accountable_refcell::{Ref, RefCell};
+use crate::fetch::ResourceLoader;
+use crate::gfx::shape::{shape_text, GlyphOrientation, GlyphRun, ShapingFace};
+use crate::style::font_face::{FontFaceRule, FontFaceSource};
+use crate::style::values::computed::length::CSSPixelLength;
+use crate::style::values::computed::{
+    ComputedValues, Direction, FontStretch, FontStyle, FontWeight, GenericFontFamily,
+    SingleFontFamily,
+};
+use accountable_refcell::RefCell;
 use font_kit::error::{FontLoadingError, SelectionError};
+use font_kit::family_name::FamilyName;
 use font_kit::loaders::default::Font;
+use font_kit::properties::{Properties, Stretch, Style, Weight};
 use font_kit::source::SystemSource;
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
+use url::Url;
+
+/// The generic families tried, in order, for characters that none of the families of an element's
+/// `font-family` have a glyph for.
+const FALLBACK_FAMILIES: [GenericFontFamily; 3] = [
+    GenericFontFamily::SansSerif,
+    GenericFontFamily::Serif,
+    GenericFontFamily::Monospace,
+];
+
+/// Identifies a font loaded by a `FontHandle`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FontId(usize);
 
 /// Provides a handle for loading and caching fonts that abstracts over all different font loaders
 /// and sources.
 #[derive(Default)]
 pub struct FontHandle {
-    cached_fonts: RefCell<HashMap<String, Font>>,
+    fonts: RefCell<Vec<Rc<Font>>>,
+    /// The result of each font lookup so far, so that fonts missing from the system aren't looked
+    /// up over and over again.
+    font_ids: RefCell<HashMap<FontKey, Option<FontId>>>,
+    /// The faces added by `@font-face` rules, in the order the rules were added.
+    font_faces: RefCell<Vec<(Rc<FontFaceRule>, FontId)>>,
+    /// Each font shaped with so far, parsed for shaping, or `None` if the shaper can't read it.
+    shaping_faces: RefCell<HashMap<FontId, Option<Rc<ShapingFace>>>>,
 }
 
 pub type PostscriptName = String;
 
+/// What a font was looked up by.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum FontKey {
+    PostscriptName(PostscriptName),
+    /// The URL of a font file, which fonts of `@font-face` rules are loaded from.
+    Url(Url),
+    /// A family and the `Properties` of the face within it, which aren't hashable themselves.
+    Family(SingleFontFamily, FontPropertiesKey),
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct FontPropertiesKey {
+    weight: u32,
+    style: FontStyle,
+    stretch: u32,
+}
+
 impl FontHandle {
     pub fn new() -> FontHandle {
-        FontHandle {
-            cached_fonts: RefCell::new(HashMap::new()),
+        FontHandle::default()
+    }
+
+    pub fn get_font(&self, postscript_name: &str) -> Result<Rc<Font>, FontError> {
+        let key = FontKey::PostscriptName(postscript_name.to_owned());
+        let font_id = self.get_or_load_font(key, || load_font(postscript_name))?;
+        Ok(self.font(font_id))
+    }
+
+    /// Returns the font used when no more specific font has been selected, which is the system's
+    /// best match for the generic `sans-serif` family.
+    pub fn default_font(&self) -> Result<Rc<Font>, FontError> {
+        let font_id = self.select_font(
+            &SingleFontFamily::Generic(GenericFontFamily::SansSerif),
+            &FontDescriptor::default(),
+        )?;
+        Ok(self.font(font_id))
+    }
+
+    /// Loads the font of an `@font-face` rule from the first of its sources that can be loaded,
+    /// making it available to `font-family` under the rule's family name.  Fonts at URLs are loaded
+    /// through `loader`, and the URLs must already be resolved against the stylesheet the rule is
+    /// from.
+    pub fn add_font_face(
+        &self,
+        rule: &FontFaceRule,
+        loader: &dyn ResourceLoader,
+    ) -> Result<(), FontError> {
+        let mut last_error = FontError::Selection(SelectionError::NotFound);
+        for source in &rule.sources {
+            match self.load_font_face_source(source, loader) {
+                Ok(font_id) => {
+                    self.font_faces
+                        .borrow_mut()
+                        .push((Rc::new(rule.clone()), font_id));
+                    return Ok(());
+                }
+                Err(err) => last_error = err,
+            }
         }
+        Err(last_error)
     }
 
-    pub fn get_font(&self, postscript_name: &str) -> Result<Ref<Font>, FontError> {
-        let key = postscript_name.to_owned();
-        {
-            let mut cached_fonts = self.cached_fonts.borrow_mut();
-            if !cached_fonts.contains_key(&key) {
-                let font = load_font(postscript_name)?;
-                cached_fonts.insert(key.clone(), font);
+    fn load_font_face_source(
+        &self,
+        source: &FontFaceSource,
+        loader: &dyn ResourceLoader,
+    ) -> Result<FontId, FontError> {
+        match source {
+            FontFaceSource::Local(name) => {
+                self.get_or_load_font(FontKey::PostscriptName(name.clone()), || load_font(name))
+            }
+            FontFaceSource::Url(url) => {
+                let url = Url::parse(url).map_err(|_| FontError::InvalidUrl(url.clone()))?;
+                self.get_or_load_font(FontKey::Url(url.clone()), || {
+                    let font_data = loader.load(&url).map_err(FontError::Fetch)?.contents;
+                    Ok(Font::from_bytes(Arc::new(font_data), 0)?)
+                })
+            }
+        }
+    }
+
+    /// Returns the font loaded as `font_id`.
+    pub fn font(&self, font_id: FontId) -> Rc<Font> {
+        Rc::clone(&self.fonts.borrow()[font_id.0])
+    }
+
+    /// Returns the font loaded as `font_id` parsed for shaping, or `None` if the shaper can't read
+    /// it.  Each font is only parsed the first time it's shaped with.
+    pub fn shaping_face(&self, font_id: FontId) -> Option<Rc<ShapingFace>> {
+        if let Some(shaping_face) = self.shaping_faces.borrow().get(&font_id) {
+            return shaping_face.clone();
+        }
+        let shaping_face = ShapingFace::new(&self.font(font_id)).map(Rc::new);
+        self.shaping_faces
+            .borrow_mut()
+            .insert(font_id, shaping_face.clone());
+        shaping_face
+    }
+
+    /// Returns the first font of `descriptor`'s families installed on the system, which is the
+    /// font whose metrics are used for the element's inline boxes.
+    pub fn primary_font(&self, descriptor: &FontDescriptor) -> Result<Rc<Font>, FontError> {
+        let candidates = self.candidate_fonts(descriptor)?;
+        // Faces restricted to other characters by `unicode-range` are skipped.
+        let primary = candidates
+            .iter()
+            .find(|candidate| candidate.covers(' '))
+            .unwrap_or(&candidates[0]);
+        Ok(self.font(primary.font_id))
+    }
+
+    /// Splits `text` into runs of characters set in the same font.  Each character is set in the
+    /// first font of `descriptor`'s families that has a glyph for it and whose `unicode-range`
+    /// covers it, falling back to generic families when none of them do, and to the first font of
+    /// the families when no font does.
+    ///
+    /// https://drafts.csswg.org/css-fonts-4/#font-matching-algorithm
+    pub fn font_runs(
+        &self,
+        text: &str,
+        descriptor: &FontDescriptor,
+    ) -> Result<Vec<(Range<usize>, FontId)>, FontError> {
+        let candidates = self.candidate_fonts(descriptor)?;
+        let mut runs: Vec<(Range<usize>, FontId)> = Vec::new();
+        for (index, ch) in text.char_indices() {
+            let end = index + ch.len_utf8();
+            if let Some((range, _)) = runs.last_mut() {
+                // Keep marks with the character they apply to, since they can't be shaped apart.
+                if continues_cluster(ch) {
+                    range.end = end;
+                    continue;
+                }
             }
+            let font_id = candidates
+                .iter()
+                .find(|candidate| {
+                    candidate.covers(ch)
+                        && self.font(candidate.font_id).glyph_for_char(ch).is_some()
+                })
+                .unwrap_or(&candidates[0])
+                .font_id;
+            match runs.last_mut() {
+                Some((range, run_font_id)) if *run_font_id == font_id => range.end = end,
+                _ => runs.push((index..end, font_id)),
+            }
+        }
+        Ok(runs)
+    }
+
+    /// The fonts of `descriptor`'s families that were added by `@font-face` rules or are installed
+    /// on the system, followed by those of the fallback families, without duplicates.  Never empty
+    /// when successful.
+    fn candidate_fonts(
+        &self,
+        descriptor: &FontDescriptor,
+    ) -> Result<Vec<CandidateFont>, FontError> {
+        let fallback_families = FALLBACK_FAMILIES
+            .iter()
+            .map(|&generic| SingleFontFamily::Generic(generic));
+        let mut candidates: Vec<CandidateFont> = Vec::new();
+        let mut last_error = None;
+        for family in descriptor.families.iter().cloned().chain(fallback_families) {
+            if let SingleFontFamily::FamilyName(name) = &family {
+                let faces = self.matching_font_faces(name, descriptor);
+                if !faces.is_empty() {
+                    candidates.extend(faces.into_iter().map(|(face, font_id)| CandidateFont {
+                        font_id,
+                        face: Some(face),
+                    }));
+                    continue;
+                }
+            }
+            match self.select_font(&family, descriptor) {
+                Ok(font_id) if !candidates.iter().any(|c| c.font_id == font_id) => {
+                    candidates.push(CandidateFont {
+                        font_id,
+                        face: None,
+                    })
+                }
+                Ok(_) => {}
+                Err(err) => last_error = Some(err),
+            }
+        }
+        match last_error {
+            Some(err) if candidates.is_empty() => Err(err),
+            _ if candidates.is_empty() => Err(FontError::Selection(SelectionError::NotFound)),
+            _ => Ok(candidates),
+        }
+    }
+
+    /// The faces added by `@font-face` rules for the family `name` whose style and weight best
+    /// match `descriptor`.  There may be several, covering different characters, in which case the
+    /// faces of later rules come first.
+    ///
+    /// https://drafts.csswg.org/css-fonts-4/#font-style-matching
+    fn matching_font_faces(
+        &self,
+        name: &str,
+        descriptor: &FontDescriptor,
+    ) -> Vec<(Rc<FontFaceRule>, FontId)> {
+        let mut faces = self
+            .font_faces
+            .borrow()
+            .iter()
+            .filter(|(face, _)| face.family.eq_ignore_ascii_case(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        let style_preference = match descriptor.style {
+            FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+            FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+            FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+        };
+        let style = match style_preference
+            .iter()
+            .find(|&&style| faces.iter().any(|(face, _)| face.style == style))
+        {
+            Some(&style) => style,
+            None => return Vec::new(),
+        };
+        faces.retain(|(face, _)| face.style == style);
+        let weight = closest_weight(
+            descriptor.weight.0,
+            faces.iter().map(|(face, _)| face.weight.0),
+        );
+        faces.retain(|(face, _)| Some(face.weight.0) == weight);
+        faces.reverse();
+        faces
+    }
+
+    /// Selects the face of `family` that best matches the weight, style, and stretch of
+    /// `descriptor`.
+    fn select_font(
+        &self,
+        family: &SingleFontFamily,
+        descriptor: &FontDescriptor,
+    ) -> Result<FontId, FontError> {
+        let key = FontKey::Family(family.clone(), descriptor.properties_key());
+        self.get_or_load_font(key, || {
+            Ok(SystemSource::new()
+                .select_best_match(&[family_name(family)], &descriptor.properties())?
+                .load()?)
+        })
+    }
+
+    fn get_or_load_font<F>(&self, key: FontKey, load: F) -> Result<FontId, FontError>
+    where
+        F: FnOnce() -> Result<Font, FontError>,
+    {
+        if let Some(cached) = self.font_ids.borrow().get(&key) {
+            return cached.ok_or(FontError::Selection(SelectionError::NotFound));
+        }
+        let loaded = load();
+        let font_id = match &loaded {
+            Ok(_) => Some(FontId(self.fonts.borrow().len())),
+            Err(_) => None,
+        };
+        self.font_ids.borrow_mut().insert(key, font_id);
+        let font = loaded?;
+        self.fonts.borrow_mut().push(Rc::new(font));
+        Ok(font_id.unwrap())
+    }
+}
+
+/// A font that text may be set in, and the `@font-face` rule it was added by, if any.
+struct CandidateFont {
+    font_id: FontId,
+    face: Option<Rc<FontFaceRule>>,
+}
+
+impl CandidateFont {
+    fn covers(&self, ch: char) -> bool {
+        self.face.as_ref().map_or(true, |face| face.covers(ch))
+    }
+}
+
+/// The weight among `weights` that best matches `desired_weight`, or `None` if there are none.
+fn closest_weight(desired_weight: f32, weights: impl Iterator<Item = f32>) -> Option<f32> {
+    weights.min_by(|&a, &b| {
+        let (a_direction, a_distance) = weight_distance(desired_weight, a);
+        let (b_direction, b_distance) = weight_distance(desired_weight, b);
+        a_direction
+            .cmp(&b_direction)
+            .then(a_distance.total_cmp(&b_distance))
+    })
+}
+
+/// How far `weight` is from `desired_weight` when matching font faces, with lower being a better
+/// match.  Weights between 400 and 500 prefer heavier weights up to 500, then lighter weights;
+/// lighter desired weights prefer lighter weights, and heavier ones prefer heavier weights.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-style-matching
+fn weight_distance(desired_weight: f32, weight: f32) -> (u8, f32) {
+    let distance = (weight - desired_weight).abs();
+    if desired_weight >= 400. && desired_weight <= 500. {
+        match weight {
+            weight if weight >= desired_weight && weight <= 500. => (0, distance),
+            weight if weight < desired_weight => (1, distance),
+            _ => (2, distance),
+        }
+    } else if desired_weight < 400. {
+        (if weight <= desired_weight { 0 } else { 1 }, distance)
+    } else {
+        (if weight >= desired_weight { 0 } else { 1 }, distance)
+    }
+}
+
+impl fmt::Debug for FontHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FontHandle")
+            .field(
+                "font_ids",
+                &self.font_ids.borrow().keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// The computed font properties of an element that determine which fonts its text is set in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontDescriptor {
+    pub families: Vec<SingleFontFamily>,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+    pub stretch: FontStretch,
+}
+
+impl FontDescriptor {
+    pub fn new(computed_values: &ComputedValues) -> FontDescriptor {
+        FontDescriptor {
+            families: computed_values.get_font().font_family.families.clone(),
+            weight: computed_values.get_font().font_weight,
+            style: computed_values.get_font().font_style,
+            stretch: computed_values.get_font().font_stretch,
+        }
+    }
+
+    fn properties(&self) -> Properties {
+        Properties {
+            weight: Weight(self.weight.0),
+            style: match self.style {
+                FontStyle::Normal => Style::Normal,
+                FontStyle::Italic => Style::Italic,
+                FontStyle::Oblique => Style::Oblique,
+            },
+            stretch: Stretch((self.stretch.0).0),
+        }
+    }
+
+    fn properties_key(&self) -> FontPropertiesKey {
+        FontPropertiesKey {
+            weight: self.weight.0.to_bits(),
+            style: self.style,
+            stretch: (self.stretch.0).0.to_bits(),
+        }
+    }
+}
+
+impl Default for FontDescriptor {
+    fn default() -> Self {
+        FontDescriptor::new(&ComputedValues::default())
+    }
+}
+
+fn family_name(family: &SingleFontFamily) -> FamilyName {
+    match family {
+        SingleFontFamily::FamilyName(name) => FamilyName::Title(name.clone()),
+        SingleFontFamily::Generic(GenericFontFamily::Serif) => FamilyName::Serif,
+        SingleFontFamily::Generic(GenericFontFamily::SansSerif) => FamilyName::SansSerif,
+        SingleFontFamily::Generic(GenericFontFamily::Monospace) => FamilyName::Monospace,
+        SingleFontFamily::Generic(GenericFontFamily::Cursive) => FamilyName::Cursive,
+        SingleFontFamily::Generic(GenericFontFamily::Fantasy) => FamilyName::Fantasy,
+        // font-kit has no notion of the system UI font, and it's usually sans-serif.
+        SingleFontFamily::Generic(GenericFontFamily::SystemUi) => FamilyName::SansSerif,
+    }
+}
+
+/// Whether `ch` belongs to the same cluster as the character before it, rather than starting a
+/// new one.  This covers the combining marks, joiners, and variation selectors that commonly follow
+/// a base character.
+fn continues_cluster(ch: char) -> bool {
+    match ch as u32 {
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F => {
+            true
         }
-        let cached_fonts = self.cached_fonts.borrow();
-        let font = Ref::map(cached_fonts, |fonts| fonts.get(&key).unwrap());
-        Ok(font)
+        0x200C | 0x200D | 0xFE00..=0xFE0F | 0xE0100..=0xE01EF => true,
+        _ => false,
     }
 }
 
@@ -41,11 +442,121 @@ fn load_font(postscript_name: &str) -> Result<Font, FontError> {
         .load()?)
 }
 
+/// Shapes `text` in the fonts `font_handle` selects for it per `descriptor`, returning a glyph run
+/// for each run of characters set in the same font, in logical order.  Without any font, the text
+/// is returned as a single run without any glyphs.
+pub fn shape_with_fallback(
+    font_handle: &FontHandle,
+    text: &str,
+    descriptor: &FontDescriptor,
+    font_size: CSSPixelLength,
+    direction: Option<Direction>,
+    orientation: GlyphOrientation,
+) -> Vec<GlyphRun> {
+    match font_handle.font_runs(text, descriptor) {
+        Ok(font_runs) => font_runs
+            .into_iter()
+            .map(|(range, font_id)| {
+                shape_text(
+                    &font_handle.font(font_id),
+                    font_id,
+                    font_handle.shaping_face(font_id).as_deref(),
+                    &text[range],
+                    font_size,
+                    direction,
+                    orientation,
+                )
+            })
+            .collect(),
+        Err(_) => vec![GlyphRun::new(
+            text.to_owned(),
+            Vec::new(),
+            None,
+            font_size,
+            orientation,
+        )],
+    }
+}
+
+/// Returns the advance of `text` in the inline direction when set in the fonts selected by
+/// `descriptor` at `font_size`, in `orientation`.
+pub fn str_advance(
+    font_handle: &FontHandle,
+    text: &str,
+    descriptor: &FontDescriptor,
+    font_size: CSSPixelLength,
+    orientation: GlyphOrientation,
+) -> CSSPixelLength {
+    shape_with_fallback(font_handle, text, descriptor, font_size, None, orientation)
+        .iter()
+        .fold(CSSPixelLength::new(0.), |acc, run| acc + run.advance())
+}
+
+/// The vertical metrics of a font scaled to a font size, in CSS pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontMetrics {
+    /// The distance from the baseline to the top of the font's content area.
+    pub ascent: CSSPixelLength,
+    /// The distance from the baseline to the bottom of the font's content area.  Unlike in the font
+    /// itself, this is positive for fonts that descend below the baseline.
+    pub descent: CSSPixelLength,
+    /// The extra space the font designer recommends adding between lines.
+    pub line_gap: CSSPixelLength,
+    /// The height of lowercase letters such as "x" above the baseline.
+    pub x_height: CSSPixelLength,
+}
+
+impl FontMetrics {
+    pub fn new(font: &Font, font_size: CSSPixelLength) -> FontMetrics {
+        let metrics = font.metrics();
+        let scale = font_size.px() / metrics.units_per_em as f32;
+        FontMetrics {
+            ascent: CSSPixelLength::new(metrics.ascent * scale),
+            descent: CSSPixelLength::new(metrics.descent.abs() * scale),
+            line_gap: CSSPixelLength::new(metrics.line_gap * scale),
+            x_height: CSSPixelLength::new(metrics.x_height * scale),
+        }
+    }
+
+    /// Metrics resembling those of a typical sans-serif font, for use when no font could be loaded.
+    pub fn fallback(font_size: CSSPixelLength) -> FontMetrics {
+        FontMetrics {
+            ascent: font_size * 0.8,
+            descent: font_size * 0.2,
+            line_gap: CSSPixelLength::new(0.),
+            x_height: font_size * 0.5,
+        }
+    }
+
+    /// The block size of the content area of an inline box set in this font.
+    ///
+    /// https://www.w3.org/TR/CSS2/visudet.html#inline-non-replaced
+    pub fn content_area_size(&self) -> CSSPixelLength {
+        self.ascent + self.descent
+    }
+
+    /// The used value of `line-height: normal` for this font.
+    pub fn normal_line_height(&self) -> CSSPixelLength {
+        self.content_area_size() + self.line_gap
+    }
+}
+
 // TODO: This error type seems a bit too general.  May want to refactor as this module evolves.
 #[derive(Debug)]
 pub enum FontError {
+    Io(std::io::Error),
     Loading(FontLoadingError),
     Selection(SelectionError),
+    /// A font URL that can't be parsed.
+    InvalidUrl(String),
+    /// An error loading a font file.
+    Fetch(String),
+}
+
+impl From<std::io::Error> for FontError {
+    fn from(err: std::io::Error) -> Self {
+        FontError::Io(err)
+    }
 }
 
 impl From<FontLoadingError> for FontError {
@@ -59,3 +570,23 @@ impl From<SelectionError> for FontError {
         FontError::Selection(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_weights_in_the_preferred_direction() {
+        let closest = |desired_weight, weights: &[f32]| {
+            closest_weight(desired_weight, weights.iter().copied())
+        };
+        let weights = [300., 500., 700.];
+        assert_eq!(closest(400., &weights), Some(500.));
+        assert_eq!(closest(450., &[300., 600.]), Some(300.));
+        assert_eq!(closest(350., &weights), Some(300.));
+        assert_eq!(closest(200., &weights), Some(300.));
+        assert_eq!(closest(600., &weights), Some(700.));
+        assert_eq!(closest(800., &weights), Some(700.));
+        assert_eq!(closest(400., &[]), None);
+    }
+}
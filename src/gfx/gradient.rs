@@ -0,0 +1,477 @@
+//! Gradients resolved against the size of the box they're painted in, which is all that's needed
+//! to paint them.
+
+use crate::style::values::computed::image::{
+    Circle, Ellipse, EndingShape, Gradient, GradientItem, GradientKind, LineDirection, ShapeExtent,
+    StopPosition,
+};
+use crate::style::values::computed::length::{CSSPixelLength, LengthPercentage};
+use crate::style::values::computed::position::Position;
+use cssparser::RGBA;
+use pathfinder_geometry::vector::Vector2F;
+use std::f32::consts::SQRT_2;
+
+/// The most color stops a gradient is painted with, once its color hints have been replaced by
+/// stops.  Any stops past it are dropped.
+pub const MAX_GRADIENT_STOPS: usize = 64;
+
+/// How many color stops each color hint is replaced by, which together approximate the curve of
+/// the transition between the stops on either side of the hint.
+const STOPS_PER_HINT: usize = 9;
+
+/// A gradient resolved against the size of its gradient box.
+#[derive(Clone, Debug)]
+pub struct GradientCommand {
+    pub geometry: GradientGeometry,
+    /// The color stops, in order of non-decreasing position.  Positions are fractions of the
+    /// gradient line of linear gradients, the gradient ray of radial gradients, and the turn of
+    /// conic gradients.  There are always at least two stops.
+    pub stops: Vec<ColorStop>,
+    pub repeating: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub position: f32,
+    pub color: RGBA,
+}
+
+/// The shape of a gradient, with points relative to the top-left corner of the gradient box, in
+/// CSS pixels.
+#[derive(Clone, Copy, Debug)]
+pub enum GradientGeometry {
+    /// Colors change along the gradient line from `start` (0%) to `end` (100%).
+    Linear { start: Vector2F, end: Vector2F },
+    /// Colors change outwards from `center` (0%) to the ellipse with the given horizontal and
+    /// vertical radii (100%).
+    Radial { center: Vector2F, radii: Vector2F },
+    /// Colors change clockwise around `center`, starting from `start_angle`, in radians clockwise
+    /// from straight up.
+    Conic { center: Vector2F, start_angle: f32 },
+}
+
+impl GradientCommand {
+    /// Resolves `gradient` for a gradient box of `width` by `height` CSS pixels, such as a tile of
+    /// a background image.
+    pub fn new(gradient: &Gradient, width: f32, height: f32) -> GradientCommand {
+        // The length that lengths and percentages of stop positions are relative to.
+        let (geometry, length) = match &gradient.kind {
+            GradientKind::Linear(direction) => linear_geometry(*direction, width, height),
+            GradientKind::Radial(shape, position) => {
+                radial_geometry(*shape, *position, width, height)
+            }
+            GradientKind::Conic(from_angle, position) => (
+                GradientGeometry::Conic {
+                    center: position_in_box(*position, width, height),
+                    start_angle: from_angle.radians(),
+                },
+                0.,
+            ),
+        };
+        let mut stops = resolve_stops(&gradient.items, length);
+        stops.truncate(MAX_GRADIENT_STOPS);
+        GradientCommand {
+            geometry,
+            stops,
+            repeating: gradient.repeating,
+        }
+    }
+}
+
+/// The gradient line of a linear gradient, and its length.  The line passes through the center
+/// of the box, and is long enough that the corners of the box are at 0% and 100%.
+///
+/// https://drafts.csswg.org/css-images-3/#linear-gradient-syntax
+fn linear_geometry(direction: LineDirection, width: f32, height: f32) -> (GradientGeometry, f32) {
+    let direction = match direction {
+        LineDirection::Angle(angle) => Vector2F::new(angle.radians().sin(), -angle.radians().cos()),
+        LineDirection::Corner {
+            to_right,
+            to_bottom,
+        } => {
+            // The line is perpendicular to the diagonal between the other two corners, so that
+            // they're both at 50%.
+            let towards_corner = Vector2F::new(
+                if to_right { height } else { -height },
+                if to_bottom { width } else { -width },
+            );
+            if towards_corner.length() == 0. {
+                Vector2F::new(0., 1.)
+            } else {
+                towards_corner * (1. / towards_corner.length())
+            }
+        }
+    };
+    let length = (width * direction.x()).abs() + (height * direction.y()).abs();
+    let center = Vector2F::new(width / 2., height / 2.);
+    let half_line = direction * (length / 2.);
+    (
+        GradientGeometry::Linear {
+            start: center - half_line,
+            end: center + half_line,
+        },
+        length,
+    )
+}
+
+/// The center and radii of the ending shape of a radial gradient, and the length of its gradient
+/// ray, which is the horizontal radius.
+///
+/// https://drafts.csswg.org/css-images-3/#radial-gradient-syntax
+fn radial_geometry(
+    shape: EndingShape,
+    position: Position,
+    width: f32,
+    height: f32,
+) -> (GradientGeometry, f32) {
+    let center = position_in_box(position, width, height);
+    let (left, right) = (center.x().abs(), (width - center.x()).abs());
+    let (top, bottom) = (center.y().abs(), (height - center.y()).abs());
+    let closest_sides = Vector2F::new(left.min(right), top.min(bottom));
+    let farthest_sides = Vector2F::new(left.max(right), top.max(bottom));
+    let radii = match shape {
+        EndingShape::Circle(Circle::Radius(radius)) => Vector2F::new(radius.px(), radius.px()),
+        EndingShape::Circle(Circle::Extent(extent)) => {
+            let radius = match extent {
+                ShapeExtent::ClosestSide => closest_sides.x().min(closest_sides.y()),
+                ShapeExtent::FarthestSide => farthest_sides.x().max(farthest_sides.y()),
+                ShapeExtent::ClosestCorner => closest_sides.length(),
+                ShapeExtent::FarthestCorner => farthest_sides.length(),
+            };
+            Vector2F::new(radius, radius)
+        }
+        EndingShape::Ellipse(Ellipse::Radii(horizontal, vertical)) => Vector2F::new(
+            resolve_length(horizontal, width),
+            resolve_length(vertical, height),
+        ),
+        // An ellipse through a corner has the same aspect ratio as the one that touches the
+        // sides that meet there.
+        EndingShape::Ellipse(Ellipse::Extent(extent)) => match extent {
+            ShapeExtent::ClosestSide => closest_sides,
+            ShapeExtent::FarthestSide => farthest_sides,
+            ShapeExtent::ClosestCorner => closest_sides * SQRT_2,
+            ShapeExtent::FarthestCorner => farthest_sides * SQRT_2,
+        },
+    };
+    (GradientGeometry::Radial { center, radii }, radii.x())
+}
+
+fn position_in_box(position: Position, width: f32, height: f32) -> Vector2F {
+    Vector2F::new(
+        position.horizontal.to_px(CSSPixelLength::new(width)).px(),
+        position.vertical.to_px(CSSPixelLength::new(height)).px(),
+    )
+}
+
+fn resolve_length(length: LengthPercentage, relative_to: f32) -> f32 {
+    match length {
+        LengthPercentage::Length(length) => length.px(),
+        LengthPercentage::Percentage(percentage) => percentage.0 * relative_to,
+    }
+}
+
+/// A color stop or hint whose position has been resolved to a fraction of the gradient, if it
+/// has one.
+enum ResolvedItem {
+    ColorStop(RGBA, Option<f32>),
+    Hint(f32),
+}
+
+/// Resolves the positions of `items` to fractions of `length`, and replaces color hints with
+/// color stops that approximate them.
+///
+/// https://drafts.csswg.org/css-images-4/#color-stop-fixup
+fn resolve_stops(items: &[GradientItem], length: f32) -> Vec<ColorStop> {
+    let to_fraction = |position: StopPosition| match position {
+        StopPosition::LengthPercentage(LengthPercentage::Percentage(percentage)) => percentage.0,
+        StopPosition::LengthPercentage(LengthPercentage::Length(px)) if length > 0. => {
+            px.px() / length
+        }
+        StopPosition::LengthPercentage(LengthPercentage::Length(_)) => 0.,
+        StopPosition::Angle(angle) => angle.turns(),
+    };
+    let mut items = items
+        .iter()
+        .map(|item| match *item {
+            GradientItem::ColorStop(color, position) => {
+                ResolvedItem::ColorStop(color, position.map(to_fraction))
+            }
+            GradientItem::Hint(position) => ResolvedItem::Hint(to_fraction(position)),
+        })
+        .collect::<Vec<_>>();
+
+    // The first and last stops default to the start and end of the gradient.
+    if let Some(ResolvedItem::ColorStop(_, position @ None)) = items.first_mut() {
+        *position = Some(0.);
+    }
+    if let Some(ResolvedItem::ColorStop(_, position @ None)) = items.last_mut() {
+        *position = Some(1.);
+    }
+    // Positions never go backwards.
+    let mut max_position = f32::NEG_INFINITY;
+    for item in &mut items {
+        let position = match item {
+            ResolvedItem::ColorStop(_, Some(position)) | ResolvedItem::Hint(position) => position,
+            ResolvedItem::ColorStop(_, None) => continue,
+        };
+        *position = position.max(max_position);
+        max_position = *position;
+    }
+
+    let mut stops = items
+        .iter()
+        .filter_map(|item| match *item {
+            ResolvedItem::ColorStop(color, position) => Some((color, position)),
+            ResolvedItem::Hint(_) => None,
+        })
+        .collect::<Vec<_>>();
+    // Runs of stops without positions are spread evenly between the stops around them.
+    let mut run_start = 0;
+    for index in 1..stops.len() {
+        if let Some(end) = stops[index].1 {
+            let start = stops[run_start].1.unwrap_or(end);
+            let run_len = (index - run_start) as f32;
+            for (offset, stop) in stops[run_start + 1..index].iter_mut().enumerate() {
+                stop.1 = Some(start + (end - start) * (offset + 1) as f32 / run_len);
+            }
+            run_start = index;
+        }
+    }
+    let stops = stops
+        .into_iter()
+        .map(|(color, position)| ColorStop {
+            color,
+            position: position.unwrap_or(0.),
+        })
+        .collect::<Vec<_>>();
+
+    let mut resolved = Vec::with_capacity(stops.len());
+    let mut stop_index = 0;
+    for item in &items {
+        match *item {
+            ResolvedItem::ColorStop(..) => {
+                resolved.push(stops[stop_index]);
+                stop_index += 1;
+            }
+            // Hints are always between two color stops.
+            ResolvedItem::Hint(hint) => push_hint_stops(
+                &mut resolved,
+                stops[stop_index - 1],
+                stops[stop_index],
+                hint,
+            ),
+        }
+    }
+    resolved
+}
+
+/// Pushes the color stops that approximate the transition from `from` to `to` with its midpoint
+/// at the position of `hint`, excluding `from` and `to` themselves.
+///
+/// https://drafts.csswg.org/css-images-4/#coloring-gradient-line
+fn push_hint_stops(stops: &mut Vec<ColorStop>, from: ColorStop, to: ColorStop, hint: f32) {
+    let span = to.position - from.position;
+    if span <= 0. {
+        return;
+    }
+    let midpoint = (hint - from.position) / span;
+    if midpoint <= 0. {
+        stops.push(ColorStop {
+            position: from.position,
+            color: to.color,
+        });
+    } else if midpoint >= 1. {
+        stops.push(ColorStop {
+            position: to.position,
+            color: from.color,
+        });
+    } else {
+        let exponent = 0.5f32.ln() / midpoint.ln();
+        for step in 1..=STOPS_PER_HINT {
+            let fraction = step as f32 / (STOPS_PER_HINT + 1) as f32;
+            stops.push(ColorStop {
+                position: from.position + span * fraction,
+                color: interpolate(from.color, to.color, fraction.powf(exponent)),
+            });
+        }
+    }
+}
+
+/// Interpolates between `from` and `to` in premultiplied sRGB, which is how gradients transition
+/// between colors, so that transitions to `transparent` don't turn gray.
+///
+/// https://drafts.csswg.org/css-images-4/#coloring-gradient-line
+pub fn interpolate(from: RGBA, to: RGBA, weight: f32) -> RGBA {
+    let premultiplied = |color: RGBA| {
+        let alpha = color.alpha_f32();
+        [
+            color.red_f32() * alpha,
+            color.green_f32() * alpha,
+            color.blue_f32() * alpha,
+            alpha,
+        ]
+    };
+    let (from, to) = (premultiplied(from), premultiplied(to));
+    let mix = |channel: usize| from[channel] + (to[channel] - from[channel]) * weight;
+    let alpha = mix(3);
+    if alpha == 0. {
+        return RGBA::transparent();
+    }
+    RGBA::from_floats(mix(0) / alpha, mix(1) / alpha, mix(2) / alpha, alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::angle::Angle;
+    use crate::style::values::computed::position::PositionComponent;
+    use crate::style::values::computed::Percentage;
+
+    fn red() -> RGBA {
+        RGBA::new(255, 0, 0, 255)
+    }
+
+    fn blue() -> RGBA {
+        RGBA::new(0, 0, 255, 255)
+    }
+
+    fn percent(percent: f32) -> Option<StopPosition> {
+        Some(StopPosition::LengthPercentage(
+            LengthPercentage::Percentage(Percentage(percent / 100.)),
+        ))
+    }
+
+    fn positions(items: &[GradientItem], length: f32) -> Vec<f32> {
+        resolve_stops(items, length)
+            .iter()
+            .map(|stop| stop.position)
+            .collect()
+    }
+
+    fn center() -> Position {
+        let center = PositionComponent {
+            from_end: false,
+            offset: LengthPercentage::Percentage(Percentage(0.5)),
+        };
+        Position {
+            horizontal: center,
+            vertical: center,
+        }
+    }
+
+    #[test]
+    fn fixes_up_stop_positions() {
+        let stop = |position| GradientItem::ColorStop(red(), position);
+        assert_eq!(
+            positions(&[stop(None), stop(None), stop(None)], 100.),
+            vec![0., 0.5, 1.]
+        );
+        assert_eq!(
+            positions(
+                &[
+                    stop(percent(20.)),
+                    stop(None),
+                    stop(None),
+                    stop(percent(80.))
+                ],
+                100.
+            ),
+            vec![0.2, 0.4, 0.6, 0.8]
+        );
+        // Positions that go backwards are moved up to the largest position before them.
+        let px = |px| {
+            Some(StopPosition::LengthPercentage(LengthPercentage::new_len(
+                px,
+            )))
+        };
+        assert_eq!(
+            positions(&[stop(px(50.)), stop(percent(10.)), stop(None)], 200.),
+            vec![0.25, 0.25, 1.]
+        );
+    }
+
+    #[test]
+    fn replaces_hints_with_stops() {
+        let items = [
+            GradientItem::ColorStop(red(), None),
+            GradientItem::Hint(percent(50.).unwrap()),
+            GradientItem::ColorStop(blue(), None),
+        ];
+        let stops = resolve_stops(&items, 100.);
+        assert_eq!(stops.len(), STOPS_PER_HINT + 2);
+        // A hint halfway between two stops transitions linearly.
+        let middle = stops[(STOPS_PER_HINT + 1) / 2];
+        assert_eq!(middle.position, 0.5);
+        assert_eq!(middle.color, interpolate(red(), blue(), 0.5));
+
+        let items = [
+            GradientItem::ColorStop(red(), None),
+            GradientItem::Hint(percent(0.).unwrap()),
+            GradientItem::ColorStop(blue(), None),
+        ];
+        assert_eq!(
+            resolve_stops(&items, 100.)[1],
+            ColorStop {
+                position: 0.,
+                color: blue()
+            }
+        );
+    }
+
+    #[test]
+    fn interpolates_in_premultiplied_space() {
+        let transparent = RGBA::transparent();
+        assert_eq!(
+            interpolate(red(), transparent, 0.5),
+            RGBA::from_floats(1., 0., 0., 0.5)
+        );
+    }
+
+    #[test]
+    fn linear_gradient_lines_reach_the_corners() {
+        match linear_geometry(LineDirection::Angle(Angle::from_degrees(90.)), 200., 100.) {
+            (GradientGeometry::Linear { start, end }, length) => {
+                assert_eq!(length, 200.);
+                assert!((start.x() - 0.).abs() < 1e-4 && (start.y() - 50.).abs() < 1e-4);
+                assert!((end.x() - 200.).abs() < 1e-4 && (end.y() - 50.).abs() < 1e-4);
+            }
+            _ => panic!("expected a linear gradient"),
+        }
+        // Towards a corner, the other two corners are at 50%, so the line is as long as the box's
+        // height projected onto it twice.
+        let direction = LineDirection::Corner {
+            to_right: true,
+            to_bottom: false,
+        };
+        match linear_geometry(direction, 100., 100.) {
+            (GradientGeometry::Linear { start, end }, length) => {
+                assert!((length - 100. * SQRT_2).abs() < 1e-3);
+                assert!((start.x() - 0.).abs() < 1e-3 && (start.y() - 100.).abs() < 1e-3);
+                assert!((end.x() - 100.).abs() < 1e-3 && end.y().abs() < 1e-3);
+            }
+            _ => panic!("expected a linear gradient"),
+        }
+    }
+
+    #[test]
+    fn sizes_radial_gradients_by_extent() {
+        let radii = |shape| match radial_geometry(shape, center(), 200., 100.) {
+            (GradientGeometry::Radial { radii, .. }, _) => (radii.x(), radii.y()),
+            _ => panic!("expected a radial gradient"),
+        };
+        assert_eq!(
+            radii(EndingShape::Circle(Circle::Extent(
+                ShapeExtent::ClosestSide
+            ))),
+            (50., 50.)
+        );
+        assert_eq!(
+            radii(EndingShape::Ellipse(Ellipse::Extent(
+                ShapeExtent::FarthestSide
+            ))),
+            (100., 50.)
+        );
+        let (x, y) = radii(EndingShape::initial_value());
+        assert!((x - 100. * SQRT_2).abs() < 1e-3 && (y - 50. * SQRT_2).abs() < 1e-3);
+    }
+}
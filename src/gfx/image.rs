@@ -0,0 +1,221 @@
+//! Decoded images, and the loading of the images `<img>` elements are replaced by and the images
+//! used by stylesheets.
+
+use crate::dom::tree::NodeRef;
+use crate::fetch::ResourceLoader;
+use crate::style::properties::PropertyDeclaration;
+use crate::style::values::specified;
+use crate::style::CssRule;
+use std::fmt;
+use std::rc::Rc;
+use url::Url;
+
+/// An image decoded into 8-bit RGBA pixels, stored row by row from the top-left corner.
+#[derive(Clone, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image from `pixels`, which must hold four bytes (red, green, blue, and alpha) for
+    /// each of its `width * height` pixels.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Image {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4);
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Decodes a PNG, JPEG, GIF, or WebP image.  Only the first frame of animated images is kept.
+    pub fn decode(bytes: &[u8]) -> Result<Image, String> {
+        let decoded = image::load_from_memory(bytes)
+            .map_err(|err| format!("failed to decode image: {}", err))?
+            .to_rgba8();
+        let (width, height) = decoded.dimensions();
+        Ok(Image::new(width, height, decoded.into_raw()))
+    }
+
+    /// The intrinsic width of the image, in CSS pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The intrinsic height of the image, in CSS pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+impl fmt::Debug for Image {
+    // The pixels would drown out everything else in the debug output of boxes and display lists.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Image({}x{})", self.width, self.height)
+    }
+}
+
+/// The state of the image of an `<img>` element, once loading it has been attempted.
+///
+/// https://html.spec.whatwg.org/multipage/images.html#img-req-state
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageState {
+    /// The image was loaded and decoded, so the element is replaced by it.
+    Available(Rc<Image>),
+    /// The image has no `src`, or failed to load or decode.  The element is rendered as its `alt`
+    /// text instead.
+    Broken,
+}
+
+/// Loads and decodes the image of every `<img>` element in `dom`, recording the result on the
+/// element.  Each `src` is resolved against the base URL of the document.
+pub fn load_images(loader: &dyn ResourceLoader, dom: &NodeRef) {
+    for node in dom.inclusive_descendants() {
+        let element = match node.as_element() {
+            Some(element) if element.name.local == local_name!("img") => element,
+            _ => continue,
+        };
+        let state = match load_image(loader, &node) {
+            Ok(image) => ImageState::Available(Rc::new(image)),
+            Err(err) => {
+                println!("error loading image: {}", err);
+                ImageState::Broken
+            }
+        };
+        *element.image.borrow_mut() = Some(state);
+    }
+}
+
+/// Loads and decodes the background images used by the style rules among `rules`, recording the
+/// result on the document of `dom` by URL.  Images already recorded there aren't loaded again.
+///
+/// The URLs have already been resolved against the base URLs of their stylesheets.
+pub fn load_background_images(loader: &dyn ResourceLoader, dom: &NodeRef, rules: &[CssRule]) {
+    let document = match dom.as_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let image_urls = rules
+        .iter()
+        .filter_map(|rule| match rule {
+            CssRule::Style(style_rule) => Some(style_rule.block.declarations()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|declaration| match declaration {
+            PropertyDeclaration::BackgroundImage(background_image) => Some(&background_image.0),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|image| match image {
+            Some(specified::image::Image::Url(url)) => Url::parse(url).ok(),
+            _ => None,
+        });
+    for url in image_urls {
+        if document.image(&url).is_some() {
+            continue;
+        }
+        let state = match loader
+            .load(&url)
+            .and_then(|resource| Image::decode(&resource.contents))
+        {
+            Ok(image) => ImageState::Available(Rc::new(image)),
+            Err(err) => {
+                println!("error loading background image '{}': {}", url, err);
+                ImageState::Broken
+            }
+        };
+        document.set_image(url, state);
+    }
+}
+
+fn load_image(loader: &dyn ResourceLoader, img: &NodeRef) -> Result<Image, String> {
+    let url = img
+        .url_attribute(local_name!("src"))
+        .ok_or_else(|| "image has no valid src".to_owned())?;
+    Image::decode(&loader.load(&url)?.contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::fetch::InMemoryResourceLoader;
+    use crate::style::stylesheet::parse_css_to_stylesheet;
+
+    /// A 2x1 PNG with a red and a transparent pixel.
+    fn png() -> Vec<u8> {
+        let pixels = image::RgbaImage::from_raw(2, 1, vec![255, 0, 0, 255, 0, 0, 0, 0]).unwrap();
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(pixels)
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn decodes_to_rgba() {
+        let image = Image::decode(&png()).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(image.pixels(), &[255, 0, 0, 255, 0, 0, 0, 0]);
+        assert!(Image::decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn images_that_fail_to_load_are_broken() {
+        let dom = parse_html()
+            .one(r#"<img id="ok" src="dot.png"><img id="missing" src="gone.png"><img id="none">"#);
+        let document_url = Url::parse("https://example.com/page.html").unwrap();
+        dom.as_document().unwrap().set_url(document_url.clone());
+        let mut loader = InMemoryResourceLoader::new();
+        loader.add(
+            document_url.join("dot.png").unwrap(),
+            Some("image/png"),
+            png(),
+        );
+        load_images(&loader, &dom);
+
+        let state = |id: &str| {
+            let img = dom.select_first(&format!("#{}", id)).unwrap();
+            let state = img.image.borrow().clone();
+            state
+        };
+        assert!(matches!(state("ok"), Some(ImageState::Available(image)) if image.width() == 2));
+        assert_eq!(state("missing"), Some(ImageState::Broken));
+        assert_eq!(state("none"), Some(ImageState::Broken));
+    }
+
+    #[test]
+    fn loads_background_images_of_style_rules() {
+        let dom = parse_html().one("<p>");
+        let mut loader = InMemoryResourceLoader::new();
+        let url = |url: &str| Url::parse(url).unwrap();
+        loader.add(url("https://example.com/dot.png"), Some("image/png"), png());
+        let sheet = parse_css_to_stylesheet(
+            None,
+            &mut "p { background: url(https://example.com/dot.png), \
+                  url(https://example.com/gone.png), linear-gradient(red, blue) }"
+                .to_owned(),
+        )
+        .unwrap();
+        load_background_images(&loader, &dom, sheet.rules());
+
+        let document = dom.as_document().unwrap();
+        assert!(matches!(
+            document.image(&url("https://example.com/dot.png")),
+            Some(ImageState::Available(image)) if image.width() == 2
+        ));
+        assert_eq!(
+            document.image(&url("https://example.com/gone.png")),
+            Some(ImageState::Broken)
+        );
+    }
+}
@@ -4,13 +4,17 @@ use gl::{Gl, BLEND, ONE_MINUS_SRC_ALPHA, SRC_ALPHA};
 use glutin::dpi::PhysicalSize;
 use glutin::{Context, GlProfile, PixelFormat, PossiblyCurrent, WindowedContext};
 
+pub mod border;
 pub mod char;
 pub mod display;
 pub mod font;
+pub mod gradient;
 pub mod headed;
 pub mod headless;
+pub mod image;
 pub mod ndc;
 pub mod paint;
+pub mod shape;
 
 pub const DEFAULT_LAYOUT_VIEWPORT_WIDTH_PX: f32 = 1920.;
 pub const DEFAULT_LAYOUT_VIEWPORT_HEIGHT_PX: f32 = 1080.;
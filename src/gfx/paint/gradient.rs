@@ -0,0 +1,153 @@
+use crate::gfx::gradient::{GradientCommand, GradientGeometry, MAX_GRADIENT_STOPS};
+use crate::gfx::paint::{build_program, with_clip_functions, Clips};
+use gl::buffer::vbo::VertexBufferObject;
+use gl::program::Program;
+use gl::types::GLint;
+use gl::vao::VertexArrayObject;
+use gl::{Gl, FALSE, FLOAT, TRIANGLES};
+use pathfinder_geometry::vector::Vector2F;
+use std::ffi::CString;
+
+/// Data necessary to paint a gradient with OpenGL.
+#[derive(Clone, Debug)]
+pub struct GradientPaintData {
+    pub gradient: GradientCommand,
+    pub vertices: Vec<f32>,
+}
+
+impl GradientPaintData {
+    pub fn new(gradient: GradientCommand, vertices: Vec<f32>) -> Self {
+        GradientPaintData { gradient, vertices }
+    }
+}
+
+/// Uses given OpenGL context handle to paint gradients, computing the color of each fragment from
+/// the gradient's color stops.
+pub struct GradientPainter {
+    /// An instance of OpenGL.
+    gl: Gl,
+    /// The OpenGL program that will be used to paint gradients.
+    program: Program,
+    /// The VAO to use to paint gradients.
+    vao: VertexArrayObject,
+}
+
+impl GradientPainter {
+    pub fn new(gl: &Gl) -> Result<GradientPainter, String> {
+        let vbo = VertexBufferObject::new(gl);
+        let config_vao = |gl: &Gl| {
+            unsafe {
+                // Enable use of `layout (location=0)` data in our vertex shader.
+                gl.EnableVertexAttribArray(0);
+                // Each vertex is a position and a point within the gradient box, two floats each.
+                gl.VertexAttribPointer(
+                    0,
+                    4,
+                    FLOAT,
+                    FALSE,
+                    (4 * std::mem::size_of::<f32>()) as GLint,
+                    std::ptr::null(),
+                );
+            }
+        };
+        let vao = unsafe { VertexArrayObject::new(vbo, config_vao, gl) };
+
+        Ok(GradientPainter {
+            gl: gl.clone(),
+            program: build_gradient_program(gl)?,
+            vao,
+        })
+    }
+
+    pub fn paint(&mut self, paintable_gradients: &[GradientPaintData], clips: &Clips) {
+        self.program.use_globally();
+        clips.set_uniforms(&self.gl, &self.program);
+        unsafe {
+            self.gl.BindVertexArray(self.vao.name());
+        }
+        for gradient in paintable_gradients {
+            // Panic rather than truncate data.
+            assert!(gradient.vertices.len() <= i32::max_value() as usize);
+
+            self.set_uniforms(&gradient.gradient);
+            unsafe {
+                self.vao.store_vertex_data(&gradient.vertices);
+                // Each vertex is four floats.  Casting the `usize` to `GLint` will not truncate due
+                // to the above assert!().
+                self.gl
+                    .DrawArrays(TRIANGLES, 0, (gradient.vertices.len() / 4) as i32);
+            }
+        }
+        unsafe {
+            self.gl.BindVertexArray(0);
+        }
+    }
+
+    fn set_uniforms(&self, gradient: &GradientCommand) {
+        let (kind, origin, extent) = match gradient.geometry {
+            GradientGeometry::Linear { start, end } => (0, start, end),
+            GradientGeometry::Radial { center, radii } => (1, center, radii),
+            GradientGeometry::Conic {
+                center,
+                start_angle,
+            } => (2, center, Vector2F::new(start_angle, 0.)),
+        };
+        let stops = &gradient.stops[..gradient.stops.len().min(MAX_GRADIENT_STOPS)];
+        let colors = stops
+            .iter()
+            .flat_map(|stop| {
+                vec![
+                    stop.color.red_f32(),
+                    stop.color.green_f32(),
+                    stop.color.blue_f32(),
+                    stop.color.alpha_f32(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let positions = stops.iter().map(|stop| stop.position).collect::<Vec<_>>();
+        unsafe {
+            self.gl.Uniform1i(self.uniform_location("kind"), kind);
+            self.gl
+                .Uniform2f(self.uniform_location("origin"), origin.x(), origin.y());
+            self.gl
+                .Uniform2f(self.uniform_location("extent"), extent.x(), extent.y());
+            self.gl.Uniform1i(
+                self.uniform_location("repeating"),
+                gradient.repeating as GLint,
+            );
+            self.gl
+                .Uniform1i(self.uniform_location("stopCount"), stops.len() as GLint);
+            self.gl.Uniform4fv(
+                self.uniform_location("stopColors"),
+                stops.len() as GLint,
+                colors.as_ptr(),
+            );
+            self.gl.Uniform1fv(
+                self.uniform_location("stopPositions"),
+                stops.len() as GLint,
+                positions.as_ptr(),
+            );
+        }
+    }
+
+    fn uniform_location(&self, name: &str) -> GLint {
+        let name = CString::new(name).expect("couldn't create uniform name cstring");
+        unsafe { self.gl.GetUniformLocation(self.program.id(), name.as_ptr()) }
+    }
+}
+
+fn build_gradient_program(gl: &Gl) -> Result<Program, String> {
+    let vertex_shader_src = &CString::new(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/shader_src/gradient.vert"
+    )))
+    .expect("could not create cstring for gradient program");
+
+    let frag_shader_src = &CString::new(with_clip_functions(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/shader_src/gradient.frag"
+    ))))
+    .expect("could not create cstring for gradient program");
+
+    build_program(vertex_shader_src, frag_shader_src, gl)
+}
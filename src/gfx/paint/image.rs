@@ -0,0 +1,182 @@
+use crate::gfx::image::Image;
+use crate::gfx::ndc::{ndc_x, ndc_y};
+use crate::gfx::paint::{build_program, with_clip_functions, Clips, ToVertices};
+use crate::layout::rect::Rect;
+use crate::layout::LayoutViewportDimensions;
+use gl::buffer::vbo::VertexBufferObject;
+use gl::program::Program;
+use gl::texture::{Texture, TextureKind};
+use gl::types::GLint;
+use gl::vao::VertexArrayObject;
+use gl::{
+    Gl, CLAMP_TO_EDGE, FALSE, FLOAT, LINEAR, RGBA, TEXTURE0, TEXTURE_2D, TEXTURE_MAG_FILTER,
+    TEXTURE_MIN_FILTER, TEXTURE_WRAP_S, TEXTURE_WRAP_T, TRIANGLES, UNSIGNED_BYTE,
+};
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::rc::Rc;
+
+/// Data necessary to paint an image with OpenGL.
+#[derive(Clone, Debug)]
+pub struct ImagePaintData {
+    pub image: Rc<Image>,
+    pub vertices: Vec<f32>,
+}
+
+impl ImagePaintData {
+    pub fn new(image: Rc<Image>, vertices: Vec<f32>) -> Self {
+        ImagePaintData { image, vertices }
+    }
+}
+
+/// Uses given OpenGL context handle to paint decoded images, stretched over rectangles.
+pub struct ImagePainter {
+    /// An instance of OpenGL.
+    gl: Gl,
+    /// The OpenGL program that will be used to paint images.
+    program: Program,
+    /// The VAO to use to paint images.
+    vao: VertexArrayObject,
+    /// The texture each image was uploaded to, keyed by the address of the image.  The image is
+    /// kept alongside its texture so that the address can't be reused by another image while the
+    /// texture is cached.
+    textures: HashMap<*const Image, (Rc<Image>, Texture)>,
+    /// The images painted since textures were last dropped, whose textures are kept.
+    painted: HashSet<*const Image>,
+}
+
+impl ImagePainter {
+    pub fn new(gl: &Gl) -> Result<ImagePainter, String> {
+        let vbo = VertexBufferObject::new(gl);
+        let config_vao = |gl: &Gl| {
+            unsafe {
+                // Enable use of `layout (location=0)` data in our vertex shader.
+                gl.EnableVertexAttribArray(0);
+                // Each vertex is a position and a texture coordinate, two floats each.
+                gl.VertexAttribPointer(
+                    0,
+                    4,
+                    FLOAT,
+                    FALSE,
+                    (4 * std::mem::size_of::<f32>()) as GLint,
+                    std::ptr::null(),
+                );
+            }
+        };
+        let vao = unsafe { VertexArrayObject::new(vbo, config_vao, gl) };
+
+        Ok(ImagePainter {
+            gl: gl.clone(),
+            program: build_image_program(gl)?,
+            vao,
+            textures: HashMap::new(),
+            painted: HashSet::new(),
+        })
+    }
+
+    pub fn paint(&mut self, paintable_images: &[ImagePaintData], clips: &Clips) {
+        self.program.use_globally();
+        clips.set_uniforms(&self.gl, &self.program);
+        unsafe {
+            self.gl.ActiveTexture(TEXTURE0);
+            self.gl.BindVertexArray(self.vao.name());
+        }
+        for image in paintable_images {
+            // Panic rather than truncate data.
+            assert!(image.vertices.len() <= i32::max_value() as usize);
+
+            let gl = &self.gl;
+            self.painted.insert(Rc::as_ptr(&image.image));
+            let (_, texture) = self
+                .textures
+                .entry(Rc::as_ptr(&image.image))
+                .or_insert_with(|| (image.image.clone(), setup_texture(gl, &image.image)));
+            unsafe {
+                self.gl.BindTexture(TEXTURE_2D, texture.id());
+                self.vao.store_vertex_data(&image.vertices);
+                // Each vertex is four floats.  Casting the `usize` to `GLint` will not truncate due
+                // to the above assert!().
+                self.gl
+                    .DrawArrays(TRIANGLES, 0, (image.vertices.len() / 4) as i32);
+            }
+        }
+        unsafe {
+            self.gl.BindVertexArray(0);
+            self.gl.BindTexture(TEXTURE_2D, 0);
+        }
+    }
+
+    /// Drops the textures of images that weren't painted since this was last called, e.g. after
+    /// navigating away from their document.  Called once everything in a display list is painted.
+    pub fn drop_unpainted_textures(&mut self) {
+        let painted = &self.painted;
+        self.textures.retain(|image, _| painted.contains(image));
+        self.painted.clear();
+    }
+}
+
+fn setup_texture(gl: &Gl, image: &Image) -> Texture {
+    let texture = Texture::new(TextureKind::TwoDimensional, gl);
+    unsafe {
+        gl.BindTexture(TEXTURE_2D, texture.id());
+        gl.TexImage2D(
+            TEXTURE_2D,
+            0,
+            RGBA as GLint,
+            image.width() as GLint,
+            image.height() as GLint,
+            0,
+            RGBA,
+            UNSIGNED_BYTE,
+            image.pixels().as_ptr() as *const c_void,
+        );
+        gl.TexParameteri(TEXTURE_2D, TEXTURE_WRAP_S, CLAMP_TO_EDGE as GLint);
+        gl.TexParameteri(TEXTURE_2D, TEXTURE_WRAP_T, CLAMP_TO_EDGE as GLint);
+        gl.TexParameteri(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR as GLint);
+        gl.TexParameteri(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as GLint);
+    }
+    texture
+}
+
+fn build_image_program(gl: &Gl) -> Result<Program, String> {
+    let vertex_shader_src = &CString::new(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/shader_src/image.vert"
+    )))
+    .expect("could not create cstring for image program");
+
+    let frag_shader_src = &CString::new(with_clip_functions(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/shader_src/image.frag"
+    ))))
+    .expect("could not create cstring for image program");
+
+    build_program(vertex_shader_src, frag_shader_src, gl)
+}
+
+impl ToVertices for Rect {
+    /// Two triangles covering the rect, with texture coordinates mapping the whole of a texture
+    /// onto it.  The first row of an image is its top, so the texture's `t` axis points down.
+    fn to_vertices(&self, viewport: LayoutViewportDimensions, scale_factor: f32) -> Vec<f32> {
+        let rect = self.scaled_by(scale_factor);
+        let (viewport_width, viewport_height) = viewport.width_height_px();
+        let left = ndc_x(rect.start_x, viewport_width);
+        let top = ndc_y(rect.start_y, viewport_height);
+        let right = ndc_x((rect.start_x + rect.width).px(), viewport_width);
+        let bottom = ndc_y((rect.start_y + rect.height).px(), viewport_height);
+
+        let mut vertices = Vec::new();
+        for vertex in &[
+            [left, top, 0.0, 0.0],
+            [left, bottom, 0.0, 1.0],
+            [right, bottom, 1.0, 1.0],
+            [left, top, 0.0, 0.0],
+            [right, bottom, 1.0, 1.0],
+            [right, top, 1.0, 0.0],
+        ] {
+            vertices.extend_from_slice(vertex);
+        }
+        vertices
+    }
+}
@@ -1,27 +1,48 @@
-use crate::gfx::display::DisplayCommand;
+use crate::gfx::display::{BackgroundLayerCommand, DisplayCommand, LayerContent};
+use crate::gfx::ndc::{ndc_x, ndc_y};
+use crate::gfx::paint::gradient::{GradientPaintData, GradientPainter};
+use crate::gfx::paint::image::{ImagePaintData, ImagePainter};
 use crate::gfx::paint::rect::RectPainter;
+use crate::gfx::paint::rounded_rect::{RoundedRectPaintData, RoundedRectPainter};
 use crate::gfx::paint::text::TextPainter;
+use crate::layout::rect::RoundedRect;
 use crate::layout::LayoutViewportDimensions;
 use cssparser::RGBA;
 use gl::program::Program;
 use gl::shader::{Shader, ShaderKind};
 use gl::texture::TextureId;
+use gl::types::GLint;
 use gl::Gl;
 use glutin::{PossiblyCurrent, WindowedContext};
 use std::ffi::CString;
 
+pub mod gradient;
+pub mod image;
 pub mod rect;
+pub mod rounded_rect;
 pub mod text;
 
 /// Wraps other painters to ensure they are only painting OpenGL vertex data (paint) that
 /// corresponds to their "bucket".  This is necessary because vertex data for a rectangle needs to
 /// be painted differently (namely, different OpenGL drawing sequences) than vertex data for text,
 /// as an example.
+///
+/// Buckets are painted in the order rects, rounded rects, gradients, images, then text, so whenever
+/// a command would be painted before commands preceding it in the display list, the buckets are
+/// painted and emptied first.  The same goes for pushing and popping clips, since everything in the
+/// buckets is painted with the clips that were in place when it was added.
 pub struct MasterPainter {
     /// The OpenGL context to paint to.
     gl: Gl,
+    clips: Clips,
+    gradient_painter: GradientPainter,
+    gradient_vertices: Vec<GradientPaintData>,
+    image_painter: ImagePainter,
+    image_vertices: Vec<ImagePaintData>,
     rect_painter: RectPainter,
     rect_vertices: Vec<f32>,
+    rounded_rect_painter: RoundedRectPainter,
+    rounded_rect_vertices: Vec<RoundedRectPaintData>,
     scale_factor: f32,
     text_painter: TextPainter,
     text_vertices: Vec<CharPaintData>,
@@ -50,8 +71,15 @@ impl MasterPainter {
     pub fn new(gl: &Gl, scale_factor: f32) -> Result<MasterPainter, String> {
         Ok(MasterPainter {
             gl: gl.clone(),
+            clips: Clips::default(),
+            gradient_painter: GradientPainter::new(gl)?,
+            gradient_vertices: Vec::new(),
+            image_painter: ImagePainter::new(gl)?,
+            image_vertices: Vec::new(),
             rect_painter: RectPainter::new(gl)?,
             rect_vertices: Vec::new(),
+            rounded_rect_painter: RoundedRectPainter::new(gl)?,
+            rounded_rect_vertices: Vec::new(),
             scale_factor,
             text_painter: TextPainter::new(gl)?,
             text_vertices: Vec::new(),
@@ -89,13 +117,64 @@ impl MasterPainter {
     }
 
     fn paint_inner(&mut self, viewport: LayoutViewportDimensions, display_list: &[DisplayCommand]) {
+        self.clips = Clips {
+            rects: Vec::new(),
+            viewport_height: viewport.width_height_px().1,
+        };
         for command in display_list {
+            if self.would_paint_out_of_order(command) {
+                self.paint_buckets();
+            }
             self.process_display_command(command, viewport);
         }
-        self.rect_painter.paint(self.rect_vertices.as_slice());
-        self.text_painter.paint(self.text_vertices.as_slice());
+        self.paint_buckets();
+        self.image_painter.drop_unpainted_textures();
+    }
+
+    /// Whether `command` would be painted before something already in the paint buckets.
+    fn would_paint_out_of_order(&self, command: &DisplayCommand) -> bool {
+        match command {
+            DisplayCommand::RectSolidColor(..) | DisplayCommand::Polygon(..) => {
+                !self.rounded_rect_vertices.is_empty()
+                    || !self.gradient_vertices.is_empty()
+                    || !self.image_vertices.is_empty()
+                    || !self.text_vertices.is_empty()
+            }
+            DisplayCommand::RoundedRect(_) => {
+                !self.gradient_vertices.is_empty()
+                    || !self.image_vertices.is_empty()
+                    || !self.text_vertices.is_empty()
+            }
+            DisplayCommand::BackgroundLayer(BackgroundLayerCommand {
+                content: LayerContent::Gradient(_),
+                ..
+            }) => !self.image_vertices.is_empty() || !self.text_vertices.is_empty(),
+            DisplayCommand::BackgroundLayer(_) | DisplayCommand::Image(..) => {
+                !self.text_vertices.is_empty()
+            }
+            DisplayCommand::PushClip(_) | DisplayCommand::PopClip => true,
+            DisplayCommand::Char(_) | DisplayCommand::ViewportBackground(_) => false,
+        }
+    }
+
+    /// Paints everything in the paint buckets, then empties them.
+    fn paint_buckets(&mut self) {
+        let clips = &self.clips;
+        self.rect_painter
+            .paint(self.rect_vertices.as_slice(), clips);
+        self.rounded_rect_painter
+            .paint(self.rounded_rect_vertices.as_slice(), clips);
+        self.gradient_painter
+            .paint(self.gradient_vertices.as_slice(), clips);
+        self.image_painter
+            .paint(self.image_vertices.as_slice(), clips);
+        self.text_painter
+            .paint(self.text_vertices.as_slice(), clips);
         // Now that we've painted, let's dump the paint buckets so they're clean for the next paint.
         self.rect_vertices.clear();
+        self.rounded_rect_vertices.clear();
+        self.gradient_vertices.clear();
+        self.image_vertices.clear();
         self.text_vertices.clear();
     }
 
@@ -112,9 +191,40 @@ impl MasterPainter {
                     char_command.to_vertices(viewport, self.scale_factor),
                 ));
             }
+            DisplayCommand::Image(rect, image) => self.image_vertices.push(ImagePaintData::new(
+                image.clone(),
+                rect.to_vertices(viewport, self.scale_factor),
+            )),
+            DisplayCommand::BackgroundLayer(layer) => {
+                let vertices = layer.to_vertices(viewport, self.scale_factor);
+                match &layer.content {
+                    LayerContent::Image(image) => self
+                        .image_vertices
+                        .push(ImagePaintData::new(image.clone(), vertices)),
+                    LayerContent::Gradient(gradient) => self
+                        .gradient_vertices
+                        .push(GradientPaintData::new(gradient.clone(), vertices)),
+                }
+            }
             DisplayCommand::RectSolidColor(rgba, rect) => self
                 .rect_vertices
                 .extend((rect, rgba).to_vertices(viewport, self.scale_factor)),
+            DisplayCommand::Polygon(rgba, points) => self
+                .rect_vertices
+                .extend((rgba, points.as_slice()).to_vertices(viewport, self.scale_factor)),
+            DisplayCommand::RoundedRect(rounded_rect) => {
+                self.rounded_rect_vertices.push(RoundedRectPaintData::new(
+                    rounded_rect,
+                    self.scale_factor,
+                    rounded_rect.to_vertices(viewport, self.scale_factor),
+                ))
+            }
+            DisplayCommand::PushClip(clip) => {
+                self.clips.rects.push(clip.scaled_by(self.scale_factor))
+            }
+            DisplayCommand::PopClip => {
+                self.clips.rects.pop();
+            }
             DisplayCommand::ViewportBackground(rgba) => unsafe {
                 self.gl.ClearColor(
                     rgba.red_f32(),
@@ -128,6 +238,96 @@ impl MasterPainter {
     }
 }
 
+/// The most clips the fragment shaders can test against, which is `MAX_CLIPS` in `clip.glsl`.
+const MAX_CLIPS: usize = 8;
+
+/// The rounded rects that everything painted is clipped to, which every painter passes to the
+/// `clipCoverage()` function its fragment shader gets from `clip.glsl`.
+#[derive(Clone, Debug, Default)]
+pub struct Clips {
+    /// The clips pushed and not yet popped, in device pixels, from the outermost in.
+    rects: Vec<RoundedRect>,
+    /// The height of the viewport in device pixels, which the shaders need to flip window
+    /// coordinates with, since their y axis points up.
+    viewport_height: f32,
+}
+
+impl Clips {
+    /// Passes the clips to `program`, which must be in use.
+    pub fn set_uniforms(&self, gl: &Gl, program: &Program) {
+        // Beyond what the shaders can hold, only the innermost clips are kept, which are usually
+        // the smallest.
+        let rects = &self.rects[self.rects.len().saturating_sub(MAX_CLIPS)..];
+        let mut clip_rects = Vec::new();
+        let mut clip_radii_x = Vec::new();
+        let mut clip_radii_y = Vec::new();
+        for &rect in rects {
+            let (rect, radii_x, radii_y) = rounded_rect_uniforms(rect);
+            clip_rects.extend_from_slice(&rect);
+            clip_radii_x.extend_from_slice(&radii_x);
+            clip_radii_y.extend_from_slice(&radii_y);
+        }
+        let location = |name: &str| {
+            let name = CString::new(name).expect("couldn't create uniform name cstring");
+            unsafe { gl.GetUniformLocation(program.id(), name.as_ptr()) }
+        };
+        let count = rects.len() as GLint;
+        unsafe {
+            gl.Uniform1i(location("clipCount"), count);
+            gl.Uniform1f(location("viewportHeight"), self.viewport_height);
+            if count > 0 {
+                gl.Uniform4fv(location("clipRects"), count, clip_rects.as_ptr());
+                gl.Uniform4fv(location("clipRadiiX"), count, clip_radii_x.as_ptr());
+                gl.Uniform4fv(location("clipRadiiY"), count, clip_radii_y.as_ptr());
+            }
+        }
+    }
+}
+
+/// How shaders take a rounded rect: its start, width and height, then the horizontal radii of its
+/// corners, then their vertical radii, clockwise from the top-left corner.
+pub fn rounded_rect_uniforms(rounded_rect: RoundedRect) -> ([f32; 4], [f32; 4], [f32; 4]) {
+    let rect = rounded_rect.rect;
+    let radii = rounded_rect.radii;
+    let corners = [
+        radii.top_left,
+        radii.top_right,
+        radii.bottom_right,
+        radii.bottom_left,
+    ];
+    (
+        [
+            rect.start_x,
+            rect.start_y,
+            rect.width.px(),
+            rect.height.px(),
+        ],
+        [
+            corners[0].x(),
+            corners[1].x(),
+            corners[2].x(),
+            corners[3].x(),
+        ],
+        [
+            corners[0].y(),
+            corners[1].y(),
+            corners[2].y(),
+            corners[3].y(),
+        ],
+    )
+}
+
+/// Inserts the functions from `clip.glsl` after the `#version` line of a fragment shader, which
+/// GLSL requires to come first, so that the shader can call them.
+pub fn with_clip_functions(fragment_shader_src: &str) -> String {
+    let clip_src = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/shader_src/clip.glsl"));
+    let (version, rest) = match fragment_shader_src.find('\n') {
+        Some(index) => fragment_shader_src.split_at(index + 1),
+        None => (fragment_shader_src, ""),
+    };
+    format!("{}{}\n{}", version, clip_src, rest)
+}
+
 /// Represents the conversion from some entity to OpenGL vertex data.
 pub trait ToVertices {
     fn to_vertices(&self, viewport: LayoutViewportDimensions, scale_factor: f32) -> Vec<f32>;
@@ -146,6 +346,46 @@ impl ToVertices for RGBA {
     }
 }
 
+impl ToVertices for BackgroundLayerCommand {
+    /// Two triangles for the part of each tile that's inside the clip, with the coordinates of each
+    /// vertex within its tile.  Those are texture coordinates for images, and CSS pixels from the
+    /// top-left corner of the tile for gradients.
+    fn to_vertices(&self, viewport: LayoutViewportDimensions, scale_factor: f32) -> Vec<f32> {
+        let (viewport_width, viewport_height) = viewport.width_height_px();
+        let (unit_x, unit_y) = match self.content {
+            LayerContent::Image(_) => (self.tile.width.px(), self.tile.height.px()),
+            LayerContent::Gradient(_) => (1., 1.),
+        };
+        let mut vertices = Vec::new();
+        for tile in self.tiles() {
+            let visible = match tile.intersection(&self.clip) {
+                Some(visible) => visible,
+                None => continue,
+            };
+            let scaled = visible.scaled_by(scale_factor);
+            let left = ndc_x(scaled.start_x, viewport_width);
+            let top = ndc_y(scaled.start_y, viewport_height);
+            let right = ndc_x((scaled.start_x + scaled.width).px(), viewport_width);
+            let bottom = ndc_y((scaled.start_y + scaled.height).px(), viewport_height);
+            let s_left = (visible.start_x - tile.start_x) / unit_x;
+            let t_top = (visible.start_y - tile.start_y) / unit_y;
+            let s_right = s_left + visible.width.px() / unit_x;
+            let t_bottom = t_top + visible.height.px() / unit_y;
+            for vertex in &[
+                [left, top, s_left, t_top],
+                [left, bottom, s_left, t_bottom],
+                [right, bottom, s_right, t_bottom],
+                [left, top, s_left, t_top],
+                [right, bottom, s_right, t_bottom],
+                [right, top, s_right, t_top],
+            ] {
+                vertices.extend_from_slice(vertex);
+            }
+        }
+        vertices
+    }
+}
+
 pub fn build_program(
     vertex_shader_src: &CString,
     fragment_shader_src: &CString,
@@ -1,5 +1,5 @@
 use crate::gfx::ndc::{ndc_x, ndc_y};
-use crate::gfx::paint::{build_program, ToVertices};
+use crate::gfx::paint::{build_program, with_clip_functions, Clips, ToVertices};
 use crate::layout::rect::Rect;
 use crate::layout::LayoutViewportDimensions;
 use cssparser::RGBA;
@@ -8,9 +8,11 @@ use gl::program::Program;
 use gl::types::{GLint, GLvoid};
 use gl::vao::VertexArrayObject;
 use gl::Gl;
+use pathfinder_geometry::vector::Vector2F;
 use std::ffi::CString;
 
-/// Uses given OpenGL context handle to paint arbitrary rectangles.
+/// Uses given OpenGL context handle to paint arbitrary rectangles, and other polygons filled with a
+/// solid color.
 pub struct RectPainter {
     /// The OpenGL program that will be used to paint rectangles.
     program: Program,
@@ -55,11 +57,12 @@ impl RectPainter {
         })
     }
 
-    pub fn paint(&mut self, vertices: &[f32]) {
+    pub fn paint(&mut self, vertices: &[f32], clips: &Clips) {
         // Panic rather than truncate data.
         assert!(vertices.len() <= i32::max_value() as usize);
 
         self.program.use_globally();
+        clips.set_uniforms(&self.gl, &self.program);
         self.vao.store_vertex_data(&vertices[..]);
         unsafe {
             self.gl.BindVertexArray(self.vao.name());
@@ -81,10 +84,10 @@ fn build_triangle_program(gl: &Gl) -> Result<Program, String> {
     )))
     .expect("could not create cstring for triangle program");
 
-    let frag_shader_src = &CString::new(include_str!(concat!(
+    let frag_shader_src = &CString::new(with_clip_functions(include_str!(concat!(
         env!("CARGO_MANIFEST_DIR"),
         "/shader_src/triangle.frag"
-    )))
+    ))))
     .expect("could not create cstring for triangle program");
 
     build_program(vertex_shader_src, frag_shader_src, gl)
@@ -143,3 +146,29 @@ impl ToVertices for (&Rect, &RGBA) {
         vertex_data
     }
 }
+
+impl ToVertices for (&RGBA, &[Vector2F]) {
+    /// A fan of triangles covering the convex polygon with the given points.
+    fn to_vertices(&self, viewport: LayoutViewportDimensions, scale_factor: f32) -> Vec<f32> {
+        let (rgba, points) = *self;
+        let colors = rgba.to_vertices(viewport, scale_factor);
+        let (viewport_width, viewport_height) = viewport.width_height_px();
+        let vertex = |point: Vector2F| {
+            [
+                ndc_x(point.x() * scale_factor, viewport_width),
+                ndc_y(point.y() * scale_factor, viewport_height),
+                // TODO: Implement z-indexing.
+                0.0,
+            ]
+        };
+
+        let mut vertex_data = Vec::new();
+        for index in 1..points.len().saturating_sub(1) {
+            for &point in &[points[0], points[index], points[index + 1]] {
+                vertex_data.extend_from_slice(&vertex(point));
+                vertex_data.extend_from_slice(&colors);
+            }
+        }
+        vertex_data
+    }
+}
@@ -0,0 +1,170 @@
+use crate::gfx::display::RoundedRectCommand;
+use crate::gfx::ndc::{ndc_x, ndc_y};
+use crate::gfx::paint::{
+    build_program, rounded_rect_uniforms, with_clip_functions, Clips, ToVertices,
+};
+use crate::layout::rect::RoundedRect;
+use crate::layout::LayoutViewportDimensions;
+use cssparser::RGBA;
+use gl::buffer::vbo::VertexBufferObject;
+use gl::program::Program;
+use gl::types::GLint;
+use gl::vao::VertexArrayObject;
+use gl::{Gl, FALSE, FLOAT, TRIANGLES};
+use std::ffi::CString;
+
+/// Data necessary to paint a rounded rect with OpenGL.
+#[derive(Clone, Debug)]
+pub struct RoundedRectPaintData {
+    pub color: RGBA,
+    /// The rounded rect to fill, in device pixels.
+    pub outer: RoundedRect,
+    /// The rounded rect to leave unpainted inside of `outer`, in device pixels.
+    pub inner: Option<RoundedRect>,
+    pub vertices: Vec<f32>,
+}
+
+impl RoundedRectPaintData {
+    pub fn new(command: &RoundedRectCommand, scale_factor: f32, vertices: Vec<f32>) -> Self {
+        RoundedRectPaintData {
+            color: command.color,
+            outer: command.outer.scaled_by(scale_factor),
+            inner: command.inner.map(|inner| inner.scaled_by(scale_factor)),
+            vertices,
+        }
+    }
+}
+
+/// Uses given OpenGL context handle to paint rounded rects, and the rings between two of them,
+/// computing how much of each fragment they cover from their signed distance.
+pub struct RoundedRectPainter {
+    /// An instance of OpenGL.
+    gl: Gl,
+    /// The OpenGL program that will be used to paint rounded rects.
+    program: Program,
+    /// The VAO to use to paint rounded rects.
+    vao: VertexArrayObject,
+}
+
+impl RoundedRectPainter {
+    pub fn new(gl: &Gl) -> Result<RoundedRectPainter, String> {
+        let vbo = VertexBufferObject::new(gl);
+        let config_vao = |gl: &Gl| {
+            unsafe {
+                // Enable use of `layout (location=0)` data in our vertex shader.
+                gl.EnableVertexAttribArray(0);
+                // Each vertex is only a position, of two floats.
+                gl.VertexAttribPointer(
+                    0,
+                    2,
+                    FLOAT,
+                    FALSE,
+                    (2 * std::mem::size_of::<f32>()) as GLint,
+                    std::ptr::null(),
+                );
+            }
+        };
+        let vao = unsafe { VertexArrayObject::new(vbo, config_vao, gl) };
+
+        Ok(RoundedRectPainter {
+            gl: gl.clone(),
+            program: build_rounded_rect_program(gl)?,
+            vao,
+        })
+    }
+
+    pub fn paint(&mut self, paintable_rounded_rects: &[RoundedRectPaintData], clips: &Clips) {
+        self.program.use_globally();
+        clips.set_uniforms(&self.gl, &self.program);
+        unsafe {
+            self.gl.BindVertexArray(self.vao.name());
+        }
+        for rounded_rect in paintable_rounded_rects {
+            // Panic rather than truncate data.
+            assert!(rounded_rect.vertices.len() <= i32::max_value() as usize);
+
+            self.set_uniforms(rounded_rect);
+            unsafe {
+                self.vao.store_vertex_data(&rounded_rect.vertices);
+                // Each vertex is two floats.  Casting the `usize` to `GLint` will not truncate due
+                // to the above assert!().
+                self.gl
+                    .DrawArrays(TRIANGLES, 0, (rounded_rect.vertices.len() / 2) as i32);
+            }
+        }
+        unsafe {
+            self.gl.BindVertexArray(0);
+        }
+    }
+
+    fn set_uniforms(&self, rounded_rect: &RoundedRectPaintData) {
+        let color = rounded_rect.color;
+        let (outer_rect, outer_radii_x, outer_radii_y) = rounded_rect_uniforms(rounded_rect.outer);
+        let (inner_rect, inner_radii_x, inner_radii_y) =
+            rounded_rect_uniforms(rounded_rect.inner.unwrap_or_default());
+        unsafe {
+            self.gl.Uniform4f(
+                self.uniform_location("color"),
+                color.red_f32(),
+                color.green_f32(),
+                color.blue_f32(),
+                color.alpha_f32(),
+            );
+            for &(name, values) in &[
+                ("outerRect", outer_rect),
+                ("outerRadiiX", outer_radii_x),
+                ("outerRadiiY", outer_radii_y),
+                ("innerRect", inner_rect),
+                ("innerRadiiX", inner_radii_x),
+                ("innerRadiiY", inner_radii_y),
+            ] {
+                self.gl
+                    .Uniform4fv(self.uniform_location(name), 1, values.as_ptr());
+            }
+            self.gl.Uniform1i(
+                self.uniform_location("hasInner"),
+                rounded_rect.inner.is_some() as GLint,
+            );
+        }
+    }
+
+    fn uniform_location(&self, name: &str) -> GLint {
+        let name = CString::new(name).expect("couldn't create uniform name cstring");
+        unsafe { self.gl.GetUniformLocation(self.program.id(), name.as_ptr()) }
+    }
+}
+
+fn build_rounded_rect_program(gl: &Gl) -> Result<Program, String> {
+    let vertex_shader_src = &CString::new(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/shader_src/rounded_rect.vert"
+    )))
+    .expect("could not create cstring for rounded rect program");
+
+    let frag_shader_src = &CString::new(with_clip_functions(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/shader_src/rounded_rect.frag"
+    ))))
+    .expect("could not create cstring for rounded rect program");
+
+    build_program(vertex_shader_src, frag_shader_src, gl)
+}
+
+impl ToVertices for RoundedRectCommand {
+    /// A fan of triangles covering the convex polygon of the command's area, which the fragment
+    /// shader cuts down to the rounded rects.
+    fn to_vertices(&self, viewport: LayoutViewportDimensions, scale_factor: f32) -> Vec<f32> {
+        let (viewport_width, viewport_height) = viewport.width_height_px();
+        let points = &self.area;
+        let mut vertices = Vec::new();
+        for index in 1..points.len().saturating_sub(1) {
+            for &point in &[points[0], points[index], points[index + 1]] {
+                vertices.extend_from_slice(&[
+                    ndc_x(point.x() * scale_factor, viewport_width),
+                    ndc_y(point.y() * scale_factor, viewport_height),
+                ]);
+            }
+        }
+        vertices
+    }
+}
@@ -1,12 +1,14 @@
 use crate::gfx::display::CharCommand;
 use crate::gfx::ndc::{ndc_x, ndc_y};
-use crate::gfx::paint::{build_program, CharPaintData, ToVertices};
+use crate::gfx::paint::{build_program, with_clip_functions, CharPaintData, Clips, ToVertices};
+use crate::gfx::shape::GlyphOrientation;
 use crate::layout::LayoutViewportDimensions;
 use gl::buffer::vbo::VertexBufferObject;
 use gl::program::Program;
 use gl::types::{GLint, GLsizeiptr};
 use gl::vao::VertexArrayObject;
 use gl::{Gl, ARRAY_BUFFER, DYNAMIC_DRAW, FALSE, FLOAT, TEXTURE0, TEXTURE_2D, TRIANGLES};
+use pathfinder_geometry::vector::Vector2F;
 use std::ffi::CString;
 
 /// Uses given OpenGL context handle to paint arbitrary text.
@@ -57,8 +59,9 @@ impl TextPainter {
         })
     }
 
-    pub fn paint(&mut self, paintable_chars: &[CharPaintData]) {
+    pub fn paint(&mut self, paintable_chars: &[CharPaintData], clips: &Clips) {
         self.program.use_globally();
+        clips.set_uniforms(&self.gl, &self.program);
 
         let text_color_str =
             CString::new("textColor").expect("couldn't create `textColor` cstring");
@@ -82,11 +85,6 @@ impl TextPainter {
                 self.vao.store_vertex_data(&ch.vertices);
                 // Casting the `usize` to `GLint` will not truncate due to the above assert!().
                 self.gl.DrawArrays(TRIANGLES, 0, ch.vertices.len() as i32);
-                // TODO:  We need to increment the `x_pos` (which is calculated somewhere else) with the advance of this character.
-                // This should probably happen somewhere else (layout?  display command processing?)
-                // Here is some C++ code that does this work:
-                //     now advance cursors for next glyph (note that advance is number of 1/64 pixels)
-                //     x += (ch.Advance >> 6) * scale; // bitshift by 6 to get value in pixels (2^6 = 64)
             }
         }
 
@@ -104,64 +102,54 @@ fn build_text_program(gl: &Gl) -> Result<Program, String> {
     )))
     .expect("could not create cstring for text program");
 
-    let frag_shader_src = &CString::new(include_str!(concat!(
+    let frag_shader_src = &CString::new(with_clip_functions(include_str!(concat!(
         env!("CARGO_MANIFEST_DIR"),
         "/shader_src/text.frag"
-    )))
+    ))))
     .expect("could not create cstring for text program");
 
     build_program(vertex_shader_src, frag_shader_src, gl)
 }
 
 impl ToVertices for CharCommand {
-    fn to_vertices(&self, viewport: LayoutViewportDimensions, _scale_factor: f32) -> Vec<f32> {
-        // TODO: Use scale_factor
-        let x_pos = self.start_coords().x() + self.bearing().x();
-        let y_pos = self.start_coords().y() - (self.size().y() - self.bearing().y());
+    fn to_vertices(&self, viewport: LayoutViewportDimensions, scale_factor: f32) -> Vec<f32> {
+        // The glyph origin comes from layout and is in CSS pixels, while the glyph was rasterized
+        // (and its bearing and size measured) in device pixels.
+        let origin = self.start_coords() * scale_factor;
+        let top_left = self.bearing();
+        let bottom_right = top_left + self.size();
+        // The corners of the glyph relative to its origin, before rotating it into place, along
+        // with their texture coordinates.  The first row of the glyph texture is the top of the
+        // glyph, so the texture's `t` axis points down.
+        let corners = [
+            (top_left, [0.0, 0.0]),
+            (Vector2F::new(top_left.x(), bottom_right.y()), [0.0, 1.0]),
+            (bottom_right, [1.0, 1.0]),
+            (Vector2F::new(bottom_right.x(), top_left.y()), [1.0, 0.0]),
+        ];
         let (viewport_width, viewport_height) = viewport.width_height_px();
+        let vertex = |index: usize| {
+            let (corner, texture_coords) = corners[index];
+            let corner = match self.orientation() {
+                GlyphOrientation::Horizontal | GlyphOrientation::Upright => corner,
+                // A quarter turn clockwise, given that y points down.
+                GlyphOrientation::SidewaysRight => Vector2F::new(-corner.y(), corner.x()),
+                GlyphOrientation::SidewaysLeft => Vector2F::new(corner.y(), -corner.x()),
+            };
+            let position = origin + corner;
+            [
+                ndc_x(position.x(), viewport_width),
+                ndc_y(position.y(), viewport_height),
+                texture_coords[0],
+                texture_coords[1],
+            ]
+        };
 
-        // Transpose the quad width and height values (which are the second half of each vertex)
-        // relative to what https://learnopengl.com/In-Practice/Text-Rendering has because of the
-        // way we flip `ndc_y` values to render them at the top of the screen.
+        // Each vertex is an NDC position followed by a texture coordinate.
         let mut vertices = Vec::new();
-        vertices.extend_from_slice(&[
-            ndc_x(x_pos, viewport_width),
-            ndc_y(y_pos + self.size().y(), viewport_height),
-            1.0,
-            1.0,
-        ]);
-        vertices.extend_from_slice(&[
-            ndc_x(x_pos, viewport_width),
-            ndc_y(y_pos, viewport_height),
-            1.0,
-            0.0,
-        ]);
-        vertices.extend_from_slice(&[
-            ndc_x(x_pos + self.size().x(), viewport_width),
-            ndc_y(y_pos, viewport_height),
-            0.0,
-            0.0,
-        ]);
-
-        vertices.extend_from_slice(&[
-            ndc_x(x_pos, viewport_width),
-            ndc_y(y_pos + self.size().y(), viewport_height),
-            1.0,
-            1.0,
-        ]);
-        vertices.extend_from_slice(&[
-            ndc_x(x_pos + self.size().x(), viewport_width),
-            ndc_y(y_pos, viewport_height),
-            0.0,
-            0.0,
-        ]);
-        vertices.extend_from_slice(&[
-            ndc_x(x_pos + self.size().x(), viewport_width),
-            ndc_y(y_pos + self.size().y(), viewport_height),
-            0.0,
-            1.0,
-        ]);
-
+        for &index in &[0, 1, 2, 0, 2, 3] {
+            vertices.extend_from_slice(&vertex(index));
+        }
         vertices
     }
 }
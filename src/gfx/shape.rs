@@ -0,0 +1,363 @@
+use crate::gfx::font::FontId;
+use crate::style::values::computed::length::CSSPixelLength;
+use crate::style::values::computed::Direction;
+use crate::style::values::CSSFloat;
+use font_kit::handle::Handle;
+use font_kit::loaders::default::Font;
+use pathfinder_geometry::vector::Vector2F;
+use rustybuzz::{Face, UnicodeBuffer};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A glyph positioned by the shaper.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapedGlyph {
+    /// The id of the glyph within the font it was shaped with.
+    pub glyph_id: u32,
+    /// The byte offset into the shaped text of the first character this glyph was shaped from.
+    /// Glyphs sharing a cluster, such as the glyphs of a base character and its combining marks,
+    /// can't be separated from each other, and neither can the characters of a cluster (e.g. those
+    /// forming a ligature).
+    pub cluster: usize,
+    /// How far to move the pen in the inline direction after drawing this glyph, in CSS pixels.
+    pub advance: CSSFloat,
+    /// How far to move this glyph from the pen position, in CSS pixels.  Positive values move the
+    /// glyph right and up, as in the font itself.
+    pub offset: Vector2F,
+}
+
+/// How glyphs are set relative to the line they're on.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#glyph-orientation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GlyphOrientation {
+    /// Upright glyphs, along a horizontal line.
+    Horizontal,
+    /// Upright glyphs, stacked from top to bottom along a vertical line.  Glyphs are centered on
+    /// the line, and advance by their vertical advance.
+    Upright,
+    /// Glyphs rotated a quarter turn clockwise, running from top to bottom along a vertical line.
+    SidewaysRight,
+    /// Glyphs rotated a quarter turn counter-clockwise, running from bottom to top along a vertical
+    /// line.
+    SidewaysLeft,
+}
+
+/// A run of text that has been shaped into positioned glyphs, all set in a single font at a single
+/// size and in a single orientation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphRun {
+    text: String,
+    glyphs: Vec<ShapedGlyph>,
+    /// The font the glyphs belong to, or `None` if no font could be loaded, in which case there
+    /// are no glyphs.
+    font: Option<FontId>,
+    font_size: CSSPixelLength,
+    orientation: GlyphOrientation,
+}
+
+impl GlyphRun {
+    pub fn new(
+        text: String,
+        glyphs: Vec<ShapedGlyph>,
+        font: Option<FontId>,
+        font_size: CSSPixelLength,
+        orientation: GlyphOrientation,
+    ) -> GlyphRun {
+        GlyphRun {
+            text,
+            glyphs,
+            font,
+            font_size,
+            orientation,
+        }
+    }
+
+    /// The text this run was shaped from.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The glyphs of this run, in the order they should be drawn.
+    pub fn glyphs(&self) -> &[ShapedGlyph] {
+        &self.glyphs
+    }
+
+    pub fn font(&self) -> Option<FontId> {
+        self.font
+    }
+
+    pub fn font_size(&self) -> CSSPixelLength {
+        self.font_size
+    }
+
+    pub fn orientation(&self) -> GlyphOrientation {
+        self.orientation
+    }
+
+    /// The total advance of this run in the inline direction.
+    pub fn advance(&self) -> CSSPixelLength {
+        self.advance_of(0..self.text.len())
+    }
+
+    /// The total advance of the glyphs shaped from the text in the byte range `range`.
+    pub fn advance_of(&self, range: Range<usize>) -> CSSPixelLength {
+        CSSPixelLength::new(
+            self.glyphs
+                .iter()
+                .filter(|glyph| range.contains(&glyph.cluster))
+                .map(|glyph| glyph.advance)
+                .sum(),
+        )
+    }
+
+    /// Returns the part of this run shaped from the text in the byte range `range`.  Both ends of
+    /// `range` should be cluster boundaries, such as the positions of soft wrap opportunities.
+    pub fn slice(&self, range: Range<usize>) -> GlyphRun {
+        GlyphRun {
+            text: self.text[range.clone()].to_owned(),
+            glyphs: self
+                .glyphs
+                .iter()
+                .filter(|glyph| range.contains(&glyph.cluster))
+                .map(|glyph| ShapedGlyph {
+                    cluster: glyph.cluster - range.start,
+                    ..*glyph
+                })
+                .collect(),
+            font: self.font,
+            font_size: self.font_size,
+            orientation: self.orientation,
+        }
+    }
+
+    /// Appends the text and glyphs of `other` to this run.  Both runs should be set in the same
+    /// font and orientation.
+    pub fn append(&mut self, other: GlyphRun) {
+        debug_assert_eq!(self.font, other.font);
+        debug_assert_eq!(self.orientation, other.orientation);
+        let cluster_offset = self.text.len();
+        self.text.push_str(&other.text);
+        self.glyphs
+            .extend(other.glyphs.into_iter().map(|glyph| ShapedGlyph {
+                cluster: glyph.cluster + cluster_offset,
+                ..glyph
+            }));
+    }
+}
+
+/// A font parsed by the shaper.  Parsing a font is too slow to repeat for every run of text shaped
+/// with it, so this is done once per font and kept by the `FontHandle`.
+pub struct ShapingFace {
+    /// Borrows from `_data`, which is why it's declared first, to be dropped before the data.
+    face: Face<'static>,
+    _data: Arc<Vec<u8>>,
+}
+
+impl ShapingFace {
+    /// Parses `font` for shaping, or returns `None` if the shaper can't read it.
+    pub fn new(font: &Font) -> Option<ShapingFace> {
+        let font_index = match font.handle() {
+            Some(Handle::Path { font_index, .. }) | Some(Handle::Memory { font_index, .. }) => {
+                font_index
+            }
+            None => 0,
+        };
+        let data = font.copy_font_data()?;
+        // The face only borrows the bytes behind the `Arc`, which are never changed or moved, and
+        // are kept alive alongside the face for as long as it is.
+        let bytes: &'static [u8] = unsafe { &*(data.as_slice() as *const [u8]) };
+        let face = Face::from_slice(bytes, font_index)?;
+        Some(ShapingFace { face, _data: data })
+    }
+
+    fn face(&self) -> &Face<'_> {
+        &self.face
+    }
+}
+
+/// Shapes `text` into a run of glyphs set in `font`, loaded as `font_id`, at `font_size`.  Shaping
+/// applies the font's ligatures, kerning, and mark positioning, and is required to correctly
+/// display complex scripts.
+///
+/// `direction` is the direction `text` is laid out in, normally that of its bidi embedding level.
+/// Glyphs are always returned in left-to-right order (top-to-bottom for upright glyphs), so the
+/// glyphs of rtl text start with its last character.  When `direction` is `None`, it's guessed from
+/// the script of `text`.  Upright glyphs are shaped with the font's vertical metrics, while all
+/// other orientations are shaped horizontally and rotated when painted.
+///
+/// `shaping_face` is `font` parsed for shaping.  If the shaper couldn't read the font, each
+/// character is simply mapped to the font's glyph for it instead.
+pub fn shape_text(
+    font: &Font,
+    font_id: FontId,
+    shaping_face: Option<&ShapingFace>,
+    text: &str,
+    font_size: CSSPixelLength,
+    direction: Option<Direction>,
+    orientation: GlyphOrientation,
+) -> GlyphRun {
+    let face = match shaping_face {
+        Some(shaping_face) => shaping_face.face(),
+        None => return map_chars_to_glyphs(font, font_id, text, font_size, direction, orientation),
+    };
+
+    let scale = font_size.px() / font.metrics().units_per_em as CSSFloat;
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    match (orientation, direction) {
+        (GlyphOrientation::Upright, _) => buffer.set_direction(rustybuzz::Direction::TopToBottom),
+        (_, Some(Direction::Ltr)) => buffer.set_direction(rustybuzz::Direction::LeftToRight),
+        (_, Some(Direction::Rtl)) => buffer.set_direction(rustybuzz::Direction::RightToLeft),
+        (_, None) => {}
+    }
+    // Only fills in the segment properties that haven't been set.
+    buffer.guess_segment_properties();
+    let glyph_buffer = rustybuzz::shape(face, &[], buffer);
+    let glyphs = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .zip(glyph_buffer.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            glyph_id: info.glyph_id,
+            cluster: info.cluster as usize,
+            // Vertical advances point down, which is negative in font units.
+            advance: match orientation {
+                GlyphOrientation::Upright => -position.y_advance as CSSFloat * scale,
+                _ => position.x_advance as CSSFloat * scale,
+            },
+            offset: Vector2F::new(
+                position.x_offset as CSSFloat * scale,
+                position.y_offset as CSSFloat * scale,
+            ),
+        })
+        .collect();
+    GlyphRun::new(
+        text.to_owned(),
+        glyphs,
+        Some(font_id),
+        font_size,
+        orientation,
+    )
+}
+
+/// Maps each character of `text` to its glyph in `font`, without any shaping.  Characters `font`
+/// has no glyph for are skipped.
+fn map_chars_to_glyphs(
+    font: &Font,
+    font_id: FontId,
+    text: &str,
+    font_size: CSSPixelLength,
+    direction: Option<Direction>,
+    orientation: GlyphOrientation,
+) -> GlyphRun {
+    let metrics = font.metrics();
+    let scale = font_size.px() / metrics.units_per_em as CSSFloat;
+    let mut glyphs: Vec<ShapedGlyph> = text
+        .char_indices()
+        .filter_map(|(index, ch)| font.glyph_for_char(ch).map(|glyph_id| (index, glyph_id)))
+        .map(|(index, glyph_id)| {
+            let horizontal_advance = font
+                .advance(glyph_id)
+                .map_or(0., |advance| advance.x() * scale);
+            match orientation {
+                // Without vertical metrics, stack glyphs one em box (ascent plus descent) apart,
+                // centered horizontally, with the top of the em box at the pen.
+                GlyphOrientation::Upright => ShapedGlyph {
+                    glyph_id,
+                    cluster: index,
+                    advance: (metrics.ascent - metrics.descent) * scale,
+                    offset: Vector2F::new(-horizontal_advance / 2., -metrics.ascent * scale),
+                },
+                _ => ShapedGlyph {
+                    glyph_id,
+                    cluster: index,
+                    advance: horizontal_advance,
+                    offset: Vector2F::zero(),
+                },
+            }
+        })
+        .collect();
+    if direction == Some(Direction::Rtl) && orientation != GlyphOrientation::Upright {
+        glyphs.reverse();
+    }
+    GlyphRun::new(
+        text.to_owned(),
+        glyphs,
+        Some(font_id),
+        font_size,
+        orientation,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A run in which every byte of `text` was shaped into its own one pixel wide glyph.
+    fn run(text: &str) -> GlyphRun {
+        let glyphs = (0..text.len())
+            .map(|index| ShapedGlyph {
+                glyph_id: index as u32,
+                cluster: index,
+                advance: 1.,
+                offset: Vector2F::zero(),
+            })
+            .collect();
+        GlyphRun::new(
+            text.to_owned(),
+            glyphs,
+            None,
+            CSSPixelLength::new(16.),
+            GlyphOrientation::Horizontal,
+        )
+    }
+
+    #[test]
+    fn slices_by_cluster() {
+        let hello_world = run("hello world");
+        let world = hello_world.slice(6..11);
+        assert_eq!(world.text(), "world");
+        assert_eq!(world.glyphs().len(), 5);
+        assert_eq!(world.glyphs()[0].cluster, 0);
+        assert_eq!(world.glyphs()[0].glyph_id, 6);
+        assert_eq!(hello_world.advance_of(5..6), CSSPixelLength::new(1.));
+    }
+
+    #[test]
+    fn append_offsets_clusters() {
+        let mut hello = run("hello ");
+        hello.append(run("world"));
+        assert_eq!(hello.text(), "hello world");
+        assert_eq!(hello.advance(), CSSPixelLength::new(11.));
+        assert_eq!(hello.glyphs()[6].cluster, 6);
+    }
+
+    #[test]
+    fn ligature_glyphs_stay_together() {
+        // "fi" shaped into a single ligature glyph belonging to the cluster starting at "f".
+        let glyphs = vec![
+            ShapedGlyph {
+                glyph_id: 1,
+                cluster: 0,
+                advance: 2.,
+                offset: Vector2F::zero(),
+            },
+            ShapedGlyph {
+                glyph_id: 2,
+                cluster: 2,
+                advance: 1.,
+                offset: Vector2F::zero(),
+            },
+        ];
+        let run = GlyphRun::new(
+            "fi ".to_owned(),
+            glyphs,
+            None,
+            CSSPixelLength::new(16.),
+            GlyphOrientation::Horizontal,
+        );
+        assert_eq!(run.slice(0..2).advance(), CSSPixelLength::new(2.));
+        assert_eq!(run.slice(2..3).glyphs().len(), 1);
+    }
+}
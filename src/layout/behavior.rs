@@ -36,6 +36,9 @@ macro_rules! apply_page_relative_properties_base_box_passthrough_impls {
 /// children at all (e.g. for text runs).
 #[enum_dispatch(LayoutBox, BlockLevelBox, InlineLevelBox, InlineLevelContent)]
 pub trait BaseLayoutBoxBehavior {
+    /// Forgets the cached intrinsic sizes of this box, e.g. because its subtree or computed values
+    /// changed.
+    fn clear_intrinsic_sizes(&self);
     fn computed_values(&self) -> Ref<ComputedValues>;
     fn dimensions(&self) -> Dimensions;
     fn dimensions_mut(&mut self) -> &mut Dimensions;
@@ -47,6 +50,11 @@ pub trait BaseLayoutBoxBehavior {
 #[macro_export]
 macro_rules! layout_box_behavior_base_box_passthrough_impls {
     () => {
+        #[inline(always)]
+        fn clear_intrinsic_sizes(&self) {
+            self.base.clear_intrinsic_sizes()
+        }
+
         #[inline(always)]
         fn computed_values(&self) -> Ref<ComputedValues> {
             self.base.computed_values()
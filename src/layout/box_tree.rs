@@ -1,11 +1,13 @@
-use crate::dom::tree::{NodeData, NodeRef};
+use crate::dom::tree::{DirtyFlags, NodeData, NodeRef};
 use crate::layout::behavior::BaseLayoutBoxBehavior;
 use crate::layout::flow::block::{AnonymousBlockBox, BlockLevelBox};
-use crate::layout::flow::inline::{InlineBox, TextRun};
+use crate::layout::flow::inline::{InlineBox, InlineLevelContent, TextRun};
+use crate::layout::flow::white_space::{collapse_white_space, is_collapsible_white_space};
 use crate::layout::formatting_context::{
     FormattingContext, FormattingContextRef, QualifiedFormattingContext,
 };
 use crate::layout::layout_box::LayoutBox;
+use crate::layout::replaced::{image_fallback_text, replaced_image, ReplacedBox};
 use crate::style::values::computed::display::{DisplayBox, InnerDisplay, OuterDisplay};
 use crate::style::values::computed::Display;
 
@@ -49,7 +51,7 @@ pub fn build_box_tree(
     let mut layout_box = if let NodeData::Text(text) = node.data() {
         // https://drafts.csswg.org/css-display-3/#flow-layout
         // > If the [text] sequence contains no text, however, it does not generate a text run.
-        let contents = text.clone().take().trim().to_owned();
+        let contents = text.clone().take();
         if contents.is_empty() {
             return None;
         }
@@ -62,15 +64,42 @@ pub fn build_box_tree(
             None => return None,
         }
     };
+    if matches!(
+        layout_box,
+        LayoutBox::BlockLevel(BlockLevelBox::Replaced(_))
+            | LayoutBox::InlineLevel(InlineLevelContent::Replaced(_))
+    ) {
+        // The content of replaced elements is their image, not boxes generated by their children.
+        return Some(layout_box);
+    }
+    // An image that isn't replaced by its image is rendered as its alt text.
+    if let Some(alt) = image_fallback_text(&node) {
+        let inline_container = get_or_create_inline_container(&mut layout_box, node.clone());
+        inline_container.add_child(
+            TextRun::new(node.clone(), inline_container.formatting_context(), alt).into(),
+        );
+    }
 
     for child in node.children() {
         if let NodeData::Text(text) = child.data() {
             // https://drafts.csswg.org/css-display-3/#flow-layout
             // > If the [text] sequence contains no text, however, it does not generate a text run.
-            let contents = text.clone().take().trim().to_owned();
+            let contents = text.clone().take();
             if contents.is_empty() {
                 continue;
             }
+            // https://www.w3.org/TR/CSS2/visuren.html#anonymous
+            // > White space content that would subsequently be collapsed away according to the
+            // > 'white-space' property does not generate any anonymous inline boxes.
+            //
+            // White space that would start a new inline formatting context is always collapsed
+            // away, since it either sits between block-level boxes or at the start of a line.
+            let white_space = child.computed_values().get_inherited_text().white_space;
+            if layout_box.get_mut_inline_container().is_none()
+                && is_collapsible_white_space(&contents, white_space)
+            {
+                continue;
+            }
 
             // Get (or create, if necessary) an inline container for this new text-run.
             let inline_container = get_or_create_inline_container(&mut layout_box, child.clone());
@@ -78,7 +107,7 @@ pub fn build_box_tree(
                 TextRun::new(
                     child.clone(),
                     inline_container.formatting_context(),
-                    text.clone().take().trim().to_owned(),
+                    contents,
                 )
                 .into(),
             );
@@ -86,12 +115,126 @@ pub fn build_box_tree(
         }
         handle_child_node_by_display(&mut layout_box, child);
     }
+    // Every inline formatting context directly inside this box is now complete, so the white
+    // space inside it can be processed.
+    if let LayoutBox::BlockLevel(BlockLevelBox::BlockContainer(block_container)) = &mut layout_box {
+        for child in block_container.children_mut() {
+            if let LayoutBox::BlockLevel(BlockLevelBox::AnonymousBlock(anonymous_block)) = child {
+                for root_inline_box in anonymous_block.children_mut() {
+                    if let LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(ilb)) =
+                        root_inline_box
+                    {
+                        collapse_white_space(ilb.children_mut());
+                    }
+                }
+            }
+        }
+    }
     Some(layout_box)
 }
 
+/// Brings `box_tree`, the box tree of `dom`, up to date with the nodes of `dom` whose boxes are
+/// dirty.  Rather than building the whole box tree again, only the boxes of the nearest block-level
+/// ancestor of each such node are rebuilt, since any anonymous boxes and white space processing
+/// affected by the node are contained within them.  Returns whether any box changed, meaning the
+/// box tree must be laid out again.
+pub fn update_box_tree(dom: &NodeRef, box_tree: &mut Option<LayoutBox>) -> bool {
+    let mut dirty_nodes = Vec::new();
+    collect_dirty_box_nodes(dom, &mut dirty_nodes);
+    if dirty_nodes.is_empty() {
+        return false;
+    }
+    let mut rebuild_roots = Vec::new();
+    let mut rebuild_everything = false;
+    for node in &dirty_nodes {
+        if node.dirty_flags().contains(DirtyFlags::BOX_TREE) {
+            match rebuild_root(node) {
+                Some(root) => rebuild_roots.push(root),
+                None => rebuild_everything = true,
+            }
+        }
+    }
+    if rebuild_everything {
+        *box_tree = build_box_tree(dom.clone(), None);
+    } else if let Some(layout_box) = box_tree {
+        // Boxes rebuilt as part of an ancestor's boxes don't need rebuilding on their own.
+        let outermost_roots = rebuild_roots
+            .iter()
+            .filter(|root| {
+                !root
+                    .ancestors()
+                    .any(|ancestor| rebuild_roots.contains(&ancestor))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        update_boxes(layout_box, &outermost_roots);
+    }
+    for node in dirty_nodes {
+        node.remove_dirty_flags(DirtyFlags::BOX_TREE | DirtyFlags::LAYOUT);
+    }
+    true
+}
+
+/// Adds the inclusive descendants of `node` whose boxes are dirty to `dirty_nodes`, in tree order,
+/// only descending into nodes flagged as having such descendants.  Those flags are cleared.
+fn collect_dirty_box_nodes(node: &NodeRef, dirty_nodes: &mut Vec<NodeRef>) {
+    let flags = node.dirty_flags();
+    if flags.intersects(DirtyFlags::BOX_TREE | DirtyFlags::LAYOUT) {
+        dirty_nodes.push(node.clone());
+    }
+    if flags.contains(DirtyFlags::DESCENDANT_BOXES) {
+        node.remove_dirty_flags(DirtyFlags::DESCENDANT_BOXES);
+        for child in node.children() {
+            collect_dirty_box_nodes(&child, dirty_nodes);
+        }
+    }
+}
+
+/// Returns the node whose boxes must be rebuilt for the boxes of `node` to be rebuilt, which is
+/// its nearest block-level or `display: none` ancestor.  Returns `None` if the whole box tree must be
+/// rebuilt.
+fn rebuild_root(node: &NodeRef) -> Option<NodeRef> {
+    for ancestor in node.ancestors() {
+        if let NodeData::Document(_) = ancestor.data() {
+            return None;
+        }
+        let display = ancestor.computed_values().get_box().display;
+        match display {
+            Display::Full(full_display) if full_display.outer() == OuterDisplay::Inline => {}
+            _ => return Some(ancestor),
+        }
+    }
+    None
+}
+
+/// Rebuilds the boxes of the block containers generated by `rebuild_roots`, and clears the cached
+/// intrinsic sizes of every box whose subtree changed.  Returns whether any box below (or
+/// including) `layout_box` changed.
+fn update_boxes(layout_box: &mut LayoutBox, rebuild_roots: &[NodeRef]) -> bool {
+    if let LayoutBox::BlockLevel(BlockLevelBox::BlockContainer(_)) = layout_box {
+        let node = layout_box.node();
+        if rebuild_roots.contains(&node) {
+            if let Some(rebuilt) = build_box_tree(node, Some(layout_box.formatting_context())) {
+                *layout_box = rebuilt;
+            }
+            return true;
+        }
+    }
+    let mut changed = layout_box.node().dirty_flags().contains(DirtyFlags::LAYOUT);
+    if let Some(children) = layout_box.children_mut() {
+        for child in children {
+            changed |= update_boxes(child, rebuild_roots);
+        }
+    }
+    if changed {
+        layout_box.clear_intrinsic_sizes();
+    }
+    changed
+}
+
 fn handle_child_node_by_display(parent_box: &mut LayoutBox, child_node: NodeRef) {
     let child_computed_values = &*child_node.computed_values();
-    match child_computed_values.display {
+    match child_computed_values.get_box().display {
         Display::Full(full_display) => {
             match (full_display.outer(), full_display.inner()) {
                 (OuterDisplay::Block, InnerDisplay::Flow)
@@ -107,7 +250,10 @@ fn handle_child_node_by_display(parent_box: &mut LayoutBox, child_node: NodeRef)
                         parent_box.add_child(child_box)
                     }
                 }
-                (OuterDisplay::Inline, InnerDisplay::Flow) => {
+                // Replaced elements are atomic inlines whatever their inner display type.
+                (OuterDisplay::Inline, inner)
+                    if inner == InnerDisplay::Flow || replaced_image(&child_node).is_some() =>
+                {
                     let inline_container =
                         get_or_create_inline_container(parent_box, child_node.clone());
                     if let Some(child_box) = build_box_tree(
@@ -129,9 +275,32 @@ fn build_box_from_display(
     parent_context: Option<FormattingContextRef>,
 ) -> Option<LayoutBox> {
     let computed_values = node.computed_values();
+    if let Some(image) = replaced_image(&node) {
+        // Replaced elements generate a single box, whatever their inner display type.
+        return match computed_values.get_box().display {
+            Display::Full(full_display) => Some(match full_display.outer() {
+                OuterDisplay::Block => {
+                    LayoutBox::BlockLevel(BlockLevelBox::Replaced(ReplacedBox::new(
+                        node.clone(),
+                        parent_context.unwrap_or_else(FormattingContextRef::new_independent_block),
+                        image,
+                    )))
+                }
+                OuterDisplay::Inline => {
+                    LayoutBox::InlineLevel(InlineLevelContent::Replaced(ReplacedBox::new(
+                        node.clone(),
+                        parent_context
+                            .expect("there was no parent formatting context to add inline box to"),
+                        image,
+                    )))
+                }
+            }),
+            Display::Box(DisplayBox::None) => None,
+        };
+    }
     // Per the "Generated box" column from the table in this section, decide what boxes to generate
     // from this DOM node.  https://drafts.csswg.org/css-display/#the-display-properties
-    Some(match computed_values.display {
+    Some(match computed_values.get_box().display {
         Display::Full(full_display) => {
             match (full_display.outer(), full_display.inner()) {
                 (OuterDisplay::Block, InnerDisplay::Flow) => {
@@ -0,0 +1,196 @@
+use crate::style::values::computed::{Direction, UnicodeBidi};
+use std::ops::Range;
+use unicode_bidi::Level;
+
+const LRE: char = '\u{202A}';
+const RLE: char = '\u{202B}';
+const PDF: char = '\u{202C}';
+const LRO: char = '\u{202D}';
+const RLO: char = '\u{202E}';
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// Returns the bidi control characters to insert before and after the contents of an inline box,
+/// so that running the Unicode Bidirectional Algorithm over the text of the inline formatting
+/// context applies the box's `direction` and `unicode-bidi`.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#bidi-control-codes-injection-table
+pub fn inline_box_controls(direction: Direction, unicode_bidi: UnicodeBidi) -> (String, String) {
+    let (isolate, embed, override_) = match direction {
+        Direction::Ltr => (LRI, LRE, LRO),
+        Direction::Rtl => (RLI, RLE, RLO),
+    };
+    let (start, end): (&[char], &[char]) = match unicode_bidi {
+        UnicodeBidi::Normal => (&[], &[]),
+        UnicodeBidi::Embed => (&[embed], &[PDF]),
+        UnicodeBidi::Isolate => (&[isolate], &[PDI]),
+        UnicodeBidi::BidiOverride => (&[override_], &[PDF]),
+        UnicodeBidi::IsolateOverride => (&[isolate, override_], &[PDF, PDI]),
+        UnicodeBidi::Plaintext => (&[FSI], &[PDI]),
+    };
+    (start.iter().collect(), end.iter().collect())
+}
+
+/// Returns the bidi control characters to insert around the inline content of a block container.
+/// Besides the paragraph embedding level, the only thing `unicode-bidi` on a block container
+/// affects is whether the directionality of its content is overridden.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#block-unicode-bidi
+pub fn block_container_controls(
+    direction: Direction,
+    unicode_bidi: UnicodeBidi,
+) -> (String, String) {
+    match unicode_bidi {
+        UnicodeBidi::BidiOverride | UnicodeBidi::IsolateOverride => {
+            inline_box_controls(direction, UnicodeBidi::BidiOverride)
+        }
+        _ => (String::new(), String::new()),
+    }
+}
+
+/// The paragraph embedding level of the inline content of a block container, or `None` if it
+/// should be determined from the content itself, per rules P2 and P3 of the Unicode Bidirectional
+/// Algorithm.
+pub fn paragraph_level(direction: Direction, unicode_bidi: UnicodeBidi) -> Option<Level> {
+    match (unicode_bidi, direction) {
+        (UnicodeBidi::Plaintext, _) => None,
+        (_, Direction::Ltr) => Some(Level::ltr()),
+        (_, Direction::Rtl) => Some(Level::rtl()),
+    }
+}
+
+/// The embedding level established by an inline box inside content at `parent_level`.  Boxes that
+/// don't insert any bidi control characters stay at their parent's level.
+pub fn embedding_level(
+    parent_level: Level,
+    direction: Direction,
+    unicode_bidi: UnicodeBidi,
+) -> Level {
+    let next_level = match (unicode_bidi, direction) {
+        (UnicodeBidi::Normal, _) => return parent_level,
+        (_, Direction::Ltr) => parent_level.new_explicit_next_ltr(),
+        (_, Direction::Rtl) => parent_level.new_explicit_next_rtl(),
+    };
+    // Embeddings beyond the maximum depth are ignored by the algorithm.
+    next_level.unwrap_or(parent_level)
+}
+
+/// Splits `levels`, the embedding levels of each byte of some text, into maximal runs of the same
+/// level.
+pub fn level_runs(levels: &[Level]) -> Vec<(Range<usize>, Level)> {
+    let mut runs: Vec<(Range<usize>, Level)> = Vec::new();
+    for (index, &level) in levels.iter().enumerate() {
+        match runs.last_mut() {
+            Some((range, run_level)) if *run_level == level => range.end = index + 1,
+            _ => runs.push((index..index + 1, level)),
+        }
+    }
+    runs
+}
+
+/// Returns the indices of the items of a line with the given embedding `levels` in visual order,
+/// from left to right (or top to bottom), by reversing every sequence of items at or above each
+/// odd level.
+///
+/// https://unicode.org/reports/tr9/#L2
+pub fn visual_order(levels: &[Level]) -> Vec<usize> {
+    let mut order = (0..levels.len()).collect::<Vec<_>>();
+    let highest_level = match levels.iter().map(Level::number).max() {
+        Some(level) => level,
+        None => return order,
+    };
+    let lowest_odd_level = match levels
+        .iter()
+        .map(Level::number)
+        .filter(|level| level % 2 == 1)
+        .min()
+    {
+        Some(level) => level,
+        None => return order,
+    };
+    for level in (lowest_odd_level..=highest_level).rev() {
+        let mut index = 0;
+        while index < order.len() {
+            if levels[order[index]].number() < level {
+                index += 1;
+                continue;
+            }
+            let run_start = index;
+            while index < order.len() && levels[order[index]].number() >= level {
+                index += 1;
+            }
+            order[run_start..index].reverse();
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(numbers: &[u8]) -> Vec<Level> {
+        numbers
+            .iter()
+            .map(|&number| Level::new(number).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn reverses_odd_levels() {
+        assert_eq!(visual_order(&levels(&[0, 0, 0])), vec![0, 1, 2]);
+        assert_eq!(visual_order(&levels(&[0, 1, 1, 0])), vec![0, 2, 1, 3]);
+        assert_eq!(visual_order(&levels(&[1, 1, 1])), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn keeps_nested_ltr_runs_in_order() {
+        // An rtl paragraph with an ltr phrase in the middle: the phrase itself reads left to right,
+        // but ends up in the rtl order of the paragraph.
+        assert_eq!(visual_order(&levels(&[1, 2, 2, 1, 1])), vec![4, 3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn splits_levels_into_runs() {
+        assert_eq!(
+            level_runs(&levels(&[0, 0, 1, 1, 1, 0])),
+            vec![
+                (0..2, Level::ltr()),
+                (2..5, Level::rtl()),
+                (5..6, Level::ltr())
+            ]
+        );
+        assert!(level_runs(&[]).is_empty());
+    }
+
+    #[test]
+    fn embeds_at_next_level() {
+        let ltr = Level::ltr();
+        assert_eq!(
+            embedding_level(ltr, Direction::Rtl, UnicodeBidi::Embed),
+            Level::rtl()
+        );
+        assert_eq!(
+            embedding_level(ltr, Direction::Ltr, UnicodeBidi::Isolate).number(),
+            2
+        );
+        assert_eq!(
+            embedding_level(ltr, Direction::Rtl, UnicodeBidi::Normal),
+            ltr
+        );
+    }
+
+    #[test]
+    fn injects_control_characters() {
+        assert_eq!(
+            inline_box_controls(Direction::Rtl, UnicodeBidi::IsolateOverride),
+            ("\u{2067}\u{202E}".to_owned(), "\u{202C}\u{2069}".to_owned())
+        );
+        assert_eq!(
+            block_container_controls(Direction::Rtl, UnicodeBidi::Isolate),
+            (String::new(), String::new())
+        );
+    }
+}
@@ -1,10 +1,13 @@
 use crate::dom::tree::NodeRef;
+use crate::gfx::font::FontHandle;
 use crate::layout::behavior::{ApplyPageRelativeProperties, BaseLayoutBoxBehavior};
 use crate::layout::containing_block::ContainingBlock;
 use crate::layout::dimensions::Dimensions;
 use crate::layout::flow::{BlockContainer, FlowSide, OriginRelativeProgression};
 use crate::layout::formatting_context::FormattingContextRef;
+use crate::layout::intrinsic::{block_container_intrinsic_sizes, IntrinsicSizes, IntrinsicSizing};
 use crate::layout::layout_box::{get_anonymous_inline_layout_box, BaseBox, LayoutBox};
+use crate::layout::replaced::ReplacedBox;
 use crate::layout::{BoxComponent, DumpLayoutFormat, Layout, LayoutContext};
 use crate::layout_box_behavior_base_box_passthrough_impls;
 use crate::style::values::computed::length::{
@@ -26,6 +29,9 @@ pub enum BlockLevelBox {
     /// A block-level block container (note block containers can also be inline-level).
     /// https://drafts.csswg.org/css-display-3/#block-container
     BlockContainer(BlockContainer),
+    /// A block-level replaced element, such as an `<img>` with `display: block`.
+    /// https://www.w3.org/TR/CSS2/visudet.html#block-replaced-width
+    Replaced(ReplacedBox),
 }
 
 impl BlockLevelBox {
@@ -38,13 +44,24 @@ impl BlockLevelBox {
         match self {
             BlockLevelBox::AnonymousBlock(ab) => ab.add_child(new_child),
             BlockLevelBox::BlockContainer(bc) => bc.add_child(new_child),
+            BlockLevelBox::Replaced(_) => panic!("tried to add child to replaced box"),
         }
     }
 
-    pub fn children(&self) -> &Vec<LayoutBox> {
+    /// Returns the children of this box, or `None` for replaced boxes, which have none.
+    pub fn children(&self) -> Option<&Vec<LayoutBox>> {
+        match self {
+            BlockLevelBox::AnonymousBlock(ab) => Some(ab.children()),
+            BlockLevelBox::BlockContainer(bc) => Some(bc.children()),
+            BlockLevelBox::Replaced(_) => None,
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Option<&mut Vec<LayoutBox>> {
         match self {
-            BlockLevelBox::AnonymousBlock(ab) => ab.children(),
-            BlockLevelBox::BlockContainer(bc) => bc.children(),
+            BlockLevelBox::AnonymousBlock(ab) => Some(ab.children_mut()),
+            BlockLevelBox::BlockContainer(bc) => Some(bc.children_mut()),
+            BlockLevelBox::Replaced(_) => None,
         }
     }
 
@@ -64,16 +81,18 @@ impl BlockLevelBox {
                     _ => None,
                 })
                 .flatten(),
+            BlockLevelBox::Replaced(_) => None,
         }
     }
 
-    fn layout_children(&mut self, containing_block: ContainingBlock) {
-        let direction = self.computed_values().direction;
-        let writing_mode = self.computed_values().writing_mode;
+    fn layout_children(&mut self, containing_block: ContainingBlock, font_handle: &FontHandle) {
+        let direction = self.computed_values().get_inherited_box().direction;
+        let writing_mode = self.computed_values().get_inherited_box().writing_mode;
 
         let (children, self_dimensions) = match self {
             BlockLevelBox::AnonymousBlock(abb) => (&mut abb.children, abb.base.dimensions_mut()),
             BlockLevelBox::BlockContainer(bc) => (&mut bc.children, bc.base.dimensions_mut()),
+            BlockLevelBox::Replaced(_) => return,
         };
         for child in children {
             // The rectangle selected as the containing block will need to change when we support other
@@ -83,11 +102,10 @@ impl BlockLevelBox {
             // 10.1.2: For other [not-root] elements, if the element's position is 'relative' or
             // 'static', the containing block is formed by the content edge of the nearest block
             // container ancestor box.
-            child.layout(LayoutContext::new(ContainingBlock::new(
-                self_dimensions.content,
-                direction,
-                writing_mode,
-            )));
+            child.layout(LayoutContext::new(
+                ContainingBlock::new(self_dimensions.content, direction, writing_mode),
+                font_handle,
+            ));
 
             // Add this child's margin-box to our content box so the next child is laid out after
             // this one.
@@ -98,7 +116,11 @@ impl BlockLevelBox {
         }
     }
 
-    pub fn solve_and_set_inline_level_properties(&mut self, containing_block: ContainingBlock) {
+    pub fn solve_and_set_inline_level_properties(
+        &mut self,
+        containing_block: ContainingBlock,
+        font_handle: &FontHandle,
+    ) {
         // Use the containing block's writing mode for resolving flow-relative directions.
         // https://drafts.csswg.org/css-writing-modes-4/#logical-direction-layout
         let writing_mode = containing_block.writing_mode();
@@ -112,18 +134,51 @@ impl BlockLevelBox {
             computed_values.padding_flow_relative(FlowSide::InlineStart, writing_mode);
         let padding_inline_end =
             computed_values.padding_flow_relative(FlowSide::InlineEnd, writing_mode);
+        let margin_inline_start =
+            computed_values.margin_flow_relative(FlowSide::InlineStart, writing_mode);
+        let margin_inline_end =
+            computed_values.margin_flow_relative(FlowSide::InlineEnd, writing_mode);
+
+        let inline_size = if let BlockLevelBox::Replaced(replaced) = self {
+            // The inline size of block-level replaced elements is determined as for inline-level
+            // ones, and the margins follow from it.
+            // https://www.w3.org/TR/CSS2/visudet.html#block-replaced-width
+            let (inline_size, _) = replaced.used_size(
+                Some(containing_block.self_relative_inline_size()),
+                writing_mode,
+            );
+            LengthPercentageOrAuto::new_len_px(inline_size)
+        } else {
+            match computed_values.inline_size_content_keyword(writing_mode) {
+                Some(keyword) => {
+                    // Content-based sizes are resolved against the space left over after this
+                    // box's margins, borders, and padding (auto margins being zero) are subtracted
+                    // from the containing block.
+                    let cb_inline_size = containing_block.self_relative_inline_size();
+                    let inline_mbp = margin_inline_start.to_px(cb_inline_size)
+                        + margin_inline_end.to_px(cb_inline_size)
+                        + border_inline_start
+                        + border_inline_end
+                        + padding_inline_start.to_px(cb_inline_size)
+                        + padding_inline_end.to_px(cb_inline_size);
+                    LengthPercentageOrAuto::new_len_px(
+                        self.intrinsic_sizes(font_handle)
+                            .resolve_keyword(keyword, cb_inline_size - inline_mbp),
+                    )
+                }
+                None => computed_values.inline_size(writing_mode),
+            }
+        };
 
         let solved_inline_sizes = solve_block_level_inline_size(SolveInlineSizeInput {
             containing_block,
-            margin_inline_start: computed_values
-                .margin_flow_relative(FlowSide::InlineStart, writing_mode),
-            margin_inline_end: computed_values
-                .margin_flow_relative(FlowSide::InlineEnd, writing_mode),
+            margin_inline_start,
+            margin_inline_end,
             border_inline_start,
             border_inline_end,
             padding_inline_start,
             padding_inline_end,
-            inline_size: computed_values.inline_size(writing_mode),
+            inline_size,
         });
         // Release this &self borrow so we can mutably borrow below.
         drop(computed_values);
@@ -170,10 +225,10 @@ impl BlockLevelBox {
 
         let cvs = self.computed_values();
         let (ml, mr, mb, mt) = (
-            cvs.margin_left,
-            cvs.margin_right,
-            cvs.margin_bottom,
-            cvs.margin_top,
+            cvs.get_margin().margin_left,
+            cvs.get_margin().margin_right,
+            cvs.get_margin().margin_bottom,
+            cvs.get_margin().margin_top,
         );
         drop(cvs);
         if let LengthPercentageOrAuto::LengthPercentage(lp) = ml.size {
@@ -198,6 +253,28 @@ impl BlockLevelBox {
             .set_inline_start_coord(inline_start_coord, containing_block.writing_mode());
     }
 
+    /// Anonymous block boxes borrow the computed values of the nearest element, but have no margins,
+    /// borders, padding, or specified sizes of their own.  They fill the inline size of their
+    /// containing block and are stacked after their preceding siblings like any other block.
+    ///
+    /// https://www.w3.org/TR/CSS2/visuren.html#anonymous-block-level
+    fn solve_and_set_anonymous_block_properties(&mut self, containing_block: ContainingBlock) {
+        let writing_mode = containing_block.writing_mode();
+        self.dimensions_mut()
+            .set_inline_size(containing_block.self_relative_inline_size(), writing_mode);
+        self.dimensions_mut().set_inline_start_coord(
+            containing_block.self_relative_inline_start_coord(),
+            writing_mode,
+        );
+        let block_start_coord = compute_block_start_coord(
+            &self.dimensions(),
+            containing_block.self_relative_block_size(),
+            containing_block,
+        );
+        self.dimensions_mut()
+            .set_block_start_coord(block_start_coord, writing_mode);
+    }
+
     /// Corresponds to CSS 2.1 section 10.6.3.  Currently no other sections are implemented.
     /// https://www.w3.org/TR/2011/REC-CSS2-20110607/visudet.html#normal-block
     pub fn solve_and_set_block_level_properties(&mut self, containing_block: ContainingBlock) {
@@ -282,7 +359,7 @@ impl BlockLevelBox {
         let block_start_coord = compute_block_start_coord(
             &self.dimensions(),
             preceeding_sibling_blockwise_space_consumed,
-            LayoutContext::new(containing_block),
+            containing_block,
         );
         self.dimensions_mut()
             .set_block_start_coord(block_start_coord, containing_block.writing_mode());
@@ -296,10 +373,27 @@ impl Layout for BlockLevelBox {
     // [1] "self-relative" means the containing block evaluates abstract flow directions against its
     // own writing-mode, rather than that of it's own containing block.
     fn layout(&mut self, context: LayoutContext) {
-        let LayoutContext { containing_block } = context;
-        self.solve_and_set_inline_level_properties(containing_block);
+        let (containing_block, font_handle) = (context.containing_block(), context.font_handle());
+        if let BlockLevelBox::AnonymousBlock(_) = self {
+            self.solve_and_set_anonymous_block_properties(containing_block);
+            self.layout_children(containing_block, font_handle);
+            return;
+        }
+        self.solve_and_set_inline_level_properties(containing_block, font_handle);
         self.solve_and_set_block_level_properties(containing_block);
-        self.layout_children(containing_block);
+        if let BlockLevelBox::Replaced(replaced) = self {
+            // Replaced boxes have no children, and their used size already accounts for the
+            // author's specified sizes.
+            let writing_mode = containing_block.writing_mode();
+            let (_, block_size) = replaced.used_size(
+                Some(containing_block.self_relative_inline_size()),
+                writing_mode,
+            );
+            self.dimensions_mut()
+                .set_block_size(block_size, writing_mode);
+            return;
+        }
+        self.layout_children(containing_block, font_handle);
 
         // After computing and applying values normally through layout, override these values with
         // the author's specified page relative properties (if present).
@@ -344,6 +438,18 @@ impl ApplyPageRelativeProperties for AnonymousBlockBox {
     apply_page_relative_properties_base_box_passthrough_impls!();
 }
 
+impl IntrinsicSizing for AnonymousBlockBox {
+    fn intrinsic_sizes(&self, font_handle: &FontHandle) -> IntrinsicSizes {
+        self.base.intrinsic_sizes_or_else(|| {
+            block_container_intrinsic_sizes(
+                &self.children,
+                font_handle,
+                self.computed_values().get_inherited_box().writing_mode,
+            )
+        })
+    }
+}
+
 impl DumpLayoutFormat for AnonymousBlockBox {
     fn dump_layout_format(&self) -> String {
         // Anonymous boxes are not generated by an element of the DOM, so simply print the name
@@ -483,10 +589,9 @@ fn solve_block_level_inline_size_to_spec(input: SolveInlineSizeInput) -> SolveIn
 fn compute_block_start_coord(
     box_dimensions: &Dimensions,
     preceeding_sibling_blockwise_space_consumed: CSSPixelLength,
-    layout_context: LayoutContext,
+    containing_block: ContainingBlock,
 ) -> CSSFloat {
-    let containing_block = layout_context.containing_block;
-    match layout_context.block_start_origin_relative_progression() {
+    match containing_block.block_start_origin_relative_progression() {
         OriginRelativeProgression::AwayFromOrigin => {
             containing_block.self_relative_block_start_coord()
                 + preceeding_sibling_blockwise_space_consumed
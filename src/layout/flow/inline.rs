@@ -1,12 +1,21 @@
 use crate::apply_page_relative_properties_base_box_passthrough_impls;
 use crate::dom::tree::NodeRef;
+use crate::gfx::font::{str_advance, FontDescriptor, FontHandle};
 use crate::layout::behavior::{ApplyPageRelativeProperties, BaseLayoutBoxBehavior};
 use crate::layout::containing_block::ContainingBlock;
 use crate::layout::dimensions::Dimensions;
+use crate::layout::flow::line::{layout_inline_formatting_context, LineCoordinates, TextFragment};
+use crate::layout::flow::orientation::glyph_orientation_runs;
+use crate::layout::flow::white_space::is_space_or_tab;
 use crate::layout::formatting_context::FormattingContextRef;
+use crate::layout::intrinsic::{
+    inline_box_intrinsic_sizes, InlineEdges, IntrinsicSizes, IntrinsicSizing,
+};
 use crate::layout::layout_box::{BaseBox, LayoutBox};
+use crate::layout::replaced::ReplacedBox;
 use crate::layout::{DumpLayoutFormat, Layout, LayoutContext};
 use crate::layout_box_behavior_base_box_passthrough_impls;
+use crate::style::values::computed::length::CSSPixelLength;
 use crate::style::values::computed::ComputedValues;
 use accountable_refcell::Ref;
 use enum_dispatch::enum_dispatch;
@@ -22,13 +31,18 @@ pub enum InlineLevelContent {
     ///
     /// https://drafts.csswg.org/css-display-3/#text-run
     TextRun(TextRun),
+    /// An inline-level replaced element, such as an `<img>`.  It's an atomic inline, meaning it's
+    /// placed on a line as a single opaque box.
+    ///
+    /// https://drafts.csswg.org/css-display-3/#atomic-inline
+    Replaced(ReplacedBox),
 }
 
 impl InlineLevelContent {
     pub fn is_anonymous_inline(&self) -> bool {
         match self {
             InlineLevelContent::InlineLevelBox(ilb) => ilb.is_anonymous_inline(),
-            InlineLevelContent::TextRun(_) => false,
+            InlineLevelContent::TextRun(_) | InlineLevelContent::Replaced(_) => false,
         }
     }
 }
@@ -41,6 +55,8 @@ impl ApplyPageRelativeProperties for InlineLevelContent {
             }
             // The underlying implementation of this method applies computed values, which can't be targeted at text runs by authors.  So do nothing here.
             InlineLevelContent::TextRun(_) => {}
+            // The used size of replaced boxes already accounts for these properties.
+            InlineLevelContent::Replaced(_) => {}
         }
     }
 
@@ -51,6 +67,8 @@ impl ApplyPageRelativeProperties for InlineLevelContent {
             }
             // The underlying implementation of this method applies computed values, which can't be targeted at text runs by authors.  So do nothing here.
             InlineLevelContent::TextRun(_) => {}
+            // The used size of replaced boxes already accounts for these properties.
+            InlineLevelContent::Replaced(_) => {}
         }
     }
 }
@@ -59,10 +77,13 @@ impl Layout for InlineLevelContent {
     fn layout(&mut self, context: LayoutContext) {
         match self {
             InlineLevelContent::InlineLevelBox(ilb) => ilb.layout(context),
-            InlineLevelContent::TextRun(tr) => unimplemented!(
-                "layout called on text run with contents '{}'",
+            InlineLevelContent::TextRun(tr) => unreachable!(
+                "text run with contents '{}' should have been laid out by its root inline box",
                 tr.contents.clone()
             ),
+            InlineLevelContent::Replaced(_) => unreachable!(
+                "inline-level replaced box should have been laid out by its root inline box"
+            ),
         }
     }
 }
@@ -105,6 +126,13 @@ impl InlineLevelBox {
         }
     }
 
+    pub fn children_mut(&mut self) -> &mut Vec<LayoutBox> {
+        match self {
+            InlineLevelBox::AnonymousInline(aib) => aib.children_mut(),
+            InlineLevelBox::InlineBox(ib) => ib.children_mut(),
+        }
+    }
+
     pub fn is_anonymous_inline(&self) -> bool {
         match self {
             InlineLevelBox::AnonymousInline(_) => true,
@@ -114,8 +142,15 @@ impl InlineLevelBox {
 }
 
 impl Layout for InlineLevelBox {
-    fn layout(&mut self, _context: LayoutContext) {
-        unimplemented!()
+    fn layout(&mut self, context: LayoutContext) {
+        match self {
+            InlineLevelBox::AnonymousInline(aib) => aib.layout(context),
+            // Inline boxes can be split across lines, so they are laid out along with the rest of
+            // their inline formatting context by the root inline box.
+            InlineLevelBox::InlineBox(_) => {
+                unreachable!("inline box should have been laid out by its root inline box")
+            }
+        }
     }
 }
 
@@ -136,6 +171,10 @@ impl AnonymousInlineBox {
     pub fn children(&self) -> &Vec<LayoutBox> {
         &self.children
     }
+
+    pub fn children_mut(&mut self) -> &mut Vec<LayoutBox> {
+        &mut self.children
+    }
 }
 
 impl BaseLayoutBoxBehavior for AnonymousInlineBox {
@@ -146,6 +185,52 @@ impl ApplyPageRelativeProperties for AnonymousInlineBox {
     apply_page_relative_properties_base_box_passthrough_impls!();
 }
 
+impl Layout for AnonymousInlineBox {
+    /// Lays out the inline formatting context this box is the root of.  The root inline box spans
+    /// the full inline size of its containing block, and is as tall as the line boxes it contains.
+    fn layout(&mut self, context: LayoutContext) {
+        let containing_block = context.containing_block();
+        let writing_mode = containing_block.writing_mode();
+        // Any preceding siblings have already been added to the containing block's block size.
+        let block_start_coord = containing_block.self_relative_block_start_coord()
+            + containing_block.self_relative_block_size().px();
+        let coordinates = LineCoordinates::new(
+            containing_block.self_relative_inline_start_coord(),
+            block_start_coord,
+            containing_block.self_relative_inline_size(),
+            writing_mode,
+            containing_block.direction(),
+        );
+        let block_size = layout_inline_formatting_context(
+            &self.base.computed_values(),
+            &mut self.children,
+            coordinates,
+            context.font_handle(),
+        );
+
+        let dimensions = self.base.dimensions_mut();
+        dimensions.set_inline_start_coord(
+            containing_block.self_relative_inline_start_coord(),
+            writing_mode,
+        );
+        dimensions.set_block_start_coord(block_start_coord, writing_mode);
+        dimensions.set_inline_size(containing_block.self_relative_inline_size(), writing_mode);
+        dimensions.set_block_size(block_size, writing_mode);
+    }
+}
+
+impl IntrinsicSizing for AnonymousInlineBox {
+    fn intrinsic_sizes(&self, font_handle: &FontHandle) -> IntrinsicSizes {
+        self.base.intrinsic_sizes_or_else(|| {
+            inline_box_intrinsic_sizes(
+                &self.children,
+                font_handle,
+                self.computed_values().get_inherited_box().writing_mode,
+            )
+        })
+    }
+}
+
 impl DumpLayoutFormat for AnonymousInlineBox {
     fn dump_layout_format(&self) -> String {
         // Anonymous boxes are not generated by an element of the DOM, so simply print the name
@@ -171,6 +256,10 @@ impl InlineBox {
     fn children(&self) -> &Vec<LayoutBox> {
         &self.children
     }
+
+    pub fn children_mut(&mut self) -> &mut Vec<LayoutBox> {
+        &mut self.children
+    }
 }
 
 impl BaseLayoutBoxBehavior for InlineBox {
@@ -181,6 +270,18 @@ impl ApplyPageRelativeProperties for InlineBox {
     apply_page_relative_properties_base_box_passthrough_impls!();
 }
 
+impl IntrinsicSizing for InlineBox {
+    fn intrinsic_sizes(&self, font_handle: &FontHandle) -> IntrinsicSizes {
+        self.base.intrinsic_sizes_or_else(|| {
+            inline_box_intrinsic_sizes(
+                &self.children,
+                font_handle,
+                self.computed_values().get_inherited_box().writing_mode,
+            )
+        })
+    }
+}
+
 impl DumpLayoutFormat for InlineBox {
     fn dump_layout_format(&self) -> String {
         let node_data = self.node().data().dump_layout_format();
@@ -203,6 +304,8 @@ pub struct TextRun {
     /// TODO: This can be an owned String for now for simplicity's sake, but it would be probably
     /// be more efficient if this were a `&'DOM_LIFETIME str`.
     contents: String,
+    /// The pieces of `contents` placed on each line this text run spans, set during layout.
+    fragments: Vec<TextFragment>,
 }
 
 impl TextRun {
@@ -210,18 +313,83 @@ impl TextRun {
         Self {
             base: BaseBox::new(node, formatting_context),
             contents,
+            fragments: Vec::new(),
         }
     }
 
     pub fn contents(&self) -> String {
         self.contents.clone()
     }
+
+    pub fn set_contents(&mut self, contents: String) {
+        self.contents = contents;
+    }
+
+    pub fn fragments(&self) -> &[TextFragment] {
+        &self.fragments
+    }
+
+    pub fn set_fragments(&mut self, fragments: Vec<TextFragment>) {
+        self.fragments = fragments;
+    }
 }
 
 impl BaseLayoutBoxBehavior for TextRun {
     layout_box_behavior_base_box_passthrough_impls!();
 }
 
+impl IntrinsicSizing for TextRun {
+    fn intrinsic_sizes(&self, font_handle: &FontHandle) -> IntrinsicSizes {
+        self.base.intrinsic_sizes_or_else(|| {
+            let font = FontDescriptor::new(&self.computed_values());
+            let font_size = self.computed_values().get_font().font_size.size;
+            let white_space = self.computed_values().get_inherited_text().white_space;
+            let writing_mode = self.computed_values().get_inherited_box().writing_mode;
+            let text_orientation = self.computed_values().get_inherited_box().text_orientation;
+            // Spaces at the end of a line hang or are removed, so they don't contribute to either
+            // size.  Only preserved segment breaks can be left in the contents at this point, and
+            // those always break the line.
+            let line_advance = |line: &str| {
+                let line = line.trim_end_matches(is_space_or_tab);
+                glyph_orientation_runs(line, writing_mode, text_orientation)
+                    .into_iter()
+                    .map(|(range, orientation)| {
+                        str_advance(font_handle, &line[range], &font, font_size, orientation)
+                    })
+                    .fold(CSSPixelLength::new(0.), |acc, advance| acc + advance)
+            };
+            let max_content = self
+                .contents
+                .split('\n')
+                .map(line_advance)
+                .fold(CSSPixelLength::new(0.), CSSPixelLength::max);
+            // When wrapping is allowed, every space is a soft wrap opportunity, so the min-content
+            // size is that of the longest word.  Otherwise, only preserved segment breaks are.
+            let allows_wrapping = white_space.allows_wrapping();
+            let runs = self
+                .contents
+                .split(|ch: char| ch == '\n' || (allows_wrapping && is_space_or_tab(ch)))
+                .map(line_advance)
+                .collect::<Vec<_>>();
+            let min_content = runs
+                .iter()
+                .copied()
+                .fold(CSSPixelLength::new(0.), CSSPixelLength::max);
+            // The first and last words join the text next to this run, such as that of a sibling
+            // inline box, unless there's a space between them.
+            let edges = match runs.as_slice() {
+                [run] => InlineEdges::unbreakable(*run),
+                runs => InlineEdges {
+                    leading: runs[0],
+                    trailing: runs[runs.len() - 1],
+                    unbreakable: false,
+                },
+            };
+            IntrinsicSizes::new(min_content, max_content).with_edges(edges)
+        })
+    }
+}
+
 impl DumpLayoutFormat for TextRun {
     fn dump_layout_format(&self) -> String {
         let node_data = self.node().data().dump_layout_format();
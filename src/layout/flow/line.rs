@@ -0,0 +1,1788 @@
+use crate::gfx::font::{shape_with_fallback, FontDescriptor, FontHandle, FontMetrics};
+use crate::gfx::shape::{GlyphOrientation, GlyphRun};
+use crate::layout::behavior::BaseLayoutBoxBehavior;
+use crate::layout::dimensions::Dimensions;
+use crate::layout::flow::bidi::{
+    block_container_controls, embedding_level, inline_box_controls, level_runs, paragraph_level,
+    visual_order,
+};
+use crate::layout::flow::inline::{InlineLevelBox, InlineLevelContent};
+use crate::layout::flow::orientation::{forces_ltr, glyph_orientation_runs};
+use crate::layout::flow::white_space::is_space_or_tab;
+use crate::layout::flow::{FlowSide, OriginRelativeProgression};
+use crate::layout::layout_box::LayoutBox;
+use crate::layout::rect::Rect;
+use crate::style::values::computed::length::CSSPixelLength;
+use crate::style::values::computed::{
+    ComputedValues, Direction, LineHeight, TextOrientation, UnicodeBidi, VerticalAlign, WhiteSpace,
+    WritingMode,
+};
+use crate::style::values::specified::vertical_align::VerticalAlignKeyword;
+use crate::style::values::used::ToPx;
+use crate::style::values::CSSFloat;
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
+
+/// The fraction of the parent's font size by which `vertical-align: sub` lowers a box.  Fonts can
+/// provide their own subscript offsets, but like other engines do when they don't, we approximate.
+const SUB_OFFSET_FACTOR: CSSFloat = 1. / 5.;
+/// The fraction of the parent's font size by which `vertical-align: super` raises a box.
+const SUPER_OFFSET_FACTOR: CSSFloat = 1. / 3.;
+/// The character atomic inlines are represented by in the text the bidi algorithm is run over,
+/// which is treated as a neutral.
+const OBJECT_REPLACEMENT_CHARACTER: char = '\u{FFFC}';
+
+/// A piece of a text run that was placed on a single line box.
+#[derive(Clone, Debug)]
+pub struct TextFragment {
+    /// The glyphs placed on the line.  Collapsible spaces at the start and end of the line have
+    /// already been removed.
+    pub glyphs: GlyphRun,
+    /// The content area of this fragment, which is as tall as the ascent plus the descent of the
+    /// font.
+    pub rect: Rect,
+    /// The block-axis coordinate (`y` in horizontal writing modes) of this fragment's baseline.
+    pub baseline: CSSFloat,
+}
+
+/// Maps logical coordinates relative to the content edges of a root inline box to physical
+/// coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct LineCoordinates {
+    inline_start_coord: CSSFloat,
+    block_start_coord: CSSFloat,
+    inline_size: CSSPixelLength,
+    /// The sum of the block sizes of all the line boxes, which is only known once every line has
+    /// been placed.  Lines are placed from the block-end edge of that extent when the block-start
+    /// side is on the far side from the origin, as in `vertical-rl`.
+    block_size: CSSPixelLength,
+    writing_mode: WritingMode,
+    direction: Direction,
+}
+
+impl LineCoordinates {
+    pub fn new(
+        inline_start_coord: CSSFloat,
+        block_start_coord: CSSFloat,
+        inline_size: CSSPixelLength,
+        writing_mode: WritingMode,
+        direction: Direction,
+    ) -> Self {
+        LineCoordinates {
+            inline_start_coord,
+            block_start_coord,
+            inline_size,
+            block_size: CSSPixelLength::new(0.),
+            writing_mode,
+            direction,
+        }
+    }
+
+    /// These coordinates, for line boxes that add up to `block_size`.
+    fn with_block_size(self, block_size: CSSPixelLength) -> Self {
+        LineCoordinates { block_size, ..self }
+    }
+
+    /// The inline size available to each line box.
+    pub fn inline_size(&self) -> CSSPixelLength {
+        self.inline_size
+    }
+
+    /// Whether the over side of lines, which ascenders point towards, is their block-end side
+    /// rather than their block-start side.  The over side is the right side in all vertical
+    /// writing modes except `sideways-lr`, where glyphs are rotated to point left.
+    ///
+    /// https://drafts.csswg.org/css-writing-modes-4/#line-orientation
+    fn line_over_is_block_end(&self) -> bool {
+        self.writing_mode == WritingMode::VerticalLr
+    }
+
+    pub fn to_physical(&self, rect: LogicalRect) -> Rect {
+        let inline_start_coord =
+            match OriginRelativeProgression::inline_start_origin_relative_direction(
+                self.writing_mode,
+                self.direction,
+            ) {
+                OriginRelativeProgression::AwayFromOrigin => {
+                    self.inline_start_coord + rect.inline_start.px()
+                }
+                OriginRelativeProgression::TowardsOrigin => {
+                    self.inline_start_coord
+                        + (self.inline_size - rect.inline_start - rect.inline_size).px()
+                }
+            };
+        let block_start_coord = self.to_physical_block_coord(rect.block_start, rect.block_size);
+        if self.writing_mode.is_horizontal() {
+            Rect {
+                start_x: inline_start_coord,
+                start_y: block_start_coord,
+                width: rect.inline_size,
+                height: rect.block_size,
+            }
+        } else {
+            Rect {
+                start_x: block_start_coord,
+                start_y: inline_start_coord,
+                width: rect.block_size,
+                height: rect.inline_size,
+            }
+        }
+    }
+
+    /// The physical coordinate of the side nearest the origin of something `block_size` long that
+    /// starts `block_offset` from the block-start edge of the line boxes.  Points have no size.
+    fn to_physical_block_coord(
+        &self,
+        block_offset: CSSPixelLength,
+        block_size: CSSPixelLength,
+    ) -> CSSFloat {
+        match OriginRelativeProgression::block_start_origin_relative_direction(self.writing_mode) {
+            OriginRelativeProgression::AwayFromOrigin => self.block_start_coord + block_offset.px(),
+            OriginRelativeProgression::TowardsOrigin => {
+                (self.block_start_coord + self.block_size - block_offset - block_size).px()
+            }
+        }
+    }
+}
+
+/// A rectangle in flow-relative terms.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LogicalRect {
+    pub inline_start: CSSPixelLength,
+    pub block_start: CSSPixelLength,
+    pub inline_size: CSSPixelLength,
+    pub block_size: CSSPixelLength,
+}
+
+impl LogicalRect {
+    /// Returns the smallest rectangle containing both `self` and `other`.
+    fn union(self, other: LogicalRect) -> LogicalRect {
+        let inline_start = self.inline_start.min(other.inline_start);
+        let block_start = self.block_start.min(other.block_start);
+        let inline_end =
+            (self.inline_start + self.inline_size).max(other.inline_start + other.inline_size);
+        let block_end =
+            (self.block_start + self.block_size).max(other.block_start + other.block_size);
+        LogicalRect {
+            inline_start,
+            block_start,
+            inline_size: inline_end - inline_start,
+            block_size: block_end - block_start,
+        }
+    }
+}
+
+/// Lays out the children of a root inline box into line boxes, breaking lines wherever the content
+/// would otherwise overflow the inline size of `coordinates`.  Every inline box, atomic inline, and
+/// text run in `children` is given its position, and text runs are split into one fragment per
+/// line.
+///
+/// The Unicode Bidirectional Algorithm is run over all the text of the inline formatting context,
+/// and the content of each line is then reordered visually, per the bidi levels of its text.
+///
+/// Returns the sum of the block sizes of all the line boxes, which is the block size of the root
+/// inline box.
+///
+/// https://www.w3.org/TR/CSS2/visuren.html#inline-formatting
+/// https://www.w3.org/TR/CSS2/visudet.html#line-height
+/// https://drafts.csswg.org/css-writing-modes-4/#text-direction
+pub fn layout_inline_formatting_context(
+    root_computed_values: &ComputedValues,
+    children: &mut [LayoutBox],
+    coordinates: LineCoordinates,
+    font_handle: &FontHandle,
+) -> CSSPixelLength {
+    let direction = root_computed_values.get_inherited_box().direction;
+    let unicode_bidi = root_computed_values.get_box().unicode_bidi;
+    let mut collector = InlineContentCollector {
+        items: Vec::new(),
+        boxes: vec![InlineBoxMetrics::new(
+            root_computed_values,
+            None,
+            font_handle,
+        )],
+        box_bidi: vec![(direction, UnicodeBidi::Normal)],
+        text_run_count: 0,
+        bidi_text: String::new(),
+        coordinates,
+        font_handle,
+    };
+    let (content_start_controls, content_end_controls) =
+        block_container_controls(direction, unicode_bidi);
+    collector.bidi_text.push_str(&content_start_controls);
+    collector.collect(children, 0);
+    collector.bidi_text.push_str(&content_end_controls);
+
+    let InlineContentCollector {
+        items,
+        boxes,
+        box_bidi,
+        text_run_count,
+        bidi_text,
+        ..
+    } = collector;
+    let default_paragraph_level = paragraph_level(direction, unicode_bidi);
+    let bidi_info = BidiInfo::new(&bidi_text, default_paragraph_level);
+    // TODO: With `unicode-bidi: plaintext`, each paragraph (i.e. each line ended by a preserved
+    // segment break) should be reordered per its own paragraph level, not that of the first one.
+    let paragraph_level = bidi_info.paragraphs.first().map_or_else(
+        || default_paragraph_level.unwrap_or_else(Level::ltr),
+        |paragraph| paragraph.level,
+    );
+    let mut box_levels = vec![paragraph_level; boxes.len()];
+    for (index, metrics) in boxes.iter().enumerate().skip(1) {
+        let parent = metrics
+            .parent
+            .expect("non-root inline box without a parent");
+        let (direction, unicode_bidi) = box_bidi[index];
+        box_levels[index] = embedding_level(box_levels[parent], direction, unicode_bidi);
+    }
+    let bidi_levels = BidiLevels {
+        paragraph: paragraph_level,
+        boxes: box_levels,
+        inline_start_is_line_right: coordinates.direction == Direction::Rtl,
+    };
+    let items = shape_items(
+        items,
+        &bidi_text,
+        &bidi_info.levels,
+        coordinates.writing_mode,
+        font_handle,
+    );
+
+    let mut results = InlineLayoutResults {
+        box_rects: vec![None; boxes.len()],
+        text_fragments: vec![Vec::new(); text_run_count],
+    };
+    let mut block_size = CSSPixelLength::new(0.);
+    for line in break_lines(items, coordinates.inline_size()) {
+        block_size += place_line(
+            line,
+            &boxes,
+            &bidi_levels,
+            coordinates.line_over_is_block_end(),
+            block_size,
+            &mut results,
+        );
+    }
+
+    let mut next_indices = ContentIndices {
+        // Index zero belongs to the root inline box.
+        box_index: 1,
+        text_run_index: 0,
+    };
+    apply_layout_results(
+        children,
+        &results,
+        coordinates.with_block_size(block_size),
+        &mut next_indices,
+    );
+    block_size
+}
+
+/// The information about an inline box needed to align it within line boxes.
+#[derive(Clone, Copy, Debug)]
+struct InlineBoxMetrics {
+    /// The index of the parent inline box, or `None` for the root inline box.
+    parent: Option<usize>,
+    font: FontMetrics,
+    font_size: CSSPixelLength,
+    /// The used `line-height`.
+    line_height: CSSPixelLength,
+    vertical_align: VerticalAlign,
+    /// The block size of the margin box of an atomic inline, or `None` for inline boxes.  Atomic
+    /// inlines are aligned by their margin box rather than by the line height around their font.
+    atomic_block_size: Option<CSSPixelLength>,
+}
+
+impl InlineBoxMetrics {
+    fn new(
+        computed_values: &ComputedValues,
+        parent: Option<usize>,
+        font_handle: &FontHandle,
+    ) -> Self {
+        let font_size = computed_values.get_font().font_size.size;
+        let font = font_metrics(
+            font_handle,
+            &FontDescriptor::new(computed_values),
+            font_size,
+        );
+        let line_height = match computed_values.get_inherited_text().line_height {
+            LineHeight::Normal => font.normal_line_height(),
+            LineHeight::Number(number) => font_size * number,
+            LineHeight::Length(length) => length,
+        };
+        InlineBoxMetrics {
+            parent,
+            font,
+            font_size,
+            line_height,
+            vertical_align: computed_values.get_box().vertical_align,
+            atomic_block_size: None,
+        }
+    }
+
+    /// Creates the metrics of an atomic inline whose margin box is `margin_box_block_size` tall.
+    fn new_atomic(
+        computed_values: &ComputedValues,
+        parent: usize,
+        font_handle: &FontHandle,
+        margin_box_block_size: CSSPixelLength,
+    ) -> Self {
+        InlineBoxMetrics {
+            atomic_block_size: Some(margin_box_block_size),
+            ..InlineBoxMetrics::new(computed_values, Some(parent), font_handle)
+        }
+    }
+
+    /// Half the difference between the line height and the content area, added both above and
+    /// below the content area.  Negative when the line height is smaller than the content area.
+    ///
+    /// https://www.w3.org/TR/CSS2/visudet.html#leading
+    fn half_leading(&self) -> CSSPixelLength {
+        (self.line_height - self.font.content_area_size()) / 2.
+    }
+
+    /// The distance from the baseline to the block-start edge of the box's layout bounds, which is
+    /// the area that has to fit in the line box.  The baseline of an atomic inline is the
+    /// block-end edge of its margin box.
+    ///
+    /// https://www.w3.org/TR/CSS2/visudet.html#propdef-vertical-align
+    fn layout_ascent(&self) -> CSSPixelLength {
+        match self.atomic_block_size {
+            Some(block_size) => block_size,
+            None => self.font.ascent + self.half_leading(),
+        }
+    }
+
+    /// The distance from the baseline to the block-end edge of the box's layout bounds.
+    fn layout_descent(&self) -> CSSPixelLength {
+        match self.atomic_block_size {
+            Some(_) => CSSPixelLength::new(0.),
+            None => self.font.descent + self.half_leading(),
+        }
+    }
+}
+
+/// Returns how far `child`'s baseline sits below its parent's baseline (negative values meaning
+/// above), or `None` if `child` is aligned relative to the line box itself (`top` and `bottom`).
+fn baseline_offset_from_parent(
+    child: &InlineBoxMetrics,
+    parent: &InlineBoxMetrics,
+) -> Option<CSSPixelLength> {
+    Some(match child.vertical_align {
+        VerticalAlign::Keyword(keyword) => match keyword {
+            VerticalAlignKeyword::Baseline => CSSPixelLength::new(0.),
+            VerticalAlignKeyword::Sub => parent.font_size * SUB_OFFSET_FACTOR,
+            VerticalAlignKeyword::Super => -(parent.font_size * SUPER_OFFSET_FACTOR),
+            // Line up the block-start edges of the child's layout bounds and the parent's content
+            // area.
+            VerticalAlignKeyword::TextTop => child.layout_ascent() - parent.font.ascent,
+            // Line up the block-end edges of the child's layout bounds and the parent's content
+            // area.
+            VerticalAlignKeyword::TextBottom => parent.font.descent - child.layout_descent(),
+            VerticalAlignKeyword::Middle => {
+                (child.layout_ascent() - child.layout_descent()) / 2. - parent.font.x_height / 2.
+            }
+            VerticalAlignKeyword::Top | VerticalAlignKeyword::Bottom => return None,
+        },
+        VerticalAlign::LengthPercentage(lp) => -lp.to_px(child.line_height),
+    })
+}
+
+/// The result of aligning the inline boxes on a line in the block axis.
+#[derive(Debug)]
+struct LineBoxAlignment {
+    /// For each inline box on the line, how far its baseline sits below the baseline of the root
+    /// inline box.
+    baseline_offsets: Vec<CSSPixelLength>,
+    /// The offset of the block-start edge of the line box from the root inline box's baseline.
+    block_start: CSSPixelLength,
+    /// The offset of the block-end edge of the line box from the root inline box's baseline.
+    block_end: CSSPixelLength,
+}
+
+impl LineBoxAlignment {
+    fn block_size(&self) -> CSSPixelLength {
+        self.block_end - self.block_start
+    }
+}
+
+/// Aligns the inline boxes marked in `on_line` per their `vertical-align`, and sizes the line box
+/// to fit the layout bounds of all of them.  `boxes` must be ordered such that parents come before
+/// their children, with the root inline box first.
+///
+/// https://www.w3.org/TR/CSS2/visudet.html#line-height
+fn align_line_boxes(boxes: &[InlineBoxMetrics], on_line: &[bool]) -> LineBoxAlignment {
+    let zero = CSSPixelLength::new(0.);
+    let mut baseline_offsets = vec![zero; boxes.len()];
+    // The `top` or `bottom` aligned box whose subtree each box belongs to.  For boxes in such a
+    // subtree, baseline offsets are relative to the baseline of that aligned box until the line box
+    // is known.
+    let mut aligned_subtree_roots: Vec<Option<usize>> = vec![None; boxes.len()];
+    for (index, metrics) in boxes.iter().enumerate().skip(1) {
+        if !on_line[index] {
+            continue;
+        }
+        let parent = metrics
+            .parent
+            .expect("non-root inline box without a parent");
+        match baseline_offset_from_parent(metrics, &boxes[parent]) {
+            Some(offset) => {
+                baseline_offsets[index] = baseline_offsets[parent] + offset;
+                aligned_subtree_roots[index] = aligned_subtree_roots[parent];
+            }
+            None => aligned_subtree_roots[index] = Some(index),
+        }
+    }
+
+    let layout_bounds = |index: usize, baseline_offsets: &[CSSPixelLength]| {
+        (
+            baseline_offsets[index] - boxes[index].layout_ascent(),
+            baseline_offsets[index] + boxes[index].layout_descent(),
+        )
+    };
+    let subtree_bounds = |subtree_root: Option<usize>, baseline_offsets: &[CSSPixelLength]| {
+        (0..boxes.len())
+            .filter(|&index| on_line[index] && aligned_subtree_roots[index] == subtree_root)
+            .map(|index| layout_bounds(index, baseline_offsets))
+            .fold(
+                None,
+                |acc: Option<(CSSPixelLength, CSSPixelLength)>, bounds| {
+                    Some(match acc {
+                        Some((start, end)) => (start.min(bounds.0), end.max(bounds.1)),
+                        None => bounds,
+                    })
+                },
+            )
+    };
+
+    // Boxes not inside a `top` or `bottom` aligned subtree are positioned relative to the root
+    // inline box's baseline, which is always on the line.
+    let (mut block_start, mut block_end) =
+        subtree_bounds(None, &baseline_offsets).unwrap_or((zero, zero));
+    let aligned_roots = (1..boxes.len())
+        .filter(|&index| on_line[index] && aligned_subtree_roots[index] == Some(index))
+        .collect::<Vec<_>>();
+    // The line box must be tall enough to fit every `top` and `bottom` aligned subtree.
+    for &aligned_root in &aligned_roots {
+        let (start, end) = subtree_bounds(Some(aligned_root), &baseline_offsets).unwrap();
+        let subtree_block_size = end - start;
+        if subtree_block_size > block_end - block_start {
+            match boxes[aligned_root].vertical_align {
+                VerticalAlign::Keyword(VerticalAlignKeyword::Bottom) => {
+                    block_start = block_end - subtree_block_size
+                }
+                _ => block_end = block_start + subtree_block_size,
+            }
+        }
+    }
+    for &aligned_root in &aligned_roots {
+        let (start, end) = subtree_bounds(Some(aligned_root), &baseline_offsets).unwrap();
+        let shift = match boxes[aligned_root].vertical_align {
+            VerticalAlign::Keyword(VerticalAlignKeyword::Bottom) => block_end - end,
+            _ => block_start - start,
+        };
+        for (offset, subtree_root) in baseline_offsets.iter_mut().zip(&aligned_subtree_roots) {
+            if *subtree_root == Some(aligned_root) {
+                *offset += shift;
+            }
+        }
+    }
+
+    LineBoxAlignment {
+        baseline_offsets,
+        block_start,
+        block_end,
+    }
+}
+
+/// Content of an inline formatting context, flattened into the sequence in which it is placed on
+/// lines.
+#[derive(Clone, Debug)]
+enum InlineItem {
+    /// The start of an inline box, taking up its inline-start margin, border, and padding.
+    BoxStart {
+        box_index: usize,
+        inline_size: CSSPixelLength,
+    },
+    /// The end of an inline box, taking up its inline-end margin, border, and padding.
+    BoxEnd {
+        box_index: usize,
+        inline_size: CSSPixelLength,
+    },
+    /// A piece of text that can't be broken across lines, all at a single bidi embedding level.
+    Text {
+        text_run_index: usize,
+        /// The index of the inline box the text run is a child of.
+        box_index: usize,
+        glyphs: GlyphRun,
+        bidi_level: Level,
+        /// The inline size of the spaces at the end of `glyphs`, which hang (don't take up any
+        /// space) when they end up at the end of a line.
+        trailing_spaces_inline_size: CSSPixelLength,
+        /// Whether the line may be broken right after this text.
+        soft_wrap_opportunity_after: bool,
+        /// Whether the spaces in this text are collapsible, meaning they're removed when they end
+        /// up at the start or end of a line.
+        collapsible_spaces: bool,
+    },
+    /// An atomic inline, such as an inline-level replaced element, which is placed on a line as a
+    /// single unbreakable unit.
+    Atomic {
+        /// The index of the atomic inline's own entry among the inline boxes.
+        box_index: usize,
+        /// The inline size of the atomic inline's margin box.
+        inline_size: CSSPixelLength,
+        bidi_level: Level,
+        /// Whether the line may be broken right before and right after the atomic inline.
+        soft_wrap_opportunities_around: bool,
+    },
+    /// A forced line break, such as a segment break preserved by `white-space`.
+    LineBreak { box_index: usize },
+}
+
+impl InlineItem {
+    fn inline_size(&self) -> CSSPixelLength {
+        match self {
+            InlineItem::BoxStart { inline_size, .. }
+            | InlineItem::BoxEnd { inline_size, .. }
+            | InlineItem::Atomic { inline_size, .. } => *inline_size,
+            InlineItem::Text { glyphs, .. } => glyphs.advance(),
+            InlineItem::LineBreak { .. } => CSSPixelLength::new(0.),
+        }
+    }
+
+    fn box_index(&self) -> usize {
+        match self {
+            InlineItem::BoxStart { box_index, .. }
+            | InlineItem::BoxEnd { box_index, .. }
+            | InlineItem::Text { box_index, .. }
+            | InlineItem::Atomic { box_index, .. }
+            | InlineItem::LineBreak { box_index } => *box_index,
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, InlineItem::Text { .. })
+    }
+
+    /// Whether this item is in-flow content of the line, rather than the edge of an inline box or a
+    /// line break.
+    fn is_content(&self) -> bool {
+        matches!(self, InlineItem::Text { .. } | InlineItem::Atomic { .. })
+    }
+
+    /// The inline size of the part of this item that hangs when the item ends a line.
+    fn hanging_inline_size(&self) -> CSSPixelLength {
+        match self {
+            InlineItem::Text {
+                trailing_spaces_inline_size,
+                ..
+            } => *trailing_spaces_inline_size,
+            _ => CSSPixelLength::new(0.),
+        }
+    }
+
+    /// Whether the line may be broken right before this item, whatever precedes it.
+    fn soft_wrap_opportunity_before(&self) -> bool {
+        matches!(
+            self,
+            InlineItem::Atomic {
+                soft_wrap_opportunities_around: true,
+                ..
+            }
+        )
+    }
+
+    /// Whether the line may be broken right after this item.
+    fn soft_wrap_opportunity_after(&self) -> bool {
+        match self {
+            InlineItem::Text {
+                soft_wrap_opportunity_after,
+                ..
+            } => *soft_wrap_opportunity_after,
+            InlineItem::Atomic {
+                soft_wrap_opportunities_around,
+                ..
+            } => *soft_wrap_opportunities_around,
+            _ => false,
+        }
+    }
+
+    /// Whether this item is text made up entirely of collapsible spaces, which is removed when it
+    /// ends up at the start of a line.
+    fn is_collapsible_spaces(&self) -> bool {
+        match self {
+            InlineItem::Text {
+                glyphs,
+                collapsible_spaces,
+                ..
+            } => *collapsible_spaces && glyphs.text().chars().all(is_space_or_tab),
+            _ => false,
+        }
+    }
+}
+
+/// Content of an inline formatting context as collected from the box tree, before its text is
+/// shaped.
+#[derive(Debug)]
+enum CollectedItem {
+    Item(InlineItem),
+    /// A line of a text run (i.e. the text between preserved segment breaks), which can't be shaped
+    /// until the bidi levels of its characters are known.
+    TextLine {
+        text_run_index: usize,
+        box_index: usize,
+        font: FontDescriptor,
+        font_size: CSSPixelLength,
+        white_space: WhiteSpace,
+        text_orientation: TextOrientation,
+        /// The byte range of the line within the text the bidi algorithm is run over.
+        bidi_text_range: Range<usize>,
+    },
+    /// An atomic inline, whose bidi level isn't known until the bidi algorithm has run.
+    Atomic {
+        box_index: usize,
+        inline_size: CSSPixelLength,
+        soft_wrap_opportunities_around: bool,
+        /// The byte index of the atomic inline's character within the text the bidi algorithm is
+        /// run over.
+        bidi_text_index: usize,
+    },
+}
+
+/// Flattens the box tree of an inline formatting context into `CollectedItem`s.  Inline boxes
+/// (including atomic inlines) and text runs are numbered in tree order, which
+/// `apply_layout_results` relies on.
+struct InlineContentCollector<'a> {
+    items: Vec<CollectedItem>,
+    boxes: Vec<InlineBoxMetrics>,
+    /// The `direction` and `unicode-bidi` of each inline box.
+    box_bidi: Vec<(Direction, UnicodeBidi)>,
+    text_run_count: usize,
+    /// All the text of the inline formatting context, with bidi control characters inserted at the
+    /// boundaries of inline boxes that affect bidi reordering.
+    bidi_text: String,
+    coordinates: LineCoordinates,
+    font_handle: &'a FontHandle,
+}
+
+impl InlineContentCollector<'_> {
+    fn collect(&mut self, children: &[LayoutBox], parent_box_index: usize) {
+        for child in children {
+            match child {
+                LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(ilb)) => {
+                    let box_index = self.boxes.len();
+                    let cvs = ilb.computed_values();
+                    self.boxes.push(InlineBoxMetrics::new(
+                        &cvs,
+                        Some(parent_box_index),
+                        self.font_handle,
+                    ));
+                    let (inline_start_mbp, inline_end_mbp) = if ilb.is_anonymous_inline() {
+                        (CSSPixelLength::new(0.), CSSPixelLength::new(0.))
+                    } else {
+                        (
+                            self.mbp(&cvs, FlowSide::InlineStart),
+                            self.mbp(&cvs, FlowSide::InlineEnd),
+                        )
+                    };
+                    // Anonymous inline boxes share the computed values of their parent element,
+                    // whose `unicode-bidi` has already been applied.
+                    let unicode_bidi = if ilb.is_anonymous_inline() {
+                        UnicodeBidi::Normal
+                    } else {
+                        cvs.get_box().unicode_bidi
+                    };
+                    let (start_controls, end_controls) =
+                        inline_box_controls(cvs.get_inherited_box().direction, unicode_bidi);
+                    self.box_bidi
+                        .push((cvs.get_inherited_box().direction, unicode_bidi));
+                    drop(cvs);
+                    self.items.push(CollectedItem::Item(InlineItem::BoxStart {
+                        box_index,
+                        inline_size: inline_start_mbp,
+                    }));
+                    self.bidi_text.push_str(&start_controls);
+                    self.collect(ilb.children(), box_index);
+                    self.bidi_text.push_str(&end_controls);
+                    self.items.push(CollectedItem::Item(InlineItem::BoxEnd {
+                        box_index,
+                        inline_size: inline_end_mbp,
+                    }));
+                }
+                LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => {
+                    let text_run_index = self.text_run_count;
+                    self.text_run_count += 1;
+                    let font = FontDescriptor::new(&text_run.computed_values());
+                    let font_size = text_run.computed_values().get_font().font_size.size;
+                    let white_space = text_run.computed_values().get_inherited_text().white_space;
+                    let text_orientation = text_run
+                        .computed_values()
+                        .get_inherited_box()
+                        .text_orientation;
+                    let (start_controls, end_controls) =
+                        if forces_ltr(self.coordinates.writing_mode, text_orientation) {
+                            inline_box_controls(Direction::Ltr, UnicodeBidi::BidiOverride)
+                        } else {
+                            (String::new(), String::new())
+                        };
+                    let contents = text_run.contents();
+                    // Segment breaks are only left in the contents when they're preserved, in
+                    // which case each of them forces a line break.  They also separate bidi
+                    // paragraphs.
+                    for (line_index, line) in contents.split('\n').enumerate() {
+                        if line_index > 0 {
+                            self.items.push(CollectedItem::Item(InlineItem::LineBreak {
+                                box_index: parent_box_index,
+                            }));
+                            self.bidi_text.push('\n');
+                        }
+                        self.bidi_text.push_str(&start_controls);
+                        let line_start = self.bidi_text.len();
+                        self.bidi_text.push_str(line);
+                        self.items.push(CollectedItem::TextLine {
+                            text_run_index,
+                            box_index: parent_box_index,
+                            font: font.clone(),
+                            font_size,
+                            white_space,
+                            text_orientation,
+                            bidi_text_range: line_start..self.bidi_text.len(),
+                        });
+                        self.bidi_text.push_str(&end_controls);
+                    }
+                }
+                LayoutBox::InlineLevel(InlineLevelContent::Replaced(replaced)) => {
+                    let box_index = self.boxes.len();
+                    let cvs = replaced.computed_values();
+                    let (inline_size, block_size) = replaced.used_size(
+                        Some(self.coordinates.inline_size()),
+                        self.coordinates.writing_mode,
+                    );
+                    let margin_box_block_size = block_size
+                        + self.mbp(&cvs, FlowSide::BlockStart)
+                        + self.mbp(&cvs, FlowSide::BlockEnd);
+                    self.boxes.push(InlineBoxMetrics::new_atomic(
+                        &cvs,
+                        parent_box_index,
+                        self.font_handle,
+                        margin_box_block_size,
+                    ));
+                    self.box_bidi
+                        .push((cvs.get_inherited_box().direction, UnicodeBidi::Normal));
+                    self.items.push(CollectedItem::Atomic {
+                        box_index,
+                        inline_size: inline_size
+                            + self.mbp(&cvs, FlowSide::InlineStart)
+                            + self.mbp(&cvs, FlowSide::InlineEnd),
+                        soft_wrap_opportunities_around: cvs
+                            .get_inherited_text()
+                            .white_space
+                            .allows_wrapping(),
+                        bidi_text_index: self.bidi_text.len(),
+                    });
+                    self.bidi_text.push(OBJECT_REPLACEMENT_CHARACTER);
+                }
+                // TODO: Block-level boxes inside inline boxes should split the inline box around
+                // them.  See the related TODO in `box_tree.rs`.  For now, they're left unlaid-out.
+                LayoutBox::BlockLevel(_) => {}
+            }
+        }
+    }
+
+    /// The sum of the margin, border, and padding on the given side of an inline-level box.
+    /// Percentages refer to the inline size of the containing block on every side.
+    fn mbp(&self, cvs: &ComputedValues, side: FlowSide) -> CSSPixelLength {
+        let writing_mode = self.coordinates.writing_mode;
+        let cb_inline_size = self.coordinates.inline_size();
+        // `auto` margins are zero for inline-level boxes, which `to_px` takes care of.
+        cvs.margin_flow_relative(side, writing_mode)
+            .to_px(cb_inline_size)
+            + cvs.border_flow_relative(side, writing_mode)
+            + cvs
+                .padding_flow_relative(side, writing_mode)
+                .to_px(cb_inline_size)
+    }
+}
+
+/// Shapes the lines of text in `collected`, given the bidi `levels` of each byte of `bidi_text`, and
+/// splits them into `InlineItem::Text`s at soft wrap opportunities, bidi level boundaries, and
+/// changes in glyph orientation.
+fn shape_items(
+    collected: Vec<CollectedItem>,
+    bidi_text: &str,
+    levels: &[Level],
+    writing_mode: WritingMode,
+    font_handle: &FontHandle,
+) -> Vec<InlineItem> {
+    let mut items = Vec::with_capacity(collected.len());
+    for collected_item in collected {
+        let (
+            text_run_index,
+            box_index,
+            font,
+            font_size,
+            white_space,
+            text_orientation,
+            bidi_text_range,
+        ) = match collected_item {
+            CollectedItem::Item(item) => {
+                items.push(item);
+                continue;
+            }
+            CollectedItem::Atomic {
+                box_index,
+                inline_size,
+                soft_wrap_opportunities_around,
+                bidi_text_index,
+            } => {
+                items.push(InlineItem::Atomic {
+                    box_index,
+                    inline_size,
+                    bidi_level: levels[bidi_text_index],
+                    soft_wrap_opportunities_around,
+                });
+                continue;
+            }
+            CollectedItem::TextLine {
+                text_run_index,
+                box_index,
+                font,
+                font_size,
+                white_space,
+                text_orientation,
+                bidi_text_range,
+            } => (
+                text_run_index,
+                box_index,
+                font,
+                font_size,
+                white_space,
+                text_orientation,
+                bidi_text_range,
+            ),
+        };
+        let line = &bidi_text[bidi_text_range.clone()];
+        for (level_run_range, bidi_level) in level_runs(&levels[bidi_text_range]) {
+            let level_run = &line[level_run_range];
+            let direction = if bidi_level.is_rtl() {
+                Direction::Rtl
+            } else {
+                Direction::Ltr
+            };
+            for (orientation_run_range, orientation) in
+                glyph_orientation_runs(level_run, writing_mode, text_orientation)
+            {
+                let orientation_run = &level_run[orientation_run_range];
+                // Shape each run of characters set in the same font at once, so that kerning and
+                // the like apply across segments, then split the glyphs at the same places as the
+                // text.
+                for shaped_run in shape_with_fallback(
+                    font_handle,
+                    orientation_run,
+                    &font,
+                    font_size,
+                    Some(direction),
+                    orientation,
+                ) {
+                    let run = shaped_run.text();
+                    let mut segment_start = 0;
+                    for segment in split_at_soft_wrap_opportunities(run) {
+                        let segment_end = segment_start + segment.len();
+                        let trailing_spaces_start =
+                            segment_start + segment.trim_end_matches(is_space_or_tab).len();
+                        let trailing_spaces_inline_size = if white_space.hangs_trailing_spaces() {
+                            shaped_run.advance_of(trailing_spaces_start..segment_end)
+                        } else {
+                            CSSPixelLength::new(0.)
+                        };
+                        items.push(InlineItem::Text {
+                            text_run_index,
+                            box_index,
+                            glyphs: shaped_run.slice(segment_start..segment_end),
+                            bidi_level,
+                            trailing_spaces_inline_size,
+                            soft_wrap_opportunity_after: white_space.allows_wrapping()
+                                && trailing_spaces_start < segment_end,
+                            collapsible_spaces: white_space.collapses_spaces(),
+                        });
+                        segment_start = segment_end;
+                    }
+                }
+            }
+        }
+    }
+    items
+}
+
+/// Splits `text` after each sequence of spaces and tabs, which are the only soft wrap opportunities
+/// we support.  Each resulting segment is a word followed by its trailing spaces.  Whether `text`
+/// may actually be wrapped at these opportunities is up to `white-space`.
+///
+/// https://drafts.csswg.org/css-text-3/#soft-wrap-opportunity
+fn split_at_soft_wrap_opportunities(text: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    let mut previous_was_space = false;
+    for (index, ch) in text.char_indices() {
+        let is_space = is_space_or_tab(ch);
+        if previous_was_space && !is_space {
+            segments.push(&text[segment_start..index]);
+            segment_start = index;
+        }
+        previous_was_space = is_space;
+    }
+    if segment_start < text.len() {
+        segments.push(&text[segment_start..]);
+    }
+    segments
+}
+
+/// Greedily breaks `items` into lines no longer than `available_inline_size`, breaking at soft wrap
+/// opportunities and forced line breaks.  Lines can still overflow when there's no soft wrap
+/// opportunity early enough, e.g. when a single word is longer than the available inline size.
+///
+/// Besides the spaces in text, there's a soft wrap opportunity before and after each atomic inline
+/// where `white-space` allows wrapping.
+///
+/// https://drafts.csswg.org/css-text-3/#line-break-details
+fn break_lines(
+    items: Vec<InlineItem>,
+    available_inline_size: CSSPixelLength,
+) -> Vec<Vec<InlineItem>> {
+    let mut lines = Vec::new();
+    let mut current_line: Vec<InlineItem> = Vec::new();
+    let mut current_inline_size = CSSPixelLength::new(0.);
+    for item in items {
+        if let InlineItem::LineBreak { .. } = item {
+            current_line.push(item);
+            lines.push(std::mem::take(&mut current_line));
+            current_inline_size = CSSPixelLength::new(0.);
+            continue;
+        }
+        if item.is_content() {
+            // Trailing spaces hang off the end of the line, so they never cause a break.
+            let inline_size_if_placed =
+                current_inline_size + item.inline_size() - item.hanging_inline_size();
+            let can_break = current_line
+                .iter()
+                .rev()
+                .find(|item| item.is_content())
+                .map_or(false, |previous| {
+                    previous.soft_wrap_opportunity_after() || item.soft_wrap_opportunity_before()
+                });
+            if can_break && inline_size_if_placed > available_inline_size {
+                // Inline boxes starting right before the break belong with the content they
+                // contain, so carry them over to the new line.
+                let split_index = current_line
+                    .iter()
+                    .rposition(|item| !matches!(item, InlineItem::BoxStart { .. }))
+                    .map_or(0, |index| index + 1);
+                let carried_over = current_line.split_off(split_index);
+                lines.push(current_line);
+                current_inline_size = carried_over
+                    .iter()
+                    .fold(CSSPixelLength::new(0.), |acc, item| {
+                        acc + item.inline_size()
+                    });
+                current_line = carried_over;
+            }
+        }
+        current_inline_size += item.inline_size();
+        current_line.push(item);
+    }
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+    lines
+}
+
+/// Layout results of an inline formatting context, in coordinates relative to the content edges
+/// of the root inline box.
+struct InlineLayoutResults {
+    /// The content area of each inline box, spanning all of the lines it was placed on.
+    box_rects: Vec<Option<LogicalRect>>,
+    /// The fragments of each text run.
+    text_fragments: Vec<Vec<(GlyphRun, LogicalRect, CSSPixelLength)>>,
+}
+
+/// The bidi embedding levels of an inline formatting context, used to reorder each line.
+#[derive(Debug)]
+struct BidiLevels {
+    paragraph: Level,
+    /// The embedding level of each inline box, which its margins, borders, and padding are
+    /// reordered at.
+    boxes: Vec<Level>,
+    /// Whether the inline-start edge of lines is their right (or bottom) edge, meaning lines are
+    /// placed from right to left.
+    inline_start_is_line_right: bool,
+}
+
+/// Places the items of a line, starting at `line_block_start`, and returns the block size of the
+/// line box.  When `line_over_is_block_end` is set, the line's content is flipped in the block axis
+/// so that ascenders point towards its block-end side.
+fn place_line(
+    line: Vec<InlineItem>,
+    boxes: &[InlineBoxMetrics],
+    bidi_levels: &BidiLevels,
+    line_over_is_block_end: bool,
+    line_block_start: CSSPixelLength,
+    results: &mut InlineLayoutResults,
+) -> CSSPixelLength {
+    let zero = CSSPixelLength::new(0.);
+    let mut on_line = vec![false; boxes.len()];
+    on_line[0] = true;
+    for item in &line {
+        let mut box_index = Some(item.box_index());
+        while let Some(index) = box_index {
+            on_line[index] = true;
+            box_index = boxes[index].parent;
+        }
+    }
+    // https://www.w3.org/TR/CSS2/visuren.html#inline-formatting
+    // > Line boxes that contain no text, no preserved white space, no inline elements with non-zero
+    // > margins, padding, or borders or other in-flow content [...] must be treated as zero-height
+    // > line boxes
+    let is_empty = !line.iter().any(|item| {
+        (item.is_content() && !item.is_collapsible_spaces())
+            || matches!(item, InlineItem::LineBreak { .. })
+            || item.inline_size() != zero
+    });
+
+    let line = remove_collapsible_spaces_at_line_edges(line);
+    let line = reorder_line(line, bidi_levels);
+
+    // Place everything in the inline axis, in visual order.
+    let mut content_ranges: Vec<Option<(CSSPixelLength, CSSPixelLength)>> = vec![None; boxes.len()];
+    // Where the content of each box without any content on this line sits, between its margins,
+    // borders, and padding.
+    let mut empty_content_positions = vec![None; boxes.len()];
+    let mut placed_text = Vec::new();
+    let mut placed_atomics = Vec::new();
+    let mut previous_text_run_index = None;
+    let mut inline_cursor = zero;
+    for item in line {
+        let inline_start = inline_cursor;
+        let inline_size = item.inline_size();
+        inline_cursor += inline_size;
+        // The margins, borders, and padding of an inline box are part of its parent's content, as
+        // is the whole of an atomic inline.
+        let mut content_box_index = match &item {
+            InlineItem::BoxStart { box_index, .. }
+            | InlineItem::BoxEnd { box_index, .. }
+            | InlineItem::Atomic { box_index, .. } => boxes[*box_index].parent,
+            InlineItem::Text { box_index, .. } => Some(*box_index),
+            InlineItem::LineBreak { .. } => None,
+        };
+        while let Some(index) = content_box_index {
+            content_ranges[index] = Some(match content_ranges[index] {
+                Some((start, end)) => (start.min(inline_start), end.max(inline_cursor)),
+                None => (inline_start, inline_cursor),
+            });
+            content_box_index = boxes[index].parent;
+        }
+        let text_run_index = match item {
+            InlineItem::BoxStart { box_index, .. } => {
+                // When reordering puts a box's start after its end, its content is between them.
+                empty_content_positions[box_index] =
+                    Some(if empty_content_positions[box_index].is_some() {
+                        inline_start
+                    } else {
+                        inline_cursor
+                    });
+                None
+            }
+            InlineItem::BoxEnd { box_index, .. } => {
+                empty_content_positions[box_index] = Some(inline_start);
+                None
+            }
+            InlineItem::Text {
+                text_run_index,
+                box_index,
+                glyphs,
+                ..
+            } => {
+                // Pieces of the same text run that end up next to each other on a line form a
+                // single fragment.
+                let continues_fragment = previous_text_run_index == Some(text_run_index);
+                placed_text.push((
+                    text_run_index,
+                    box_index,
+                    glyphs,
+                    inline_start,
+                    inline_size,
+                    continues_fragment,
+                ));
+                Some(text_run_index)
+            }
+            InlineItem::Atomic { box_index, .. } => {
+                placed_atomics.push((box_index, inline_start, inline_size));
+                None
+            }
+            InlineItem::LineBreak { .. } => None,
+        };
+        previous_text_run_index = text_run_index;
+    }
+
+    // Then in the block axis.
+    let alignment = align_line_boxes(boxes, &on_line);
+    let (line_block_size, baseline) = if is_empty {
+        (zero, line_block_start)
+    } else {
+        (
+            alignment.block_size(),
+            line_block_start - alignment.block_start,
+        )
+    };
+    let line_block_end = line_block_start + line_block_size;
+    let box_baseline = |box_index: usize| {
+        let box_baseline = baseline + alignment.baseline_offsets[box_index];
+        if line_over_is_block_end {
+            line_block_end - (box_baseline - line_block_start)
+        } else {
+            box_baseline
+        }
+    };
+    let content_area = |box_index: usize, inline_start, inline_size| {
+        let font = &boxes[box_index].font;
+        let block_start = if line_over_is_block_end {
+            box_baseline(box_index) - font.descent
+        } else {
+            box_baseline(box_index) - font.ascent
+        };
+        LogicalRect {
+            inline_start,
+            block_start,
+            inline_size,
+            block_size: font.content_area_size(),
+        }
+    };
+
+    for (text_run_index, box_index, glyphs, inline_start, inline_size, continues_fragment) in
+        placed_text
+    {
+        let rect = content_area(box_index, inline_start, inline_size);
+        let fragments = &mut results.text_fragments[text_run_index];
+        match fragments.last_mut() {
+            Some((fragment_glyphs, fragment_rect, _))
+                if continues_fragment
+                    && fragment_glyphs.font() == glyphs.font()
+                    && fragment_glyphs.orientation() == glyphs.orientation() =>
+            {
+                // Glyphs are drawn from left to right, while lines may be placed from right to
+                // left.
+                if bidi_levels.inline_start_is_line_right {
+                    let mut merged_glyphs = glyphs;
+                    merged_glyphs.append(fragment_glyphs.clone());
+                    *fragment_glyphs = merged_glyphs;
+                } else {
+                    fragment_glyphs.append(glyphs);
+                }
+                *fragment_rect = fragment_rect.union(rect);
+            }
+            _ => fragments.push((glyphs, rect, box_baseline(box_index))),
+        }
+    }
+    for (box_index, inline_start, inline_size) in placed_atomics {
+        // The block-end margin edge of an atomic inline sits on its baseline, so its margin box
+        // takes up its whole layout bounds.
+        let block_size = boxes[box_index].layout_ascent();
+        let block_start = if line_over_is_block_end {
+            box_baseline(box_index)
+        } else {
+            box_baseline(box_index) - block_size
+        };
+        results.box_rects[box_index] = Some(LogicalRect {
+            inline_start,
+            block_start,
+            inline_size,
+            block_size,
+        });
+    }
+    for box_index in
+        (1..boxes.len()).filter(|&index| on_line[index] && boxes[index].atomic_block_size.is_none())
+    {
+        let (inline_start, inline_end) = content_ranges[box_index]
+            .or_else(|| empty_content_positions[box_index].map(|position| (position, position)))
+            .unwrap_or((zero, inline_cursor));
+        let rect = content_area(box_index, inline_start, inline_end - inline_start);
+        results.box_rects[box_index] = Some(match results.box_rects[box_index] {
+            Some(previous_lines_rect) => previous_lines_rect.union(rect),
+            None => rect,
+        });
+    }
+    line_block_size
+}
+
+/// Removes the collapsible spaces at the start and end of a line.  Text left empty is removed
+/// entirely.
+///
+/// https://drafts.csswg.org/css-text-3/#white-space-phase-2
+fn remove_collapsible_spaces_at_line_edges(line: Vec<InlineItem>) -> Vec<InlineItem> {
+    // > A sequence of collapsible spaces at the beginning of a line is removed.
+    let first_content_index = line
+        .iter()
+        .position(|item| item.is_content() && !item.is_collapsible_spaces())
+        .unwrap_or(line.len());
+    let last_content_index = line.iter().rposition(InlineItem::is_content);
+    line.into_iter()
+        .enumerate()
+        .filter_map(|(item_index, mut item)| {
+            if let InlineItem::Text {
+                glyphs,
+                collapsible_spaces,
+                ..
+            } = &mut item
+            {
+                if item_index < first_content_index && *collapsible_spaces {
+                    return None;
+                }
+                // > A sequence of collapsible spaces at the end of a line is removed.
+                //
+                // Preserved spaces are kept, though they may hang.
+                if Some(item_index) == last_content_index && *collapsible_spaces {
+                    *glyphs =
+                        glyphs.slice(0..glyphs.text().trim_end_matches(is_space_or_tab).len());
+                }
+                if glyphs.text().is_empty() {
+                    return None;
+                }
+            }
+            Some(item)
+        })
+        .collect()
+}
+
+/// Reorders the items of a line from logical order into visual order, starting from the
+/// inline-start edge of the line.  The margins, borders, and padding of inline boxes are reordered
+/// at the embedding level of the box, so that they stay on the start and end sides of the box's own
+/// direction.
+///
+/// https://unicode.org/reports/tr9/#Reordering_Resolved_Levels
+fn reorder_line(line: Vec<InlineItem>, bidi_levels: &BidiLevels) -> Vec<InlineItem> {
+    let mut levels = line
+        .iter()
+        .map(|item| match item {
+            InlineItem::BoxStart { box_index, .. } | InlineItem::BoxEnd { box_index, .. } => {
+                bidi_levels.boxes[*box_index]
+            }
+            InlineItem::Text { bidi_level, .. } | InlineItem::Atomic { bidi_level, .. } => {
+                *bidi_level
+            }
+            InlineItem::LineBreak { .. } => bidi_levels.paragraph,
+        })
+        .collect::<Vec<_>>();
+    // > [...] any sequence of whitespace characters [...] at the end of the line [is reset] to the
+    // > paragraph embedding level.
+    for (item, level) in line.iter().zip(levels.iter_mut()).rev() {
+        match item {
+            InlineItem::Text { glyphs, .. } if glyphs.text().chars().all(is_space_or_tab) => {
+                *level = bidi_levels.paragraph
+            }
+            InlineItem::Text { .. } | InlineItem::Atomic { .. } => break,
+            _ => {}
+        }
+    }
+
+    let mut order = visual_order(&levels);
+    if bidi_levels.inline_start_is_line_right {
+        order.reverse();
+    }
+    let mut items = line.into_iter().map(Some).collect::<Vec<_>>();
+    order
+        .into_iter()
+        .map(|index| items[index].take().expect("item reordered twice"))
+        .collect()
+}
+
+/// The index of the next inline box and text run to visit in `apply_layout_results`.
+struct ContentIndices {
+    box_index: usize,
+    text_run_index: usize,
+}
+
+/// Writes `results` to the boxes and text runs in `children`, visiting them in the same order as
+/// `InlineContentCollector::collect` did.
+fn apply_layout_results(
+    children: &mut [LayoutBox],
+    results: &InlineLayoutResults,
+    coordinates: LineCoordinates,
+    next_indices: &mut ContentIndices,
+) {
+    for child in children {
+        match child {
+            LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(ilb)) => {
+                let box_index = next_indices.box_index;
+                next_indices.box_index += 1;
+                let content =
+                    coordinates.to_physical(results.box_rects[box_index].unwrap_or_default());
+                if !ilb.is_anonymous_inline() {
+                    let cvs = (*ilb.computed_values()).clone();
+                    set_inline_box_edges(ilb.dimensions_mut(), &cvs, coordinates, false);
+                }
+                ilb.dimensions_mut().content = content;
+                let children = match ilb {
+                    InlineLevelBox::AnonymousInline(aib) => aib.children_mut(),
+                    InlineLevelBox::InlineBox(ib) => ib.children_mut(),
+                };
+                apply_layout_results(children, results, coordinates, next_indices);
+            }
+            LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => {
+                let text_run_index = next_indices.text_run_index;
+                next_indices.text_run_index += 1;
+                let logical_fragments = &results.text_fragments[text_run_index];
+                let union = logical_fragments
+                    .iter()
+                    .map(|(_, rect, _)| *rect)
+                    .fold(None, |acc: Option<LogicalRect>, rect| {
+                        Some(acc.map_or(rect, |acc| acc.union(rect)))
+                    })
+                    .unwrap_or_default();
+                text_run.dimensions_mut().content = coordinates.to_physical(union);
+                text_run.set_fragments(
+                    logical_fragments
+                        .iter()
+                        .map(|(glyphs, rect, baseline)| TextFragment {
+                            glyphs: glyphs.clone(),
+                            rect: coordinates.to_physical(*rect),
+                            baseline: coordinates
+                                .to_physical_block_coord(*baseline, CSSPixelLength::new(0.)),
+                        })
+                        .collect(),
+                );
+            }
+            LayoutBox::InlineLevel(InlineLevelContent::Replaced(replaced)) => {
+                let box_index = next_indices.box_index;
+                next_indices.box_index += 1;
+                let margin_box =
+                    coordinates.to_physical(results.box_rects[box_index].unwrap_or_default());
+                let cvs = (*replaced.computed_values()).clone();
+                let dimensions = replaced.dimensions_mut();
+                set_inline_box_edges(dimensions, &cvs, coordinates, true);
+                dimensions.content = margin_box
+                    .shrunk_by_edges(dimensions.margin)
+                    .shrunk_by_edges(dimensions.border)
+                    .shrunk_by_edges(dimensions.padding);
+            }
+            LayoutBox::BlockLevel(_) => {}
+        }
+    }
+}
+
+/// Sets the margins, borders, and padding of an inline box, or of an atomic inline if `atomic` is
+/// set.  Block-axis margins have no effect on inline boxes, while block-axis borders and padding are
+/// painted but don't affect the height of the line box.  All of them take up space around atomic
+/// inlines.
+///
+/// https://www.w3.org/TR/CSS2/visudet.html#inline-non-replaced
+fn set_inline_box_edges(
+    dimensions: &mut Dimensions,
+    cvs: &ComputedValues,
+    coordinates: LineCoordinates,
+    atomic: bool,
+) {
+    let writing_mode = coordinates.writing_mode;
+    let direction = coordinates.direction;
+    let cb_inline_size = coordinates.inline_size();
+    for side in &[
+        FlowSide::InlineStart,
+        FlowSide::InlineEnd,
+        FlowSide::BlockStart,
+        FlowSide::BlockEnd,
+    ] {
+        let margin = match side {
+            FlowSide::BlockStart | FlowSide::BlockEnd if !atomic => CSSPixelLength::new(0.),
+            _ => cvs
+                .margin_flow_relative(*side, writing_mode)
+                .to_px(cb_inline_size),
+        };
+        dimensions.set_margin(*side, margin, writing_mode, direction);
+        dimensions.set_border(
+            *side,
+            cvs.border_flow_relative(*side, writing_mode),
+            writing_mode,
+            direction,
+        );
+        // Padding percentages refer to the inline size of the containing block in both axes.
+        dimensions.set_padding(
+            *side,
+            cvs.padding_flow_relative(*side, writing_mode)
+                .to_px(cb_inline_size),
+            writing_mode,
+            direction,
+        );
+    }
+}
+
+/// The metrics of the primary font of an inline box, which determine its content area and the
+/// position of its baseline.
+fn font_metrics(
+    font_handle: &FontHandle,
+    descriptor: &FontDescriptor,
+    font_size: CSSPixelLength,
+) -> FontMetrics {
+    match font_handle.primary_font(descriptor) {
+        Ok(font) => FontMetrics::new(&font, font_size),
+        Err(_) => FontMetrics::fallback(font_size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::shape::ShapedGlyph;
+    use crate::style::values::computed::length::LengthPercentage;
+    use pathfinder_geometry::vector::Vector2F;
+
+    fn px(px: CSSFloat) -> CSSPixelLength {
+        CSSPixelLength::new(px)
+    }
+
+    fn metrics(parent: Option<usize>, vertical_align: VerticalAlign) -> InlineBoxMetrics {
+        let font_size = px(10.);
+        InlineBoxMetrics {
+            parent,
+            font: FontMetrics::fallback(font_size),
+            font_size,
+            line_height: px(20.),
+            vertical_align,
+            atomic_block_size: None,
+        }
+    }
+
+    fn keyword(keyword: VerticalAlignKeyword) -> VerticalAlign {
+        VerticalAlign::Keyword(keyword)
+    }
+
+    /// A text item where every character of `text` is its own equally wide glyph.
+    fn text(text_run_index: usize, text: &str, inline_size: CSSFloat) -> InlineItem {
+        let glyph_advance = inline_size / text.len() as CSSFloat;
+        let glyphs = (0..text.len())
+            .map(|cluster| ShapedGlyph {
+                glyph_id: 0,
+                cluster,
+                advance: glyph_advance,
+                offset: Vector2F::zero(),
+            })
+            .collect();
+        let trailing_spaces = text.len() - text.trim_end().len();
+        InlineItem::Text {
+            text_run_index,
+            box_index: 0,
+            glyphs: GlyphRun::new(
+                text.to_owned(),
+                glyphs,
+                None,
+                px(10.),
+                GlyphOrientation::Horizontal,
+            ),
+            bidi_level: Level::ltr(),
+            trailing_spaces_inline_size: px(trailing_spaces as CSSFloat * glyph_advance),
+            soft_wrap_opportunity_after: trailing_spaces > 0,
+            collapsible_spaces: true,
+        }
+    }
+
+    fn at_level(mut item: InlineItem, level: u8) -> InlineItem {
+        if let InlineItem::Text { bidi_level, .. } = &mut item {
+            *bidi_level = Level::new(level).unwrap();
+        }
+        item
+    }
+
+    fn texts(line: &[InlineItem]) -> Vec<&str> {
+        line.iter()
+            .filter_map(|item| match item {
+                InlineItem::Text { glyphs, .. } => Some(glyphs.text()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_text_after_spaces() {
+        assert_eq!(
+            split_at_soft_wrap_opportunities("hello  world foo"),
+            vec!["hello  ", "world ", "foo"]
+        );
+        assert_eq!(split_at_soft_wrap_opportunities("word"), vec!["word"]);
+        assert_eq!(
+            split_at_soft_wrap_opportunities(" leading\ttab"),
+            vec![" ", "leading\t", "tab"]
+        );
+    }
+
+    #[test]
+    fn breaks_lines_without_counting_hanging_spaces() {
+        let items = vec![
+            text(0, "aaaa ", 5.),
+            text(0, "bbbb ", 5.),
+            text(0, "cc", 2.),
+        ];
+        let lines = break_lines(items, px(8.));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 1);
+        assert_eq!(lines[1].len(), 2);
+    }
+
+    #[test]
+    fn only_breaks_at_soft_wrap_opportunities() {
+        let mut nowrap = text(0, "aaaa ", 5.);
+        if let InlineItem::Text {
+            soft_wrap_opportunity_after,
+            ..
+        } = &mut nowrap
+        {
+            *soft_wrap_opportunity_after = false;
+        }
+        let items = vec![nowrap, text(0, "bb", 2.), text(1, "cc", 2.)];
+        let lines = break_lines(items, px(4.));
+        // There's no soft wrap opportunity between "bb" and "cc" either.
+        assert_eq!(lines.len(), 1);
+    }
+
+    #[test]
+    fn breaks_around_atomic_inlines() {
+        let atomic = |box_index| InlineItem::Atomic {
+            box_index,
+            inline_size: px(3.),
+            bidi_level: Level::ltr(),
+            soft_wrap_opportunities_around: true,
+        };
+        let items = vec![text(0, "aa", 2.), atomic(1), atomic(2), text(0, "bb", 2.)];
+        let lines = break_lines(items, px(5.));
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(
+            lines[1][..],
+            [InlineItem::Atomic { box_index: 2, .. }]
+        ));
+    }
+
+    #[test]
+    fn forces_line_breaks() {
+        let items = vec![
+            text(0, "a", 1.),
+            InlineItem::LineBreak { box_index: 0 },
+            InlineItem::LineBreak { box_index: 0 },
+            text(0, "b", 1.),
+        ];
+        let lines = break_lines(items, px(100.));
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(lines[1][..], [InlineItem::LineBreak { .. }]));
+    }
+
+    #[test]
+    fn carries_box_start_over_to_next_line() {
+        let items = vec![
+            text(0, "aaaa ", 5.),
+            InlineItem::BoxStart {
+                box_index: 1,
+                inline_size: px(1.),
+            },
+            text(1, "bbbb", 4.),
+        ];
+        let lines = break_lines(items, px(8.));
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(lines[1][0], InlineItem::BoxStart { .. }));
+    }
+
+    #[test]
+    fn reorders_rtl_text_in_ltr_line() {
+        let bidi_levels = BidiLevels {
+            paragraph: Level::ltr(),
+            boxes: vec![Level::ltr(), Level::ltr()],
+            inline_start_is_line_right: false,
+        };
+        let line = vec![
+            text(0, "abc ", 4.),
+            InlineItem::BoxStart {
+                box_index: 1,
+                inline_size: px(1.),
+            },
+            at_level(text(1, "def ", 4.), 1),
+            at_level(text(1, "ghi", 3.), 1),
+            InlineItem::BoxEnd {
+                box_index: 1,
+                inline_size: px(1.),
+            },
+        ];
+        let line = reorder_line(line, &bidi_levels);
+        assert_eq!(texts(&line), vec!["abc ", "ghi", "def "]);
+        // The ltr inline box's start stays on its left.
+        assert!(matches!(line[1], InlineItem::BoxStart { .. }));
+        assert!(matches!(line[4], InlineItem::BoxEnd { .. }));
+    }
+
+    #[test]
+    fn places_rtl_lines_from_inline_start() {
+        let bidi_levels = BidiLevels {
+            paragraph: Level::rtl(),
+            boxes: vec![Level::rtl()],
+            inline_start_is_line_right: true,
+        };
+        // An ltr phrase in an rtl paragraph still reads from left to right, so its last word is
+        // closest to the inline-start (right) edge of the line.
+        let line = vec![
+            at_level(text(0, "hello ", 6.), 2),
+            at_level(text(0, "world", 5.), 2),
+        ];
+        assert_eq!(
+            texts(&reorder_line(line.clone(), &bidi_levels)),
+            vec!["world", "hello "]
+        );
+
+        let boxes = vec![metrics(None, keyword(VerticalAlignKeyword::Baseline))];
+        let mut results = InlineLayoutResults {
+            box_rects: vec![None],
+            text_fragments: vec![Vec::new()],
+        };
+        place_line(line, &boxes, &bidi_levels, false, px(0.), &mut results);
+        let fragments = &results.text_fragments[0];
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].0.text(), "hello world");
+        assert_eq!(fragments[0].1.inline_size, px(11.));
+    }
+
+    #[test]
+    fn mirrors_baseline_when_line_over_is_block_end() {
+        let bidi_levels = BidiLevels {
+            paragraph: Level::ltr(),
+            boxes: vec![Level::ltr()],
+            inline_start_is_line_right: false,
+        };
+        let boxes = vec![metrics(None, keyword(VerticalAlignKeyword::Baseline))];
+        let place = |line_over_is_block_end| {
+            let mut results = InlineLayoutResults {
+                box_rects: vec![None],
+                text_fragments: vec![Vec::new()],
+            };
+            let line_block_size = place_line(
+                vec![text(0, "abc", 3.)],
+                &boxes,
+                &bidi_levels,
+                line_over_is_block_end,
+                px(0.),
+                &mut results,
+            );
+            let (_, rect, baseline) = results.text_fragments[0][0].clone();
+            (line_block_size, rect, baseline)
+        };
+        let (line_block_size, over_at_start, over_at_start_baseline) = place(false);
+        let (_, over_at_end, over_at_end_baseline) = place(true);
+        assert_eq!(
+            over_at_end_baseline,
+            line_block_size - over_at_start_baseline
+        );
+        assert_eq!(
+            over_at_end.block_start,
+            line_block_size - (over_at_start.block_start + over_at_start.block_size)
+        );
+    }
+
+    #[test]
+    fn resets_trailing_spaces_to_paragraph_level() {
+        let bidi_levels = BidiLevels {
+            paragraph: Level::ltr(),
+            boxes: vec![Level::ltr()],
+            inline_start_is_line_right: false,
+        };
+        let mut trailing_spaces = at_level(text(0, "  ", 2.), 1);
+        if let InlineItem::Text {
+            collapsible_spaces, ..
+        } = &mut trailing_spaces
+        {
+            *collapsible_spaces = false;
+        }
+        let line = vec![at_level(text(0, "abc ", 4.), 1), trailing_spaces];
+        assert_eq!(texts(&reorder_line(line, &bidi_levels)), vec!["abc ", "  "]);
+    }
+
+    #[test]
+    fn baseline_aligned_line_is_line_height_tall() {
+        let boxes = vec![
+            metrics(None, keyword(VerticalAlignKeyword::Baseline)),
+            metrics(Some(0), keyword(VerticalAlignKeyword::Baseline)),
+        ];
+        let alignment = align_line_boxes(&boxes, &[true, true]);
+        assert_eq!(alignment.block_size(), px(20.));
+        assert_eq!(alignment.baseline_offsets[1], px(0.));
+    }
+
+    #[test]
+    fn atomic_inlines_sit_on_the_baseline() {
+        let root = metrics(None, keyword(VerticalAlignKeyword::Baseline));
+        let atomic = InlineBoxMetrics {
+            atomic_block_size: Some(px(50.)),
+            ..metrics(Some(0), keyword(VerticalAlignKeyword::Baseline))
+        };
+        let alignment = align_line_boxes(&[root, atomic], &[true, true]);
+        assert_eq!(alignment.block_start, px(-50.));
+        assert_eq!(alignment.block_end, root.layout_descent());
+    }
+
+    #[test]
+    fn sub_and_super_shift_baseline() {
+        let root = metrics(None, keyword(VerticalAlignKeyword::Baseline));
+        let sub = metrics(Some(0), keyword(VerticalAlignKeyword::Sub));
+        let sup = metrics(Some(0), keyword(VerticalAlignKeyword::Super));
+        assert_eq!(baseline_offset_from_parent(&sub, &root), Some(px(2.)));
+        let super_offset = -(px(10.) * SUPER_OFFSET_FACTOR);
+        assert_eq!(baseline_offset_from_parent(&sup, &root), Some(super_offset));
+        // Both the lowered and the raised box need to fit, growing the line box.
+        let alignment = align_line_boxes(&[root, sub, sup], &[true, true, true]);
+        assert_eq!(alignment.block_start, super_offset - px(13.));
+        assert_eq!(alignment.block_end, px(9.));
+    }
+
+    #[test]
+    fn length_raises_box() {
+        let root = metrics(None, keyword(VerticalAlignKeyword::Baseline));
+        let raised = metrics(
+            Some(0),
+            VerticalAlign::LengthPercentage(LengthPercentage::new_len(5.)),
+        );
+        assert_eq!(baseline_offset_from_parent(&raised, &root), Some(px(-5.)));
+    }
+
+    #[test]
+    fn text_top_aligns_with_parent_content_area() {
+        let root = metrics(None, keyword(VerticalAlignKeyword::Baseline));
+        let child = metrics(Some(0), keyword(VerticalAlignKeyword::TextTop));
+        let offset = baseline_offset_from_parent(&child, &root).unwrap();
+        assert_eq!(offset - child.layout_ascent(), -root.font.ascent);
+    }
+
+    #[test]
+    fn bottom_aligned_subtree_sits_at_line_bottom() {
+        let mut tall = metrics(Some(0), keyword(VerticalAlignKeyword::Bottom));
+        tall.line_height = px(40.);
+        let boxes = vec![metrics(None, keyword(VerticalAlignKeyword::Baseline)), tall];
+        let alignment = align_line_boxes(&boxes, &[true, true]);
+        assert_eq!(alignment.block_size(), px(40.));
+        assert_eq!(
+            alignment.baseline_offsets[1] + boxes[1].layout_descent(),
+            alignment.block_end
+        );
+    }
+
+    #[test]
+    fn vertical_rl_lines_progress_leftwards_from_the_block_end_edge() {
+        let coordinates =
+            LineCoordinates::new(5., 100., px(50.), WritingMode::VerticalRl, Direction::Ltr)
+                .with_block_size(px(40.));
+        let line = |block_start| LogicalRect {
+            inline_start: px(10.),
+            block_start: px(block_start),
+            inline_size: px(30.),
+            block_size: px(20.),
+        };
+        let first = coordinates.to_physical(line(0.));
+        let second = coordinates.to_physical(line(20.));
+        assert_eq!((first.start_x, first.start_y), (120., 15.));
+        assert_eq!((second.start_x, second.start_y), (100., 15.));
+        assert_eq!((first.width, first.height), (px(20.), px(30.)));
+        assert_eq!(coordinates.to_physical_block_coord(px(5.), px(0.)), 135.);
+    }
+}
@@ -1,12 +1,18 @@
+pub mod bidi;
 pub mod block;
 pub mod inline;
+pub mod line;
+pub mod orientation;
+pub mod white_space;
 
 use crate::apply_page_relative_properties_base_box_passthrough_impls;
 use crate::dom::tree::NodeRef;
+use crate::gfx::font::FontHandle;
 use crate::layout::behavior::{ApplyPageRelativeProperties, BaseLayoutBoxBehavior};
 use crate::layout::containing_block::ContainingBlock;
 use crate::layout::dimensions::Dimensions;
 use crate::layout::formatting_context::FormattingContextRef;
+use crate::layout::intrinsic::{block_container_intrinsic_sizes, IntrinsicSizes, IntrinsicSizing};
 use crate::layout::layout_box::{BaseBox, LayoutBox};
 use crate::layout::DumpLayoutFormat;
 use crate::layout_box_behavior_base_box_passthrough_impls;
@@ -55,6 +61,18 @@ impl ApplyPageRelativeProperties for BlockContainer {
     apply_page_relative_properties_base_box_passthrough_impls!();
 }
 
+impl IntrinsicSizing for BlockContainer {
+    fn intrinsic_sizes(&self, font_handle: &FontHandle) -> IntrinsicSizes {
+        self.base.intrinsic_sizes_or_else(|| {
+            block_container_intrinsic_sizes(
+                &self.children,
+                font_handle,
+                self.computed_values().get_inherited_box().writing_mode,
+            )
+        })
+    }
+}
+
 impl DumpLayoutFormat for BlockContainer {
     fn dump_layout_format(&self) -> String {
         let node_data = self.node().data().dump_layout_format();
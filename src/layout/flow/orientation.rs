@@ -0,0 +1,135 @@
+use crate::gfx::shape::GlyphOrientation;
+use crate::style::values::computed::{TextOrientation, WritingMode};
+use std::ops::Range;
+
+/// Splits `text` into runs of characters that are set in the same orientation, given the writing
+/// mode of the line they're on and their `text-orientation`.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#text-orientation
+pub fn glyph_orientation_runs(
+    text: &str,
+    writing_mode: WritingMode,
+    text_orientation: TextOrientation,
+) -> Vec<(Range<usize>, GlyphOrientation)> {
+    let uniform_orientation = match (writing_mode, text_orientation) {
+        (WritingMode::HorizontalTb, _) => Some(GlyphOrientation::Horizontal),
+        (WritingMode::SidewaysRl, _) => Some(GlyphOrientation::SidewaysRight),
+        (WritingMode::SidewaysLr, _) => Some(GlyphOrientation::SidewaysLeft),
+        (_, TextOrientation::Upright) => Some(GlyphOrientation::Upright),
+        (_, TextOrientation::Sideways) => Some(GlyphOrientation::SidewaysRight),
+        (_, TextOrientation::Mixed) => None,
+    };
+    if let Some(orientation) = uniform_orientation {
+        return if text.is_empty() {
+            Vec::new()
+        } else {
+            vec![(0..text.len(), orientation)]
+        };
+    }
+
+    let mut runs: Vec<(Range<usize>, GlyphOrientation)> = Vec::new();
+    for (index, ch) in text.char_indices() {
+        let orientation = if is_upright_in_mixed_orientation(ch) {
+            GlyphOrientation::Upright
+        } else {
+            GlyphOrientation::SidewaysRight
+        };
+        let end = index + ch.len_utf8();
+        match runs.last_mut() {
+            Some((range, run_orientation)) if *run_orientation == orientation => range.end = end,
+            _ => runs.push((index..end, orientation)),
+        }
+    }
+    runs
+}
+
+/// Whether `text-orientation: upright` applies to text in `writing_mode`.  Such text is always set
+/// left to right, regardless of the directionality of its characters.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#valdef-text-orientation-upright
+pub fn forces_ltr(writing_mode: WritingMode, text_orientation: TextOrientation) -> bool {
+    writing_mode.is_vertical_typographic_mode() && text_orientation == TextOrientation::Upright
+}
+
+/// Whether `ch` is set upright by `text-orientation: mixed`, meaning its `Vertical_Orientation` is
+/// `U` or `Tu`.  This approximates the property with the blocks of vertical scripts and East Asian
+/// symbols that make up nearly all of its upright ranges.
+///
+/// https://www.unicode.org/reports/tr50/
+fn is_upright_in_mixed_orientation(ch: char) -> bool {
+    match ch as u32 {
+        // Latin-1 symbols such as § © ® ± ¼ ½ ¾ × ÷.
+        0xA7 | 0xA9 | 0xAE | 0xB1 | 0xBC..=0xBE | 0xD7 | 0xF7 => true,
+        // Hangul Jamo.
+        0x1100..=0x11FF => true,
+        // Canadian Syllabics.
+        0x1400..=0x167F | 0x18B0..=0x18FF => true,
+        // Control Pictures through Enclosed Alphanumerics, and Miscellaneous Symbols.
+        0x2400..=0x24FF | 0x2600..=0x26FF => true,
+        // CJK Radicals Supplement through Yi Radicals, except for the long vowel mark and
+        // brackets of CJK Symbols and Punctuation, which rotate.
+        0x3008..=0x3011 | 0x3014..=0x301F | 0x30FC => false,
+        0x2E80..=0xA4CF => true,
+        // Hangul Syllables and Jamo Extended-B.
+        0xA960..=0xA97F | 0xAC00..=0xD7FF => true,
+        // CJK Compatibility Ideographs, Vertical Forms, and CJK Compatibility Forms.
+        0xF900..=0xFAFF | 0xFE10..=0xFE1F | 0xFE30..=0xFE4F => true,
+        // Fullwidth forms (but not halfwidth ones).
+        0xFF01..=0xFF60 | 0xFFE0..=0xFFE7 => true,
+        // Supplementary planes of CJK ideographs, and emoji.
+        0x1F000..=0x1FAFF | 0x20000..=0x3FFFD => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_sets_cjk_upright_and_latin_sideways() {
+        let runs = glyph_orientation_runs(
+            "日本語 abc",
+            WritingMode::VerticalRl,
+            TextOrientation::Mixed,
+        );
+        assert_eq!(
+            runs,
+            vec![
+                (0..9, GlyphOrientation::Upright),
+                (9..13, GlyphOrientation::SidewaysRight)
+            ]
+        );
+    }
+
+    #[test]
+    fn text_orientation_only_applies_in_vertical_typographic_mode() {
+        for &(writing_mode, orientation) in &[
+            (WritingMode::HorizontalTb, GlyphOrientation::Horizontal),
+            (WritingMode::SidewaysRl, GlyphOrientation::SidewaysRight),
+            (WritingMode::SidewaysLr, GlyphOrientation::SidewaysLeft),
+            (WritingMode::VerticalLr, GlyphOrientation::Upright),
+        ] {
+            assert_eq!(
+                glyph_orientation_runs("日本 ab", writing_mode, TextOrientation::Upright),
+                vec![(0..9, orientation)]
+            );
+        }
+        assert!(
+            glyph_orientation_runs("", WritingMode::VerticalLr, TextOrientation::Mixed).is_empty()
+        );
+    }
+
+    #[test]
+    fn upright_forces_ltr_in_vertical_writing_modes() {
+        assert!(forces_ltr(
+            WritingMode::VerticalRl,
+            TextOrientation::Upright
+        ));
+        assert!(!forces_ltr(WritingMode::VerticalRl, TextOrientation::Mixed));
+        assert!(!forces_ltr(
+            WritingMode::SidewaysRl,
+            TextOrientation::Upright
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::layout::behavior::BaseLayoutBoxBehavior;
+use crate::layout::flow::inline::InlineLevelContent;
+use crate::layout::layout_box::LayoutBox;
+use crate::style::values::computed::WhiteSpace;
+
+/// Whether `ch` is a space or tab, which are the only characters `white-space` can collapse besides
+/// segment breaks.
+///
+/// https://drafts.csswg.org/css-text-3/#white-space-processing
+pub fn is_space_or_tab(ch: char) -> bool {
+    ch == ' ' || ch == '\t'
+}
+
+/// Whether `text` consists entirely of white space that `white_space` collapses.  Such text
+/// doesn't start a new inline formatting context, since it would only be collapsed away.
+///
+/// https://www.w3.org/TR/CSS2/visuren.html#anonymous
+pub fn is_collapsible_white_space(text: &str, white_space: WhiteSpace) -> bool {
+    white_space.collapses_spaces()
+        && text.chars().all(|ch| {
+            is_space_or_tab(ch) || (ch == '\n' && !white_space.preserves_segment_breaks())
+        })
+}
+
+/// Performs the first phase of white space processing (collapsing and transformation) on the text
+/// runs of the inline formatting context rooted at the box whose children are `children`.  Text
+/// runs left empty are removed.
+///
+/// Collapsible spaces at the start and end of each line are removed later, during line layout.
+///
+/// https://drafts.csswg.org/css-text-3/#white-space-phase-1
+pub fn collapse_white_space(children: &mut Vec<LayoutBox>) {
+    // Spaces at the start of the inline formatting context would be removed from the start of the
+    // first line anyways, so treat the start as if it were preceded by a collapsible space.
+    let mut follows_collapsible_space = true;
+    collapse_white_space_in(children, &mut follows_collapsible_space);
+}
+
+fn collapse_white_space_in(children: &mut Vec<LayoutBox>, follows_collapsible_space: &mut bool) {
+    for child in children.iter_mut() {
+        match child {
+            LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(ilb)) => {
+                collapse_white_space_in(ilb.children_mut(), follows_collapsible_space)
+            }
+            LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => {
+                let white_space = text_run.computed_values().get_inherited_text().white_space;
+                let collapsed =
+                    collapse_text(&text_run.contents(), white_space, follows_collapsible_space);
+                text_run.set_contents(collapsed);
+            }
+            // Spaces don't collapse across atomic inlines, which are neither spaces nor removable.
+            LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => {
+                *follows_collapsible_space = false
+            }
+            LayoutBox::BlockLevel(_) => {}
+        }
+    }
+    children.retain(|child| match child {
+        LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => {
+            !text_run.contents().is_empty()
+        }
+        _ => true,
+    });
+}
+
+/// Collapses and transforms the white space in `text`.  `follows_collapsible_space` carries over
+/// whether the previous text in the inline formatting context ended in a collapsible space, since
+/// spaces collapse across inline box boundaries.
+fn collapse_text(
+    text: &str,
+    white_space: WhiteSpace,
+    follows_collapsible_space: &mut bool,
+) -> String {
+    if !white_space.collapses_spaces() {
+        if !text.is_empty() {
+            *follows_collapsible_space = false;
+        }
+        return text.to_owned();
+    }
+
+    // > Any sequence of collapsible spaces and tabs immediately preceding or following a segment
+    // > break is removed.
+    let line_count = text.split('\n').count();
+    let lines = text.split('\n').enumerate().map(|(index, line)| {
+        let line = if index > 0 {
+            line.trim_start_matches(is_space_or_tab)
+        } else {
+            line
+        };
+        if index + 1 < line_count {
+            line.trim_end_matches(is_space_or_tab)
+        } else {
+            line
+        }
+    });
+    // > Collapsible segment breaks are transformed for rendering according to the segment break
+    // > transformation rules.
+    //
+    // TODO: Segment breaks between East Asian wide characters should be removed rather than
+    // converted to a space.
+    let segment_break = if white_space.preserves_segment_breaks() {
+        "\n"
+    } else {
+        " "
+    };
+    let transformed = lines.collect::<Vec<_>>().join(segment_break);
+
+    let mut collapsed = String::with_capacity(transformed.len());
+    for ch in transformed.chars() {
+        // > Every collapsible tab is converted to a collapsible space.
+        let ch = if ch == '\t' { ' ' } else { ch };
+        // > Any collapsible space immediately following another collapsible space—even one outside
+        // > the boundary of the inline containing that space, provided both spaces are within the
+        // > same inline formatting context—is collapsed to have zero advance width.
+        if ch == ' ' && *follows_collapsible_space {
+            continue;
+        }
+        // Spaces following a preserved segment break would be removed from the start of the next
+        // line anyways.
+        *follows_collapsible_space = ch == ' ' || ch == '\n';
+        collapsed.push(ch);
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collapse(text: &str, white_space: WhiteSpace) -> String {
+        collapse_text(text, white_space, &mut false)
+    }
+
+    #[test]
+    fn collapses_spaces_tabs_and_segment_breaks() {
+        assert_eq!(
+            collapse("  hello \t\n  world\n\n", WhiteSpace::Normal),
+            " hello world "
+        );
+        assert_eq!(
+            collapse("  hello \t\n  world", WhiteSpace::Nowrap),
+            " hello world"
+        );
+    }
+
+    #[test]
+    fn pre_line_preserves_segment_breaks() {
+        assert_eq!(
+            collapse("hello  \n  world\n\nagain", WhiteSpace::PreLine),
+            "hello\nworld\n\nagain"
+        );
+    }
+
+    #[test]
+    fn preserves_white_space() {
+        for white_space in &[
+            WhiteSpace::Pre,
+            WhiteSpace::PreWrap,
+            WhiteSpace::BreakSpaces,
+        ] {
+            assert_eq!(collapse(" a \t\n  b ", *white_space), " a \t\n  b ");
+        }
+    }
+
+    #[test]
+    fn collapses_across_text_runs() {
+        let mut follows_collapsible_space = false;
+        assert_eq!(
+            collapse_text("hello ", WhiteSpace::Normal, &mut follows_collapsible_space),
+            "hello "
+        );
+        assert_eq!(
+            collapse_text(" world", WhiteSpace::Normal, &mut follows_collapsible_space),
+            "world"
+        );
+        // Preserved spaces never collapse, and aren't collapsed into either.
+        assert_eq!(
+            collapse_text("  ", WhiteSpace::Pre, &mut follows_collapsible_space),
+            "  "
+        );
+        assert_eq!(
+            collapse_text(" again", WhiteSpace::Normal, &mut follows_collapsible_space),
+            " again"
+        );
+    }
+
+    #[test]
+    fn detects_collapsible_white_space() {
+        assert!(is_collapsible_white_space(" \n\t", WhiteSpace::Normal));
+        assert!(!is_collapsible_white_space(" \n", WhiteSpace::PreLine));
+        assert!(!is_collapsible_white_space("  ", WhiteSpace::Pre));
+        assert!(!is_collapsible_white_space(" a ", WhiteSpace::Normal));
+    }
+}
@@ -0,0 +1,196 @@
+//! Finding what lies under a point of the laid-out box tree, e.g. under the mouse pointer.
+
+use crate::dom::tree::NodeRef;
+use crate::layout::behavior::BaseLayoutBoxBehavior;
+use crate::layout::flow::block::BlockLevelBox;
+use crate::layout::flow::inline::{InlineLevelBox, InlineLevelContent};
+use crate::layout::layout_box::LayoutBox;
+use crate::style::values::CSSFloat;
+
+/// Returns the nodes of the boxes of `layout_box` and its descendants under the point (`x`, `y`),
+/// in CSS pixels, from the topmost to the bottommost in painting order.  Each node is returned
+/// once, even if it generated more than one box under the point.  For text, this is the text node
+/// itself.
+///
+/// Boxes are hit by their border box, with its corners rounded by `border-radius`, and text runs by
+/// the rects of their line fragments.  The descendants of a box whose `overflow` clips them can
+/// only be hit inside its rounded padding box, where they're painted.  Painting order is tree order
+/// for now, matching the display list, as nothing creates a stacking context until `z-index` is
+/// supported.
+///
+/// https://www.w3.org/TR/CSS22/zindex.html
+/// https://www.w3.org/TR/css-backgrounds-3/#corner-clipping
+pub fn hit_test(layout_box: &LayoutBox, x: CSSFloat, y: CSSFloat) -> Vec<NodeRef> {
+    let mut hits = Vec::new();
+    collect_hits(layout_box, x, y, &mut hits);
+    // Hits were collected in painting order, so the topmost box is last.
+    hits.reverse();
+    let mut nodes: Vec<NodeRef> = Vec::with_capacity(hits.len());
+    for node in hits {
+        if !nodes.contains(&node) {
+            nodes.push(node);
+        }
+    }
+    nodes
+}
+
+/// Pushes the nodes of the boxes of `layout_box` and its descendants under the point onto `hits`,
+/// in painting order.
+fn collect_hits(layout_box: &LayoutBox, x: CSSFloat, y: CSSFloat, hits: &mut Vec<NodeRef>) {
+    let hit = match layout_box {
+        LayoutBox::BlockLevel(BlockLevelBox::BlockContainer(_))
+        | LayoutBox::BlockLevel(BlockLevelBox::Replaced(_))
+        | LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(InlineLevelBox::InlineBox(
+            _,
+        )))
+        | LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => {
+            layout_box.rounded_border_box().contains(x, y)
+        }
+        LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => text_run
+            .fragments()
+            .iter()
+            .any(|fragment| fragment.rect.contains(x, y)),
+        // Anonymous boxes aren't generated by their node, so the node isn't under the point
+        // because they are.
+        LayoutBox::BlockLevel(BlockLevelBox::AnonymousBlock(_))
+        | LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(
+            InlineLevelBox::AnonymousInline(_),
+        )) => false,
+    };
+    if hit {
+        hits.push(layout_box.node());
+    }
+    // Nothing is painted outside the clip, so nothing there can be hit.
+    if let Some(clip) = layout_box.overflow_clip() {
+        if !clip.contains(x, y) {
+            return;
+        }
+    }
+    if let Some(children) = layout_box.children() {
+        for child in children {
+            collect_hits(child, x, y, hits);
+        }
+    }
+}
+
+/// Returns the path from the root element to `node` (or to its parent element, if it isn't an
+/// element itself), e.g. `html > body > div#main > p.intro.wide`.
+pub fn element_path(node: &NodeRef) -> String {
+    let mut path = node
+        .inclusive_ancestors()
+        .filter_map(|node| {
+            let element = node.as_element()?;
+            let attributes = element.attributes.borrow();
+            let mut step = element.name.local.to_string();
+            if let Some(id) = attributes.get(local_name!("id")) {
+                step.push('#');
+                step.push_str(id);
+            }
+            if let Some(classes) = attributes.get(local_name!("class")) {
+                for class in classes.split_ascii_whitespace() {
+                    step.push('.');
+                    step.push_str(class);
+                }
+            }
+            Some(step)
+        })
+        .collect::<Vec<_>>();
+    path.reverse();
+    path.join(" > ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::gfx::font::FontHandle;
+    use crate::layout::{global_layout, LayoutViewportDimensions};
+    use crate::page::Page;
+    use crate::style::dom_integration::DocumentStyles;
+    use crate::style::stylesheet::parse_css_to_stylesheet;
+
+    fn hit_paths(html: &str, css: &str, x: CSSFloat, y: CSSFloat) -> Vec<String> {
+        let sheet = parse_css_to_stylesheet(None, &mut css.to_owned()).unwrap();
+        let styles = DocumentStyles::new(vec![], vec![], vec![sheet], vec![]);
+        let page = Page::new(parse_html().one(html), styles);
+        let mut box_tree = page.box_tree().unwrap();
+        global_layout(
+            &mut box_tree,
+            LayoutViewportDimensions::new_px(400., 300.),
+            1.0,
+            &FontHandle::new(),
+        );
+        hit_test(&box_tree, x, y).iter().map(element_path).collect()
+    }
+
+    const CSS: &str = "
+        html, body, div { display: block }
+        div { height: 20px }
+        .wide { width: 200px }
+        .narrow { width: 50px }
+        .bordered { border-left-style: solid; border-left-width: 10px }
+        .clipping { overflow: hidden }
+        .tall { height: 60px }
+        .rounded { border-radius: 20px }
+    ";
+
+    #[test]
+    fn returns_boxes_from_topmost_to_bottommost() {
+        let html = r#"<div id="outer" class="wide"><div class="narrow"></div></div>"#;
+        assert_eq!(
+            hit_paths(html, CSS, 10., 10.),
+            vec![
+                "html > body > div#outer.wide > div.narrow",
+                "html > body > div#outer.wide",
+                "html > body",
+                "html",
+            ]
+        );
+        assert_eq!(
+            hit_paths(html, CSS, 100., 10.),
+            vec!["html > body > div#outer.wide", "html > body", "html"]
+        );
+    }
+
+    #[test]
+    fn boxes_are_hit_by_their_border_box() {
+        let html = r#"<div class="narrow bordered"></div><div class="wide"></div>"#;
+        assert_eq!(
+            hit_paths(html, CSS, 55., 10.),
+            vec!["html > body > div.narrow.bordered", "html > body", "html"]
+        );
+        assert_eq!(hit_paths(html, CSS, 65., 10.), vec!["html > body", "html"]);
+        assert_eq!(
+            hit_paths(html, CSS, 65., 30.),
+            vec!["html > body > div.wide", "html > body", "html"]
+        );
+    }
+
+    #[test]
+    fn clipped_descendants_are_only_hit_inside_the_clip() {
+        let html = r#"<div class="narrow clipping"><div class="wide"></div></div>"#;
+        assert_eq!(
+            hit_paths(html, CSS, 40., 10.),
+            vec![
+                "html > body > div.narrow.clipping > div.wide",
+                "html > body > div.narrow.clipping",
+                "html > body",
+                "html",
+            ]
+        );
+        // The child overflows its parent here, but is clipped away.
+        assert_eq!(hit_paths(html, CSS, 100., 10.), vec!["html > body", "html"]);
+    }
+
+    #[test]
+    fn rounded_corners_are_not_hit() {
+        let html = r#"<div class="wide tall rounded"></div>"#;
+        assert_eq!(hit_paths(html, CSS, 1., 1.), vec!["html > body", "html"]);
+        assert_eq!(
+            hit_paths(html, CSS, 20., 1.),
+            vec!["html > body > div.wide.tall.rounded", "html > body", "html"]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use crate::gfx::font::FontHandle;
+use crate::layout::behavior::BaseLayoutBoxBehavior;
+use crate::layout::flow::block::BlockLevelBox;
+use crate::layout::flow::inline::{InlineLevelBox, InlineLevelContent};
+use crate::layout::flow::FlowSide;
+use crate::layout::layout_box::LayoutBox;
+use crate::style::values::computed::length::{
+    CSSPixelLength, LengthPercentage, LengthPercentageOrAuto,
+};
+use crate::style::values::computed::WritingMode;
+use crate::style::values::specified::width::ContentSizeKeyword;
+use crate::style::values::used::ToPx;
+use enum_dispatch::enum_dispatch;
+
+/// The min-content and max-content inline sizes of a box's content.
+///
+/// https://drafts.csswg.org/css-sizing-3/#intrinsic-sizes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IntrinsicSizes {
+    /// The narrowest inline size the content can take without overflowing, e.g. the inline size
+    /// of the longest word in a run of text.
+    pub min_content: CSSPixelLength,
+    /// The inline size the content would take given infinite available space, e.g. the inline
+    /// size of a run of text laid out without any soft wraps.
+    pub max_content: CSSPixelLength,
+    /// The parts of the content that can't be wrapped away from what's placed before and after it
+    /// on a line.
+    pub edges: InlineEdges,
+}
+
+impl IntrinsicSizes {
+    pub fn new(min_content: CSSPixelLength, max_content: CSSPixelLength) -> Self {
+        IntrinsicSizes {
+            min_content,
+            max_content,
+            edges: InlineEdges::default(),
+        }
+    }
+
+    pub fn with_edges(self, edges: InlineEdges) -> Self {
+        IntrinsicSizes { edges, ..self }
+    }
+
+    /// Returns the fit-content size (also known as the shrink-to-fit size) for the given amount of
+    /// available inline space.
+    ///
+    /// https://drafts.csswg.org/css-sizing-3/#fit-content-size
+    pub fn fit_content(&self, available_space: CSSPixelLength) -> CSSPixelLength {
+        self.max_content.min(self.min_content.max(available_space))
+    }
+
+    /// Resolves the given content-based sizing keyword against these intrinsic sizes.
+    pub fn resolve_keyword(
+        &self,
+        keyword: ContentSizeKeyword,
+        available_space: CSSPixelLength,
+    ) -> CSSPixelLength {
+        match keyword {
+            ContentSizeKeyword::MinContent => self.min_content,
+            ContentSizeKeyword::MaxContent => self.max_content,
+            ContentSizeKeyword::FitContent => self.fit_content(available_space),
+        }
+    }
+
+    /// Adds `start` and `end` to both the min-content and max-content sizes, and to the edges
+    /// they're at.
+    fn add(self, start: CSSPixelLength, end: CSSPixelLength) -> Self {
+        IntrinsicSizes {
+            min_content: self.min_content + start + end,
+            max_content: self.max_content + start + end,
+            edges: self.edges.add(start, end),
+        }
+    }
+}
+
+/// The unbreakable runs at the start and end of some inline-level content, which share a line with
+/// the content placed next to them unless there's a soft wrap opportunity in between.  Content
+/// that can't be wrapped anywhere is a single run, which is both of them.
+///
+/// The default is content that can be wrapped before and after, such as a replaced element.
+///
+/// https://drafts.csswg.org/css-text-3/#line-breaking
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InlineEdges {
+    /// The inline size of the content before its first soft wrap opportunity.
+    pub leading: CSSPixelLength,
+    /// The inline size of the content after its last soft wrap opportunity.
+    pub trailing: CSSPixelLength,
+    /// Whether the content has no soft wrap opportunity, in which case `leading` and `trailing`
+    /// are both its whole inline size.
+    pub unbreakable: bool,
+}
+
+impl InlineEdges {
+    /// Content that can't be wrapped anywhere, and is `size` long.
+    pub fn unbreakable(size: CSSPixelLength) -> Self {
+        InlineEdges {
+            leading: size,
+            trailing: size,
+            unbreakable: true,
+        }
+    }
+
+    /// Adds `start` to the start of the content, and `end` to its end.
+    fn add(self, start: CSSPixelLength, end: CSSPixelLength) -> Self {
+        if self.unbreakable {
+            InlineEdges::unbreakable(self.leading + start + end)
+        } else {
+            InlineEdges {
+                leading: self.leading + start,
+                trailing: self.trailing + end,
+                unbreakable: false,
+            }
+        }
+    }
+}
+
+/// Behavior for computing the intrinsic inline sizes of the content of a box, in the box's own
+/// writing mode.  Implementations should cache their result on the box, since computing
+/// intrinsic sizes requires visiting the entire subtree.
+///
+/// https://drafts.csswg.org/css-sizing-3/#intrinsic
+#[enum_dispatch(LayoutBox, BlockLevelBox, InlineLevelBox, InlineLevelContent)]
+pub trait IntrinsicSizing {
+    fn intrinsic_sizes(&self, font_handle: &FontHandle) -> IntrinsicSizes;
+
+    fn min_content_inline_size(&self, font_handle: &FontHandle) -> CSSPixelLength {
+        self.intrinsic_sizes(font_handle).min_content
+    }
+
+    fn max_content_inline_size(&self, font_handle: &FontHandle) -> CSSPixelLength {
+        self.intrinsic_sizes(font_handle).max_content
+    }
+}
+
+/// Returns the min-content and max-content contributions of `layout_box` to a parent laid out in
+/// `writing_mode`.  A contribution is the outer size of the box, meaning the inline-axis margins,
+/// borders, and padding are included.
+///
+/// Percentages are resolved against an indefinite size here, and are therefore treated as zero
+/// (or `auto`, for the inline size itself).
+///
+/// https://drafts.csswg.org/css-sizing-3/#contributions
+pub fn intrinsic_contributions(
+    layout_box: &LayoutBox,
+    font_handle: &FontHandle,
+    writing_mode: WritingMode,
+) -> IntrinsicSizes {
+    let intrinsic_sizes = layout_box.intrinsic_sizes(font_handle);
+    let is_anonymous_or_text = match layout_box {
+        LayoutBox::BlockLevel(BlockLevelBox::AnonymousBlock(_))
+        | LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(
+            InlineLevelBox::AnonymousInline(_),
+        ))
+        | LayoutBox::InlineLevel(InlineLevelContent::TextRun(_)) => true,
+        LayoutBox::BlockLevel(BlockLevelBox::BlockContainer(_))
+        | LayoutBox::BlockLevel(BlockLevelBox::Replaced(_))
+        | LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(
+            InlineLevelBox::InlineBox(_),
+        ))
+        | LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => false,
+    };
+    if is_anonymous_or_text {
+        // Anonymous boxes and text runs borrow the computed values of the nearest element, so
+        // those values don't describe any margins, borders, or padding of their own.
+        return intrinsic_sizes;
+    }
+
+    let cvs = layout_box.computed_values();
+    let indefinite = CSSPixelLength::new(0.);
+    let content_sizes = match cvs.inline_size_content_keyword(writing_mode) {
+        Some(ContentSizeKeyword::MinContent) => {
+            IntrinsicSizes::new(intrinsic_sizes.min_content, intrinsic_sizes.min_content)
+        }
+        Some(ContentSizeKeyword::MaxContent) => {
+            IntrinsicSizes::new(intrinsic_sizes.max_content, intrinsic_sizes.max_content)
+        }
+        Some(ContentSizeKeyword::FitContent) | None => match cvs.inline_size(writing_mode) {
+            LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Length(length)) => {
+                IntrinsicSizes::new(length, length)
+            }
+            LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Percentage(_))
+            | LengthPercentageOrAuto::Auto => intrinsic_sizes,
+        },
+    };
+    let mbp = |side: FlowSide| {
+        cvs.margin_flow_relative(side, writing_mode)
+            .to_px(indefinite)
+            + cvs.border_flow_relative(side, writing_mode)
+            + cvs
+                .padding_flow_relative(side, writing_mode)
+                .to_px(indefinite)
+    };
+    content_sizes
+        .with_edges(intrinsic_sizes.edges)
+        .add(mbp(FlowSide::InlineStart), mbp(FlowSide::InlineEnd))
+}
+
+/// Intrinsic sizes of a block container, whose children are stacked in the block direction.  The
+/// box is as wide as its widest child.
+pub fn block_container_intrinsic_sizes(
+    children: &[LayoutBox],
+    font_handle: &FontHandle,
+    writing_mode: WritingMode,
+) -> IntrinsicSizes {
+    children
+        .iter()
+        .map(|child| intrinsic_contributions(child, font_handle, writing_mode))
+        .fold(IntrinsicSizes::default(), |acc, contribution| {
+            IntrinsicSizes::new(
+                acc.min_content.max(contribution.min_content),
+                acc.max_content.max(contribution.max_content),
+            )
+        })
+}
+
+/// Intrinsic sizes of an inline box, whose children are placed next to each other in the inline
+/// direction.  Without any soft wrap opportunities every child shares one line, so the max-content
+/// size is the sum of the children's contributions.  Wrapping at every soft wrap opportunity gives
+/// the min-content size, which is the longest run of content between two of them, even if that run
+/// spans several children.
+pub fn inline_box_intrinsic_sizes(
+    children: &[LayoutBox],
+    font_handle: &FontHandle,
+    writing_mode: WritingMode,
+) -> IntrinsicSizes {
+    let mut min_content = CSSPixelLength::new(0.);
+    let mut max_content = CSSPixelLength::new(0.);
+    // The unbreakable run before the first soft wrap opportunity, once one is found.
+    let mut leading = None;
+    // The unbreakable run since the last soft wrap opportunity.
+    let mut run = CSSPixelLength::new(0.);
+    for child in children {
+        let contribution = intrinsic_contributions(child, font_handle, writing_mode);
+        min_content = min_content.max(contribution.min_content);
+        max_content += contribution.max_content;
+        run += contribution.edges.leading;
+        if !contribution.edges.unbreakable {
+            min_content = min_content.max(run);
+            leading.get_or_insert(run);
+            run = contribution.edges.trailing;
+        }
+    }
+    min_content = min_content.max(run);
+    let edges = match leading {
+        Some(leading) => InlineEdges {
+            leading,
+            trailing: run,
+            unbreakable: false,
+        },
+        None => InlineEdges::unbreakable(run),
+    };
+    IntrinsicSizes::new(min_content, max_content).with_edges(edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::layout::box_tree::build_box_tree;
+    use crate::style::dom_integration::apply_styles;
+    use crate::style::stylesheet::parse_css_to_stylesheet;
+
+    /// The min-content size of the box generated by the element with each of the given ids, in
+    /// order, in a document of `html`.
+    fn min_content_sizes(html: &str, ids: &[&str]) -> Vec<CSSPixelLength> {
+        let sheet =
+            parse_css_to_stylesheet(None, &mut "html, body, div { display: block }".to_owned())
+                .unwrap();
+        let dom = parse_html().one(html);
+        apply_styles(dom.clone(), &[], &[], &[sheet], &[]);
+        let box_tree = build_box_tree(dom, None).unwrap();
+        let font_handle = FontHandle::new();
+        fn find<'a>(layout_box: &'a LayoutBox, id: &str) -> Option<&'a LayoutBox> {
+            let node = layout_box.node();
+            let has_id = node.as_element().map_or(false, |element| {
+                element.attributes.borrow().get(local_name!("id")) == Some(id)
+            });
+            if has_id {
+                return Some(layout_box);
+            }
+            layout_box
+                .children()?
+                .iter()
+                .find_map(|child| find(child, id))
+        }
+        ids.iter()
+            .map(|id| {
+                find(&box_tree, id)
+                    .unwrap()
+                    .min_content_inline_size(&font_handle)
+            })
+            .collect()
+    }
+
+    fn sizes(min_content: f32, max_content: f32) -> IntrinsicSizes {
+        IntrinsicSizes::new(
+            CSSPixelLength::new(min_content),
+            CSSPixelLength::new(max_content),
+        )
+    }
+
+    #[test]
+    fn fit_content_clamps_available_space_between_min_and_max_content() {
+        let intrinsic_sizes = sizes(50., 200.);
+        assert_eq!(
+            intrinsic_sizes.fit_content(CSSPixelLength::new(100.)),
+            CSSPixelLength::new(100.)
+        );
+        assert_eq!(
+            intrinsic_sizes.fit_content(CSSPixelLength::new(500.)),
+            CSSPixelLength::new(200.)
+        );
+        assert_eq!(
+            intrinsic_sizes.fit_content(CSSPixelLength::new(10.)),
+            CSSPixelLength::new(50.)
+        );
+    }
+
+    #[test]
+    fn resolve_keyword() {
+        let intrinsic_sizes = sizes(50., 200.);
+        let available_space = CSSPixelLength::new(100.);
+        assert_eq!(
+            intrinsic_sizes.resolve_keyword(ContentSizeKeyword::MinContent, available_space),
+            CSSPixelLength::new(50.)
+        );
+        assert_eq!(
+            intrinsic_sizes.resolve_keyword(ContentSizeKeyword::MaxContent, available_space),
+            CSSPixelLength::new(200.)
+        );
+        assert_eq!(
+            intrinsic_sizes.resolve_keyword(ContentSizeKeyword::FitContent, available_space),
+            CSSPixelLength::new(100.)
+        );
+    }
+
+    #[test]
+    fn min_content_joins_adjacent_inline_children_without_a_wrap_opportunity() {
+        let sizes = min_content_sizes(
+            r#"<div id="joined">foo<b>bar</b></div>
+               <div id="word">foobar</div>
+               <div id="spaced">foo <b>bar</b></div>"#,
+            &["joined", "word", "spaced"],
+        );
+        assert_eq!(sizes[0], sizes[1]);
+        assert!(sizes[2] < sizes[0]);
+    }
+}
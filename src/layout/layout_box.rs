@@ -9,12 +9,17 @@ use crate::layout::flow::inline::{
     AnonymousInlineBox, InlineBox, InlineLevelBox, InlineLevelContent, TextRun,
 };
 use crate::layout::formatting_context::FormattingContextRef;
+use crate::layout::intrinsic::IntrinsicSizes;
+use crate::layout::rect::{CornerRadii, RoundedRect};
 use crate::layout::{DumpLayout, DumpLayoutFormat, Layout, LayoutContext};
-use crate::style::values::computed::length::LengthPercentageOrAuto;
+use crate::style::values::computed::length::{CSSPixelLength, LengthPercentageOrAuto};
 use crate::style::values::computed::ComputedValues;
 use crate::style::values::used::ToPx;
+use crate::Corner;
 use accountable_refcell::Ref;
 use enum_dispatch::enum_dispatch;
+use pathfinder_geometry::vector::Vector2F;
+use std::cell::Cell;
 use std::io::Write;
 use strum_macros::IntoStaticStr;
 
@@ -52,15 +57,31 @@ impl LayoutBox {
                     tr.contents()
                 )
             }
+            LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => {
+                panic!("tried to add child to replaced box")
+            }
         }
     }
 
     /// Returns the children of this layout box, if there are any.
     pub fn children(&self) -> Option<&Vec<LayoutBox>> {
         match self {
-            LayoutBox::BlockLevel(blb) => Some(blb.children()),
+            LayoutBox::BlockLevel(blb) => blb.children(),
             LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(ilb)) => Some(ilb.children()),
-            LayoutBox::InlineLevel(InlineLevelContent::TextRun(_)) => None,
+            LayoutBox::InlineLevel(InlineLevelContent::TextRun(_))
+            | LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => None,
+        }
+    }
+
+    /// Returns the children of this layout box mutably, if there are any.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<LayoutBox>> {
+        match self {
+            LayoutBox::BlockLevel(blb) => blb.children_mut(),
+            LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(ilb)) => {
+                Some(ilb.children_mut())
+            }
+            LayoutBox::InlineLevel(InlineLevelContent::TextRun(_))
+            | LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => None,
         }
     }
 
@@ -74,7 +95,8 @@ impl LayoutBox {
                 InlineLevelBox::AnonymousInline(_) => self,
                 InlineLevelBox::InlineBox(_) => self,
             }),
-            LayoutBox::InlineLevel(InlineLevelContent::TextRun(_)) => None,
+            LayoutBox::InlineLevel(InlineLevelContent::TextRun(_))
+            | LayoutBox::InlineLevel(InlineLevelContent::Replaced(_)) => None,
         }
     }
 
@@ -84,6 +106,73 @@ impl LayoutBox {
             LayoutBox::InlineLevel(ilc) => ilc.is_anonymous_inline(),
         }
     }
+
+    /// What the contents of this box are clipped to, if its `overflow` clips them: its padding
+    /// box, curved like the inner edge of its border.  Along an axis that isn't clipped, the
+    /// contents are left alone, and the corners are square.
+    ///
+    /// https://www.w3.org/TR/css-overflow-3/#overflow-properties
+    pub fn overflow_clip(&self) -> Option<RoundedRect> {
+        // TODO: The `overflow` of the root element applies to the viewport instead, which doesn't
+        // clip anything yet.
+        let is_block_container = matches!(
+            self,
+            LayoutBox::BlockLevel(BlockLevelBox::BlockContainer(_))
+        );
+        if self.is_root() || !is_block_container {
+            return None;
+        }
+        let cvs = self.computed_values();
+        let box_style = cvs.get_box();
+        let (clips_x, clips_y) = (box_style.overflow_x.clips(), box_style.overflow_y.clips());
+        if !clips_x && !clips_y {
+            return None;
+        }
+        let mut clip = self
+            .rounded_border_box()
+            .shrunk_by_edges(self.dimensions().border);
+        if !clips_x || !clips_y {
+            // Far enough out that nothing is clipped.
+            let unclipped = 1e7;
+            clip.radii = CornerRadii::default();
+            if !clips_x {
+                clip.rect.start_x = -unclipped;
+                clip.rect.width = CSSPixelLength::new(2. * unclipped);
+            }
+            if !clips_y {
+                clip.rect.start_y = -unclipped;
+                clip.rect.height = CSSPixelLength::new(2. * unclipped);
+            }
+        }
+        Some(clip)
+    }
+
+    /// The border box of this box, with its corners rounded by the radii its `border-radius` is
+    /// drawn with: percentages are resolved against the size of the border box, and all of the
+    /// radii are scaled down together if those along a side would overlap.
+    ///
+    /// https://www.w3.org/TR/css-backgrounds-3/#border-radius
+    pub fn rounded_border_box(&self) -> RoundedRect {
+        let cvs = self.computed_values();
+        let rect = self.dimensions().border_box();
+        let radius = |corner: Corner| {
+            let radius = cvs.border_corner_radius(corner);
+            Vector2F::new(
+                radius.horizontal.to_px(rect.width).px(),
+                radius.vertical.to_px(rect.height).px(),
+            )
+        };
+        let radii = CornerRadii {
+            top_left: radius(Corner::TopLeft),
+            top_right: radius(Corner::TopRight),
+            bottom_right: radius(Corner::BottomRight),
+            bottom_left: radius(Corner::BottomLeft),
+        };
+        RoundedRect {
+            rect,
+            radii: radii.fitted_to(rect.width.px(), rect.height.px()),
+        }
+    }
 }
 
 impl From<AnonymousBlockBox> for LayoutBox {
@@ -139,6 +228,10 @@ pub struct BaseBox {
     /// to a node to get computed values during layout.  If the box is a block, inline, or any other
     /// non-anonymous box, this field is simply the actual DOM node associated with this box.
     node: NodeRef,
+    /// The min-content and max-content inline sizes of this box, computed on first request.  These
+    /// only depend on the box's subtree and computed values, not on the result of layout, so they
+    /// stay valid for as long as this box does.
+    intrinsic_sizes: Cell<Option<IntrinsicSizes>>,
 }
 
 impl BaseBox {
@@ -147,18 +240,37 @@ impl BaseBox {
             dimensions: Dimensions::default(),
             formatting_context,
             node,
+            intrinsic_sizes: Cell::new(None),
+        }
+    }
+
+    /// Returns the cached intrinsic sizes of this box, computing and caching them with `compute`
+    /// if they haven't been computed yet.
+    pub fn intrinsic_sizes_or_else<F>(&self, compute: F) -> IntrinsicSizes
+    where
+        F: FnOnce() -> IntrinsicSizes,
+    {
+        if let Some(intrinsic_sizes) = self.intrinsic_sizes.get() {
+            return intrinsic_sizes;
         }
+        let intrinsic_sizes = compute();
+        self.intrinsic_sizes.set(Some(intrinsic_sizes));
+        intrinsic_sizes
+    }
+
+    pub fn clear_intrinsic_sizes(&self) {
+        self.intrinsic_sizes.set(None);
     }
 
     pub fn apply_block_page_relative_properties(&mut self, containing_block: ContainingBlock) {
         if containing_block.writing_mode().is_horizontal() {
-            let height = self.computed_values().height.size;
+            let height = self.computed_values().get_position().height.size;
             if let LengthPercentageOrAuto::LengthPercentage(lp) = height {
                 self.dimensions_mut()
                     .set_height(lp.to_px(containing_block.rect().height));
             }
         } else {
-            let width = self.computed_values().width.size;
+            let width = self.computed_values().get_position().width.size;
             if let LengthPercentageOrAuto::LengthPercentage(lp) = width {
                 self.dimensions_mut()
                     .set_width(lp.to_px(containing_block.rect().width));
@@ -168,13 +280,13 @@ impl BaseBox {
 
     pub fn apply_inline_page_relative_properties(&mut self, containing_block: ContainingBlock) {
         if containing_block.writing_mode().is_horizontal() {
-            let width = self.computed_values().width.size;
+            let width = self.computed_values().get_position().width.size;
             if let LengthPercentageOrAuto::LengthPercentage(lp) = width {
                 self.dimensions_mut()
                     .set_width(lp.to_px(containing_block.rect().width));
             }
         } else {
-            let height = self.computed_values().height.size;
+            let height = self.computed_values().get_position().height.size;
             if let LengthPercentageOrAuto::LengthPercentage(lp) = height {
                 self.dimensions_mut()
                     .set_height(lp.to_px(containing_block.rect().height));
@@ -4,12 +4,16 @@ pub mod containing_block;
 pub mod dimensions;
 pub mod flow;
 pub mod formatting_context;
+pub mod hit_test;
+pub mod intrinsic;
 pub mod layout_box;
 pub mod rect;
+pub mod replaced;
 pub mod values;
 
 use crate::cli::DumpLayoutVerbosity;
 use crate::dom::tree::NodeData;
+use crate::gfx::font::FontHandle;
 use crate::layout::behavior::BaseLayoutBoxBehavior;
 use crate::layout::containing_block::ContainingBlock;
 use crate::layout::flow::block::BlockLevelBox;
@@ -29,19 +33,29 @@ pub fn global_layout(
     layout_root_box: &mut LayoutBox,
     viewport: LayoutViewportDimensions,
     scale_factor: f32,
+    font_handle: &FontHandle,
 ) {
-    let writing_mode = layout_root_box.computed_values().writing_mode;
-    let direction = layout_root_box.computed_values().direction;
-    layout_root_box.layout(LayoutContext::new(ContainingBlock::new(
-        Rect {
-            start_x: 0.0,
-            start_y: 0.0,
-            width: CSSPixelLength::new(viewport.width.px() / scale_factor),
-            height: CSSPixelLength::new(viewport.height.px() / scale_factor),
-        },
-        direction,
-        writing_mode,
-    )));
+    let writing_mode = layout_root_box
+        .computed_values()
+        .get_inherited_box()
+        .writing_mode;
+    let direction = layout_root_box
+        .computed_values()
+        .get_inherited_box()
+        .direction;
+    layout_root_box.layout(LayoutContext::new(
+        ContainingBlock::new(
+            Rect {
+                start_x: 0.0,
+                start_y: 0.0,
+                width: CSSPixelLength::new(viewport.width.px() / scale_factor),
+                height: CSSPixelLength::new(viewport.height.px() / scale_factor),
+            },
+            direction,
+            writing_mode,
+        ),
+        font_handle,
+    ));
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -52,13 +66,26 @@ pub enum BoxComponent {
 }
 
 #[derive(Copy, Clone, Debug)]
-pub struct LayoutContext {
+pub struct LayoutContext<'a> {
     containing_block: ContainingBlock,
+    /// Handle used to load the fonts needed to measure text.
+    font_handle: &'a FontHandle,
 }
 
-impl LayoutContext {
-    pub fn new(containing_block: ContainingBlock) -> Self {
-        LayoutContext { containing_block }
+impl<'a> LayoutContext<'a> {
+    pub fn new(containing_block: ContainingBlock, font_handle: &'a FontHandle) -> Self {
+        LayoutContext {
+            containing_block,
+            font_handle,
+        }
+    }
+
+    pub fn containing_block(&self) -> ContainingBlock {
+        self.containing_block
+    }
+
+    pub fn font_handle(&self) -> &'a FontHandle {
+        self.font_handle
     }
 
     pub fn inline_start_origin_relative_progression(&self) -> OriginRelativeProgression {
@@ -1,5 +1,6 @@
 use crate::style::values::computed::length::CSSPixelLength;
 use crate::style::values::CSSFloat;
+use pathfinder_geometry::vector::Vector2F;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Rect {
@@ -21,6 +22,16 @@ impl Rect {
         }
     }
 
+    /// The inverse of `expanded_by_edges`, moving each side of this rect inwards by `edge`.
+    pub fn shrunk_by_edges(self, edge: EdgeSizes) -> Rect {
+        Rect {
+            start_x: (self.start_x + edge.left).px(),
+            start_y: (self.start_y + edge.top).px(),
+            width: self.width - edge.left - edge.right,
+            height: self.height - edge.top - edge.bottom,
+        }
+    }
+
     /// Expands this rect by another.  The resulting rect has the same `start_x` and `start_y` as
     /// the `self` rect, but with width and height expanded by other rect.
     pub fn expanded_by_rect(self, rect: Rect) -> Rect {
@@ -32,6 +43,44 @@ impl Rect {
         }
     }
 
+    /// Whether the point (`x`, `y`) lies inside this rect.
+    pub fn contains(&self, x: CSSFloat, y: CSSFloat) -> bool {
+        x >= self.start_x
+            && x < self.start_x + self.width.px()
+            && y >= self.start_y
+            && y < self.start_y + self.height.px()
+    }
+
+    /// The area where this rect and `other` overlap, or `None` if they don't.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let start_x = self.start_x.max(other.start_x);
+        let start_y = self.start_y.max(other.start_y);
+        let end_x = (self.start_x + self.width.px()).min(other.start_x + other.width.px());
+        let end_y = (self.start_y + self.height.px()).min(other.start_y + other.height.px());
+        if end_x <= start_x || end_y <= start_y {
+            return None;
+        }
+        Some(Rect {
+            start_x,
+            start_y,
+            width: CSSPixelLength::new(end_x - start_x),
+            height: CSSPixelLength::new(end_y - start_y),
+        })
+    }
+
+    /// The corners of this rect, clockwise from the top-left one.
+    pub fn corners(&self) -> [Vector2F; 4] {
+        let (left, top) = (self.start_x, self.start_y);
+        let right = left + self.width.px();
+        let bottom = top + self.height.px();
+        [
+            Vector2F::new(left, top),
+            Vector2F::new(right, top),
+            Vector2F::new(right, bottom),
+            Vector2F::new(left, bottom),
+        ]
+    }
+
     pub fn scaled_by(&self, scale_factor: f32) -> Rect {
         Rect {
             start_x: self.start_x * scale_factor,
@@ -68,3 +117,188 @@ impl EdgeSizes {
         }
     }
 }
+
+/// The horizontal (x) and vertical (y) radii of each corner of a rounded rect.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CornerRadii {
+    pub top_left: Vector2F,
+    pub top_right: Vector2F,
+    pub bottom_right: Vector2F,
+    pub bottom_left: Vector2F,
+}
+
+impl CornerRadii {
+    /// Whether every corner is square.
+    pub fn is_zero(&self) -> bool {
+        [
+            self.top_left,
+            self.top_right,
+            self.bottom_right,
+            self.bottom_left,
+        ]
+        .iter()
+        .all(|radius| radius.x() <= 0. || radius.y() <= 0.)
+    }
+
+    /// Scales all the radii down by the same factor, if needed, so that the radii along each side
+    /// of a box of the given size add up to no more than the length of that side.
+    ///
+    /// https://www.w3.org/TR/css-backgrounds-3/#corner-overlap
+    pub fn fitted_to(self, width: CSSFloat, height: CSSFloat) -> CornerRadii {
+        let ratio = |length: CSSFloat, first: f32, second: f32| {
+            if first + second > 0. {
+                length / (first + second)
+            } else {
+                1.
+            }
+        };
+        let factor = ratio(width, self.top_left.x(), self.top_right.x())
+            .min(ratio(width, self.bottom_left.x(), self.bottom_right.x()))
+            .min(ratio(height, self.top_left.y(), self.bottom_left.y()))
+            .min(ratio(height, self.top_right.y(), self.bottom_right.y()))
+            .min(1.)
+            .max(0.);
+        self.scaled_by(factor)
+    }
+
+    /// The radii of the curve `edge` inside of these, such as along the padding edge of a box
+    /// whose border edge is rounded with these radii.  Each radius is reduced by the size of the
+    /// edge it's along, and never goes below zero.
+    ///
+    /// https://www.w3.org/TR/css-backgrounds-3/#corner-shaping
+    pub fn shrunk_by_edges(self, edge: EdgeSizes) -> CornerRadii {
+        let shrunk = |radius: Vector2F, x: CSSPixelLength, y: CSSPixelLength| {
+            Vector2F::new((radius.x() - x.px()).max(0.), (radius.y() - y.px()).max(0.))
+        };
+        CornerRadii {
+            top_left: shrunk(self.top_left, edge.left, edge.top),
+            top_right: shrunk(self.top_right, edge.right, edge.top),
+            bottom_right: shrunk(self.bottom_right, edge.right, edge.bottom),
+            bottom_left: shrunk(self.bottom_left, edge.left, edge.bottom),
+        }
+    }
+
+    pub fn scaled_by(self, scale_factor: f32) -> CornerRadii {
+        CornerRadii {
+            top_left: self.top_left * scale_factor,
+            top_right: self.top_right * scale_factor,
+            bottom_right: self.bottom_right * scale_factor,
+            bottom_left: self.bottom_left * scale_factor,
+        }
+    }
+}
+
+/// A rect whose corners are rounded into quarter ellipses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RoundedRect {
+    pub rect: Rect,
+    pub radii: CornerRadii,
+}
+
+impl RoundedRect {
+    /// Moves each side of this rect inwards by `edge`, curving the corners along with it.
+    pub fn shrunk_by_edges(self, edge: EdgeSizes) -> RoundedRect {
+        RoundedRect {
+            rect: self.rect.shrunk_by_edges(edge),
+            radii: self.radii.shrunk_by_edges(edge),
+        }
+    }
+
+    pub fn scaled_by(&self, scale_factor: f32) -> RoundedRect {
+        RoundedRect {
+            rect: self.rect.scaled_by(scale_factor),
+            radii: self.radii.scaled_by(scale_factor),
+        }
+    }
+
+    /// Whether the point (`x`, `y`) is inside this rect, and not cut off by one of its corners.
+    pub fn contains(&self, x: CSSFloat, y: CSSFloat) -> bool {
+        if !self.rect.contains(x, y) {
+            return false;
+        }
+        let (left, top) = (self.rect.start_x, self.rect.start_y);
+        let right = (self.rect.start_x + self.rect.width).px();
+        let bottom = (self.rect.start_y + self.rect.height).px();
+        // Each corner, with the directions pointing into the rect from it.
+        let corners = [
+            (self.radii.top_left, left, top, 1., 1.),
+            (self.radii.top_right, right, top, -1., 1.),
+            (self.radii.bottom_right, right, bottom, -1., -1.),
+            (self.radii.bottom_left, left, bottom, 1., -1.),
+        ];
+        corners
+            .iter()
+            .all(|&(radius, corner_x, corner_y, inwards_x, inwards_y)| {
+                if radius.x() <= 0. || radius.y() <= 0. {
+                    return true;
+                }
+                // How far the point is past the center of the corner's ellipse, towards the corner.
+                let past_x = (corner_x + inwards_x * radius.x() - x) * inwards_x;
+                let past_y = (corner_y + inwards_y * radius.y() - y) * inwards_y;
+                past_x <= 0.
+                    || past_y <= 0.
+                    || (past_x / radius.x()).powi(2) + (past_y / radius.y()).powi(2) <= 1.
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn radii(top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) -> CornerRadii {
+        let circular = |radius: f32| Vector2F::new(radius, radius);
+        CornerRadii {
+            top_left: circular(top_left),
+            top_right: circular(top_right),
+            bottom_right: circular(bottom_right),
+            bottom_left: circular(bottom_left),
+        }
+    }
+
+    #[test]
+    fn overlapping_radii_are_scaled_down_together() {
+        // The radii along the top add up to 200px on a 100px wide box, so they're all halved.
+        assert_eq!(
+            radii(150., 50., 20., 10.).fitted_to(100., 400.),
+            radii(75., 25., 10., 5.)
+        );
+        assert_eq!(
+            radii(10., 10., 10., 10.).fitted_to(100., 100.),
+            radii(10., 10., 10., 10.)
+        );
+    }
+
+    #[test]
+    fn inner_radii_are_reduced_by_the_edges() {
+        let edge = EdgeSizes {
+            left: CSSPixelLength::new(4.),
+            right: CSSPixelLength::new(20.),
+            top: CSSPixelLength::new(2.),
+            bottom: CSSPixelLength::new(2.),
+        };
+        let inner = radii(10., 10., 10., 10.).shrunk_by_edges(edge);
+        assert_eq!(inner.top_left, Vector2F::new(6., 8.));
+        assert_eq!(inner.top_right, Vector2F::new(0., 8.));
+    }
+
+    #[test]
+    fn rounded_rects_contain_points_inside_their_corner_curves() {
+        let rounded = RoundedRect {
+            rect: Rect {
+                start_x: 0.,
+                start_y: 0.,
+                width: CSSPixelLength::new(100.),
+                height: CSSPixelLength::new(100.),
+            },
+            radii: radii(20., 0., 20., 0.),
+        };
+        assert!(rounded.contains(10., 10.));
+        assert!(!rounded.contains(3., 3.));
+        assert!(rounded.contains(50., 1.));
+        assert!(!rounded.contains(97., 97.));
+        // The top right corner is square.
+        assert!(rounded.contains(99., 0.));
+        assert!(!rounded.contains(100., 50.));
+    }
+}
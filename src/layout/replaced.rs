@@ -0,0 +1,270 @@
+//! Replaced elements, whose content is outside the scope of the CSS formatting model, such as the
+//! image of an `<img>` element.
+//!
+//! https://www.w3.org/TR/CSS2/conform.html#replaced-element
+
+use crate::apply_page_relative_properties_base_box_passthrough_impls;
+use crate::dom::tree::NodeRef;
+use crate::gfx::font::FontHandle;
+use crate::gfx::image::{Image, ImageState};
+use crate::layout::behavior::{ApplyPageRelativeProperties, BaseLayoutBoxBehavior};
+use crate::layout::containing_block::ContainingBlock;
+use crate::layout::dimensions::Dimensions;
+use crate::layout::formatting_context::FormattingContextRef;
+use crate::layout::intrinsic::{IntrinsicSizes, IntrinsicSizing};
+use crate::layout::layout_box::BaseBox;
+use crate::layout::DumpLayoutFormat;
+use crate::layout_box_behavior_base_box_passthrough_impls;
+use crate::style::values::computed::length::{
+    CSSPixelLength, LengthPercentage, LengthPercentageOrAuto,
+};
+use crate::style::values::computed::{ComputedValues, WritingMode};
+use crate::style::values::used::ToPx;
+use crate::style::values::CSSFloat;
+use accountable_refcell::Ref;
+use html5ever::LocalName;
+use std::rc::Rc;
+
+/// A box whose content is an image, rather than boxes generated from the element's children.  It
+/// can be either block-level or inline-level, and is never split across lines.
+#[derive(Clone, Debug)]
+pub struct ReplacedBox {
+    base: BaseBox,
+    image: Rc<Image>,
+}
+
+impl ReplacedBox {
+    pub fn new(node: NodeRef, formatting_context: FormattingContextRef, image: Rc<Image>) -> Self {
+        ReplacedBox {
+            base: BaseBox::new(node, formatting_context),
+            image,
+        }
+    }
+
+    pub fn image(&self) -> Rc<Image> {
+        self.image.clone()
+    }
+
+    /// Returns the used inline size and block size of this box's content, in that order, for a
+    /// containing block of the given inline size and writing mode.  The containing block's inline
+    /// size is `None` when it isn't known, e.g. while computing intrinsic sizes.
+    ///
+    /// The `width` and `height` properties are used when they aren't `auto`, falling back to the
+    /// `width` and `height` attributes of the element.  A size that is still missing follows from
+    /// the other size and the image's intrinsic ratio, or is the image's intrinsic size if both
+    /// are missing.
+    ///
+    /// https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-width
+    /// https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-height
+    pub fn used_size(
+        &self,
+        cb_inline_size: Option<CSSPixelLength>,
+        writing_mode: WritingMode,
+    ) -> (CSSPixelLength, CSSPixelLength) {
+        let cvs = self.computed_values();
+        // Percentages refer to the size of the containing block in the same axis, but only its
+        // inline size is known before its content is laid out.  Percentages of its block size are
+        // therefore treated as `auto`.
+        let (cb_width, cb_height) = if writing_mode.is_horizontal() {
+            (cb_inline_size, None)
+        } else {
+            (None, cb_inline_size)
+        };
+        let width = specified_size(cvs.get_position().width.size, cb_width)
+            .or_else(|| dimension_attribute(&self.node(), local_name!("width")));
+        let height = specified_size(cvs.get_position().height.size, cb_height)
+            .or_else(|| dimension_attribute(&self.node(), local_name!("height")));
+        let (width, height) = resolve_replaced_size(
+            width,
+            height,
+            self.image.width() as CSSFloat,
+            self.image.height() as CSSFloat,
+        );
+        if writing_mode.is_horizontal() {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+}
+
+impl BaseLayoutBoxBehavior for ReplacedBox {
+    layout_box_behavior_base_box_passthrough_impls!();
+}
+
+impl ApplyPageRelativeProperties for ReplacedBox {
+    apply_page_relative_properties_base_box_passthrough_impls!();
+}
+
+impl IntrinsicSizing for ReplacedBox {
+    fn intrinsic_sizes(&self, _font_handle: &FontHandle) -> IntrinsicSizes {
+        self.base.intrinsic_sizes_or_else(|| {
+            let writing_mode = self.computed_values().get_inherited_box().writing_mode;
+            // The image can't be wrapped, so both intrinsic sizes are the size it's drawn at.
+            let (inline_size, _) = self.used_size(None, writing_mode);
+            IntrinsicSizes::new(inline_size, inline_size)
+        })
+    }
+}
+
+impl DumpLayoutFormat for ReplacedBox {
+    fn dump_layout_format(&self) -> String {
+        let node_data = self.node().data().dump_layout_format();
+        if node_data.is_empty() {
+            "ReplacedBox".to_string()
+        } else {
+            format!("{} {}", node_data, "ReplacedBox")
+        }
+    }
+}
+
+/// Returns the image `node` is replaced by, which is the image of an `<img>` element whose image
+/// loaded successfully.
+pub fn replaced_image(node: &NodeRef) -> Option<Rc<Image>> {
+    let element = node.as_element()?;
+    if element.name.local != local_name!("img") {
+        return None;
+    }
+    match &*element.image.borrow() {
+        Some(ImageState::Available(image)) => Some(image.clone()),
+        Some(ImageState::Broken) | None => None,
+    }
+}
+
+/// Returns the text an `<img>` element is rendered as when it isn't replaced by its image, which
+/// is its `alt` attribute.  Returns `None` for other nodes, and for images without any alt text.
+///
+/// https://html.spec.whatwg.org/multipage/rendering.html#images-3
+pub fn image_fallback_text(node: &NodeRef) -> Option<String> {
+    let element = node.as_element()?;
+    if element.name.local != local_name!("img") || replaced_image(node).is_some() {
+        return None;
+    }
+    let attributes = element.attributes.borrow();
+    let alt = attributes.get(local_name!("alt"))?;
+    if alt.is_empty() {
+        None
+    } else {
+        Some(alt.to_owned())
+    }
+}
+
+/// Returns the size given by the `width` or `height` property, or `None` if it's `auto` or a
+/// percentage of an unknown size.
+fn specified_size(
+    size: LengthPercentageOrAuto,
+    percentage_basis: Option<CSSPixelLength>,
+) -> Option<CSSPixelLength> {
+    match size {
+        LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Length(length)) => Some(length),
+        LengthPercentageOrAuto::LengthPercentage(lp) => {
+            percentage_basis.map(|basis| lp.to_px(basis))
+        }
+        LengthPercentageOrAuto::Auto => None,
+    }
+}
+
+/// Parses the attribute `name` of `node` as a number of CSS pixels, per the rules for parsing
+/// dimension values.  Percentages aren't supported, so are ignored.
+///
+/// https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-dimension-values
+fn dimension_attribute(node: &NodeRef, name: LocalName) -> Option<CSSPixelLength> {
+    let element = node.as_element()?;
+    let attributes = element.attributes.borrow();
+    let value = attributes
+        .get(name)?
+        .trim_start_matches(|ch: char| ch.is_ascii_whitespace());
+    let number_end = value
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or_else(|| value.len());
+    if value[number_end..].starts_with('%') {
+        return None;
+    }
+    let number = value[..number_end].trim_end_matches('.').parse().ok()?;
+    Some(CSSPixelLength::new(number))
+}
+
+/// Resolves the used width and height of a replaced element from its specified width and height
+/// (either of which may be missing), and the intrinsic width and height of its content.
+fn resolve_replaced_size(
+    width: Option<CSSPixelLength>,
+    height: Option<CSSPixelLength>,
+    intrinsic_width: CSSFloat,
+    intrinsic_height: CSSFloat,
+) -> (CSSPixelLength, CSSPixelLength) {
+    match (width, height) {
+        (Some(width), Some(height)) => (width, height),
+        // Content without an intrinsic size in one of the axes has no intrinsic ratio.
+        (Some(width), None) if intrinsic_width > 0. => {
+            (width, width * (intrinsic_height / intrinsic_width))
+        }
+        (None, Some(height)) if intrinsic_height > 0. => {
+            (height * (intrinsic_width / intrinsic_height), height)
+        }
+        (Some(width), None) => (width, CSSPixelLength::new(intrinsic_height)),
+        (None, Some(height)) => (CSSPixelLength::new(intrinsic_width), height),
+        (None, None) => (
+            CSSPixelLength::new(intrinsic_width),
+            CSSPixelLength::new(intrinsic_height),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+
+    fn px(px: CSSFloat) -> Option<CSSPixelLength> {
+        Some(CSSPixelLength::new(px))
+    }
+
+    #[test]
+    fn missing_sizes_follow_from_the_intrinsic_ratio() {
+        let size = |width, height| resolve_replaced_size(width, height, 40., 20.);
+        assert_eq!(size(None, None), (px(40.).unwrap(), px(20.).unwrap()));
+        assert_eq!(size(px(100.), None), (px(100.).unwrap(), px(50.).unwrap()));
+        assert_eq!(size(None, px(10.)), (px(20.).unwrap(), px(10.).unwrap()));
+        assert_eq!(size(px(7.), px(9.)), (px(7.).unwrap(), px(9.).unwrap()));
+        assert_eq!(
+            resolve_replaced_size(None, px(10.), 40., 0.),
+            (px(40.).unwrap(), px(10.).unwrap())
+        );
+    }
+
+    #[test]
+    fn parses_dimension_attributes() {
+        let dom = parse_html().one(
+            r#"<img id="a" width=" 12px" height="3.5"><img id="b" width="50%" height="tall">"#,
+        );
+        let attribute = |id: &str, name: LocalName| {
+            let img = dom.select_first(&format!("#{}", id)).unwrap();
+            dimension_attribute(img.as_node(), name)
+        };
+        assert_eq!(attribute("a", local_name!("width")), px(12.));
+        assert_eq!(attribute("a", local_name!("height")), px(3.5));
+        assert_eq!(attribute("b", local_name!("width")), None);
+        assert_eq!(attribute("b", local_name!("height")), None);
+    }
+
+    #[test]
+    fn broken_images_fall_back_to_alt_text() {
+        let dom = parse_html().one(r#"<img alt="a cat"><img alt=""><p alt="not an image">"#);
+        let fallback_texts = dom
+            .descendants()
+            .map(|node| image_fallback_text(&node))
+            .collect::<Vec<_>>();
+        assert!(fallback_texts.contains(&Some("a cat".to_owned())));
+        assert_eq!(
+            fallback_texts.iter().filter(|text| text.is_some()).count(),
+            1
+        );
+        let img = dom.select_first("img").unwrap();
+        *img.image.borrow_mut() =
+            Some(ImageState::Available(Rc::new(Image::new(1, 1, vec![0; 4]))));
+        assert!(image_fallback_text(img.as_node()).is_none());
+        assert!(replaced_image(img.as_node()).is_some());
+    }
+}
@@ -1,6 +1,8 @@
 #![feature(or_patterns)]
 #![feature(type_name_of_val)]
 
+#[macro_use]
+extern crate bitflags;
 #[macro_use]
 extern crate cssparser;
 #[macro_use]
@@ -12,11 +14,11 @@ extern crate strum_macros;
 #[macro_use]
 extern crate derive_builder;
 
-use std::fs::File;
-
 use crate::dom::parser::parse_html;
 use crate::dom::traits::TendrilSink;
-use glutin::event::{Event, WindowEvent};
+use glutin::event::{
+    ElementState as InputState, Event, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent,
+};
 use glutin::event_loop::EventLoop;
 
 use crate::dom::tree::NodeRef;
@@ -25,37 +27,49 @@ use crate::layout::{global_layout, DumpLayout, LayoutViewportDimensions};
 pub mod cli;
 pub mod common;
 pub mod dom;
+pub mod encoding;
+pub mod fetch;
 pub mod gfx;
 pub mod layout;
+pub mod navigation;
+pub mod page;
 pub mod style;
 
 use crate::cli::{
-    get_command, setup_and_get_cli_args, CliCommand, Command, DumpLayoutCmd, RenderCmd,
+    get_command, setup_and_get_cli_args, CliCommand, Command, DumpLayoutCmd, HitTestCmd, RenderCmd,
     SimilarityCmd,
 };
+use crate::encoding::decode_html;
+use crate::fetch::{url_from_file_or_url, DefaultResourceLoader, ResourceLoader};
 use crate::gfx::char::CharHandle;
-use crate::gfx::display::{build_display_list, DisplayCommand, DisplayList};
+use crate::gfx::display::{build_display_list, scroll_display_list, DisplayCommand, DisplayList};
+use crate::gfx::font::FontHandle;
 use crate::gfx::headed::init_window_and_gl;
 use crate::gfx::headless::init_framebuffer_and_gl;
+use crate::gfx::image::{load_background_images, load_images};
 use crate::gfx::paint::MasterPainter;
 use crate::gfx::{
     resize_window, LogGlInfo, DEFAULT_LAYOUT_VIEWPORT_HEIGHT_PX, DEFAULT_LAYOUT_VIEWPORT_WIDTH_PX,
 };
-use crate::layout::box_tree::build_box_tree;
+use crate::layout::hit_test::{element_path, hit_test};
 use crate::layout::layout_box::LayoutBox;
-use crate::style::dom_integration::{apply_styles, extract_embedded_styles};
-use crate::style::parse_css_to_rules;
-use crate::style::stylesheet::Stylesheet;
-pub use common::Side;
+use crate::navigation::{
+    clamp_scroll_position, fragment_scroll_position, link_destination, link_inclusive_ancestor,
+    navigate, History, HistoryUpdate, Navigation, Traversal,
+};
+use crate::page::{focusable_inclusive_ancestor, Page};
+use crate::style::damage::RestyleDamage;
+use crate::style::dom_integration::{extract_document_stylesheets, DocumentStyles};
+use crate::style::stylesheet::{load_stylesheet, Stylesheet};
+use crate::style::CssRule;
+pub use common::{Corner, Side};
 use cssparser::RGBA;
 use gl::pixels::RgbaPixel;
 use gl::Gl;
 use glutin::event_loop::ControlFlow;
 use glutin::{PossiblyCurrent, WindowedContext};
 use std::cmp::{max, min};
-use std::error::Error;
 use std::io::Write;
-use std::path::Path;
 use url::Url;
 
 const UA_STYLESHEET_STR: &str = include_str!("../web/useragent.css");
@@ -77,6 +91,7 @@ pub enum CommandReturn {
     DumpLayout(<DumpLayoutCmd as CliCommand>::RunReturn),
     Render(<RenderCmd as CliCommand>::RunReturn),
     Similarity(<SimilarityCmd as CliCommand>::RunReturn),
+    HitTest(<HitTestCmd as CliCommand>::RunReturn),
 }
 
 impl CliCommand for Command {
@@ -87,6 +102,7 @@ impl CliCommand for Command {
             Command::Render(cmd) => cmd.run().map(|_| CommandReturn::Render(())),
             Command::DumpLayout(cmd) => cmd.run().map(|_| CommandReturn::DumpLayout(())),
             Command::Similarity(cmd) => cmd.run().map(CommandReturn::Similarity),
+            Command::HitTest(cmd) => cmd.run().map(|_| CommandReturn::HitTest(())),
         }
     }
 }
@@ -96,16 +112,22 @@ impl CliCommand for DumpLayoutCmd {
 
     fn run(&self) -> Result<Self::RunReturn, String> {
         let html_file_path = html_file_path_from_files(self.file_paths.clone()).unwrap();
-        let styled_dom = load_and_style_dom_from_file(
+        let viewport = LayoutViewportDimensions::new_px(self.window_width, self.window_height);
+        let font_handle = FontHandle::new();
+        let loader = DefaultResourceLoader;
+        let page = load_page_from_file(
+            &loader,
             html_file_path,
-            get_author_sheets(self.file_paths.clone()),
-        );
+            get_user_sheets(&loader, &self.user_stylesheets, viewport)?,
+            get_author_sheets(&loader, self.file_paths.clone(), viewport)?,
+            viewport,
+            &font_handle,
+        )?;
 
-        let viewport = LayoutViewportDimensions::new_px(self.window_width, self.window_height);
         let write_to = &mut std::io::stdout();
-        match build_box_tree(styled_dom, None) {
+        match page.box_tree() {
             Some(mut box_tree) => {
-                global_layout(&mut box_tree, viewport, self.scale_factor);
+                global_layout(&mut box_tree, viewport, self.scale_factor, &font_handle);
                 box_tree.dump_layout(write_to, 0, self.verbosity);
             }
             None => {
@@ -118,6 +140,53 @@ impl CliCommand for DumpLayoutCmd {
     }
 }
 
+impl CliCommand for HitTestCmd {
+    type RunReturn = ();
+
+    fn run(&self) -> Result<Self::RunReturn, String> {
+        let html_file_path = html_file_path_from_files(self.file_paths.clone()).ok_or_else(|| {
+            "The `hit-test` command requires an .html file.  Run --help to learn how to use `hit-test`.".to_owned()
+        })?;
+        let viewport = LayoutViewportDimensions::new_px(
+            self.window_width
+                .unwrap_or(DEFAULT_LAYOUT_VIEWPORT_WIDTH_PX),
+            self.window_height
+                .unwrap_or(DEFAULT_LAYOUT_VIEWPORT_HEIGHT_PX),
+        );
+        let font_handle = FontHandle::new();
+        let loader = DefaultResourceLoader;
+        let page = load_page_from_file(
+            &loader,
+            html_file_path,
+            get_user_sheets(&loader, &self.user_stylesheets, viewport)?,
+            get_author_sheets(&loader, self.file_paths.clone(), viewport)?,
+            viewport,
+            &font_handle,
+        )?;
+        let laid_out_box_tree = page.box_tree().map(|mut box_tree| {
+            global_layout(
+                &mut box_tree,
+                viewport,
+                self.scale_factor.unwrap_or(1.0),
+                &font_handle,
+            );
+            box_tree
+        });
+
+        for &(x, y) in &self.points {
+            // The topmost node's path includes every element it's painted over by way of its
+            // ancestors, so that's all that's printed.
+            let path = laid_out_box_tree
+                .as_ref()
+                .and_then(|box_tree| hit_test(box_tree, x, y).into_iter().next())
+                .map(|node| element_path(&node))
+                .unwrap_or_else(|| "nothing".to_owned());
+            println!("{},{}: {}", x, y, path);
+        }
+        Ok(())
+    }
+}
+
 impl CliCommand for SimilarityCmd {
     type RunReturn = f64;
 
@@ -125,6 +194,7 @@ impl CliCommand for SimilarityCmd {
         fn paint_and_get_pixels(
             box_tree: Option<LayoutBox>,
             viewport: LayoutViewportDimensions,
+            font_handle: &FontHandle,
             scale_factor: f32,
         ) -> Result<Vec<RgbaPixel>, String> {
             let (viewport_width, viewport_height) = viewport.width_height_px();
@@ -134,7 +204,14 @@ impl CliCommand for SimilarityCmd {
             headless_gfx_context.bind_framebuffer();
             let mut painter = MasterPainter::new(headless_gfx_context.gl(), scale_factor)?;
             let char_handle = CharHandle::new(headless_gfx_context.gl());
-            layout_and_paint_headless(box_tree, viewport, &char_handle, &mut painter, scale_factor);
+            layout_and_paint_headless(
+                box_tree,
+                viewport,
+                &char_handle,
+                font_handle,
+                &mut painter,
+                scale_factor,
+            );
             Ok(headless_gfx_context.read_pixels(viewport_width, viewport_height))
         }
 
@@ -157,21 +234,39 @@ impl CliCommand for SimilarityCmd {
             html_file_paths.get(0).unwrap(),
             html_file_paths.get(1).unwrap(),
         );
-        let (dom_one, dom_two) = (
-            load_and_style_dom_from_file(html_file_one, vec![]),
-            load_and_style_dom_from_file(html_file_two, vec![]),
-        );
-        let (box_tree_one, box_tree_two) =
-            (build_box_tree(dom_one, None), build_box_tree(dom_two, None));
         let viewport = LayoutViewportDimensions::new_px(
             self.window_width
                 .unwrap_or(DEFAULT_LAYOUT_VIEWPORT_WIDTH_PX),
             self.window_height
                 .unwrap_or(DEFAULT_LAYOUT_VIEWPORT_HEIGHT_PX),
         );
+        let (font_handle_one, font_handle_two) = (FontHandle::new(), FontHandle::new());
+        let loader = DefaultResourceLoader;
+        let user_sheets = get_user_sheets(&loader, &self.user_stylesheets, viewport)?;
+        let (page_one, page_two) = (
+            load_page_from_file(
+                &loader,
+                html_file_one,
+                user_sheets.clone(),
+                vec![],
+                viewport,
+                &font_handle_one,
+            )?,
+            load_page_from_file(
+                &loader,
+                html_file_two,
+                user_sheets,
+                vec![],
+                viewport,
+                &font_handle_two,
+            )?,
+        );
+        let (box_tree_one, box_tree_two) = (page_one.box_tree(), page_two.box_tree());
         let scale_factor = self.scale_factor.unwrap_or(1.0);
-        let pixels_one = paint_and_get_pixels(box_tree_one, viewport, scale_factor)?;
-        let pixels_two = paint_and_get_pixels(box_tree_two, viewport, scale_factor)?;
+        let pixels_one =
+            paint_and_get_pixels(box_tree_one, viewport, &font_handle_one, scale_factor)?;
+        let pixels_two =
+            paint_and_get_pixels(box_tree_two, viewport, &font_handle_two, scale_factor)?;
         let longest_len = max(pixels_one.len(), pixels_two.len());
         let shortest_len = min(pixels_one.len(), pixels_two.len());
         let mut num_differing_pixels: u64 = 0;
@@ -215,74 +310,74 @@ impl CliCommand for RenderCmd {
 
     fn run(&self) -> Result<Self::RunReturn, String> {
         let fallback_local_html = "tests/websrc/rainbow-divs.html".to_owned();
+        // Stylesheets are only evaluated against the initial size of the window.
+        let viewport = LayoutViewportDimensions::new_px(
+            self.window_width
+                .unwrap_or(DEFAULT_LAYOUT_VIEWPORT_WIDTH_PX),
+            self.window_height
+                .unwrap_or(DEFAULT_LAYOUT_VIEWPORT_HEIGHT_PX),
+        );
+        let loader = DefaultResourceLoader;
+        let user_sheets = get_user_sheets(&loader, &self.user_stylesheets, viewport)?;
         let author_sheets = self
             .files_or_urls
             .clone()
-            .map(get_author_sheets)
+            .map(|files_or_urls| get_author_sheets(&loader, files_or_urls, viewport))
+            .transpose()?
             .unwrap_or_default();
-        let styled_dom = if let Some(files_or_urls) = self.files_or_urls.clone() {
-            style_dom(
-                self.dom_from_file_or_url(files_or_urls.get(0).unwrap())?,
-                author_sheets,
-            )
-        } else {
-            load_and_style_dom_from_file(fallback_local_html, author_sheets)
+        let font_handle = FontHandle::new();
+        // Every file or URL that isn't a stylesheet is a document, the first of which is rendered,
+        // and the rest of which can be gone forward to.
+        let document_urls = self
+            .files_or_urls
+            .clone()
+            .map(|files_or_urls| {
+                let css_file_paths = css_file_paths_from_files(files_or_urls.clone());
+                files_or_urls
+                    .iter()
+                    .filter(|file_or_url| !css_file_paths.contains(file_or_url))
+                    .map(|file_or_url| url_from_file_or_url(file_or_url))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let mut history = match History::with_forward_entries(document_urls) {
+            Some(history) => history,
+            None => History::new(url_from_file_or_url(&fallback_local_html)?),
+        };
+        let page_loader = PageLoader {
+            resource_loader: Box::new(loader),
+            user_sheets,
+            author_sheets,
         };
+        let (page, document_url) = page_loader.load(history.current(), viewport, &font_handle)?;
+        history.replace_current(document_url);
         let (windowed_context, event_loop, gl) =
             init_window_and_gl(self.window_width, self.window_height, LogGlInfo::Yes);
         run_event_loop(
             event_loop,
             gl,
-            styled_dom,
+            page,
+            history,
+            page_loader,
             windowed_context,
+            font_handle,
             self.scale_factor,
         );
         Ok(())
     }
 }
 
-impl RenderCmd {
-    fn dom_from_file_or_url<S: AsRef<str>>(&self, file_or_url: S) -> Result<NodeRef, String> {
-        fn dom_from_readable<R: std::io::Read>(mut readable: R) -> Result<NodeRef, Box<dyn Error>> {
-            parse_html()
-                .from_utf8()
-                .read_from(&mut readable)
-                .map_err(|err| err.into())
-        }
-        let url_error = match Url::parse(file_or_url.as_ref()) {
-            Ok(url) => match isahc::get(url.as_str()) {
-                Ok(mut response) => {
-                    return dom_from_readable(response.body_mut()).map_err(|err| {
-                        format!(
-                            "failed to parse html with contents from url '{}': {}",
-                            url.as_str(),
-                            err
-                        )
-                    });
-                }
-                Err(err) => format!("{}", err),
-            },
-            Err(err) => format!("{}", err),
-        };
-        let file_error = match File::open(file_or_url.as_ref()) {
-            Ok(mut file) => {
-                return dom_from_readable(&mut file).map_err(|err| {
-                    format!(
-                        "failed to parse html with contents from file '{}': {}",
-                        file_or_url.as_ref(),
-                        err
-                    )
-                })
-            }
-            Err(err) => err,
-        };
-        Err(format!(
-            "input '{}' was neither a valid file path nor valid url\n{}\n{}",
-            file_or_url.as_ref(),
-            url_error,
-            file_error.to_string()
-        ))
+/// Loads and parses the document at `url`, returning it along with the URL it was loaded from,
+/// which differs from `url` if it was redirected.
+fn load_document(loader: &dyn ResourceLoader, url: &Url) -> Result<(NodeRef, Url), String> {
+    let document = loader.load(url)?;
+    let html = decode_html(&document.contents, document.content_type.as_deref());
+    let dom = parse_html().one(html);
+    if let Some(document_data) = dom.as_document() {
+        document_data.set_url(document.url.clone());
     }
+    Ok((dom, document.url))
 }
 
 fn html_file_path_from_files<S: AsRef<str>>(files: Vec<S>) -> Option<String> {
@@ -312,93 +407,191 @@ fn css_file_paths_from_files<S: AsRef<str>>(files: Vec<S>) -> Vec<String> {
         .collect::<Vec<_>>()
 }
 
-fn style_dom(dom: NodeRef, author_sheets: Vec<Stylesheet>) -> NodeRef {
-    let mut embedded_styles_str = extract_embedded_styles(dom.clone());
-    let embedded_styles = match parse_css_to_rules(&mut embedded_styles_str) {
-        Ok(rules) => rules,
-        Err(parse_error) => {
-            println!("error parsing embedded styles: {:?}", parse_error);
-            Vec::new()
-        }
-    };
+/// Creates a page from `dom`, styled with the user agent, user, author, and document styles, adding
+/// the fonts of any `@font-face` rules among them to `font_handle`.  Stylesheets, fonts, the images
+/// of `<img>` elements, and background images are loaded through `loader`.
+fn create_page(
+    loader: &dyn ResourceLoader,
+    dom: NodeRef,
+    user_sheets: Vec<Stylesheet>,
+    author_sheets: Vec<Stylesheet>,
+    viewport: LayoutViewportDimensions,
+    font_handle: &FontHandle,
+) -> Page {
     let ua_sheet = style::stylesheet::parse_css_to_stylesheet(
         Some("browser.css".to_owned()),
         &mut UA_STYLESHEET_STR.to_owned(),
     )
     .expect("parse stylesheet fail");
-    apply_styles(
-        dom.clone(),
-        &embedded_styles,
-        &[ua_sheet],
-        &[],
-        &author_sheets,
-    );
-    dom
+    let document_sheets = extract_document_stylesheets(loader, &dom, viewport);
+    for sheet in user_sheets.iter().chain(&author_sheets) {
+        add_font_faces(loader, font_handle, sheet.rules());
+        load_background_images(loader, &dom, sheet.rules());
+    }
+    for document_sheet in &document_sheets {
+        add_font_faces(loader, font_handle, document_sheet.sheet().rules());
+        load_background_images(loader, &dom, document_sheet.sheet().rules());
+    }
+    load_images(loader, &dom);
+    let styles = DocumentStyles::new(vec![ua_sheet], user_sheets, author_sheets, document_sheets);
+    Page::new(dom, styles)
+}
+
+fn add_font_faces(loader: &dyn ResourceLoader, font_handle: &FontHandle, rules: &[CssRule]) {
+    for rule in rules {
+        if let CssRule::FontFace(font_face) = rule {
+            if let Err(err) = font_handle.add_font_face(font_face, loader) {
+                println!(
+                    "error loading font face for family '{}': {:?}",
+                    font_face.family, err
+                );
+            }
+        }
+    }
 }
 
-fn load_and_style_dom_from_file<P: AsRef<Path>>(
-    html_file_path: P,
+fn load_page_from_file<S: AsRef<str>>(
+    loader: &dyn ResourceLoader,
+    html_file_path: S,
+    user_sheets: Vec<Stylesheet>,
     author_sheets: Vec<Stylesheet>,
-) -> NodeRef {
-    let dom = parse_html()
-        .from_utf8()
-        .read_from(&mut File::open(html_file_path).unwrap())
-        .unwrap();
-    style_dom(dom, author_sheets)
+    viewport: LayoutViewportDimensions,
+    font_handle: &FontHandle,
+) -> Result<Page, String> {
+    let (dom, _) = load_document(loader, &url_from_file_or_url(html_file_path.as_ref())?)?;
+    Ok(create_page(
+        loader,
+        dom,
+        user_sheets,
+        author_sheets,
+        viewport,
+        font_handle,
+    ))
+}
+
+/// Loads the user stylesheets at the given paths or URLs.
+fn get_user_sheets(
+    loader: &dyn ResourceLoader,
+    files_or_urls: &[String],
+    viewport: LayoutViewportDimensions,
+) -> Result<Vec<Stylesheet>, String> {
+    files_or_urls
+        .iter()
+        .map(|file_or_url| {
+            load_stylesheet(loader, &url_from_file_or_url(file_or_url)?, viewport)
+                .map_err(|err| format!("error loading user stylesheet: {}", err))
+        })
+        .collect()
 }
 
-fn get_author_sheets<S: AsRef<str>>(file_paths: Vec<S>) -> Vec<Stylesheet> {
+/// Loads the author stylesheets among the given files, which are those ending in `.css`.
+fn get_author_sheets<S: AsRef<str>>(
+    loader: &dyn ResourceLoader,
+    file_paths: Vec<S>,
+    viewport: LayoutViewportDimensions,
+) -> Result<Vec<Stylesheet>, String> {
     css_file_paths_from_files(file_paths)
         .iter()
         .map(|css_file_path| {
-            style::stylesheet::parse_css_to_stylesheet(
-                Some((*css_file_path).to_owned()),
-                &mut std::fs::read_to_string(css_file_path)
-                    .expect("couldn't read css file to string"),
-            )
-            .expect("error parsing stylesheet")
+            load_stylesheet(loader, &url_from_file_or_url(css_file_path)?, viewport)
+                .map_err(|err| format!("error loading stylesheet: {}", err))
         })
-        .collect::<Vec<_>>()
+        .collect()
+}
+
+/// Loads the pages shown in the window, through `resource_loader` and styled with the user and
+/// author stylesheets given on the command line.
+pub struct PageLoader {
+    resource_loader: Box<dyn ResourceLoader>,
+    user_sheets: Vec<Stylesheet>,
+    author_sheets: Vec<Stylesheet>,
+}
+
+impl PageLoader {
+    /// Loads the document at `url` as a page, returning it along with the URL it was loaded from,
+    /// which differs from `url` if it was redirected.
+    fn load(
+        &self,
+        url: &Url,
+        viewport: LayoutViewportDimensions,
+        font_handle: &FontHandle,
+    ) -> Result<(Page, Url), String> {
+        let (dom, document_url) = load_document(&*self.resource_loader, url)?;
+        let page = create_page(
+            &*self.resource_loader,
+            dom,
+            self.user_sheets.clone(),
+            self.author_sheets.clone(),
+            viewport,
+            font_handle,
+        );
+        Ok((page, document_url))
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run_event_loop(
     event_loop: EventLoop<()>,
     gl: Gl,
-    styled_dom: NodeRef,
+    mut page: Page,
+    mut history: History,
+    page_loader: PageLoader,
     windowed_context: WindowedContext<PossiblyCurrent>,
+    font_handle: FontHandle,
     cli_specified_scale_factor: Option<f32>,
 ) {
-    // An un-laid-out tree of boxes, to be cloned from whenever a global layout is required.
-    // This saves us from having to rebuild the entire box tree from the DOM when necessary,
-    // instead only needing a clone.
-    let clean_box_tree = build_box_tree(styled_dom, None);
     let char_handle = CharHandle::new(&gl);
     let mut scale = cli_specified_scale_factor.unwrap_or_else(|| {
         sanitize_windowed_context_scale_factor(windowed_context.window().scale_factor() as f32)
     });
     let mut master_painter = MasterPainter::new(&gl, scale).unwrap();
-    layout_and_paint_headed(
-        clean_box_tree.clone(),
+    let mut laid_out_box_tree = layout_box_tree(
+        page.box_tree(),
+        windowed_context.window().inner_size().into(),
+        scale,
+        &font_handle,
+    );
+    // The URL of the page being displayed, and how far down it's scrolled in CSS pixels.
+    let mut document_url = history.current().clone();
+    let mut scroll_y = document_url
+        .fragment()
+        .and_then(|fragment| {
+            scroll_to_fragment(
+                &page,
+                laid_out_box_tree.as_ref(),
+                fragment,
+                &windowed_context,
+                scale,
+            )
+        })
+        .unwrap_or(0.);
+    paint_headed(
+        laid_out_box_tree.as_ref(),
         &windowed_context,
         &char_handle,
+        &font_handle,
         &mut master_painter,
         scale,
+        scroll_y,
     );
+    // Where the pointer is in the window, in CSS pixels, if it's in the window at all.
+    let mut cursor_position: Option<(f32, f32)> = None;
+    let mut modifiers = ModifiersState::empty();
+    // The link the left mouse button was pressed on, which is followed if the button is released
+    // over it too.
+    let mut pressed_link: Option<NodeRef> = None;
+    // Where to navigate to once the events at hand have been handled, and how the history changes
+    // if it succeeds.
+    let mut pending_navigation: Option<(Navigation, HistoryUpdate)> = None;
     event_loop.run(move |event, _, control_flow| {
         // println!("{:?}", event);
         *control_flow = ControlFlow::Wait;
-        match event {
-            Event::LoopDestroyed => {}
+        let mut relayout = match event {
+            Event::LoopDestroyed => false,
             Event::WindowEvent { ref event, .. } => match event {
                 WindowEvent::Resized(physical_size) => {
                     resize_window(&gl, &windowed_context, physical_size);
-                    layout_and_paint_headed(
-                        clean_box_tree.clone(),
-                        &windowed_context,
-                        &char_handle,
-                        &mut master_painter,
-                        scale,
-                    )
+                    true
                 }
                 WindowEvent::ScaleFactorChanged {
                     scale_factor,
@@ -406,35 +599,265 @@ pub fn run_event_loop(
                 } => {
                     scale = *scale_factor as f32;
                     resize_window(&gl, &windowed_context, new_inner_size);
-                    layout_and_paint_headed(
-                        clean_box_tree.clone(),
+                    true
+                }
+                WindowEvent::CloseRequested => {
+                    *control_flow = ControlFlow::Exit;
+                    false
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    let point = (position.x as f32 / scale, position.y as f32 / scale);
+                    cursor_position = Some(point);
+                    page.set_hovered(element_at(laid_out_box_tree.as_ref(), point, scroll_y));
+                    false
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    cursor_position = None;
+                    page.set_hovered(None);
+                    false
+                }
+                WindowEvent::MouseInput {
+                    state,
+                    button: MouseButton::Left,
+                    ..
+                } => {
+                    let element = cursor_position
+                        .and_then(|point| element_at(laid_out_box_tree.as_ref(), point, scroll_y));
+                    let link = element.as_ref().and_then(link_inclusive_ancestor);
+                    match state {
+                        InputState::Pressed => {
+                            page.set_focused(
+                                element.as_ref().and_then(focusable_inclusive_ancestor),
+                            );
+                            page.set_active(element);
+                            pressed_link = link;
+                        }
+                        InputState::Released => {
+                            page.set_active(None);
+                            if let Some(link) =
+                                link.filter(|link| pressed_link.as_ref() == Some(link))
+                            {
+                                if let Some(destination) = link_destination(&link) {
+                                    pending_navigation = Some((
+                                        Navigation::follow(&document_url, destination),
+                                        HistoryUpdate::Push,
+                                    ));
+                                }
+                            }
+                            pressed_link = None;
+                        }
+                    }
+                    false
+                }
+                WindowEvent::MouseInput {
+                    state: InputState::Pressed,
+                    button: MouseButton::Other(button),
+                    ..
+                } => {
+                    if let Some(traversal) = traversal_of_mouse_button(*button) {
+                        pending_navigation = traverse_history(&history, traversal, &document_url);
+                    }
+                    false
+                }
+                WindowEvent::Focused(focused) => {
+                    page.set_window_focused(*focused);
+                    false
+                }
+                WindowEvent::ModifiersChanged(new_modifiers) => {
+                    modifiers = *new_modifiers;
+                    false
+                }
+                WindowEvent::KeyboardInput { input, .. } if input.state == InputState::Pressed => {
+                    match input.virtual_keycode {
+                        Some(VirtualKeyCode::Tab) => page.focus_next(modifiers.shift()),
+                        Some(VirtualKeyCode::Left) if modifiers.alt() => {
+                            pending_navigation =
+                                traverse_history(&history, Traversal::Back, &document_url);
+                        }
+                        Some(VirtualKeyCode::Right) if modifiers.alt() => {
+                            pending_navigation =
+                                traverse_history(&history, Traversal::Forward, &document_url);
+                        }
+                        _ => {}
+                    }
+                    false
+                }
+                _ => false,
+            },
+            // All events have been handled, so bring the page up to date with any changes they
+            // made to the DOM, doing only as much work as the damage requires.
+            Event::MainEventsCleared => match page.update() {
+                RestyleDamage::None => false,
+                RestyleDamage::Repaint => {
+                    paint_headed(
+                        laid_out_box_tree.as_ref(),
                         &windowed_context,
                         &char_handle,
+                        &font_handle,
                         &mut master_painter,
                         scale,
-                    )
+                        scroll_y,
+                    );
+                    false
                 }
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                _ => (),
+                RestyleDamage::Relayout | RestyleDamage::RebuildBoxTree => true,
             },
-            _ => (),
+            _ => false,
+        };
+        let navigated_url = match pending_navigation.take() {
+            Some((navigation, update)) => {
+                let viewport = windowed_context.window().inner_size().into();
+                match navigate(&mut history, navigation, update, |url| {
+                    page_loader.load(url, viewport, &font_handle)
+                }) {
+                    Ok((new_page, document_url)) => {
+                        if let Some(new_page) = new_page {
+                            page = new_page;
+                            scroll_y = 0.;
+                            relayout = true;
+                        }
+                        Some(document_url)
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        if relayout {
+            laid_out_box_tree = layout_box_tree(
+                page.box_tree(),
+                windowed_context.window().inner_size().into(),
+                scale,
+                &font_handle,
+            );
         }
+        if let Some(url) = navigated_url {
+            if let Some(fragment) = url.fragment() {
+                scroll_y = scroll_to_fragment(
+                    &page,
+                    laid_out_box_tree.as_ref(),
+                    fragment,
+                    &windowed_context,
+                    scale,
+                )
+                .unwrap_or(scroll_y);
+            }
+            document_url = url;
+        } else if !relayout {
+            return;
+        }
+        if let Some(box_tree) = &laid_out_box_tree {
+            let viewport_height = windowed_context.window().inner_size().height as f32 / scale;
+            scroll_y = clamp_scroll_position(box_tree, scroll_y, viewport_height);
+        }
+        paint_headed(
+            laid_out_box_tree.as_ref(),
+            &windowed_context,
+            &char_handle,
+            &font_handle,
+            &mut master_painter,
+            scale,
+            scroll_y,
+        );
     });
 }
 
-fn layout_and_paint_headed(
+/// Returns the navigation to the URL back or forward in `history`, which moves there once the
+/// navigation succeeds.
+fn traverse_history(
+    history: &History,
+    traversal: Traversal,
+    document_url: &Url,
+) -> Option<(Navigation, HistoryUpdate)> {
+    history.peek(traversal).map(|url| {
+        (
+            Navigation::traverse(document_url, url.clone()),
+            HistoryUpdate::Traverse(traversal),
+        )
+    })
+}
+
+/// The history traversal done by a mouse's "back" and "forward" buttons, which are usually its
+/// fourth and fifth buttons.  Each platform numbers them differently.
+fn traversal_of_mouse_button(button: u16) -> Option<Traversal> {
+    let (back, forward): (&[u16], &[u16]) = if cfg!(target_os = "windows") {
+        (&[1], &[2])
+    } else if cfg!(target_os = "macos") {
+        (&[3], &[4])
+    } else {
+        // X11 buttons 8 and 9, and the Linux input event codes `BTN_SIDE` and `BTN_EXTRA` reported
+        // under Wayland.
+        (&[8, 0x113], &[9, 0x114])
+    };
+    if back.contains(&button) {
+        Some(Traversal::Back)
+    } else if forward.contains(&button) {
+        Some(Traversal::Forward)
+    } else {
+        None
+    }
+}
+
+/// Returns how far to scroll for the element `fragment` indicates in `page` to be in view, if
+/// there's anything to scroll to.
+fn scroll_to_fragment(
+    page: &Page,
+    laid_out_box_tree: Option<&LayoutBox>,
+    fragment: &str,
+    windowed_context: &WindowedContext<PossiblyCurrent>,
+    scale_factor: f32,
+) -> Option<f32> {
+    let viewport_height = windowed_context.window().inner_size().height as f32 / scale_factor;
+    fragment_scroll_position(page.dom(), laid_out_box_tree?, fragment, viewport_height)
+}
+
+/// Returns the element under `point` of the window, in CSS pixels, of the laid-out box tree
+/// scrolled down by `scroll_y`.
+fn element_at(
+    laid_out_box_tree: Option<&LayoutBox>,
+    point: (f32, f32),
+    scroll_y: f32,
+) -> Option<NodeRef> {
+    let node = hit_test(laid_out_box_tree?, point.0, point.1 + scroll_y)
+        .into_iter()
+        .next()?;
+    node.inclusive_ancestors()
+        .find(|node| node.as_element().is_some())
+}
+
+/// Lays out `box_tree_opt` in the given viewport.
+fn layout_box_tree(
     box_tree_opt: Option<LayoutBox>,
+    viewport: LayoutViewportDimensions,
+    scale_factor: f32,
+    font_handle: &FontHandle,
+) -> Option<LayoutBox> {
+    box_tree_opt.map(|mut box_tree| {
+        global_layout(&mut box_tree, viewport, scale_factor, font_handle);
+        box_tree
+    })
+}
+
+fn paint_headed(
+    laid_out_box_tree: Option<&LayoutBox>,
     windowed_context: &WindowedContext<PossiblyCurrent>,
     char_handle: &CharHandle,
+    font_handle: &FontHandle,
     painter: &mut MasterPainter,
     scale_factor: f32,
+    scroll_y: f32,
 ) {
-    let display_list = display_list_from_box_tree(
-        box_tree_opt,
+    let mut display_list = display_list_from_box_tree(
+        laid_out_box_tree,
         windowed_context.window().inner_size().into(),
         char_handle,
+        font_handle,
         scale_factor,
     );
+    scroll_display_list(&mut display_list, scroll_y);
     painter.paint_headed(&windowed_context, &display_list);
 }
 
@@ -442,23 +865,30 @@ fn layout_and_paint_headless(
     box_tree_opt: Option<LayoutBox>,
     viewport: LayoutViewportDimensions,
     char_handle: &CharHandle,
+    font_handle: &FontHandle,
     painter: &mut MasterPainter,
     scale_factor: f32,
 ) {
-    let display_list =
-        display_list_from_box_tree(box_tree_opt, viewport, char_handle, scale_factor);
+    let laid_out_box_tree = layout_box_tree(box_tree_opt, viewport, scale_factor, font_handle);
+    let display_list = display_list_from_box_tree(
+        laid_out_box_tree.as_ref(),
+        viewport,
+        char_handle,
+        font_handle,
+        scale_factor,
+    );
     painter.paint_headless(viewport, &display_list);
 }
 
 fn display_list_from_box_tree(
-    box_tree_opt: Option<LayoutBox>,
+    laid_out_box_tree: Option<&LayoutBox>,
     viewport: LayoutViewportDimensions,
     char_handle: &CharHandle,
+    font_handle: &FontHandle,
     scale_factor: f32,
 ) -> DisplayList {
-    if let Some(mut box_tree) = box_tree_opt {
-        global_layout(&mut box_tree, viewport, scale_factor);
-        build_display_list(&box_tree, &char_handle, scale_factor)
+    if let Some(box_tree) = laid_out_box_tree {
+        build_display_list(box_tree, viewport, &char_handle, font_handle, scale_factor)
     } else {
         // There is no box tree to paint (e.g. in the case of `html { display: none }`, so paint
         // only the viewport background.
@@ -0,0 +1,402 @@
+//! Following links between documents, and the session history that lets users go back to where
+//! they've been.
+
+use crate::dom::tree::NodeRef;
+use crate::layout::behavior::BaseLayoutBoxBehavior;
+use crate::layout::flow::block::BlockLevelBox;
+use crate::layout::flow::inline::{InlineLevelBox, InlineLevelContent};
+use crate::layout::layout_box::LayoutBox;
+use html5ever::LocalName;
+use url::Url;
+
+/// The URLs visited in a window, in the order they were visited.
+///
+/// https://html.spec.whatwg.org/multipage/history.html#joint-session-history
+#[derive(Clone, Debug)]
+pub struct History {
+    entries: Vec<Url>,
+    /// The index into `entries` of the URL being displayed.
+    current: usize,
+}
+
+impl History {
+    pub fn new(url: Url) -> History {
+        History {
+            entries: vec![url],
+            current: 0,
+        }
+    }
+
+    /// Creates a history from `urls`, the first of which is the URL being displayed and the rest of
+    /// which can be gone forward to.  Returns `None` if there are no URLs.
+    pub fn with_forward_entries(urls: Vec<Url>) -> Option<History> {
+        if urls.is_empty() {
+            return None;
+        }
+        Some(History {
+            entries: urls,
+            current: 0,
+        })
+    }
+
+    /// The URL being displayed.
+    pub fn current(&self) -> &Url {
+        &self.entries[self.current]
+    }
+
+    /// Makes `url` the URL being displayed, dropping any entries that could have been gone forward
+    /// to.
+    pub fn push(&mut self, url: Url) {
+        self.entries.truncate(self.current + 1);
+        self.entries.push(url);
+        self.current += 1;
+    }
+
+    /// Replaces the URL being displayed with `url`, e.g. with the URL it redirected to.
+    pub fn replace_current(&mut self, url: Url) {
+        self.entries[self.current] = url;
+    }
+
+    /// Moves one entry back or forward through the history, returning the URL that's now being
+    /// displayed, or `None` if there's no entry in that direction.
+    pub fn traverse(&mut self, traversal: Traversal) -> Option<&Url> {
+        self.current = match traversal {
+            Traversal::Back => self.current.checked_sub(1)?,
+            Traversal::Forward if self.current + 1 < self.entries.len() => self.current + 1,
+            Traversal::Forward => return None,
+        };
+        Some(self.current())
+    }
+
+    /// The URL that traversing the history would display, without traversing it.
+    pub fn peek(&self, traversal: Traversal) -> Option<&Url> {
+        let index = match traversal {
+            Traversal::Back => self.current.checked_sub(1)?,
+            Traversal::Forward => self.current + 1,
+        };
+        self.entries.get(index)
+    }
+
+    /// Records a navigation to `url`, the URL of the document now being displayed, changing the
+    /// history as `update` says.
+    pub fn record(&mut self, update: HistoryUpdate, url: Url) {
+        match update {
+            HistoryUpdate::Push => self.push(url),
+            HistoryUpdate::Traverse(traversal) => {
+                self.traverse(traversal);
+                self.replace_current(url);
+            }
+        }
+    }
+}
+
+/// A direction in which the history can be traversed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Traversal {
+    Back,
+    Forward,
+}
+
+/// How a navigation changes the history once it's done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryUpdate {
+    /// A new entry is added for the destination, as when following a link.
+    Push,
+    /// The history moves to the neighboring entry in the given direction.
+    Traverse(Traversal),
+}
+
+/// Returns the nearest inclusive ancestor of `node` that's a link, which is what's followed when
+/// `node` is clicked.
+///
+/// https://html.spec.whatwg.org/multipage/links.html#hyperlink
+pub fn link_inclusive_ancestor(node: &NodeRef) -> Option<NodeRef> {
+    node.inclusive_ancestors().find(is_link)
+}
+
+/// Returns the URL the link `link` points to, resolved against the base URL of its document, or
+/// `None` if it isn't a link or its `href` can't be parsed.
+pub fn link_destination(link: &NodeRef) -> Option<Url> {
+    if !is_link(link) {
+        return None;
+    }
+    link.url_attribute(local_name!("href"))
+}
+
+/// Whether `node` is an element that creates a hyperlink.
+fn is_link(node: &NodeRef) -> bool {
+    node.as_element().map_or(false, |element| {
+        matches!(element.name.local, local_name!("a") | local_name!("area"))
+            && element
+                .attributes
+                .borrow()
+                .get(local_name!("href"))
+                .is_some()
+    })
+}
+
+/// A URL to show in the window, and how it's shown.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Navigation {
+    /// The URL is of the document being shown, which is scrolled to the URL's fragment rather than
+    /// loaded again.
+    SameDocument(Url),
+    /// The URL is loaded as a new document.
+    NewDocument(Url),
+}
+
+impl Navigation {
+    /// The navigation done by following a link to `to` from the document at `from`.  Only a link
+    /// with a fragment keeps the document, so that following a link to the current URL reloads it.
+    ///
+    /// https://html.spec.whatwg.org/multipage/browsing-the-web.html#navigate-fragid
+    pub fn follow(from: &Url, to: Url) -> Navigation {
+        if to.fragment().is_some() && without_fragment(from) == without_fragment(&to) {
+            Navigation::SameDocument(to)
+        } else {
+            Navigation::NewDocument(to)
+        }
+    }
+
+    /// The navigation done by traversing the history to `to` from the document at `from`.
+    pub fn traverse(from: &Url, to: Url) -> Navigation {
+        if without_fragment(from) == without_fragment(&to) {
+            Navigation::SameDocument(to)
+        } else {
+            Navigation::NewDocument(to)
+        }
+    }
+}
+
+/// Carries out `navigation`, loading its document with `load` if it's a new one, and only once that
+/// has succeeded records it in `history` as `update` says, so that the history never holds a URL
+/// that failed to load.  Returns the new document, if any, along with the URL of the document now
+/// being displayed, which may differ from the destination after a redirect.
+pub fn navigate<D>(
+    history: &mut History,
+    navigation: Navigation,
+    update: HistoryUpdate,
+    load: impl FnOnce(&Url) -> Result<(D, Url), String>,
+) -> Result<(Option<D>, Url), String> {
+    let (document, url) = match navigation {
+        Navigation::SameDocument(url) => (None, url),
+        Navigation::NewDocument(url) => {
+            let (document, document_url) =
+                load(&url).map_err(|err| format!("error navigating to '{}': {}", url, err))?;
+            (Some(document), document_url)
+        }
+    };
+    history.record(update, url.clone());
+    Ok((document, url))
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+/// Returns the element of `dom` indicated by `fragment`, which is the first with an id of
+/// `fragment`, or failing that the first `a` element named `fragment`.
+///
+/// https://html.spec.whatwg.org/multipage/browsing-the-web.html#find-a-potential-indicated-element
+pub fn indicated_element(dom: &NodeRef, fragment: &str) -> Option<NodeRef> {
+    let has_attribute = |node: &NodeRef, name: LocalName| {
+        node.as_element().map_or(false, |element| {
+            element.attributes.borrow().get(name) == Some(fragment)
+        })
+    };
+    dom.descendants()
+        .find(|node| has_attribute(node, local_name!("id")))
+        .or_else(|| {
+            dom.descendants().find(|node| {
+                node.as_element()
+                    .map_or(false, |element| element.name.local == local_name!("a"))
+                    && has_attribute(node, local_name!("name"))
+            })
+        })
+}
+
+/// Returns how far the document should be scrolled for `fragment` to be in view, in CSS pixels
+/// from the top of the document.  The top of the indicated element's border box is scrolled to
+/// the top of the viewport, as far as the height of the document allows.  An empty fragment or
+/// `top` that indicates no element scrolls to the top of the document, and `None` is returned if
+/// nothing else is indicated.
+///
+/// https://html.spec.whatwg.org/multipage/browsing-the-web.html#scroll-to-the-fragment-identifier
+pub fn fragment_scroll_position(
+    dom: &NodeRef,
+    laid_out_box_tree: &LayoutBox,
+    fragment: &str,
+    viewport_height: f32,
+) -> Option<f32> {
+    let target_y = match indicated_element(dom, fragment) {
+        Some(element) => {
+            box_of(laid_out_box_tree, &element)?
+                .dimensions()
+                .border_box()
+                .start_y
+        }
+        None if fragment.is_empty() || fragment.eq_ignore_ascii_case("top") => 0.,
+        None => return None,
+    };
+    Some(clamp_scroll_position(
+        laid_out_box_tree,
+        target_y,
+        viewport_height,
+    ))
+}
+
+/// Clamps `scroll_y` so that the viewport, `viewport_height` CSS pixels tall, stays within the
+/// document.
+pub fn clamp_scroll_position(
+    laid_out_box_tree: &LayoutBox,
+    scroll_y: f32,
+    viewport_height: f32,
+) -> f32 {
+    let document_height = laid_out_box_tree.dimensions().margin_box().height.px();
+    scroll_y.min(document_height - viewport_height).max(0.)
+}
+
+/// Returns the first box generated by `element`.
+fn box_of<'a>(layout_box: &'a LayoutBox, element: &NodeRef) -> Option<&'a LayoutBox> {
+    let is_anonymous = matches!(
+        layout_box,
+        LayoutBox::BlockLevel(BlockLevelBox::AnonymousBlock(_))
+            | LayoutBox::InlineLevel(InlineLevelContent::InlineLevelBox(
+                InlineLevelBox::AnonymousInline(_)
+            ))
+    );
+    if !is_anonymous && layout_box.node() == *element {
+        return Some(layout_box);
+    }
+    layout_box
+        .children()?
+        .iter()
+        .find_map(|child| box_of(child, element))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn pushing_drops_forward_entries() {
+        let mut history =
+            History::with_forward_entries(vec![url("file:///one.html"), url("file:///two.html")])
+                .unwrap();
+        assert_eq!(history.traverse(Traversal::Back), None);
+        assert_eq!(
+            history.traverse(Traversal::Forward),
+            Some(&url("file:///two.html"))
+        );
+        assert_eq!(history.traverse(Traversal::Forward), None);
+        assert_eq!(
+            history.traverse(Traversal::Back),
+            Some(&url("file:///one.html"))
+        );
+
+        history.push(url("file:///three.html"));
+        assert_eq!(history.current(), &url("file:///three.html"));
+        assert_eq!(history.traverse(Traversal::Forward), None);
+        assert_eq!(
+            history.traverse(Traversal::Back),
+            Some(&url("file:///one.html"))
+        );
+    }
+
+    #[test]
+    fn failed_navigations_leave_the_history_alone() {
+        let mut history = History::new(url("file:///one.html"));
+        let failed = navigate(
+            &mut history,
+            Navigation::NewDocument(url("file:///missing.html")),
+            HistoryUpdate::Push,
+            |_| Err::<((), Url), _>("not found".to_owned()),
+        );
+        assert!(failed.is_err());
+        assert_eq!(history.current(), &url("file:///one.html"));
+        assert_eq!(history.peek(Traversal::Back), None);
+
+        let redirected = navigate(
+            &mut history,
+            Navigation::NewDocument(url("file:///two.html")),
+            HistoryUpdate::Push,
+            |_| Ok(((), url("file:///redirected.html"))),
+        );
+        assert_eq!(redirected, Ok((Some(()), url("file:///redirected.html"))));
+        assert_eq!(history.current(), &url("file:///redirected.html"));
+
+        let failed = navigate(
+            &mut history,
+            Navigation::NewDocument(url("file:///one.html")),
+            HistoryUpdate::Traverse(Traversal::Back),
+            |_| Err::<((), Url), _>("not found".to_owned()),
+        );
+        assert!(failed.is_err());
+        assert_eq!(history.current(), &url("file:///redirected.html"));
+        assert_eq!(
+            history.peek(Traversal::Back),
+            Some(&url("file:///one.html"))
+        );
+    }
+
+    #[test]
+    fn links_resolve_against_the_base_url() {
+        let dom = parse_html().one(
+            r##"<a href="../other.html#part"><span>link</span></a><a name="anchor">no href</a>"##,
+        );
+        let span = dom.select_first("span").unwrap().as_node().clone();
+        let link = link_inclusive_ancestor(&span).unwrap();
+        assert_eq!(link_destination(&link), None);
+        dom.as_document()
+            .unwrap()
+            .set_url(url("https://example.com/dir/page.html"));
+        assert_eq!(
+            link_destination(&link),
+            Some(url("https://example.com/other.html#part"))
+        );
+        let anchor = dom.select_first("a[name]").unwrap().as_node().clone();
+        assert_eq!(link_inclusive_ancestor(&anchor), None);
+    }
+
+    #[test]
+    fn only_fragment_changes_keep_the_document() {
+        let page = url("https://example.com/page.html");
+        let part = url("https://example.com/page.html#part");
+        assert_eq!(
+            Navigation::follow(&page, part.clone()),
+            Navigation::SameDocument(part.clone())
+        );
+        assert_eq!(
+            Navigation::follow(&part, page.clone()),
+            Navigation::NewDocument(page.clone())
+        );
+        assert_eq!(
+            Navigation::traverse(&part, page.clone()),
+            Navigation::SameDocument(page)
+        );
+        let other = url("https://example.com/other.html#part");
+        assert_eq!(
+            Navigation::follow(&page, other.clone()),
+            Navigation::NewDocument(other)
+        );
+    }
+
+    #[test]
+    fn ids_are_preferred_over_anchor_names() {
+        let dom =
+            parse_html().one(r#"<a name="part">name</a><p id="part">id</p><a name="other">o</a>"#);
+        let name = |node: Option<NodeRef>| node.unwrap().as_element().unwrap().name.local.clone();
+        assert_eq!(name(indicated_element(&dom, "part")), local_name!("p"));
+        assert_eq!(name(indicated_element(&dom, "other")), local_name!("a"));
+        assert!(indicated_element(&dom, "missing").is_none());
+    }
+}
@@ -0,0 +1,378 @@
+//! A document loaded for display, along with everything needed to keep its styles and boxes up to
+//! date as its DOM changes.
+
+use crate::dom::tree::{ElementState, NodeRef};
+use crate::layout::box_tree::{build_box_tree, update_box_tree};
+use crate::layout::layout_box::LayoutBox;
+use crate::style::damage::RestyleDamage;
+use crate::style::dom_integration::DocumentStyles;
+
+pub struct Page {
+    dom: NodeRef,
+    styles: DocumentStyles,
+    /// The box tree of `dom` before layout, which layouts are done on clones of.  This saves us from
+    /// having to rebuild the box tree from the DOM whenever a layout is required.
+    box_tree: Option<LayoutBox>,
+    /// The element the pointer is over.
+    hovered: Option<NodeRef>,
+    /// The element being activated, e.g. by a mouse button being held down on it.
+    active: Option<NodeRef>,
+    /// The element with the keyboard focus.
+    focused: Option<NodeRef>,
+    /// Whether the window has the system focus, without which no element matches `:focus`.
+    window_focused: bool,
+}
+
+impl Page {
+    /// Styles `dom` and builds its box tree.
+    pub fn new(dom: NodeRef, styles: DocumentStyles) -> Page {
+        styles.apply(&dom);
+        let box_tree = build_box_tree(dom.clone(), None);
+        Page {
+            dom,
+            styles,
+            box_tree,
+            hovered: None,
+            active: None,
+            focused: None,
+            window_focused: true,
+        }
+    }
+
+    pub fn dom(&self) -> &NodeRef {
+        &self.dom
+    }
+
+    /// Returns a copy of the box tree, ready to be laid out.  This is `None` if the root element
+    /// generates no boxes (e.g. `html { display: none }`).
+    pub fn box_tree(&self) -> Option<LayoutBox> {
+        self.box_tree.clone()
+    }
+
+    /// Makes `element` the element the pointer is over, putting it and its ancestors in the hover
+    /// state.
+    pub fn set_hovered(&mut self, element: Option<NodeRef>) {
+        set_state_of_inclusive_ancestors(&mut self.hovered, element, ElementState::HOVER);
+    }
+
+    /// Makes `element` the element being activated, putting it and its ancestors in the active
+    /// state.
+    pub fn set_active(&mut self, element: Option<NodeRef>) {
+        set_state_of_inclusive_ancestors(&mut self.active, element, ElementState::ACTIVE);
+    }
+
+    /// Gives `element` the keyboard focus, taking it from the element that had it.
+    pub fn set_focused(&mut self, element: Option<NodeRef>) {
+        if let Some(old_focused) = &self.focused {
+            old_focused.set_element_state(ElementState::FOCUS, false);
+        }
+        if let Some(new_focused) = &element {
+            new_focused.set_element_state(ElementState::FOCUS, self.window_focused);
+        }
+        self.focused = element;
+    }
+
+    /// Records whether the window has the system focus.  The focused element only matches `:focus`
+    /// while it does.
+    pub fn set_window_focused(&mut self, window_focused: bool) {
+        self.window_focused = window_focused;
+        if let Some(focused) = &self.focused {
+            focused.set_element_state(ElementState::FOCUS, window_focused);
+        }
+    }
+
+    /// Moves the keyboard focus to the next element in sequential focus navigation order (e.g. on a
+    /// press of the Tab key), or to the previous one if `backwards` is true.  Focus wraps around
+    /// from the last element to the first.
+    ///
+    /// https://html.spec.whatwg.org/multipage/interaction.html#sequential-focus-navigation
+    pub fn focus_next(&mut self, backwards: bool) {
+        let mut navigation_order = self
+            .dom
+            .descendants()
+            .filter(|node| is_focusable(node) && tabindex(node).map_or(true, |index| index >= 0))
+            .collect::<Vec<_>>();
+        // Elements with a positive tabindex come first, in increasing order of tabindex, followed
+        // by the rest in tree order.
+        navigation_order.sort_by_key(|node| match tabindex(node) {
+            Some(index) if index > 0 => (0, index),
+            _ => (1, 0),
+        });
+        if navigation_order.is_empty() {
+            return;
+        }
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|focused| navigation_order.iter().position(|node| node == focused));
+        let len = navigation_order.len();
+        let next = match (current, backwards) {
+            (Some(index), false) => (index + 1) % len,
+            (Some(index), true) => (index + len - 1) % len,
+            (None, false) => 0,
+            (None, true) => len - 1,
+        };
+        self.set_focused(Some(navigation_order.swap_remove(next)));
+    }
+
+    /// Restyles the nodes whose style is dirty and rebuilds the boxes affected by any change to the
+    /// DOM since the last update.  Returns the damage done, so that callers know whether the page
+    /// must be laid out or painted again.
+    pub fn update(&mut self) -> RestyleDamage {
+        let damage = self.styles.restyle(&self.dom);
+        if update_box_tree(&self.dom, &mut self.box_tree) {
+            damage.max(RestyleDamage::Relayout)
+        } else {
+            damage
+        }
+    }
+}
+
+/// Moves `state` from the element in `current` and its ancestors to `element` and its ancestors,
+/// leaving alone the ancestors they share so that their styles aren't needlessly dirtied.
+fn set_state_of_inclusive_ancestors(
+    current: &mut Option<NodeRef>,
+    element: Option<NodeRef>,
+    state: ElementState,
+) {
+    let inclusive_ancestor_elements = |element: &Option<NodeRef>| {
+        element
+            .iter()
+            .flat_map(|element| element.inclusive_ancestors())
+            .filter(|node| node.as_element().is_some())
+            .collect::<Vec<_>>()
+    };
+    let old_chain = inclusive_ancestor_elements(current);
+    let new_chain = inclusive_ancestor_elements(&element);
+    for node in old_chain.iter().filter(|node| !new_chain.contains(node)) {
+        node.set_element_state(state, false);
+    }
+    for node in &new_chain {
+        node.set_element_state(state, true);
+    }
+    *current = element;
+}
+
+/// Returns the nearest inclusive ancestor of `node` that can be focused, which is the element that
+/// gets the focus when `node` is clicked.
+pub fn focusable_inclusive_ancestor(node: &NodeRef) -> Option<NodeRef> {
+    node.inclusive_ancestors().find(is_focusable)
+}
+
+/// Whether `node` is an element that can be focused.
+///
+/// https://html.spec.whatwg.org/multipage/interaction.html#focusable-area
+fn is_focusable(node: &NodeRef) -> bool {
+    let element = match node.as_element() {
+        Some(element) => element,
+        None => return false,
+    };
+    let attributes = element.attributes.borrow();
+    if attributes.contains(local_name!("tabindex")) {
+        return true;
+    }
+    match element.name.local {
+        local_name!("a") | local_name!("area") => attributes.contains(local_name!("href")),
+        local_name!("button") | local_name!("select") | local_name!("textarea") => {
+            !attributes.contains(local_name!("disabled"))
+        }
+        local_name!("input") => {
+            !attributes.contains(local_name!("disabled"))
+                && !attributes
+                    .get(local_name!("type"))
+                    .map_or(false, |input_type| {
+                        input_type.eq_ignore_ascii_case("hidden")
+                    })
+        }
+        _ => false,
+    }
+}
+
+/// The value of the `tabindex` attribute of `node`, if it has a valid one.
+fn tabindex(node: &NodeRef) -> Option<i32> {
+    node.as_element()?
+        .attributes
+        .borrow()
+        .get(local_name!("tabindex"))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::dom::tree::DirtyFlags;
+    use crate::layout::flow::inline::InlineLevelContent;
+    use crate::style::stylesheet::parse_css_to_stylesheet;
+
+    fn page(html: &str, css: &str) -> Page {
+        let sheet = parse_css_to_stylesheet(None, &mut css.to_owned()).unwrap();
+        let styles = DocumentStyles::new(vec![], vec![], vec![sheet], vec![]);
+        Page::new(parse_html().one(html), styles)
+    }
+
+    fn box_count(layout_box: &LayoutBox) -> usize {
+        1 + layout_box
+            .children()
+            .map_or(0, |children| children.iter().map(box_count).sum())
+    }
+
+    const CSS: &str = "
+        html, body, div, p { display: block }
+        .hidden { display: none }
+        .red { color: red }
+        .wide { width: 100px }
+    ";
+
+    #[test]
+    fn updating_an_unchanged_page_does_no_damage() {
+        let mut page = page("<div><p>one</p></div>", CSS);
+        assert_eq!(page.update(), RestyleDamage::None);
+    }
+
+    #[test]
+    fn class_changes_report_the_damage_they_do() {
+        let mut page = page("<div><p>one</p><p>two</p></div>", CSS);
+        let p = page.dom().select_first("p").unwrap().as_node().clone();
+
+        p.set_attribute(local_name!("class"), "red");
+        assert_eq!(page.update(), RestyleDamage::Repaint);
+
+        p.set_attribute(local_name!("class"), "wide");
+        assert_eq!(page.update(), RestyleDamage::Relayout);
+
+        let boxes_before = box_count(&page.box_tree().unwrap());
+        p.set_attribute(local_name!("class"), "hidden");
+        assert_eq!(page.update(), RestyleDamage::RebuildBoxTree);
+        assert!(box_count(&page.box_tree().unwrap()) < boxes_before);
+
+        p.remove_attribute(local_name!("class"));
+        assert_eq!(page.update(), RestyleDamage::RebuildBoxTree);
+        assert_eq!(box_count(&page.box_tree().unwrap()), boxes_before);
+        assert!(page
+            .dom()
+            .inclusive_descendants()
+            .all(|node| node.dirty_flags().is_empty()));
+    }
+
+    #[test]
+    fn text_changes_rebuild_the_boxes_of_the_containing_block() {
+        let mut page = page("<div><p>one</p><p>two</p></div>", CSS);
+        let text = page
+            .dom()
+            .select_first("p")
+            .unwrap()
+            .as_node()
+            .first_child()
+            .unwrap();
+        text.set_text("changed");
+        assert_eq!(page.update(), RestyleDamage::Relayout);
+        let box_tree = page.box_tree().unwrap();
+        let contents = text_run_contents(&box_tree);
+        assert_eq!(contents, vec!["changed".to_owned(), "two".to_owned()]);
+    }
+
+    #[test]
+    fn dirty_boxes_flag_their_ancestors_until_the_page_is_updated() {
+        let mut page = page("<div><p>one</p><p>two</p></div>", CSS);
+        let p = page.dom().select_first("p").unwrap().as_node().clone();
+        p.first_child().unwrap().set_text("changed");
+        assert!(p
+            .inclusive_ancestors()
+            .all(|node| node.dirty_flags().contains(DirtyFlags::DESCENDANT_BOXES)));
+        assert!(p.next_sibling().unwrap().dirty_flags().is_empty());
+        assert_eq!(page.update(), RestyleDamage::Relayout);
+        assert!(page
+            .dom()
+            .inclusive_descendants()
+            .all(|node| node.dirty_flags().is_empty()));
+    }
+
+    #[test]
+    fn hovering_restyles_the_hovered_element_and_its_ancestors() {
+        let mut page = page(
+            r#"<div id="outer"><p id="one">one</p><p id="two">two</p></div>"#,
+            "html, body, div, p { display: block }
+             div:hover { color: red }
+             p:hover { width: 10px }",
+        );
+        let element = |id: &str| {
+            page.dom()
+                .select_first(&format!("#{}", id))
+                .unwrap()
+                .as_node()
+                .clone()
+        };
+        let (outer, one, two) = (element("outer"), element("one"), element("two"));
+        let color = |node: &NodeRef| node.computed_values().get_inherited_text().color;
+
+        page.set_hovered(Some(one.clone()));
+        assert_eq!(page.update(), RestyleDamage::Relayout);
+        assert!(one.element_state().contains(ElementState::HOVER));
+        assert!(outer.element_state().contains(ElementState::HOVER));
+        assert_ne!(
+            color(&outer),
+            color(&page.dom().select_first("body").unwrap().as_node())
+        );
+
+        page.set_hovered(Some(two.clone()));
+        assert!(!one.element_state().contains(ElementState::HOVER));
+        assert!(outer.element_state().contains(ElementState::HOVER));
+        assert!(!outer.dirty_flags().contains(DirtyFlags::STYLE));
+        assert_eq!(page.update(), RestyleDamage::Relayout);
+
+        page.set_hovered(None);
+        assert_eq!(page.update(), RestyleDamage::Relayout);
+        assert!(page
+            .dom()
+            .descendants()
+            .all(|node| node.element_state().is_empty()));
+    }
+
+    #[test]
+    fn focus_follows_sequential_navigation_order() {
+        let mut page = page(
+            r#"<a href="/">a</a><span>span</span><button>b</button>
+               <input type="hidden"><i tabindex="-1">i</i><b tabindex="1">b</b>"#,
+            CSS,
+        );
+        let focused_name = |page: &Page| {
+            page.focused
+                .as_ref()
+                .map(|node| node.as_element().unwrap().name.local.to_string())
+        };
+        let mut order = Vec::new();
+        for _ in 0..4 {
+            page.focus_next(false);
+            order.push(focused_name(&page).unwrap());
+        }
+        assert_eq!(order, vec!["b", "a", "button", "b"]);
+        page.focus_next(true);
+        assert_eq!(focused_name(&page).unwrap(), "button");
+
+        page.set_window_focused(false);
+        let button = page.focused.clone().unwrap();
+        assert!(!button.element_state().contains(ElementState::FOCUS));
+        page.set_window_focused(true);
+        assert!(button.element_state().contains(ElementState::FOCUS));
+
+        let italic = page.dom().select_first("i").unwrap().as_node().clone();
+        let italic_text = italic.first_child().unwrap();
+        assert_eq!(focusable_inclusive_ancestor(&italic_text), Some(italic));
+    }
+
+    fn text_run_contents(layout_box: &LayoutBox) -> Vec<String> {
+        match layout_box {
+            LayoutBox::InlineLevel(InlineLevelContent::TextRun(text_run)) => {
+                vec![text_run.contents()]
+            }
+            _ => layout_box.children().map_or_else(Vec::new, |children| {
+                children.iter().flat_map(text_run_contents).collect()
+            }),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Custom properties (`--*`) and the `var()` references that substitute their values into other
+//! properties.
+//!
+//! https://drafts.csswg.org/css-variables/
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use cssparser::{ParseError, Parser, ParserInput, SourcePosition, Token};
+
+use crate::style::StyleParseErrorKind;
+
+/// The name of a custom property, without its `--` prefix.
+pub type Name = String;
+
+/// The computed values of the custom properties of an element, by name.  Each value is the CSS
+/// source of its tokens, with any `var()` references already substituted.  Custom properties that
+/// are missing here have the guaranteed-invalid value.
+pub type CustomPropertiesMap = HashMap<Name, String>;
+
+/// Returns the name of the custom property `property_name`, if it's a custom property name.
+pub fn parse_name(property_name: &str) -> Option<&str> {
+    property_name.strip_prefix("--")
+}
+
+/// A specified value of a custom property, or the value of any other property that contains
+/// `var()` references: an arbitrary sequence of tokens that's only interpreted once the references
+/// have been substituted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecifiedValue {
+    /// The CSS source of the value, with leading and trailing whitespace removed.
+    pub css: String,
+    /// The custom properties the value references, including in `var()` fallbacks.
+    pub references: HashSet<Name>,
+}
+
+impl SpecifiedValue {
+    /// Parses a `<declaration-value>`, the value of a declaration before it's interpreted as the
+    /// value of any particular property.
+    ///
+    /// https://drafts.csswg.org/css-syntax-3/#typedef-declaration-value
+    pub fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<SpecifiedValue, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let mut references = HashSet::new();
+        let start = input.position();
+        parse_declaration_value(input, &mut references)?;
+        Ok(SpecifiedValue {
+            css: input.slice_from(start).trim().to_owned(),
+            references,
+        })
+    }
+}
+
+fn parse_declaration_value<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    references: &mut HashSet<Name>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    loop {
+        let token = match input.next_including_whitespace_and_comments() {
+            Ok(token) => token.clone(),
+            Err(_) => return Ok(()),
+        };
+        let error = match token {
+            Token::BadUrl(url) => StyleParseErrorKind::BadUrlInDeclarationValueBlock(url),
+            Token::BadString(string) => {
+                StyleParseErrorKind::BadStringInDeclarationValueBlock(string)
+            }
+            Token::CloseParenthesis => {
+                StyleParseErrorKind::UnbalancedCloseParenthesisInDeclarationValueBlock
+            }
+            Token::CloseSquareBracket => {
+                StyleParseErrorKind::UnbalancedCloseSquareBracketInDeclarationValueBlock
+            }
+            Token::CloseCurlyBracket => {
+                StyleParseErrorKind::UnbalancedCloseCurlyBracketInDeclarationValueBlock
+            }
+            Token::Function(ref name) if name.eq_ignore_ascii_case("var") => {
+                input.parse_nested_block(|input| parse_var_function(input, references))?;
+                continue;
+            }
+            Token::Function(_)
+            | Token::ParenthesisBlock
+            | Token::SquareBracketBlock
+            | Token::CurlyBracketBlock => {
+                input.parse_nested_block(|input| parse_declaration_value(input, references))?;
+                continue;
+            }
+            _ => continue,
+        };
+        return Err(input.new_custom_error(error));
+    }
+}
+
+/// Parses the arguments of a `var()` reference, `<custom-property-name> [, <declaration-value>?]?`.
+fn parse_var_function<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    references: &mut HashSet<Name>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    let location = input.current_source_location();
+    let property_name = input.expect_ident_cloned()?;
+    let name = parse_name(&property_name).ok_or_else(|| {
+        location.new_custom_error(StyleParseErrorKind::OtherInvalidValue(
+            property_name.clone(),
+        ))
+    })?;
+    references.insert(name.to_owned());
+    if input.try_parse(|input| input.expect_comma()).is_ok() {
+        parse_declaration_value(input, references)?;
+    }
+    Ok(())
+}
+
+/// Computes the custom properties of an element from the values it `declared` for them, and those
+/// it `inherited` from its parent.
+///
+/// The `var()` references of declared values are substituted.  Values that can't be substituted,
+/// because they reference custom properties that are missing without a fallback, or because they
+/// reference themselves through a cycle, are invalid at computed-value time and leave the custom
+/// property with the guaranteed-invalid value.
+///
+/// https://drafts.csswg.org/css-variables/#cycles
+pub fn compute_custom_properties(
+    declared: &HashMap<&Name, &SpecifiedValue>,
+    inherited: &Arc<CustomPropertiesMap>,
+) -> Arc<CustomPropertiesMap> {
+    if declared.is_empty() {
+        return Arc::clone(inherited);
+    }
+    let in_cycle = declared
+        .keys()
+        .copied()
+        .filter(|name| references_itself(name, declared))
+        .collect::<HashSet<_>>();
+    let mut resolver = Resolver {
+        declared,
+        inherited,
+        in_cycle,
+        resolved: HashMap::new(),
+    };
+    let mut computed = CustomPropertiesMap::clone(inherited);
+    for &name in declared.keys() {
+        match resolver.resolve(name) {
+            Some(value) => computed.insert(name.clone(), value),
+            None => computed.remove(name),
+        };
+    }
+    Arc::new(computed)
+}
+
+/// Whether the declared value of `name` references `name` again, directly or through other
+/// declared custom properties.  Inherited values can't reference anything, as their references
+/// have already been substituted.
+fn references_itself(name: &Name, declared: &HashMap<&Name, &SpecifiedValue>) -> bool {
+    let mut to_visit = declared[name].references.iter().collect::<Vec<_>>();
+    let mut visited = HashSet::new();
+    while let Some(reference) = to_visit.pop() {
+        if reference == name {
+            return true;
+        }
+        if visited.insert(reference) {
+            if let Some(value) = declared.get(reference) {
+                to_visit.extend(value.references.iter());
+            }
+        }
+    }
+    false
+}
+
+struct Resolver<'a> {
+    declared: &'a HashMap<&'a Name, &'a SpecifiedValue>,
+    inherited: &'a CustomPropertiesMap,
+    in_cycle: HashSet<&'a Name>,
+    /// The computed values of the declared custom properties resolved so far.
+    resolved: HashMap<Name, Option<String>>,
+}
+
+impl Resolver<'_> {
+    /// Returns the computed value of the custom property `name`, or `None` if it has the
+    /// guaranteed-invalid value.
+    fn resolve(&mut self, name: &str) -> Option<String> {
+        if let Some(value) = self.resolved.get(name) {
+            return value.clone();
+        }
+        let declared = self.declared;
+        let value = match declared.get(&name.to_owned()) {
+            None => return self.inherited.get(name).cloned(),
+            Some(_) if self.in_cycle.contains(&name.to_owned()) => None,
+            Some(specified) if specified.references.is_empty() => Some(specified.css.clone()),
+            Some(specified) => {
+                substitute_with(&specified.css, &mut |reference| self.resolve(reference)).ok()
+            }
+        };
+        self.resolved.insert(name.to_owned(), value.clone());
+        value
+    }
+}
+
+/// Substitutes the `var()` references in `css`, the CSS source of a `SpecifiedValue`, with the
+/// values of `custom_properties`.  Fails if the value is invalid at computed-value time, which is
+/// when it references a custom property with the guaranteed-invalid value without a fallback.
+///
+/// https://drafts.csswg.org/css-variables/#substitute-a-var
+pub fn substitute(css: &str, custom_properties: &CustomPropertiesMap) -> Result<String, ()> {
+    substitute_with(css, &mut |name| custom_properties.get(name).cloned())
+}
+
+fn substitute_with(
+    css: &str,
+    lookup: &mut dyn FnMut(&str) -> Option<String>,
+) -> Result<String, ()> {
+    let mut input = ParserInput::new(css);
+    let mut input = Parser::new(&mut input);
+    let mut substituted = String::new();
+    let start = input.position();
+    let last_position = substitute_block(&mut input, start, &mut substituted, lookup)
+        .map_err(|_: ParseError<()>| ())?;
+    push_tokens(&mut substituted, input.slice_from(last_position));
+    Ok(substituted.trim().to_owned())
+}
+
+/// Substitutes the `var()` references in the rest of `input`, pushing the result to `substituted`
+/// up to the end of the last reference.  `position` is where the source that hasn't been pushed
+/// yet starts, and the new such position is returned.
+fn substitute_block<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    mut position: SourcePosition,
+    substituted: &mut String,
+    lookup: &mut dyn FnMut(&str) -> Option<String>,
+) -> Result<SourcePosition, ParseError<'i, ()>> {
+    loop {
+        let token_start = input.position();
+        let token = match input.next_including_whitespace_and_comments() {
+            Ok(token) => token.clone(),
+            Err(_) => return Ok(position),
+        };
+        match token {
+            Token::Function(ref name) if name.eq_ignore_ascii_case("var") => {
+                push_tokens(substituted, input.slice(position..token_start));
+                input.parse_nested_block(|input| {
+                    substitute_var_function(input, substituted, &mut *lookup)
+                })?;
+                position = input.position();
+            }
+            Token::Function(_)
+            | Token::ParenthesisBlock
+            | Token::SquareBracketBlock
+            | Token::CurlyBracketBlock => {
+                position = input.parse_nested_block(|input| {
+                    substitute_block(input, position, substituted, &mut *lookup)
+                })?;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn substitute_var_function<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    substituted: &mut String,
+    lookup: &mut dyn FnMut(&str) -> Option<String>,
+) -> Result<(), ParseError<'i, ()>> {
+    let property_name = input.expect_ident_cloned()?;
+    let value = match parse_name(&property_name) {
+        Some(name) => lookup(name),
+        None => None,
+    };
+    let has_fallback = input.try_parse(|input| input.expect_comma()).is_ok();
+    match value {
+        Some(value) => {
+            push_tokens(substituted, &value);
+            // The fallback is unused, but must still be consumed.
+            while input.next_including_whitespace_and_comments().is_ok() {}
+            Ok(())
+        }
+        None if has_fallback => {
+            let mut fallback = String::new();
+            let start = input.position();
+            let last_position = substitute_block(input, start, &mut fallback, lookup)?;
+            push_tokens(&mut fallback, input.slice_from(last_position));
+            push_tokens(substituted, fallback.trim());
+            Ok(())
+        }
+        None => Err(input.new_custom_error(())),
+    }
+}
+
+/// Pushes the CSS source `tokens` to `substituted`.  Substitution happens token by token, so an
+/// empty comment is put between the two if their tokens would otherwise run together, like `1`
+/// and `px` in `1var(--unit)`.
+fn push_tokens(substituted: &mut String, tokens: &str) {
+    fn may_join(ch: char) -> bool {
+        ch.is_alphanumeric()
+            || !ch.is_ascii()
+            || matches!(ch, '-' | '_' | '.' | '%' | '#' | '@' | '+' | '\\')
+    }
+    let ends_with_token = substituted.chars().last().map_or(false, may_join);
+    let starts_with_token = tokens.chars().next().map_or(false, may_join);
+    if ends_with_token && starts_with_token {
+        substituted.push_str("/**/");
+    }
+    substituted.push_str(tokens);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn specified(css: &str) -> SpecifiedValue {
+        let mut input = ParserInput::new(css);
+        SpecifiedValue::parse(&mut Parser::new(&mut input)).expect("invalid specified value")
+    }
+
+    fn compute(declarations: &[(&str, &str)], inherited: &[(&str, &str)]) -> CustomPropertiesMap {
+        let names = declarations
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        let values = declarations
+            .iter()
+            .map(|(_, css)| specified(css))
+            .collect::<Vec<_>>();
+        let declared = names.iter().zip(values.iter()).collect();
+        let inherited = inherited
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        CustomPropertiesMap::clone(&compute_custom_properties(&declared, &Arc::new(inherited)))
+    }
+
+    #[test]
+    fn finds_references_in_fallbacks() {
+        let value = specified(" calc(var(--a) + var(--b, var(--c))) ");
+        assert_eq!(value.css, "calc(var(--a) + var(--b, var(--c)))");
+        let mut expected = HashSet::new();
+        expected.extend(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+        assert_eq!(value.references, expected);
+    }
+
+    #[test]
+    fn substitutes_references_and_fallbacks() {
+        let mut custom_properties = CustomPropertiesMap::new();
+        custom_properties.insert("width".to_owned(), "10px".to_owned());
+        assert_eq!(
+            substitute("calc(var(--width) * 2)", &custom_properties),
+            Ok("calc(10px * 2)".to_owned())
+        );
+        assert_eq!(
+            substitute("var(--missing, var(--width))", &custom_properties),
+            Ok("10px".to_owned())
+        );
+        assert_eq!(substitute("var(--missing)", &custom_properties), Err(()));
+        assert_eq!(
+            substitute("1var(--width)", &custom_properties),
+            Ok("1/**/10px".to_owned())
+        );
+    }
+
+    #[test]
+    fn computes_declared_and_inherited_properties() {
+        let computed = compute(
+            &[
+                ("size", "var(--base)"),
+                ("padding", "var(--size) var(--size)"),
+            ],
+            &[("base", "4px"), ("color", "red")],
+        );
+        assert_eq!(computed["size"], "4px");
+        assert_eq!(computed["padding"], "4px 4px");
+        assert_eq!(computed["color"], "red");
+    }
+
+    #[test]
+    fn properties_in_cycles_are_invalid() {
+        let computed = compute(
+            &[
+                ("a", "var(--b)"),
+                ("b", "var(--a)"),
+                ("c", "var(--a, 1px)"),
+                ("d", "var(--b, 1px) 2px"),
+                ("e", "var(--e, 1px)"),
+            ],
+            &[("a", "3px")],
+        );
+        assert!(!computed.contains_key("a"));
+        assert!(!computed.contains_key("b"));
+        assert_eq!(computed["c"], "1px");
+        assert_eq!(computed["d"], "1px 2px");
+        assert!(!computed.contains_key("e"));
+    }
+}
@@ -0,0 +1,61 @@
+//! How much of the work after styling must be redone when a node's style changes.  This is modeled
+//! after Servo's `RestyleDamage`.
+
+use std::sync::Arc;
+
+use crate::style::values::computed::ComputedValues;
+
+/// The work that must be redone after a restyle, ordered from least to most expensive.  Each kind of
+/// damage implies all the kinds before it, e.g. a relayout must always be followed by a repaint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RestyleDamage {
+    /// Nothing that affects rendering changed.
+    None,
+    /// Only painting changed, e.g. a color.
+    Repaint,
+    /// The size or position of boxes changed, e.g. a margin or font size.
+    Relayout,
+    /// The boxes generated by the node changed, e.g. its `display`.
+    RebuildBoxTree,
+}
+
+impl RestyleDamage {
+    /// Determines the damage done by a node's computed values changing from `old` to `new`.
+    pub fn between(old: &Arc<ComputedValues>, new: &Arc<ComputedValues>) -> RestyleDamage {
+        if Arc::ptr_eq(old, new) {
+            return RestyleDamage::None;
+        }
+        if old.get_box().display != new.get_box().display
+            || old.get_inherited_text().white_space != new.get_inherited_text().white_space
+        {
+            return RestyleDamage::RebuildBoxTree;
+        }
+        let (old_border, new_border) = (old.get_border(), new.get_border());
+        let border_size_changed = old_border.border_bottom_width != new_border.border_bottom_width
+            || old_border.border_left_width != new_border.border_left_width
+            || old_border.border_right_width != new_border.border_right_width
+            || old_border.border_top_width != new_border.border_top_width
+            || old_border.border_bottom_style != new_border.border_bottom_style
+            || old_border.border_left_style != new_border.border_left_style
+            || old_border.border_right_style != new_border.border_right_style
+            || old_border.border_top_style != new_border.border_top_style;
+        if border_size_changed
+            || old.get_box() != new.get_box()
+            || old.get_font() != new.get_font()
+            || old.get_inherited_box() != new.get_inherited_box()
+            || old.get_inherited_text().line_height != new.get_inherited_text().line_height
+            || old.get_margin() != new.get_margin()
+            || old.get_padding() != new.get_padding()
+            || old.get_position() != new.get_position()
+        {
+            return RestyleDamage::Relayout;
+        }
+        if old.get_background() != new.get_background()
+            || old.get_border() != new.get_border()
+            || old.get_inherited_text().color != new.get_inherited_text().color
+        {
+            return RestyleDamage::Repaint;
+        }
+        RestyleDamage::None
+    }
+}
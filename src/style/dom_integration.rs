@@ -1,43 +1,58 @@
-use crate::dom::tree::{NodeData, NodeRef};
-use crate::style::stylesheet::{apply_css_rules_to_node, Stylesheet};
+use crate::dom::tree::{DirtyFlags, NodeData, NodeRef};
+use crate::fetch::ResourceLoader;
+use crate::layout::LayoutViewportDimensions;
+use crate::style::damage::RestyleDamage;
+use crate::style::media_queries::MediaList;
+use crate::style::sharing::StyleSharingCache;
+use crate::style::stylesheet::{load_stylesheet, resolve_imports, Stylesheet};
+use crate::style::stylist::Stylist;
 use crate::style::values::computed::compute_values;
-use crate::style::{CascadeOrigin, CssOrigin, CssRule};
+use crate::style::{parse_css_to_rules, CascadeOrigin, CssOrigin};
+use url::Url;
 
+/// A stylesheet referenced by the document itself.
+pub enum DocumentStylesheet {
+    /// The styles of a `<style>` element.
+    Embedded(Stylesheet),
+    /// A stylesheet linked to by a `<link rel="stylesheet">` element.
+    Linked(Stylesheet),
+}
+
+impl DocumentStylesheet {
+    pub fn sheet(&self) -> &Stylesheet {
+        match self {
+            DocumentStylesheet::Embedded(sheet) | DocumentStylesheet::Linked(sheet) => sheet,
+        }
+    }
+
+    pub fn css_origin(&self) -> CssOrigin {
+        match self {
+            DocumentStylesheet::Embedded(_) => CssOrigin::Embedded,
+            DocumentStylesheet::Linked(sheet) => sheet.css_origin(CascadeOrigin::Author),
+        }
+    }
+}
+
+/// Applies `document_sheets` after the author sheets, in the order they're given, so that later
+/// sheets in the document win over earlier ones of the same specificity.
 pub fn apply_styles(
     dom: NodeRef,
-    embedded_styles: &[CssRule],
     ua_sheets: &[Stylesheet],
     user_sheets: &[Stylesheet],
     author_sheets: &[Stylesheet],
+    document_sheets: &[DocumentStylesheet],
 ) {
     // https://www.w3.org/TR/css-cascade-3/#value-stages
     // The final value of a CSS property for a given element or box is the result of a multi-step calculation:
 
-    // 1. First, all the declared values applied to an element are collected, for each property on each element. There may be zero or many declared values applied to the element.
-    ua_sheets.iter().for_each(|stylesheet| {
-        apply_css_rules_to_node(
-            &dom,
-            stylesheet.rules(),
-            stylesheet.css_origin(CascadeOrigin::UserAgent),
-        );
-    });
-
-    user_sheets.iter().for_each(|stylesheet| {
-        apply_css_rules_to_node(
-            &dom,
-            stylesheet.rules(),
-            stylesheet.css_origin(CascadeOrigin::User),
-        );
-    });
+    let stylist = build_stylist(ua_sheets, user_sheets, author_sheets, document_sheets);
+    apply_stylist(dom, &stylist);
+}
 
-    author_sheets.iter().for_each(|stylesheet| {
-        apply_css_rules_to_node(
-            &dom,
-            stylesheet.rules(),
-            stylesheet.css_origin(CascadeOrigin::Author),
-        );
-    });
-    apply_css_rules_to_node(&dom, embedded_styles, CssOrigin::Embedded);
+/// Styles every node of `dom` with the rules of `stylist`.
+fn apply_stylist(dom: NodeRef, stylist: &Stylist) {
+    // 1. First, all the declared values applied to an element are collected, for each property on each element. There may be zero or many declared values applied to the element.
+    stylist.apply_rules(&dom);
     // collect all inline styles
     dom.inclusive_descendants().for_each(|node| {
         if let NodeData::Element(element_data) = node.data() {
@@ -58,6 +73,82 @@ pub fn apply_styles(
     cascade_and_compute(&dom);
 }
 
+/// Indexes the rules of the given stylesheets for matching.  Sheets are added in cascade order, so
+/// later sheets win over earlier ones.
+fn build_stylist(
+    ua_sheets: &[Stylesheet],
+    user_sheets: &[Stylesheet],
+    author_sheets: &[Stylesheet],
+    document_sheets: &[DocumentStylesheet],
+) -> Stylist {
+    let mut stylist = Stylist::new();
+    for sheet in ua_sheets {
+        stylist.add_rules(sheet.rules(), &sheet.css_origin(CascadeOrigin::UserAgent));
+    }
+    for sheet in user_sheets {
+        stylist.add_rules(sheet.rules(), &sheet.css_origin(CascadeOrigin::User));
+    }
+    for sheet in author_sheets {
+        stylist.add_rules(sheet.rules(), &sheet.css_origin(CascadeOrigin::Author));
+    }
+    for document_sheet in document_sheets {
+        stylist.add_rules(document_sheet.sheet().rules(), &document_sheet.css_origin());
+    }
+    stylist
+}
+
+/// The stylesheets applying to a document, kept so that the document can be restyled as it changes.
+pub struct DocumentStyles {
+    /// The rules of all the sheets, indexed for matching once rather than on every restyle.
+    stylist: Stylist,
+}
+
+impl DocumentStyles {
+    pub fn new(
+        ua_sheets: Vec<Stylesheet>,
+        user_sheets: Vec<Stylesheet>,
+        author_sheets: Vec<Stylesheet>,
+        document_sheets: Vec<DocumentStylesheet>,
+    ) -> Self {
+        DocumentStyles {
+            stylist: build_stylist(&ua_sheets, &user_sheets, &author_sheets, &document_sheets),
+        }
+    }
+
+    /// Styles every node of `dom`.
+    pub fn apply(&self, dom: &NodeRef) {
+        apply_stylist(dom.clone(), &self.stylist);
+    }
+
+    /// Restyles the nodes of `dom` whose style is dirty, along with their descendants.  Nodes whose
+    /// boxes must be rebuilt or laid out again because of their new style are marked as such, and
+    /// the most expensive damage done to any node is returned.
+    pub fn restyle(&self, dom: &NodeRef) -> RestyleDamage {
+        if !dom
+            .dirty_flags()
+            .intersects(DirtyFlags::STYLE | DirtyFlags::DESCENDANT_STYLE)
+        {
+            return RestyleDamage::None;
+        }
+        let mut sharing_cache = StyleSharingCache::new();
+        let mut damage = RestyleDamage::None;
+        for node in self.stylist.reapply_rules(dom) {
+            let old_computed_values = node.shared_computed_values();
+            node.contextual_decls_mut().cascade_sort();
+            compute_values(node.clone(), &mut sharing_cache);
+            let node_damage =
+                RestyleDamage::between(&old_computed_values, &node.shared_computed_values());
+            match node_damage {
+                RestyleDamage::RebuildBoxTree => node.mark_boxes_dirty(DirtyFlags::BOX_TREE),
+                RestyleDamage::Relayout => node.mark_boxes_dirty(DirtyFlags::LAYOUT),
+                RestyleDamage::Repaint | RestyleDamage::None => {}
+            }
+            damage = damage.max(node_damage);
+        }
+        damage
+    }
+}
+
 /// Performs steps 2-4 of https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#value-stages.
 ///
 /// Specifically, this is:
@@ -66,35 +157,108 @@ pub fn apply_styles(
 /// 3) Defaulting to specified values — https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#specified-value
 /// 4) Resolving specified values to computed values — https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#computed
 pub fn cascade_and_compute(start_node: &NodeRef) {
+    let mut sharing_cache = StyleSharingCache::new();
     start_node.inclusive_descendants().for_each(|node| {
         // Step 2
         node.contextual_decls_mut().cascade_sort();
         // Step 3 and 4
-        compute_values(node);
+        compute_values(node, &mut sharing_cache);
     });
 }
 
-pub fn extract_embedded_styles(node: NodeRef) -> String {
-    node.inclusive_descendants()
-        .filter(|child| {
-            if let NodeData::Element(element_data) = child.data() {
-                local_name!("style") == element_data.name.local
+/// Collects the stylesheets of the `<style>` and `<link rel="stylesheet">` elements in `dom`, in
+/// document order.  Linked stylesheets, and the `@import`s and `url()`s of embedded ones, are
+/// resolved against the document's base URL, and only loaded through `loader` if their media
+/// matches `viewport`.
+pub fn extract_document_stylesheets(
+    loader: &dyn ResourceLoader,
+    dom: &NodeRef,
+    viewport: LayoutViewportDimensions,
+) -> Vec<DocumentStylesheet> {
+    dom.inclusive_descendants()
+        .filter_map(|node| {
+            let element_data = match node.data() {
+                NodeData::Element(element_data) => element_data,
+                _ => return None,
+            };
+            // Both `<style>` and `<link>` only apply when their media queries match.
+            let matches_media = || {
+                let attributes = element_data.attributes.borrow();
+                attributes
+                    .get(local_name!("media"))
+                    .map_or(true, |media| MediaList::parse_str(media).matches(viewport))
+            };
+            if element_data.name.local == local_name!("style") {
+                if !matches_media() {
+                    return None;
+                }
+                let mut css = node.children().fold(String::new(), |accumulator, child| {
+                    if let NodeData::Text(contents) = child.data() {
+                        accumulator + contents.take().trim()
+                    } else {
+                        accumulator
+                    }
+                });
+                let rules = match parse_css_to_rules(&mut css) {
+                    Ok(rules) => rules,
+                    Err(parse_error) => {
+                        println!("error parsing embedded styles: {:?}", parse_error);
+                        return None;
+                    }
+                };
+                // Embedded styles still apply in a document without a URL to resolve theirs against.
+                let base_url = node
+                    .base_url()
+                    .unwrap_or_else(|| Url::parse("about:blank").unwrap());
+                let mut sheet = Stylesheet::new_with_name(base_url.to_string());
+                for rule in resolve_imports(
+                    loader,
+                    rules,
+                    &base_url,
+                    viewport,
+                    &mut vec![base_url.clone()],
+                ) {
+                    sheet.add_rule(rule);
+                }
+                Some(DocumentStylesheet::Embedded(sheet))
+            } else if element_data.name.local == local_name!("link") {
+                let attrs = element_data.attributes.borrow();
+                let rel = attrs.get(local_name!("rel")).unwrap_or("");
+                let has_rel = |keyword: &str| {
+                    rel.split_ascii_whitespace()
+                        .any(|token| token.eq_ignore_ascii_case(keyword))
+                };
+                // Alternate stylesheets aren't applied unless chosen by the user, which isn't
+                // possible yet.
+                if !has_rel("stylesheet") || has_rel("alternate") {
+                    return None;
+                }
+                if let Some(mime_type) = attrs.get(local_name!("type")) {
+                    if !mime_type.trim().eq_ignore_ascii_case("text/css") {
+                        return None;
+                    }
+                }
+                if !matches_media() {
+                    return None;
+                }
+                let href = attrs.get(local_name!("href"))?;
+                let url = match node.url_attribute(local_name!("href")) {
+                    Some(url) => url,
+                    None => {
+                        println!("invalid stylesheet url '{}'", href);
+                        return None;
+                    }
+                };
+                match load_stylesheet(loader, &url, viewport) {
+                    Ok(sheet) => Some(DocumentStylesheet::Linked(sheet)),
+                    Err(err) => {
+                        println!("error loading stylesheet '{}': {}", url, err);
+                        None
+                    }
+                }
             } else {
-                false
+                None
             }
         })
-        .fold(String::new(), |accumulator, style_node| {
-            let styles_in_style_node =
-                style_node
-                    .children()
-                    .fold(String::new(), |node_accumulator, child| {
-                        let style_contents = if let NodeData::Text(contents) = child.data() {
-                            contents.take()
-                        } else {
-                            "".to_owned()
-                        };
-                        node_accumulator + style_contents.trim()
-                    });
-            accumulator + &styles_in_style_node
-        })
+        .collect()
 }
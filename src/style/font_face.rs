@@ -0,0 +1,219 @@
+use cssparser::{
+    AtRuleParser, CowRcStr, DeclarationListParser, DeclarationParser, ParseError, Parser,
+    SourceLocation, UnicodeRange,
+};
+
+use crate::style::values::computed::{FontStyle, FontWeight, SingleFontFamily};
+use crate::style::values::specified;
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+
+/// An `@font-face` rule, which makes a font file available under a family name.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-face-rule
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontFaceRule {
+    /// The family name the face is matched by in `font-family`.
+    pub family: String,
+    /// The places to load the face from, in order of preference.
+    pub sources: Vec<FontFaceSource>,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+    /// The characters the face may be used for.  Empty if it may be used for any character.
+    pub unicode_range: Vec<UnicodeRange>,
+    /// The location in the sheet where it was found.
+    pub source_location: SourceLocation,
+}
+
+impl FontFaceRule {
+    /// Whether the face may be used for `ch` according to its `unicode-range`.
+    pub fn covers(&self, ch: char) -> bool {
+        self.unicode_range.is_empty()
+            || self
+                .unicode_range
+                .iter()
+                .any(|range| range.start <= ch as u32 && ch as u32 <= range.end)
+    }
+}
+
+/// One entry of the `src` descriptor of an `@font-face` rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FontFaceSource {
+    /// A font file, with the URL as written in the stylesheet.
+    Url(String),
+    /// A font installed on the system, by its full or postscript name.
+    Local(String),
+}
+
+/// The font formats that can be loaded, as named by the `format()` hint of `src`.
+const SUPPORTED_FORMATS: [&str; 3] = ["truetype", "opentype", "collection"];
+
+/// Parses the descriptor block of an `@font-face` rule.  Returns `None` if the rule is missing the
+/// `font-family` or `src` descriptors, which makes it invalid.
+pub fn parse_font_face_block(
+    input: &mut Parser,
+    source_location: SourceLocation,
+) -> Option<FontFaceRule> {
+    let parser = FontFaceDescriptorParser {
+        descriptors: FontFaceDescriptors::default(),
+    };
+    let mut decl_iter = DeclarationListParser::new(input, parser);
+    while let Some(declaration) = decl_iter.next() {
+        // Invalid descriptors are ignored, as if they weren't there.
+        if let Err((_, invalid)) = declaration {
+            println!("invalid @font-face descriptor '{}'", invalid.trim());
+        }
+    }
+    let descriptors = decl_iter.parser.descriptors;
+    Some(FontFaceRule {
+        family: descriptors.family?,
+        sources: descriptors.sources?,
+        weight: descriptors.weight.unwrap_or_else(FontWeight::normal),
+        style: descriptors.style.unwrap_or(FontStyle::Normal),
+        unicode_range: descriptors.unicode_range.unwrap_or_default(),
+        source_location,
+    })
+}
+
+#[derive(Default)]
+struct FontFaceDescriptors {
+    family: Option<String>,
+    sources: Option<Vec<FontFaceSource>>,
+    weight: Option<FontWeight>,
+    style: Option<FontStyle>,
+    unicode_range: Option<Vec<UnicodeRange>>,
+}
+
+struct FontFaceDescriptorParser {
+    descriptors: FontFaceDescriptors,
+}
+
+impl<'i> DeclarationParser<'i> for FontFaceDescriptorParser {
+    type Declaration = ();
+    type Error = StyleParseErrorKind<'i>;
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<(), ParseError<'i, Self::Error>> {
+        let location = input.current_source_location();
+        match_ignore_ascii_case! { &name,
+            "font-family" => {
+                self.descriptors.family = Some(match SingleFontFamily::parse(input)? {
+                    SingleFontFamily::FamilyName(name) => name,
+                    SingleFontFamily::Generic(_) => {
+                        return Err(location.new_custom_error(StyleParseErrorKind::OtherInvalidValue(name.clone())))
+                    }
+                });
+            },
+            "src" => {
+                let sources = input.parse_comma_separated(parse_source)?;
+                self.descriptors.sources = Some(sources.into_iter().flatten().collect());
+            },
+            "font-weight" => {
+                // Relative weights have no meaning outside of an element's style.
+                self.descriptors.weight = Some(match specified::FontWeight::parse(input)? {
+                    specified::FontWeight::Absolute(weight) => FontWeight(weight),
+                    _ => return Err(location.new_custom_error(StyleParseErrorKind::OtherInvalidValue(name.clone()))),
+                });
+            },
+            "font-style" => self.descriptors.style = Some(FontStyle::parse(input)?),
+            "unicode-range" => {
+                let ranges = input
+                    .parse_comma_separated::<_, _, StyleParseErrorKind<'i>>(|i| Ok(UnicodeRange::parse(i)?))?;
+                self.descriptors.unicode_range = Some(ranges);
+            },
+            _ => return Err(location.new_custom_error(StyleParseErrorKind::UnknownProperty(name))),
+        }
+        input.expect_exhausted()?;
+        Ok(())
+    }
+}
+
+impl<'i> AtRuleParser<'i> for FontFaceDescriptorParser {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = ();
+    type Error = StyleParseErrorKind<'i>;
+}
+
+/// Parses a single source of the `src` descriptor.  Returns `None` for URLs whose `format()` hint
+/// names only formats that can't be loaded, so that the next source is tried instead.
+fn parse_source<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Option<FontFaceSource>, ParseError<'i, StyleParseErrorKind<'i>>> {
+    if let Ok(url) = input.try_parse(|i| i.expect_url()) {
+        let url = url.to_string();
+        let formats: Result<Vec<String>, ParseError<StyleParseErrorKind>> = input.try_parse(|i| {
+            i.expect_function_matching("format")?;
+            i.parse_nested_block(|i| {
+                i.parse_comma_separated(|i| Ok(i.expect_string()?.to_ascii_lowercase()))
+            })
+        });
+        return Ok(match formats {
+            Ok(formats)
+                if !formats
+                    .iter()
+                    .any(|format| SUPPORTED_FORMATS.contains(&&**format)) =>
+            {
+                None
+            }
+            _ => Some(FontFaceSource::Url(url)),
+        });
+    }
+    input.expect_function_matching("local")?;
+    input.parse_nested_block(|i| match SingleFontFamily::parse(i)? {
+        SingleFontFamily::FamilyName(name) => Ok(Some(FontFaceSource::Local(name))),
+        SingleFontFamily::Generic(_) => {
+            Err(i.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::{parse_css_to_rules, CssRule};
+
+    fn parse_font_face(css: &str) -> Option<FontFaceRule> {
+        let mut rules = parse_css_to_rules(&mut css.to_owned()).expect("failed to parse css");
+        match rules.remove(0) {
+            CssRule::FontFace(rule) => Some(rule),
+            CssRule::None => None,
+            rule => panic!("expected a font face rule, got {:?}", rule),
+        }
+    }
+
+    #[test]
+    fn parses_font_face_descriptors() {
+        let rule = parse_font_face(
+            "@font-face {
+                font-family: \"Test Sans\";
+                src: url(fonts/missing.woff2) format(\"woff2\"), url(fonts/test.ttf) format(\"truetype\"), local(Test Sans Bold);
+                font-weight: bold;
+                font-style: italic;
+                unicode-range: U+0-7F, U+4??;
+            }",
+        )
+        .expect("font face should be valid");
+        assert_eq!(rule.family, "Test Sans");
+        assert_eq!(
+            rule.sources,
+            vec![
+                FontFaceSource::Url("fonts/test.ttf".to_owned()),
+                FontFaceSource::Local("Test Sans Bold".to_owned())
+            ]
+        );
+        assert_eq!(rule.weight, FontWeight::bold());
+        assert_eq!(rule.style, FontStyle::Italic);
+        assert!(rule.covers('a'));
+        assert!(rule.covers('\u{4FF}'));
+        assert!(!rule.covers('é'));
+    }
+
+    #[test]
+    fn font_face_without_src_is_invalid() {
+        assert_eq!(parse_font_face("@font-face { font-family: Test; }"), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Media queries, which make stylesheets conditional on the viewport they're displayed in.
+//!
+//! https://drafts.csswg.org/mediaqueries-4/
+
+use cssparser::{ParseError, Parser, ParserInput, Token};
+
+use crate::layout::LayoutViewportDimensions;
+use crate::style::values::specified::FONT_MEDIUM_PX;
+use crate::style::StyleParseErrorKind;
+
+/// A comma-separated list of media queries, which matches if any of its queries do.  An empty list
+/// matches everything.
+///
+/// https://drafts.csswg.org/mediaqueries-4/#media-query-list
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaList {
+    pub queries: Vec<MediaQuery>,
+}
+
+impl MediaList {
+    /// Parses a media query list.  Queries that can't be parsed are replaced by `not all`, rather
+    /// than invalidating the whole list.
+    pub fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> MediaList {
+        if input.is_exhausted() {
+            return MediaList::default();
+        }
+        let queries = input.parse_comma_separated::<_, _, StyleParseErrorKind<'i>>(|input| {
+            match input.try_parse(MediaQuery::parse) {
+                Ok(query) if input.is_exhausted() => Ok(query),
+                _ => {
+                    while input.next().is_ok() {}
+                    Ok(MediaQuery::never_matching())
+                }
+            }
+        });
+        MediaList {
+            queries: queries.unwrap_or_else(|_| vec![MediaQuery::never_matching()]),
+        }
+    }
+
+    /// Parses a media query list from the value of an HTML attribute, such as `<link media>`.
+    pub fn parse_str(media: &str) -> MediaList {
+        let mut input = ParserInput::new(media);
+        MediaList::parse(&mut Parser::new(&mut input))
+    }
+
+    pub fn matches(&self, viewport: LayoutViewportDimensions) -> bool {
+        self.queries.is_empty() || self.queries.iter().any(|query| query.matches(viewport))
+    }
+}
+
+/// A single media query, such as `screen and (min-width: 600px)`.
+///
+/// https://drafts.csswg.org/mediaqueries-4/#media
+#[derive(Clone, Debug, PartialEq)]
+pub struct MediaQuery {
+    /// Whether the query is negated with `not`.
+    pub negated: bool,
+    pub media_type: MediaType,
+    /// Conditions that must all be true for the query to match.
+    pub features: Vec<MediaFeature>,
+}
+
+impl MediaQuery {
+    /// The `not all` media query.
+    fn never_matching() -> MediaQuery {
+        MediaQuery {
+            negated: true,
+            media_type: MediaType::All,
+            features: Vec::new(),
+        }
+    }
+
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<MediaQuery, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let mut features = Vec::new();
+        let (negated, media_type) = if input.try_parse(|i| i.expect_parenthesis_block()).is_ok() {
+            features.push(input.parse_nested_block(MediaFeature::parse)?);
+            (false, MediaType::All)
+        } else {
+            let negated = input.try_parse(|i| i.expect_ident_matching("not")).is_ok();
+            if !negated {
+                // `only` exists to hide queries from user agents that don't understand them, and
+                // otherwise has no effect.
+                let _ = input.try_parse(|i| i.expect_ident_matching("only"));
+            }
+            (negated, MediaType::parse(input)?)
+        };
+        while input.try_parse(|i| i.expect_ident_matching("and")).is_ok() {
+            input.expect_parenthesis_block()?;
+            features.push(input.parse_nested_block(MediaFeature::parse)?);
+        }
+        Ok(MediaQuery {
+            negated,
+            media_type,
+            features,
+        })
+    }
+
+    fn matches(&self, viewport: LayoutViewportDimensions) -> bool {
+        let matches = self.media_type.matches()
+            && self
+                .features
+                .iter()
+                .all(|feature| feature.matches(viewport));
+        matches != self.negated
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+    /// Any other media type, which never matches.
+    Unknown,
+}
+
+impl MediaType {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<MediaType, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let ident = input.expect_ident()?;
+        Ok(match_ignore_ascii_case! { &ident,
+            "all" => MediaType::All,
+            "screen" => MediaType::Screen,
+            "print" => MediaType::Print,
+            // These are reserved keywords, and can't be used as media types.
+            "not" | "and" | "or" | "only" | "layer" => {
+                return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+            },
+            _ => MediaType::Unknown,
+        })
+    }
+
+    /// Whether the media type is that of Kosmonaut, which only ever displays to a screen.
+    fn matches(&self) -> bool {
+        match self {
+            MediaType::All | MediaType::Screen => true,
+            MediaType::Print | MediaType::Unknown => false,
+        }
+    }
+}
+
+/// A condition on the viewport, such as `(min-width: 600px)`.
+///
+/// https://drafts.csswg.org/mediaqueries-4/#mq-features
+#[derive(Clone, Debug, PartialEq)]
+pub enum MediaFeature {
+    Width(Range, f32),
+    Height(Range, f32),
+    Orientation(Orientation),
+}
+
+/// How a dimension in a media feature is compared to the viewport's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Range {
+    Min,
+    Max,
+    Exact,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl MediaFeature {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<MediaFeature, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let name = input.expect_ident()?.clone();
+        input.expect_colon()?;
+        let (range, feature) = if let Some(feature) = strip_prefix_ignore_ascii_case(&name, "min-")
+        {
+            (Range::Min, feature)
+        } else if let Some(feature) = strip_prefix_ignore_ascii_case(&name, "max-") {
+            (Range::Max, feature)
+        } else {
+            (Range::Exact, &*name)
+        };
+        let invalid_feature_name = || {
+            location.new_custom_error(StyleParseErrorKind::MediaQueryExpectedFeatureName(
+                name.clone(),
+            ))
+        };
+        match_ignore_ascii_case! { feature,
+            "width" => Ok(MediaFeature::Width(range, parse_length_px(input)?)),
+            "height" => Ok(MediaFeature::Height(range, parse_length_px(input)?)),
+            "orientation" => {
+                // Orientation is a discrete feature, so has no `min-` or `max-` prefixed forms.
+                if range != Range::Exact {
+                    return Err(invalid_feature_name());
+                }
+                let location = input.current_source_location();
+                let ident = input.expect_ident()?;
+                Ok(MediaFeature::Orientation(match_ignore_ascii_case! { &ident,
+                    "portrait" => Orientation::Portrait,
+                    "landscape" => Orientation::Landscape,
+                    _ => return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError)),
+                }))
+            },
+            _ => Err(invalid_feature_name()),
+        }
+    }
+
+    fn matches(&self, viewport: LayoutViewportDimensions) -> bool {
+        let (width, height) = viewport.width_height_px();
+        match *self {
+            MediaFeature::Width(range, px) => range.matches(width, px),
+            MediaFeature::Height(range, px) => range.matches(height, px),
+            MediaFeature::Orientation(orientation) => {
+                // The viewport is portrait when it's square.
+                match orientation {
+                    Orientation::Portrait => height >= width,
+                    Orientation::Landscape => width > height,
+                }
+            }
+        }
+    }
+}
+
+impl Range {
+    fn matches(self, actual: f32, px: f32) -> bool {
+        match self {
+            Range::Min => actual >= px,
+            Range::Max => actual <= px,
+            Range::Exact => (actual - px).abs() < f32::EPSILON,
+        }
+    }
+}
+
+fn strip_prefix_ignore_ascii_case<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    match name.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&name[prefix.len()..]),
+        _ => None,
+    }
+}
+
+/// Parses a length in a media feature.  Relative lengths are relative to the initial font size,
+/// since media queries don't apply to any element.
+///
+/// https://drafts.csswg.org/mediaqueries-4/#units
+fn parse_length_px<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<f32, ParseError<'i, StyleParseErrorKind<'i>>> {
+    let location = input.current_source_location();
+    match *input.next()? {
+        Token::Number { value, .. } if value == 0. => Ok(0.),
+        Token::Dimension {
+            value, ref unit, ..
+        } => match_ignore_ascii_case! { unit,
+            "px" => Ok(value),
+            "em" | "rem" => Ok(value * FONT_MEDIUM_PX as f32),
+            "in" => Ok(value * 96.),
+            "cm" => Ok(value * 96. / 2.54),
+            "mm" => Ok(value * 96. / 25.4),
+            "pt" => Ok(value * 96. / 72.),
+            "pc" => Ok(value * 16.),
+            _ => Err(location.new_custom_error(StyleParseErrorKind::UnexpectedDimension(unit.clone()))),
+        },
+        ref token => Err(location.new_unexpected_token_error(token.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(media: &str, width: f32, height: f32) -> bool {
+        MediaList::parse_str(media).matches(LayoutViewportDimensions::new_px(width, height))
+    }
+
+    #[test]
+    fn matches_media_types_and_features() {
+        assert!(matches("", 800., 600.));
+        assert!(matches("screen", 800., 600.));
+        assert!(!matches("print", 800., 600.));
+        assert!(matches("print, screen and (min-width: 50em)", 800., 600.));
+        assert!(!matches("screen and (min-width: 50em)", 799., 600.));
+        assert!(matches("only screen and (max-height: 600px)", 800., 600.));
+        assert!(matches("(orientation: landscape)", 800., 600.));
+        assert!(matches("not print", 800., 600.));
+        assert!(!matches("not all and (width: 800px)", 800., 600.));
+    }
+
+    #[test]
+    fn invalid_queries_never_match() {
+        assert!(!matches("screen and (unknown-feature: 1px)", 800., 600.));
+        assert!(!matches("and", 800., 600.));
+        assert!(matches("(width: foo), screen", 800., 600.));
+    }
+}
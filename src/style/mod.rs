@@ -1,21 +1,30 @@
 use std::convert::From;
 
 use cssparser::{
-    AtRuleParser, CowRcStr, ParseError, Parser, ParserInput, QualifiedRuleParser, RuleListParser,
-    SourceLocation, Token,
+    AtRuleParser, AtRuleType, CowRcStr, ParseError, Parser, ParserInput, QualifiedRuleParser,
+    RuleListParser, SourceLocation, Token,
 };
 use selectors::parser::SelectorParseErrorKind;
 
+use crate::style::font_face::{parse_font_face_block, FontFaceRule};
+use crate::style::media_queries::MediaList;
 use crate::style::properties::{parse_property_declaration_list, PropertyDeclarationBlock};
 use crate::style::select::Selectors;
+use crate::style::values::CssValueParse;
 
 #[macro_use]
 mod macros;
 
+pub mod custom_properties;
+pub mod damage;
 pub mod dom_integration;
+pub mod font_face;
+pub mod media_queries;
 pub mod properties;
 pub mod select;
+pub mod sharing;
 pub mod stylesheet;
+pub mod stylist;
 pub mod test_utils;
 pub mod values;
 
@@ -23,9 +32,24 @@ pub mod values;
 #[derive(Clone, Debug)]
 pub enum CssRule {
     Style(StyleRule),
+    FontFace(FontFaceRule),
+    Import(ImportRule),
     None,
 }
 
+/// An `@import` rule, which includes the rules of another stylesheet in the one it's found in.
+///
+/// https://drafts.csswg.org/css-cascade-4/#at-import
+#[derive(Clone, Debug)]
+pub struct ImportRule {
+    /// The URL of the imported stylesheet, as written.
+    pub url: String,
+    /// The media the imported stylesheet applies to.
+    pub media: MediaList,
+    /// The location in the sheet where it was found.
+    pub source_location: SourceLocation,
+}
+
 /// A style rule, with selectors and declarations.
 #[derive(Clone, Debug)]
 pub struct StyleRule {
@@ -50,6 +74,17 @@ pub enum CssOrigin {
     Sheet(StylesheetOrigin),
 }
 
+impl CssOrigin {
+    /// The cascade origin of declarations from this origin.  Inline and embedded styles are both
+    /// part of the document, so they have an author origin.
+    pub fn cascade_origin(&self) -> CascadeOrigin {
+        match self {
+            CssOrigin::Inline | CssOrigin::Embedded => CascadeOrigin::Author,
+            CssOrigin::Sheet(sheet_origin) => sheet_origin.cascade_origin,
+        }
+    }
+}
+
 /// https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#cascading-origins
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum CascadeOrigin {
@@ -83,7 +118,12 @@ pub fn parse_css_to_rules(
 ) -> Result<Vec<CssRule>, (ParseError<StyleParseErrorKind>, &str)> {
     let input = &mut ParserInput::new(css_str);
     let parser = &mut Parser::new(input);
-    let rule_parser = RuleListParser::new_for_stylesheet(parser, TopLevelRuleParser {});
+    let rule_parser = RuleListParser::new_for_stylesheet(
+        parser,
+        TopLevelRuleParser {
+            imports_allowed: true,
+        },
+    );
     let mut rules = Vec::new();
     for rule in rule_parser {
         rules.push(rule?)
@@ -92,19 +132,78 @@ pub fn parse_css_to_rules(
 }
 
 /// Parser for top-level CSS rules.
-pub struct TopLevelRuleParser {}
+pub struct TopLevelRuleParser {
+    /// Whether `@import` rules are still valid, which they are only before any other rules.
+    imports_allowed: bool,
+}
 
-// TODO: Support @ rules
-pub enum AtRuleNonBlockPrelude {}
+// TODO: Support more @ rules
+pub enum AtRuleNonBlockPrelude {
+    Import(String, MediaList),
+    /// An `@import` after other rules, which is ignored.
+    MisplacedImport,
+}
 
-pub enum AtRuleBlockPrelude {}
+pub enum AtRuleBlockPrelude {
+    FontFace,
+}
 
-/// Kosmonaut currently does not support @rules, so fall back to the default @rule error impl.
 impl<'i> AtRuleParser<'i> for TopLevelRuleParser {
     type PreludeNoBlock = AtRuleNonBlockPrelude;
     type PreludeBlock = AtRuleBlockPrelude;
     type AtRule = CssRule;
     type Error = StyleParseErrorKind<'i>;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<AtRuleType<Self::PreludeNoBlock, Self::PreludeBlock>, ParseError<'i, Self::Error>>
+    {
+        match_ignore_ascii_case! { &name,
+            "font-face" => {
+                self.imports_allowed = false;
+                Ok(AtRuleType::WithBlock(AtRuleBlockPrelude::FontFace))
+            },
+            "import" => {
+                if !self.imports_allowed {
+                    while input.next().is_ok() {}
+                    return Ok(AtRuleType::WithoutBlock(AtRuleNonBlockPrelude::MisplacedImport));
+                }
+                let url = input.expect_url_or_string()?.as_ref().to_owned();
+                let media = MediaList::parse(input);
+                Ok(AtRuleType::WithoutBlock(AtRuleNonBlockPrelude::Import(url, media)))
+            },
+            _ => Err(input.new_custom_error(StyleParseErrorKind::UnsupportedAtRule(name.clone()))),
+        }
+    }
+
+    fn rule_without_block(
+        &mut self,
+        prelude: Self::PreludeNoBlock,
+        source_location: SourceLocation,
+    ) -> Self::AtRule {
+        match prelude {
+            AtRuleNonBlockPrelude::Import(url, media) => CssRule::Import(ImportRule {
+                url,
+                media,
+                source_location,
+            }),
+            AtRuleNonBlockPrelude::MisplacedImport => CssRule::None,
+        }
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::PreludeBlock,
+        source_location: SourceLocation,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::AtRule, ParseError<'i, Self::Error>> {
+        match prelude {
+            AtRuleBlockPrelude::FontFace => Ok(parse_font_face_block(input, source_location)
+                .map_or(CssRule::None, CssRule::FontFace)),
+        }
+    }
 }
 
 impl<'i> QualifiedRuleParser<'i> for TopLevelRuleParser {
@@ -117,6 +216,7 @@ impl<'i> QualifiedRuleParser<'i> for TopLevelRuleParser {
         &mut self,
         input: &mut Parser<'i, 't>,
     ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        self.imports_allowed = false;
         Selectors::compile(input)
     }
 
@@ -221,6 +321,7 @@ pub enum ValueParseErrorKind<'i> {
 
 /// Value computations common to all CSS properties.
 /// https://www.w3.org/TR/css3-values/#common-keywords
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CssWideKeywords {
     /// Represents the value specified as the property’s initial value.
     Initial,
@@ -228,4 +329,20 @@ pub enum CssWideKeywords {
     Inherit,
     /// Acts as either inherit or initial, depending on whether the property is inherited or not.
     Unset,
+    /// Rolls the cascaded value back to the one the previous cascade origin would have produced.
+    /// https://www.w3.org/TR/css-cascade-4/#default
+    Revert,
+}
+
+impl CssValueParse for CssWideKeywords {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "initial" => Ok(CssWideKeywords::Initial),
+            "inherit" => Ok(CssWideKeywords::Inherit),
+            "unset" => Ok(CssWideKeywords::Unset),
+            "revert" => Ok(CssWideKeywords::Revert),
+        }
+    }
 }
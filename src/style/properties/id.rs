@@ -1,6 +1,7 @@
-use crate::style::properties::{ContextualPropertyDeclaration, PropertyDeclaration};
+use crate::style::custom_properties;
+use crate::style::properties::PropertyDeclaration;
 use crate::style::values::computed::{
-    ComputeContext, ComputedValuesBuilder, LineStyle, ValueDefault,
+    ComputeContext, ComputedLonghandsBuilder, LineStyle, ValueDefault,
 };
 use crate::style::values::{computed, specified};
 
@@ -14,16 +15,26 @@ pub enum PropertyId {
     Longhand(LonghandId),
     /// A shorthand property.
     Shorthand(ShorthandId),
-    // TODO: Support custom properties
-    //    /// A custom property.
-    //    Custom(crate::custom_properties::Name),
+    /// A custom property.
+    Custom(custom_properties::Name),
 }
 
 impl PropertyId {
     pub fn parse(prop_name: &str) -> Option<PropertyId> {
+        if let Some(name) = custom_properties::parse_name(prop_name) {
+            return Some(PropertyId::Custom(name.to_owned()));
+        }
         let id = match prop_name {
             // Longhands
+            "background-attachment" => PropertyId::Longhand(LonghandId::BackgroundAttachment),
+            "background-clip" => PropertyId::Longhand(LonghandId::BackgroundClip),
             "background-color" => PropertyId::Longhand(LonghandId::BackgroundColor),
+            "background-image" => PropertyId::Longhand(LonghandId::BackgroundImage),
+            "background-origin" => PropertyId::Longhand(LonghandId::BackgroundOrigin),
+            "background-position-x" => PropertyId::Longhand(LonghandId::BackgroundPositionX),
+            "background-position-y" => PropertyId::Longhand(LonghandId::BackgroundPositionY),
+            "background-repeat" => PropertyId::Longhand(LonghandId::BackgroundRepeat),
+            "background-size" => PropertyId::Longhand(LonghandId::BackgroundSize),
             "border-bottom-color" => PropertyId::Longhand(LonghandId::BorderBottomColor),
             "border-left-color" => PropertyId::Longhand(LonghandId::BorderLeftColor),
             "border-right-color" => PropertyId::Longhand(LonghandId::BorderRightColor),
@@ -36,27 +47,47 @@ impl PropertyId {
             "border-left-width" => PropertyId::Longhand(LonghandId::BorderLeftWidth),
             "border-right-width" => PropertyId::Longhand(LonghandId::BorderRightWidth),
             "border-top-width" => PropertyId::Longhand(LonghandId::BorderTopWidth),
+            "border-bottom-left-radius" => PropertyId::Longhand(LonghandId::BorderBottomLeftRadius),
+            "border-bottom-right-radius" => {
+                PropertyId::Longhand(LonghandId::BorderBottomRightRadius)
+            }
+            "border-end-end-radius" => PropertyId::Longhand(LonghandId::BorderEndEndRadius),
+            "border-end-start-radius" => PropertyId::Longhand(LonghandId::BorderEndStartRadius),
+            "border-start-end-radius" => PropertyId::Longhand(LonghandId::BorderStartEndRadius),
+            "border-start-start-radius" => PropertyId::Longhand(LonghandId::BorderStartStartRadius),
+            "border-top-left-radius" => PropertyId::Longhand(LonghandId::BorderTopLeftRadius),
+            "border-top-right-radius" => PropertyId::Longhand(LonghandId::BorderTopRightRadius),
             "color" => PropertyId::Longhand(LonghandId::Color),
             "direction" => PropertyId::Longhand(LonghandId::Direction),
             "display" => PropertyId::Longhand(LonghandId::Display),
             //            "float" => PropertyId::Longhand(LonghandId::Float),
-            //            "font-style" => PropertyId::Longhand(LonghandId::FontStyle),
-            //            "font-weight" => PropertyId::Longhand(LonghandId::FontWeight),
             //            "visibility" => PropertyId::Longhand(LonghandId::Visibility),
+            "font-family" => PropertyId::Longhand(LonghandId::FontFamily),
             "font-size" => PropertyId::Longhand(LonghandId::FontSize),
+            "font-stretch" => PropertyId::Longhand(LonghandId::FontStretch),
+            "font-style" => PropertyId::Longhand(LonghandId::FontStyle),
+            "font-weight" => PropertyId::Longhand(LonghandId::FontWeight),
             "height" => PropertyId::Longhand(LonghandId::Height),
+            "line-height" => PropertyId::Longhand(LonghandId::LineHeight),
             "margin-bottom" => PropertyId::Longhand(LonghandId::MarginBottom),
             "margin-left" => PropertyId::Longhand(LonghandId::MarginLeft),
             "margin-right" => PropertyId::Longhand(LonghandId::MarginRight),
             "margin-top" => PropertyId::Longhand(LonghandId::MarginTop),
+            "overflow-x" => PropertyId::Longhand(LonghandId::OverflowX),
+            "overflow-y" => PropertyId::Longhand(LonghandId::OverflowY),
             "padding-bottom" => PropertyId::Longhand(LonghandId::PaddingBottom),
             "padding-left" => PropertyId::Longhand(LonghandId::PaddingLeft),
             "padding-right" => PropertyId::Longhand(LonghandId::PaddingRight),
             "padding-top" => PropertyId::Longhand(LonghandId::PaddingTop),
+            "text-orientation" => PropertyId::Longhand(LonghandId::TextOrientation),
+            "unicode-bidi" => PropertyId::Longhand(LonghandId::UnicodeBidi),
+            "vertical-align" => PropertyId::Longhand(LonghandId::VerticalAlign),
+            "white-space" => PropertyId::Longhand(LonghandId::WhiteSpace),
             "width" => PropertyId::Longhand(LonghandId::Width),
             "writing-mode" => PropertyId::Longhand(LonghandId::WritingMode),
             // Shorthands
             "background" => PropertyId::Shorthand(ShorthandId::Background),
+            "background-position" => PropertyId::Shorthand(ShorthandId::BackgroundPosition),
             "border-color" => PropertyId::Shorthand(ShorthandId::BorderColor),
             "border-style" => PropertyId::Shorthand(ShorthandId::BorderStyle),
             "border-width" => PropertyId::Shorthand(ShorthandId::BorderWidth),
@@ -65,7 +96,10 @@ impl PropertyId {
             "border-bottom" => PropertyId::Shorthand(ShorthandId::BorderBottom),
             "border-left" => PropertyId::Shorthand(ShorthandId::BorderLeft),
             "border" => PropertyId::Shorthand(ShorthandId::Border),
+            "border-radius" => PropertyId::Shorthand(ShorthandId::BorderRadius),
+            "font" => PropertyId::Shorthand(ShorthandId::Font),
             "margin" => PropertyId::Shorthand(ShorthandId::Margin),
+            "overflow" => PropertyId::Shorthand(ShorthandId::Overflow),
             "padding" => PropertyId::Shorthand(ShorthandId::Padding),
             _ => return None,
         };
@@ -111,14 +145,14 @@ pub enum LonghandId {
     //    FlexWrap = 15,
     //    /// float
     //    Float = 16,
-    //    /// font-stretch
-    //    FontStretch = 17,
-    //    /// font-style
-    //    FontStyle = 18,
+    /// font-stretch
+    FontStretch = 17,
+    /// font-style
+    FontStyle = 18,
     //    /// font-variant-caps
     //    FontVariantCaps = 19,
-    //    /// font-weight
-    //    FontWeight = 20,
+    /// font-weight
+    FontWeight = 20,
     //    /// image-rendering
     //    ImageRendering = 21,
     //    /// justify-content
@@ -155,12 +189,12 @@ pub enum LonghandId {
     //    TextTransform = 37,
     //    /// transform-style
     //    TransformStyle = 38,
-    //    /// unicode-bidi
-    //    UnicodeBidi = 39,
+    /// unicode-bidi
+    UnicodeBidi = 39,
     //    /// visibility
     //    Visibility = 40,
-    //    /// white-space
-    //    WhiteSpace = 41,
+    /// white-space
+    WhiteSpace = 41,
     //    /// word-break
     //    WordBreak = 42,
     /// writing-mode
@@ -175,10 +209,10 @@ pub enum LonghandId {
     //    OverflowBlock = 47,
     //    /// overflow-inline
     //    OverflowInline = 48,
-    //    /// overflow-x
-    //    OverflowX = 49,
-    //    /// overflow-y
-    //    OverflowY = 50,
+    /// overflow-x
+    OverflowX = 49,
+    /// overflow-y
+    OverflowY = 50,
     //    /// border-block-end-style
     //    BorderBlockEndStyle = 51,
     //    /// border-block-start-style
@@ -211,22 +245,22 @@ pub enum LonghandId {
     //    AnimationPlayState = 65,
     //    /// animation-timing-function
     //    AnimationTimingFunction = 66,
-    //    /// background-attachment
-    //    BackgroundAttachment = 67,
-    //    /// background-clip
-    //    BackgroundClip = 68,
-    //    /// background-image
-    //    BackgroundImage = 69,
-    //    /// background-origin
-    //    BackgroundOrigin = 70,
-    //    /// background-position-x
-    //    BackgroundPositionX = 71,
-    //    /// background-position-y
-    //    BackgroundPositionY = 72,
-    //    /// background-repeat
-    //    BackgroundRepeat = 73,
-    //    /// background-size
-    //    BackgroundSize = 74,
+    /// background-attachment
+    BackgroundAttachment = 67,
+    /// background-clip
+    BackgroundClip = 68,
+    /// background-image
+    BackgroundImage = 69,
+    /// background-origin
+    BackgroundOrigin = 70,
+    /// background-position-x
+    BackgroundPositionX = 71,
+    /// background-position-y
+    BackgroundPositionY = 72,
+    /// background-repeat
+    BackgroundRepeat = 73,
+    /// background-size
+    BackgroundSize = 74,
     //    /// border-image-outset
     //    BorderImageOutset = 75,
     //    /// border-image-slice
@@ -259,14 +293,14 @@ pub enum LonghandId {
     //    Filter = 89,
     //    /// flex-basis
     //    FlexBasis = 90,
-    //    /// font-family
-    //    FontFamily = 91,
+    /// font-family
+    FontFamily = 91,
     /// font-size
     FontSize = 92,
     //    /// letter-spacing
     //    LetterSpacing = 93,
-    //    /// line-height
-    //    LineHeight = 94,
+    /// line-height
+    LineHeight = 94,
     //    /// list-style-image
     //    ListStyleImage = 95,
     //    /// outline-offset
@@ -301,8 +335,8 @@ pub enum LonghandId {
     //    TransitionTimingFunction = 110,
     //    /// translate
     //    Translate = 111,
-    //    /// vertical-align
-    //    VerticalAlign = 112,
+    /// vertical-align
+    VerticalAlign = 112,
     //    /// word-spacing
     //    WordSpacing = 113,
     //    /// max-block-size
@@ -313,22 +347,22 @@ pub enum LonghandId {
     //    MaxInlineSize = 116,
     //    /// max-width
     //    MaxWidth = 117,
-    //    /// border-bottom-left-radius
-    //    BorderBottomLeftRadius = 118,
-    //    /// border-bottom-right-radius
-    //    BorderBottomRightRadius = 119,
-    //    /// border-end-end-radius
-    //    BorderEndEndRadius = 120,
-    //    /// border-end-start-radius
-    //    BorderEndStartRadius = 121,
-    //    /// border-start-end-radius
-    //    BorderStartEndRadius = 122,
-    //    /// border-start-start-radius
-    //    BorderStartStartRadius = 123,
-    //    /// border-top-left-radius
-    //    BorderTopLeftRadius = 124,
-    //    /// border-top-right-radius
-    //    BorderTopRightRadius = 125,
+    /// border-bottom-left-radius
+    BorderBottomLeftRadius = 118,
+    /// border-bottom-right-radius
+    BorderBottomRightRadius = 119,
+    /// border-end-end-radius
+    BorderEndEndRadius = 120,
+    /// border-end-start-radius
+    BorderEndStartRadius = 121,
+    /// border-start-end-radius
+    BorderStartEndRadius = 122,
+    /// border-start-start-radius
+    BorderStartStartRadius = 123,
+    /// border-top-left-radius
+    BorderTopLeftRadius = 124,
+    /// border-top-right-radius
+    BorderTopRightRadius = 125,
     //    /// padding-block-end
     //    PaddingBlockEnd = 126,
     //    /// padding-block-start
@@ -431,15 +465,57 @@ pub enum LonghandId {
     //    Right = 175,
     //    /// top
     //    Top = 176,
+    /// text-orientation
+    TextOrientation = 177,
 }
 
 impl LonghandId {
-    #[allow(unreachable_patterns)]
-    pub fn value_default(self, cv_builder: &mut ComputedValuesBuilder, ctx: &ComputeContext) {
+    /// Whether the longhand is a flow-relative one.  These aren't computed themselves, but cascade
+    /// together with the physical longhand they map to for the element's writing mode.
+    ///
+    /// https://drafts.csswg.org/css-logical/#logical-longhands
+    pub fn is_logical(self) -> bool {
+        matches!(
+            self,
+            LonghandId::BorderEndEndRadius
+                | LonghandId::BorderEndStartRadius
+                | LonghandId::BorderStartEndRadius
+                | LonghandId::BorderStartStartRadius
+        )
+    }
+
+    pub fn value_default(self, cv_builder: &mut ComputedLonghandsBuilder, ctx: &ComputeContext) {
         match self {
+            LonghandId::BackgroundAttachment => {
+                cv_builder
+                    .background_attachment(computed::BackgroundAttachment::value_default(ctx));
+            }
+            LonghandId::BackgroundClip => {
+                cv_builder.background_clip(computed::BackgroundClip::value_default(ctx));
+            }
             LonghandId::BackgroundColor => {
                 cv_builder.background_color(specified::BackgroundColor::value_default(ctx));
             }
+            LonghandId::BackgroundImage => {
+                cv_builder.background_image(specified::BackgroundImage::value_default(ctx));
+            }
+            LonghandId::BackgroundOrigin => {
+                cv_builder.background_origin(computed::BackgroundOrigin::value_default(ctx));
+            }
+            LonghandId::BackgroundPositionX => {
+                cv_builder
+                    .background_position_x(specified::BackgroundPositionX::value_default(ctx));
+            }
+            LonghandId::BackgroundPositionY => {
+                cv_builder
+                    .background_position_y(specified::BackgroundPositionY::value_default(ctx));
+            }
+            LonghandId::BackgroundRepeat => {
+                cv_builder.background_repeat(computed::BackgroundRepeat::value_default(ctx));
+            }
+            LonghandId::BackgroundSize => {
+                cv_builder.background_size(specified::BackgroundSize::value_default(ctx));
+            }
             LonghandId::BorderBottomColor => {
                 cv_builder.border_bottom_color(specified::BorderColor::value_default(ctx));
             }
@@ -476,6 +552,22 @@ impl LonghandId {
             LonghandId::BorderTopWidth => {
                 cv_builder.border_top_width(specified::BorderTopWidth::value_default(ctx));
             }
+            LonghandId::BorderBottomLeftRadius => {
+                cv_builder
+                    .border_bottom_left_radius(specified::BorderCornerRadius::value_default(ctx));
+            }
+            LonghandId::BorderBottomRightRadius => {
+                cv_builder
+                    .border_bottom_right_radius(specified::BorderCornerRadius::value_default(ctx));
+            }
+            LonghandId::BorderTopLeftRadius => {
+                cv_builder
+                    .border_top_left_radius(specified::BorderCornerRadius::value_default(ctx));
+            }
+            LonghandId::BorderTopRightRadius => {
+                cv_builder
+                    .border_top_right_radius(specified::BorderCornerRadius::value_default(ctx));
+            }
             LonghandId::Color => {
                 cv_builder.color(specified::Color::value_default(ctx));
             }
@@ -485,12 +577,27 @@ impl LonghandId {
             LonghandId::Display => {
                 cv_builder.display(computed::Display::value_default(ctx));
             }
+            LonghandId::FontFamily => {
+                cv_builder.font_family(computed::FontFamily::value_default(ctx));
+            }
             LonghandId::FontSize => {
                 cv_builder.font_size(specified::FontSize::value_default(ctx));
             }
+            LonghandId::FontStretch => {
+                cv_builder.font_stretch(computed::FontStretch::value_default(ctx));
+            }
+            LonghandId::FontStyle => {
+                cv_builder.font_style(computed::FontStyle::value_default(ctx));
+            }
+            LonghandId::FontWeight => {
+                cv_builder.font_weight(specified::FontWeight::value_default(ctx));
+            }
             LonghandId::Height => {
                 cv_builder.height(specified::Height::value_default(ctx));
             }
+            LonghandId::LineHeight => {
+                cv_builder.line_height(specified::LineHeight::value_default(ctx));
+            }
             LonghandId::MarginBottom => {
                 cv_builder.margin_bottom(specified::Margin::value_default(ctx));
             }
@@ -503,6 +610,12 @@ impl LonghandId {
             LonghandId::MarginTop => {
                 cv_builder.margin_top(specified::Margin::value_default(ctx));
             }
+            LonghandId::OverflowX => {
+                cv_builder.overflow_x(computed::Overflow::value_default(ctx));
+            }
+            LonghandId::OverflowY => {
+                cv_builder.overflow_y(computed::Overflow::value_default(ctx));
+            }
             LonghandId::PaddingBottom => {
                 cv_builder.padding_bottom(specified::Padding::value_default(ctx));
             }
@@ -515,24 +628,411 @@ impl LonghandId {
             LonghandId::PaddingTop => {
                 cv_builder.padding_top(specified::Padding::value_default(ctx));
             }
+            LonghandId::TextOrientation => {
+                cv_builder.text_orientation(computed::TextOrientation::value_default(ctx));
+            }
+            LonghandId::UnicodeBidi => {
+                cv_builder.unicode_bidi(computed::UnicodeBidi::value_default(ctx));
+            }
+            LonghandId::VerticalAlign => {
+                cv_builder.vertical_align(specified::VerticalAlign::value_default(ctx));
+            }
+            LonghandId::WhiteSpace => {
+                cv_builder.white_space(computed::WhiteSpace::value_default(ctx));
+            }
             LonghandId::Width => {
                 cv_builder.width(specified::Width::value_default(ctx));
             }
             LonghandId::WritingMode => {
                 cv_builder.writing_mode(computed::WritingMode::value_default(ctx));
             }
-            _ => unimplemented!(
-                "{}",
-                format!("value default by longhand for id: {:?}", self)
-            ),
+            LonghandId::BorderEndEndRadius
+            | LonghandId::BorderEndStartRadius
+            | LonghandId::BorderStartEndRadius
+            | LonghandId::BorderStartStartRadius => {
+                unreachable!("logical longhands cascade with the physical longhand they map to")
+            }
         }
     }
+
+    /// Sets the longhand to its initial value, for the `initial` keyword.
+    ///
+    /// https://www.w3.org/TR/css-cascade-4/#initial
+    pub fn initial(self, cv_builder: &mut ComputedLonghandsBuilder, ctx: &ComputeContext) {
+        match self {
+            LonghandId::BackgroundAttachment => {
+                cv_builder.background_attachment(computed::BackgroundAttachment::initial_value());
+            }
+            LonghandId::BackgroundClip => {
+                cv_builder.background_clip(computed::BackgroundClip::initial_value());
+            }
+            LonghandId::BackgroundColor => {
+                cv_builder
+                    .background_color(computed::BackgroundColor::initial_value(ctx.color().rgba()));
+            }
+            LonghandId::BackgroundImage => {
+                cv_builder.background_image(computed::BackgroundImage::initial_value());
+            }
+            LonghandId::BackgroundOrigin => {
+                cv_builder.background_origin(computed::BackgroundOrigin::initial_value());
+            }
+            LonghandId::BackgroundPositionX => {
+                cv_builder.background_position_x(computed::BackgroundPositionX::initial_value());
+            }
+            LonghandId::BackgroundPositionY => {
+                cv_builder.background_position_y(computed::BackgroundPositionY::initial_value());
+            }
+            LonghandId::BackgroundRepeat => {
+                cv_builder.background_repeat(computed::BackgroundRepeat::initial_value());
+            }
+            LonghandId::BackgroundSize => {
+                cv_builder.background_size(computed::BackgroundSize::initial_value());
+            }
+            LonghandId::BorderBottomColor => {
+                cv_builder
+                    .border_bottom_color(computed::BorderColor::initial_value(ctx.color().rgba()));
+            }
+            LonghandId::BorderLeftColor => {
+                cv_builder
+                    .border_left_color(computed::BorderColor::initial_value(ctx.color().rgba()));
+            }
+            LonghandId::BorderRightColor => {
+                cv_builder
+                    .border_right_color(computed::BorderColor::initial_value(ctx.color().rgba()));
+            }
+            LonghandId::BorderTopColor => {
+                cv_builder
+                    .border_top_color(computed::BorderColor::initial_value(ctx.color().rgba()));
+            }
+            LonghandId::BorderBottomStyle => {
+                cv_builder.border_bottom_style(LineStyle::None);
+            }
+            LonghandId::BorderLeftStyle => {
+                cv_builder.border_left_style(LineStyle::None);
+            }
+            LonghandId::BorderRightStyle => {
+                cv_builder.border_right_style(LineStyle::None);
+            }
+            LonghandId::BorderTopStyle => {
+                cv_builder.border_top_style(LineStyle::None);
+            }
+            LonghandId::BorderBottomWidth => {
+                cv_builder.border_bottom_width(computed::BorderWidth::initial_value(
+                    ctx.border_bottom_style(),
+                ));
+            }
+            LonghandId::BorderLeftWidth => {
+                cv_builder.border_left_width(computed::BorderWidth::initial_value(
+                    ctx.border_left_style(),
+                ));
+            }
+            LonghandId::BorderRightWidth => {
+                cv_builder.border_right_width(computed::BorderWidth::initial_value(
+                    ctx.border_right_style(),
+                ));
+            }
+            LonghandId::BorderTopWidth => {
+                cv_builder
+                    .border_top_width(computed::BorderWidth::initial_value(ctx.border_top_style()));
+            }
+            LonghandId::BorderBottomLeftRadius => {
+                cv_builder.border_bottom_left_radius(computed::BorderCornerRadius::initial_value());
+            }
+            LonghandId::BorderBottomRightRadius => {
+                cv_builder
+                    .border_bottom_right_radius(computed::BorderCornerRadius::initial_value());
+            }
+            LonghandId::BorderTopLeftRadius => {
+                cv_builder.border_top_left_radius(computed::BorderCornerRadius::initial_value());
+            }
+            LonghandId::BorderTopRightRadius => {
+                cv_builder.border_top_right_radius(computed::BorderCornerRadius::initial_value());
+            }
+            LonghandId::Color => {
+                cv_builder.color(computed::Color::initial_value());
+            }
+            LonghandId::Direction => {
+                cv_builder.direction(computed::Direction::initial_value());
+            }
+            LonghandId::Display => {
+                cv_builder.display(computed::Display::initial_value());
+            }
+            LonghandId::FontFamily => {
+                cv_builder.font_family(computed::FontFamily::initial_value());
+            }
+            LonghandId::FontSize => {
+                cv_builder.font_size(computed::FontSize::initial_value());
+            }
+            LonghandId::FontStretch => {
+                cv_builder.font_stretch(computed::FontStretch::initial_value());
+            }
+            LonghandId::FontStyle => {
+                cv_builder.font_style(computed::FontStyle::initial_value());
+            }
+            LonghandId::FontWeight => {
+                cv_builder.font_weight(computed::FontWeight::initial_value());
+            }
+            LonghandId::Height => {
+                cv_builder.height(computed::height::Height::initial_value());
+            }
+            LonghandId::LineHeight => {
+                cv_builder.line_height(computed::LineHeight::initial_value());
+            }
+            LonghandId::MarginBottom => {
+                cv_builder.margin_bottom(computed::Margin::initial_value());
+            }
+            LonghandId::MarginLeft => {
+                cv_builder.margin_left(computed::Margin::initial_value());
+            }
+            LonghandId::MarginRight => {
+                cv_builder.margin_right(computed::Margin::initial_value());
+            }
+            LonghandId::MarginTop => {
+                cv_builder.margin_top(computed::Margin::initial_value());
+            }
+            LonghandId::OverflowX => {
+                cv_builder.overflow_x(computed::Overflow::initial_value());
+            }
+            LonghandId::OverflowY => {
+                cv_builder.overflow_y(computed::Overflow::initial_value());
+            }
+            LonghandId::PaddingBottom => {
+                cv_builder.padding_bottom(computed::Padding::initial_value());
+            }
+            LonghandId::PaddingLeft => {
+                cv_builder.padding_left(computed::Padding::initial_value());
+            }
+            LonghandId::PaddingRight => {
+                cv_builder.padding_right(computed::Padding::initial_value());
+            }
+            LonghandId::PaddingTop => {
+                cv_builder.padding_top(computed::Padding::initial_value());
+            }
+            LonghandId::TextOrientation => {
+                cv_builder.text_orientation(computed::TextOrientation::initial_value());
+            }
+            LonghandId::UnicodeBidi => {
+                cv_builder.unicode_bidi(computed::UnicodeBidi::initial_value());
+            }
+            LonghandId::VerticalAlign => {
+                cv_builder.vertical_align(computed::VerticalAlign::initial_value());
+            }
+            LonghandId::WhiteSpace => {
+                cv_builder.white_space(computed::WhiteSpace::initial_value());
+            }
+            LonghandId::Width => {
+                cv_builder.width(computed::width::Width::initial_value());
+            }
+            LonghandId::WritingMode => {
+                cv_builder.writing_mode(computed::WritingMode::initial_value());
+            }
+            LonghandId::BorderEndEndRadius
+            | LonghandId::BorderEndStartRadius
+            | LonghandId::BorderStartEndRadius
+            | LonghandId::BorderStartStartRadius => {
+                unreachable!("logical longhands cascade with the physical longhand they map to")
+            }
+        }
+    }
+
+    /// Sets the longhand to the computed value of the parent, for the `inherit` keyword.
+    ///
+    /// https://www.w3.org/TR/css-cascade-4/#inherit
+    pub fn inherit(self, cv_builder: &mut ComputedLonghandsBuilder, ctx: &ComputeContext) {
+        let parent = ctx.parent_computed_values;
+        match self {
+            LonghandId::BackgroundAttachment => {
+                cv_builder
+                    .background_attachment(parent.get_background().background_attachment.clone());
+            }
+            LonghandId::BackgroundClip => {
+                cv_builder.background_clip(parent.get_background().background_clip.clone());
+            }
+            LonghandId::BackgroundColor => {
+                cv_builder.background_color(parent.get_background().background_color);
+            }
+            LonghandId::BackgroundImage => {
+                cv_builder.background_image(parent.get_background().background_image.clone());
+            }
+            LonghandId::BackgroundOrigin => {
+                cv_builder.background_origin(parent.get_background().background_origin.clone());
+            }
+            LonghandId::BackgroundPositionX => {
+                cv_builder
+                    .background_position_x(parent.get_background().background_position_x.clone());
+            }
+            LonghandId::BackgroundPositionY => {
+                cv_builder
+                    .background_position_y(parent.get_background().background_position_y.clone());
+            }
+            LonghandId::BackgroundRepeat => {
+                cv_builder.background_repeat(parent.get_background().background_repeat.clone());
+            }
+            LonghandId::BackgroundSize => {
+                cv_builder.background_size(parent.get_background().background_size.clone());
+            }
+            LonghandId::BorderBottomColor => {
+                cv_builder.border_bottom_color(parent.get_border().border_bottom_color);
+            }
+            LonghandId::BorderLeftColor => {
+                cv_builder.border_left_color(parent.get_border().border_left_color);
+            }
+            LonghandId::BorderRightColor => {
+                cv_builder.border_right_color(parent.get_border().border_right_color);
+            }
+            LonghandId::BorderTopColor => {
+                cv_builder.border_top_color(parent.get_border().border_top_color);
+            }
+            LonghandId::BorderBottomStyle => {
+                cv_builder.border_bottom_style(parent.get_border().border_bottom_style);
+            }
+            LonghandId::BorderLeftStyle => {
+                cv_builder.border_left_style(parent.get_border().border_left_style);
+            }
+            LonghandId::BorderRightStyle => {
+                cv_builder.border_right_style(parent.get_border().border_right_style);
+            }
+            LonghandId::BorderTopStyle => {
+                cv_builder.border_top_style(parent.get_border().border_top_style);
+            }
+            LonghandId::BorderBottomWidth => {
+                cv_builder.border_bottom_width(parent.get_border().border_bottom_width);
+            }
+            LonghandId::BorderLeftWidth => {
+                cv_builder.border_left_width(parent.get_border().border_left_width);
+            }
+            LonghandId::BorderRightWidth => {
+                cv_builder.border_right_width(parent.get_border().border_right_width);
+            }
+            LonghandId::BorderTopWidth => {
+                cv_builder.border_top_width(parent.get_border().border_top_width);
+            }
+            LonghandId::BorderBottomLeftRadius => {
+                cv_builder.border_bottom_left_radius(parent.get_border().border_bottom_left_radius);
+            }
+            LonghandId::BorderBottomRightRadius => {
+                cv_builder
+                    .border_bottom_right_radius(parent.get_border().border_bottom_right_radius);
+            }
+            LonghandId::BorderTopLeftRadius => {
+                cv_builder.border_top_left_radius(parent.get_border().border_top_left_radius);
+            }
+            LonghandId::BorderTopRightRadius => {
+                cv_builder.border_top_right_radius(parent.get_border().border_top_right_radius);
+            }
+            LonghandId::Color => {
+                cv_builder.color(parent.get_inherited_text().color);
+            }
+            LonghandId::Direction => {
+                cv_builder.direction(parent.get_inherited_box().direction);
+            }
+            LonghandId::Display => {
+                cv_builder.display(parent.get_box().display);
+            }
+            LonghandId::FontFamily => {
+                cv_builder.font_family(parent.get_font().font_family.clone());
+            }
+            LonghandId::FontSize => {
+                cv_builder.font_size(parent.get_font().font_size);
+            }
+            LonghandId::FontStretch => {
+                cv_builder.font_stretch(parent.get_font().font_stretch);
+            }
+            LonghandId::FontStyle => {
+                cv_builder.font_style(parent.get_font().font_style);
+            }
+            LonghandId::FontWeight => {
+                cv_builder.font_weight(parent.get_font().font_weight);
+            }
+            LonghandId::Height => {
+                cv_builder.height(parent.get_position().height);
+            }
+            LonghandId::LineHeight => {
+                cv_builder.line_height(parent.get_inherited_text().line_height);
+            }
+            LonghandId::MarginBottom => {
+                cv_builder.margin_bottom(parent.get_margin().margin_bottom);
+            }
+            LonghandId::MarginLeft => {
+                cv_builder.margin_left(parent.get_margin().margin_left);
+            }
+            LonghandId::MarginRight => {
+                cv_builder.margin_right(parent.get_margin().margin_right);
+            }
+            LonghandId::MarginTop => {
+                cv_builder.margin_top(parent.get_margin().margin_top);
+            }
+            LonghandId::OverflowX => {
+                cv_builder.overflow_x(parent.get_box().overflow_x);
+            }
+            LonghandId::OverflowY => {
+                cv_builder.overflow_y(parent.get_box().overflow_y);
+            }
+            LonghandId::PaddingBottom => {
+                cv_builder.padding_bottom(parent.get_padding().padding_bottom);
+            }
+            LonghandId::PaddingLeft => {
+                cv_builder.padding_left(parent.get_padding().padding_left);
+            }
+            LonghandId::PaddingRight => {
+                cv_builder.padding_right(parent.get_padding().padding_right);
+            }
+            LonghandId::PaddingTop => {
+                cv_builder.padding_top(parent.get_padding().padding_top);
+            }
+            LonghandId::TextOrientation => {
+                cv_builder.text_orientation(parent.get_inherited_box().text_orientation);
+            }
+            LonghandId::UnicodeBidi => {
+                cv_builder.unicode_bidi(parent.get_box().unicode_bidi);
+            }
+            LonghandId::VerticalAlign => {
+                cv_builder.vertical_align(parent.get_box().vertical_align);
+            }
+            LonghandId::WhiteSpace => {
+                cv_builder.white_space(parent.get_inherited_text().white_space);
+            }
+            LonghandId::Width => {
+                cv_builder.width(parent.get_position().width);
+            }
+            LonghandId::WritingMode => {
+                cv_builder.writing_mode(parent.get_inherited_box().writing_mode);
+            }
+            LonghandId::BorderEndEndRadius
+            | LonghandId::BorderEndStartRadius
+            | LonghandId::BorderStartEndRadius
+            | LonghandId::BorderStartStartRadius => {
+                unreachable!("logical longhands cascade with the physical longhand they map to")
+            }
+        }
+    }
+}
+
+/// The property a declaration is for.  Unlike `PropertyId`, this is never a shorthand, as
+/// shorthands are expanded into longhand declarations when parsed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PropertyDeclarationId<'a> {
+    Longhand(LonghandId),
+    Custom(&'a custom_properties::Name),
 }
 
-impl From<&PropertyDeclaration> for LonghandId {
-    fn from(prop_decl: &PropertyDeclaration) -> Self {
-        match prop_decl {
+impl PropertyDeclaration {
+    pub fn id(&self) -> PropertyDeclarationId {
+        let longhand = match self {
+            PropertyDeclaration::Custom(declaration) => {
+                return PropertyDeclarationId::Custom(&declaration.name)
+            }
+            PropertyDeclaration::CssWideKeyword(declaration) => declaration.id,
+            PropertyDeclaration::WithVariables(declaration) => declaration.id,
+            PropertyDeclaration::BackgroundAttachment(_) => LonghandId::BackgroundAttachment,
+            PropertyDeclaration::BackgroundClip(_) => LonghandId::BackgroundClip,
             PropertyDeclaration::BackgroundColor(_) => LonghandId::BackgroundColor,
+            PropertyDeclaration::BackgroundImage(_) => LonghandId::BackgroundImage,
+            PropertyDeclaration::BackgroundOrigin(_) => LonghandId::BackgroundOrigin,
+            PropertyDeclaration::BackgroundPositionX(_) => LonghandId::BackgroundPositionX,
+            PropertyDeclaration::BackgroundPositionY(_) => LonghandId::BackgroundPositionY,
+            PropertyDeclaration::BackgroundRepeat(_) => LonghandId::BackgroundRepeat,
+            PropertyDeclaration::BackgroundSize(_) => LonghandId::BackgroundSize,
             PropertyDeclaration::BorderBottomColor(_) => LonghandId::BorderBottomColor,
             PropertyDeclaration::BorderLeftColor(_) => LonghandId::BorderLeftColor,
             PropertyDeclaration::BorderRightColor(_) => LonghandId::BorderRightColor,
@@ -545,40 +1045,42 @@ impl From<&PropertyDeclaration> for LonghandId {
             PropertyDeclaration::BorderLeftWidth(_) => LonghandId::BorderLeftWidth,
             PropertyDeclaration::BorderRightWidth(_) => LonghandId::BorderRightWidth,
             PropertyDeclaration::BorderTopWidth(_) => LonghandId::BorderTopWidth,
+            PropertyDeclaration::BorderBottomLeftRadius(_) => LonghandId::BorderBottomLeftRadius,
+            PropertyDeclaration::BorderBottomRightRadius(_) => LonghandId::BorderBottomRightRadius,
+            PropertyDeclaration::BorderEndEndRadius(_) => LonghandId::BorderEndEndRadius,
+            PropertyDeclaration::BorderEndStartRadius(_) => LonghandId::BorderEndStartRadius,
+            PropertyDeclaration::BorderStartEndRadius(_) => LonghandId::BorderStartEndRadius,
+            PropertyDeclaration::BorderStartStartRadius(_) => LonghandId::BorderStartStartRadius,
+            PropertyDeclaration::BorderTopLeftRadius(_) => LonghandId::BorderTopLeftRadius,
+            PropertyDeclaration::BorderTopRightRadius(_) => LonghandId::BorderTopRightRadius,
             PropertyDeclaration::Color(_) => LonghandId::Color,
             PropertyDeclaration::Direction(_) => LonghandId::Direction,
             PropertyDeclaration::Display(_) => LonghandId::Display,
+            PropertyDeclaration::FontFamily(_) => LonghandId::FontFamily,
             PropertyDeclaration::FontSize(_) => LonghandId::FontSize,
+            PropertyDeclaration::FontStretch(_) => LonghandId::FontStretch,
+            PropertyDeclaration::FontStyle(_) => LonghandId::FontStyle,
+            PropertyDeclaration::FontWeight(_) => LonghandId::FontWeight,
             PropertyDeclaration::Height(_) => LonghandId::Height,
+            PropertyDeclaration::LineHeight(_) => LonghandId::LineHeight,
             PropertyDeclaration::MarginBottom(_) => LonghandId::MarginBottom,
             PropertyDeclaration::MarginLeft(_) => LonghandId::MarginLeft,
             PropertyDeclaration::MarginRight(_) => LonghandId::MarginRight,
             PropertyDeclaration::MarginTop(_) => LonghandId::MarginTop,
+            PropertyDeclaration::OverflowX(_) => LonghandId::OverflowX,
+            PropertyDeclaration::OverflowY(_) => LonghandId::OverflowY,
             PropertyDeclaration::PaddingBottom(_) => LonghandId::PaddingBottom,
             PropertyDeclaration::PaddingLeft(_) => LonghandId::PaddingLeft,
             PropertyDeclaration::PaddingRight(_) => LonghandId::PaddingRight,
             PropertyDeclaration::PaddingTop(_) => LonghandId::PaddingTop,
+            PropertyDeclaration::TextOrientation(_) => LonghandId::TextOrientation,
+            PropertyDeclaration::UnicodeBidi(_) => LonghandId::UnicodeBidi,
+            PropertyDeclaration::VerticalAlign(_) => LonghandId::VerticalAlign,
+            PropertyDeclaration::WhiteSpace(_) => LonghandId::WhiteSpace,
             PropertyDeclaration::Width(_) => LonghandId::Width,
             PropertyDeclaration::WritingMode(_) => LonghandId::WritingMode,
-        }
-    }
-}
-
-impl From<PropertyDeclaration> for LonghandId {
-    fn from(prop_decl: PropertyDeclaration) -> Self {
-        LonghandId::from(&prop_decl)
-    }
-}
-
-impl From<&ContextualPropertyDeclaration> for LonghandId {
-    fn from(contextual_decl: &ContextualPropertyDeclaration) -> Self {
-        LonghandId::from(&contextual_decl.inner_decl)
-    }
-}
-
-impl From<ContextualPropertyDeclaration> for LonghandId {
-    fn from(contextual_decl: ContextualPropertyDeclaration) -> Self {
-        LonghandId::from(&contextual_decl)
+        };
+        PropertyDeclarationId::Longhand(longhand)
     }
 }
 
@@ -588,8 +1090,8 @@ impl From<ContextualPropertyDeclaration> for LonghandId {
 pub enum ShorthandId {
     /// background
     Background = 0,
-    //    /// background-position
-    //    BackgroundPosition = 1,
+    /// background-position
+    BackgroundPosition = 1,
     /// border-color
     BorderColor = 2,
     /// border-style
@@ -614,8 +1116,8 @@ pub enum ShorthandId {
     //    BorderInlineEnd = 12,
     /// border
     Border = 13,
-    //    /// border-radius
-    //    BorderRadius = 14,
+    /// border-radius
+    BorderRadius = 14,
     //    /// border-image
     //    BorderImage = 15,
     //    /// border-block-width
@@ -634,16 +1136,16 @@ pub enum ShorthandId {
     //    BorderBlock = 22,
     //    /// border-inline
     //    BorderInline = 23,
-    //    /// overflow
-    //    Overflow = 24,
+    /// overflow
+    Overflow = 24,
     //    /// transition
     //    Transition = 25,
     //    /// animation
     //    Animation = 26,
     //    /// columns
     //    Columns = 27,
-    //    /// font
-    //    Font = 28,
+    /// font
+    Font = 28,
     //    /// font-variant
     //    FontVariant = 29,
     //    /// list-style
@@ -677,3 +1179,105 @@ pub enum ShorthandId {
     //    /// all
     //    All = 44,
 }
+
+impl ShorthandId {
+    /// The longhands the shorthand sets.
+    pub fn longhands(self) -> &'static [LonghandId] {
+        match self {
+            ShorthandId::Background => &[
+                LonghandId::BackgroundColor,
+                LonghandId::BackgroundImage,
+                LonghandId::BackgroundPositionX,
+                LonghandId::BackgroundPositionY,
+                LonghandId::BackgroundSize,
+                LonghandId::BackgroundRepeat,
+                LonghandId::BackgroundAttachment,
+                LonghandId::BackgroundOrigin,
+                LonghandId::BackgroundClip,
+            ],
+            ShorthandId::BackgroundPosition => &[
+                LonghandId::BackgroundPositionX,
+                LonghandId::BackgroundPositionY,
+            ],
+            ShorthandId::BorderColor => &[
+                LonghandId::BorderTopColor,
+                LonghandId::BorderRightColor,
+                LonghandId::BorderBottomColor,
+                LonghandId::BorderLeftColor,
+            ],
+            ShorthandId::BorderStyle => &[
+                LonghandId::BorderTopStyle,
+                LonghandId::BorderRightStyle,
+                LonghandId::BorderBottomStyle,
+                LonghandId::BorderLeftStyle,
+            ],
+            ShorthandId::BorderWidth => &[
+                LonghandId::BorderTopWidth,
+                LonghandId::BorderRightWidth,
+                LonghandId::BorderBottomWidth,
+                LonghandId::BorderLeftWidth,
+            ],
+            ShorthandId::BorderTop => &[
+                LonghandId::BorderTopWidth,
+                LonghandId::BorderTopStyle,
+                LonghandId::BorderTopColor,
+            ],
+            ShorthandId::BorderRight => &[
+                LonghandId::BorderRightWidth,
+                LonghandId::BorderRightStyle,
+                LonghandId::BorderRightColor,
+            ],
+            ShorthandId::BorderBottom => &[
+                LonghandId::BorderBottomWidth,
+                LonghandId::BorderBottomStyle,
+                LonghandId::BorderBottomColor,
+            ],
+            ShorthandId::BorderLeft => &[
+                LonghandId::BorderLeftWidth,
+                LonghandId::BorderLeftStyle,
+                LonghandId::BorderLeftColor,
+            ],
+            ShorthandId::Border => &[
+                LonghandId::BorderTopWidth,
+                LonghandId::BorderRightWidth,
+                LonghandId::BorderBottomWidth,
+                LonghandId::BorderLeftWidth,
+                LonghandId::BorderTopStyle,
+                LonghandId::BorderRightStyle,
+                LonghandId::BorderBottomStyle,
+                LonghandId::BorderLeftStyle,
+                LonghandId::BorderTopColor,
+                LonghandId::BorderRightColor,
+                LonghandId::BorderBottomColor,
+                LonghandId::BorderLeftColor,
+            ],
+            ShorthandId::BorderRadius => &[
+                LonghandId::BorderTopLeftRadius,
+                LonghandId::BorderTopRightRadius,
+                LonghandId::BorderBottomRightRadius,
+                LonghandId::BorderBottomLeftRadius,
+            ],
+            ShorthandId::Font => &[
+                LonghandId::FontStyle,
+                LonghandId::FontWeight,
+                LonghandId::FontStretch,
+                LonghandId::FontSize,
+                LonghandId::LineHeight,
+                LonghandId::FontFamily,
+            ],
+            ShorthandId::Margin => &[
+                LonghandId::MarginTop,
+                LonghandId::MarginRight,
+                LonghandId::MarginBottom,
+                LonghandId::MarginLeft,
+            ],
+            ShorthandId::Overflow => &[LonghandId::OverflowX, LonghandId::OverflowY],
+            ShorthandId::Padding => &[
+                LonghandId::PaddingTop,
+                LonghandId::PaddingRight,
+                LonghandId::PaddingBottom,
+                LonghandId::PaddingLeft,
+            ],
+        }
+    }
+}
@@ -1,31 +1,43 @@
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::mem;
+use std::sync::Arc;
 
 use cssparser::{
     parse_important, AtRuleParser, CowRcStr, DeclarationListParser, DeclarationParser, Delimiter,
-    ParseError, Parser, SourceLocation,
+    ParseError, Parser, ParserInput, SourceLocation,
 };
 use smallbitvec::SmallBitVec;
 
-use crate::style::properties::id::{LonghandId, PropertyId, ShorthandId};
+use crate::style::custom_properties::{self, CustomPropertiesMap};
+use crate::style::properties::id::{LonghandId, PropertyDeclarationId, PropertyId, ShorthandId};
 use crate::style::select::Specificity;
 use crate::style::values::computed::direction::WritingMode;
-use crate::style::values::computed::{Direction, Display, LineStyle};
+use crate::style::values::computed::{
+    BackgroundAttachment, BackgroundClip, BackgroundOrigin, BackgroundRepeat, Direction, Display,
+    FontFamily, FontStretch, FontStyle, LineStyle, Overflow, TextOrientation, UnicodeBidi,
+    WhiteSpace,
+};
+use crate::style::values::specified::background::{
+    parse_background_position_shorthand_into, parse_background_shorthand_into,
+};
 use crate::style::values::specified::border::{
-    parse_border_color_shorthand_into, parse_border_shorthand_into,
-    parse_border_side_shorthand_into, parse_border_style_shorthand_into,
-    parse_border_width_shorthand_into, BorderBottomWidth, BorderColor, BorderLeftWidth,
-    BorderRightWidth, BorderTopWidth,
+    parse_border_color_shorthand_into, parse_border_radius_shorthand_into,
+    parse_border_shorthand_into, parse_border_side_shorthand_into,
+    parse_border_style_shorthand_into, parse_border_width_shorthand_into, BorderBottomWidth,
+    BorderColor, BorderCornerRadius, BorderLeftWidth, BorderRightWidth, BorderTopWidth,
 };
+use crate::style::values::specified::font::parse_font_shorthand_into;
 use crate::style::values::specified::margin::parse_margin_shorthand_into;
+use crate::style::values::specified::overflow::parse_overflow_shorthand_into;
 use crate::style::values::specified::padding::parse_padding_shorthand_into;
 use crate::style::values::specified::{
-    BackgroundColor, Color, FontSize, Height, Margin, Padding, Width,
+    BackgroundColor, BackgroundImage, BackgroundPositionX, BackgroundPositionY, BackgroundSize,
+    Color, FontSize, FontWeight, Height, LineHeight, Margin, Padding, VerticalAlign, Width,
 };
 use crate::style::values::CssValueParse;
 use crate::style::CascadeOrigin;
-use crate::style::{CssOrigin, StyleParseErrorKind};
+use crate::style::{CssOrigin, CssWideKeywords, StyleParseErrorKind};
 use crate::Side;
 
 pub mod id;
@@ -120,8 +132,8 @@ impl PropertyDeclarationBlock {
     ) {
         let mut swap_index = None;
         for (i, existing_decl) in self.declarations.iter().enumerate() {
-            if mem::discriminant(existing_decl) == mem::discriminant(&new_decl) {
-                // the props are the same "type", e.g. both `font-size, both `display`, etc
+            if existing_decl.id() == new_decl.id() {
+                // the props are the same property, e.g. both `font-size, both `display`, etc
                 // take the `new_decl`, since the latest/newest prop should always be taken
                 swap_index = Some(i);
             }
@@ -148,6 +160,10 @@ impl PropertyDeclarationBlock {
         &self.declarations
     }
 
+    pub fn declarations_mut(&mut self) -> &mut [PropertyDeclaration] {
+        &mut self.declarations
+    }
+
     pub fn remove_decl(&mut self, index: usize) {
         self.declarations.remove(index);
     }
@@ -163,15 +179,106 @@ impl PropertyDeclaration {
         id: PropertyId,
         input: &mut Parser<'i, 't>,
     ) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let PropertyId::Custom(name) = id {
+            let value = match input.try_parse(parse_css_wide_keyword) {
+                Ok(keyword) => CustomDeclarationValue::CssWideKeyword(keyword),
+                Err(_) => CustomDeclarationValue::Value(Arc::new(
+                    custom_properties::SpecifiedValue::parse(input)?,
+                )),
+            };
+            declarations.push(PropertyDeclaration::Custom(CustomDeclaration {
+                name,
+                value,
+            }));
+            return Ok(());
+        }
+
+        // Values with `var()` references can only be parsed once the references have been
+        // substituted, at computed-value time.
+        let start = input.state();
+        if let Ok(value) = custom_properties::SpecifiedValue::parse(input) {
+            if !value.references.is_empty() {
+                match id {
+                    PropertyId::Longhand(longhand) => {
+                        declarations.push(PropertyDeclaration::WithVariables(
+                            VariableDeclaration {
+                                id: longhand,
+                                value: Arc::new(UnparsedValue {
+                                    css: value.css,
+                                    from_shorthand: None,
+                                }),
+                            },
+                        ));
+                    }
+                    PropertyId::Shorthand(shorthand) => {
+                        // Each longhand shares the shorthand's value, and picks its own
+                        // declaration out of the shorthand once substituted.
+                        let unparsed = Arc::new(UnparsedValue {
+                            css: value.css,
+                            from_shorthand: Some(shorthand),
+                        });
+                        for &longhand in shorthand.longhands() {
+                            declarations.push(PropertyDeclaration::WithVariables(
+                                VariableDeclaration {
+                                    id: longhand,
+                                    value: unparsed.clone(),
+                                },
+                            ));
+                        }
+                    }
+                    PropertyId::Custom(_) => unreachable!(),
+                }
+                return Ok(());
+            }
+        }
+        input.reset(&start);
+
         match id {
             PropertyId::Longhand(longhand) => {
-                PropertyDeclaration::parse_into_longhand(declarations, longhand, input)?
+                PropertyDeclaration::parse_value_into_longhand(declarations, longhand, input)
             }
             PropertyId::Shorthand(shorthand) => {
-                PropertyDeclaration::parse_into_shorthand(declarations, shorthand, input)?
+                PropertyDeclaration::parse_value_into_shorthand(declarations, shorthand, input)
             }
+            PropertyId::Custom(_) => unreachable!(),
         }
-        Ok(())
+    }
+
+    /// Parses the value of `id`, which is either one of the CSS-wide keywords or a value of the
+    /// property itself.
+    fn parse_value_into_longhand<'i, 't>(
+        declarations: &mut Vec<PropertyDeclaration>,
+        id: LonghandId,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(keyword) = input.try_parse(parse_css_wide_keyword) {
+            declarations.push(PropertyDeclaration::CssWideKeyword(
+                WideKeywordDeclaration { id, keyword },
+            ));
+            return Ok(());
+        }
+        PropertyDeclaration::parse_into_longhand(declarations, id, input)
+    }
+
+    /// Parses the value of `id`, which is either one of the CSS-wide keywords, which then applies
+    /// to each of its longhands, or a value of the shorthand itself.
+    fn parse_value_into_shorthand<'i, 't>(
+        declarations: &mut Vec<PropertyDeclaration>,
+        id: ShorthandId,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(keyword) = input.try_parse(parse_css_wide_keyword) {
+            for &longhand in id.longhands() {
+                declarations.push(PropertyDeclaration::CssWideKeyword(
+                    WideKeywordDeclaration {
+                        id: longhand,
+                        keyword,
+                    },
+                ));
+            }
+            return Ok(());
+        }
+        PropertyDeclaration::parse_into_shorthand(declarations, id, input)
     }
 
     #[allow(unreachable_patterns)]
@@ -181,9 +288,33 @@ impl PropertyDeclaration {
         input: &mut Parser<'i, 't>,
     ) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
         match id {
+            LonghandId::BackgroundAttachment => declarations.push(
+                PropertyDeclaration::BackgroundAttachment(BackgroundAttachment::parse(input)?),
+            ),
+            LonghandId::BackgroundClip => declarations.push(PropertyDeclaration::BackgroundClip(
+                BackgroundClip::parse(input)?,
+            )),
             LonghandId::BackgroundColor => declarations.push(PropertyDeclaration::BackgroundColor(
                 BackgroundColor::parse(input)?,
             )),
+            LonghandId::BackgroundImage => declarations.push(PropertyDeclaration::BackgroundImage(
+                BackgroundImage::parse(input)?,
+            )),
+            LonghandId::BackgroundOrigin => declarations.push(
+                PropertyDeclaration::BackgroundOrigin(BackgroundOrigin::parse(input)?),
+            ),
+            LonghandId::BackgroundPositionX => declarations.push(
+                PropertyDeclaration::BackgroundPositionX(BackgroundPositionX::parse(input)?),
+            ),
+            LonghandId::BackgroundPositionY => declarations.push(
+                PropertyDeclaration::BackgroundPositionY(BackgroundPositionY::parse(input)?),
+            ),
+            LonghandId::BackgroundRepeat => declarations.push(
+                PropertyDeclaration::BackgroundRepeat(BackgroundRepeat::parse(input)?),
+            ),
+            LonghandId::BackgroundSize => declarations.push(PropertyDeclaration::BackgroundSize(
+                BackgroundSize::parse(input)?,
+            )),
             LonghandId::BorderBottomColor => declarations.push(
                 PropertyDeclaration::BorderBottomColor(BorderColor::parse(input)?),
             ),
@@ -220,6 +351,30 @@ impl PropertyDeclaration {
             LonghandId::BorderTopWidth => declarations.push(PropertyDeclaration::BorderTopWidth(
                 BorderTopWidth::parse(input)?,
             )),
+            LonghandId::BorderBottomLeftRadius => declarations.push(
+                PropertyDeclaration::BorderBottomLeftRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderBottomRightRadius => declarations.push(
+                PropertyDeclaration::BorderBottomRightRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderEndEndRadius => declarations.push(
+                PropertyDeclaration::BorderEndEndRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderEndStartRadius => declarations.push(
+                PropertyDeclaration::BorderEndStartRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderStartEndRadius => declarations.push(
+                PropertyDeclaration::BorderStartEndRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderStartStartRadius => declarations.push(
+                PropertyDeclaration::BorderStartStartRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderTopLeftRadius => declarations.push(
+                PropertyDeclaration::BorderTopLeftRadius(BorderCornerRadius::parse(input)?),
+            ),
+            LonghandId::BorderTopRightRadius => declarations.push(
+                PropertyDeclaration::BorderTopRightRadius(BorderCornerRadius::parse(input)?),
+            ),
             LonghandId::Color => {
                 declarations.push(PropertyDeclaration::Color(Color::parse(input)?))
             }
@@ -229,12 +384,27 @@ impl PropertyDeclaration {
             LonghandId::Display => {
                 declarations.push(PropertyDeclaration::Display(Display::parse(input)?))
             }
+            LonghandId::FontFamily => {
+                declarations.push(PropertyDeclaration::FontFamily(FontFamily::parse(input)?));
+            }
             LonghandId::FontSize => {
                 declarations.push(PropertyDeclaration::FontSize(FontSize::parse(input)?));
             }
+            LonghandId::FontStretch => {
+                declarations.push(PropertyDeclaration::FontStretch(FontStretch::parse(input)?));
+            }
+            LonghandId::FontStyle => {
+                declarations.push(PropertyDeclaration::FontStyle(FontStyle::parse(input)?));
+            }
+            LonghandId::FontWeight => {
+                declarations.push(PropertyDeclaration::FontWeight(FontWeight::parse(input)?));
+            }
             LonghandId::Height => {
                 declarations.push(PropertyDeclaration::Height(Height::parse(input)?));
             }
+            LonghandId::LineHeight => {
+                declarations.push(PropertyDeclaration::LineHeight(LineHeight::parse(input)?));
+            }
             LonghandId::MarginBottom => {
                 declarations.push(PropertyDeclaration::MarginBottom(Margin::parse(input)?));
             }
@@ -247,6 +417,12 @@ impl PropertyDeclaration {
             LonghandId::MarginTop => {
                 declarations.push(PropertyDeclaration::MarginTop(Margin::parse(input)?));
             }
+            LonghandId::OverflowX => {
+                declarations.push(PropertyDeclaration::OverflowX(Overflow::parse(input)?));
+            }
+            LonghandId::OverflowY => {
+                declarations.push(PropertyDeclaration::OverflowY(Overflow::parse(input)?));
+            }
             LonghandId::PaddingBottom => {
                 declarations.push(PropertyDeclaration::PaddingBottom(Padding::parse(input)?));
             }
@@ -259,6 +435,22 @@ impl PropertyDeclaration {
             LonghandId::PaddingTop => {
                 declarations.push(PropertyDeclaration::PaddingTop(Padding::parse(input)?));
             }
+            LonghandId::TextOrientation => {
+                declarations.push(PropertyDeclaration::TextOrientation(
+                    TextOrientation::parse(input)?,
+                ));
+            }
+            LonghandId::UnicodeBidi => {
+                declarations.push(PropertyDeclaration::UnicodeBidi(UnicodeBidi::parse(input)?));
+            }
+            LonghandId::VerticalAlign => {
+                declarations.push(PropertyDeclaration::VerticalAlign(VerticalAlign::parse(
+                    input,
+                )?));
+            }
+            LonghandId::WhiteSpace => {
+                declarations.push(PropertyDeclaration::WhiteSpace(WhiteSpace::parse(input)?));
+            }
             LonghandId::Width => {
                 declarations.push(PropertyDeclaration::Width(Width::parse(input)?));
             }
@@ -277,6 +469,10 @@ impl PropertyDeclaration {
         input: &mut Parser<'i, 't>,
     ) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
         match id {
+            ShorthandId::Background => parse_background_shorthand_into(declarations, input)?,
+            ShorthandId::BackgroundPosition => {
+                parse_background_position_shorthand_into(declarations, input)?
+            }
             ShorthandId::BorderColor => parse_border_color_shorthand_into(declarations, input)?,
             ShorthandId::BorderStyle => parse_border_style_shorthand_into(declarations, input)?,
             ShorthandId::BorderWidth => parse_border_width_shorthand_into(declarations, input)?,
@@ -293,7 +489,10 @@ impl PropertyDeclaration {
                 parse_border_side_shorthand_into(Side::Left, declarations, input)?
             }
             ShorthandId::Border => parse_border_shorthand_into(declarations, input)?,
+            ShorthandId::BorderRadius => parse_border_radius_shorthand_into(declarations, input)?,
+            ShorthandId::Font => parse_font_shorthand_into(declarations, input)?,
             ShorthandId::Margin => parse_margin_shorthand_into(declarations, input)?,
+            ShorthandId::Overflow => parse_overflow_shorthand_into(declarations, input)?,
             ShorthandId::Padding => parse_padding_shorthand_into(declarations, input)?,
             _ => unimplemented!("{}", format!("parse shorthand with id: {:?}", id)),
         };
@@ -304,7 +503,15 @@ impl PropertyDeclaration {
 #[derive(Clone, Debug)]
 #[repr(u16)]
 pub enum PropertyDeclaration {
+    BackgroundAttachment(crate::style::values::computed::BackgroundAttachment),
+    BackgroundClip(crate::style::values::computed::BackgroundClip),
     BackgroundColor(crate::style::values::specified::BackgroundColor),
+    BackgroundImage(crate::style::values::specified::BackgroundImage),
+    BackgroundOrigin(crate::style::values::computed::BackgroundOrigin),
+    BackgroundPositionX(crate::style::values::specified::BackgroundPositionX),
+    BackgroundPositionY(crate::style::values::specified::BackgroundPositionY),
+    BackgroundRepeat(crate::style::values::computed::BackgroundRepeat),
+    BackgroundSize(crate::style::values::specified::BackgroundSize),
     BorderBottomColor(crate::style::values::specified::BorderColor),
     BorderLeftColor(crate::style::values::specified::BorderColor),
     BorderRightColor(crate::style::values::specified::BorderColor),
@@ -317,21 +524,127 @@ pub enum PropertyDeclaration {
     BorderLeftWidth(crate::style::values::specified::BorderLeftWidth),
     BorderRightWidth(crate::style::values::specified::BorderRightWidth),
     BorderTopWidth(crate::style::values::specified::BorderTopWidth),
+    BorderBottomLeftRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderBottomRightRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderEndEndRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderEndStartRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderStartEndRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderStartStartRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderTopLeftRadius(crate::style::values::specified::BorderCornerRadius),
+    BorderTopRightRadius(crate::style::values::specified::BorderCornerRadius),
     Color(crate::style::values::specified::Color),
     Direction(crate::style::values::computed::Direction),
     Display(crate::style::values::computed::Display),
+    FontFamily(crate::style::values::computed::FontFamily),
     FontSize(crate::style::values::specified::FontSize),
+    FontStretch(crate::style::values::computed::FontStretch),
+    FontStyle(crate::style::values::computed::FontStyle),
+    FontWeight(crate::style::values::specified::FontWeight),
     Height(crate::style::values::specified::Height),
+    LineHeight(crate::style::values::specified::LineHeight),
     MarginBottom(crate::style::values::specified::Margin),
     MarginLeft(crate::style::values::specified::Margin),
     MarginRight(crate::style::values::specified::Margin),
     MarginTop(crate::style::values::specified::Margin),
+    OverflowX(crate::style::values::computed::Overflow),
+    OverflowY(crate::style::values::computed::Overflow),
     PaddingBottom(crate::style::values::specified::Padding),
     PaddingLeft(crate::style::values::specified::Padding),
     PaddingRight(crate::style::values::specified::Padding),
     PaddingTop(crate::style::values::specified::Padding),
+    TextOrientation(crate::style::values::computed::TextOrientation),
+    UnicodeBidi(crate::style::values::computed::UnicodeBidi),
+    VerticalAlign(crate::style::values::specified::VerticalAlign),
+    WhiteSpace(crate::style::values::computed::WhiteSpace),
     Width(crate::style::values::specified::Width),
     WritingMode(crate::style::values::computed::WritingMode),
+    /// A longhand whose value is one of the CSS-wide keywords.
+    CssWideKeyword(WideKeywordDeclaration),
+    /// A longhand whose value contains `var()` references.
+    WithVariables(VariableDeclaration),
+    /// A custom property.
+    Custom(CustomDeclaration),
+}
+
+/// A declaration of a longhand whose value is one of the CSS-wide keywords, which are resolved
+/// when the value is computed.
+#[derive(Clone, Copy, Debug)]
+pub struct WideKeywordDeclaration {
+    pub id: LonghandId,
+    pub keyword: CssWideKeywords,
+}
+
+/// Parses one of the CSS-wide keywords, which must be the entire value of the declaration.
+fn parse_css_wide_keyword<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<CssWideKeywords, ParseError<'i, StyleParseErrorKind<'i>>> {
+    let keyword = CssWideKeywords::parse(input)?;
+    input.expect_exhausted()?;
+    Ok(keyword)
+}
+
+/// A declaration of a custom property.
+#[derive(Clone, Debug)]
+pub struct CustomDeclaration {
+    pub name: custom_properties::Name,
+    pub value: CustomDeclarationValue,
+}
+
+/// The value of a custom property declaration.
+#[derive(Clone, Debug)]
+pub enum CustomDeclarationValue {
+    Value(Arc<custom_properties::SpecifiedValue>),
+    CssWideKeyword(CssWideKeywords),
+}
+
+/// A declaration of a longhand whose value contains `var()` references, and so can't be parsed
+/// until the custom properties it references are known.
+#[derive(Clone, Debug)]
+pub struct VariableDeclaration {
+    pub id: LonghandId,
+    pub value: Arc<UnparsedValue>,
+}
+
+/// The value of a declaration with `var()` references, as written.
+#[derive(Clone, Debug)]
+pub struct UnparsedValue {
+    pub css: String,
+    /// The shorthand the value was written for, if the declaration was expanded from one.  The
+    /// whole shorthand is re-parsed after substitution and the longhand picked out of its result.
+    pub from_shorthand: Option<ShorthandId>,
+}
+
+impl VariableDeclaration {
+    /// Substitutes the `var()` references in the value and parses the result.  Returns `None` if
+    /// the declaration is invalid at computed-value time, in which case the property is `unset`.
+    ///
+    /// https://drafts.csswg.org/css-variables/#invalid-at-computed-value-time
+    pub fn substitute_variables(
+        &self,
+        custom_properties: &CustomPropertiesMap,
+    ) -> Option<PropertyDeclaration> {
+        let css = custom_properties::substitute(&self.value.css, custom_properties).ok()?;
+        let mut parser_input = ParserInput::new(&css);
+        let mut input = Parser::new(&mut parser_input);
+        let mut declarations = Vec::new();
+        input
+            .parse_entirely(|input| match self.value.from_shorthand {
+                Some(shorthand) => PropertyDeclaration::parse_value_into_shorthand(
+                    &mut declarations,
+                    shorthand,
+                    input,
+                ),
+                None => PropertyDeclaration::parse_value_into_longhand(
+                    &mut declarations,
+                    self.id,
+                    input,
+                ),
+            })
+            .ok()?;
+        declarations
+            .into_iter()
+            .find(|decl| decl.id() == PropertyDeclarationId::Longhand(self.id))
+    }
 }
 
 pub struct ComputedPropertyDeclarations {}
@@ -360,6 +673,10 @@ pub struct ContextualPropertyDeclarations {
     /// will be found later in the `Vec`.
     /// https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#cascading
     is_sorted: bool,
+    /// The rules the declarations came from, as indices into the `Stylist` that matched them.
+    /// Nodes that matched the same rules and have the same parent style can share their computed
+    /// values.  `None` if any declarations didn't come from a matched rule.
+    matched_rules: Option<Vec<usize>>,
 }
 
 impl ContextualPropertyDeclarations {
@@ -388,6 +705,12 @@ impl ContextualPropertyDeclarations {
         self.longhands.contains(&longhand)
     }
 
+    /// The declarations, in the order they were added or, once sorted, in cascade order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &ContextualPropertyDeclaration> {
+        self.decls.iter()
+    }
+
     /// Finds the first matching `ContextualPropertyDeclaration` by `LonghandId`.
     #[inline]
     pub fn get_by_longhand(&self, longhand: LonghandId) -> Option<&ContextualPropertyDeclaration> {
@@ -397,14 +720,114 @@ impl ContextualPropertyDeclarations {
             self.decls
                 .iter()
                 .rev()
-                .find(|decl| LonghandId::from(*decl).eq(&longhand))
+                .find(|decl| decl.inner_decl.id() == PropertyDeclarationId::Longhand(longhand))
         }
     }
 
+    /// The declarations of `longhand`, from the winner of the cascade to the loser.
+    pub fn cascade_order_by_longhand(
+        &self,
+        longhand: LonghandId,
+    ) -> impl Iterator<Item = &ContextualPropertyDeclaration> {
+        let id = PropertyDeclarationId::Longhand(longhand);
+        self.decls
+            .iter()
+            .rev()
+            .filter(move |decl| decl.inner_decl.id() == id)
+    }
+
+    /// The declarations of any of `longhands`, from the winner of the cascade to the loser.  This
+    /// is for longhands that share a value, like a logical longhand and the physical longhand it
+    /// maps to, whose declarations aren't ordered relative to each other by `cascade_sort`.
+    pub fn cascade_order_by_longhands(
+        &self,
+        longhands: &[LonghandId],
+    ) -> Vec<&ContextualPropertyDeclaration> {
+        let mut decls = self
+            .decls
+            .iter()
+            .enumerate()
+            .filter(|(_, decl)| match decl.inner_decl.id() {
+                PropertyDeclarationId::Longhand(longhand) => longhands.contains(&longhand),
+                PropertyDeclarationId::Custom(_) => false,
+            })
+            .collect::<Vec<_>>();
+        // Ties are broken by order of appearance, which is the order the declarations are in.
+        decls.sort_by(|(a_index, a), (b_index, b)| {
+            a.cmp_cascade_precedence(b).then(a_index.cmp(b_index))
+        });
+        decls.into_iter().rev().map(|(_, decl)| decl).collect()
+    }
+
+    /// The cascaded values of the custom properties declared, by name.  A value of `None` means
+    /// the custom property was declared `initial`, giving it the guaranteed-invalid value.
+    /// Custom properties declared `inherit` or `unset`, or reverted to no declaration at all, are
+    /// left out, since custom properties are inherited.
+    pub fn custom_declarations(
+        &self,
+    ) -> HashMap<&custom_properties::Name, Option<&custom_properties::SpecifiedValue>> {
+        let mut cascaded = HashMap::new();
+        let mut reverted_origins: HashMap<&custom_properties::Name, Vec<CascadeOrigin>> =
+            HashMap::new();
+        for decl in self.decls.iter().rev() {
+            let custom = match &decl.inner_decl {
+                PropertyDeclaration::Custom(custom) => custom,
+                _ => continue,
+            };
+            if cascaded.contains_key(&custom.name) {
+                continue;
+            }
+            let origin = decl.origin.cascade_origin();
+            let reverted = reverted_origins.entry(&custom.name).or_default();
+            if reverted.contains(&origin) {
+                continue;
+            }
+            let value = match &custom.value {
+                CustomDeclarationValue::Value(value) => Some(Some(&**value)),
+                CustomDeclarationValue::CssWideKeyword(CssWideKeywords::Initial) => Some(None),
+                CustomDeclarationValue::CssWideKeyword(CssWideKeywords::Revert) => {
+                    reverted.push(origin);
+                    continue;
+                }
+                CustomDeclarationValue::CssWideKeyword(CssWideKeywords::Inherit)
+                | CustomDeclarationValue::CssWideKeyword(CssWideKeywords::Unset) => None,
+            };
+            cascaded.insert(&custom.name, value);
+        }
+        cascaded
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect()
+    }
+
     #[inline]
     pub fn add(&mut self, new_decl: ContextualPropertyDeclaration) {
-        self.longhands
-            .insert(LonghandId::from(&new_decl.inner_decl));
+        self.push(new_decl);
+        self.matched_rules = None;
+    }
+
+    /// Adds the declarations of the rule at `rule_index` in the `Stylist` that matched it.
+    pub fn add_matched_rule<I: IntoIterator<Item = ContextualPropertyDeclaration>>(
+        &mut self,
+        rule_index: usize,
+        decls: I,
+    ) {
+        decls.into_iter().for_each(|decl| self.push(decl));
+        if let Some(matched_rules) = &mut self.matched_rules {
+            matched_rules.push(rule_index);
+        }
+    }
+
+    /// The indices of the rules the declarations came from, or `None` if some didn't come from a
+    /// rule.
+    pub fn matched_rules(&self) -> Option<&[usize]> {
+        self.matched_rules.as_deref()
+    }
+
+    fn push(&mut self, new_decl: ContextualPropertyDeclaration) {
+        if let PropertyDeclarationId::Longhand(longhand) = new_decl.inner_decl.id() {
+            self.longhands.insert(longhand);
+        }
         self.decls.push(new_decl);
         self.is_sorted = false;
     }
@@ -416,6 +839,7 @@ impl Default for ContextualPropertyDeclarations {
             decls: Vec::default(),
             longhands: HashSet::default(),
             is_sorted: true,
+            matched_rules: Some(Vec::new()),
         }
     }
 }
@@ -447,28 +871,35 @@ impl Default for ContextualPropertyDeclarations {
 ///         * Declarations from style attributes are ordered according to the document order of the element the style attribute appears on, and are all placed after any style sheets.
 impl Ord for ContextualPropertyDeclaration {
     fn cmp(&self, other: &Self) -> Ordering {
-        if mem::discriminant(&self.inner_decl) == mem::discriminant(&other.inner_decl) {
-            if self.important && !other.important {
-                return Ordering::Greater;
-            } else if !self.important && other.important {
-                return Ordering::Less;
-            } else if self.important && other.important {
-                match cmp_important_origins(&self.origin, &other.origin) {
-                    Ordering::Greater => return Ordering::Greater,
-                    Ordering::Less => return Ordering::Less,
-                    Ordering::Equal => return self.specificity.cmp(&other.specificity),
-                }
-            } else if !self.important && !other.important {
-                // When both declarations are not important, run the same routine as the one to
-                // compare important declarations but flip the result.
-                return match cmp_important_origins(&self.origin, &other.origin) {
-                    Ordering::Less => Ordering::Greater,
-                    Ordering::Greater => Ordering::Less,
-                    Ordering::Equal => return self.specificity.cmp(&other.specificity),
-                };
+        if self.inner_decl.id() == other.inner_decl.id() {
+            return self.cmp_cascade_precedence(other);
+        }
+        Ordering::Equal
+    }
+}
+
+impl ContextualPropertyDeclaration {
+    /// Compares the declarations by origin, importance and specificity, regardless of the
+    /// properties they're for.
+    fn cmp_cascade_precedence(&self, other: &Self) -> Ordering {
+        if self.important && !other.important {
+            return Ordering::Greater;
+        } else if !self.important && other.important {
+            return Ordering::Less;
+        } else if self.important && other.important {
+            match cmp_important_origins(&self.origin, &other.origin) {
+                Ordering::Greater => return Ordering::Greater,
+                Ordering::Less => return Ordering::Less,
+                Ordering::Equal => return self.specificity.cmp(&other.specificity),
             }
         }
-        return Ordering::Equal;
+        // When both declarations are not important, run the same routine as the one to compare
+        // important declarations but flip the result.
+        return match cmp_important_origins(&self.origin, &other.origin) {
+            Ordering::Less => Ordering::Greater,
+            Ordering::Greater => Ordering::Less,
+            Ordering::Equal => self.specificity.cmp(&other.specificity),
+        };
 
         fn cmp_important_origins(a: &CssOrigin, b: &CssOrigin) -> Ordering {
             match (a, b) {
@@ -521,8 +952,7 @@ impl Eq for ContextualPropertyDeclaration {}
 
 impl PartialEq for ContextualPropertyDeclaration {
     fn eq(&self, other: &Self) -> bool {
-        mem::discriminant(&self.inner_decl) == mem::discriminant(&other.inner_decl)
-            && self.origin == other.origin
+        self.inner_decl.id() == other.inner_decl.id() && self.origin == other.origin
     }
 }
 
@@ -744,6 +1174,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn logical_and_physical_declarations_cascade_together() {
+        let corner_radius = |px: f32, specificity: u32| {
+            let length = LengthPercentage::Length(NoCalcLength::Absolute(AbsoluteLength::Px(px)));
+            let radius = BorderCornerRadius {
+                horizontal: length,
+                vertical: length,
+            };
+            let inner_decl = if specificity == 0 {
+                PropertyDeclaration::BorderTopLeftRadius(radius)
+            } else {
+                PropertyDeclaration::BorderStartStartRadius(radius)
+            };
+            ContextualPropertyDeclaration {
+                inner_decl,
+                important: false,
+                origin: CssOrigin::Inline,
+                source_location: None,
+                specificity: Specificity::new(specificity),
+            }
+        };
+        let mut decls = ContextualPropertyDeclarations::new();
+        decls.add(corner_radius(1.0, 10));
+        decls.add(corner_radius(2.0, 0));
+        decls.add(corner_radius(3.0, 10));
+        decls.cascade_sort();
+        let cascade_order = decls.cascade_order_by_longhands(&[
+            LonghandId::BorderTopLeftRadius,
+            LonghandId::BorderStartStartRadius,
+        ]);
+        let horizontal_radii = cascade_order
+            .iter()
+            .map(|decl| match &decl.inner_decl {
+                PropertyDeclaration::BorderTopLeftRadius(radius)
+                | PropertyDeclaration::BorderStartStartRadius(radius) => radius.horizontal,
+                decl => panic!("expected a corner radius declaration, got {:?}", decl),
+            })
+            .collect::<Vec<_>>();
+        // The more specific logical declarations win over the physical one, regardless of the order
+        // of appearance, and the last of them wins over the first.
+        assert_eq!(
+            horizontal_radii,
+            [3.0, 1.0, 2.0]
+                .iter()
+                .map(|&px| LengthPercentage::Length(NoCalcLength::Absolute(AbsoluteLength::Px(px))))
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn decl_cmp_diff_prop_types_are_equal() {
         let font_size = ContextualPropertyDeclaration {
@@ -789,4 +1268,90 @@ mod tests {
         assert_eq!(decl_block.declarations.len(), 1);
         assert_eq!(&24.0, font_size_px_or_panic(&decl_block.declarations[0]));
     }
+
+    fn parse_declarations(css: &str) -> Vec<PropertyDeclaration> {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        parse_property_declaration_list(&mut parser)
+            .declarations()
+            .to_vec()
+    }
+
+    #[test]
+    fn substitutes_variables_into_longhands() {
+        let decls = parse_declarations("--size: 16px; font-size: var(--size)");
+        let font_size = match &decls[1] {
+            PropertyDeclaration::WithVariables(declaration) => declaration,
+            decl => panic!("expected a declaration with variables, got {:?}", decl),
+        };
+        let mut custom_properties = CustomPropertiesMap::new();
+        custom_properties.insert("size".to_owned(), "16px".to_owned());
+        let substituted = font_size
+            .substitute_variables(&custom_properties)
+            .expect("font-size should be valid after substitution");
+        assert_eq!(font_size_px_or_panic(&substituted), &16.0);
+        // A reference to a missing custom property makes the declaration invalid.
+        assert!(font_size
+            .substitute_variables(&CustomPropertiesMap::new())
+            .is_none());
+    }
+
+    #[test]
+    fn shorthands_with_variables_set_every_longhand() {
+        let decls = parse_declarations("margin: var(--vertical) 4px");
+        let ids: Vec<_> = decls.iter().map(|decl| decl.id()).collect();
+        assert_eq!(
+            ids,
+            ShorthandId::Margin
+                .longhands()
+                .iter()
+                .map(|&longhand| PropertyDeclarationId::Longhand(longhand))
+                .collect::<Vec<_>>()
+        );
+        let mut custom_properties = CustomPropertiesMap::new();
+        custom_properties.insert("vertical".to_owned(), "2px".to_owned());
+        for decl in decls {
+            match decl {
+                PropertyDeclaration::WithVariables(declaration) => {
+                    let substituted = declaration
+                        .substitute_variables(&custom_properties)
+                        .expect("margin should be valid after substitution");
+                    assert_eq!(
+                        substituted.id(),
+                        PropertyDeclarationId::Longhand(declaration.id)
+                    );
+                }
+                decl => panic!("expected a declaration with variables, got {:?}", decl),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_css_wide_keywords_for_every_longhand_of_a_shorthand() {
+        let decls = parse_declarations("padding: unset; color: INHERIT; --custom: revert");
+        assert_eq!(decls.len(), 6);
+        for (decl, &longhand) in decls.iter().zip(ShorthandId::Padding.longhands()) {
+            match decl {
+                PropertyDeclaration::CssWideKeyword(declaration) => {
+                    assert_eq!(declaration.id, longhand);
+                    assert_eq!(declaration.keyword, CssWideKeywords::Unset);
+                }
+                decl => panic!("expected a CSS-wide keyword, got {:?}", decl),
+            }
+        }
+        match &decls[4] {
+            PropertyDeclaration::CssWideKeyword(declaration) => {
+                assert_eq!(declaration.id, LonghandId::Color);
+                assert_eq!(declaration.keyword, CssWideKeywords::Inherit);
+            }
+            decl => panic!("expected a CSS-wide keyword, got {:?}", decl),
+        }
+        match &decls[5] {
+            PropertyDeclaration::Custom(CustomDeclaration {
+                value: CustomDeclarationValue::CssWideKeyword(CssWideKeywords::Revert),
+                ..
+            }) => {}
+            decl => panic!("expected a custom property set to revert, got {:?}", decl),
+        }
+    }
 }
@@ -3,16 +3,17 @@
 use crate::dom::attributes::ExpandedName;
 use crate::dom::iter::{NodeIterator, Select};
 use crate::dom::node_data_ref::NodeDataRef;
-use crate::dom::tree::{ElementData, Node, NodeData, NodeRef};
+use crate::dom::tree::{ElementData, ElementState, Node, NodeData, NodeRef};
 use crate::style::StyleParseErrorKind;
 
 use cssparser::{self, CowRcStr, ParseError, SourceLocation, ToCss};
 use html5ever::{LocalName, Namespace};
 use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
+use selectors::bloom::{BloomFilter, BLOOM_HASH_MASK};
 use selectors::context::QuirksMode;
 use selectors::parser::{
-    NonTSPseudoClass, Parser, Selector as GenericSelector, SelectorImpl, SelectorList,
-    SelectorParseErrorKind,
+    AncestorHashes, NonTSPseudoClass, Parser, Selector as GenericSelector, SelectorImpl,
+    SelectorIter, SelectorList, SelectorParseErrorKind,
 };
 use selectors::{self, matching, OpaqueElement};
 use std::fmt;
@@ -236,9 +237,13 @@ impl selectors::Element for NodeDataRef<ElementData> {
     {
         use self::PseudoClass::*;
         match *pseudo {
-            Active | Focus | Hover | Enabled | Disabled | Checked | Indeterminate | Visited => {
-                false
-            }
+            Active => self
+                .as_node()
+                .element_state()
+                .contains(ElementState::ACTIVE),
+            Focus => self.as_node().element_state().contains(ElementState::FOCUS),
+            Hover => self.as_node().element_state().contains(ElementState::HOVER),
+            Enabled | Disabled | Checked | Indeterminate | Visited => false,
             AnyLink | Link => {
                 self.name.ns == ns!(html)
                     && matches!(
@@ -431,12 +436,83 @@ impl Selector {
         matching::matches_selector(&self.0, 0, None, element, &mut context, &mut |_, _| {})
     }
 
+    /// Returns whether the given element matches this selector.  `bloom_filter` must contain the
+    /// hashes of the element's ancestors (see `each_bloom_hash`), and is used with `hashes` (this
+    /// selector's `ancestor_hashes`) to reject the selector without matching it where possible.
+    #[inline]
+    pub fn matches_with_bloom_filter(
+        &self,
+        element: &NodeDataRef<ElementData>,
+        hashes: &AncestorHashes,
+        bloom_filter: &BloomFilter,
+    ) -> bool {
+        let mut context = matching::MatchingContext::new(
+            matching::MatchingMode::Normal,
+            Some(bloom_filter),
+            None,
+            QuirksMode::NoQuirks,
+        );
+        matching::matches_selector(
+            &self.0,
+            0,
+            Some(hashes),
+            element,
+            &mut context,
+            &mut |_, _| {},
+        )
+    }
+
+    /// The hashes of the simple selectors an element's ancestors must match for this selector to
+    /// match the element.
+    pub fn ancestor_hashes(&self) -> AncestorHashes {
+        AncestorHashes::new(&self.0, QuirksMode::NoQuirks)
+    }
+
+    /// The simple selectors of the rightmost compound selector, which are the ones that must
+    /// match the element itself.
+    pub fn rightmost_compound(&self) -> SelectorIter<KosmonautSelectors> {
+        self.0.iter()
+    }
+
     /// Return the specificity of this selector.
     pub fn specificity(&self) -> Specificity {
         Specificity(self.0.specificity())
     }
 }
 
+/// Calls `f` with the hash of each part of `element` that an ancestor selector can match against,
+/// for insertion into (or removal from) a bloom filter of ancestors.
+pub fn each_bloom_hash<F: FnMut(u32)>(element: &ElementData, mut f: F) {
+    f(element.name.local.get_hash() & BLOOM_HASH_MASK);
+    f(element.name.ns.get_hash() & BLOOM_HASH_MASK);
+    let attrs = element.attributes.borrow();
+    if let Some(id) = attrs.get(local_name!("id")) {
+        f(LocalName::from(id).get_hash() & BLOOM_HASH_MASK);
+    }
+    if let Some(classes) = attrs.get(local_name!("class")) {
+        classes
+            .split(SELECTOR_WHITESPACE)
+            .filter(|class| !class.is_empty())
+            .for_each(|class| f(LocalName::from(class).get_hash() & BLOOM_HASH_MASK));
+    }
+}
+
+/// The classes of `element`, from its `class` attribute.
+pub fn element_classes(element: &ElementData) -> Vec<LocalName> {
+    element
+        .attributes
+        .borrow()
+        .get(local_name!("class"))
+        .map(|classes| {
+            classes
+                .split(SELECTOR_WHITESPACE)
+                .filter(|class| !class.is_empty())
+                .map(LocalName::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 impl ::std::str::FromStr for Selectors {
     type Err = ();
     #[inline]
@@ -502,4 +578,22 @@ mod tests {
             Specificity(2049)
         )
     }
+
+    #[test]
+    fn user_action_pseudo_classes_match_element_state() {
+        let div = get_div("", "hello")
+            .into_element_ref()
+            .expect("should be able to get element ref for canned node");
+        let hover = Selectors::compile_str("div:hover").unwrap();
+        let active_focus = Selectors::compile_str(":active:focus").unwrap();
+        assert!(!hover.matches(&div));
+        div.as_node().set_element_state(ElementState::HOVER, true);
+        assert!(hover.matches(&div));
+        assert!(!active_focus.matches(&div));
+        div.as_node()
+            .set_element_state(ElementState::ACTIVE | ElementState::FOCUS, true);
+        assert!(active_focus.matches(&div));
+        div.as_node().set_element_state(ElementState::HOVER, false);
+        assert!(!hover.matches(&div));
+    }
 }
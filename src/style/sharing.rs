@@ -0,0 +1,125 @@
+//! Sharing of computed values between nodes.
+//!
+//! A node's computed values depend only on its cascaded declarations and its parent's computed
+//! values, so two nodes that matched the same rules and have the same parent style (most commonly,
+//! siblings such as the items of a list) have the same computed values.  Rather than computing
+//! them again, the second node reuses the first node's `ComputedValues`.  This is modeled after
+//! Servo's style sharing cache, though much simpler.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::dom::tree::NodeRef;
+use crate::style::values::computed::ComputedValues;
+
+/// How many recently styled nodes are remembered as candidates to share with.
+const SHARING_CACHE_SIZE: usize = 16;
+
+struct Candidate {
+    parent: Arc<ComputedValues>,
+    matched_rules: Vec<usize>,
+    computed_values: Arc<ComputedValues>,
+}
+
+/// The computed values of the most recently styled nodes that could share them.
+#[derive(Default)]
+pub struct StyleSharingCache {
+    /// Most recently used first.
+    candidates: VecDeque<Candidate>,
+}
+
+impl StyleSharingCache {
+    pub fn new() -> Self {
+        StyleSharingCache::default()
+    }
+
+    /// Finds computed values `node` can share, given the computed values of its parent.
+    pub fn lookup(
+        &mut self,
+        node: &NodeRef,
+        parent: &Arc<ComputedValues>,
+    ) -> Option<Arc<ComputedValues>> {
+        let decls = node.contextual_decls();
+        let matched_rules = decls.matched_rules()?;
+        let position = self.candidates.iter().position(|candidate| {
+            Arc::ptr_eq(&candidate.parent, parent) && candidate.matched_rules == matched_rules
+        })?;
+        let candidate = self.candidates.remove(position)?;
+        let computed_values = Arc::clone(&candidate.computed_values);
+        self.candidates.push_front(candidate);
+        Some(computed_values)
+    }
+
+    /// Remembers the computed values of `node`, so that later nodes can share them.  Nodes with
+    /// declarations that didn't come from matched rules can't share their values, and are
+    /// ignored.
+    pub fn insert(
+        &mut self,
+        node: &NodeRef,
+        parent: &Arc<ComputedValues>,
+        computed_values: Arc<ComputedValues>,
+    ) {
+        let decls = node.contextual_decls();
+        let matched_rules = match decls.matched_rules() {
+            Some(matched_rules) => matched_rules.to_vec(),
+            None => return,
+        };
+        if self.candidates.len() == SHARING_CACHE_SIZE {
+            self.candidates.pop_back();
+        }
+        self.candidates.push_front(Candidate {
+            parent: Arc::clone(parent),
+            matched_rules,
+            computed_values,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::style::dom_integration::apply_styles;
+    use crate::style::stylesheet::parse_css_to_stylesheet;
+
+    fn styled_dom(html: &str, css: &str) -> NodeRef {
+        let dom = parse_html().one(html);
+        let sheet = parse_css_to_stylesheet(None, &mut css.to_owned()).unwrap();
+        apply_styles(dom.clone(), &[], &[], &[sheet], &[]);
+        dom
+    }
+
+    #[test]
+    fn siblings_matching_the_same_rules_share_computed_values() {
+        let dom = styled_dom(
+            r#"<ul><li class="a">one</li><li class="a">two</li><li class="b">three</li></ul>"#,
+            ".a { color: red } .b { color: blue }",
+        );
+        let items = dom
+            .select("li")
+            .unwrap()
+            .map(|li| li.as_node().shared_computed_values())
+            .collect::<Vec<_>>();
+        assert!(Arc::ptr_eq(&items[0], &items[1]));
+        assert!(!Arc::ptr_eq(&items[1], &items[2]));
+    }
+
+    #[test]
+    fn inherited_style_structs_are_shared_with_the_parent() {
+        let dom = styled_dom(
+            r#"<div class="a"><p class="b">text</p></div>"#,
+            ".a { font-size: 20px } .b { color: red }",
+        );
+        let div = dom.select_first("div").unwrap();
+        let p = dom.select_first("p").unwrap();
+        let div_values = div.as_node().shared_computed_values();
+        let p_values = p.as_node().shared_computed_values();
+        assert!(std::ptr::eq(div_values.get_font(), p_values.get_font()));
+        assert!(!std::ptr::eq(
+            div_values.get_inherited_text(),
+            p_values.get_inherited_text()
+        ));
+    }
+}
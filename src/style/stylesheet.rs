@@ -1,9 +1,12 @@
-use std::mem::discriminant;
-
 use cssparser::ParseError;
+use url::Url;
 
-use crate::dom::tree::NodeRef;
-use crate::style::properties::ContextualPropertyDeclaration;
+use crate::encoding::decode_css;
+use crate::fetch::ResourceLoader;
+use crate::layout::LayoutViewportDimensions;
+use crate::style::font_face::FontFaceSource;
+use crate::style::properties::PropertyDeclaration;
+use crate::style::values::specified::image::Image;
 use crate::style::{
     parse_css_to_rules, CascadeOrigin, CssOrigin, CssRule, StyleParseErrorKind, StylesheetOrigin,
 };
@@ -24,36 +27,116 @@ pub fn parse_css_to_stylesheet(
     Ok(sheet)
 }
 
-pub fn apply_css_rules_to_node(node: &NodeRef, rules: &[CssRule], origin: CssOrigin) {
-    rules.iter().for_each(|rule| {
-        if let CssRule::Style(style_rule) = rule {
-            node.select(&style_rule.selectors)
-                .for_each(|matching_node| {
-                    style_rule
-                        .block
-                        .declarations()
-                        .iter()
-                        .enumerate()
-                        .for_each(|(index, decl)| {
-                            matching_node.as_node().add_decl(ContextualPropertyDeclaration {
-                                inner_decl: decl.clone(),
-                                important: style_rule
-                                    .block
-                                    .declarations_importance()
-                                    .get(index)
-                                    .expect("important bit not set for declaration"),
-                                origin: origin.clone(),
-                                source_location: Some(style_rule.source_location),
-                                specificity: style_rule
-                                    .selectors
-                                    .most_specific_match(&matching_node)
-                                    .expect("there should be at least one matching selector at this point")
-                                    .specificity()
-                            });
-                        });
-                });
+/// Loads the stylesheet at `url` through `loader`, along with the stylesheets it imports that apply
+/// to `viewport`.
+pub fn load_stylesheet(
+    loader: &dyn ResourceLoader,
+    url: &Url,
+    viewport: LayoutViewportDimensions,
+) -> Result<Stylesheet, String> {
+    let mut sheet = Stylesheet::new_with_name(url.to_string());
+    for rule in load_rules(loader, url, viewport, &mut vec![url.clone()])? {
+        sheet.add_rule(rule);
+    }
+    Ok(sheet)
+}
+
+/// Replaces the `@import` rules among `rules`, which are from a stylesheet at `base_url`, with the
+/// rules of the stylesheets they import, loaded through `loader`.  Imports whose media doesn't
+/// match `viewport` are dropped, as are imports that fail to load.
+///
+/// `ancestors` are the URLs of the stylesheets that (transitively) imported this one, which aren't
+/// imported again so that cyclic imports terminate.
+pub fn resolve_imports(
+    loader: &dyn ResourceLoader,
+    rules: Vec<CssRule>,
+    base_url: &Url,
+    viewport: LayoutViewportDimensions,
+    ancestors: &mut Vec<Url>,
+) -> Vec<CssRule> {
+    let mut resolved = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let import = match rule {
+            CssRule::Import(import) => import,
+            CssRule::FontFace(mut font_face) => {
+                // Font sources are relative to the stylesheet they're declared in, which is lost
+                // once its rules are merged into the importing stylesheet.
+                for source in &mut font_face.sources {
+                    if let FontFaceSource::Url(url) = source {
+                        if let Ok(absolute_url) = base_url.join(url) {
+                            *url = absolute_url.to_string();
+                        }
+                    }
+                }
+                resolved.push(CssRule::FontFace(font_face));
+                continue;
+            }
+            CssRule::Style(mut style_rule) => {
+                // Likewise for the URLs of background images.
+                for declaration in style_rule.block.declarations_mut() {
+                    if let PropertyDeclaration::BackgroundImage(background_image) = declaration {
+                        for image in background_image.0.iter_mut().flatten() {
+                            if let Image::Url(url) = image {
+                                if let Ok(absolute_url) = base_url.join(url) {
+                                    *url = absolute_url.to_string();
+                                }
+                            }
+                        }
+                    }
+                }
+                resolved.push(CssRule::Style(style_rule));
+                continue;
+            }
+            rule => {
+                resolved.push(rule);
+                continue;
+            }
+        };
+        if !import.media.matches(viewport) {
+            continue;
+        }
+        let url = match base_url.join(&import.url) {
+            Ok(url) => url,
+            Err(err) => {
+                println!("invalid @import url '{}': {}", import.url, err);
+                continue;
+            }
+        };
+        if ancestors.contains(&url) {
+            println!("ignoring cyclic @import of '{}'", url);
+            continue;
+        }
+        ancestors.push(url.clone());
+        match load_rules(loader, &url, viewport, ancestors) {
+            Ok(rules) => resolved.extend(rules),
+            Err(err) => println!("error loading @import of '{}': {}", url, err),
         }
-    });
+        ancestors.pop();
+    }
+    resolved
+}
+
+fn load_rules(
+    loader: &dyn ResourceLoader,
+    url: &Url,
+    viewport: LayoutViewportDimensions,
+    ancestors: &mut Vec<Url>,
+) -> Result<Vec<CssRule>, String> {
+    let stylesheet = loader.load(url)?;
+    let mut css = decode_css(&stylesheet.contents, stylesheet.content_type.as_deref());
+    let rules = parse_css_to_rules(&mut css)
+        .map_err(|(err, _)| format!("error parsing stylesheet '{}': {:?}", url, err))?;
+    // Imports are relative to the URL the stylesheet was redirected to, if it was, and mustn't
+    // import it again either.
+    let redirected = stylesheet.url != *url;
+    if redirected {
+        ancestors.push(stylesheet.url.clone());
+    }
+    let rules = resolve_imports(loader, rules, &stylesheet.url, viewport, ancestors);
+    if redirected {
+        ancestors.pop();
+    }
+    Ok(rules)
 }
 
 #[derive(Debug)]
@@ -120,15 +203,16 @@ impl Stylesheet {
                                     existing_style.block.declarations().iter().enumerate()
                                 {
                                     for new_prop in new_style.block.declarations() {
-                                        if discriminant(new_prop) == discriminant(existing_prop) {
-                                            // the props are the same "type", e.g. both `font-size, both `display`, etc
+                                        if new_prop.id() == existing_prop.id() {
+                                            // the props are the same property, e.g. both `font-size, both `display`, etc
                                             // take the `new_prop`, since the latest/newest prop should always be taken
                                             obsolete_prop_indices.push(prop_index);
                                         }
                                     }
                                 }
 
-                                for index in obsolete_prop_indices {
+                                // Remove from the back so the earlier indices stay valid.
+                                for index in obsolete_prop_indices.into_iter().rev() {
                                     existing_style.block.remove_decl(index);
                                 }
                                 if existing_style.block.declarations().is_empty() {
@@ -137,7 +221,7 @@ impl Stylesheet {
                                 }
                             }
                         }
-                        CssRule::None => {}
+                        CssRule::FontFace(_) | CssRule::Import(_) | CssRule::None => {}
                     }
                 }
 
@@ -145,7 +229,7 @@ impl Stylesheet {
                     self.rules.remove(index);
                 }
             }
-            CssRule::None => {}
+            CssRule::FontFace(_) | CssRule::Import(_) | CssRule::None => {}
         }
         self.rules.push(new_rule);
     }
@@ -153,6 +237,7 @@ impl Stylesheet {
 
 #[cfg(test)]
 mod tests {
+    use crate::fetch::InMemoryResourceLoader;
     use crate::style::test_utils::font_size_px_or_panic;
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
@@ -180,4 +265,78 @@ mod tests {
             _ => panic!("should always be a `StyleRule` CssRule"),
         }
     }
+
+    #[test]
+    fn imports_load_relative_to_the_importing_stylesheet() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        let mut loader = InMemoryResourceLoader::new();
+        loader.add_redirect(
+            url("https://a.com/main.css"),
+            url("https://b.com/css/main.css"),
+        );
+        loader.add(
+            url("https://b.com/css/main.css"),
+            Some("text/css"),
+            "@import 'imported.css'; .main { font-size: 10px }",
+        );
+        loader.add(
+            url("https://b.com/css/imported.css"),
+            Some("text/css"),
+            "@import 'main.css'; .imported { font-size: 12px }",
+        );
+        let viewport = LayoutViewportDimensions::new_px(800., 600.);
+        let sheet = load_stylesheet(&loader, &url("https://a.com/main.css"), viewport).unwrap();
+        let font_sizes = sheet
+            .rules()
+            .iter()
+            .map(|rule| match rule {
+                CssRule::Style(style_rule) => {
+                    *font_size_px_or_panic(&style_rule.block.declarations()[0])
+                }
+                _ => panic!("imports should have been replaced by the rules they import"),
+            })
+            .collect::<Vec<_>>();
+        // The cyclic import of `main.css` is ignored.
+        assert_eq!(font_sizes, vec![12.0, 10.0]);
+    }
+
+    #[test]
+    fn background_image_urls_resolve_against_their_stylesheet() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        let mut loader = InMemoryResourceLoader::new();
+        loader.add(
+            url("https://a.com/main.css"),
+            Some("text/css"),
+            "@import 'css/imported.css'; .main { background-image: url(main.png) }",
+        );
+        loader.add(
+            url("https://a.com/css/imported.css"),
+            Some("text/css"),
+            ".imported { background: url(../img/imported.png), none }",
+        );
+        let viewport = LayoutViewportDimensions::new_px(800., 600.);
+        let sheet = load_stylesheet(&loader, &url("https://a.com/main.css"), viewport).unwrap();
+        let image_urls = sheet
+            .rules()
+            .iter()
+            .flat_map(|rule| match rule {
+                CssRule::Style(style_rule) => style_rule.block.declarations().to_vec(),
+                _ => panic!("imports should have been replaced by the rules they import"),
+            })
+            .filter_map(|declaration| match declaration {
+                PropertyDeclaration::BackgroundImage(background_image) => Some(background_image.0),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            image_urls,
+            vec![
+                vec![
+                    Some(Image::Url("https://a.com/img/imported.png".to_owned())),
+                    None
+                ],
+                vec![Some(Image::Url("https://a.com/main.png".to_owned()))],
+            ]
+        );
+    }
 }
@@ -0,0 +1,340 @@
+//! Matching of style rules to the elements of a document.
+//!
+//! Rather than matching every rule against every element, rules are indexed by the rightmost
+//! compound selector of each of their selectors, so that each element is only matched against the
+//! rules that could possibly apply to it.  This is modeled after Servo's `Stylist` and
+//! `SelectorMap`.
+
+use std::collections::HashMap;
+
+use html5ever::LocalName;
+use selectors::bloom::BloomFilter;
+use selectors::parser::{AncestorHashes, Component};
+
+use crate::dom::iter::NodeEdge;
+use crate::dom::node_data_ref::NodeDataRef;
+use crate::dom::tree::{DirtyFlags, ElementData, NodeRef};
+use crate::style::properties::{ContextualPropertyDeclaration, ContextualPropertyDeclarations};
+use crate::style::select::{each_bloom_hash, element_classes, Selector, Specificity};
+use crate::style::{CssOrigin, CssRule, StyleRule};
+
+/// The style rules of all the stylesheets applying to a document, indexed for matching.  The
+/// rules are copied out of their stylesheets, so that the stylist can be kept and reused for as
+/// long as the stylesheets don't change.
+#[derive(Default)]
+pub struct Stylist {
+    /// The style rules, in cascade order: rules from stylesheets added later, and rules later in
+    /// the same stylesheet, come later.
+    rules: Vec<(StyleRule, CssOrigin)>,
+    map: SelectorMap,
+}
+
+impl Stylist {
+    pub fn new() -> Self {
+        Stylist::default()
+    }
+
+    /// Adds the style rules among `rules`, with the given origin.  Rules added later win the
+    /// cascade over rules added earlier, all else being equal.
+    pub fn add_rules(&mut self, rules: &[CssRule], origin: &CssOrigin) {
+        for rule in rules {
+            if let CssRule::Style(style_rule) = rule {
+                let rule_index = self.rules.len();
+                for (selector_index, selector) in style_rule.selectors.0.iter().enumerate() {
+                    self.map.insert(
+                        selector,
+                        Rule {
+                            hashes: selector.ancestor_hashes(),
+                            rule_index,
+                            selector_index,
+                        },
+                    );
+                }
+                self.rules.push((style_rule.clone(), origin.clone()));
+            }
+        }
+    }
+
+    /// The selector of the style rule that `rule` was made for.
+    fn selector(&self, rule: &Rule) -> &Selector {
+        &self.rules[rule.rule_index].0.selectors.0[rule.selector_index]
+    }
+
+    /// Adds the declarations of the rules matching each element below `root` to the element, in
+    /// cascade order, in a single traversal of the tree.
+    pub fn apply_rules(&self, root: &NodeRef) {
+        // Holds the hashes of the ancestors of the element being matched, so that selectors
+        // whose ancestors can't match are rejected without walking up the tree.
+        let mut bloom_filter = BloomFilter::new();
+        for edge in root.traverse() {
+            match edge {
+                NodeEdge::Start(node) => {
+                    if let Some(element) = node.clone().into_element_ref() {
+                        self.apply_matching_rules(&element, &bloom_filter);
+                        each_bloom_hash(&element, |hash| bloom_filter.insert_hash(hash));
+                    }
+                }
+                NodeEdge::End(node) => {
+                    if let Some(element) = node.as_element() {
+                        each_bloom_hash(element, |hash| bloom_filter.remove_hash(hash));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replaces the declarations of the nodes whose style is dirty, and of their descendants, with
+    /// those of the rules matching them now.  Returns the restyled nodes, in tree order.
+    pub fn reapply_rules(&self, root: &NodeRef) -> Vec<NodeRef> {
+        let mut restyled = Vec::new();
+        self.reapply_rules_to_subtree(root, false, &mut BloomFilter::new(), &mut restyled);
+        restyled
+    }
+
+    fn reapply_rules_to_subtree(
+        &self,
+        node: &NodeRef,
+        ancestor_restyled: bool,
+        bloom_filter: &mut BloomFilter,
+        restyled: &mut Vec<NodeRef>,
+    ) {
+        let flags = node.dirty_flags();
+        let restyle = ancestor_restyled || flags.contains(DirtyFlags::STYLE);
+        if !restyle && !flags.contains(DirtyFlags::DESCENDANT_STYLE) {
+            return;
+        }
+        node.remove_dirty_flags(DirtyFlags::STYLE | DirtyFlags::DESCENDANT_STYLE);
+        if restyle {
+            *node.contextual_decls_mut() = ContextualPropertyDeclarations::new();
+            if let Some(element) = node.clone().into_element_ref() {
+                self.apply_matching_rules(&element, bloom_filter);
+            }
+            restyled.push(node.clone());
+        }
+        if let Some(element) = node.as_element() {
+            each_bloom_hash(element, |hash| bloom_filter.insert_hash(hash));
+        }
+        for child in node.children() {
+            self.reapply_rules_to_subtree(&child, restyle, bloom_filter, restyled);
+        }
+        if let Some(element) = node.as_element() {
+            each_bloom_hash(element, |hash| bloom_filter.remove_hash(hash));
+        }
+    }
+
+    fn apply_matching_rules(&self, element: &NodeDataRef<ElementData>, bloom_filter: &BloomFilter) {
+        // The matched rules, along with the specificity of their most specific matching selector.
+        let mut matched: Vec<(usize, Specificity)> = Vec::new();
+        self.map.for_each_candidate(element, |rule| {
+            let selector = self.selector(rule);
+            if selector.matches_with_bloom_filter(element, &rule.hashes, bloom_filter) {
+                matched.push((rule.rule_index, selector.specificity()));
+            }
+        });
+        // A rule can match through several of its selectors, but only applies once.
+        matched.sort_by(|(index_a, spec_a), (index_b, spec_b)| {
+            index_a.cmp(index_b).then(spec_b.cmp(spec_a))
+        });
+        matched.dedup_by_key(|(rule_index, _)| *rule_index);
+        let mut contextual_decls = element.as_node().contextual_decls_mut();
+        for (rule_index, specificity) in matched {
+            let (style_rule, origin) = &self.rules[rule_index];
+            let importance = style_rule.block.declarations_importance();
+            let decls = style_rule
+                .block
+                .declarations()
+                .iter()
+                .enumerate()
+                .map(|(index, decl)| ContextualPropertyDeclaration {
+                    inner_decl: decl.clone(),
+                    important: importance
+                        .get(index)
+                        .expect("important bit not set for declaration"),
+                    origin: origin.clone(),
+                    source_location: Some(style_rule.source_location),
+                    specificity,
+                });
+            contextual_decls.add_matched_rule(rule_index, decls);
+        }
+    }
+}
+
+/// A single selector of a style rule.
+struct Rule {
+    hashes: AncestorHashes,
+    /// The index of the selector's rule in `Stylist::rules`.
+    rule_index: usize,
+    /// The index of the selector among the selectors of its rule.
+    selector_index: usize,
+}
+
+/// Buckets selectors by the most selective simple selector of their rightmost compound selector.
+#[derive(Default)]
+struct SelectorMap {
+    id_hash: HashMap<LocalName, Vec<Rule>>,
+    class_hash: HashMap<LocalName, Vec<Rule>>,
+    local_name_hash: HashMap<LocalName, Vec<Rule>>,
+    /// Selectors without an id, class, or type selector in their rightmost compound selector, such
+    /// as `*` or `[href]`, which must be matched against every element.
+    other: Vec<Rule>,
+}
+
+impl SelectorMap {
+    /// Buckets `rule`, which was made for `selector`.
+    fn insert(&mut self, selector: &Selector, rule: Rule) {
+        let mut class = None;
+        let mut local_name = None;
+        for component in selector.rightmost_compound() {
+            match component {
+                Component::ID(id) => {
+                    self.id_hash.entry(id.clone()).or_default().push(rule);
+                    return;
+                }
+                Component::Class(name) if class.is_none() => class = Some(name.clone()),
+                Component::LocalName(name) if local_name.is_none() => {
+                    local_name = Some(name.lower_name.clone())
+                }
+                _ => {}
+            }
+        }
+        if let Some(class) = class {
+            self.class_hash.entry(class).or_default().push(rule);
+        } else if let Some(local_name) = local_name {
+            self.local_name_hash
+                .entry(local_name)
+                .or_default()
+                .push(rule);
+        } else {
+            self.other.push(rule);
+        }
+    }
+
+    /// Calls `f` with each selector that could match `element`.
+    fn for_each_candidate<F: FnMut(&Rule)>(&self, element: &NodeDataRef<ElementData>, mut f: F) {
+        if let Some(id) = element.attributes.borrow().get(local_name!("id")) {
+            if let Some(rules) = self.id_hash.get(&LocalName::from(id)) {
+                rules.iter().for_each(&mut f);
+            }
+        }
+        for class in element_classes(element) {
+            if let Some(rules) = self.class_hash.get(&class) {
+                rules.iter().for_each(&mut f);
+            }
+        }
+        if let Some(rules) = self.local_name_hash.get(&element.name.local) {
+            rules.iter().for_each(&mut f);
+        }
+        self.other.iter().for_each(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::style::parse_css_to_rules;
+    use crate::style::select::Selectors;
+
+    /// The lines of the rules matching each element of `html`, found by matching every rule of
+    /// `css` against every element.
+    fn matched_lines_naively(html: &str, css: &str) -> Vec<Vec<u32>> {
+        let dom = parse_html().one(html);
+        let rules = parse_css_to_rules(&mut css.to_owned()).unwrap();
+        dom.descendants()
+            .filter_map(NodeRef::into_element_ref)
+            .map(|element| {
+                rules
+                    .iter()
+                    .filter_map(|rule| match rule {
+                        CssRule::Style(style_rule) => Some(style_rule),
+                        _ => None,
+                    })
+                    .filter(|style_rule| style_rule.selectors.matches(&element))
+                    .map(|style_rule| style_rule.source_location.line)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The lines of the declarations added to each element of `html` by the stylist.
+    fn matched_lines(html: &str, css: &str) -> Vec<Vec<u32>> {
+        let dom = parse_html().one(html);
+        let rules = parse_css_to_rules(&mut css.to_owned()).unwrap();
+        let mut stylist = Stylist::new();
+        stylist.add_rules(&rules, &CssOrigin::Embedded);
+        stylist.apply_rules(&dom);
+        dom.descendants()
+            .filter_map(NodeRef::into_element_ref)
+            .map(|element| {
+                let decls = element.as_node().contextual_decls();
+                let mut lines = decls
+                    .iter()
+                    .map(|decl| decl.source_location.unwrap().line)
+                    .collect::<Vec<_>>();
+                lines.dedup();
+                lines
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_the_same_rules_as_naive_matching() {
+        let html = r#"
+            <div id="outer" class="a b">
+                <p class="b">text <span id="inner" lang="en">more</span></p>
+                <section><p>para</p></section>
+            </div>
+            <p class="c"></p>
+        "#;
+        let css = "
+            div { width: 1px }
+            .b { width: 1px }
+            #outer .b { width: 1px }
+            #inner { width: 1px }
+            div > p, section p { width: 1px }
+            * { width: 1px }
+            [lang] { width: 1px }
+            div span { width: 1px }
+            section > span { width: 1px }
+            .c.a, P { width: 1px }
+        ";
+        assert_eq!(matched_lines(html, css), matched_lines_naively(html, css));
+    }
+
+    #[test]
+    fn reapplying_rules_only_restyles_dirty_subtrees() {
+        let dom = parse_html().one(r#"<div><p id="one">a</p><p id="two"><b>b</b></p></div>"#);
+        let rules = parse_css_to_rules(&mut ".on b { width: 1px }".to_owned()).unwrap();
+        let mut stylist = Stylist::new();
+        stylist.add_rules(&rules, &CssOrigin::Embedded);
+        stylist.apply_rules(&dom);
+        let two = dom.select_first("#two").unwrap().as_node().clone();
+        two.set_attribute(local_name!("class"), "on");
+        let restyled = stylist.reapply_rules(&dom);
+        let b = dom.select_first("b").unwrap().as_node().clone();
+        assert_eq!(restyled, vec![two, b.clone(), b.first_child().unwrap()]);
+        assert_eq!(b.contextual_decls().iter().count(), 1);
+        assert!(dom
+            .inclusive_descendants()
+            .all(|node| node.dirty_flags().is_empty()));
+    }
+
+    #[test]
+    fn rules_matching_through_several_selectors_apply_once() {
+        let dom = parse_html().one(r#"<div id="one" class="two"></div>"#);
+        let rules = parse_css_to_rules(&mut "div, .two, #one { width: 1px }".to_owned()).unwrap();
+        let mut stylist = Stylist::new();
+        stylist.add_rules(&rules, &CssOrigin::Embedded);
+        stylist.apply_rules(&dom);
+        let div = dom.select_first("div").unwrap();
+        let decls = div.as_node().contextual_decls();
+        let specificities = decls
+            .iter()
+            .map(|decl| decl.specificity)
+            .collect::<Vec<_>>();
+        let id_specificity = Selectors::compile_str("#one").unwrap().0[0].specificity();
+        assert_eq!(specificities, vec![id_specificity]);
+    }
+}
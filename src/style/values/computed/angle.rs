@@ -0,0 +1,79 @@
+use crate::style::values::{CSSFloat, CssValueParse};
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser, Token};
+use std::f32::consts::PI;
+
+/// A computed `<angle>`, in radians.  Angles are never relative to anything, so there is no
+/// specified counterpart.
+///
+/// https://drafts.csswg.org/css-values-4/#angles
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Angle(CSSFloat);
+
+impl Angle {
+    pub fn from_radians(radians: CSSFloat) -> Angle {
+        Angle(radians)
+    }
+
+    pub fn from_degrees(degrees: CSSFloat) -> Angle {
+        Angle(degrees.to_radians())
+    }
+
+    pub fn radians(self) -> CSSFloat {
+        self.0
+    }
+
+    /// The fraction of a full turn this angle is.
+    pub fn turns(self) -> CSSFloat {
+        self.0 / (2. * PI)
+    }
+
+    /// Parses an angle from a dimension with the given unit.
+    pub fn parse_dimension(value: CSSFloat, unit: &str) -> Result<Angle, ()> {
+        Ok(match_ignore_ascii_case! { unit,
+            "deg" => Angle::from_degrees(value),
+            "grad" => Angle(value * PI / 200.),
+            "rad" => Angle(value),
+            "turn" => Angle(value * 2. * PI),
+            _ => return Err(())
+        })
+    }
+}
+
+impl CssValueParse for Angle {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let token = input.next()?;
+        match *token {
+            Token::Dimension {
+                value, ref unit, ..
+            } => Angle::parse_dimension(value, unit)
+                .map_err(|()| location.new_unexpected_token_error(token.clone())),
+            _ => Err(location.new_unexpected_token_error(token.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cssparser::ParserInput;
+
+    fn parse_angle(css: &str) -> Option<Angle> {
+        let mut input = ParserInput::new(css);
+        Angle::parse(&mut Parser::new(&mut input)).ok()
+    }
+
+    #[test]
+    fn parses_every_angle_unit() {
+        let turns = |css| parse_angle(css).map(|angle| (angle.turns() * 1000.).round() / 1000.);
+        assert_eq!(turns("90deg"), Some(0.25));
+        assert_eq!(turns("200grad"), Some(0.5));
+        assert_eq!(turns("3.14159265rad"), Some(0.5));
+        assert_eq!(turns("1.5turn"), Some(1.5));
+        assert_eq!(parse_angle("90"), None);
+        assert_eq!(parse_angle("90px"), None);
+    }
+}
@@ -1,11 +1,18 @@
-use crate::style::values::computed::{ComputeContext, ComputeValueWithContext, ValueDefault};
+use crate::style::values::computed::image::Image;
+use crate::style::values::computed::length::{LengthPercentage, LengthPercentageOrAuto};
+use crate::style::values::computed::position::PositionComponent;
+use crate::style::values::computed::{
+    ComputeContext, ComputeValue, ComputeValueWithContext, Percentage, ValueDefault,
+};
 use crate::style::values::specified;
-use cssparser::RGBA;
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser, RGBA};
 
 /// Computed values for the `background-color` property.
 ///
 /// https://www.w3.org/TR/2017/CR-css-backgrounds-3-20171017/#propdef-background-color
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BackgroundColor(RGBA);
 
 impl BackgroundColor {
@@ -36,3 +43,404 @@ impl ValueDefault for specified::BackgroundColor {
         BackgroundColor::initial_value(context.color().rgba())
     }
 }
+
+/// Returns the value of a background property for the background layer at `index`.  The number of
+/// layers is the number of `background-image` values, and the values of the other background
+/// properties repeat if there are fewer of them.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#layering
+pub fn layer_value<T>(values: &[T], index: usize) -> &T {
+    &values[index % values.len()]
+}
+
+/// Computed values for the `background-image` property, one for each layer, from top to bottom.
+/// Layers with an image of `none`, or a URL that isn't valid, are `None`.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-image
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundImage(pub Vec<Option<Image>>);
+
+impl BackgroundImage {
+    pub fn initial_value() -> BackgroundImage {
+        BackgroundImage(vec![None])
+    }
+}
+
+impl ComputeValueWithContext for specified::BackgroundImage {
+    type ComputedValue = BackgroundImage;
+
+    fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundImage(
+            self.0
+                .iter()
+                .map(|image| image.as_ref()?.compute_value_with_context(context))
+                .collect(),
+        )
+    }
+}
+
+impl ValueDefault for specified::BackgroundImage {
+    type ComputedValue = BackgroundImage;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundImage::initial_value()
+    }
+}
+
+/// Computed values for the `background-position-x` property.
+///
+/// https://drafts.csswg.org/css-backgrounds-4/#background-position-longhands
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundPositionX(pub Vec<PositionComponent>);
+
+impl BackgroundPositionX {
+    pub fn initial_value() -> BackgroundPositionX {
+        BackgroundPositionX(vec![initial_position_component()])
+    }
+}
+
+impl ComputeValueWithContext for specified::BackgroundPositionX {
+    type ComputedValue = BackgroundPositionX;
+
+    fn compute_value_with_context(&self, _context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundPositionX(self.0.iter().map(ComputeValue::compute_value).collect())
+    }
+}
+
+impl ValueDefault for specified::BackgroundPositionX {
+    type ComputedValue = BackgroundPositionX;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundPositionX::initial_value()
+    }
+}
+
+/// Computed values for the `background-position-y` property.
+///
+/// https://drafts.csswg.org/css-backgrounds-4/#background-position-longhands
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundPositionY(pub Vec<PositionComponent>);
+
+impl BackgroundPositionY {
+    pub fn initial_value() -> BackgroundPositionY {
+        BackgroundPositionY(vec![initial_position_component()])
+    }
+}
+
+impl ComputeValueWithContext for specified::BackgroundPositionY {
+    type ComputedValue = BackgroundPositionY;
+
+    fn compute_value_with_context(&self, _context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundPositionY(self.0.iter().map(ComputeValue::compute_value).collect())
+    }
+}
+
+impl ValueDefault for specified::BackgroundPositionY {
+    type ComputedValue = BackgroundPositionY;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundPositionY::initial_value()
+    }
+}
+
+/// The initial position of a background image, `0%`, along either axis.
+fn initial_position_component() -> PositionComponent {
+    PositionComponent {
+        from_end: false,
+        offset: LengthPercentage::Percentage(Percentage(0.)),
+    }
+}
+
+/// Computed values for the `background-size` property.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-size
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundSize(pub Vec<BackgroundSizeLayer>);
+
+impl BackgroundSize {
+    pub fn initial_value() -> BackgroundSize {
+        BackgroundSize(vec![BackgroundSizeLayer::Explicit {
+            width: LengthPercentageOrAuto::Auto,
+            height: LengthPercentageOrAuto::Auto,
+        }])
+    }
+}
+
+/// The computed size of a single background layer's image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackgroundSizeLayer {
+    /// A width and height, where percentages refer to the background positioning area.  Sizes
+    /// that are `auto` follow from the image's intrinsic size and ratio.
+    Explicit {
+        width: LengthPercentageOrAuto,
+        height: LengthPercentageOrAuto,
+    },
+    /// Scale the image to the smallest size that covers the background positioning area.
+    Cover,
+    /// Scale the image to the largest size that fits in the background positioning area.
+    Contain,
+}
+
+impl ComputeValueWithContext for specified::BackgroundSize {
+    type ComputedValue = BackgroundSize;
+
+    fn compute_value_with_context(&self, _context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundSize(
+            self.0
+                .iter()
+                .map(|layer| match layer {
+                    specified::background::BackgroundSizeLayer::Explicit { width, height } => {
+                        BackgroundSizeLayer::Explicit {
+                            width: width.compute_value(),
+                            height: height.compute_value(),
+                        }
+                    }
+                    specified::background::BackgroundSizeLayer::Cover => BackgroundSizeLayer::Cover,
+                    specified::background::BackgroundSizeLayer::Contain => {
+                        BackgroundSizeLayer::Contain
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+impl ValueDefault for specified::BackgroundSize {
+    type ComputedValue = BackgroundSize;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundSize::initial_value()
+    }
+}
+
+/// Computed values for the `background-repeat` property.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-repeat
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundRepeat(pub Vec<BackgroundRepeatLayer>);
+
+impl BackgroundRepeat {
+    pub fn initial_value() -> BackgroundRepeat {
+        BackgroundRepeat(vec![BackgroundRepeatLayer {
+            x: RepeatStyle::Repeat,
+            y: RepeatStyle::Repeat,
+        }])
+    }
+}
+
+impl CssValueParse for BackgroundRepeat {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(BackgroundRepeatLayer::parse)
+            .map(BackgroundRepeat)
+    }
+}
+
+impl ValueDefault for BackgroundRepeat {
+    type ComputedValue = BackgroundRepeat;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundRepeat::initial_value()
+    }
+}
+
+/// How a single background layer's image is repeated horizontally and vertically.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BackgroundRepeatLayer {
+    pub x: RepeatStyle,
+    pub y: RepeatStyle,
+}
+
+/// Parses `repeat-x | repeat-y | <repeat-style>{1,2}`.
+impl CssValueParse for BackgroundRepeatLayer {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let axis_keyword = input.try_parse(|i| {
+            try_match_ident_ignore_ascii_case! { i,
+                "repeat-x" => Ok(BackgroundRepeatLayer {
+                    x: RepeatStyle::Repeat,
+                    y: RepeatStyle::NoRepeat,
+                }),
+                "repeat-y" => Ok(BackgroundRepeatLayer {
+                    x: RepeatStyle::NoRepeat,
+                    y: RepeatStyle::Repeat,
+                }),
+            }
+        });
+        if axis_keyword.is_ok() {
+            return axis_keyword;
+        }
+        let x = RepeatStyle::parse(input)?;
+        let y = input.try_parse(RepeatStyle::parse).unwrap_or(x);
+        Ok(BackgroundRepeatLayer { x, y })
+    }
+}
+
+/// How a background image is repeated along one axis.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RepeatStyle {
+    /// Tiled as often as needed to cover the background painting area.
+    Repeat,
+    /// Tiled as often as fits in the background positioning area without being clipped, with the
+    /// space left over distributed between the tiles.
+    Space,
+    /// Tiled as often as fits in the background positioning area, with the tiles scaled so that a
+    /// whole number of them fit exactly.
+    Round,
+    /// Placed once.
+    NoRepeat,
+}
+
+impl CssValueParse for RepeatStyle {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "repeat" => Ok(RepeatStyle::Repeat),
+            "space" => Ok(RepeatStyle::Space),
+            "round" => Ok(RepeatStyle::Round),
+            "no-repeat" => Ok(RepeatStyle::NoRepeat),
+        }
+    }
+}
+
+/// Computed values for the `background-attachment` property.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-attachment
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundAttachment(pub Vec<Attachment>);
+
+impl BackgroundAttachment {
+    pub fn initial_value() -> BackgroundAttachment {
+        BackgroundAttachment(vec![Attachment::Scroll])
+    }
+}
+
+impl CssValueParse for BackgroundAttachment {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(Attachment::parse)
+            .map(BackgroundAttachment)
+    }
+}
+
+impl ValueDefault for BackgroundAttachment {
+    type ComputedValue = BackgroundAttachment;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundAttachment::initial_value()
+    }
+}
+
+/// What a background image is fixed relative to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Attachment {
+    /// The element, so the image scrolls along with the document.
+    Scroll,
+    /// The viewport, so the image doesn't move when the document is scrolled.
+    Fixed,
+    /// The element's contents.  Elements don't scroll their own contents yet, so this is the same
+    /// as `Scroll`.
+    Local,
+}
+
+impl CssValueParse for Attachment {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "scroll" => Ok(Attachment::Scroll),
+            "fixed" => Ok(Attachment::Fixed),
+            "local" => Ok(Attachment::Local),
+        }
+    }
+}
+
+/// Computed values for the `background-clip` property, which is the background painting area of
+/// each layer.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-clip
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundClip(pub Vec<BackgroundBox>);
+
+impl BackgroundClip {
+    pub fn initial_value() -> BackgroundClip {
+        BackgroundClip(vec![BackgroundBox::BorderBox])
+    }
+}
+
+impl CssValueParse for BackgroundClip {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(BackgroundBox::parse)
+            .map(BackgroundClip)
+    }
+}
+
+impl ValueDefault for BackgroundClip {
+    type ComputedValue = BackgroundClip;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundClip::initial_value()
+    }
+}
+
+/// Computed values for the `background-origin` property, which is the background positioning
+/// area of each layer.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-origin
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundOrigin(pub Vec<BackgroundBox>);
+
+impl BackgroundOrigin {
+    pub fn initial_value() -> BackgroundOrigin {
+        BackgroundOrigin(vec![BackgroundBox::PaddingBox])
+    }
+}
+
+impl CssValueParse for BackgroundOrigin {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(BackgroundBox::parse)
+            .map(BackgroundOrigin)
+    }
+}
+
+impl ValueDefault for BackgroundOrigin {
+    type ComputedValue = BackgroundOrigin;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BackgroundOrigin::initial_value()
+    }
+}
+
+/// One of the boxes of an element that a background layer can be positioned in or clipped to.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#typedef-box
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BackgroundBox {
+    BorderBox,
+    PaddingBox,
+    ContentBox,
+}
+
+impl CssValueParse for BackgroundBox {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "border-box" => Ok(BackgroundBox::BorderBox),
+            "padding-box" => Ok(BackgroundBox::PaddingBox),
+            "content-box" => Ok(BackgroundBox::ContentBox),
+        }
+    }
+}
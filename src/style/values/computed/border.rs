@@ -1,10 +1,12 @@
-use crate::style::values::computed::length::CSSPixelLength;
+use crate::layout::flow::OriginRelativeProgression;
+use crate::style::values::computed::length::{CSSPixelLength, LengthPercentage};
 use crate::style::values::computed::{
-    ComputeContext, ComputeValue, ComputeValueWithContext, ValueDefault,
+    ComputeContext, ComputeValue, ComputeValueWithContext, Direction, ValueDefault, WritingMode,
 };
 use crate::style::values::specified::border::LineWidth;
 use crate::style::values::{specified, CssValueParse};
 use crate::style::StyleParseErrorKind;
+use crate::Corner;
 use cssparser::{ParseError, Parser, RGBA};
 
 /// The intitial value for `border-<side>-style` properties.  This would normally be handled by
@@ -42,7 +44,7 @@ pub fn compute_border_side_width(
 /// Computed `border-<side>-color`.
 ///
 /// https://www.w3.org/TR/css-backgrounds-3/#border-color
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BorderColor {
     pub rgba: RGBA,
 }
@@ -83,7 +85,7 @@ impl ValueDefault for specified::BorderColor {
 /// Computed `border-<side>-width`.
 ///
 /// https://www.w3.org/TR/css-backgrounds-3/#border-width
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct BorderWidth {
     pub size: CSSPixelLength,
 }
@@ -171,6 +173,76 @@ impl ValueDefault for specified::BorderTopWidth {
     }
 }
 
+/// Computed `border-<corner>-radius`.  Only the physical corners are computed, as the logical
+/// corner properties cascade together with the physical corner they map to.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#border-radius
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BorderCornerRadius {
+    pub horizontal: LengthPercentage,
+    pub vertical: LengthPercentage,
+}
+
+impl BorderCornerRadius {
+    pub fn initial_value() -> BorderCornerRadius {
+        BorderCornerRadius {
+            horizontal: LengthPercentage::new_len(0.),
+            vertical: LengthPercentage::new_len(0.),
+        }
+    }
+}
+
+impl ComputeValueWithContext for specified::BorderCornerRadius {
+    type ComputedValue = BorderCornerRadius;
+
+    fn compute_value_with_context(&self, _context: &ComputeContext) -> Self::ComputedValue {
+        BorderCornerRadius {
+            horizontal: self.horizontal.compute_value(),
+            vertical: self.vertical.compute_value(),
+        }
+    }
+}
+
+impl ValueDefault for specified::BorderCornerRadius {
+    type ComputedValue = BorderCornerRadius;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        BorderCornerRadius::initial_value()
+    }
+}
+
+/// The physical corner between the block-start (or block-end, if `block_start` is false) side and
+/// the inline-start (or inline-end) side of a box with the given `writing-mode` and `direction`.
+///
+/// https://drafts.csswg.org/css-logical/#border-radius-properties
+pub fn logical_corner_to_physical(
+    block_start: bool,
+    inline_start: bool,
+    writing_mode: WritingMode,
+    direction: Direction,
+) -> Corner {
+    // The start side of an axis is nearest the origin when the axis progresses away from it.
+    let block_nearest_origin = block_start
+        == (OriginRelativeProgression::block_start_origin_relative_direction(writing_mode)
+            == OriginRelativeProgression::AwayFromOrigin);
+    let inline_nearest_origin = inline_start
+        == (OriginRelativeProgression::inline_start_origin_relative_direction(
+            writing_mode,
+            direction,
+        ) == OriginRelativeProgression::AwayFromOrigin);
+    let (left, top) = if writing_mode.is_horizontal() {
+        (inline_nearest_origin, block_nearest_origin)
+    } else {
+        (block_nearest_origin, inline_nearest_origin)
+    };
+    match (left, top) {
+        (true, true) => Corner::TopLeft,
+        (false, true) => Corner::TopRight,
+        (false, false) => Corner::BottomRight,
+        (true, false) => Corner::BottomLeft,
+    }
+}
+
 /// The border `<line-style>` type.
 ///
 /// https://www.w3.org/TR/2017/CR-css-backgrounds-3-20171017/#typedef-line-style
@@ -219,3 +291,32 @@ impl ComputeValue for LineWidth {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_logical_corners_to_physical_corners() {
+        assert_eq!(
+            logical_corner_to_physical(true, false, WritingMode::HorizontalTb, Direction::Ltr),
+            Corner::TopRight
+        );
+        assert_eq!(
+            logical_corner_to_physical(true, true, WritingMode::HorizontalTb, Direction::Rtl),
+            Corner::TopRight
+        );
+        assert_eq!(
+            logical_corner_to_physical(true, true, WritingMode::VerticalRl, Direction::Ltr),
+            Corner::TopRight
+        );
+        assert_eq!(
+            logical_corner_to_physical(false, true, WritingMode::VerticalLr, Direction::Rtl),
+            Corner::BottomRight
+        );
+        assert_eq!(
+            logical_corner_to_physical(true, true, WritingMode::SidewaysLr, Direction::Ltr),
+            Corner::BottomLeft
+        );
+    }
+}
@@ -7,7 +7,11 @@ impl ComputeValueWithContext for specified::ColorUnit {
 
     fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
         match self {
-            specified::ColorUnit::CurrentColor => context.parent_computed_values.color.rgba(),
+            specified::ColorUnit::CurrentColor => context
+                .parent_computed_values
+                .get_inherited_text()
+                .color
+                .rgba(),
             specified::ColorUnit::Numeric(rgba) => *rgba,
         }
     }
@@ -16,7 +20,7 @@ impl ComputeValueWithContext for specified::ColorUnit {
 /// Computed value for the `color` property.
 ///
 /// https://www.w3.org/TR/css-color-3/#foreground
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Color(RGBA);
 
 impl Color {
@@ -52,7 +56,7 @@ impl ComputeValueWithContext for specified::Color {
 
     fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
         match self {
-            specified::Color::Inherit => context.parent_computed_values.color,
+            specified::Color::Inherit => context.parent_computed_values.get_inherited_text().color,
             specified::Color::Unit(color_unit) => {
                 Color(color_unit.compute_value_with_context(context))
             }
@@ -64,6 +68,6 @@ impl ValueDefault for specified::Color {
     type ComputedValue = Color;
 
     fn value_default(context: &ComputeContext) -> Self::ComputedValue {
-        context.parent_computed_values.color
+        context.parent_computed_values.get_inherited_text().color
     }
 }
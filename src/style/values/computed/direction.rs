@@ -31,7 +31,7 @@ impl ValueDefault for Direction {
     type ComputedValue = Direction;
 
     fn value_default(context: &ComputeContext) -> Self::ComputedValue {
-        context.parent_computed_values.direction
+        context.parent_computed_values.get_inherited_box().direction
     }
 }
 
@@ -62,6 +62,18 @@ impl WritingMode {
             WritingMode::HorizontalTb => true,
         }
     }
+
+    /// Whether text is set in vertical typographic mode, with glyphs stacked along vertical lines
+    /// per `text-orientation`.  The `sideways-*` writing modes lay out lines vertically, but set
+    /// text in horizontal typographic mode, rotated as a whole.
+    ///
+    /// https://drafts.csswg.org/css-writing-modes-4/#typographic-mode
+    pub fn is_vertical_typographic_mode(&self) -> bool {
+        match self {
+            WritingMode::VerticalLr | WritingMode::VerticalRl => true,
+            WritingMode::HorizontalTb | WritingMode::SidewaysLr | WritingMode::SidewaysRl => false,
+        }
+    }
 }
 
 impl CssValueParse for WritingMode {
@@ -82,6 +94,98 @@ impl ValueDefault for WritingMode {
     type ComputedValue = WritingMode;
 
     fn value_default(context: &ComputeContext) -> Self::ComputedValue {
-        context.parent_computed_values.writing_mode
+        context
+            .parent_computed_values
+            .get_inherited_box()
+            .writing_mode
+    }
+}
+
+/// Computed `text-orientation` values, which determine the orientation of glyphs in vertical
+/// typographic mode.  They have no effect in horizontal typographic mode.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#text-orientation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TextOrientation {
+    /// Characters of horizontal-only scripts are set sideways, while those of vertical scripts
+    /// (such as CJK ideographs) are set upright.
+    Mixed,
+    /// All characters are set upright.
+    Upright,
+    /// All characters are set sideways, as if in a horizontal layout rotated a quarter turn
+    /// clockwise.
+    Sideways,
+}
+
+impl TextOrientation {
+    pub fn initial_value() -> TextOrientation {
+        TextOrientation::Mixed
+    }
+}
+
+impl CssValueParse for TextOrientation {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "mixed" => Ok(TextOrientation::Mixed),
+            "upright" => Ok(TextOrientation::Upright),
+            "sideways" => Ok(TextOrientation::Sideways),
+        }
+    }
+}
+
+impl ValueDefault for TextOrientation {
+    type ComputedValue = TextOrientation;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context
+            .parent_computed_values
+            .get_inherited_box()
+            .text_orientation
+    }
+}
+
+/// Computed `unicode-bidi` values, which determine how an element's text is embedded in, or
+/// isolated from, the bidirectional text surrounding it, and whether the directionality of its
+/// characters is overridden by `direction`.
+///
+/// https://drafts.csswg.org/css-writing-modes-4/#unicode-bidi
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnicodeBidi {
+    Normal,
+    Embed,
+    Isolate,
+    BidiOverride,
+    IsolateOverride,
+    Plaintext,
+}
+
+impl UnicodeBidi {
+    pub fn initial_value() -> UnicodeBidi {
+        UnicodeBidi::Normal
+    }
+}
+
+impl CssValueParse for UnicodeBidi {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "normal" => Ok(UnicodeBidi::Normal),
+            "embed" => Ok(UnicodeBidi::Embed),
+            "isolate" => Ok(UnicodeBidi::Isolate),
+            "bidi-override" => Ok(UnicodeBidi::BidiOverride),
+            "isolate-override" => Ok(UnicodeBidi::IsolateOverride),
+            "plaintext" => Ok(UnicodeBidi::Plaintext),
+        }
+    }
+}
+
+impl ValueDefault for UnicodeBidi {
+    type ComputedValue = UnicodeBidi;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        UnicodeBidi::initial_value()
     }
 }
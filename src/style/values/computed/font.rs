@@ -1,11 +1,14 @@
 use crate::style::values::computed::length::CSSPixelLength;
 use crate::style::values::computed::{
-    ComputeContext, ComputeValue, ComputeValueWithContext, ValueDefault,
+    ComputeContext, ComputeValue, ComputeValueWithContext, Percentage, ValueDefault,
 };
 use crate::style::values::specified;
 use crate::style::values::specified::font::KeywordSize;
 use crate::style::values::specified::{LengthPercentage, NoCalcLength};
+use crate::style::values::{CSSFloat, CssValueParse};
+use crate::style::StyleParseErrorKind;
 use app_units::Au;
+use cssparser::{ParseError, Parser};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 /// The computed value of font-size
@@ -37,7 +40,7 @@ impl ValueDefault for specified::FontSize {
     type ComputedValue = FontSize;
 
     fn value_default(context: &ComputeContext) -> Self::ComputedValue {
-        context.parent_computed_values.font_size
+        context.parent_computed_values.get_font().font_size
     }
 }
 
@@ -54,7 +57,7 @@ impl ComputeValueWithContext for specified::FontSize {
                 abs_len,
             ))) => (abs_len.compute_value(), None),
             specified::FontSize::Length(specified::LengthPercentage::Percentage(percentage)) => {
-                let parent_font = context.parent_computed_values.font_size;
+                let parent_font = context.parent_computed_values.get_font().font_size;
                 (
                     CSSPixelLength::from(Au::from(parent_font.size).scale_by(percentage.0)),
                     None,
@@ -91,3 +94,287 @@ impl ComputeValueWithContext for KeywordSize {
         .into()
     }
 }
+
+/// A generic font family, which the system maps to an installed font of that kind.
+///
+/// https://drafts.csswg.org/css-fonts-4/#generic-font-families
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GenericFontFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+    SystemUi,
+}
+
+/// One entry of a `font-family` list.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SingleFontFamily {
+    /// The name of a font family, such as "Times New Roman".
+    FamilyName(String),
+    Generic(GenericFontFamily),
+}
+
+impl CssValueParse for SingleFontFamily {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(name) = input.try_parse(|i| i.expect_string_cloned()) {
+            return Ok(SingleFontFamily::FamilyName(name.to_string()));
+        }
+        let first_ident = input.expect_ident_cloned()?;
+        let generic = match_ignore_ascii_case! { &first_ident,
+            "serif" => Some(GenericFontFamily::Serif),
+            "sans-serif" => Some(GenericFontFamily::SansSerif),
+            "monospace" => Some(GenericFontFamily::Monospace),
+            "cursive" => Some(GenericFontFamily::Cursive),
+            "fantasy" => Some(GenericFontFamily::Fantasy),
+            "system-ui" => Some(GenericFontFamily::SystemUi),
+            _ => None,
+        };
+        // Family names may be written as a sequence of unquoted identifiers, but a generic family
+        // keyword on its own always names the generic family.
+        let mut name = first_ident.to_string();
+        let mut ident_count = 1;
+        while let Ok(ident) = input.try_parse(|i| i.expect_ident_cloned()) {
+            name.push(' ');
+            name.push_str(&ident);
+            ident_count += 1;
+        }
+        match generic {
+            Some(generic) if ident_count == 1 => Ok(SingleFontFamily::Generic(generic)),
+            _ => Ok(SingleFontFamily::FamilyName(name)),
+        }
+    }
+}
+
+/// Computed (and specified) value of the `font-family` property, a prioritized list of families.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-family-prop
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FontFamily {
+    pub families: Vec<SingleFontFamily>,
+}
+
+impl FontFamily {
+    /// The initial value depends on the user agent.  Kosmonaut uses the system's sans-serif font.
+    pub fn initial_value() -> FontFamily {
+        FontFamily {
+            families: vec![SingleFontFamily::Generic(GenericFontFamily::SansSerif)],
+        }
+    }
+}
+
+impl CssValueParse for FontFamily {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let families = input.parse_comma_separated(|i| SingleFontFamily::parse(i))?;
+        Ok(FontFamily { families })
+    }
+}
+
+impl ValueDefault for FontFamily {
+    type ComputedValue = FontFamily;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context
+            .parent_computed_values
+            .get_font()
+            .font_family
+            .clone()
+    }
+}
+
+/// Computed value of the `font-weight` property, a number from 1 to 1000 where 400 is normal and
+/// 700 is bold.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-weight-prop
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct FontWeight(pub CSSFloat);
+
+impl FontWeight {
+    pub fn normal() -> FontWeight {
+        FontWeight(400.)
+    }
+
+    pub fn bold() -> FontWeight {
+        FontWeight(700.)
+    }
+
+    pub fn initial_value() -> FontWeight {
+        FontWeight::normal()
+    }
+
+    /// The weight `font-weight: bolder` computes to when the inherited weight is `self`.
+    ///
+    /// https://drafts.csswg.org/css-fonts-4/#relative-weights
+    pub fn bolder(self) -> FontWeight {
+        match self.0 {
+            weight if weight < 350. => FontWeight(400.),
+            weight if weight < 550. => FontWeight(700.),
+            weight if weight < 900. => FontWeight(900.),
+            weight => FontWeight(weight),
+        }
+    }
+
+    /// The weight `font-weight: lighter` computes to when the inherited weight is `self`.
+    pub fn lighter(self) -> FontWeight {
+        match self.0 {
+            weight if weight < 100. => FontWeight(weight),
+            weight if weight < 550. => FontWeight(100.),
+            weight if weight < 750. => FontWeight(400.),
+            _ => FontWeight(700.),
+        }
+    }
+}
+
+impl ValueDefault for specified::FontWeight {
+    type ComputedValue = FontWeight;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context.parent_computed_values.get_font().font_weight
+    }
+}
+
+impl ComputeValueWithContext for specified::FontWeight {
+    type ComputedValue = FontWeight;
+
+    fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
+        let parent_weight = context.parent_computed_values.get_font().font_weight;
+        match self {
+            specified::FontWeight::Absolute(weight) => FontWeight(*weight),
+            specified::FontWeight::Bolder => parent_weight.bolder(),
+            specified::FontWeight::Lighter => parent_weight.lighter(),
+        }
+    }
+}
+
+/// Computed (and specified) value of the `font-style` property.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-style-prop
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl FontStyle {
+    pub fn initial_value() -> FontStyle {
+        FontStyle::Normal
+    }
+}
+
+impl CssValueParse for FontStyle {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "normal" => Ok(FontStyle::Normal),
+            "italic" => Ok(FontStyle::Italic),
+            "oblique" => Ok(FontStyle::Oblique),
+        }
+    }
+}
+
+impl ValueDefault for FontStyle {
+    type ComputedValue = FontStyle;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context.parent_computed_values.get_font().font_style
+    }
+}
+
+/// Computed (and specified) value of the `font-stretch` property, the width of the font face as a
+/// percentage of its normal width.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-stretch-prop
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct FontStretch(pub Percentage);
+
+impl FontStretch {
+    pub fn initial_value() -> FontStretch {
+        FontStretch(Percentage::hundred())
+    }
+
+    /// Parses one of the keyword values of `font-stretch`, which are the only values accepted by
+    /// the `font` shorthand.
+    pub fn parse_keyword<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let ident = input.expect_ident_cloned()?;
+        let percentage = match_ignore_ascii_case! { &ident,
+            "ultra-condensed" => 0.5,
+            "extra-condensed" => 0.625,
+            "condensed" => 0.75,
+            "semi-condensed" => 0.875,
+            "normal" => 1.,
+            "semi-expanded" => 1.125,
+            "expanded" => 1.25,
+            "extra-expanded" => 1.5,
+            "ultra-expanded" => 2.,
+            _ => return Err(location.new_custom_error(
+                StyleParseErrorKind::OtherInvalidValue(ident.clone())
+            )),
+        };
+        Ok(FontStretch(Percentage(percentage)))
+    }
+}
+
+impl CssValueParse for FontStretch {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        if let Ok(percentage) = input.try_parse(|i| i.expect_percentage()) {
+            // Negative values are invalid.
+            return if percentage >= 0. {
+                Ok(FontStretch(Percentage(percentage)))
+            } else {
+                Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+            };
+        }
+        FontStretch::parse_keyword(input)
+    }
+}
+
+impl ValueDefault for FontStretch {
+    type ComputedValue = FontStretch;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context.parent_computed_values.get_font().font_stretch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cssparser::ParserInput;
+
+    #[test]
+    fn relative_weights() {
+        assert_eq!(FontWeight(300.).bolder(), FontWeight(400.));
+        assert_eq!(FontWeight::normal().bolder(), FontWeight::bold());
+        assert_eq!(FontWeight::bold().bolder(), FontWeight(900.));
+        assert_eq!(FontWeight::normal().lighter(), FontWeight(100.));
+        assert_eq!(FontWeight::bold().lighter(), FontWeight::normal());
+        assert_eq!(FontWeight(900.).lighter(), FontWeight::bold());
+    }
+
+    #[test]
+    fn generic_keywords_only_name_generic_families_on_their_own() {
+        let mut input = ParserInput::new("serif, \"serif\", Serif Pro");
+        let family = FontFamily::parse(&mut Parser::new(&mut input)).unwrap();
+        assert_eq!(
+            family.families,
+            vec![
+                SingleFontFamily::Generic(GenericFontFamily::Serif),
+                SingleFontFamily::FamilyName("serif".to_owned()),
+                SingleFontFamily::FamilyName("Serif Pro".to_owned()),
+            ]
+        );
+    }
+}
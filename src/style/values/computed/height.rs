@@ -5,7 +5,7 @@ use crate::style::values::computed::{
 use crate::style::values::specified;
 
 /// Computed value of a `height`.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Height {
     pub size: LengthPercentageOrAuto,
 }
@@ -0,0 +1,271 @@
+use crate::style::values::computed::angle::Angle;
+use crate::style::values::computed::length::{CSSPixelLength, LengthPercentage};
+use crate::style::values::computed::position::Position;
+use crate::style::values::computed::{ComputeContext, ComputeValue, ComputeValueWithContext};
+use crate::style::values::specified;
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser, RGBA};
+use url::Url;
+
+/// A computed `<image>`.
+///
+/// https://drafts.csswg.org/css-images-3/#image-values
+#[derive(Clone, Debug, PartialEq)]
+pub enum Image {
+    /// An image loaded from an absolute URL.
+    Url(Url),
+    Gradient(Box<Gradient>),
+}
+
+/// The computed value of a specified image is `None` when it's a URL that isn't valid, which is
+/// then treated like an image that failed to load.
+impl ComputeValueWithContext for specified::image::Image {
+    type ComputedValue = Option<Image>;
+
+    fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
+        match self {
+            // URLs have already been resolved against the base URL of their stylesheet, so any
+            // that are still relative can't be resolved.
+            specified::image::Image::Url(url) => Url::parse(url).ok().map(Image::Url),
+            specified::image::Image::Gradient(gradient) => Some(Image::Gradient(Box::new(
+                gradient.compute_value_with_context(context),
+            ))),
+        }
+    }
+}
+
+/// A computed `<gradient>`.
+///
+/// https://drafts.csswg.org/css-images-4/#gradients
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    /// The color stops and color hints, in order.  There are always at least two color stops, and
+    /// hints are always between two color stops.
+    pub items: Vec<GradientItem>,
+    /// Whether this is one of the `repeating-*-gradient()` functions, which repeat the color stops
+    /// in both directions past the first and the last.
+    pub repeating: bool,
+}
+
+impl ComputeValueWithContext for specified::image::Gradient {
+    type ComputedValue = Gradient;
+
+    fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
+        Gradient {
+            kind: match &self.kind {
+                specified::image::GradientKind::Linear(direction) => {
+                    GradientKind::Linear(*direction)
+                }
+                specified::image::GradientKind::Radial(shape, position) => {
+                    GradientKind::Radial(shape.compute_value(), position.compute_value())
+                }
+                specified::image::GradientKind::Conic(from_angle, position) => {
+                    GradientKind::Conic(*from_angle, position.compute_value())
+                }
+            },
+            items: self
+                .items
+                .iter()
+                .map(|item| match item {
+                    specified::image::GradientItem::ColorStop(color, position) => {
+                        GradientItem::ColorStop(
+                            color.compute_value_with_context(context),
+                            position.map(|position| position.compute_value()),
+                        )
+                    }
+                    specified::image::GradientItem::Hint(position) => {
+                        GradientItem::Hint(position.compute_value())
+                    }
+                })
+                .collect(),
+            repeating: self.repeating,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GradientKind {
+    /// `linear-gradient()`, whose colors change along a line in the given direction.
+    Linear(LineDirection),
+    /// `radial-gradient()`, whose colors change outwards from the given position to the edge of
+    /// the ending shape.
+    Radial(EndingShape, Position),
+    /// `conic-gradient()`, whose colors change clockwise around the given position, starting at
+    /// the given angle.
+    Conic(Angle, Position),
+}
+
+/// The direction of a linear gradient's gradient line.
+///
+/// https://drafts.csswg.org/css-images-4/#linear-gradient-syntax
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineDirection {
+    /// An angle, where `0deg` points up and positive angles turn clockwise.  The `to <side>` forms
+    /// are converted to angles, e.g. `to right` is `90deg`.
+    Angle(Angle),
+    /// Towards a corner of the gradient box, which is an angle that depends on the box's size.
+    Corner { to_right: bool, to_bottom: bool },
+}
+
+impl LineDirection {
+    /// The default direction, `to bottom`.
+    pub fn initial_value() -> LineDirection {
+        LineDirection::Angle(Angle::from_degrees(180.))
+    }
+}
+
+/// Parses `<angle> | to <side-or-corner>`.
+impl CssValueParse for LineDirection {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(angle) = input.try_parse(Angle::parse) {
+            return Ok(LineDirection::Angle(angle));
+        }
+        input.expect_ident_matching("to")?;
+        let mut horizontal = None;
+        let mut vertical = None;
+        for _ in 0..2 {
+            let side = input.try_parse(|i| {
+                try_match_ident_ignore_ascii_case! { i,
+                    "left" => Ok((Some(false), None)),
+                    "right" => Ok((Some(true), None)),
+                    "top" => Ok((None, Some(false))),
+                    "bottom" => Ok((None, Some(true))),
+                }
+            });
+            match side {
+                Ok((Some(to_right), None)) if horizontal.is_none() => horizontal = Some(to_right),
+                Ok((None, Some(to_bottom))) if vertical.is_none() => vertical = Some(to_bottom),
+                Ok(_) => return Err(input.new_custom_error(StyleParseErrorKind::UnspecifiedError)),
+                Err(err) if horizontal.is_none() && vertical.is_none() => return Err(err),
+                Err(_) => break,
+            }
+        }
+        Ok(match (horizontal, vertical) {
+            (Some(to_right), Some(to_bottom)) => LineDirection::Corner {
+                to_right,
+                to_bottom,
+            },
+            (Some(to_right), None) => {
+                LineDirection::Angle(Angle::from_degrees(if to_right { 90. } else { 270. }))
+            }
+            (None, Some(to_bottom)) => {
+                LineDirection::Angle(Angle::from_degrees(if to_bottom { 180. } else { 0. }))
+            }
+            (None, None) => unreachable!("at least one side is parsed"),
+        })
+    }
+}
+
+/// The ending shape of a radial gradient, which is where the last color stop is placed by
+/// default.
+///
+/// https://drafts.csswg.org/css-images-3/#radial-gradient-syntax
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndingShape {
+    Circle(Circle),
+    Ellipse(Ellipse),
+}
+
+impl EndingShape {
+    /// The default shape, `ellipse farthest-corner`.
+    pub fn initial_value() -> EndingShape {
+        EndingShape::Ellipse(Ellipse::Extent(ShapeExtent::FarthestCorner))
+    }
+}
+
+impl ComputeValue for specified::image::EndingShape {
+    type ComputedValue = EndingShape;
+
+    fn compute_value(&self) -> Self::ComputedValue {
+        match self {
+            specified::image::EndingShape::Circle(specified::image::Circle::Radius(radius)) => {
+                EndingShape::Circle(Circle::Radius(radius.compute_value()))
+            }
+            specified::image::EndingShape::Circle(specified::image::Circle::Extent(extent)) => {
+                EndingShape::Circle(Circle::Extent(*extent))
+            }
+            specified::image::EndingShape::Ellipse(specified::image::Ellipse::Radii(x, y)) => {
+                EndingShape::Ellipse(Ellipse::Radii(x.compute_value(), y.compute_value()))
+            }
+            specified::image::EndingShape::Ellipse(specified::image::Ellipse::Extent(extent)) => {
+                EndingShape::Ellipse(Ellipse::Extent(*extent))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Circle {
+    Radius(CSSPixelLength),
+    Extent(ShapeExtent),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ellipse {
+    /// The horizontal and vertical radii, in that order.  Percentages refer to the width and the
+    /// height of the gradient box respectively.
+    Radii(LengthPercentage, LengthPercentage),
+    Extent(ShapeExtent),
+}
+
+/// Sizes a radial gradient's ending shape relative to the sides or corners of the gradient box.
+///
+/// https://drafts.csswg.org/css-images-3/#typedef-extent-keyword
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShapeExtent {
+    ClosestSide,
+    FarthestSide,
+    ClosestCorner,
+    FarthestCorner,
+}
+
+impl CssValueParse for ShapeExtent {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "closest-side" => Ok(ShapeExtent::ClosestSide),
+            "farthest-side" => Ok(ShapeExtent::FarthestSide),
+            "closest-corner" => Ok(ShapeExtent::ClosestCorner),
+            "farthest-corner" => Ok(ShapeExtent::FarthestCorner),
+        }
+    }
+}
+
+/// A color stop or a color hint of a gradient.
+///
+/// https://drafts.csswg.org/css-images-4/#color-stop-syntax
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientItem {
+    /// A color, and the position along the gradient it's placed at, or `None` if it's placed
+    /// halfway between its neighbours.
+    ColorStop(RGBA, Option<StopPosition>),
+    /// The position of the midpoint of the transition between the color stops on either side.
+    Hint(StopPosition),
+}
+
+/// The position of a color stop or hint along a gradient.  Linear and radial gradients position
+/// them with lengths along the gradient line or ray, and conic gradients with angles around the
+/// gradient's center.  Percentages are valid in both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopPosition {
+    LengthPercentage(LengthPercentage),
+    Angle(Angle),
+}
+
+impl ComputeValue for specified::image::StopPosition {
+    type ComputedValue = StopPosition;
+
+    fn compute_value(&self) -> Self::ComputedValue {
+        match self {
+            specified::image::StopPosition::LengthPercentage(lp) => {
+                StopPosition::LengthPercentage(lp.compute_value())
+            }
+            specified::image::StopPosition::Angle(angle) => StopPosition::Angle(*angle),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::style::values::computed::length::CSSPixelLength;
+use crate::style::values::computed::{
+    ComputeContext, ComputeValue, ComputeValueWithContext, ValueDefault,
+};
+use crate::style::values::{specified, CSSFloat};
+
+/// Computed value of `line-height`.
+///
+/// https://drafts.csswg.org/css-inline-3/#line-height-property
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineHeight {
+    Normal,
+    /// A multiple of the font size.  This stays a number so that descendants with a different
+    /// font size scale the line height with their own font size.
+    Number(CSSFloat),
+    /// An absolute line height.  Percentages compute to a length relative to the element's own
+    /// font size.
+    Length(CSSPixelLength),
+}
+
+impl LineHeight {
+    pub fn initial_value() -> LineHeight {
+        LineHeight::Normal
+    }
+}
+
+impl ComputeValueWithContext for specified::LineHeight {
+    type ComputedValue = LineHeight;
+
+    fn compute_value_with_context(&self, context: &ComputeContext) -> Self::ComputedValue {
+        match self {
+            specified::LineHeight::Normal => LineHeight::Normal,
+            specified::LineHeight::Number(number) => LineHeight::Number(*number),
+            specified::LineHeight::LengthPercentage(specified::LengthPercentage::Length(
+                no_calc_length,
+            )) => LineHeight::Length(no_calc_length.compute_value()),
+            specified::LineHeight::LengthPercentage(specified::LengthPercentage::Percentage(
+                percentage,
+            )) => LineHeight::Length(percentage.px_relative_to(context.font_size().size)),
+        }
+    }
+}
+
+impl ValueDefault for specified::LineHeight {
+    type ComputedValue = LineHeight;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context
+            .parent_computed_values
+            .get_inherited_text()
+            .line_height
+    }
+}
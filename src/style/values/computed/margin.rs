@@ -7,7 +7,7 @@ use crate::style::values::specified;
 /// Computed value of a `margin-<side>`.
 ///
 /// https://www.w3.org/TR/css-box-3/#margin-physical
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Margin {
     pub size: LengthPercentageOrAuto,
 }
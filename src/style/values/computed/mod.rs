@@ -1,5 +1,6 @@
 /// Some of this code was taken from Servo: https://github.com/servo/servo
 /// Kosmonaut complies with Servo's license, the Mozilla Public License 2.0.
+pub mod angle;
 pub mod background;
 pub mod border;
 pub mod color;
@@ -7,10 +8,17 @@ pub mod direction;
 pub mod display;
 pub mod font;
 pub mod height;
+pub mod image;
 pub mod length;
+pub mod line_height;
 pub mod margin;
+pub mod overflow;
 pub mod padding;
 pub mod percentage;
+pub mod position;
+pub mod style_structs;
+pub mod vertical_align;
+pub mod white_space;
 pub mod width;
 
 use crate::style::values::computed::height::Height;
@@ -18,27 +26,47 @@ pub use crate::style::values::computed::margin::Margin;
 pub use crate::style::values::computed::padding::Padding;
 use crate::style::values::computed::width::Width;
 
+use std::sync::Arc;
+
 use crate::dom::tree::NodeRef;
+use crate::style::custom_properties::{compute_custom_properties, CustomPropertiesMap};
 use crate::style::properties::id::LonghandId;
-use crate::style::properties::PropertyDeclaration;
+use crate::style::properties::{
+    ContextualPropertyDeclaration, PropertyDeclaration, WideKeywordDeclaration,
+};
+use crate::style::sharing::StyleSharingCache;
 use crate::style::values::specified;
+use crate::style::values::specified::width::ContentSizeKeyword;
+use crate::style::CssWideKeywords;
 
 use crate::layout::flow::FlowSide;
+pub use crate::style::values::computed::direction::TextOrientation;
+pub use crate::style::values::computed::direction::UnicodeBidi;
 pub use crate::style::values::computed::direction::WritingMode;
 use crate::style::values::computed::length::{
     CSSPixelLength, LengthPercentage, LengthPercentageOrAuto,
 };
-use crate::Side;
-pub use background::BackgroundColor;
+use crate::{Corner, Side};
+pub use background::{
+    BackgroundAttachment, BackgroundClip, BackgroundColor, BackgroundImage, BackgroundOrigin,
+    BackgroundPositionX, BackgroundPositionY, BackgroundRepeat, BackgroundSize,
+};
+use border::logical_corner_to_physical;
 pub use border::LineStyle;
-pub use border::{border_side_initial_style, BorderColor, BorderWidth};
+pub use border::{border_side_initial_style, BorderColor, BorderCornerRadius, BorderWidth};
 pub use color::Color;
 use cssparser::RGBA;
 pub use direction::Direction;
 pub use display::Display;
-pub use font::FontSize;
+pub use font::{
+    FontFamily, FontSize, FontStretch, FontStyle, FontWeight, GenericFontFamily, SingleFontFamily,
+};
+pub use line_height::LineHeight;
+pub use overflow::Overflow;
 pub use percentage::Percentage;
 use strum::IntoEnumIterator;
+pub use vertical_align::VerticalAlign;
+pub use white_space::WhiteSpace;
 
 /// A trait to represent the conversion between computed and specified values where a context is
 /// required to properly compute the specified value.
@@ -76,12 +104,18 @@ pub trait ValueDefault {
     fn value_default(context: &ComputeContext) -> Self::ComputedValue;
 }
 
-/// A finalized set of computed values.
-///
-/// https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#computed-value
+/// The computed value of every longhand of a node, before they're grouped into `ComputedValues`.
 #[derive(Debug, Clone, Builder)]
-pub struct ComputedValues {
+pub struct ComputedLonghands {
+    pub background_attachment: BackgroundAttachment,
+    pub background_clip: BackgroundClip,
     pub background_color: BackgroundColor,
+    pub background_image: BackgroundImage,
+    pub background_origin: BackgroundOrigin,
+    pub background_position_x: BackgroundPositionX,
+    pub background_position_y: BackgroundPositionY,
+    pub background_repeat: BackgroundRepeat,
+    pub background_size: BackgroundSize,
     pub border_bottom_color: BorderColor,
     pub border_left_color: BorderColor,
     pub border_right_color: BorderColor,
@@ -94,55 +128,292 @@ pub struct ComputedValues {
     pub border_left_width: BorderWidth,
     pub border_right_width: BorderWidth,
     pub border_top_width: BorderWidth,
+    pub border_bottom_left_radius: BorderCornerRadius,
+    pub border_bottom_right_radius: BorderCornerRadius,
+    pub border_top_left_radius: BorderCornerRadius,
+    pub border_top_right_radius: BorderCornerRadius,
     pub color: Color,
     pub direction: Direction,
     pub display: Display,
+    pub font_family: FontFamily,
     pub font_size: FontSize,
+    pub font_stretch: FontStretch,
+    pub font_style: FontStyle,
+    pub font_weight: FontWeight,
     pub height: Height,
+    pub line_height: LineHeight,
     pub margin_bottom: Margin,
     pub margin_left: Margin,
     pub margin_right: Margin,
     pub margin_top: Margin,
+    pub overflow_x: Overflow,
+    pub overflow_y: Overflow,
     pub padding_bottom: Padding,
     pub padding_left: Padding,
     pub padding_right: Padding,
     pub padding_top: Padding,
+    pub text_orientation: TextOrientation,
+    pub unicode_bidi: UnicodeBidi,
+    pub vertical_align: VerticalAlign,
+    pub white_space: WhiteSpace,
     pub width: Width,
     pub writing_mode: WritingMode,
 }
 
+/// A finalized set of computed values, split into groups of related properties that can be shared
+/// between nodes.
+///
+/// https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#computed-value
+#[derive(Debug, Clone)]
+pub struct ComputedValues {
+    background: Arc<style_structs::Background>,
+    border: Arc<style_structs::Border>,
+    box_: Arc<style_structs::Box>,
+    font: Arc<style_structs::Font>,
+    inherited_box: Arc<style_structs::InheritedBox>,
+    inherited_text: Arc<style_structs::InheritedText>,
+    margin: Arc<style_structs::Margin>,
+    padding: Arc<style_structs::Padding>,
+    position: Arc<style_structs::Position>,
+    /// The custom properties of the element, which are always inherited.
+    pub custom_properties: Arc<CustomPropertiesMap>,
+}
+
+thread_local! {
+    static INITIAL_VALUES: Arc<ComputedValues> = Arc::new(ComputedValues::from_longhands(
+        ComputedLonghands::default(),
+        Arc::new(CustomPropertiesMap::new()),
+        &[],
+    ));
+}
+
 impl ComputedValues {
+    /// The computed values of a node with no parent and no declarations, where every property has
+    /// its initial value.
+    pub fn initial() -> Arc<ComputedValues> {
+        INITIAL_VALUES.with(Arc::clone)
+    }
+
+    /// Groups `longhands` into computed values.  Groups equal to those of any of `share_with` are
+    /// shared with them rather than allocated anew.
+    fn from_longhands(
+        longhands: ComputedLonghands,
+        custom_properties: Arc<CustomPropertiesMap>,
+        share_with: &[&ComputedValues],
+    ) -> ComputedValues {
+        fn share<T: PartialEq>(
+            group: T,
+            share_with: &[&ComputedValues],
+            get_group: impl Fn(&ComputedValues) -> &Arc<T>,
+        ) -> Arc<T> {
+            share_with
+                .iter()
+                .map(|computed_values| get_group(*computed_values))
+                .find(|shared| ***shared == group)
+                .map(Arc::clone)
+                .unwrap_or_else(|| Arc::new(group))
+        }
+
+        ComputedValues {
+            background: share(
+                style_structs::Background {
+                    background_attachment: longhands.background_attachment,
+                    background_clip: longhands.background_clip,
+                    background_color: longhands.background_color,
+                    background_image: longhands.background_image,
+                    background_origin: longhands.background_origin,
+                    background_position_x: longhands.background_position_x,
+                    background_position_y: longhands.background_position_y,
+                    background_repeat: longhands.background_repeat,
+                    background_size: longhands.background_size,
+                },
+                share_with,
+                |cv| &cv.background,
+            ),
+            border: share(
+                style_structs::Border {
+                    border_bottom_color: longhands.border_bottom_color,
+                    border_left_color: longhands.border_left_color,
+                    border_right_color: longhands.border_right_color,
+                    border_top_color: longhands.border_top_color,
+                    border_bottom_style: longhands.border_bottom_style,
+                    border_left_style: longhands.border_left_style,
+                    border_right_style: longhands.border_right_style,
+                    border_top_style: longhands.border_top_style,
+                    border_bottom_width: longhands.border_bottom_width,
+                    border_left_width: longhands.border_left_width,
+                    border_right_width: longhands.border_right_width,
+                    border_top_width: longhands.border_top_width,
+                    border_bottom_left_radius: longhands.border_bottom_left_radius,
+                    border_bottom_right_radius: longhands.border_bottom_right_radius,
+                    border_top_left_radius: longhands.border_top_left_radius,
+                    border_top_right_radius: longhands.border_top_right_radius,
+                },
+                share_with,
+                |cv| &cv.border,
+            ),
+            box_: share(
+                style_structs::Box {
+                    display: longhands.display,
+                    overflow_x: longhands.overflow_x,
+                    overflow_y: longhands.overflow_y,
+                    unicode_bidi: longhands.unicode_bidi,
+                    vertical_align: longhands.vertical_align,
+                },
+                share_with,
+                |cv| &cv.box_,
+            ),
+            font: share(
+                style_structs::Font {
+                    font_family: longhands.font_family,
+                    font_size: longhands.font_size,
+                    font_stretch: longhands.font_stretch,
+                    font_style: longhands.font_style,
+                    font_weight: longhands.font_weight,
+                },
+                share_with,
+                |cv| &cv.font,
+            ),
+            inherited_box: share(
+                style_structs::InheritedBox {
+                    direction: longhands.direction,
+                    text_orientation: longhands.text_orientation,
+                    writing_mode: longhands.writing_mode,
+                },
+                share_with,
+                |cv| &cv.inherited_box,
+            ),
+            inherited_text: share(
+                style_structs::InheritedText {
+                    color: longhands.color,
+                    line_height: longhands.line_height,
+                    white_space: longhands.white_space,
+                },
+                share_with,
+                |cv| &cv.inherited_text,
+            ),
+            margin: share(
+                style_structs::Margin {
+                    margin_bottom: longhands.margin_bottom,
+                    margin_left: longhands.margin_left,
+                    margin_right: longhands.margin_right,
+                    margin_top: longhands.margin_top,
+                },
+                share_with,
+                |cv| &cv.margin,
+            ),
+            padding: share(
+                style_structs::Padding {
+                    padding_bottom: longhands.padding_bottom,
+                    padding_left: longhands.padding_left,
+                    padding_right: longhands.padding_right,
+                    padding_top: longhands.padding_top,
+                },
+                share_with,
+                |cv| &cv.padding,
+            ),
+            position: share(
+                style_structs::Position {
+                    height: longhands.height,
+                    width: longhands.width,
+                },
+                share_with,
+                |cv| &cv.position,
+            ),
+            custom_properties,
+        }
+    }
+
+    pub fn get_background(&self) -> &style_structs::Background {
+        &self.background
+    }
+
+    pub fn get_border(&self) -> &style_structs::Border {
+        &self.border
+    }
+
+    pub fn get_box(&self) -> &style_structs::Box {
+        &self.box_
+    }
+
+    pub fn get_font(&self) -> &style_structs::Font {
+        &self.font
+    }
+
+    pub fn get_inherited_box(&self) -> &style_structs::InheritedBox {
+        &self.inherited_box
+    }
+
+    pub fn get_inherited_text(&self) -> &style_structs::InheritedText {
+        &self.inherited_text
+    }
+
+    pub fn get_margin(&self) -> &style_structs::Margin {
+        &self.margin
+    }
+
+    pub fn get_padding(&self) -> &style_structs::Padding {
+        &self.padding
+    }
+
+    pub fn get_position(&self) -> &style_structs::Position {
+        &self.position
+    }
+
     pub fn border_style(&self, side: Side) -> LineStyle {
         match side {
-            Side::Bottom => self.border_bottom_style,
-            Side::Left => self.border_left_style,
-            Side::Right => self.border_right_style,
-            Side::Top => self.border_top_style,
+            Side::Bottom => self.border.border_bottom_style,
+            Side::Left => self.border.border_left_style,
+            Side::Right => self.border.border_right_style,
+            Side::Top => self.border.border_top_style,
         }
     }
 
     pub fn border_color_rgba(&self, side: Side) -> RGBA {
         match side {
-            Side::Bottom => self.border_bottom_color.rgba,
-            Side::Left => self.border_left_color.rgba,
-            Side::Right => self.border_right_color.rgba,
-            Side::Top => self.border_top_color.rgba,
+            Side::Bottom => self.border.border_bottom_color.rgba,
+            Side::Left => self.border.border_left_color.rgba,
+            Side::Right => self.border.border_right_color.rgba,
+            Side::Top => self.border.border_top_color.rgba,
+        }
+    }
+
+    pub fn border_corner_radius(&self, corner: Corner) -> BorderCornerRadius {
+        match corner {
+            Corner::BottomLeft => self.border.border_bottom_left_radius,
+            Corner::BottomRight => self.border.border_bottom_right_radius,
+            Corner::TopLeft => self.border.border_top_left_radius,
+            Corner::TopRight => self.border.border_top_right_radius,
         }
     }
 
     pub fn inline_size(&self, writing_mode: WritingMode) -> LengthPercentageOrAuto {
         if writing_mode.is_horizontal() {
-            self.width.size
+            self.position.width.size
         } else {
-            self.height.size
+            self.position.height.size
+        }
+    }
+
+    /// Returns the content-based sizing keyword (e.g. `min-content`) specified for the inline size,
+    /// if any.  Only `width` accepts these keywords today, so this is always `None` in vertical
+    /// writing modes.
+    pub fn inline_size_content_keyword(
+        &self,
+        writing_mode: WritingMode,
+    ) -> Option<ContentSizeKeyword> {
+        if writing_mode.is_horizontal() {
+            self.position.width.content_size
+        } else {
+            None
         }
     }
 
     pub fn block_size(&self, writing_mode: WritingMode) -> LengthPercentageOrAuto {
         if writing_mode.is_horizontal() {
-            self.height.size
+            self.position.height.size
         } else {
-            self.width.size
+            self.position.width.size
         }
     }
 
@@ -155,30 +426,30 @@ impl ComputedValues {
         match side {
             FlowSide::BlockEnd => {
                 if is_horizontal_mode {
-                    self.padding_bottom.size
+                    self.padding.padding_bottom.size
                 } else {
-                    self.padding_right.size
+                    self.padding.padding_right.size
                 }
             }
             FlowSide::InlineStart => {
                 if is_horizontal_mode {
-                    self.padding_left.size
+                    self.padding.padding_left.size
                 } else {
-                    self.padding_top.size
+                    self.padding.padding_top.size
                 }
             }
             FlowSide::InlineEnd => {
                 if is_horizontal_mode {
-                    self.padding_right.size
+                    self.padding.padding_right.size
                 } else {
-                    self.padding_bottom.size
+                    self.padding.padding_bottom.size
                 }
             }
             FlowSide::BlockStart => {
                 if is_horizontal_mode {
-                    self.padding_top.size
+                    self.padding.padding_top.size
                 } else {
-                    self.padding_left.size
+                    self.padding.padding_left.size
                 }
             }
         }
@@ -193,30 +464,30 @@ impl ComputedValues {
         match side {
             FlowSide::BlockEnd => {
                 if is_horizontal_mode {
-                    self.border_bottom_width.size
+                    self.border.border_bottom_width.size
                 } else {
-                    self.border_right_width.size
+                    self.border.border_right_width.size
                 }
             }
             FlowSide::InlineStart => {
                 if is_horizontal_mode {
-                    self.border_left_width.size
+                    self.border.border_left_width.size
                 } else {
-                    self.border_top_width.size
+                    self.border.border_top_width.size
                 }
             }
             FlowSide::InlineEnd => {
                 if is_horizontal_mode {
-                    self.border_right_width.size
+                    self.border.border_right_width.size
                 } else {
-                    self.border_bottom_width.size
+                    self.border.border_bottom_width.size
                 }
             }
             FlowSide::BlockStart => {
                 if is_horizontal_mode {
-                    self.border_top_width.size
+                    self.border.border_top_width.size
                 } else {
-                    self.border_left_width.size
+                    self.border.border_left_width.size
                 }
             }
         }
@@ -231,46 +502,53 @@ impl ComputedValues {
         match side {
             FlowSide::BlockEnd => {
                 if is_horizontal_mode {
-                    self.margin_bottom.size
+                    self.margin.margin_bottom.size
                 } else {
-                    self.margin_right.size
+                    self.margin.margin_right.size
                 }
             }
             FlowSide::InlineStart => {
                 if is_horizontal_mode {
-                    self.margin_left.size
+                    self.margin.margin_left.size
                 } else {
-                    self.margin_top.size
+                    self.margin.margin_top.size
                 }
             }
             FlowSide::InlineEnd => {
                 if is_horizontal_mode {
-                    self.margin_right.size
+                    self.margin.margin_right.size
                 } else {
-                    self.margin_bottom.size
+                    self.margin.margin_bottom.size
                 }
             }
             FlowSide::BlockStart => {
                 if is_horizontal_mode {
-                    self.margin_top.size
+                    self.margin.margin_top.size
                 } else {
-                    self.margin_left.size
+                    self.margin.margin_left.size
                 }
             }
         }
     }
 }
 
-/// Create a default set of computed values.  Likely most useful for the case in which we're working
-/// with the root node of a DOM, which has no parent to inherit from.
-impl Default for ComputedValues {
+/// Every longhand at its initial value.
+impl Default for ComputedLonghands {
     // TODO: We might eventually need to not use the `Default` trait here in case we need `ComputeContext`
     // to calculate the default computed values.
     fn default() -> Self {
         let initial_color_prop = Color::initial_value();
         let initial_border_style = border_side_initial_style();
-        ComputedValues {
+        ComputedLonghands {
+            background_attachment: BackgroundAttachment::initial_value(),
+            background_clip: BackgroundClip::initial_value(),
             background_color: BackgroundColor::initial_value(initial_color_prop.rgba()),
+            background_image: BackgroundImage::initial_value(),
+            background_origin: BackgroundOrigin::initial_value(),
+            background_position_x: BackgroundPositionX::initial_value(),
+            background_position_y: BackgroundPositionY::initial_value(),
+            background_repeat: BackgroundRepeat::initial_value(),
+            background_size: BackgroundSize::initial_value(),
             border_bottom_color: BorderColor::initial_value(initial_color_prop.rgba()),
             border_left_color: BorderColor::initial_value(initial_color_prop.rgba()),
             border_right_color: BorderColor::initial_value(initial_color_prop.rgba()),
@@ -283,31 +561,54 @@ impl Default for ComputedValues {
             border_left_width: BorderWidth::initial_value(initial_border_style),
             border_right_width: BorderWidth::initial_value(initial_border_style),
             border_top_width: BorderWidth::initial_value(initial_border_style),
+            border_bottom_left_radius: BorderCornerRadius::initial_value(),
+            border_bottom_right_radius: BorderCornerRadius::initial_value(),
+            border_top_left_radius: BorderCornerRadius::initial_value(),
+            border_top_right_radius: BorderCornerRadius::initial_value(),
             color: initial_color_prop,
             direction: Direction::initial_value(),
             display: Display::initial_value(),
+            font_family: FontFamily::initial_value(),
             font_size: FontSize::initial_value(),
+            font_stretch: FontStretch::initial_value(),
+            font_style: FontStyle::initial_value(),
+            font_weight: FontWeight::initial_value(),
             height: Height::initial_value(),
+            line_height: LineHeight::initial_value(),
             margin_bottom: Margin::initial_value(),
             margin_left: Margin::initial_value(),
             margin_right: Margin::initial_value(),
             margin_top: Margin::initial_value(),
+            overflow_x: Overflow::initial_value(),
+            overflow_y: Overflow::initial_value(),
             padding_bottom: Padding::initial_value(),
             padding_left: Padding::initial_value(),
             padding_right: Padding::initial_value(),
             padding_top: Padding::initial_value(),
+            text_orientation: TextOrientation::initial_value(),
+            unicode_bidi: UnicodeBidi::initial_value(),
+            vertical_align: VerticalAlign::initial_value(),
+            white_space: WhiteSpace::initial_value(),
             width: Width::initial_value(),
             writing_mode: WritingMode::initial_value(),
         }
     }
 }
 
+/// Create a default set of computed values.  Likely most useful for the case in which we're working
+/// with the root node of a DOM, which has no parent to inherit from.
+impl Default for ComputedValues {
+    fn default() -> Self {
+        (*ComputedValues::initial()).clone()
+    }
+}
+
 /// A `ComputeContext` is all the data a specified value could ever need to compute
 /// itself and be transformed to a computed value.
 pub struct ComputeContext<'a> {
     // TODO: Viewport dimensions will be needed
     /// The computed values of the parent for cases where inheritance is necessary.  If the current
-    /// node has no parent (it is the root node), this is `ComputedValues::default()`.
+    /// node has no parent (it is the root node), this is `ComputedValues::initial()`.
     pub parent_computed_values: &'a ComputedValues,
 
     /// The computed value of the `color` property for the node being computed.  Some properties,
@@ -322,6 +623,12 @@ pub struct ComputeContext<'a> {
     ///
     /// `None` if these values haven't been computed yet.
     pub computed_border_styles: Option<BorderSideStyleContext>,
+
+    /// The computed value of the `font-size` property for the node being computed.  Percentages
+    /// of some properties, such as `line-height`, refer to this value.
+    ///
+    /// `None` if `font-size` has not been computed yet.
+    pub computed_font_size: Option<FontSize>,
 }
 
 impl ComputeContext<'_> {
@@ -330,6 +637,11 @@ impl ComputeContext<'_> {
             .expect("color property not yet computed and applied to compute context")
     }
 
+    pub fn font_size(&self) -> FontSize {
+        self.computed_font_size
+            .expect("font-size property not yet computed and applied to compute context")
+    }
+
     pub fn border_bottom_style(&self) -> LineStyle {
         self.border_styles().bottom
     }
@@ -358,31 +670,80 @@ pub struct BorderSideStyleContext {
     pub top: LineStyle,
 }
 
-pub fn compute_values(node: NodeRef) {
-    let mut cv_builder = ComputedValuesBuilder::default();
-    let parent = node.parent();
+/// Computes the values of `node`, reusing those of a node in `sharing_cache` that has the same
+/// parent style and matched rules, if there is one.
+pub fn compute_values(node: NodeRef, sharing_cache: &mut StyleSharingCache) {
     // If this is the root node (aka there is no parent to inherit properties from), just default all properties to
     // their initial values.
-    let parent_computed_values = parent.map_or(ComputedValues::default(), |p| {
-        // TODO: This _could_ be an expensive clone when we actually support all CSS properties.
-        p.computed_values().clone()
-    });
+    let parent_computed_values = node
+        .parent()
+        .map_or_else(ComputedValues::initial, |p| p.shared_computed_values());
+    if let Some(shared) = sharing_cache.lookup(&node, &parent_computed_values) {
+        node.set_computed_values(shared);
+        return;
+    }
+    let mut cv_builder = ComputedLonghandsBuilder::default();
     let mut context = ComputeContext {
         parent_computed_values: &parent_computed_values,
         computed_color: None,
         computed_border_styles: None,
+        computed_font_size: None,
     };
-    compute_early_properties(node.clone(), &mut context);
+    let custom_properties = cascade_custom_properties(&node, &parent_computed_values);
+    compute_early_properties(node.clone(), &custom_properties, &mut context);
+    let (writing_mode, direction) =
+        compute_flow_early(&node, &parent_computed_values, &custom_properties);
 
     LonghandId::iter().for_each(|longhand: LonghandId| {
-        match node.contextual_decls().get_by_longhand(longhand) {
-            Some(contextual_decl) => {
-                match &contextual_decl.inner_decl {
+        if longhand.is_logical() {
+            // Cascaded along with the physical longhand it maps to.
+            return;
+        }
+        let cascaded = match logical_equivalent(longhand, writing_mode, direction) {
+            Some(logical) => {
+                cascaded_declaration_with_logical(&node, longhand, logical, &custom_properties)
+            }
+            None => cascaded_declaration(&node, longhand, &custom_properties),
+        };
+        match cascaded {
+            Some(declaration) => {
+                match &declaration {
+                    PropertyDeclaration::BackgroundAttachment(background_attachment) => {
+                        cv_builder.background_attachment(background_attachment.clone());
+                    }
+                    PropertyDeclaration::BackgroundClip(background_clip) => {
+                        cv_builder.background_clip(background_clip.clone());
+                    }
                     PropertyDeclaration::BackgroundColor(background_color) => {
                         cv_builder.background_color(
                             background_color.compute_value_with_context(&context),
                         );
                     }
+                    PropertyDeclaration::BackgroundImage(background_image) => {
+                        cv_builder.background_image(
+                            background_image.compute_value_with_context(&context),
+                        );
+                    }
+                    PropertyDeclaration::BackgroundOrigin(background_origin) => {
+                        cv_builder.background_origin(background_origin.clone());
+                    }
+                    PropertyDeclaration::BackgroundPositionX(background_position_x) => {
+                        cv_builder.background_position_x(
+                            background_position_x.compute_value_with_context(&context),
+                        );
+                    }
+                    PropertyDeclaration::BackgroundPositionY(background_position_y) => {
+                        cv_builder.background_position_y(
+                            background_position_y.compute_value_with_context(&context),
+                        );
+                    }
+                    PropertyDeclaration::BackgroundRepeat(background_repeat) => {
+                        cv_builder.background_repeat(background_repeat.clone());
+                    }
+                    PropertyDeclaration::BackgroundSize(background_size) => {
+                        cv_builder
+                            .background_size(background_size.compute_value_with_context(&context));
+                    }
                     PropertyDeclaration::BorderBottomColor(border_bottom_color) => {
                         cv_builder.border_bottom_color(
                             border_bottom_color.compute_value_with_context(&context),
@@ -435,6 +796,33 @@ pub fn compute_values(node: NodeRef) {
                             border_top_width.compute_value_with_context(&context),
                         );
                     }
+                    PropertyDeclaration::BorderBottomLeftRadius(radius)
+                    | PropertyDeclaration::BorderBottomRightRadius(radius)
+                    | PropertyDeclaration::BorderEndEndRadius(radius)
+                    | PropertyDeclaration::BorderEndStartRadius(radius)
+                    | PropertyDeclaration::BorderStartEndRadius(radius)
+                    | PropertyDeclaration::BorderStartStartRadius(radius)
+                    | PropertyDeclaration::BorderTopLeftRadius(radius)
+                    | PropertyDeclaration::BorderTopRightRadius(radius) => {
+                        // The winning declaration may be for the logical corner that maps to the
+                        // physical `longhand`.
+                        let radius = radius.compute_value_with_context(&context);
+                        match longhand {
+                            LonghandId::BorderBottomLeftRadius => {
+                                cv_builder.border_bottom_left_radius(radius)
+                            }
+                            LonghandId::BorderBottomRightRadius => {
+                                cv_builder.border_bottom_right_radius(radius)
+                            }
+                            LonghandId::BorderTopLeftRadius => {
+                                cv_builder.border_top_left_radius(radius)
+                            }
+                            LonghandId::BorderTopRightRadius => {
+                                cv_builder.border_top_right_radius(radius)
+                            }
+                            _ => unreachable!("corner radii are cascaded for physical corners"),
+                        };
+                    }
                     PropertyDeclaration::Color(_) => {
                         cv_builder.color(context.color());
                     }
@@ -450,8 +838,23 @@ pub fn compute_values(node: NodeRef) {
                     PropertyDeclaration::Height(height) => {
                         cv_builder.height(height.compute_value_with_context(&context));
                     }
-                    PropertyDeclaration::FontSize(font_size) => {
-                        cv_builder.font_size(font_size.compute_value_with_context(&context));
+                    PropertyDeclaration::FontFamily(font_family) => {
+                        cv_builder.font_family(font_family.clone());
+                    }
+                    PropertyDeclaration::FontSize(_) => {
+                        cv_builder.font_size(context.font_size());
+                    }
+                    PropertyDeclaration::FontStretch(font_stretch) => {
+                        cv_builder.font_stretch(*font_stretch);
+                    }
+                    PropertyDeclaration::FontStyle(font_style) => {
+                        cv_builder.font_style(*font_style);
+                    }
+                    PropertyDeclaration::FontWeight(font_weight) => {
+                        cv_builder.font_weight(font_weight.compute_value_with_context(&context));
+                    }
+                    PropertyDeclaration::LineHeight(line_height) => {
+                        cv_builder.line_height(line_height.compute_value_with_context(&context));
                     }
                     PropertyDeclaration::MarginBottom(margin_bottom) => {
                         cv_builder
@@ -466,6 +869,12 @@ pub fn compute_values(node: NodeRef) {
                     PropertyDeclaration::MarginTop(margin_top) => {
                         cv_builder.margin_top(margin_top.compute_value_with_context(&context));
                     }
+                    PropertyDeclaration::OverflowX(overflow_x) => {
+                        cv_builder.overflow_x(*overflow_x);
+                    }
+                    PropertyDeclaration::OverflowY(overflow_y) => {
+                        cv_builder.overflow_y(*overflow_y);
+                    }
                     PropertyDeclaration::PaddingBottom(padding_bottom) => {
                         cv_builder
                             .padding_bottom(padding_bottom.compute_value_with_context(&context));
@@ -480,12 +889,35 @@ pub fn compute_values(node: NodeRef) {
                     PropertyDeclaration::PaddingTop(padding_top) => {
                         cv_builder.padding_top(padding_top.compute_value_with_context(&context));
                     }
+                    PropertyDeclaration::TextOrientation(text_orientation) => {
+                        cv_builder.text_orientation(*text_orientation);
+                    }
+                    PropertyDeclaration::UnicodeBidi(unicode_bidi) => {
+                        cv_builder.unicode_bidi(*unicode_bidi);
+                    }
+                    PropertyDeclaration::VerticalAlign(vertical_align) => {
+                        cv_builder
+                            .vertical_align(vertical_align.compute_value_with_context(&context));
+                    }
+                    PropertyDeclaration::WhiteSpace(white_space) => {
+                        cv_builder.white_space(*white_space);
+                    }
                     PropertyDeclaration::Width(width) => {
                         cv_builder.width(width.compute_value_with_context(&context));
                     }
                     PropertyDeclaration::WritingMode(writing_mode) => {
                         cv_builder.writing_mode(*writing_mode);
                     }
+                    PropertyDeclaration::CssWideKeyword(declaration) => match declaration.keyword {
+                        CssWideKeywords::Initial => longhand.initial(&mut cv_builder, &context),
+                        CssWideKeywords::Inherit => longhand.inherit(&mut cv_builder, &context),
+                        CssWideKeywords::Unset | CssWideKeywords::Revert => {
+                            unreachable!("unset and revert are resolved by the cascade")
+                        }
+                    },
+                    PropertyDeclaration::WithVariables(_) | PropertyDeclaration::Custom(_) => {
+                        unreachable!("cascaded declarations are always longhands with no variables")
+                    }
                 }
             }
             None => {
@@ -493,70 +925,283 @@ pub fn compute_values(node: NodeRef) {
             }
         };
     });
-    *node.computed_values_mut() = cv_builder
+    let mut longhands = cv_builder
         .build()
         .expect("couldn't build computed values - maybe a field wasn't given to the builder?");
+    // A box can't be a scroll container along only one axis.
+    if longhands.overflow_x.is_scrollable() || longhands.overflow_y.is_scrollable() {
+        longhands.overflow_x = longhands.overflow_x.to_scrollable();
+        longhands.overflow_y = longhands.overflow_y.to_scrollable();
+    }
+    let computed_values = Arc::new(ComputedValues::from_longhands(
+        longhands,
+        custom_properties,
+        &[&*parent_computed_values, &*ComputedValues::initial()],
+    ));
+    sharing_cache.insert(&node, &parent_computed_values, Arc::clone(&computed_values));
+    node.set_computed_values(computed_values);
+}
+
+/// Computes the custom properties of `node`.
+fn cascade_custom_properties(
+    node: &NodeRef,
+    parent_computed_values: &ComputedValues,
+) -> Arc<CustomPropertiesMap> {
+    let contextual_decls = node.contextual_decls();
+    let cascaded = contextual_decls.custom_declarations();
+    let mut inherited = Arc::clone(&parent_computed_values.custom_properties);
+    // Custom properties declared `initial` don't inherit their parent's value.
+    let initial = cascaded
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|(&name, _)| name)
+        .collect::<Vec<_>>();
+    if !initial.is_empty() {
+        let inherited = Arc::make_mut(&mut inherited);
+        for name in initial {
+            inherited.remove(name);
+        }
+    }
+    let declared = cascaded
+        .iter()
+        .filter_map(|(&name, &value)| Some((name, value?)))
+        .collect();
+    compute_custom_properties(&declared, &inherited)
+}
+
+/// Returns the declaration of `longhand` that wins the cascade for `node`, with any `var()`
+/// references substituted and `revert` rolled back to the declaration of the previous cascade
+/// origin.  Returns `None` if there is no such declaration, if it's invalid at computed-value time
+/// or if it's `unset`, in which case the longhand takes its value-default.  The only CSS-wide
+/// keywords left in the returned declaration are `initial` and `inherit`.
+fn cascaded_declaration(
+    node: &NodeRef,
+    longhand: LonghandId,
+    custom_properties: &CustomPropertiesMap,
+) -> Option<PropertyDeclaration> {
+    let contextual_decls = node.contextual_decls();
+    winning_declaration(
+        contextual_decls.cascade_order_by_longhand(longhand),
+        custom_properties,
+    )
+}
+
+/// Like `cascaded_declaration`, but for a physical longhand whose declarations cascade together
+/// with those of the `logical` longhand that maps to it.  The winning declaration may be for either.
+///
+/// https://drafts.csswg.org/css-logical/#box
+fn cascaded_declaration_with_logical(
+    node: &NodeRef,
+    physical: LonghandId,
+    logical: LonghandId,
+    custom_properties: &CustomPropertiesMap,
+) -> Option<PropertyDeclaration> {
+    let contextual_decls = node.contextual_decls();
+    winning_declaration(
+        contextual_decls
+            .cascade_order_by_longhands(&[physical, logical])
+            .into_iter(),
+        custom_properties,
+    )
+}
+
+/// The first declaration of `cascade_order` that isn't reverted, as described by
+/// `cascaded_declaration`.
+fn winning_declaration<'a>(
+    cascade_order: impl Iterator<Item = &'a ContextualPropertyDeclaration>,
+    custom_properties: &CustomPropertiesMap,
+) -> Option<PropertyDeclaration> {
+    let mut reverted_origins = Vec::new();
+    for contextual_decl in cascade_order {
+        let origin = contextual_decl.origin.cascade_origin();
+        if reverted_origins.contains(&origin) {
+            continue;
+        }
+        let declaration = match &contextual_decl.inner_decl {
+            PropertyDeclaration::WithVariables(declaration) => {
+                declaration.substitute_variables(custom_properties)?
+            }
+            declaration => declaration.clone(),
+        };
+        return match declaration {
+            PropertyDeclaration::CssWideKeyword(WideKeywordDeclaration {
+                keyword: CssWideKeywords::Revert,
+                ..
+            }) => {
+                // Carry on as if the declarations of this origin didn't exist.
+                reverted_origins.push(origin);
+                continue;
+            }
+            PropertyDeclaration::CssWideKeyword(WideKeywordDeclaration {
+                keyword: CssWideKeywords::Unset,
+                ..
+            }) => None,
+            declaration => Some(declaration),
+        };
+    }
+    None
 }
 
 /// Computes "early phase" properties and adds them to the compute context.  Early phase properties
 /// are those that are depended upon by other properties to compute properly (hence their addition
 /// to the compute context).
-fn compute_early_properties(node: NodeRef, context: &mut ComputeContext) {
-    if let Some(contextual_decl) = node.contextual_decls().get_by_longhand(LonghandId::Color) {
-        context.computed_color = match &contextual_decl.inner_decl {
+fn compute_early_properties(
+    node: NodeRef,
+    custom_properties: &CustomPropertiesMap,
+    context: &mut ComputeContext,
+) {
+    if let Some(declaration) = cascaded_declaration(&node, LonghandId::Color, custom_properties) {
+        context.computed_color = match &declaration {
             PropertyDeclaration::Color(color) => Some(color.compute_value_with_context(&context)),
+            PropertyDeclaration::CssWideKeyword(declaration) => Some(match declaration.keyword {
+                CssWideKeywords::Inherit => {
+                    context.parent_computed_values.get_inherited_text().color
+                }
+                _ => Color::initial_value(),
+            }),
             _ => panic!("needed color property declaration"),
         }
     } else {
         context.computed_color = Some(specified::Color::value_default(&context));
     }
 
-    context.computed_border_styles = Some(compute_border_styles_early(node));
+    if let Some(declaration) = cascaded_declaration(&node, LonghandId::FontSize, custom_properties)
+    {
+        context.computed_font_size = match &declaration {
+            PropertyDeclaration::FontSize(font_size) => {
+                Some(font_size.compute_value_with_context(&context))
+            }
+            PropertyDeclaration::CssWideKeyword(declaration) => Some(match declaration.keyword {
+                CssWideKeywords::Inherit => context.parent_computed_values.get_font().font_size,
+                _ => FontSize::initial_value(),
+            }),
+            _ => panic!("needed font-size property declaration"),
+        }
+    } else {
+        context.computed_font_size = Some(specified::FontSize::value_default(&context));
+    }
+
+    context.computed_border_styles = Some(compute_border_styles_early(
+        node,
+        context.parent_computed_values,
+        custom_properties,
+    ));
 }
 
-fn compute_border_styles_early(node: NodeRef) -> BorderSideStyleContext {
-    let bottom = if let Some(contextual_decl) = node
-        .contextual_decls()
-        .get_by_longhand(LonghandId::BorderBottomStyle)
+/// Computes `writing-mode` and `direction`, which determine the physical longhands that logical
+/// longhands map to.
+fn compute_flow_early(
+    node: &NodeRef,
+    parent_computed_values: &ComputedValues,
+    custom_properties: &CustomPropertiesMap,
+) -> (WritingMode, Direction) {
+    // Both properties are inherited, so `inherit` is the same as there being no declaration.
+    let parent = parent_computed_values.get_inherited_box();
+    let writing_mode = match cascaded_declaration(node, LonghandId::WritingMode, custom_properties)
     {
-        match &contextual_decl.inner_decl {
+        Some(PropertyDeclaration::WritingMode(writing_mode)) => writing_mode,
+        Some(PropertyDeclaration::CssWideKeyword(WideKeywordDeclaration {
+            keyword: CssWideKeywords::Initial,
+            ..
+        })) => WritingMode::initial_value(),
+        _ => parent.writing_mode,
+    };
+    let direction = match cascaded_declaration(node, LonghandId::Direction, custom_properties) {
+        Some(PropertyDeclaration::Direction(direction)) => direction,
+        Some(PropertyDeclaration::CssWideKeyword(WideKeywordDeclaration {
+            keyword: CssWideKeywords::Initial,
+            ..
+        })) => Direction::initial_value(),
+        _ => parent.direction,
+    };
+    (writing_mode, direction)
+}
+
+/// The logical longhand that maps to the physical `longhand` in the given writing mode, if any.
+fn logical_equivalent(
+    longhand: LonghandId,
+    writing_mode: WritingMode,
+    direction: Direction,
+) -> Option<LonghandId> {
+    let corner = match longhand {
+        LonghandId::BorderBottomLeftRadius => Corner::BottomLeft,
+        LonghandId::BorderBottomRightRadius => Corner::BottomRight,
+        LonghandId::BorderTopLeftRadius => Corner::TopLeft,
+        LonghandId::BorderTopRightRadius => Corner::TopRight,
+        _ => return None,
+    };
+    [
+        (LonghandId::BorderStartStartRadius, true, true),
+        (LonghandId::BorderStartEndRadius, true, false),
+        (LonghandId::BorderEndStartRadius, false, true),
+        (LonghandId::BorderEndEndRadius, false, false),
+    ]
+    .iter()
+    .find(|(_, block_start, inline_start)| {
+        logical_corner_to_physical(*block_start, *inline_start, writing_mode, direction) == corner
+    })
+    .map(|(logical, _, _)| *logical)
+}
+
+fn compute_border_styles_early(
+    node: NodeRef,
+    parent_computed_values: &ComputedValues,
+    custom_properties: &CustomPropertiesMap,
+) -> BorderSideStyleContext {
+    let bottom = if let Some(declaration) =
+        cascaded_declaration(&node, LonghandId::BorderBottomStyle, custom_properties)
+    {
+        match &declaration {
             PropertyDeclaration::BorderBottomStyle(line_style) => *line_style,
+            PropertyDeclaration::CssWideKeyword(declaration) => match declaration.keyword {
+                CssWideKeywords::Inherit => parent_computed_values.get_border().border_bottom_style,
+                _ => border_side_initial_style(),
+            },
             _ => panic!("needed border-bottom-style property declaration"),
         }
     } else {
         border_side_initial_style()
     };
 
-    let left = if let Some(contextual_decl) = node
-        .contextual_decls()
-        .get_by_longhand(LonghandId::BorderLeftStyle)
+    let left = if let Some(declaration) =
+        cascaded_declaration(&node, LonghandId::BorderLeftStyle, custom_properties)
     {
-        match &contextual_decl.inner_decl {
+        match &declaration {
             PropertyDeclaration::BorderLeftStyle(line_style) => *line_style,
+            PropertyDeclaration::CssWideKeyword(declaration) => match declaration.keyword {
+                CssWideKeywords::Inherit => parent_computed_values.get_border().border_left_style,
+                _ => border_side_initial_style(),
+            },
             _ => panic!("needed border-left-style property declaration"),
         }
     } else {
         border_side_initial_style()
     };
 
-    let right = if let Some(contextual_decl) = node
-        .contextual_decls()
-        .get_by_longhand(LonghandId::BorderRightStyle)
+    let right = if let Some(declaration) =
+        cascaded_declaration(&node, LonghandId::BorderRightStyle, custom_properties)
     {
-        match &contextual_decl.inner_decl {
+        match &declaration {
             PropertyDeclaration::BorderRightStyle(line_style) => *line_style,
+            PropertyDeclaration::CssWideKeyword(declaration) => match declaration.keyword {
+                CssWideKeywords::Inherit => parent_computed_values.get_border().border_right_style,
+                _ => border_side_initial_style(),
+            },
             _ => panic!("needed border-right-style property declaration"),
         }
     } else {
         border_side_initial_style()
     };
 
-    let top = if let Some(contextual_decl) = node
-        .contextual_decls()
-        .get_by_longhand(LonghandId::BorderTopStyle)
+    let top = if let Some(declaration) =
+        cascaded_declaration(&node, LonghandId::BorderTopStyle, custom_properties)
     {
-        match &contextual_decl.inner_decl {
+        match &declaration {
             PropertyDeclaration::BorderTopStyle(line_style) => *line_style,
+            PropertyDeclaration::CssWideKeyword(declaration) => match declaration.keyword {
+                CssWideKeywords::Inherit => parent_computed_values.get_border().border_top_style,
+                _ => border_side_initial_style(),
+            },
             _ => panic!("needed border-top-style property declaration"),
         }
     } else {
@@ -570,3 +1215,110 @@ fn compute_border_styles_early(node: NodeRef) -> BorderSideStyleContext {
         top,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dom::parser::parse_html;
+    use crate::dom::traits::TendrilSink;
+    use crate::style::dom_integration::apply_styles;
+    use crate::style::stylesheet::{parse_css_to_stylesheet, Stylesheet};
+    use crate::style::values::CSSFloat;
+
+    const PARENT_CSS: &str = "div { white-space: pre; margin-top: 10px }";
+
+    fn sheet(css: &str) -> Stylesheet {
+        parse_css_to_stylesheet(None, &mut css.to_owned()).unwrap()
+    }
+
+    /// The computed `white-space`, an inherited property, and `margin-top`, a reset one, of the
+    /// `p` in a `div` styled by `PARENT_CSS`.
+    fn keyword_results(ua_css: &str, user_css: &str, author_css: &str) -> (WhiteSpace, Margin) {
+        let dom = parse_html().one("<div><p>text</p></div>");
+        apply_styles(
+            dom.clone(),
+            &[sheet(ua_css)],
+            &[sheet(user_css)],
+            &[sheet(PARENT_CSS), sheet(author_css)],
+            &[],
+        );
+        let p = dom.select_first("p").unwrap();
+        let values = p.as_node().computed_values();
+        (
+            values.get_inherited_text().white_space,
+            values.get_margin().margin_top,
+        )
+    }
+
+    fn margin(px: CSSFloat) -> Margin {
+        Margin {
+            size: LengthPercentageOrAuto::new_len(px),
+        }
+    }
+
+    #[test]
+    fn initial_keyword_uses_initial_values() {
+        assert_eq!(
+            keyword_results("", "", "p { white-space: initial; margin-top: initial }"),
+            (WhiteSpace::Normal, margin(0.))
+        );
+    }
+
+    #[test]
+    fn inherit_keyword_uses_parent_values() {
+        assert_eq!(
+            keyword_results("", "", "p { white-space: inherit; margin-top: inherit }"),
+            (WhiteSpace::Pre, margin(10.))
+        );
+    }
+
+    #[test]
+    fn unset_keyword_inherits_inherited_properties_and_resets_the_others() {
+        assert_eq!(
+            keyword_results("", "", "p { white-space: unset; margin-top: unset }"),
+            (WhiteSpace::Pre, margin(0.))
+        );
+    }
+
+    #[test]
+    fn revert_keyword_without_other_origins_acts_like_unset() {
+        assert_eq!(
+            keyword_results("", "", "p { white-space: revert; margin-top: revert }"),
+            (WhiteSpace::Pre, margin(0.))
+        );
+    }
+
+    #[test]
+    fn revert_keyword_falls_back_to_user_agent_origin() {
+        assert_eq!(
+            keyword_results(
+                "p { white-space: nowrap; margin-top: 5px }",
+                "",
+                "p { white-space: revert; margin-top: revert }"
+            ),
+            (WhiteSpace::Nowrap, margin(5.))
+        );
+    }
+
+    #[test]
+    fn revert_keyword_falls_back_to_user_origin() {
+        assert_eq!(
+            keyword_results(
+                "p { white-space: nowrap; margin-top: 5px }",
+                "p { white-space: pre-line; margin-top: 7px }",
+                "p { white-space: revert; margin-top: revert }"
+            ),
+            (WhiteSpace::PreLine, margin(7.))
+        );
+        // Reverting in the user origin falls back further, to the user-agent origin.
+        assert_eq!(
+            keyword_results(
+                "p { white-space: nowrap; margin-top: 5px }",
+                "p { white-space: revert; margin-top: revert }",
+                ""
+            ),
+            (WhiteSpace::Nowrap, margin(5.))
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::style::values::computed::{ComputeContext, ValueDefault};
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser};
+
+/// Computed `overflow-x` and `overflow-y` values, which determine what happens to content that
+/// overflows a box's padding box along that axis.
+///
+/// https://drafts.csswg.org/css-overflow-3/#overflow-properties
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Overflow {
+    Visible,
+    Hidden,
+    Clip,
+    Scroll,
+    Auto,
+}
+
+impl Overflow {
+    pub fn initial_value() -> Overflow {
+        Overflow::Visible
+    }
+
+    /// Whether the box is a scroll container along this axis.  Kosmonaut doesn't scroll boxes
+    /// other than the viewport yet, so their overflowing content is clipped as if it were `hidden`.
+    pub fn is_scrollable(&self) -> bool {
+        match self {
+            Overflow::Hidden | Overflow::Scroll | Overflow::Auto => true,
+            Overflow::Visible | Overflow::Clip => false,
+        }
+    }
+
+    /// Whether overflowing content is clipped to the padding box along this axis.
+    pub fn clips(&self) -> bool {
+        match self {
+            Overflow::Visible => false,
+            Overflow::Hidden | Overflow::Clip | Overflow::Scroll | Overflow::Auto => true,
+        }
+    }
+
+    /// The value when the other axis is a scroll container, since a box can't be a scroll
+    /// container along only one axis.
+    ///
+    /// https://drafts.csswg.org/css-overflow-3/#overflow-control
+    pub fn to_scrollable(self) -> Overflow {
+        match self {
+            Overflow::Visible => Overflow::Auto,
+            Overflow::Clip => Overflow::Hidden,
+            Overflow::Hidden | Overflow::Scroll | Overflow::Auto => self,
+        }
+    }
+}
+
+impl CssValueParse for Overflow {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "visible" => Ok(Overflow::Visible),
+            "hidden" => Ok(Overflow::Hidden),
+            "clip" => Ok(Overflow::Clip),
+            "scroll" => Ok(Overflow::Scroll),
+            "auto" => Ok(Overflow::Auto),
+        }
+    }
+}
+
+impl ValueDefault for Overflow {
+    type ComputedValue = Overflow;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        Overflow::initial_value()
+    }
+}
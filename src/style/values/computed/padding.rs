@@ -6,7 +6,7 @@ use crate::style::values::specified;
 
 /// Computed value of a `padding-<side>`.
 /// https://www.w3.org/TR/css-box-3/#padding-physical
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Padding {
     pub size: LengthPercentage,
 }
@@ -0,0 +1,60 @@
+use crate::style::values::computed::length::{CSSPixelLength, LengthPercentage};
+use crate::style::values::computed::ComputeValue;
+use crate::style::values::specified;
+use crate::style::values::used::ToPx;
+
+/// The computed position of something along one axis of an area, as an offset from either the
+/// start or the end edge of that axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionComponent {
+    /// Whether `offset` is from the end edge (right or bottom), rather than the start edge (left or
+    /// top).
+    pub from_end: bool,
+    pub offset: LengthPercentage,
+}
+
+impl PositionComponent {
+    /// The distance from the start edge of the axis, where `available` is the free space along
+    /// it, which percentages refer to.  For a background image, this is the size of its
+    /// positioning area minus the size of the image, so that `100%` aligns the image with the end
+    /// edge.
+    pub fn to_px(self, available: CSSPixelLength) -> CSSPixelLength {
+        let offset = self.offset.to_px(available);
+        if self.from_end {
+            available - offset
+        } else {
+            offset
+        }
+    }
+}
+
+impl ComputeValue for specified::position::PositionComponent {
+    type ComputedValue = PositionComponent;
+
+    fn compute_value(&self) -> Self::ComputedValue {
+        PositionComponent {
+            from_end: self.from_end,
+            offset: self.offset.compute_value(),
+        }
+    }
+}
+
+/// A computed `<position>`.
+///
+/// https://drafts.csswg.org/css-values-4/#position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub horizontal: PositionComponent,
+    pub vertical: PositionComponent,
+}
+
+impl ComputeValue for specified::position::Position {
+    type ComputedValue = Position;
+
+    fn compute_value(&self) -> Self::ComputedValue {
+        Position {
+            horizontal: self.horizontal.compute_value(),
+            vertical: self.vertical.compute_value(),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! The groups of related properties that `ComputedValues` is made of.  Each group is reference
+//! counted, so that nodes whose values for a whole group are the same (e.g. a child that inherits
+//! all of its parent's font properties, or an element with no margins) share it rather than each
+//! having their own copy.
+//!
+//! Inherited groups are usually shared with the parent, and reset groups with the initial values.
+//!
+//! https://www.w3.org/TR/2018/CR-css-cascade-3-20180828/#inheriting
+
+use crate::style::values::computed::height::Height;
+use crate::style::values::computed::width::Width;
+use crate::style::values::computed::{
+    BackgroundAttachment, BackgroundClip, BackgroundColor, BackgroundImage, BackgroundOrigin,
+    BackgroundPositionX, BackgroundPositionY, BackgroundRepeat, BackgroundSize, BorderColor,
+    BorderCornerRadius, BorderWidth, Color, Direction, Display, FontFamily, FontSize, FontStretch,
+    FontStyle, FontWeight, LineHeight, LineStyle, Overflow, TextOrientation, UnicodeBidi,
+    VerticalAlign, WhiteSpace, WritingMode,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Background {
+    pub background_attachment: BackgroundAttachment,
+    pub background_clip: BackgroundClip,
+    pub background_color: BackgroundColor,
+    pub background_image: BackgroundImage,
+    pub background_origin: BackgroundOrigin,
+    pub background_position_x: BackgroundPositionX,
+    pub background_position_y: BackgroundPositionY,
+    pub background_repeat: BackgroundRepeat,
+    pub background_size: BackgroundSize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Border {
+    pub border_bottom_color: BorderColor,
+    pub border_left_color: BorderColor,
+    pub border_right_color: BorderColor,
+    pub border_top_color: BorderColor,
+    pub border_bottom_style: LineStyle,
+    pub border_left_style: LineStyle,
+    pub border_right_style: LineStyle,
+    pub border_top_style: LineStyle,
+    pub border_bottom_width: BorderWidth,
+    pub border_left_width: BorderWidth,
+    pub border_right_width: BorderWidth,
+    pub border_top_width: BorderWidth,
+    pub border_bottom_left_radius: BorderCornerRadius,
+    pub border_bottom_right_radius: BorderCornerRadius,
+    pub border_top_left_radius: BorderCornerRadius,
+    pub border_top_right_radius: BorderCornerRadius,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Box {
+    pub display: Display,
+    pub overflow_x: Overflow,
+    pub overflow_y: Overflow,
+    pub unicode_bidi: UnicodeBidi,
+    pub vertical_align: VerticalAlign,
+}
+
+/// Inherited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Font {
+    pub font_family: FontFamily,
+    pub font_size: FontSize,
+    pub font_stretch: FontStretch,
+    pub font_style: FontStyle,
+    pub font_weight: FontWeight,
+}
+
+/// Inherited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InheritedBox {
+    pub direction: Direction,
+    pub text_orientation: TextOrientation,
+    pub writing_mode: WritingMode,
+}
+
+/// Inherited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InheritedText {
+    pub color: Color,
+    pub line_height: LineHeight,
+    pub white_space: WhiteSpace,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Margin {
+    pub margin_bottom: super::Margin,
+    pub margin_left: super::Margin,
+    pub margin_right: super::Margin,
+    pub margin_top: super::Margin,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Padding {
+    pub padding_bottom: super::Padding,
+    pub padding_left: super::Padding,
+    pub padding_right: super::Padding,
+    pub padding_top: super::Padding,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub height: Height,
+    pub width: Width,
+}
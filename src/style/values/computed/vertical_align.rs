@@ -0,0 +1,49 @@
+use crate::style::values::computed::length::LengthPercentage;
+use crate::style::values::computed::{
+    ComputeContext, ComputeValue, ComputeValueWithContext, ValueDefault,
+};
+use crate::style::values::specified;
+use crate::style::values::specified::vertical_align::VerticalAlignKeyword;
+
+/// Computed value of `vertical-align`.
+///
+/// https://www.w3.org/TR/CSS2/visudet.html#propdef-vertical-align
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VerticalAlign {
+    Keyword(VerticalAlignKeyword),
+    /// Percentages are kept as-is, since they refer to the used `line-height` of the box, which
+    /// isn't known until layout.
+    LengthPercentage(LengthPercentage),
+}
+
+impl VerticalAlign {
+    pub fn initial_value() -> VerticalAlign {
+        VerticalAlign::Keyword(VerticalAlignKeyword::Baseline)
+    }
+}
+
+impl ComputeValueWithContext for specified::VerticalAlign {
+    type ComputedValue = VerticalAlign;
+
+    fn compute_value_with_context(&self, _context: &ComputeContext) -> Self::ComputedValue {
+        match self {
+            specified::VerticalAlign::Keyword(keyword) => VerticalAlign::Keyword(*keyword),
+            specified::VerticalAlign::LengthPercentage(specified::LengthPercentage::Length(
+                no_calc_length,
+            )) => VerticalAlign::LengthPercentage(LengthPercentage::Length(
+                no_calc_length.compute_value(),
+            )),
+            specified::VerticalAlign::LengthPercentage(
+                specified::LengthPercentage::Percentage(percentage),
+            ) => VerticalAlign::LengthPercentage(LengthPercentage::Percentage(*percentage)),
+        }
+    }
+}
+
+impl ValueDefault for specified::VerticalAlign {
+    type ComputedValue = VerticalAlign;
+
+    fn value_default(_context: &ComputeContext) -> Self::ComputedValue {
+        VerticalAlign::initial_value()
+    }
+}
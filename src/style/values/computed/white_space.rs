@@ -0,0 +1,97 @@
+use crate::style::values::computed::{ComputeContext, ValueDefault};
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser};
+
+/// Computed `white-space` values, which determine whether and how white space inside an element is
+/// collapsed, and whether lines may wrap at soft wrap opportunities.
+///
+/// https://drafts.csswg.org/css-text-3/#white-space-property
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    BreakSpaces,
+    PreLine,
+}
+
+impl WhiteSpace {
+    pub fn initial_value() -> WhiteSpace {
+        WhiteSpace::Normal
+    }
+
+    /// Whether sequences of spaces and tabs collapse into a single space.
+    pub fn collapses_spaces(&self) -> bool {
+        match self {
+            WhiteSpace::Normal | WhiteSpace::Nowrap | WhiteSpace::PreLine => true,
+            WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::BreakSpaces => false,
+        }
+    }
+
+    /// Whether segment breaks (newlines in the source) are preserved as forced line breaks, rather
+    /// than being transformed into spaces.
+    pub fn preserves_segment_breaks(&self) -> bool {
+        match self {
+            WhiteSpace::Pre
+            | WhiteSpace::PreWrap
+            | WhiteSpace::BreakSpaces
+            | WhiteSpace::PreLine => true,
+            WhiteSpace::Normal | WhiteSpace::Nowrap => false,
+        }
+    }
+
+    /// Whether lines may be broken at soft wrap opportunities.
+    pub fn allows_wrapping(&self) -> bool {
+        match self {
+            WhiteSpace::Normal
+            | WhiteSpace::PreWrap
+            | WhiteSpace::BreakSpaces
+            | WhiteSpace::PreLine => true,
+            WhiteSpace::Pre | WhiteSpace::Nowrap => false,
+        }
+    }
+
+    /// Whether preserved spaces at the end of a line hang, meaning they're not considered when
+    /// deciding where to break lines.  Collapsible spaces at the end of a line are removed, and so
+    /// never need to fit either.
+    pub fn hangs_trailing_spaces(&self) -> bool {
+        match self {
+            WhiteSpace::Normal
+            | WhiteSpace::Nowrap
+            | WhiteSpace::PreLine
+            | WhiteSpace::PreWrap
+            | WhiteSpace::Pre => true,
+            // > [...] any sequence of preserved white space always takes up space, including at
+            // > the end of the line.
+            WhiteSpace::BreakSpaces => false,
+        }
+    }
+}
+
+impl CssValueParse for WhiteSpace {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "normal" => Ok(WhiteSpace::Normal),
+            "pre" => Ok(WhiteSpace::Pre),
+            "nowrap" => Ok(WhiteSpace::Nowrap),
+            "pre-wrap" => Ok(WhiteSpace::PreWrap),
+            "break-spaces" => Ok(WhiteSpace::BreakSpaces),
+            "pre-line" => Ok(WhiteSpace::PreLine),
+        }
+    }
+}
+
+impl ValueDefault for WhiteSpace {
+    type ComputedValue = WhiteSpace;
+
+    fn value_default(context: &ComputeContext) -> Self::ComputedValue {
+        context
+            .parent_computed_values
+            .get_inherited_text()
+            .white_space
+    }
+}
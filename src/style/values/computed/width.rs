@@ -3,17 +3,23 @@ use crate::style::values::computed::{
     ComputeContext, ComputeValue, ComputeValueWithContext, ValueDefault,
 };
 use crate::style::values::specified;
+use crate::style::values::specified::width::ContentSizeKeyword;
 
 /// Computed value of a `width`.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Width {
     pub size: LengthPercentageOrAuto,
+    /// Set when `width` is one of the content-based sizing keywords (e.g. `min-content`).  In this
+    /// case, `size` is `auto`, which is how these keywords behave in any context that doesn't know
+    /// how to resolve them against the box's intrinsic sizes.
+    pub content_size: Option<ContentSizeKeyword>,
 }
 
 impl Width {
     pub fn initial_value() -> Width {
         Width {
             size: LengthPercentageOrAuto::Auto,
+            content_size: None,
         }
     }
 }
@@ -38,10 +44,17 @@ impl ComputeValueWithContext for specified::Width {
                     }
                 },
             },
+            specified::Width::ContentSize(keyword) => {
+                return Width {
+                    size: LengthPercentageOrAuto::Auto,
+                    content_size: Some(*keyword),
+                }
+            }
         };
 
         Width {
             size: computed_lp_auto,
+            content_size: None,
         }
     }
 }
@@ -1,4 +1,14 @@
-use crate::style::values::specified::ColorUnit;
+use crate::style::properties::PropertyDeclaration;
+use crate::style::values::computed::background::{Attachment, RepeatStyle};
+use crate::style::values::computed::background::{
+    BackgroundAttachment, BackgroundBox, BackgroundClip, BackgroundOrigin, BackgroundRepeat,
+    BackgroundRepeatLayer,
+};
+use crate::style::values::specified::image::Image;
+use crate::style::values::specified::position::{Axis, Position, PositionComponent};
+use crate::style::values::specified::{
+    ColorUnit, LengthPercentage, LengthPercentageOrAuto, NoCalcLength,
+};
 use crate::style::values::CssValueParse;
 use crate::style::StyleParseErrorKind;
 use cssparser::{ParseError, Parser};
@@ -28,3 +38,473 @@ impl CssValueParse for BackgroundColor {
             .map(BackgroundColor)
     }
 }
+
+/// Specified value for the `background-image` property, one image for each layer, where `None` is
+/// the `none` keyword.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-image
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundImage(pub Vec<Option<Image>>);
+
+impl BackgroundImage {
+    pub fn initial_value() -> Self {
+        BackgroundImage(vec![None])
+    }
+}
+
+impl CssValueParse for BackgroundImage {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(parse_image_or_none)
+            .map(BackgroundImage)
+    }
+}
+
+/// Parses `<image> | none`.
+fn parse_image_or_none<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Option<Image>, ParseError<'i, StyleParseErrorKind<'i>>> {
+    if input.try_parse(|i| i.expect_ident_matching("none")).is_ok() {
+        return Ok(None);
+    }
+    Image::parse(input).map(Some)
+}
+
+/// Specified value for the `background-position-x` property.
+///
+/// https://drafts.csswg.org/css-backgrounds-4/#background-position-longhands
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundPositionX(pub Vec<PositionComponent>);
+
+impl BackgroundPositionX {
+    pub fn initial_value() -> Self {
+        BackgroundPositionX(vec![PositionComponent::start()])
+    }
+}
+
+impl CssValueParse for BackgroundPositionX {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(|i| PositionComponent::parse_axis(i, Axis::Horizontal))
+            .map(BackgroundPositionX)
+    }
+}
+
+/// Specified value for the `background-position-y` property.
+///
+/// https://drafts.csswg.org/css-backgrounds-4/#background-position-longhands
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundPositionY(pub Vec<PositionComponent>);
+
+impl BackgroundPositionY {
+    pub fn initial_value() -> Self {
+        BackgroundPositionY(vec![PositionComponent::start()])
+    }
+}
+
+impl CssValueParse for BackgroundPositionY {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(|i| PositionComponent::parse_axis(i, Axis::Vertical))
+            .map(BackgroundPositionY)
+    }
+}
+
+/// Specified value for the `background-size` property.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-size
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundSize(pub Vec<BackgroundSizeLayer>);
+
+impl BackgroundSize {
+    pub fn initial_value() -> Self {
+        BackgroundSize(vec![BackgroundSizeLayer::auto()])
+    }
+}
+
+impl CssValueParse for BackgroundSize {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        input
+            .parse_comma_separated(BackgroundSizeLayer::parse)
+            .map(BackgroundSize)
+    }
+}
+
+/// The specified size of a single background layer's image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackgroundSizeLayer {
+    Explicit {
+        width: LengthPercentageOrAuto,
+        height: LengthPercentageOrAuto,
+    },
+    Cover,
+    Contain,
+}
+
+impl BackgroundSizeLayer {
+    fn auto() -> BackgroundSizeLayer {
+        BackgroundSizeLayer::Explicit {
+            width: LengthPercentageOrAuto::Auto,
+            height: LengthPercentageOrAuto::Auto,
+        }
+    }
+}
+
+/// Parses `[ <length-percentage [0,∞]> | auto ]{1,2} | cover | contain`, where a missing height
+/// is `auto`.
+impl CssValueParse for BackgroundSizeLayer {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let keyword = input.try_parse(|i| {
+            try_match_ident_ignore_ascii_case! { i,
+                "cover" => Ok(BackgroundSizeLayer::Cover),
+                "contain" => Ok(BackgroundSizeLayer::Contain),
+            }
+        });
+        if keyword.is_ok() {
+            return keyword;
+        }
+        let width = parse_size_component(input)?;
+        let height = input
+            .try_parse(parse_size_component)
+            .unwrap_or(LengthPercentageOrAuto::Auto);
+        Ok(BackgroundSizeLayer::Explicit { width, height })
+    }
+}
+
+/// Parses a non-negative `<length-percentage>`, or `auto`.
+fn parse_size_component<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<LengthPercentageOrAuto, ParseError<'i, StyleParseErrorKind<'i>>> {
+    if input.try_parse(|i| i.expect_ident_matching("auto")).is_ok() {
+        return Ok(LengthPercentageOrAuto::Auto);
+    }
+    let location = input.current_source_location();
+    let size = LengthPercentage::parse_allowing_unitless_zero(input)?;
+    let is_negative = match size {
+        LengthPercentage::Length(NoCalcLength::Absolute(length)) => length.to_px() < 0.,
+        LengthPercentage::Percentage(percentage) => percentage.0 < 0.,
+    };
+    if is_negative {
+        return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+    }
+    Ok(LengthPercentageOrAuto::LengthPercentage(size))
+}
+
+/// The values of a single layer of the `background` shorthand.  Values that are omitted are
+/// `None`, and are reset to their initial values.
+#[derive(Default)]
+struct ShorthandLayer {
+    image: Option<Option<Image>>,
+    position: Option<Position>,
+    size: Option<BackgroundSizeLayer>,
+    repeat: Option<BackgroundRepeatLayer>,
+    attachment: Option<Attachment>,
+    origin: Option<BackgroundBox>,
+    clip: Option<BackgroundBox>,
+    color: Option<BackgroundColor>,
+}
+
+impl ShorthandLayer {
+    /// Parses `<bg-layer>`, or `<final-bg-layer>`, which may also include a color.  The parts of
+    /// a layer can be given in any order, except that the size must directly follow the position
+    /// after a `/`.
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let mut layer = ShorthandLayer::default();
+        loop {
+            if layer.image.is_none() {
+                if let Ok(image) = input.try_parse(parse_image_or_none) {
+                    layer.image = Some(image);
+                    continue;
+                }
+            }
+            if layer.position.is_none() {
+                if let Ok(position) = input.try_parse(Position::parse) {
+                    layer.position = Some(position);
+                    if input.try_parse(|i| i.expect_delim('/')).is_ok() {
+                        layer.size = Some(BackgroundSizeLayer::parse(input)?);
+                    }
+                    continue;
+                }
+            }
+            if layer.repeat.is_none() {
+                if let Ok(repeat) = input.try_parse(BackgroundRepeatLayer::parse) {
+                    layer.repeat = Some(repeat);
+                    continue;
+                }
+            }
+            if layer.attachment.is_none() {
+                if let Ok(attachment) = input.try_parse(Attachment::parse) {
+                    layer.attachment = Some(attachment);
+                    continue;
+                }
+            }
+            if layer.clip.is_none() {
+                if let Ok(background_box) = input.try_parse(BackgroundBox::parse) {
+                    // The first box is the origin, and the second, if there is one, the clip.
+                    if layer.origin.is_none() {
+                        layer.origin = Some(background_box);
+                    } else {
+                        layer.clip = Some(background_box);
+                    }
+                    continue;
+                }
+            }
+            if layer.color.is_none() {
+                if let Ok(color) = input.try_parse(BackgroundColor::parse) {
+                    layer.color = Some(color);
+                    continue;
+                }
+            }
+            break;
+        }
+        let is_empty = layer.image.is_none()
+            && layer.position.is_none()
+            && layer.repeat.is_none()
+            && layer.attachment.is_none()
+            && layer.origin.is_none()
+            && layer.color.is_none();
+        if is_empty {
+            return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+        }
+        Ok(layer)
+    }
+}
+
+/// Parses the `background` shorthand, which sets every background longhand at once, resetting
+/// any of them that are omitted to their initial values.  Each comma-separated layer sets the
+/// values of that layer, and only the final layer may set the background color.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background
+pub fn parse_background_shorthand_into<'i, 't>(
+    declarations: &mut Vec<PropertyDeclaration>,
+    input: &mut Parser<'i, 't>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    let location = input.current_source_location();
+    let mut layers = input.parse_comma_separated(ShorthandLayer::parse)?;
+    let color = layers.last_mut().and_then(|layer| layer.color.take());
+    if layers.iter().any(|layer| layer.color.is_some()) {
+        return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+    }
+
+    let mut image = Vec::new();
+    let mut position_x = Vec::new();
+    let mut position_y = Vec::new();
+    let mut size = Vec::new();
+    let mut repeat = Vec::new();
+    let mut attachment = Vec::new();
+    let mut origin = Vec::new();
+    let mut clip = Vec::new();
+    for layer in layers {
+        let position = layer.position.unwrap_or(Position {
+            horizontal: PositionComponent::start(),
+            vertical: PositionComponent::start(),
+        });
+        image.push(layer.image.unwrap_or(None));
+        position_x.push(position.horizontal);
+        position_y.push(position.vertical);
+        size.push(layer.size.unwrap_or_else(BackgroundSizeLayer::auto));
+        repeat.push(layer.repeat.unwrap_or(BackgroundRepeatLayer {
+            x: RepeatStyle::Repeat,
+            y: RepeatStyle::Repeat,
+        }));
+        attachment.push(layer.attachment.unwrap_or(Attachment::Scroll));
+        // A single box sets both the origin and the clip.
+        origin.push(layer.origin.unwrap_or(BackgroundBox::PaddingBox));
+        clip.push(
+            layer
+                .clip
+                .or(layer.origin)
+                .unwrap_or(BackgroundBox::BorderBox),
+        );
+    }
+
+    declarations.push(PropertyDeclaration::BackgroundColor(
+        color.unwrap_or_else(BackgroundColor::initial_value),
+    ));
+    declarations.push(PropertyDeclaration::BackgroundImage(BackgroundImage(image)));
+    declarations.push(PropertyDeclaration::BackgroundPositionX(
+        BackgroundPositionX(position_x),
+    ));
+    declarations.push(PropertyDeclaration::BackgroundPositionY(
+        BackgroundPositionY(position_y),
+    ));
+    declarations.push(PropertyDeclaration::BackgroundSize(BackgroundSize(size)));
+    declarations.push(PropertyDeclaration::BackgroundRepeat(BackgroundRepeat(
+        repeat,
+    )));
+    declarations.push(PropertyDeclaration::BackgroundAttachment(
+        BackgroundAttachment(attachment),
+    ));
+    declarations.push(PropertyDeclaration::BackgroundOrigin(BackgroundOrigin(
+        origin,
+    )));
+    declarations.push(PropertyDeclaration::BackgroundClip(BackgroundClip(clip)));
+    Ok(())
+}
+
+/// Parses the `background-position` shorthand, which sets `background-position-x` and
+/// `background-position-y` from a `<position>` for each layer.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#background-position
+pub fn parse_background_position_shorthand_into<'i, 't>(
+    declarations: &mut Vec<PropertyDeclaration>,
+    input: &mut Parser<'i, 't>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    let positions = input.parse_comma_separated(Position::parse)?;
+    declarations.push(PropertyDeclaration::BackgroundPositionX(
+        BackgroundPositionX(positions.iter().map(|p| p.horizontal).collect()),
+    ));
+    declarations.push(PropertyDeclaration::BackgroundPositionY(
+        BackgroundPositionY(positions.iter().map(|p| p.vertical).collect()),
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::Percentage;
+    use crate::style::values::specified::AbsoluteLength;
+    use cssparser::{ParserInput, RGBA};
+
+    fn parse_background(css: &str) -> Option<Vec<PropertyDeclaration>> {
+        let mut declarations = Vec::new();
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        parser
+            .parse_entirely(|i| parse_background_shorthand_into(&mut declarations, i))
+            .ok()?;
+        Some(declarations)
+    }
+
+    fn parse_size(css: &str) -> Option<BackgroundSize> {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        parser.parse_entirely(BackgroundSize::parse).ok()
+    }
+
+    fn px(px: f32) -> LengthPercentage {
+        LengthPercentage::Length(NoCalcLength::Absolute(AbsoluteLength::Px(px)))
+    }
+
+    #[test]
+    fn parses_background_sizes() {
+        assert_eq!(
+            parse_size("cover, 10px, auto 50%, 0 0"),
+            Some(BackgroundSize(vec![
+                BackgroundSizeLayer::Cover,
+                BackgroundSizeLayer::Explicit {
+                    width: LengthPercentageOrAuto::LengthPercentage(px(10.)),
+                    height: LengthPercentageOrAuto::Auto,
+                },
+                BackgroundSizeLayer::Explicit {
+                    width: LengthPercentageOrAuto::Auto,
+                    height: LengthPercentageOrAuto::LengthPercentage(LengthPercentage::Percentage(
+                        Percentage(0.5)
+                    )),
+                },
+                BackgroundSizeLayer::Explicit {
+                    width: LengthPercentageOrAuto::LengthPercentage(px(0.)),
+                    height: LengthPercentageOrAuto::LengthPercentage(px(0.)),
+                },
+            ]))
+        );
+        assert_eq!(parse_size("-10px"), None);
+        assert_eq!(parse_size("cover contain"), None);
+    }
+
+    #[test]
+    fn parses_background_shorthand() {
+        let declarations = parse_background(
+            "url(a.png) right 10px top / 20px no-repeat fixed content-box padding-box, \
+             linear-gradient(red, blue) red",
+        )
+        .unwrap();
+        assert_eq!(declarations.len(), 9);
+        assert!(matches!(
+            declarations[0],
+            PropertyDeclaration::BackgroundColor(color)
+                if color.unit() == ColorUnit::Numeric(RGBA::new(255, 0, 0, 255))
+        ));
+        match &declarations[1] {
+            PropertyDeclaration::BackgroundImage(BackgroundImage(images)) => {
+                assert_eq!(images.len(), 2);
+                assert_eq!(images[0], Some(Image::Url("a.png".to_owned())));
+                assert!(matches!(images[1], Some(Image::Gradient(_))));
+            }
+            _ => panic!("expected background-image declaration"),
+        }
+        match &declarations[2] {
+            PropertyDeclaration::BackgroundPositionX(BackgroundPositionX(x)) => assert_eq!(
+                x,
+                &vec![
+                    PositionComponent {
+                        from_end: true,
+                        offset: px(10.),
+                    },
+                    PositionComponent::start(),
+                ]
+            ),
+            _ => panic!("expected background-position-x declaration"),
+        }
+        match &declarations[5] {
+            PropertyDeclaration::BackgroundRepeat(BackgroundRepeat(repeat)) => {
+                assert_eq!(repeat[0].x, RepeatStyle::NoRepeat);
+                assert_eq!(repeat[1].y, RepeatStyle::Repeat);
+            }
+            _ => panic!("expected background-repeat declaration"),
+        }
+        match (&declarations[7], &declarations[8]) {
+            (
+                PropertyDeclaration::BackgroundOrigin(BackgroundOrigin(origin)),
+                PropertyDeclaration::BackgroundClip(BackgroundClip(clip)),
+            ) => {
+                assert_eq!(
+                    origin,
+                    &vec![BackgroundBox::ContentBox, BackgroundBox::PaddingBox]
+                );
+                assert_eq!(
+                    clip,
+                    &vec![BackgroundBox::PaddingBox, BackgroundBox::BorderBox]
+                );
+            }
+            _ => panic!("expected background-origin and background-clip declarations"),
+        }
+    }
+
+    #[test]
+    fn background_shorthand_single_box_sets_origin_and_clip() {
+        let declarations = parse_background("border-box").unwrap();
+        assert!(matches!(
+            &declarations[7],
+            PropertyDeclaration::BackgroundOrigin(BackgroundOrigin(origin))
+                if origin == &vec![BackgroundBox::BorderBox]
+        ));
+        assert!(matches!(
+            &declarations[8],
+            PropertyDeclaration::BackgroundClip(BackgroundClip(clip))
+                if clip == &vec![BackgroundBox::BorderBox]
+        ));
+    }
+
+    #[test]
+    fn background_shorthand_only_allows_color_in_final_layer() {
+        assert!(parse_background("red, url(a.png)").is_none());
+        assert!(parse_background("url(a.png) red red").is_none());
+        assert!(parse_background("url(a.png), ").is_none());
+        assert!(parse_background("none, none green").is_some());
+    }
+}
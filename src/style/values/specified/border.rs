@@ -1,6 +1,8 @@
 use crate::style::properties::PropertyDeclaration;
 use crate::style::values::computed::LineStyle;
-use crate::style::values::specified::{parse_shorthand_sides, ColorUnit, NoCalcLength};
+use crate::style::values::specified::{
+    parse_shorthand_sides, ColorUnit, LengthPercentage, NoCalcLength,
+};
 use crate::style::values::CssValueParse;
 use crate::style::StyleParseErrorKind;
 use crate::Side;
@@ -131,6 +133,36 @@ pub fn parse_border_width_shorthand_into<'i, 't>(
     Ok(())
 }
 
+/// Parses the `border-radius` shorthand: the horizontal radii of the corners, optionally followed
+/// by a `/` and their vertical radii.  Each list assigns its values to the corners clockwise from
+/// the top-left one, in the same pattern as the sides of the `margin` shorthand.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#border-radius
+pub fn parse_border_radius_shorthand_into<'i, 't>(
+    declarations: &mut Vec<PropertyDeclaration>,
+    input: &mut Parser<'i, 't>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    let horizontal = parse_shorthand_sides::<Radius>(input)?;
+    let vertical = if input.try_parse(|i| i.expect_delim('/')).is_ok() {
+        parse_shorthand_sides::<Radius>(input)?
+    } else {
+        horizontal
+    };
+    declarations.push(PropertyDeclaration::BorderTopLeftRadius(
+        BorderCornerRadius::new(horizontal.top, vertical.top),
+    ));
+    declarations.push(PropertyDeclaration::BorderTopRightRadius(
+        BorderCornerRadius::new(horizontal.right, vertical.right),
+    ));
+    declarations.push(PropertyDeclaration::BorderBottomRightRadius(
+        BorderCornerRadius::new(horizontal.bottom, vertical.bottom),
+    ));
+    declarations.push(PropertyDeclaration::BorderBottomLeftRadius(
+        BorderCornerRadius::new(horizontal.left, vertical.left),
+    ));
+    Ok(())
+}
+
 fn parse_border_shorthand_inner<'i, 't>(
     input: &mut Parser<'i, 't>,
 ) -> Result<
@@ -334,3 +366,140 @@ impl CssValueParse for LineWidth {
         }
     }
 }
+
+/// Specified `border-<corner>-radius` value, which is the same for the physical corners (e.g.
+/// `border-top-left-radius`) and the logical ones (e.g. `border-start-end-radius`).  A single
+/// radius rounds the corner with a quarter circle, and two with a quarter ellipse.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#border-radius
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BorderCornerRadius {
+    pub horizontal: LengthPercentage,
+    pub vertical: LengthPercentage,
+}
+
+impl BorderCornerRadius {
+    fn new(horizontal: Radius, vertical: Radius) -> BorderCornerRadius {
+        BorderCornerRadius {
+            horizontal: horizontal.0,
+            vertical: vertical.0,
+        }
+    }
+}
+
+impl CssValueParse for BorderCornerRadius {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let horizontal = Radius::parse(input)?;
+        let vertical = input.try_parse(|i| Radius::parse(i)).unwrap_or(horizontal);
+        Ok(BorderCornerRadius::new(horizontal, vertical))
+    }
+}
+
+/// A single radius of a corner, which can't be negative.
+#[derive(Clone, Copy, Debug)]
+struct Radius(LengthPercentage);
+
+impl CssValueParse for Radius {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let radius = LengthPercentage::parse_allowing_unitless_zero(input)?;
+        let is_negative = match radius {
+            LengthPercentage::Length(NoCalcLength::Absolute(length)) => length.to_px() < 0.,
+            LengthPercentage::Percentage(percentage) => percentage.0 < 0.,
+        };
+        if is_negative {
+            return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+        }
+        Ok(Radius(radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::Percentage;
+    use crate::style::values::specified::AbsoluteLength;
+    use cssparser::ParserInput;
+
+    fn parse_border_radius(css: &str) -> Option<Vec<BorderCornerRadius>> {
+        let mut declarations = Vec::new();
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        parser
+            .parse_entirely(|i| parse_border_radius_shorthand_into(&mut declarations, i))
+            .ok()?;
+        Some(
+            declarations
+                .into_iter()
+                .map(|declaration| match declaration {
+                    PropertyDeclaration::BorderTopLeftRadius(radius)
+                    | PropertyDeclaration::BorderTopRightRadius(radius)
+                    | PropertyDeclaration::BorderBottomRightRadius(radius)
+                    | PropertyDeclaration::BorderBottomLeftRadius(radius) => radius,
+                    _ => panic!("expected a corner radius declaration"),
+                })
+                .collect(),
+        )
+    }
+
+    fn parse_corner_radius(css: &str) -> Option<BorderCornerRadius> {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        parser.parse_entirely(BorderCornerRadius::parse).ok()
+    }
+
+    fn px(px: f32) -> LengthPercentage {
+        LengthPercentage::Length(NoCalcLength::Absolute(AbsoluteLength::Px(px)))
+    }
+
+    fn radius(horizontal: LengthPercentage, vertical: LengthPercentage) -> BorderCornerRadius {
+        BorderCornerRadius {
+            horizontal,
+            vertical,
+        }
+    }
+
+    #[test]
+    fn parses_corner_radii() {
+        assert_eq!(parse_corner_radius("4px"), Some(radius(px(4.), px(4.))));
+        assert_eq!(
+            parse_corner_radius("0 50%"),
+            Some(radius(
+                px(0.),
+                LengthPercentage::Percentage(Percentage(0.5))
+            ))
+        );
+        assert_eq!(parse_corner_radius("-1px"), None);
+        assert_eq!(parse_corner_radius("1px 2px 3px"), None);
+    }
+
+    #[test]
+    fn parses_border_radius_shorthand() {
+        // Corners are assigned clockwise from the top-left, in the order the declarations are in.
+        assert_eq!(
+            parse_border_radius("1px 2px 3px"),
+            Some(vec![
+                radius(px(1.), px(1.)),
+                radius(px(2.), px(2.)),
+                radius(px(3.), px(3.)),
+                radius(px(2.), px(2.)),
+            ])
+        );
+        assert_eq!(
+            parse_border_radius("1px 2px / 3px"),
+            Some(vec![
+                radius(px(1.), px(3.)),
+                radius(px(2.), px(3.)),
+                radius(px(1.), px(3.)),
+                radius(px(2.), px(3.)),
+            ])
+        );
+        assert_eq!(parse_border_radius("1px /"), None);
+        assert_eq!(parse_border_radius("1px -2px"), None);
+        assert_eq!(parse_border_radius("1px 2px 3px 4px 5px"), None);
+    }
+}
@@ -43,7 +43,7 @@ impl CssValueParse for Color {
 /// https://www.w3.org/TR/css-color-3/#valuea-def-color
 // TODO: There is currently no computed counterpart to this type, as we currently represent computed
 // color units as simply `cssparser::RGBA`.  This might need to change eventually.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ColorUnit {
     /// The `currentColor` keyword.
     CurrentColor,
@@ -1,8 +1,10 @@
-use crate::style::StyleParseErrorKind;
-use cssparser::{ParseError, Parser};
-
+use crate::style::properties::PropertyDeclaration;
+use crate::style::values::computed::{FontFamily, FontStretch, FontStyle};
 use crate::style::values::specified::length::LengthPercentage;
-use crate::style::values::CssValueParse;
+use crate::style::values::specified::LineHeight;
+use crate::style::values::{CSSFloat, CssValueParse};
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser, Token};
 
 /// The default font size.
 pub const FONT_MEDIUM_PX: i32 = 16;
@@ -70,3 +72,175 @@ impl CssValueParse for FontSize {
         }
     }
 }
+
+/// Specified value of the `font-weight` property.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-weight-prop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontWeight {
+    /// A weight from 1 to 1000, including those given by the `normal` and `bold` keywords.
+    Absolute(CSSFloat),
+    /// Bolder than the inherited weight.
+    Bolder,
+    /// Lighter than the inherited weight.
+    Lighter,
+}
+
+impl CssValueParse for FontWeight {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let start = input.state();
+        let location = input.current_source_location();
+        let token = input.next()?;
+        match *token {
+            Token::Number { value, .. } if value >= 1. && value <= 1000. => {
+                return Ok(FontWeight::Absolute(value))
+            }
+            Token::Number { .. } => return Err(location.new_unexpected_token_error(token.clone())),
+            _ => {}
+        };
+        input.reset(&start);
+        try_match_ident_ignore_ascii_case! { input,
+            "normal" => Ok(FontWeight::Absolute(400.)),
+            "bold" => Ok(FontWeight::Absolute(700.)),
+            "bolder" => Ok(FontWeight::Bolder),
+            "lighter" => Ok(FontWeight::Lighter),
+        }
+    }
+}
+
+/// Parses the `font` shorthand, which sets `font-style`, `font-weight`, `font-stretch`,
+/// `font-size`, `line-height`, and `font-family` at once, resetting any of them that are omitted to
+/// their initial values.
+///
+/// `font-variant` isn't supported, so its only accepted value is `normal`.  System font keywords
+/// such as `caption` aren't supported either.
+///
+/// https://drafts.csswg.org/css-fonts-4/#font-prop
+pub fn parse_font_shorthand_into<'i, 't>(
+    declarations: &mut Vec<PropertyDeclaration>,
+    input: &mut Parser<'i, 't>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    // Style, variant, weight, and stretch are optional, and can appear in any order before the
+    // size.  `normal` is a valid value for all of them, so it just leaves one of them unset.
+    let (mut style, mut weight, mut stretch) = (None, None, None);
+    let mut parsed_count = 0;
+    while parsed_count < 4 {
+        if input
+            .try_parse(|i| i.expect_ident_matching("normal"))
+            .is_ok()
+        {
+            parsed_count += 1;
+            continue;
+        }
+        if style.is_none() {
+            if let Ok(parsed_style) = input.try_parse(|i| FontStyle::parse(i)) {
+                style = Some(parsed_style);
+                parsed_count += 1;
+                continue;
+            }
+        }
+        if weight.is_none() {
+            if let Ok(parsed_weight) = input.try_parse(|i| FontWeight::parse(i)) {
+                weight = Some(parsed_weight);
+                parsed_count += 1;
+                continue;
+            }
+        }
+        if stretch.is_none() {
+            if let Ok(parsed_stretch) = input.try_parse(|i| FontStretch::parse_keyword(i)) {
+                stretch = Some(parsed_stretch);
+                parsed_count += 1;
+                continue;
+            }
+        }
+        break;
+    }
+    let size = FontSize::parse(input)?;
+    let line_height = if input.try_parse(|i| i.expect_delim('/')).is_ok() {
+        LineHeight::parse(input)?
+    } else {
+        LineHeight::Normal
+    };
+    let family = FontFamily::parse(input)?;
+
+    declarations.push(PropertyDeclaration::FontStyle(
+        style.unwrap_or_else(FontStyle::initial_value),
+    ));
+    declarations.push(PropertyDeclaration::FontWeight(
+        weight.unwrap_or(FontWeight::Absolute(400.)),
+    ));
+    declarations.push(PropertyDeclaration::FontStretch(
+        stretch.unwrap_or_else(FontStretch::initial_value),
+    ));
+    declarations.push(PropertyDeclaration::FontSize(size));
+    declarations.push(PropertyDeclaration::LineHeight(line_height));
+    declarations.push(PropertyDeclaration::FontFamily(family));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::{GenericFontFamily, SingleFontFamily};
+    use crate::style::values::specified::{AbsoluteLength, NoCalcLength};
+    use cssparser::ParserInput;
+
+    fn parse_font(css: &str) -> Vec<PropertyDeclaration> {
+        let mut declarations = Vec::new();
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        parse_font_shorthand_into(&mut declarations, &mut parser).unwrap();
+        assert!(parser.is_exhausted());
+        declarations
+    }
+
+    #[test]
+    fn parses_font_shorthand() {
+        let declarations =
+            parse_font("bold italic 12px/1.5 \"Helvetica Neue\", Arial Black, serif");
+        assert!(matches!(
+            declarations[0],
+            PropertyDeclaration::FontStyle(FontStyle::Italic)
+        ));
+        assert!(matches!(
+            declarations[1],
+            PropertyDeclaration::FontWeight(FontWeight::Absolute(weight)) if weight == 700.
+        ));
+        assert!(matches!(
+            declarations[3],
+            PropertyDeclaration::FontSize(FontSize::Length(LengthPercentage::Length(
+                NoCalcLength::Absolute(AbsoluteLength::Px(size))
+            ))) if size == 12.
+        ));
+        assert!(matches!(
+            declarations[4],
+            PropertyDeclaration::LineHeight(LineHeight::Number(number)) if number == 1.5
+        ));
+        match &declarations[5] {
+            PropertyDeclaration::FontFamily(family) => assert_eq!(
+                family.families,
+                vec![
+                    SingleFontFamily::FamilyName("Helvetica Neue".to_owned()),
+                    SingleFontFamily::FamilyName("Arial Black".to_owned()),
+                    SingleFontFamily::Generic(GenericFontFamily::Serif),
+                ]
+            ),
+            _ => panic!("expected font-family declaration"),
+        }
+    }
+
+    #[test]
+    fn font_shorthand_resets_omitted_longhands() {
+        let declarations = parse_font("normal 16px monospace");
+        assert!(matches!(
+            declarations[0],
+            PropertyDeclaration::FontStyle(FontStyle::Normal)
+        ));
+        assert!(matches!(
+            declarations[4],
+            PropertyDeclaration::LineHeight(LineHeight::Normal)
+        ));
+    }
+}
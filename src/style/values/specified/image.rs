@@ -0,0 +1,486 @@
+use crate::style::values::computed::angle::Angle;
+use crate::style::values::computed::image::{LineDirection, ShapeExtent};
+use crate::style::values::specified::position::Position;
+use crate::style::values::specified::{ColorUnit, LengthPercentage, NoCalcLength};
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser};
+
+/// A specified `<image>`.
+///
+/// https://drafts.csswg.org/css-images-3/#image-values
+#[derive(Clone, Debug, PartialEq)]
+pub enum Image {
+    /// An image loaded from a URL.  Relative URLs are resolved against the base URL of the
+    /// stylesheet once its imports are resolved.
+    Url(String),
+    Gradient(Box<Gradient>),
+}
+
+impl CssValueParse for Image {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(url) = input.try_parse(|i| i.expect_url()) {
+            return Ok(Image::Url(url.to_string()));
+        }
+        Ok(Image::Gradient(Box::new(Gradient::parse(input)?)))
+    }
+}
+
+/// A specified `<gradient>`.
+///
+/// https://drafts.csswg.org/css-images-4/#gradients
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub items: Vec<GradientItem>,
+    pub repeating: bool,
+}
+
+impl CssValueParse for Gradient {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        enum Function {
+            Linear,
+            Radial,
+            Conic,
+        }
+        let location = input.current_source_location();
+        let function = input.expect_function()?.clone();
+        let (function, repeating) = match_ignore_ascii_case! { &function,
+            "linear-gradient" => (Function::Linear, false),
+            "repeating-linear-gradient" => (Function::Linear, true),
+            "radial-gradient" => (Function::Radial, false),
+            "repeating-radial-gradient" => (Function::Radial, true),
+            "conic-gradient" => (Function::Conic, false),
+            "repeating-conic-gradient" => (Function::Conic, true),
+            _ => return Err(location.new_custom_error(
+                StyleParseErrorKind::UnexpectedFunction(function.clone())
+            )),
+        };
+        input.parse_nested_block(|i| {
+            let kind = match function {
+                Function::Linear => parse_linear_gradient_kind(i)?,
+                Function::Radial => parse_radial_gradient_kind(i)?,
+                Function::Conic => parse_conic_gradient_kind(i)?,
+            };
+            let angular = matches!(kind, GradientKind::Conic(..));
+            let items = parse_gradient_items(i, angular)?;
+            Ok(Gradient {
+                kind,
+                items,
+                repeating,
+            })
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GradientKind {
+    Linear(LineDirection),
+    Radial(EndingShape, Position),
+    Conic(Angle, Position),
+}
+
+/// Parses the arguments of `linear-gradient()` before its color stops, which are its direction
+/// followed by a comma, if given.
+fn parse_linear_gradient_kind<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<GradientKind, ParseError<'i, StyleParseErrorKind<'i>>> {
+    let direction = input
+        .try_parse(|i| -> Result<_, ParseError<'i, StyleParseErrorKind<'i>>> {
+            let direction = LineDirection::parse(i)?;
+            i.expect_comma()?;
+            Ok(direction)
+        })
+        .unwrap_or_else(|_| LineDirection::initial_value());
+    Ok(GradientKind::Linear(direction))
+}
+
+/// Parses the arguments of `radial-gradient()` before its color stops, which are its ending shape
+/// and position followed by a comma, if either is given.
+fn parse_radial_gradient_kind<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<GradientKind, ParseError<'i, StyleParseErrorKind<'i>>> {
+    let shape_and_position =
+        input.try_parse(|i| -> Result<_, ParseError<'i, StyleParseErrorKind<'i>>> {
+            let shape = i.try_parse(EndingShape::parse).ok();
+            let position = parse_at_position(i).ok();
+            if shape.is_none() && position.is_none() {
+                return Err(i.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+            }
+            i.expect_comma()?;
+            Ok((shape, position))
+        });
+    let (shape, position) = shape_and_position.unwrap_or((None, None));
+    Ok(GradientKind::Radial(
+        shape.unwrap_or(EndingShape::Ellipse(Ellipse::Extent(
+            ShapeExtent::FarthestCorner,
+        ))),
+        position.unwrap_or_else(Position::center),
+    ))
+}
+
+/// Parses the arguments of `conic-gradient()` before its color stops, which are the angle it
+/// starts at and its position followed by a comma, if either is given.
+fn parse_conic_gradient_kind<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<GradientKind, ParseError<'i, StyleParseErrorKind<'i>>> {
+    let angle_and_position =
+        input.try_parse(|i| -> Result<_, ParseError<'i, StyleParseErrorKind<'i>>> {
+            let from_angle = i
+                .try_parse(|i| -> Result<_, ParseError<'i, StyleParseErrorKind<'i>>> {
+                    i.expect_ident_matching("from")?;
+                    Angle::parse(i)
+                })
+                .ok();
+            let position = parse_at_position(i).ok();
+            if from_angle.is_none() && position.is_none() {
+                return Err(i.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+            }
+            i.expect_comma()?;
+            Ok((from_angle, position))
+        });
+    let (from_angle, position) = angle_and_position.unwrap_or((None, None));
+    Ok(GradientKind::Conic(
+        from_angle.unwrap_or_default(),
+        position.unwrap_or_else(Position::center),
+    ))
+}
+
+/// Parses `at <position>`.
+fn parse_at_position<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Position, ParseError<'i, StyleParseErrorKind<'i>>> {
+    input.try_parse(|i| {
+        i.expect_ident_matching("at")?;
+        Position::parse(i)
+    })
+}
+
+/// The specified ending shape of a radial gradient.
+///
+/// https://drafts.csswg.org/css-images-3/#radial-gradient-syntax
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndingShape {
+    Circle(Circle),
+    Ellipse(Ellipse),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Circle {
+    Radius(NoCalcLength),
+    Extent(ShapeExtent),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ellipse {
+    Radii(LengthPercentage, LengthPercentage),
+    Extent(ShapeExtent),
+}
+
+/// Parses `<ending-shape> || <size>`.  Without the `circle` or `ellipse` keyword, the shape is
+/// a circle if it's sized by a single length, and an ellipse otherwise.
+impl CssValueParse for EndingShape {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        #[derive(PartialEq)]
+        enum Shape {
+            Circle,
+            Ellipse,
+        }
+        let parse_shape = |i: &mut Parser<'i, '_>| {
+            i.try_parse(|i| -> Result<_, ParseError<'i, StyleParseErrorKind<'i>>> {
+                try_match_ident_ignore_ascii_case! { i,
+                    "circle" => Ok(Shape::Circle),
+                    "ellipse" => Ok(Shape::Ellipse),
+                }
+            })
+            .ok()
+        };
+
+        let location = input.current_source_location();
+        let mut shape = parse_shape(input);
+        let extent = input.try_parse(ShapeExtent::parse).ok();
+        let mut lengths = Vec::new();
+        if extent.is_none() {
+            while lengths.len() < 2 {
+                match input.try_parse(LengthPercentage::parse_allowing_unitless_zero) {
+                    Ok(length) => lengths.push(length),
+                    Err(_) => break,
+                }
+            }
+        }
+        if shape.is_none() {
+            shape = parse_shape(input);
+        }
+        let ending_shape = match (shape, extent, lengths.as_slice()) {
+            (Some(Shape::Circle), None, []) => Some(EndingShape::Circle(Circle::Extent(
+                ShapeExtent::FarthestCorner,
+            ))),
+            (Some(Shape::Ellipse), None, []) => Some(EndingShape::Ellipse(Ellipse::Extent(
+                ShapeExtent::FarthestCorner,
+            ))),
+            (Some(Shape::Circle), Some(extent), []) => {
+                Some(EndingShape::Circle(Circle::Extent(extent)))
+            }
+            (Some(Shape::Ellipse), Some(extent), []) | (None, Some(extent), []) => {
+                Some(EndingShape::Ellipse(Ellipse::Extent(extent)))
+            }
+            // Circles can't be sized by percentages.
+            (Some(Shape::Circle), None, [LengthPercentage::Length(radius)])
+            | (None, None, [LengthPercentage::Length(radius)]) => {
+                Some(EndingShape::Circle(Circle::Radius(*radius)))
+            }
+            (Some(Shape::Ellipse), None, [x, y]) | (None, None, [x, y]) => {
+                Some(EndingShape::Ellipse(Ellipse::Radii(*x, *y)))
+            }
+            _ => None,
+        };
+        ending_shape.ok_or_else(|| location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+    }
+}
+
+/// A specified color stop or color hint of a gradient.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientItem {
+    ColorStop(ColorUnit, Option<StopPosition>),
+    Hint(StopPosition),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopPosition {
+    LengthPercentage(LengthPercentage),
+    Angle(Angle),
+}
+
+impl StopPosition {
+    /// Parses the position of a color stop or hint, which is an `<angle-percentage>` in an
+    /// `angular` (conic) gradient and a `<length-percentage>` otherwise.
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+        angular: bool,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if !angular {
+            return LengthPercentage::parse_allowing_unitless_zero(input)
+                .map(StopPosition::LengthPercentage);
+        }
+        if let Ok(angle) = input.try_parse(Angle::parse) {
+            return Ok(StopPosition::Angle(angle));
+        }
+        let location = input.current_source_location();
+        match LengthPercentage::parse(input)? {
+            percentage @ LengthPercentage::Percentage(_) => {
+                Ok(StopPosition::LengthPercentage(percentage))
+            }
+            LengthPercentage::Length(_) => {
+                Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+            }
+        }
+    }
+}
+
+/// Parses the comma-separated color stops and hints of a gradient.  A color stop with two
+/// positions is the same as two color stops with the same color, one at each position.
+///
+/// https://drafts.csswg.org/css-images-4/#color-stop-syntax
+fn parse_gradient_items<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    angular: bool,
+) -> Result<Vec<GradientItem>, ParseError<'i, StyleParseErrorKind<'i>>> {
+    let location = input.current_source_location();
+    let groups =
+        input.parse_comma_separated(|i| -> Result<_, ParseError<'i, StyleParseErrorKind<'i>>> {
+            if let Ok(color) = i.try_parse(ColorUnit::parse) {
+                let first = i.try_parse(|i| StopPosition::parse(i, angular)).ok();
+                let second = match first {
+                    Some(_) => i.try_parse(|i| StopPosition::parse(i, angular)).ok(),
+                    None => None,
+                };
+                let mut stops = vec![GradientItem::ColorStop(color, first)];
+                if second.is_some() {
+                    stops.push(GradientItem::ColorStop(color, second));
+                }
+                return Ok(stops);
+            }
+            Ok(vec![GradientItem::Hint(StopPosition::parse(i, angular)?)])
+        })?;
+    let items = groups.into_iter().flatten().collect::<Vec<_>>();
+
+    let is_hint = |item: &GradientItem| matches!(item, GradientItem::Hint(_));
+    let color_stop_count = items.iter().filter(|item| !is_hint(item)).count();
+    let hints_are_between_color_stops = items.first().map_or(false, |item| !is_hint(item))
+        && items.last().map_or(false, |item| !is_hint(item))
+        && !items
+            .windows(2)
+            .any(|pair| is_hint(&pair[0]) && is_hint(&pair[1]));
+    if color_stop_count < 2 || !hints_are_between_color_stops {
+        return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::computed::Percentage;
+    use crate::style::values::specified::position::PositionComponent;
+    use crate::style::values::specified::AbsoluteLength;
+    use cssparser::{ParserInput, RGBA};
+
+    fn parse_image(css: &str) -> Option<Image> {
+        let mut input = ParserInput::new(css);
+        Parser::new(&mut input).parse_entirely(Image::parse).ok()
+    }
+
+    fn parse_gradient(css: &str) -> Option<Gradient> {
+        match parse_image(css)? {
+            Image::Gradient(gradient) => Some(*gradient),
+            Image::Url(_) => None,
+        }
+    }
+
+    fn px(px: f32) -> LengthPercentage {
+        LengthPercentage::Length(NoCalcLength::Absolute(AbsoluteLength::Px(px)))
+    }
+
+    fn percent(percent: f32) -> StopPosition {
+        StopPosition::LengthPercentage(LengthPercentage::Percentage(Percentage(percent / 100.)))
+    }
+
+    fn rgb(red: u8, green: u8, blue: u8) -> ColorUnit {
+        ColorUnit::Numeric(RGBA::new(red, green, blue, 255))
+    }
+
+    #[test]
+    fn parses_urls() {
+        assert_eq!(
+            parse_image("url(cat.png)"),
+            Some(Image::Url("cat.png".to_owned()))
+        );
+        assert_eq!(
+            parse_image("url('dir/cat.png')"),
+            Some(Image::Url("dir/cat.png".to_owned()))
+        );
+        assert_eq!(parse_image("cat.png"), None);
+    }
+
+    #[test]
+    fn parses_linear_gradients() {
+        let gradient = parse_gradient("linear-gradient(red, blue)").unwrap();
+        assert_eq!(
+            gradient,
+            Gradient {
+                kind: GradientKind::Linear(LineDirection::initial_value()),
+                items: vec![
+                    GradientItem::ColorStop(rgb(255, 0, 0), None),
+                    GradientItem::ColorStop(rgb(0, 0, 255), None),
+                ],
+                repeating: false,
+            }
+        );
+
+        let gradient =
+            parse_gradient("repeating-linear-gradient(to left top, red 0 10%, 20%, blue 30%)")
+                .unwrap();
+        assert_eq!(
+            gradient.kind,
+            GradientKind::Linear(LineDirection::Corner {
+                to_right: false,
+                to_bottom: false
+            })
+        );
+        assert_eq!(
+            gradient.items,
+            vec![
+                GradientItem::ColorStop(
+                    rgb(255, 0, 0),
+                    Some(StopPosition::LengthPercentage(px(0.)))
+                ),
+                GradientItem::ColorStop(rgb(255, 0, 0), Some(percent(10.))),
+                GradientItem::Hint(percent(20.)),
+                GradientItem::ColorStop(rgb(0, 0, 255), Some(percent(30.))),
+            ]
+        );
+        assert!(gradient.repeating);
+
+        assert_eq!(
+            parse_gradient("linear-gradient(to right, red, blue)")
+                .unwrap()
+                .kind,
+            GradientKind::Linear(LineDirection::Angle(Angle::from_degrees(90.)))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_color_stop_lists() {
+        assert_eq!(parse_gradient("linear-gradient(red)"), None);
+        assert_eq!(parse_gradient("linear-gradient(red, 10%, 20%, blue)"), None);
+        assert_eq!(parse_gradient("linear-gradient(10%, red, blue)"), None);
+        assert_eq!(parse_gradient("linear-gradient(to up, red, blue)"), None);
+        assert_eq!(parse_gradient("conic-gradient(red 10px, blue)"), None);
+    }
+
+    #[test]
+    fn parses_radial_gradients() {
+        let kind = |css| parse_gradient(css).map(|gradient| gradient.kind);
+        assert_eq!(
+            kind("radial-gradient(red, blue)"),
+            Some(GradientKind::Radial(
+                EndingShape::Ellipse(Ellipse::Extent(ShapeExtent::FarthestCorner)),
+                Position::center()
+            ))
+        );
+        assert_eq!(
+            kind("radial-gradient(20px at left, red, blue)"),
+            Some(GradientKind::Radial(
+                EndingShape::Circle(Circle::Radius(NoCalcLength::Absolute(AbsoluteLength::Px(
+                    20.
+                )))),
+                Position {
+                    horizontal: PositionComponent::start(),
+                    vertical: PositionComponent::center(),
+                }
+            ))
+        );
+        assert_eq!(
+            kind("radial-gradient(closest-side circle, red, blue)"),
+            Some(GradientKind::Radial(
+                EndingShape::Circle(Circle::Extent(ShapeExtent::ClosestSide)),
+                Position::center()
+            ))
+        );
+        assert_eq!(
+            kind("radial-gradient(ellipse 10px 50%, red, blue)"),
+            Some(GradientKind::Radial(
+                EndingShape::Ellipse(Ellipse::Radii(
+                    px(10.),
+                    LengthPercentage::Percentage(Percentage(0.5))
+                )),
+                Position::center()
+            ))
+        );
+        assert_eq!(kind("radial-gradient(circle 50%, red, blue)"), None);
+    }
+
+    #[test]
+    fn parses_conic_gradients() {
+        let gradient = parse_gradient("conic-gradient(from 90deg, red, blue 0.5turn)").unwrap();
+        assert_eq!(
+            gradient.kind,
+            GradientKind::Conic(Angle::from_degrees(90.), Position::center())
+        );
+        assert_eq!(
+            gradient.items[1],
+            GradientItem::ColorStop(
+                rgb(0, 0, 255),
+                Some(StopPosition::Angle(Angle::from_radians(
+                    std::f32::consts::PI
+                )))
+            )
+        );
+    }
+}
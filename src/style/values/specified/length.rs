@@ -110,6 +110,27 @@ pub enum LengthPercentage {
     //    Calc(Box<CalcLengthPercentage>),
 }
 
+impl LengthPercentage {
+    /// Parses a `<length-percentage>`, also accepting a unitless zero, which is a valid `<length>`.
+    pub fn parse_allowing_unitless_zero<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let is_zero = input.try_parse(|i| {
+            let location = i.current_source_location();
+            match *i.next()? {
+                Token::Number { value, .. } if value == 0. => Ok(()),
+                ref token => Err(location.new_unexpected_token_error(token.clone())),
+            }
+        });
+        if is_zero.is_ok() {
+            return Ok(LengthPercentage::Length(NoCalcLength::Absolute(
+                AbsoluteLength::Px(0.),
+            )));
+        }
+        LengthPercentage::parse(input)
+    }
+}
+
 impl CssValueParse for LengthPercentage {
     fn parse<'i, 't>(
         input: &mut Parser<'i, 't>,
@@ -0,0 +1,40 @@
+use crate::style::values::specified::LengthPercentage;
+use crate::style::values::{CSSFloat, CssValueParse};
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser, Token};
+
+/// Specified value for the `line-height` property.
+///
+/// https://drafts.csswg.org/css-inline-3/#line-height-property
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineHeight {
+    /// Use a line height derived from the metrics of the font.
+    Normal,
+    /// A multiple of the element's font size.  Unlike a percentage, the number itself (rather than
+    /// the resulting length) is inherited.
+    Number(CSSFloat),
+    LengthPercentage(LengthPercentage),
+}
+
+impl CssValueParse for LineHeight {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(lp) = input.try_parse(|i| LengthPercentage::parse(i)) {
+            return Ok(LineHeight::LengthPercentage(lp));
+        }
+        let start = input.state();
+        let location = input.current_source_location();
+        let token = input.next()?;
+        match *token {
+            // Negative values are invalid.
+            Token::Number { value, .. } if value >= 0. => return Ok(LineHeight::Number(value)),
+            Token::Number { .. } => return Err(location.new_unexpected_token_error(token.clone())),
+            _ => {}
+        };
+        input.reset(&start);
+        try_match_ident_ignore_ascii_case! { input,
+            "normal" => Ok(LineHeight::Normal),
+        }
+    }
+}
@@ -14,15 +14,25 @@ pub mod border;
 pub mod color;
 pub mod font;
 pub mod height;
+pub mod image;
 pub mod length;
+pub mod line_height;
 pub mod margin;
+pub mod overflow;
 pub mod padding;
+pub mod position;
+pub mod vertical_align;
 pub mod width;
 
 pub use background::BackgroundColor;
+pub use background::BackgroundImage;
+pub use background::BackgroundPositionX;
+pub use background::BackgroundPositionY;
+pub use background::BackgroundSize;
 
 pub use border::BorderBottomWidth;
 pub use border::BorderColor;
+pub use border::BorderCornerRadius;
 pub use border::BorderLeftWidth;
 pub use border::BorderRightWidth;
 pub use border::BorderTopWidth;
@@ -31,6 +41,7 @@ pub use color::Color;
 pub use color::ColorUnit;
 
 pub use font::FontSize;
+pub use font::FontWeight;
 pub use font::FONT_MEDIUM_PX;
 
 pub use height::Height;
@@ -40,9 +51,13 @@ pub use length::LengthPercentage;
 pub use length::LengthPercentageOrAuto;
 pub use length::NoCalcLength;
 
+pub use line_height::LineHeight;
+
 pub use margin::Margin;
 pub use padding::Padding;
 
+pub use vertical_align::VerticalAlign;
+
 use crate::style::values::CssValueParse;
 use crate::style::StyleParseErrorKind;
 use cssparser::{ParseError, Parser};
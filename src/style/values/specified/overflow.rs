@@ -0,0 +1,20 @@
+use crate::style::properties::PropertyDeclaration;
+use crate::style::values::computed::Overflow;
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser};
+
+/// Parses the `overflow` shorthand, which sets `overflow-x` to its first value, and `overflow-y`
+/// to its second value or, if omitted, to the first value too.
+///
+/// https://drafts.csswg.org/css-overflow-3/#propdef-overflow
+pub fn parse_overflow_shorthand_into<'i, 't>(
+    declarations: &mut Vec<PropertyDeclaration>,
+    input: &mut Parser<'i, 't>,
+) -> Result<(), ParseError<'i, StyleParseErrorKind<'i>>> {
+    let x = Overflow::parse(input)?;
+    let y = input.try_parse(|i| Overflow::parse(i)).unwrap_or(x);
+    declarations.push(PropertyDeclaration::OverflowX(x));
+    declarations.push(PropertyDeclaration::OverflowY(y));
+    Ok(())
+}
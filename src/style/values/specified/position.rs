@@ -0,0 +1,375 @@
+use crate::style::values::computed::Percentage;
+use crate::style::values::specified::LengthPercentage;
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser};
+
+/// The specified position of something along one axis of an area, as an offset from either the
+/// start or the end edge of that axis.  Keywords are converted to offsets when parsed, e.g.
+/// `center` is `50%` from the start edge, and `right 10px` is `10px` from the end edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionComponent {
+    /// Whether `offset` is from the end edge (right or bottom), rather than the start edge (left or
+    /// top).
+    pub from_end: bool,
+    pub offset: LengthPercentage,
+}
+
+impl PositionComponent {
+    pub fn start() -> PositionComponent {
+        PositionComponent::from_start(LengthPercentage::Percentage(Percentage(0.)))
+    }
+
+    pub fn center() -> PositionComponent {
+        PositionComponent::from_start(LengthPercentage::Percentage(Percentage(0.5)))
+    }
+
+    fn from_start(offset: LengthPercentage) -> PositionComponent {
+        PositionComponent {
+            from_end: false,
+            offset,
+        }
+    }
+
+    /// Parses a position along a single axis, such as the value of `background-position-x`.
+    pub fn parse_axis<'i, 't>(
+        input: &mut Parser<'i, 't>,
+        axis: Axis,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let keyword = input.try_parse(PositionKeyword::parse).ok();
+        let offset = match keyword {
+            Some(PositionKeyword::Center) => None,
+            _ => input
+                .try_parse(LengthPercentage::parse_allowing_unitless_zero)
+                .ok(),
+        };
+        let component = match keyword {
+            Some(keyword) => keyword.component(offset, axis),
+            None => offset.map(PositionComponent::from_start),
+        };
+        component.ok_or_else(|| location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+    }
+}
+
+/// The axis a position component is along.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// A specified `<position>`, such as the position of a background image in its positioning area.
+///
+/// https://drafts.csswg.org/css-values-4/#position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub horizontal: PositionComponent,
+    pub vertical: PositionComponent,
+}
+
+impl Position {
+    pub fn center() -> Position {
+        Position {
+            horizontal: PositionComponent::center(),
+            vertical: PositionComponent::center(),
+        }
+    }
+}
+
+impl CssValueParse for Position {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        let location = input.current_source_location();
+        let mut items = Vec::new();
+        let mut states = Vec::new();
+        while items.len() < 4 {
+            match input.try_parse(PositionItem::parse) {
+                Ok(item) => {
+                    items.push(item);
+                    states.push(input.state());
+                }
+                Err(_) => break,
+            }
+        }
+        // Items that don't form a position together may be a shorter position followed by
+        // something else, e.g. `left 10px` in `left 10px 20px` is the whole position, where the
+        // `20px` is the size of a background given in the `background` shorthand.
+        for len in (1..=items.len()).rev() {
+            if let Some(position) = position_from_items(&items[..len]) {
+                input.reset(&states[len - 1]);
+                return Ok(position);
+            }
+        }
+        Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum PositionKeyword {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Center,
+}
+
+impl PositionKeyword {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "left" => Ok(PositionKeyword::Left),
+            "right" => Ok(PositionKeyword::Right),
+            "top" => Ok(PositionKeyword::Top),
+            "bottom" => Ok(PositionKeyword::Bottom),
+            "center" => Ok(PositionKeyword::Center),
+        }
+    }
+
+    /// The axis the keyword is for, or `None` for `center`, which is valid for either axis.
+    fn axis(self) -> Option<Axis> {
+        match self {
+            PositionKeyword::Left | PositionKeyword::Right => Some(Axis::Horizontal),
+            PositionKeyword::Top | PositionKeyword::Bottom => Some(Axis::Vertical),
+            PositionKeyword::Center => None,
+        }
+    }
+
+    /// The component of a position along `axis` given by this keyword followed by `offset`, or
+    /// `None` if the keyword isn't for that axis or can't be followed by an offset.
+    fn component(self, offset: Option<LengthPercentage>, axis: Axis) -> Option<PositionComponent> {
+        if self == PositionKeyword::Center {
+            return match offset {
+                Some(_) => None,
+                None => Some(PositionComponent::center()),
+            };
+        }
+        if self.axis() != Some(axis) {
+            return None;
+        }
+        Some(PositionComponent {
+            from_end: self == PositionKeyword::Right || self == PositionKeyword::Bottom,
+            offset: offset.unwrap_or(LengthPercentage::Percentage(Percentage(0.))),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PositionItem {
+    Keyword(PositionKeyword),
+    Offset(LengthPercentage),
+}
+
+impl PositionItem {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(keyword) = input.try_parse(PositionKeyword::parse) {
+            return Ok(PositionItem::Keyword(keyword));
+        }
+        LengthPercentage::parse_allowing_unitless_zero(input).map(PositionItem::Offset)
+    }
+}
+
+/// Interprets the one to four items of a `<position>`, or returns `None` if they don't form one.
+fn position_from_items(items: &[PositionItem]) -> Option<Position> {
+    use PositionItem::{Keyword, Offset};
+
+    let component = |item: PositionItem, axis: Axis| match item {
+        Keyword(keyword) => keyword.component(None, axis),
+        Offset(offset) => Some(PositionComponent::from_start(offset)),
+    };
+    match *items {
+        [Keyword(keyword)] if keyword.axis() == Some(Axis::Vertical) => Some(Position {
+            horizontal: PositionComponent::center(),
+            vertical: component(items[0], Axis::Vertical)?,
+        }),
+        [item] => Some(Position {
+            horizontal: component(item, Axis::Horizontal)?,
+            vertical: PositionComponent::center(),
+        }),
+        [first, second] => {
+            // Two keywords can be given in either order, e.g. `top left`.
+            let (horizontal, vertical) = match (first, second) {
+                (Keyword(first_keyword), Keyword(second_keyword))
+                    if first_keyword.axis() == Some(Axis::Vertical)
+                        || second_keyword.axis() == Some(Axis::Horizontal) =>
+                {
+                    (second, first)
+                }
+                _ => (first, second),
+            };
+            Some(Position {
+                horizontal: component(horizontal, Axis::Horizontal)?,
+                vertical: component(vertical, Axis::Vertical)?,
+            })
+        }
+        _ => {
+            // Three or four items are two keywords, at least one of them followed by an offset,
+            // e.g. `right 10px top` or `bottom 10% right 20px`.
+            let mut groups = Vec::new();
+            let mut rest = items;
+            while let Some((Keyword(keyword), after_keyword)) = rest.split_first() {
+                match after_keyword.split_first() {
+                    Some((Offset(offset), after_offset)) => {
+                        groups.push((*keyword, Some(*offset)));
+                        rest = after_offset;
+                    }
+                    _ => {
+                        groups.push((*keyword, None));
+                        rest = after_keyword;
+                    }
+                }
+            }
+            let ((first, first_offset), (second, second_offset)) = match *groups {
+                [first, second] if rest.is_empty() => (first, second),
+                _ => return None,
+            };
+            let first_is_vertical =
+                first.axis() == Some(Axis::Vertical) || second.axis() == Some(Axis::Horizontal);
+            let (horizontal, vertical) = if first_is_vertical {
+                (
+                    second.component(second_offset, Axis::Horizontal)?,
+                    first.component(first_offset, Axis::Vertical)?,
+                )
+            } else {
+                (
+                    first.component(first_offset, Axis::Horizontal)?,
+                    second.component(second_offset, Axis::Vertical)?,
+                )
+            };
+            Some(Position {
+                horizontal,
+                vertical,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::values::specified::{AbsoluteLength, NoCalcLength};
+    use cssparser::ParserInput;
+
+    fn parse_position(css: &str) -> Option<Position> {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        let position = Position::parse(&mut parser).ok()?;
+        if parser.is_exhausted() {
+            Some(position)
+        } else {
+            None
+        }
+    }
+
+    fn px(px: f32) -> LengthPercentage {
+        LengthPercentage::Length(NoCalcLength::Absolute(AbsoluteLength::Px(px)))
+    }
+
+    fn percent(percent: f32) -> LengthPercentage {
+        LengthPercentage::Percentage(Percentage(percent / 100.))
+    }
+
+    fn from_start(offset: LengthPercentage) -> PositionComponent {
+        PositionComponent {
+            from_end: false,
+            offset,
+        }
+    }
+
+    fn from_end(offset: LengthPercentage) -> PositionComponent {
+        PositionComponent {
+            from_end: true,
+            offset,
+        }
+    }
+
+    fn position(horizontal: PositionComponent, vertical: PositionComponent) -> Option<Position> {
+        Some(Position {
+            horizontal,
+            vertical,
+        })
+    }
+
+    #[test]
+    fn parses_one_and_two_value_positions() {
+        let center = PositionComponent::center();
+        assert_eq!(parse_position("center"), position(center, center));
+        assert_eq!(
+            parse_position("bottom"),
+            position(center, from_end(percent(0.)))
+        );
+        assert_eq!(
+            parse_position("10px"),
+            position(from_start(px(10.)), center)
+        );
+        assert_eq!(
+            parse_position("0 25%"),
+            position(from_start(px(0.)), from_start(percent(25.)))
+        );
+        assert_eq!(
+            parse_position("top right"),
+            position(from_end(percent(0.)), from_start(percent(0.)))
+        );
+        assert_eq!(
+            parse_position("left 5px"),
+            position(from_start(percent(0.)), from_start(px(5.)))
+        );
+        assert_eq!(parse_position("5px left"), None);
+        assert_eq!(parse_position("left right"), None);
+    }
+
+    #[test]
+    fn parses_three_and_four_value_positions() {
+        assert_eq!(
+            parse_position("right 10px top"),
+            position(from_end(px(10.)), from_start(percent(0.)))
+        );
+        assert_eq!(
+            parse_position("bottom 10% right 20px"),
+            position(from_end(px(20.)), from_end(percent(10.)))
+        );
+        assert_eq!(
+            parse_position("center bottom 3px"),
+            position(PositionComponent::center(), from_end(px(3.)))
+        );
+        assert_eq!(parse_position("center 10px top"), None);
+        assert_eq!(parse_position("left 10px right 5px"), None);
+    }
+
+    #[test]
+    fn leaves_items_that_are_not_part_of_the_position() {
+        let mut input = ParserInput::new("left 10px 20px");
+        let mut parser = Parser::new(&mut input);
+        assert_eq!(
+            Position::parse(&mut parser).ok(),
+            position(from_start(percent(0.)), from_start(px(10.)))
+        );
+        assert!(LengthPercentage::parse(&mut parser).is_ok());
+        assert!(parser.is_exhausted());
+    }
+
+    #[test]
+    fn parses_positions_along_one_axis() {
+        let parse_axis = |css, axis| {
+            let mut input = ParserInput::new(css);
+            let mut parser = Parser::new(&mut input);
+            parser
+                .parse_entirely(|i| PositionComponent::parse_axis(i, axis))
+                .ok()
+        };
+        assert_eq!(
+            parse_axis("right 4px", Axis::Horizontal),
+            Some(from_end(px(4.)))
+        );
+        assert_eq!(
+            parse_axis("30%", Axis::Vertical),
+            Some(from_start(percent(30.)))
+        );
+        assert_eq!(parse_axis("right", Axis::Vertical), None);
+        assert_eq!(parse_axis("center 4px", Axis::Horizontal), None);
+    }
+}
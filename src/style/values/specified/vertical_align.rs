@@ -0,0 +1,65 @@
+use crate::style::values::specified::LengthPercentage;
+use crate::style::values::CssValueParse;
+use crate::style::StyleParseErrorKind;
+use cssparser::{ParseError, Parser};
+
+/// Specified value for the `vertical-align` property.
+///
+/// https://www.w3.org/TR/CSS2/visudet.html#propdef-vertical-align
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VerticalAlign {
+    Keyword(VerticalAlignKeyword),
+    /// Raise (positive values) or lower (negative values) the box by this distance.  Percentages
+    /// refer to the `line-height` of the box itself.
+    LengthPercentage(LengthPercentage),
+}
+
+impl CssValueParse for VerticalAlign {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        if let Ok(lp) = input.try_parse(|i| LengthPercentage::parse(i)) {
+            return Ok(VerticalAlign::LengthPercentage(lp));
+        }
+        VerticalAlignKeyword::parse(input).map(VerticalAlign::Keyword)
+    }
+}
+
+/// The keyword values of `vertical-align`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VerticalAlignKeyword {
+    /// Align the baseline of the box with the baseline of the parent box.
+    Baseline,
+    /// Lower the baseline of the box to the proper position for subscripts of the parent's box.
+    Sub,
+    /// Raise the baseline of the box to the proper position for superscripts of the parent's box.
+    Super,
+    /// Align the top of the box with the top of the parent's content area.
+    TextTop,
+    /// Align the bottom of the box with the bottom of the parent's content area.
+    TextBottom,
+    /// Align the vertical midpoint of the box with the baseline of the parent box plus half the
+    /// x-height of the parent.
+    Middle,
+    /// Align the top of the aligned subtree with the top of the line box.
+    Top,
+    /// Align the bottom of the aligned subtree with the bottom of the line box.
+    Bottom,
+}
+
+impl CssValueParse for VerticalAlignKeyword {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "baseline" => Ok(VerticalAlignKeyword::Baseline),
+            "sub" => Ok(VerticalAlignKeyword::Sub),
+            "super" => Ok(VerticalAlignKeyword::Super),
+            "text-top" => Ok(VerticalAlignKeyword::TextTop),
+            "text-bottom" => Ok(VerticalAlignKeyword::TextBottom),
+            "middle" => Ok(VerticalAlignKeyword::Middle),
+            "top" => Ok(VerticalAlignKeyword::Top),
+            "bottom" => Ok(VerticalAlignKeyword::Bottom),
+        }
+    }
+}
@@ -6,18 +6,47 @@ use cssparser::{ParseError, Parser};
 /// Specified value for the `width` property.
 ///
 /// https://www.w3.org/TR/css-sizing-3/#property-index
-// TODO: Need to support various other value types, such as `{min, max}-content`.
 #[derive(Clone, Copy, Debug)]
 pub enum Width {
     LengthPercentageOrAuto(LengthPercentageOrAuto),
+    ContentSize(ContentSizeKeyword),
 }
 
 impl CssValueParse for Width {
     fn parse<'i, 't>(
         input: &mut Parser<'i, 't>,
     ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
-        input
-            .try_parse(|i| LengthPercentageOrAuto::parse(i))
-            .map(Width::LengthPercentageOrAuto)
+        if let Ok(lp_auto) = input.try_parse(|i| LengthPercentageOrAuto::parse(i)) {
+            return Ok(Width::LengthPercentageOrAuto(lp_auto));
+        }
+        ContentSizeKeyword::parse(input).map(Width::ContentSize)
+    }
+}
+
+/// Keywords that size a box based on its contents rather than its containing block.
+///
+/// https://drafts.csswg.org/css-sizing-3/#sizing-values
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContentSizeKeyword {
+    /// Use the min-content size, which is the narrowest a box can get without overflowing its
+    /// contents (e.g. the width of the longest word for text).
+    MinContent,
+    /// Use the max-content size, which is the size a box would take given infinite space (e.g.
+    /// the width of the text if it were laid out on a single line).
+    MaxContent,
+    /// Use the available space clamped between the min-content and max-content sizes, also known
+    /// as shrink-to-fit.
+    FitContent,
+}
+
+impl CssValueParse for ContentSizeKeyword {
+    fn parse<'i, 't>(
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self, ParseError<'i, StyleParseErrorKind<'i>>> {
+        try_match_ident_ignore_ascii_case! { input,
+            "min-content" => Ok(ContentSizeKeyword::MinContent),
+            "max-content" => Ok(ContentSizeKeyword::MaxContent),
+            "fit-content" => Ok(ContentSizeKeyword::FitContent),
+        }
     }
 }
@@ -157,9 +157,18 @@ fn dump_layout_insta_settings(
 }
 
 pub fn reftest_expect_similar<S: AsRef<str> + AsRef<OsStr>>(file_path_one: S, file_path_two: S) {
+    reftest_expect_similar_with_user_sheets(file_path_one, file_path_two, &[])
+}
+
+/// Like `reftest_expect_similar`, but renders both files with the given user stylesheets.
+pub fn reftest_expect_similar_with_user_sheets<S: AsRef<str> + AsRef<OsStr>>(
+    file_path_one: S,
+    file_path_two: S,
+    user_sheets: &[&str],
+) {
     let (file_path_one, file_path_two): (&str, &str) =
         (file_path_one.as_ref(), file_path_two.as_ref());
-    match run_similarity_cmd(file_path_one, file_path_two) {
+    match run_similarity_cmd(file_path_one, file_path_two, user_sheets) {
         Ok(percent_similar) => {
             if percent_similar != 100.0f64 {
                 panic!(format!("reftest_expect_similar failure.  files '{}' and '{}' were only {}% similar (expected 100%)", file_path_one, file_path_two, percent_similar));
@@ -177,12 +186,17 @@ pub fn reftest_expect_similar<S: AsRef<str> + AsRef<OsStr>>(file_path_one: S, fi
 fn run_similarity_cmd<S: AsRef<str> + AsRef<OsStr>>(
     file_path_one: S,
     file_path_two: S,
+    user_sheets: &[&str],
 ) -> Result<f64, String> {
     let mut cmd = CommandUnderTest::new();
     cmd.arg("similarity");
     cmd.arg(file_path_one);
     cmd.arg(file_path_two);
     cmd.arg("--similarity-percent-only");
+    for user_sheet in user_sheets {
+        cmd.arg("--user-stylesheet");
+        cmd.arg(user_sheet);
+    }
     cmd.succeeds();
     let stdout = cmd.stdout().trim();
     stdout.parse::<f64>().map_err(|_| stdout.trim().to_owned())
@@ -1 +1,2 @@
 pub mod shorthands;
+pub mod user_stylesheet;
@@ -0,0 +1,15 @@
+use crate::reftest_expect_similar_with_user_sheets;
+
+const USER_STYLESHEET_WEBSRC_DIR: &str = "tests/websrc/style/user-stylesheet";
+fn test_file_path<S: AsRef<str>>(filename: S) -> String {
+    format!("{}/{}", USER_STYLESHEET_WEBSRC_DIR, filename.as_ref())
+}
+
+#[test]
+fn important_user_declarations_beat_author_declarations() {
+    reftest_expect_similar_with_user_sheets(
+        test_file_path("user-stylesheet.html"),
+        test_file_path("user-stylesheet-expected.html"),
+        &[&test_file_path("high-contrast.css")],
+    )
+}
@@ -0,0 +1,283 @@
+#!/usr/bin/env python3
+"""Generates ahem.ttf, a TrueType font with the metrics and glyph shapes of the Ahem test font.
+
+Ahem is the font the CSS test suites are written against:
+https://web-platform-tests.org/writing-tests/ahem.html
+
+Its glyphs are plain boxes, so text set in it renders identically on every machine and can be
+compared against boxes drawn with borders or backgrounds.  Only the properties tests rely on are
+reproduced:
+
+  * The em square is 1000 units, with an ascent of 800 and a descent of 200.  The x-height and cap
+    height are both 800.
+  * Every glyph has an advance of 1em.
+  * Most glyphs are a box filling the entire em square.
+  * 'p' only fills the descent, below the baseline, and 'É' only fills the ascent, above it.
+  * Space and no-break space are blank.
+
+Run with no arguments to regenerate ahem.ttf next to this script.  It only uses the standard library.
+"""
+
+import os
+import struct
+
+UNITS_PER_EM = 1000
+ASCENT = 800
+DESCENT = 200
+FAMILY_NAME = "Ahem"
+VERSION = "Version 1.0"
+
+# Glyph IDs.
+NOTDEF, BLANK, BOX, DESCENT_BOX, ASCENT_BOX = range(5)
+# The outline of each glyph as (y_min, y_max), or None if the glyph is blank.
+GLYPH_OUTLINES = [
+    None,
+    None,
+    (-DESCENT, ASCENT),
+    (-DESCENT, 0),
+    (0, ASCENT),
+]
+
+
+def char_to_glyph():
+    mapping = {}
+    for code in list(range(0x21, 0x7F)) + list(range(0xA1, 0x100)):
+        mapping[code] = BOX
+    mapping[0x20] = BLANK
+    mapping[0xA0] = BLANK
+    mapping[ord("p")] = DESCENT_BOX
+    mapping[ord("É")] = ASCENT_BOX
+    return mapping
+
+
+def glyph_data(outline):
+    if outline is None:
+        return b""
+    y_min, y_max = outline
+    # A single clockwise contour of four on-curve points.
+    points = [(0, y_min), (0, y_max), (UNITS_PER_EM, y_max), (UNITS_PER_EM, y_min)]
+    data = struct.pack(">hhhhh", 1, 0, y_min, UNITS_PER_EM, y_max)
+    data += struct.pack(">HH", len(points) - 1, 0)
+    data += bytes([0x01] * len(points))
+    previous = (0, 0)
+    x_deltas, y_deltas = b"", b""
+    for x, y in points:
+        x_deltas += struct.pack(">h", x - previous[0])
+        y_deltas += struct.pack(">h", y - previous[1])
+        previous = (x, y)
+    return pad(data + x_deltas + y_deltas)
+
+
+def pad(data):
+    return data + b"\0" * (-len(data) % 4)
+
+
+def checksum(data):
+    data = pad(data)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def glyf_and_loca():
+    glyf, offsets = b"", []
+    for outline in GLYPH_OUTLINES:
+        offsets.append(len(glyf))
+        glyf += glyph_data(outline)
+    offsets.append(len(glyf))
+    return glyf, struct.pack(">%dI" % len(offsets), *offsets)
+
+
+def head():
+    return struct.pack(
+        ">IIIIHHqqhhhhHHhhh",
+        0x00010000,  # version
+        0x00010000,  # fontRevision
+        0,  # checkSumAdjustment, filled in once the whole font is assembled
+        0x5F0F3CF5,  # magicNumber
+        0x000B,  # flags
+        UNITS_PER_EM,
+        0,  # created
+        0,  # modified
+        0,
+        -DESCENT,
+        UNITS_PER_EM,
+        ASCENT,
+        0,  # macStyle
+        8,  # lowestRecPPEM
+        2,  # fontDirectionHint
+        1,  # indexToLocFormat: long offsets
+        0,  # glyphDataFormat
+    )
+
+
+def hhea():
+    return struct.pack(
+        ">IhhhHhhhhhhhhhhhH",
+        0x00010000,
+        ASCENT,
+        -DESCENT,
+        0,  # lineGap
+        UNITS_PER_EM,  # advanceWidthMax
+        0,  # minLeftSideBearing
+        0,  # minRightSideBearing
+        UNITS_PER_EM,  # xMaxExtent
+        1,  # caretSlopeRise
+        0,  # caretSlopeRun
+        0,  # caretOffset
+        0,
+        0,
+        0,
+        0,
+        0,  # metricDataFormat
+        len(GLYPH_OUTLINES),  # numberOfHMetrics
+    )
+
+
+def hmtx():
+    return b"".join(struct.pack(">Hh", UNITS_PER_EM, 0) for _ in GLYPH_OUTLINES)
+
+
+def maxp():
+    return struct.pack(
+        ">IHHHHHHHHHHHHHH", 0x00010000, len(GLYPH_OUTLINES), 4, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0
+    )
+
+
+def cmap():
+    """A format 4 subtable mapping each run of consecutive characters through the glyph ID array."""
+    mapping = char_to_glyph()
+    codes = sorted(mapping)
+    runs = []
+    for code in codes:
+        if runs and runs[-1][1] == code - 1:
+            runs[-1][1] = code
+        else:
+            runs.append([code, code])
+    runs.append([0xFFFF, 0xFFFF])
+    seg_count = len(runs)
+    glyph_ids = []
+    range_offsets = []
+    for index, (start, end) in enumerate(runs):
+        if start == 0xFFFF:
+            range_offsets.append(0)
+            continue
+        # Offset in bytes from this segment's idRangeOffset to its first glyph ID.
+        range_offsets.append(2 * (seg_count - index + len(glyph_ids)))
+        glyph_ids.extend(mapping[code] for code in range(start, end + 1))
+    search_range = 2 * 2 ** (seg_count.bit_length() - 1)
+    entry_selector = seg_count.bit_length() - 1
+    subtable = struct.pack(
+        ">HHHH", seg_count * 2, search_range, entry_selector, seg_count * 2 - search_range
+    )
+    subtable += struct.pack(">%dH" % seg_count, *(end for _, end in runs))
+    subtable += struct.pack(">H", 0)  # reservedPad
+    subtable += struct.pack(">%dH" % seg_count, *(start for start, _ in runs))
+    subtable += struct.pack(">%dh" % seg_count, *([0] * (seg_count - 1) + [1]))
+    subtable += struct.pack(">%dH" % seg_count, *range_offsets)
+    subtable += struct.pack(">%dH" % len(glyph_ids), *glyph_ids)
+    subtable = struct.pack(">HHH", 4, len(subtable) + 6, 0) + subtable
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + subtable
+
+
+def os2():
+    return struct.pack(
+        ">HhHHHhhhhhhhhhhh10sIIII4sHHHhhhHHIIhhHHH",
+        4,  # version
+        UNITS_PER_EM,  # xAvgCharWidth
+        400,  # usWeightClass
+        5,  # usWidthClass
+        0,  # fsType: installable
+        UNITS_PER_EM // 2,  # ySubscriptXSize
+        UNITS_PER_EM // 2,  # ySubscriptYSize
+        0,  # ySubscriptXOffset
+        DESCENT,  # ySubscriptYOffset
+        UNITS_PER_EM // 2,  # ySuperscriptXSize
+        UNITS_PER_EM // 2,  # ySuperscriptYSize
+        0,  # ySuperscriptXOffset
+        ASCENT // 2,  # ySuperscriptYOffset
+        50,  # yStrikeoutSize
+        ASCENT // 2,  # yStrikeoutPosition
+        0,  # sFamilyClass
+        bytes(10),  # panose
+        0b11,  # ulUnicodeRange1: Basic Latin and Latin-1 Supplement
+        0,
+        0,
+        0,
+        b"NONE",  # achVendID
+        0x00C0,  # fsSelection: REGULAR | USE_TYPO_METRICS
+        0x20,  # usFirstCharIndex
+        0xFF,  # usLastCharIndex
+        ASCENT,  # sTypoAscender
+        -DESCENT,  # sTypoDescender
+        0,  # sTypoLineGap
+        ASCENT,  # usWinAscent
+        DESCENT,  # usWinDescent
+        0b1,  # ulCodePageRange1: Latin 1
+        0,
+        ASCENT,  # sxHeight
+        ASCENT,  # sCapHeight
+        0,  # usDefaultChar
+        0x20,  # usBreakChar
+        1,  # usMaxContext
+    )
+
+
+def name():
+    names = {
+        1: FAMILY_NAME,
+        2: "Regular",
+        3: FAMILY_NAME + " Regular",
+        4: FAMILY_NAME,
+        5: VERSION,
+        6: FAMILY_NAME,
+    }
+    records, strings = b"", b""
+    for name_id, value in sorted(names.items()):
+        encoded = value.encode("utf-16-be")
+        records += struct.pack(">HHHHHH", 3, 1, 0x409, name_id, len(encoded), len(strings))
+        strings += encoded
+    return struct.pack(">HHH", 0, len(names), 6 + len(records)) + records + strings
+
+
+def post():
+    return struct.pack(">IIhhIIIII", 0x00030000, 0, -DESCENT // 2, 50, 1, 0, 0, 0, 0)
+
+
+def build_font():
+    glyf, loca = glyf_and_loca()
+    tables = {
+        b"OS/2": os2(),
+        b"cmap": cmap(),
+        b"glyf": glyf,
+        b"head": head(),
+        b"hhea": hhea(),
+        b"hmtx": hmtx(),
+        b"loca": loca,
+        b"maxp": maxp(),
+        b"name": name(),
+        b"post": post(),
+    }
+    num_tables = len(tables)
+    entry_selector = num_tables.bit_length() - 1
+    search_range = 16 * 2 ** entry_selector
+    font = struct.pack(
+        ">IHHHH", 0x00010000, num_tables, search_range, entry_selector, num_tables * 16 - search_range
+    )
+    offset = len(font) + 16 * num_tables
+    directory, data = b"", b""
+    head_offset = None
+    for tag in sorted(tables):
+        table = tables[tag]
+        if tag == b"head":
+            head_offset = offset
+        directory += struct.pack(">4sIII", tag, checksum(table), offset, len(table))
+        data += pad(table)
+        offset += len(pad(table))
+    font += directory + data
+    adjustment = (0xB1B0AFBA - checksum(font)) & 0xFFFFFFFF
+    return font[: head_offset + 8] + struct.pack(">I", adjustment) + font[head_offset + 12 :]
+
+
+if __name__ == "__main__":
+    path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "ahem.ttf")
+    with open(path, "wb") as file:
+        file.write(build_font())